@@ -0,0 +1,27 @@
+//! Android app for `TetaNES`, built with `cargo apk`.
+
+/// Entry point when running as an Android app.
+#[cfg(target_os = "android")]
+#[no_mangle]
+fn android_main(app: winit::platform::android::activity::AndroidApp) {
+    use tetanes::{logging, nes};
+
+    // Apps can only write to their own sandboxed storage, so keep everything together there
+    if let Some(dir) = app.internal_data_path() {
+        tetanes_core::control_deck::Config::set_portable_dir(Some(dir));
+    }
+    let _log = logging::init();
+    let mut cfg = nes::config::Config::load(None);
+    // File dialogs aren't available, so scan ROMs copied to the app's shared storage instead
+    if let Some(dir) = app.external_data_path().map(|dir| dir.join("roms")) {
+        if let Err(err) = std::fs::create_dir_all(&dir) {
+            tracing::error!("failed to create roms directory {dir:?}: {err:?}");
+        }
+        if !cfg.renderer.library_dirs.contains(&dir) {
+            cfg.renderer.library_dirs.push(dir);
+        }
+    }
+    if let Err(err) = nes::Nes::run_android(cfg, app) {
+        tracing::error!("{err:?}");
+    }
+}
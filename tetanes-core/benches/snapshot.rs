@@ -0,0 +1,97 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::{fs::File, io::BufReader, path::Path, time::Duration};
+use tetanes_core::{
+    control_deck::{Config, ControlDeck},
+    mem::RamState,
+    snapshot::{Snapshot, SnapshotRing},
+};
+
+fn load_deck(rom_path: impl AsRef<Path>) -> ControlDeck {
+    let base_path = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let rom_path = base_path.join(rom_path);
+    assert!(rom_path.exists(), "No test rom found for {rom_path:?}");
+    let mut rom = BufReader::new(File::open(&rom_path).expect("failed to open path"));
+    let mut deck = ControlDeck::with_config(Config {
+        ram_state: RamState::AllZeros,
+        ..Default::default()
+    });
+    deck.load_rom(rom_path.to_string_lossy(), &mut rom)
+        .expect("failed to load rom");
+    deck
+}
+
+fn push_frames(deck: &mut ControlDeck, ring: &mut SnapshotRing, frames: u32) {
+    for _ in 0..frames {
+        deck.clock_frame().expect("valid frame clock");
+        deck.clear_audio_samples();
+        ring.push(deck.cpu(), ()).expect("valid snapshot");
+    }
+}
+
+fn run_ahead(c: &mut Criterion) {
+    let mut group = c.benchmark_group("snapshot");
+    group.measurement_time(Duration::from_secs(30));
+    group.sample_size(10);
+    group.bench_function("run_ahead", |b| {
+        let mut deck = load_deck("test_roms/spritecans.nes");
+        b.iter(|| {
+            for _ in 0..black_box(200) {
+                deck.clock_frame_ahead(1, |_, _, _| ())
+                    .expect("valid frame clock");
+            }
+        });
+    });
+    group.finish();
+}
+
+fn rewind(c: &mut Criterion) {
+    let mut group = c.benchmark_group("snapshot");
+    group.measurement_time(Duration::from_secs(30));
+    group.sample_size(10);
+    group.bench_function("rewind_push", |b| {
+        let mut deck = load_deck("test_roms/spritecans.nes");
+        let mut ring = SnapshotRing::new(600);
+        b.iter(|| push_frames(&mut deck, &mut ring, black_box(200)));
+    });
+    group.bench_function("rewind_pop", |b| {
+        let mut deck = load_deck("test_roms/spritecans.nes");
+        let mut ring = SnapshotRing::new(200);
+        push_frames(&mut deck, &mut ring, 200);
+        b.iter(|| {
+            let mut ring = ring.clone();
+            while let Some(cpu) = ring.pop() {
+                let _ = black_box(cpu.expect("valid snapshot"));
+            }
+        });
+    });
+    group.finish();
+}
+
+fn snapshot_restore(c: &mut Criterion) {
+    let mut group = c.benchmark_group("snapshot");
+    group.measurement_time(Duration::from_secs(30));
+    group.sample_size(10);
+    group.bench_function("serialize", |b| {
+        let mut deck = load_deck("test_roms/spritecans.nes");
+        b.iter(|| {
+            for _ in 0..black_box(200) {
+                let state = deck.save_state_bytes().expect("valid save state");
+                deck.load_state_bytes(&state).expect("valid load state");
+            }
+        });
+    });
+    group.bench_function("snapshot", |b| {
+        let mut deck = load_deck("test_roms/spritecans.nes");
+        let mut snapshot = Snapshot::new();
+        b.iter(|| {
+            for _ in 0..black_box(200) {
+                deck.snapshot(&mut snapshot);
+                deck.restore(&snapshot);
+            }
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, run_ahead, rewind, snapshot_restore);
+criterion_main!(benches);
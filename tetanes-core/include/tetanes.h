@@ -0,0 +1,89 @@
+/*
+ * C API for tetanes-core, enabled with the `capi` feature.
+ *
+ * All functions taking a `TetanesDeck` pointer require a valid pointer returned from
+ * `tetanes_create` that hasn't yet been passed to `tetanes_destroy`. Pointers returned by these
+ * functions are only valid until the next call that mutates the same deck.
+ */
+
+#ifndef TETANES_H
+#define TETANES_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Result codes. */
+#define TETANES_OK 0
+#define TETANES_ERR_INVALID_ARGUMENT -1
+#define TETANES_ERR_LOAD_ROM -2
+#define TETANES_ERR_ROM_NOT_LOADED -3
+#define TETANES_ERR_CPU_CORRUPTED -4
+
+/* Frame buffer dimensions in pixels. Each pixel is 4 bytes of RGBA. */
+#define TETANES_FRAME_WIDTH 256
+#define TETANES_FRAME_HEIGHT 240
+
+/* Joypad buttons for `tetanes_set_input`. */
+#define TETANES_BUTTON_A 0x01
+#define TETANES_BUTTON_B 0x02
+#define TETANES_BUTTON_SELECT 0x04
+#define TETANES_BUTTON_START 0x08
+#define TETANES_BUTTON_UP 0x10
+#define TETANES_BUTTON_DOWN 0x20
+#define TETANES_BUTTON_LEFT 0x40
+#define TETANES_BUTTON_RIGHT 0x80
+#define TETANES_BUTTON_TURBO_A 0x100
+#define TETANES_BUTTON_TURBO_B 0x200
+
+/* Opaque handle to an emulated NES control deck. */
+typedef struct TetanesDeck TetanesDeck;
+
+/* Create a new deck with the default configuration. Must be freed with `tetanes_destroy`. */
+TetanesDeck *tetanes_create(void);
+
+/* Free a deck created with `tetanes_create`. Passing NULL does nothing. */
+void tetanes_destroy(TetanesDeck *deck);
+
+/*
+ * Load an iNES or NES 2.0 ROM image of `len` bytes. `name` is a null-terminated UTF-8 string used
+ * to identify the ROM for battery-backed RAM, and may be NULL.
+ */
+int32_t tetanes_load_rom(TetanesDeck *deck, const char *name, const uint8_t *data, size_t len);
+
+/* Clock an entire frame. */
+int32_t tetanes_clock_frame(TetanesDeck *deck);
+
+/*
+ * Returns the RGBA frame buffer of the last frame clocked, writing its length in bytes to `len` if
+ * not NULL.
+ */
+const uint8_t *tetanes_frame_buffer(TetanesDeck *deck, size_t *len);
+
+/*
+ * Returns the audio samples generated since they were last cleared, writing the number of samples
+ * to `len` if not NULL.
+ */
+const float *tetanes_audio_samples(const TetanesDeck *deck, size_t *len);
+
+/* Clear audio samples, which should be done after processing them each frame. */
+void tetanes_clear_audio_samples(TetanesDeck *deck);
+
+/* Set the pressed `buttons` for `player` (0-3) as a bitmask of `TETANES_BUTTON_*` values. */
+int32_t tetanes_set_input(TetanesDeck *deck, uint32_t player, uint16_t buttons);
+
+/* Reset the console, emulating a power cycle if `hard` is true. */
+void tetanes_reset(TetanesDeck *deck, bool hard);
+
+/* Returns a null-terminated description of the last error, or NULL if there hasn't been one. */
+const char *tetanes_last_error(const TetanesDeck *deck);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TETANES_H */
@@ -31,6 +31,10 @@ pub enum Action {
     ZapperAimOffscreen,
     /// Trigger the [`Zapper`](crate::input::Zapper) trigger.
     ZapperTrigger,
+    /// Insert a coin into Vs. System coin slot `1` or `2`.
+    InsertCoin(u8),
+    /// Press the Vs. System service button, which adds a credit.
+    VsServiceButton,
     /// Set [`FourPlayer`] mode.
     FourPlayer(FourPlayer),
     /// Set the slot to use for save states.
@@ -4,16 +4,17 @@
 
 use crate::{
     apu::{
-        dmc::Dmc,
-        filter::{Consume, FilterChain},
+        dmc::{DmaRequest, Dmc},
+        filter::{Consume, FilterChain, FilterConfig},
         frame_counter::{FrameCounter, FrameType},
+        mixer::Mixer,
         noise::Noise,
         pulse::{OutputFreq, Pulse, PulseChannel},
         timer::TimerCycle,
         triangle::Triangle,
     },
     common::{Clock, ClockTo, NesRegion, Regional, Reset, ResetKind, Sample},
-    cpu::{Cpu, Irq},
+    cpu::{Cpu, DmaState, Irq},
 };
 use alloc::{vec, vec::Vec};
 use serde::{Deserialize, Serialize};
@@ -25,10 +26,12 @@ pub mod noise;
 pub mod pulse;
 pub mod triangle;
 
+pub mod driver;
 pub mod envelope;
 pub mod filter;
 pub mod frame_counter;
 pub mod length_counter;
+pub mod mixer;
 pub mod timer;
 
 /// Error when parsing `Channel` from a `usize`.
@@ -98,15 +101,39 @@ pub struct Apu {
     pub triangle: Triangle,
     pub noise: Noise,
     pub dmc: Dmc,
+    #[serde(deserialize_with = "crate::fs::added_in::<8, _, _>")]
+    pub dma: DmaState,
     pub filter_chain: FilterChain,
+    /// Filters used to build `filter_chain`. A frontend setting, so it isn't serialized.
+    #[serde(skip)]
+    pub filter_config: FilterConfig,
+    /// Channel volumes. A frontend setting, so it isn't serialized.
+    #[serde(skip)]
+    pub mixer: Mixer,
     #[serde(skip, default = "Apu::default_channel_outputs")]
     pub channel_outputs: Vec<f32>,
     #[serde(skip)]
     pub audio_samples: Vec<f32>,
+    /// Audio samples taken at the nominal sample rate, ignoring `rate_adjustment`, so that
+    /// recordings aren't warped by dynamic rate control. `None` unless enabled with
+    /// [`Apu::set_capture_record_samples`].
+    #[serde(skip)]
+    pub record_samples: Option<Vec<f32>>,
+    /// Per-channel output levels captured alongside each nominal rate sample, used for
+    /// visualizing channel waveforms and recording stems. `None` unless enabled with
+    /// [`Apu::set_capture_channel_samples`].
+    #[serde(skip)]
+    pub channel_samples: Option<Vec<[f32; Apu::MAX_CHANNEL_COUNT]>>,
     pub sample_rate: f32,
     pub sample_period: f32,
     pub sample_counter: f32,
+    #[serde(deserialize_with = "crate::fs::added_in::<2, _, _>")]
+    pub record_sample_counter: f32,
     pub speed: f32,
+    /// Small adjustment to the effective sample rate used to keep audio output in sync with
+    /// playback. See [`Apu::set_sample_rate_adjustment`].
+    #[serde(skip, default = "Apu::default_rate_adjustment")]
+    pub rate_adjustment: f32,
     pub mapper_silenced: bool,
     pub skip_mixing: bool,
     pub should_clock: bool,
@@ -135,19 +162,30 @@ impl Apu {
             triangle: Triangle::new(),
             noise: Noise::new(region),
             dmc: Dmc::new(region),
-            filter_chain: FilterChain::new(region, sample_rate),
+            dma: DmaState::default(),
+            filter_chain: FilterChain::new(region, sample_rate, FilterConfig::default()),
+            filter_config: FilterConfig::default(),
+            mixer: Mixer::new(),
             channel_outputs: Self::default_channel_outputs(),
             audio_samples: Vec::with_capacity((sample_rate / 60.0) as usize),
+            record_samples: None,
+            channel_samples: None,
             sample_rate,
             sample_period,
             sample_counter: sample_period,
+            record_sample_counter: sample_period,
             speed: 1.0,
+            rate_adjustment: Self::default_rate_adjustment(),
             mapper_silenced: true,
             skip_mixing: false,
             should_clock: false,
         }
     }
 
+    pub const fn default_rate_adjustment() -> f32 {
+        1.0
+    }
+
     pub fn default_channel_outputs() -> Vec<f32> {
         vec![0.0; Self::MAX_CHANNEL_COUNT * Self::CYCLE_SIZE]
     }
@@ -172,17 +210,74 @@ impl Apu {
                 warn!("invalid channel outputs");
                 return;
             };
-            let pulse_idx = (pulse1 + pulse2) as usize;
-            let tnd_idx = (libm::fmaf(3.0f32, *triangle, 2.0 * noise) + dmc) as usize;
-            let apu_output = PULSE_TABLE[pulse_idx] + TND_TABLE[tnd_idx];
             let mapper_output = if self.mapper_silenced { 0.0 } else { *mapper };
+            let output = self
+                .mixer
+                .mix([*pulse1, *pulse2, *triangle, *noise, *dmc, mapper_output]);
 
-            self.filter_chain.consume(apu_output + mapper_output);
+            self.filter_chain.consume(output);
             self.sample_counter -= 1.0;
             if self.sample_counter <= 1.0 {
                 self.audio_samples.push(self.filter_chain.output());
                 self.sample_counter += self.sample_period;
             }
+
+            if self.record_samples.is_none() && self.channel_samples.is_none() {
+                continue;
+            }
+            self.record_sample_counter -= 1.0;
+            if self.record_sample_counter <= 1.0 {
+                if let Some(record_samples) = &mut self.record_samples {
+                    record_samples.push(self.filter_chain.output());
+                }
+                if let Some(channel_samples) = &mut self.channel_samples {
+                    // Normalize APU channels to 0.0..=1.0 based on their DAC resolution
+                    channel_samples.push([
+                        pulse1 / 15.0,
+                        pulse2 / 15.0,
+                        triangle / 15.0,
+                        noise / 15.0,
+                        dmc / 127.0,
+                        mapper_output,
+                    ]);
+                }
+                // Undo the rate adjustment to sample at the nominal rate
+                self.record_sample_counter += self.sample_period * self.rate_adjustment;
+            }
+        }
+    }
+
+    /// Enable or disable capturing per-channel output levels into `channel_samples`.
+    pub fn set_capture_channel_samples(&mut self, enabled: bool) {
+        if enabled {
+            self.channel_samples
+                .get_or_insert_with(|| Vec::with_capacity((self.sample_rate / 60.0) as usize));
+        } else {
+            self.channel_samples = None;
+        }
+    }
+
+    /// Enable or disable capturing nominal rate audio samples into `record_samples`.
+    pub fn set_capture_record_samples(&mut self, enabled: bool) {
+        if enabled {
+            self.record_samples
+                .get_or_insert_with(|| Vec::with_capacity((self.sample_rate / 60.0) as usize));
+        } else {
+            self.record_samples = None;
+        }
+    }
+
+    /// Convert a normalized level from `channel_samples` back into the amount that channel
+    /// contributes to the mixed output, allowing channels to be exported as separate stems.
+    #[must_use]
+    pub fn channel_mix_output(channel: Channel, level: f32) -> f32 {
+        let step = |max: f32| libm::roundf(level.clamp(0.0, 1.0) * max) as usize;
+        match channel {
+            Channel::Pulse1 | Channel::Pulse2 => PULSE_TABLE[step(15.0)],
+            Channel::Triangle => TND_TABLE[step(45.0)],
+            Channel::Noise => TND_TABLE[step(30.0)],
+            Channel::Dmc => TND_TABLE[step(127.0)],
+            Channel::Mapper => level,
         }
     }
 
@@ -190,19 +285,54 @@ impl Apu {
     #[inline]
     pub fn set_sample_rate(&mut self, sample_rate: f32) {
         self.sample_rate = sample_rate;
-        let sample_rate = self.sample_rate / self.speed;
-        self.filter_chain = FilterChain::new(self.region, sample_rate);
-        let clock_rate = Cpu::region_clock_rate(self.region);
-        self.sample_period = clock_rate / sample_rate;
+        self.update_sample_rate();
     }
 
     /// Set the frame speed of the APU, which affects the sampling rate.
     pub fn set_frame_speed(&mut self, speed: f32) {
         self.speed = speed;
-        let sample_rate = self.sample_rate / self.speed;
-        self.filter_chain = FilterChain::new(self.region, sample_rate);
-        let clock_rate = Cpu::region_clock_rate(self.region);
-        self.sample_period = clock_rate / sample_rate;
+        self.update_sample_rate();
+    }
+
+    /// Adjust the effective sample rate by a small `ratio` close to `1.0`, generating slightly
+    /// more or fewer samples per frame without rebuilding the filter chain. Used for dynamic rate
+    /// control to avoid audio underruns or growing latency when the audio device and display
+    /// clocks drift apart.
+    pub fn set_sample_rate_adjustment(&mut self, ratio: f32) {
+        self.rate_adjustment = ratio;
+        self.update_sample_period();
+    }
+
+    /// Set the filters applied to audio output.
+    pub fn set_filter_config(&mut self, config: FilterConfig) {
+        self.filter_config = config;
+        self.update_sample_rate();
+    }
+
+    fn update_sample_rate(&mut self) {
+        self.filter_chain = FilterChain::new(
+            self.region,
+            self.sample_rate / self.speed,
+            self.filter_config,
+        );
+        self.update_sample_period();
+    }
+
+    fn update_sample_period(&mut self) {
+        self.sample_period =
+            self.clock_rate / (self.sample_rate / self.speed * self.rate_adjustment);
+    }
+
+    /// IRQs requested by the frame counter and DMC.
+    pub const fn irqs(&self) -> Irq {
+        let mut irqs = Irq::empty();
+        if self.frame_counter.irq_pending {
+            irqs = irqs.union(Irq::FRAME_COUNTER);
+        }
+        if self.dmc.irq_pending {
+            irqs = irqs.union(Irq::DMC);
+        }
+        irqs
     }
 
     /// Whether a given channel is enabled.
@@ -274,7 +404,9 @@ impl Apu {
     fn should_clock(&mut self) -> bool {
         // Clock every cycle while DMC is running to get accurate CPU stalling, sprite DMA
         // emulation, etc
-        if self.dmc.should_clock() || self.should_clock {
+        let dmc_should_clock = self.dmc.should_clock();
+        self.handle_dmc_dma_request();
+        if dmc_should_clock || self.should_clock {
             self.should_clock = false;
             return true;
         }
@@ -282,6 +414,15 @@ impl Apu {
         self.frame_counter.should_clock(cycles) || self.dmc.irq_pending_in(cycles)
     }
 
+    /// Start or stop a DMC DMA requested by the DMC.
+    fn handle_dmc_dma_request(&mut self) {
+        match self.dmc.dma_request.take() {
+            Some(DmaRequest::Start) => self.dma.start_dmc(),
+            Some(DmaRequest::Stop) => self.dma.stop_dmc(),
+            None => (),
+        }
+    }
+
     fn channel_clock_to(&mut self, channel: Channel, cycle: usize) {
         fn clock_to<T>(instance: &mut T, cycle: usize, offset: usize, outputs: &mut [f32])
         where
@@ -350,6 +491,7 @@ impl ClockTo for Apu {
             self.channel_clock_to(Channel::Triangle, self.cycle);
             self.channel_clock_to(Channel::Noise, self.cycle);
             self.channel_clock_to(Channel::Dmc, self.cycle);
+            self.handle_dmc_dma_request();
         }
 
         cycles
@@ -499,10 +641,10 @@ impl ApuRegisters for Apu {
         self.clock_to(self.master_cycle);
         let val = self.peek_status();
         trace!("APU $4015 read: ${val:02X} - CYC:{}", self.cpu_cycle);
-        if Cpu::has_irq(Irq::FRAME_COUNTER) {
+        if self.frame_counter.irq_pending {
             trace!("APU Frame Counter IRQ - CYC:{}", self.cpu_cycle);
         }
-        Cpu::clear_irq(Irq::FRAME_COUNTER);
+        self.frame_counter.irq_pending = false;
         val
     }
 
@@ -527,11 +669,10 @@ impl ApuRegisters for Apu {
             trace!("dmc bytes remaining: {}", self.dmc.bytes_remaining);
             status |= 0x10;
         }
-        let irqs = Cpu::irqs();
-        if irqs.contains(Irq::FRAME_COUNTER) {
+        if self.frame_counter.irq_pending {
             status |= 0x40;
         }
-        if irqs.contains(Irq::DMC) {
+        if self.dmc.irq_pending {
             status |= 0x80;
         }
         status
@@ -543,7 +684,7 @@ impl ApuRegisters for Apu {
     fn write_status(&mut self, val: u8) {
         self.clock_to(self.master_cycle);
         trace!("APU $4015 write: ${val:02X} - CYC:{}", self.cpu_cycle);
-        Cpu::clear_irq(Irq::DMC);
+        self.dmc.irq_pending = false;
         self.pulse1.set_enabled(val & 0x01 == 0x01);
         self.pulse2.set_enabled(val & 0x02 == 0x02);
         self.triangle.set_enabled(val & 0x04 == 0x04);
@@ -569,8 +710,7 @@ impl Regional for Apu {
             self.clock_to(self.master_cycle);
             self.region = region;
             self.clock_rate = Cpu::region_clock_rate(region);
-            self.filter_chain = FilterChain::new(region, self.sample_rate);
-            self.sample_period = self.clock_rate / self.sample_rate;
+            self.update_sample_rate();
             self.frame_counter.set_region(region);
             self.noise.set_region(region);
             self.dmc.set_region(region);
@@ -590,6 +730,7 @@ impl Reset for Apu {
         self.triangle.reset(kind);
         self.noise.reset(kind);
         self.dmc.reset(kind);
+        self.dma = DmaState::default();
     }
 }
 
@@ -605,7 +746,10 @@ impl core::fmt::Debug for Apu {
             .field("triangle", &self.triangle)
             .field("noise", &self.noise)
             .field("dmc", &self.dmc)
+            .field("dma", &self.dma)
             .field("filter_chain", &self.filter_chain)
+            .field("filter_config", &self.filter_config)
+            .field("mixer", &self.mixer)
             .field("audio_samples_len", &self.audio_samples.len())
             .finish()
     }
@@ -5,11 +5,18 @@
 use crate::{
     apu::timer::{Timer, TimerCycle},
     common::{Clock, NesRegion, Regional, Reset, ResetKind, Sample},
-    cpu::{Cpu, Irq},
 };
 use serde::{Deserialize, Serialize};
 use tracing::trace;
 
+/// DMC DMA request, handled by the [`Apu`](crate::apu::Apu) after clocking the DMC.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[must_use]
+pub enum DmaRequest {
+    Start,
+    Stop,
+}
+
 /// APU DMC (Delta Modulation Channel) provides sample playback.
 ///
 /// See: <https://www.nesdev.org/wiki/APU_DMC>
@@ -20,6 +27,8 @@ pub struct Dmc {
     pub timer: Timer,
     pub force_silent: bool,
     pub irq_enabled: bool,
+    #[serde(deserialize_with = "crate::fs::added_in::<9, _, _>")]
+    pub irq_pending: bool,
     pub loops: bool,
     pub addr: u16,
     pub sample_addr: u16,
@@ -28,11 +37,15 @@ pub struct Dmc {
     pub sample_buffer: u8,
     pub buffer_empty: bool,
     pub init: u8,
+    #[serde(deserialize_with = "crate::fs::added_in::<2, _, _>")]
+    pub disable_delay: u8,
     pub output_level: u8,
     pub bits_remaining: u8,
     pub shift: u8,
     pub silence: bool,
     pub should_clock: bool,
+    #[serde(skip)]
+    pub dma_request: Option<DmaRequest>,
 }
 
 impl Default for Dmc {
@@ -55,6 +68,7 @@ impl Dmc {
             timer: Timer::preload(Self::period(region, 0)),
             force_silent: false,
             irq_enabled: false,
+            irq_pending: false,
             loops: false,
             addr: 0xC000,
             sample_addr: 0x0000,
@@ -63,11 +77,13 @@ impl Dmc {
             sample_buffer: 0x00,
             buffer_empty: true,
             init: 0,
+            disable_delay: 0,
             output_level: 0x00,
             bits_remaining: 0x08,
             shift: 0x00,
             silence: true,
             should_clock: false,
+            dma_request: None,
         }
     }
 
@@ -123,29 +139,30 @@ impl Dmc {
                 if self.loops {
                     self.init_sample();
                 } else if self.irq_enabled {
-                    Cpu::set_irq(Irq::DMC);
+                    self.irq_pending = true;
                 }
             }
         }
     }
 
-    const fn period(region: NesRegion, val: u8) -> usize {
-        let index = (val & 0x0F) as usize;
+    const fn period_table(region: NesRegion) -> &'static [usize; 16] {
         match region {
-            NesRegion::Auto | NesRegion::Ntsc | NesRegion::Dendy => {
-                Self::PERIOD_TABLE_NTSC[index] - 1
-            }
-            NesRegion::Pal => Self::PERIOD_TABLE_PAL[index] - 1,
+            NesRegion::Auto | NesRegion::Ntsc | NesRegion::Dendy => &Self::PERIOD_TABLE_NTSC,
+            NesRegion::Pal => &Self::PERIOD_TABLE_PAL,
         }
     }
 
+    const fn period(region: NesRegion, val: u8) -> usize {
+        Self::period_table(region)[(val & 0x0F) as usize] - 1
+    }
+
     /// $4010 DMC timer
     pub fn write_timer(&mut self, val: u8) {
         self.irq_enabled = val & 0x80 == 0x80;
         self.loops = val & 0x40 == 0x40;
         self.timer.period = Self::period(self.region, val);
         if !self.irq_enabled {
-            Cpu::clear_irq(Irq::DMC);
+            self.irq_pending = false;
         }
     }
 
@@ -167,8 +184,11 @@ impl Dmc {
     /// $4015 WRITE
     pub fn set_enabled(&mut self, enabled: bool, cycle: usize) {
         if !enabled {
-            self.bytes_remaining = 0;
-            self.should_clock = false;
+            // Disabling takes effect after a delay based on even/odd cycle. A DMA that starts in
+            // the meantime is aborted, but still halts the CPU.
+            if self.disable_delay == 0 {
+                self.disable_delay = if cycle & 0x01 == 0x00 { 2 } else { 3 };
+            }
         } else if self.bytes_remaining == 0 {
             self.init_sample();
             // Delay a number of cycles based on even/odd cycle
@@ -177,11 +197,19 @@ impl Dmc {
     }
 
     pub fn should_clock(&mut self) -> bool {
+        if self.disable_delay > 0 {
+            self.disable_delay -= 1;
+            if self.disable_delay == 0 {
+                self.bytes_remaining = 0;
+                self.should_clock = false;
+                self.dma_request = Some(DmaRequest::Stop);
+            }
+        }
         if self.init > 0 {
             self.init -= 1;
             if self.init == 0 && self.buffer_empty && self.bytes_remaining > 0 {
                 trace!("APU DMC DMA pending");
-                Cpu::start_dmc_dma();
+                self.dma_request = Some(DmaRequest::Start);
             }
         }
         self.should_clock
@@ -237,7 +265,7 @@ impl Clock for Dmc {
                     self.buffer_empty = true;
                     if self.bytes_remaining > 0 {
                         trace!("APU DMC DMA pending");
-                        Cpu::start_dmc_dma();
+                        self.dma_request = Some(DmaRequest::Start);
                     }
                 }
             }
@@ -254,8 +282,13 @@ impl Regional for Dmc {
     }
 
     fn set_region(&mut self, region: NesRegion) {
+        // Keep the current rate when switching regions at runtime
+        let index = Self::period_table(self.region)
+            .iter()
+            .position(|&period| period - 1 == self.timer.period)
+            .unwrap_or(0);
         self.region = region;
-        self.timer.period = Self::period(region, 0);
+        self.timer.period = Self::period(region, index as u8);
     }
 }
 
@@ -271,9 +304,11 @@ impl Reset for Dmc {
             self.sample_length = 1;
         }
         self.irq_enabled = false;
+        self.irq_pending = false;
         self.loops = false;
         self.addr = 0x0000;
         self.bytes_remaining = 0;
+        self.disable_delay = 0;
         self.sample_buffer = 0x00;
         self.buffer_empty = true;
         self.output_level = 0x00;
@@ -281,5 +316,21 @@ impl Reset for Dmc {
         self.shift = 0x00;
         self.silence = true;
         self.should_clock = false;
+        self.dma_request = None;
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_region_keeps_rate() {
+        let mut dmc = Dmc::new(NesRegion::Ntsc);
+        dmc.write_timer(0x0F);
+        dmc.set_region(NesRegion::Pal);
+        assert_eq!(dmc.timer.period, Dmc::PERIOD_TABLE_PAL[0x0F] - 1);
+        dmc.set_region(NesRegion::Ntsc);
+        assert_eq!(dmc.timer.period, Dmc::PERIOD_TABLE_NTSC[0x0F] - 1);
     }
 }
@@ -0,0 +1,396 @@
+//! Sound driver detection and track metadata extraction.
+//!
+//! Detection is a best-effort heuristic based on markers commonly left in PRG-ROM by music engines
+//! and their tools, so it can miss drivers or, rarely, misidentify them.
+
+use alloc::{string::String, vec::Vec};
+use bitflags::bitflags;
+use core::{fmt, time::Duration};
+use serde::{Deserialize, Serialize};
+
+/// Music engine used by a game.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[must_use]
+pub enum SoundDriver {
+    FamiTracker,
+    FamiStudio,
+    FamiTone,
+    Capcom,
+    Konami,
+}
+
+impl SoundDriver {
+    /// Text markers identifying each driver, checked in order so tracker engines used by homebrew
+    /// take priority over publisher names that may appear in credits or copyright text.
+    const MARKERS: [(Self, &'static [u8]); 5] = [
+        (Self::FamiStudio, b"FamiStudio"),
+        (Self::FamiTracker, b"FamiTracker"),
+        (Self::FamiTone, b"FamiTone"),
+        (Self::Capcom, b"CAPCOM"),
+        (Self::Konami, b"KONAMI"),
+    ];
+
+    /// Detect the driver from `prg_rom` and `mapper_num`.
+    #[must_use]
+    pub fn detect(mapper_num: u16, prg_rom: &[u8]) -> Option<Self> {
+        Self::MARKERS
+            .into_iter()
+            .find(|(_, marker)| contains(prg_rom, marker))
+            .map(|(driver, _)| driver)
+            .or(match mapper_num {
+                // VRC6 and VRC7 expansion audio was only used by Konami's own driver
+                24 | 26 | 85 => Some(Self::Konami),
+                _ => None,
+            })
+    }
+
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::FamiTracker => "FamiTracker",
+            Self::FamiStudio => "FamiStudio",
+            Self::FamiTone => "FamiTone",
+            Self::Capcom => "Capcom",
+            Self::Konami => "Konami",
+        }
+    }
+}
+
+impl fmt::Display for SoundDriver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+bitflags! {
+    /// Expansion audio chips used by an `NSF`, as flagged in its header.
+    #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    #[must_use]
+    pub struct ExpansionAudio: u8 {
+        const VRC6 = 0x01;
+        const VRC7 = 0x02;
+        const FDS = 0x04;
+        const MMC5 = 0x08;
+        const N163 = 0x10;
+        const SUNSOFT_5B = 0x20;
+    }
+}
+
+impl ExpansionAudio {
+    /// Chips emulated by the `NSF` player.
+    pub const SUPPORTED: Self = Self::VRC6.union(Self::MMC5);
+
+    const NAMES: [(Self, &'static str); 6] = [
+        (Self::VRC6, "VRC6"),
+        (Self::VRC7, "VRC7"),
+        (Self::FDS, "FDS"),
+        (Self::MMC5, "MMC5"),
+        (Self::N163, "Namco 163"),
+        (Self::SUNSOFT_5B, "Sunsoft 5B"),
+    ];
+
+    /// Chips that aren't emulated, and will be silent when played.
+    pub const fn unsupported(self) -> Self {
+        self.difference(Self::SUPPORTED)
+    }
+}
+
+impl fmt::Display for ExpansionAudio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names = Self::NAMES
+            .into_iter()
+            .filter(|(chip, _)| self.contains(*chip))
+            .map(|(_, name)| name);
+        if let Some(name) = names.next() {
+            f.write_str(name)?;
+        }
+        for name in names {
+            write!(f, ", {name}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Sound driver and music metadata detected in a ROM.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub struct SoundInfo {
+    pub driver: Option<SoundDriver>,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub copyright: Option<String>,
+    /// Number of tracks, if known.
+    pub track_count: Option<u8>,
+    /// Track names, indexed by track. Empty if not known.
+    pub tracks: Vec<String>,
+    /// First track to play, starting at `0`, if known.
+    pub starting_track: Option<u8>,
+    /// Track lengths before fading out, indexed by track. Empty if not known.
+    pub track_times: Vec<Option<Duration>>,
+    /// Track fade out lengths, indexed by track. Empty if not known.
+    pub track_fades: Vec<Option<Duration>>,
+    pub expansion_audio: ExpansionAudio,
+}
+
+impl SoundInfo {
+    pub(crate) const NSF_MAGIC: &'static [u8] = b"NESM\x1A";
+    pub(crate) const NSFE_MAGIC: &'static [u8] = b"NSFE";
+    pub(crate) const NSF_HEADER_SIZE: usize = 0x80;
+
+    /// Detect the sound driver in `prg_rom` and extract any music metadata left by NSF or NSFe
+    /// headers embedded in it, e.g. by NSF-to-ROM converters, or from an `NSF`, `NSF2` or `NSFe`
+    /// file itself. Returns `None` if nothing was found.
+    pub fn detect(mapper_num: u16, prg_rom: &[u8]) -> Option<Self> {
+        let mut info = Self {
+            driver: SoundDriver::detect(mapper_num, prg_rom),
+            ..Self::default()
+        };
+        if let Some(start) = find(prg_rom, Self::NSFE_MAGIC) {
+            info.parse_nsfe(&prg_rom[start + Self::NSFE_MAGIC.len()..]);
+        } else if let Some(start) = find(prg_rom, Self::NSF_MAGIC) {
+            info.parse_nsf(&prg_rom[start..]);
+        }
+        (info != Self::default()).then_some(info)
+    }
+
+    /// Name of track `index`, if known.
+    #[must_use]
+    pub fn track_name(&self, index: usize) -> Option<&str> {
+        self.tracks
+            .get(index)
+            .map(String::as_str)
+            .filter(|name| !name.is_empty())
+    }
+
+    /// Length of track `index` including its fade out, if known.
+    #[must_use]
+    pub fn track_length(&self, index: usize) -> Option<Duration> {
+        let time = self.track_times.get(index).copied().flatten()?;
+        let fade = self.track_fades.get(index).copied().flatten();
+        Some(time + fade.unwrap_or_default())
+    }
+
+    /// Parse a classic NSF header, followed by `NSFe` metadata chunks for `NSF2`.
+    ///
+    /// See: <https://www.nesdev.org/wiki/NSF#Header_Overview>
+    /// See: <https://www.nesdev.org/wiki/NSF2>
+    fn parse_nsf(&mut self, nsf: &[u8]) {
+        let Some(header) = nsf.get(..Self::NSF_HEADER_SIZE) else {
+            return;
+        };
+        self.track_count = Some(header[0x06]).filter(|&count| count > 0);
+        self.starting_track = header[0x07].checked_sub(1);
+        self.title = fixed_str(&header[0x0E..0x2E]);
+        self.artist = fixed_str(&header[0x2E..0x4E]);
+        self.copyright = fixed_str(&header[0x4E..0x6E]);
+        self.expansion_audio = ExpansionAudio::from_bits_truncate(header[0x7B]);
+        if let Some(program_len) = nsf2_program_len(header) {
+            if let Some(chunks) = nsf.get(Self::NSF_HEADER_SIZE + program_len..) {
+                self.parse_nsfe(chunks);
+            }
+        }
+    }
+
+    /// Parse NSFe chunks following the `NSFE` magic, stopping at `NEND` or the first malformed
+    /// chunk.
+    ///
+    /// See: <https://www.nesdev.org/wiki/NSFe>
+    fn parse_nsfe(&mut self, chunks: &[u8]) {
+        for (id, data) in nsfe_chunks(chunks) {
+            match id {
+                b"INFO" => {
+                    if let Some(&expansion_audio) = data.get(7) {
+                        self.expansion_audio = ExpansionAudio::from_bits_truncate(expansion_audio);
+                    }
+                    self.track_count = data.get(8).copied().filter(|&count| count > 0);
+                    self.starting_track = data.get(9).copied();
+                }
+                b"auth" => {
+                    let mut strings = null_terminated(data);
+                    self.title = strings.next().flatten();
+                    self.artist = strings.next().flatten();
+                    self.copyright = strings.next().flatten();
+                }
+                b"tlbl" => {
+                    self.tracks = null_terminated(data)
+                        .map(Option::unwrap_or_default)
+                        .collect()
+                }
+                b"time" => self.track_times = durations(data),
+                b"fade" => self.track_fades = durations(data),
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Length of the program data in an `NSF2` header, which is followed by `NSFe` metadata chunks,
+/// or `None` if there are no chunks.
+pub(crate) fn nsf2_program_len(header: &[u8]) -> Option<usize> {
+    if header[0x05] < 2 {
+        return None;
+    }
+    let len = u32::from_le_bytes([header[0x7D], header[0x7E], header[0x7F], 0]) as usize;
+    (len > 0).then_some(len)
+}
+
+/// Iterate over the IDs and data of `NSFe` chunks, stopping at `NEND` or the first malformed
+/// chunk.
+pub(crate) fn nsfe_chunks(mut chunks: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> + '_ {
+    core::iter::from_fn(move || {
+        let len = u32::from_le_bytes(chunks.get(..4)?.try_into().ok()?) as usize;
+        let id = chunks.get(4..8)?;
+        let end = len.checked_add(8)?;
+        let data = chunks.get(8..end)?;
+        if id == b"NEND" {
+            return None;
+        }
+        chunks = &chunks[end..];
+        Some((id, data))
+    })
+}
+
+/// Parse little-endian millisecond lengths, where negative lengths aren't known.
+fn durations(bytes: &[u8]) -> Vec<Option<Duration>> {
+    bytes
+        .chunks_exact(4)
+        .map(|ms| {
+            let ms = i32::from_le_bytes([ms[0], ms[1], ms[2], ms[3]]);
+            u64::try_from(ms).ok().map(Duration::from_millis)
+        })
+        .collect()
+}
+
+/// Position of the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    find(haystack, needle).is_some()
+}
+
+/// Parse a fixed-size, null-padded string, returning `None` if empty.
+fn fixed_str(bytes: &[u8]) -> Option<String> {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    non_empty(&bytes[..end])
+}
+
+/// Iterate over null-terminated strings, yielding `None` for empty ones.
+fn null_terminated(bytes: &[u8]) -> impl Iterator<Item = Option<String>> + '_ {
+    bytes
+        .strip_suffix(&[0])
+        .unwrap_or(bytes)
+        .split(|&b| b == 0)
+        .map(non_empty)
+}
+
+fn non_empty(bytes: &[u8]) -> Option<String> {
+    let s = String::from_utf8_lossy(bytes);
+    let s = s.trim();
+    (!s.is_empty() && s != "<?>").then(|| s.into())
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, vec};
+
+    #[test]
+    fn detect_driver() {
+        let mut prg_rom = vec![0xEA; 0x4000];
+        assert_eq!(SoundInfo::detect(0, &prg_rom), None);
+        assert_eq!(SoundDriver::detect(24, &prg_rom), Some(SoundDriver::Konami));
+
+        prg_rom[0x100..0x10A].copy_from_slice(b"FamiStudio");
+        prg_rom[0x200..0x206].copy_from_slice(b"KONAMI");
+        assert_eq!(
+            SoundDriver::detect(0, &prg_rom),
+            Some(SoundDriver::FamiStudio),
+            "tracker markers take priority"
+        );
+    }
+
+    #[test]
+    fn nsf_metadata() {
+        let mut prg_rom = vec![0x00; 0x1000];
+        let header = &mut prg_rom[0x400..0x480];
+        header[..5].copy_from_slice(SoundInfo::NSF_MAGIC);
+        header[0x06] = 12;
+        header[0x0E..0x13].copy_from_slice(b"Title");
+        header[0x2E..0x34].copy_from_slice(b"Artist");
+        header[0x4E..0x52].copy_from_slice(b"<?>\0");
+        let info = SoundInfo::detect(0, &prg_rom).expect("sound info");
+        assert_eq!(info.driver, None);
+        assert_eq!(info.track_count, Some(12));
+        assert_eq!(info.title.as_deref(), Some("Title"));
+        assert_eq!(info.artist.as_deref(), Some("Artist"));
+        assert_eq!(info.copyright, None);
+    }
+
+    #[test]
+    fn nsfe_metadata() {
+        let mut prg_rom = b"FamiTracker".to_vec();
+        prg_rom.extend_from_slice(SoundInfo::NSFE_MAGIC);
+        let mut chunk = |id: &[u8], data: &[u8]| {
+            prg_rom.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            prg_rom.extend_from_slice(id);
+            prg_rom.extend_from_slice(data);
+        };
+        chunk(b"INFO", &[0, 0x80, 0, 0x80, 0, 0x80, 0, 0x01, 3, 1]);
+        chunk(b"auth", b"Game\0Composer\0\0Ripper\0");
+        chunk(b"tlbl", b"Title\0\0Ending\0");
+        let times = [90_000i32, -1, 30_000];
+        chunk(b"time", &times.map(i32::to_le_bytes).concat());
+        chunk(b"fade", &5_000i32.to_le_bytes());
+        chunk(b"NEND", &[]);
+
+        let info = SoundInfo::detect(0, &prg_rom).expect("sound info");
+        assert_eq!(info.driver, Some(SoundDriver::FamiTracker));
+        assert_eq!(info.track_count, Some(3));
+        assert_eq!(info.title.as_deref(), Some("Game"));
+        assert_eq!(info.artist.as_deref(), Some("Composer"));
+        assert_eq!(info.copyright, None);
+        assert_eq!(info.track_name(0), Some("Title"));
+        assert_eq!(info.track_name(1), None);
+        assert_eq!(info.track_name(2), Some("Ending"));
+        assert_eq!(info.starting_track, Some(1));
+        assert_eq!(info.expansion_audio, ExpansionAudio::VRC6);
+        assert_eq!(info.track_length(0), Some(Duration::from_secs(95)));
+        assert_eq!(info.track_length(1), None);
+        assert_eq!(info.track_length(2), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn nsf2_metadata() {
+        let mut nsf = vec![0x00; SoundInfo::NSF_HEADER_SIZE];
+        nsf[..5].copy_from_slice(SoundInfo::NSF_MAGIC);
+        nsf[0x05] = 2;
+        nsf[0x06] = 2;
+        nsf[0x07] = 1;
+        nsf[0x0E..0x13].copy_from_slice(b"Title");
+        nsf[0x7B] = 0x28;
+        nsf[0x7D] = 0x04;
+        nsf.extend_from_slice(&[0xEA; 4]);
+        nsf.extend_from_slice(&8u32.to_le_bytes());
+        nsf.extend_from_slice(b"time");
+        nsf.extend_from_slice(&[60_000i32, 120_000].map(i32::to_le_bytes).concat());
+
+        let info = SoundInfo::detect(0, &nsf).expect("sound info");
+        assert_eq!(info.track_count, Some(2));
+        assert_eq!(info.starting_track, Some(0));
+        assert_eq!(info.title.as_deref(), Some("Title"));
+        assert_eq!(
+            info.expansion_audio,
+            ExpansionAudio::MMC5 | ExpansionAudio::SUNSOFT_5B
+        );
+        assert_eq!(
+            info.expansion_audio.unsupported(),
+            ExpansionAudio::SUNSOFT_5B
+        );
+        assert_eq!(info.expansion_audio.to_string(), "MMC5, Sunsoft 5B");
+        assert_eq!(info.track_length(1), Some(Duration::from_secs(120)));
+    }
+}
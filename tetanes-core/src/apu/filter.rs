@@ -221,6 +221,44 @@ impl SampledFilter {
     }
 }
 
+/// Cutoff frequencies in Hz of the first-order filters emulating the analog output stage of the
+/// console. A cutoff of `None` bypasses that filter.
+///
+/// The final low-pass filter used to downsample to the output sample rate is always applied.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+#[must_use]
+pub struct FilterConfig {
+    /// High-pass filter cutoffs, removing DC offset and low frequencies.
+    pub high_pass: [Option<f32>; 2],
+    /// Low-pass filter cutoff, removing high frequencies.
+    pub low_pass: Option<f32>,
+}
+
+impl Default for FilterConfig {
+    fn default() -> Self {
+        Self::NES
+    }
+}
+
+impl FilterConfig {
+    /// Filters of the NES front-loader: 90 Hz and 440 Hz high-pass and 14 kHz low-pass.
+    pub const NES: Self = Self {
+        high_pass: [Some(90.0), Some(440.0)],
+        low_pass: Some(14_000.0),
+    };
+    /// Filters of the Famicom: 37 Hz high-pass and 14 kHz low-pass.
+    pub const FAMICOM: Self = Self {
+        high_pass: [Some(37.0), None],
+        low_pass: Some(14_000.0),
+    };
+    /// No hardware filters, for clean output.
+    pub const BYPASS: Self = Self {
+        high_pass: [None; 2],
+        low_pass: None,
+    };
+}
+
 /// Represents a chain of filters for a given [`NesRegion`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FilterChain {
@@ -230,7 +268,7 @@ pub struct FilterChain {
 }
 
 impl FilterChain {
-    pub fn new(region: NesRegion, output_rate: f32) -> Self {
+    pub fn new(region: NesRegion, output_rate: f32, config: FilterConfig) -> Self {
         let clock_rate = Cpu::region_clock_rate(region);
         let intermediate_sample_rate = output_rate * 2.0 + (PI / 32.0);
         let intermediate_cutoff = output_rate * 0.4;
@@ -239,27 +277,20 @@ impl FilterChain {
             SampledFilter::new(Iir::identity(), 1.0),
             SampledFilter::new(Iir::low_pass(clock_rate, intermediate_cutoff), clock_rate),
         ];
-        // first-order high-pass filter at 90 Hz
-        filters.push(SampledFilter::new(
-            Iir::high_pass(intermediate_sample_rate, 90.0),
-            intermediate_sample_rate,
-        ));
-        // first-order high-pass filter at 440 Hz
-        filters.push(SampledFilter::new(
-            Iir::high_pass(intermediate_sample_rate, 440.0),
-            intermediate_sample_rate,
-        ));
-        // first-order low-pass filter at 14 kHz
-        filters.push(SampledFilter::new(
-            Iir::low_pass(intermediate_sample_rate, 14000.0),
-            intermediate_sample_rate,
-        ));
-        // TODO: Support famicom filter selection
-        // // first-order high-pass filter at 37 Hz
-        // filters.push(SampledFilter::new(
-        //     Iir::high_pass(intermediate_sample_rate, 37.0),
-        //     intermediate_sample_rate,
-        // ));
+        // first-order high-pass filters, 90 Hz and 440 Hz by default
+        for cutoff in config.high_pass.into_iter().flatten() {
+            filters.push(SampledFilter::new(
+                Iir::high_pass(intermediate_sample_rate, cutoff),
+                intermediate_sample_rate,
+            ));
+        }
+        // first-order low-pass filter, 14 kHz by default
+        if let Some(cutoff) = config.low_pass {
+            filters.push(SampledFilter::new(
+                Iir::low_pass(intermediate_sample_rate, cutoff),
+                intermediate_sample_rate,
+            ));
+        }
 
         // high-quality low-pass filter
         let window_size = 160;
@@ -2,10 +2,7 @@
 //!
 //! See: <https://www.nesdev.org/wiki/APU_Frame_Counter>
 
-use crate::{
-    common::{NesRegion, Reset, ResetKind},
-    cpu::{Cpu, Irq},
-};
+use crate::common::{NesRegion, Reset, ResetKind};
 use serde::{Deserialize, Serialize};
 use tracing::trace;
 
@@ -23,6 +20,8 @@ pub struct FrameCounter {
     pub block_counter: u8,
     pub cycle: usize,
     pub inhibit_irq: bool, // Set by $4017 D6
+    #[serde(deserialize_with = "crate::fs::added_in::<9, _, _>")]
+    pub irq_pending: bool,
 }
 
 /// The Frame Counter clock type.
@@ -65,6 +64,7 @@ impl FrameCounter {
             block_counter: 0,
             cycle: 0,
             inhibit_irq: false,
+            irq_pending: false,
         }
     }
 
@@ -89,7 +89,7 @@ impl FrameCounter {
         self.inhibit_irq = val & 0x40 == 0x40; // D6
         if self.inhibit_irq {
             trace!("APU Frame Counter IRQ inhibit");
-            Cpu::clear_irq(Irq::FRAME_COUNTER);
+            self.irq_pending = false;
         }
     }
 
@@ -119,7 +119,7 @@ impl FrameCounter {
                     "APU Frame Counter IRQ pending - cycles: {} >= {step_cycles}",
                     self.cycle + cycles
                 );
-                Cpu::set_irq(Irq::FRAME_COUNTER);
+                self.irq_pending = true;
             }
 
             let ty = Self::FRAME_TYPE[self.step];
@@ -176,15 +176,149 @@ impl FrameCounter {
 impl Reset for FrameCounter {
     fn reset(&mut self, kind: ResetKind) {
         self.cycle = 0;
-        if kind == ResetKind::Hard {
-            self.mode = 0;
-            // After reset, APU acts as if $4017 was written 9-12 clocks before first instruction,
-            // Reset acts as if $00 was written to $4017
-            self.write(0x00, 0);
-            self.write_delay -= 1; // FIXME: Startup timing is slightly wrong, reset_timing fails
-                                   // with the default
-        }
         self.step = 0;
         self.block_counter = 0;
+        self.irq_pending = false;
+        // After power-up, the APU acts as if $00 was written to $4017 9-12 clocks before the first
+        // instruction. Reset does the same with the last value written instead.
+        let val = match kind {
+            ResetKind::Soft => (self.mode as u8) << 7 | u8::from(self.inhibit_irq) << 6,
+            ResetKind::Hard => {
+                self.mode = 0;
+                0x00
+            }
+        };
+        self.write(val, 0);
+        self.write_delay -= 1; // FIXME: Startup timing is slightly wrong, reset_timing fails
+                               // with the default
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{
+        common::Regional,
+        control_deck::{Config, ControlDeck},
+        mem::RamState,
+    };
+    use alloc::{format, string::String, vec::Vec};
+
+    fn load_test(name: &str, region: NesRegion) -> ControlDeck {
+        let mut deck = ControlDeck::with_config(Config {
+            ram_state: RamState::AllZeros,
+            ..Default::default()
+        });
+        let path = format!("{}/test_roms/apu/{name}.nes", env!("CARGO_MANIFEST_DIR"));
+        let rom = std::fs::read(&path).expect("valid rom");
+        deck.load_rom(name, &mut rom.as_slice()).expect("valid rom");
+        deck.set_region(region);
+        deck
+    }
+
+    /// Run one of blargg's older APU tests, which store their result code at `addr` once done,
+    /// where 1 is passing.
+    fn run_legacy_test(name: &str, region: NesRegion, addr: usize) -> u8 {
+        let mut deck = load_test(name, region);
+        while deck.frame_number() < 25 {
+            deck.clock_frame().expect("valid frame clock");
+        }
+        deck.wram()[addr]
+    }
+
+    /// Run one of blargg's newer APU tests, which report their status and a text result at
+    /// $6000, pressing reset when requested. Returns the result text on failure.
+    fn run_status_test(name: &str) -> Result<(), String> {
+        const SIGNATURE: [u8; 3] = [0xDE, 0xB0, 0x61];
+        const RUNNING: u8 = 0x80;
+        const NEEDS_RESET: u8 = 0x81;
+
+        let mut deck = load_test(name, NesRegion::Ntsc);
+        let mut status = RUNNING;
+        let mut reset_frame = None;
+        while deck.frame_number() < 120 {
+            deck.clock_frame().expect("valid frame clock");
+            let sram = deck.cpu().bus.sram();
+            if sram.get(1..4) != Some(&SIGNATURE) {
+                continue;
+            }
+            // Reset a few frames after it's requested, like a person pressing the button
+            if sram[0] == NEEDS_RESET && status != NEEDS_RESET {
+                reset_frame = Some(deck.frame_number() + 5);
+            }
+            status = sram[0];
+            if status < RUNNING {
+                break;
+            }
+            if reset_frame == Some(deck.frame_number()) {
+                deck.reset(ResetKind::Soft);
+            }
+        }
+        let sram = deck.cpu().bus.sram();
+        match status {
+            0x00 => Ok(()),
+            RUNNING.. => Err(String::from("timed out")),
+            code => Err(format!(
+                "failed #{code}: {}",
+                String::from_utf8_lossy(&sram[4..])
+                    .trim_end_matches('\0')
+                    .trim()
+            )),
+        }
+    }
+
+    /// Accuracy dashboard for blargg's APU tests that report a result code. Tests that don't pass
+    /// yet are tracked in `KNOWN_FAILURES`, so both fixes and regressions fail this test until
+    /// the list is updated.
+    #[test]
+    fn blargg_apu_suite() {
+        const KNOWN_FAILURES: [&str; 0] = [];
+
+        let mut results = Vec::new();
+        for name in [
+            "clock_jitter",
+            "irq_flag",
+            "irq_flag_timing",
+            "irq_timing",
+            "len_ctr",
+            "len_halt_timing",
+            "len_reload_timing",
+            "len_table",
+            "len_timing_mode0",
+            "len_timing_mode1",
+            "reset_timing",
+        ] {
+            // blargg's NTSC APU tests store the result code at $F0
+            let result = match run_legacy_test(name, NesRegion::Ntsc, 0xF0) {
+                1 => Ok(()),
+                code => Err(format!("failed #{code}")),
+            };
+            results.push((name, result));
+        }
+        for name in [
+            "dmc_basics",
+            "dmc_rates",
+            "len_timing",
+            "reset_4015_cleared",
+            "reset_4017_timing",
+            "reset_4017_written",
+            "reset_irq_flag_cleared",
+            "reset_len_ctrs_enabled",
+            "reset_works_immediately",
+        ] {
+            results.push((name, run_status_test(name)));
+        }
+
+        let passed = results.iter().filter(|(_, result)| result.is_ok()).count();
+        std::println!("blargg APU tests: {passed}/{} passed", results.len());
+        let failures = results
+            .iter()
+            .filter_map(|(name, result)| result.as_ref().err().map(|err| (*name, err)))
+            .collect::<Vec<_>>();
+        for (name, err) in &failures {
+            std::println!("  {name}: {err}");
+        }
+        let failed = failures.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        assert_eq!(failed, KNOWN_FAILURES, "{failures:#?}");
     }
 }
@@ -0,0 +1,170 @@
+//! Channel mixing for the [`Apu`].
+//!
+//! See <https://www.nesdev.org/wiki/APU_Mixer>
+
+use crate::apu::{Apu, Channel, PULSE_TABLE, TND_TABLE};
+use serde::{Deserialize, Serialize};
+
+/// Volume levels applied when mixing [`Apu`] channels. A volume of `1.0` plays a channel at its
+/// original level.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+#[must_use]
+pub struct Mixer {
+    /// Per-channel volumes, indexed by [`Channel`].
+    pub channel_volumes: [f32; Apu::MAX_CHANNEL_COUNT],
+    /// Volume applied to the mixed output.
+    pub master_volume: f32,
+    /// Whether to mix with the nonlinear DAC formulas, modeling both DACs driving a shared load
+    /// so that the triangle, noise and DMC levels also attenuate the pulse channels and vice
+    /// versa. Otherwise, the lookup tables are used, which treat each DAC independently.
+    pub accurate: bool,
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mixer {
+    /// Maximum volume, allowing quiet channels to be boosted.
+    pub const MAX_VOLUME: f32 = 2.0;
+
+    pub const fn new() -> Self {
+        Self {
+            channel_volumes: [1.0; Apu::MAX_CHANNEL_COUNT],
+            master_volume: 1.0,
+            accurate: false,
+        }
+    }
+
+    /// Returns the volume of a given channel.
+    #[must_use]
+    pub const fn volume(&self, channel: Channel) -> f32 {
+        self.channel_volumes[channel as usize]
+    }
+
+    /// Set the volume of a given channel, from `0.0` to [`Mixer::MAX_VOLUME`].
+    pub fn set_volume(&mut self, channel: Channel, volume: f32) {
+        self.channel_volumes[channel as usize] = volume.clamp(0.0, Self::MAX_VOLUME);
+    }
+
+    /// Set the volume of the mixed output, from `0.0` to [`Mixer::MAX_VOLUME`].
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.master_volume = volume.clamp(0.0, Self::MAX_VOLUME);
+    }
+
+    /// Mix channel outputs, indexed by [`Channel`], into a single sample.
+    ///
+    /// Channels sharing a DAC are mixed at their original level first and then scaled by their
+    /// volume-weighted share of the DAC input, so channels at full volume mix exactly as they
+    /// would without volume control.
+    #[must_use]
+    pub fn mix(&self, outputs: [f32; Apu::MAX_CHANNEL_COUNT]) -> f32 {
+        let [pulse1, pulse2, triangle, noise, dmc, mapper] = outputs;
+        let [pulse1_vol, pulse2_vol, triangle_vol, noise_vol, dmc_vol, mapper_vol] =
+            self.channel_volumes;
+        let scale = |inputs: [f32; 3], volumes: [f32; 3]| {
+            let total: f32 = inputs.iter().sum();
+            // Skip weighting in the common case of all channels at the same volume
+            if volumes[0] == volumes[1] && volumes[1] == volumes[2] {
+                volumes[0]
+            } else if total > 0.0 {
+                inputs.iter().zip(volumes).map(|(i, v)| i * v).sum::<f32>() / total
+            } else {
+                0.0
+            }
+        };
+
+        let (pulse_out, tnd_out) = if self.accurate {
+            // Conductances of each DAC relative to the load resistance
+            let pulse_g = (pulse1 + pulse2) / 8128.0;
+            let tnd_inputs = [triangle / 8227.0, noise / 12241.0, dmc / 22638.0];
+            let tnd_g: f32 = tnd_inputs.iter().sum();
+            let load = pulse_g + tnd_g + 0.01;
+            (
+                0.9588 * pulse_g / load
+                    * scale([pulse1, pulse2, 0.0], [pulse1_vol, pulse2_vol, pulse2_vol]),
+                1.5979 * tnd_g / load * scale(tnd_inputs, [triangle_vol, noise_vol, dmc_vol]),
+            )
+        } else {
+            let pulse_idx = (pulse1 + pulse2) as usize;
+            let tnd_inputs = [3.0 * triangle, 2.0 * noise, dmc];
+            let tnd_idx = (libm::fmaf(3.0, triangle, 2.0 * noise) + dmc) as usize;
+            (
+                PULSE_TABLE[pulse_idx]
+                    * scale([pulse1, pulse2, 0.0], [pulse1_vol, pulse2_vol, pulse2_vol]),
+                TND_TABLE[tnd_idx] * scale(tnd_inputs, [triangle_vol, noise_vol, dmc_vol]),
+            )
+        };
+
+        (pulse_out + tnd_out + mapper * mapper_vol) * self.master_volume
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unity_volume_matches_lookup_tables() {
+        let mixer = Mixer::new();
+        let outputs = [3.0, 12.0, 9.0, 4.0, 64.0, 0.25];
+        let expected = PULSE_TABLE[15] + TND_TABLE[3 * 9 + 2 * 4 + 64] + 0.25;
+        assert!((mixer.mix(outputs) - expected).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn channel_and_master_volume() {
+        let mut mixer = Mixer::new();
+        mixer.set_volume(Channel::Pulse1, 0.0);
+        assert_eq!(
+            mixer.mix([15.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+            0.0,
+            "muted pulse1"
+        );
+        assert!(
+            mixer.mix([0.0, 15.0, 0.0, 0.0, 0.0, 0.0]) > 0.0,
+            "pulse2 unaffected"
+        );
+
+        mixer.set_volume(Channel::Mapper, 0.5);
+        mixer.set_master_volume(0.5);
+        assert!((mixer.mix([0.0, 0.0, 0.0, 0.0, 0.0, 1.0]) - 0.25).abs() < f32::EPSILON);
+
+        mixer.set_volume(Channel::Dmc, 5.0);
+        assert_eq!(mixer.volume(Channel::Dmc), Mixer::MAX_VOLUME, "clamped");
+    }
+
+    #[test]
+    fn accurate_mixing() {
+        let table = Mixer::new();
+        let accurate = Mixer {
+            accurate: true,
+            ..Mixer::new()
+        };
+
+        // Each DAC on its own matches the lookup tables, within the approximation the TND table
+        // makes by weighting triangle, noise and DMC as `3:2:1`
+        for outputs in [
+            [15.0, 15.0, 0.0, 0.0, 0.0, 0.0],
+            [8.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 15.0, 15.0, 127.0, 0.0],
+            [0.0, 0.0, 0.0, 0.0, 64.0, 0.0],
+        ] {
+            let (table, accurate) = (table.mix(outputs), accurate.mix(outputs));
+            assert!(
+                (table - accurate).abs() < 0.05 * table,
+                "{outputs:?}: {table} != {accurate}"
+            );
+        }
+
+        // A high DMC level attenuates the pulse channels
+        let pulse = |mixer: &Mixer, dmc| {
+            mixer.mix([15.0, 15.0, 0.0, 0.0, dmc, 0.0]) - mixer.mix([0.0, 0.0, 0.0, 0.0, dmc, 0.0])
+        };
+        assert!((pulse(&table, 0.0) - pulse(&table, 127.0)).abs() < f32::EPSILON);
+        assert!(pulse(&accurate, 127.0) < 0.9 * pulse(&accurate, 0.0));
+    }
+}
@@ -77,16 +77,17 @@ impl Noise {
         self.force_silent = silent;
     }
 
-    const fn period(region: NesRegion, val: u8) -> usize {
-        let index = (val & 0x0F) as usize;
+    const fn period_table(region: NesRegion) -> &'static [usize; 16] {
         match region {
-            NesRegion::Auto | NesRegion::Ntsc | NesRegion::Dendy => {
-                Self::PERIOD_TABLE_NTSC[index] - 1
-            }
-            NesRegion::Pal => Self::PERIOD_TABLE_PAL[index] - 1,
+            NesRegion::Auto | NesRegion::Ntsc | NesRegion::Dendy => &Self::PERIOD_TABLE_NTSC,
+            NesRegion::Pal => &Self::PERIOD_TABLE_PAL,
         }
     }
 
+    const fn period(region: NesRegion, val: u8) -> usize {
+        Self::period_table(region)[(val & 0x0F) as usize] - 1
+    }
+
     pub fn clock_quarter_frame(&mut self) {
         self.envelope.clock();
     }
@@ -176,7 +177,13 @@ impl Regional for Noise {
     }
 
     fn set_region(&mut self, region: NesRegion) {
+        // Keep the current rate when switching regions at runtime
+        let index = Self::period_table(self.region)
+            .iter()
+            .position(|&period| period - 1 == self.timer.period)
+            .unwrap_or(0);
         self.region = region;
+        self.timer.period = Self::period(region, index as u8);
     }
 }
 
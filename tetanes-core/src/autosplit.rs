@@ -0,0 +1,236 @@
+//! Autosplitters that drive a speedrun timer from conditions on a game's memory, e.g. starting the
+//! timer when a new game begins and splitting when a boss defeated flag is set.
+//!
+//! An [`Autosplitter`] describes the conditions for a single game and an [`AutosplitRun`] checks
+//! them at the end of each frame, returning a [`SplitEvent`] when the timer should be started,
+//! split or reset.
+
+use crate::{
+    control_deck::{ControlDeck, LoadedRom},
+    playlist::RamCondition,
+};
+use alloc::{string::String, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+/// Conditions that control a speedrun timer for a game.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+#[serde(default)]
+pub struct Autosplitter {
+    pub name: String,
+    /// CRC32 of the ROM's PRG-ROM and CHR-ROM this applies to, which takes priority over
+    /// `rom_name`.
+    pub crc32: Option<u32>,
+    /// Name of the ROM this applies to, ignoring case.
+    pub rom_name: Option<String>,
+    /// Conditions that all must be met to start the timer.
+    pub start: Vec<RamCondition>,
+    /// Conditions that all must be met to reset the timer. Resets are disabled if empty.
+    pub reset: Vec<RamCondition>,
+    /// Splits, in order.
+    pub splits: Vec<Split>,
+}
+
+/// A split in an [`Autosplitter`].
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+#[serde(default)]
+pub struct Split {
+    pub name: String,
+    /// Conditions that all must be met to split.
+    pub conditions: Vec<RamCondition>,
+}
+
+impl Autosplitter {
+    /// Whether this autosplitter applies to `rom`.
+    #[must_use]
+    pub fn matches(&self, rom: &LoadedRom) -> bool {
+        match (self.crc32, &self.rom_name) {
+            (Some(crc32), _) => crc32 == rom.crc32,
+            (None, Some(name)) => name.eq_ignore_ascii_case(&rom.name),
+            (None, None) => false,
+        }
+    }
+}
+
+/// Timer command returned by [`AutosplitRun::update`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub enum SplitEvent {
+    Start,
+    /// Index of the split that was completed.
+    Split(usize),
+    Reset,
+}
+
+/// Edge-triggered set of conditions, so that a condition that stays met only fires once.
+#[derive(Default, Debug, Clone)]
+struct Trigger {
+    met: bool,
+}
+
+impl Trigger {
+    /// Whether `conditions` became met since the last check.
+    fn update(&mut self, conditions: &[RamCondition], deck: &ControlDeck) -> bool {
+        let met = !conditions.is_empty() && conditions.iter().all(|c| c.is_met(deck));
+        let triggered = met && !self.met;
+        self.met = met;
+        triggered
+    }
+}
+
+/// Progress of a run timed by an [`Autosplitter`].
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct AutosplitRun {
+    autosplitter: Autosplitter,
+    running: bool,
+    next_split: usize,
+    start: Trigger,
+    reset: Trigger,
+    split: Trigger,
+}
+
+impl AutosplitRun {
+    pub fn new(autosplitter: Autosplitter) -> Self {
+        Self {
+            autosplitter,
+            running: false,
+            next_split: 0,
+            start: Trigger::default(),
+            reset: Trigger::default(),
+            split: Trigger::default(),
+        }
+    }
+
+    pub const fn autosplitter(&self) -> &Autosplitter {
+        &self.autosplitter
+    }
+
+    /// Whether the timer has been started and not reset.
+    #[must_use]
+    pub const fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Index of the next split, equal to the number of splits once the run is finished.
+    #[must_use]
+    pub const fn next_split(&self) -> usize {
+        self.next_split
+    }
+
+    /// Check conditions against the current state of `deck`, returning the timer command to send,
+    /// if any. Should be called once per frame.
+    pub fn update(&mut self, deck: &ControlDeck) -> Option<SplitEvent> {
+        let Self {
+            autosplitter,
+            running,
+            next_split,
+            start,
+            reset,
+            split,
+        } = self;
+
+        // Both are checked every frame so they only trigger on conditions that became met
+        let reset_triggered = reset.update(&autosplitter.reset, deck);
+        let start_triggered = start.update(&autosplitter.start, deck);
+        if reset_triggered && *running {
+            *running = false;
+            *next_split = 0;
+            return Some(SplitEvent::Reset);
+        }
+        if !*running {
+            if start_triggered {
+                *running = true;
+                *next_split = 0;
+                *split = Trigger::default();
+                return Some(SplitEvent::Start);
+            }
+            return None;
+        }
+        let conditions = &autosplitter.splits.get(*next_split)?.conditions;
+        if split.update(conditions, deck) {
+            let index = *next_split;
+            *next_split += 1;
+            // Require the next split's conditions to become met after this one
+            split.met = true;
+            return Some(SplitEvent::Split(index));
+        }
+        None
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{
+        mem::{Access, Mem},
+        playlist::Comparison,
+    };
+    use alloc::vec;
+
+    const fn equal(addr: u16, value: u8) -> RamCondition {
+        RamCondition {
+            addr,
+            comparison: Comparison::Equal,
+            value,
+        }
+    }
+
+    #[test]
+    fn autosplit_run() {
+        fn write(deck: &mut ControlDeck, addr: u16, val: u8) {
+            deck.bus_mut().write(addr, val, Access::Dummy);
+        }
+
+        let mut deck = ControlDeck::new();
+        for addr in 0x00..=0x03 {
+            write(&mut deck, addr, 0x00);
+        }
+        let mut run = AutosplitRun::new(Autosplitter {
+            name: "Any%".into(),
+            start: vec![equal(0x00, 0x01)],
+            reset: vec![equal(0x00, 0xFF)],
+            splits: vec![
+                Split {
+                    name: "Level 1".into(),
+                    conditions: vec![equal(0x01, 0x01), equal(0x02, 0x01)],
+                },
+                Split {
+                    name: "Level 2".into(),
+                    conditions: vec![equal(0x02, 0x01)],
+                },
+            ],
+            ..Default::default()
+        });
+
+        assert_eq!(run.update(&deck), None);
+        write(&mut deck, 0x00, 0x01);
+        assert_eq!(run.update(&deck), Some(SplitEvent::Start));
+        assert_eq!(run.update(&deck), None, "start only triggers once");
+        assert!(run.is_running());
+
+        write(&mut deck, 0x01, 0x01);
+        assert_eq!(run.update(&deck), None, "all conditions must be met");
+        write(&mut deck, 0x02, 0x01);
+        assert_eq!(run.update(&deck), Some(SplitEvent::Split(0)));
+        assert_eq!(
+            run.update(&deck),
+            None,
+            "next split must become met after the previous one"
+        );
+        write(&mut deck, 0x02, 0x00);
+        assert_eq!(run.update(&deck), None);
+        write(&mut deck, 0x02, 0x01);
+        assert_eq!(run.update(&deck), Some(SplitEvent::Split(1)));
+        assert_eq!(run.next_split(), 2);
+        assert_eq!(run.update(&deck), None);
+
+        write(&mut deck, 0x00, 0xFF);
+        assert_eq!(run.update(&deck), Some(SplitEvent::Reset));
+        assert!(!run.is_running());
+        write(&mut deck, 0x00, 0x01);
+        assert_eq!(run.update(&deck), Some(SplitEvent::Start));
+        assert_eq!(run.next_split(), 0);
+    }
+}
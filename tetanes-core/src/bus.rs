@@ -5,17 +5,16 @@
 use crate::{
     apu::{Apu, ApuRegisters, Channel},
     cart::Cart,
-    common::{Clock, ClockTo, NesRegion, Regional, Reset, ResetKind, Sample, Sram},
-    cpu::Cpu,
-    fs,
+    cdl::{Cdl, CdlFlags},
+    common::{Clock, ClockTo, NesRegion, Regional, Reset, ResetKind, Sample},
+    cpu::Irq,
     genie::GenieCode,
     input::{Input, InputRegisters, Player},
-    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MemMap},
+    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MapperWrite, MemMap},
     mem::{Access, Mem, RamState},
-    ppu::{Ppu, Registers},
-    Path,
+    ppu::{model::PpuModel, Ppu, Registers},
 };
-use alloc::{vec, vec::Vec};
+use alloc::{collections::VecDeque, vec, vec::Vec};
 use hashbrown::HashMap;
 use serde::{Deserialize, Serialize};
 
@@ -52,14 +51,26 @@ use serde::{Deserialize, Serialize};
 #[must_use]
 pub struct Bus {
     pub apu: Apu,
+    /// Code/Data Log of PRG-ROM accesses. `None` unless enabled.
+    #[serde(skip)]
+    pub cdl: Option<Cdl>,
     pub genie_codes: HashMap<u16, GenieCode>,
     pub input: Input,
+    /// Recent writes to the mapper address space for debugging. `None` unless enabled with
+    /// [`Bus::set_log_mapper_writes`].
+    #[serde(skip)]
+    pub mapper_writes: Option<VecDeque<MapperWrite>>,
+    /// Last value driven on the CPU data bus, returned by reads from unmapped addresses. Unlike the
+    /// PPU I/O latch, it's driven every CPU cycle so decay isn't modeled.
     pub open_bus: u8,
     pub ppu: Ppu,
     pub prg_ram_protect: bool,
     pub prg_ram: Vec<u8>,
     #[serde(skip)]
     pub prg_rom: Vec<u8>,
+    /// Values frozen at RAM addresses, overriding reads. Not serialized with save states.
+    #[serde(skip)]
+    pub ram_cheats: HashMap<u16, u8>,
     pub ram_state: RamState,
     pub region: NesRegion,
     pub wram: Vec<u8>,
@@ -73,17 +84,21 @@ impl Default for Bus {
 
 impl Bus {
     const WRAM_SIZE: usize = 0x0800; // 2K NES Work Ram available to the CPU
+    const MAX_MAPPER_WRITES: usize = 1024;
 
     pub fn new(region: NesRegion, ram_state: RamState) -> Self {
         Self {
             apu: Apu::new(region),
+            cdl: None,
             genie_codes: HashMap::new(),
             input: Input::new(region),
+            mapper_writes: None,
             open_bus: 0x00,
             ppu: Ppu::new(region),
             prg_ram: vec![],
             prg_ram_protect: false,
             prg_rom: vec![],
+            ram_cheats: HashMap::new(),
             ram_state,
             region,
             wram: RamState::filled(Self::WRAM_SIZE, ram_state),
@@ -91,6 +106,8 @@ impl Bus {
     }
 
     pub fn load_cart(&mut self, cart: Cart) {
+        self.input.vs.enabled = cart.is_vs_system();
+        self.ppu.set_model(cart.ppu_model());
         self.prg_rom = cart.prg_rom;
         self.load_sram(cart.prg_ram);
         self.ppu.bus.load_chr_rom(cart.chr_rom);
@@ -100,6 +117,8 @@ impl Bus {
     }
 
     pub fn unload_cart(&mut self) {
+        self.input.vs.enabled = false;
+        self.ppu.set_model(PpuModel::default());
         self.ppu.load_mapper(Mapper::default());
     }
 
@@ -117,6 +136,70 @@ impl Bus {
         &self.wram
     }
 
+    /// Write `val` to the RAM or ROM mapped at `addr` without side effects, e.g. mapper, PPU or
+    /// APU register writes. PRG-RAM write protection is ignored. Returns `false` if `addr` isn't
+    /// mapped to memory.
+    pub fn poke(&mut self, addr: u16, val: u8) -> bool {
+        let mem = match addr {
+            0x0000..=0x1FFF => self.wram.get_mut(usize::from(addr & 0x07FF)),
+            0x4020..=0xFFFF => match self.ppu.bus.mapper.map_peek(addr) {
+                MappedRead::PrgRam(addr) => self.prg_ram.get_mut(addr),
+                MappedRead::PrgRom(addr) => self.prg_rom.get_mut(addr),
+                _ => None,
+            },
+            _ => None,
+        };
+        mem.map(|mem| *mem = val).is_some()
+    }
+
+    /// Enable or disable logging writes to the mapper address space into `mapper_writes`.
+    pub fn set_log_mapper_writes(&mut self, enabled: bool) {
+        if enabled {
+            self.mapper_writes
+                .get_or_insert_with(|| VecDeque::with_capacity(Self::MAX_MAPPER_WRITES));
+        } else {
+            self.mapper_writes = None;
+        }
+    }
+
+    /// Mark the PRG-ROM byte mapped at `addr` with `flags` in the Code/Data Log, if enabled.
+    #[inline]
+    pub fn log_cdl(&mut self, addr: u16, flags: CdlFlags) {
+        if let Some(cdl) = &mut self.cdl {
+            if let (0x4020..=0xFFFF, MappedRead::PrgRom(offset)) =
+                (addr, self.ppu.bus.mapper.map_peek(addr))
+            {
+                cdl.mark(offset, addr, flags);
+            }
+        }
+    }
+
+    /// Returns the Code/Data Log flags of the PRG-ROM byte mapped at `addr`, if enabled and
+    /// `addr` is mapped to PRG-ROM.
+    #[must_use]
+    pub fn cdl_flags(&self, addr: u16) -> Option<CdlFlags> {
+        let cdl = self.cdl.as_ref()?;
+        match (addr, self.ppu.bus.mapper.map_peek(addr)) {
+            (0x4020..=0xFFFF, MappedRead::PrgRom(offset)) => Some(cdl.flags(offset)),
+            _ => None,
+        }
+    }
+
+    fn log_mapper_write(&mut self, addr: u16, val: u8) {
+        if let Some(mapper_writes) = &mut self.mapper_writes {
+            if mapper_writes.len() == Self::MAX_MAPPER_WRITES {
+                mapper_writes.pop_front();
+            }
+            mapper_writes.push_back(MapperWrite {
+                frame: self.ppu.frame_number(),
+                scanline: self.ppu.scanline,
+                cycle: self.ppu.cycle,
+                addr,
+                val,
+            });
+        }
+    }
+
     /// Add a Game Genie code to override memory reads/writes.
     ///
     /// # Errors
@@ -143,6 +226,32 @@ impl Bus {
             .map_or(val, |genie_code| genie_code.read(val))
     }
 
+    /// Freeze the value read from Work RAM or PRG-RAM at `addr`. Writes still update memory.
+    pub fn add_ram_cheat(&mut self, addr: u16, val: u8) {
+        self.ram_cheats.insert(addr, val);
+    }
+
+    /// Remove the frozen value at `addr`.
+    pub fn remove_ram_cheat(&mut self, addr: u16) {
+        self.ram_cheats.remove(&addr);
+    }
+
+    #[inline]
+    fn ram_cheat_read(&self, addr: u16, val: u8) -> u8 {
+        if self.ram_cheats.is_empty() {
+            val
+        } else {
+            self.ram_cheats.get(&addr).copied().unwrap_or(val)
+        }
+    }
+
+    /// IRQs requested by the APU and mapper.
+    pub fn irqs(&self) -> Irq {
+        let mut irqs = self.apu.irqs();
+        irqs.set(Irq::MAPPER, self.ppu.bus.mapper.irq_pending());
+        irqs
+    }
+
     #[must_use]
     pub fn audio_samples(&self) -> &[f32] {
         &self.apu.audio_samples
@@ -160,6 +269,7 @@ impl Clock for Bus {
         let output = match self.ppu.bus.mapper {
             Mapper::Exrom(ref exrom) => exrom.output(),
             Mapper::Vrc6(ref vrc6) => vrc6.output(),
+            Mapper::Nsf(ref nsf) => nsf.output(),
             _ => 0.0,
         };
         self.apu.add_mapper_output(output);
@@ -178,11 +288,11 @@ impl ClockTo for Bus {
 impl Mem for Bus {
     fn read(&mut self, addr: u16, _access: Access) -> u8 {
         let val = match addr {
-            0x0000..=0x07FF => self.wram[addr as usize],
+            0x0000..=0x07FF => self.ram_cheat_read(addr, self.wram[addr as usize]),
             0x4020..=0xFFFF => {
                 let val = match self.ppu.bus.mapper.map_read(addr) {
                     MappedRead::Data(val) => val,
-                    MappedRead::PrgRam(addr) => self.prg_ram[addr],
+                    MappedRead::PrgRam(mapped) => self.ram_cheat_read(addr, self.prg_ram[mapped]),
                     MappedRead::PrgRom(addr) => self.prg_rom[addr],
                     _ => self.open_bus,
                 };
@@ -191,10 +301,22 @@ impl Mem for Bus {
             0x2002 => self.ppu.read_status(),
             0x2004 => self.ppu.read_oamdata(),
             0x2007 => self.ppu.read_data(),
-            0x4015 => self.apu.read_status(),
-            0x4016 => self.input.read(Player::One, &self.ppu),
-            0x4017 => self.input.read(Player::Two, &self.ppu),
-            0x2000 | 0x2001 | 0x2003 | 0x2005 | 0x2006 => self.ppu.open_bus,
+            0x4015 => {
+                // Bit 5 is open bus and the value read is internal to the CPU, so it doesn't
+                // drive the external data bus
+                let val = (self.apu.read_status() & 0xDF) | (self.open_bus & 0x20);
+                self.ppu.bus.mapper.cpu_bus_read(addr);
+                return val;
+            }
+            0x4016 => {
+                let mask = self.input.driven_bits(Player::One);
+                (self.input.read(Player::One, &self.ppu) & mask) | (self.open_bus & !mask)
+            }
+            0x4017 => {
+                let mask = self.input.driven_bits(Player::Two);
+                (self.input.read(Player::Two, &self.ppu) & mask) | (self.open_bus & !mask)
+            }
+            0x2000 | 0x2001 | 0x2003 | 0x2005 | 0x2006 => self.ppu.open_bus.value(),
             0x0800..=0x1FFF => self.read(addr & 0x07FF, _access), // WRAM Mirrors
             0x2008..=0x3FFF => self.read(addr & 0x2007, _access), // Ppu Mirrors
             _ => self.open_bus,
@@ -206,11 +328,11 @@ impl Mem for Bus {
 
     fn peek(&self, addr: u16, _access: Access) -> u8 {
         match addr {
-            0x0000..=0x07FF => self.wram[addr as usize],
+            0x0000..=0x07FF => self.ram_cheat_read(addr, self.wram[addr as usize]),
             0x4020..=0xFFFF => {
                 let val = match self.ppu.bus.mapper.map_peek(addr) {
                     MappedRead::Data(val) => val,
-                    MappedRead::PrgRam(addr) => self.prg_ram[addr],
+                    MappedRead::PrgRam(mapped) => self.ram_cheat_read(addr, self.prg_ram[mapped]),
                     MappedRead::PrgRom(addr) => self.prg_rom[addr],
                     _ => self.open_bus,
                 };
@@ -219,10 +341,16 @@ impl Mem for Bus {
             0x2002 => self.ppu.peek_status(),
             0x2004 => self.ppu.peek_oamdata(),
             0x2007 => self.ppu.peek_data(),
-            0x4015 => self.apu.peek_status(),
-            0x4016 => self.input.peek(Player::One, &self.ppu),
-            0x4017 => self.input.peek(Player::Two, &self.ppu),
-            0x2000 | 0x2001 | 0x2003 | 0x2005 | 0x2006 => self.ppu.open_bus,
+            0x4015 => (self.apu.peek_status() & 0xDF) | (self.open_bus & 0x20),
+            0x4016 => {
+                let mask = self.input.driven_bits(Player::One);
+                (self.input.peek(Player::One, &self.ppu) & mask) | (self.open_bus & !mask)
+            }
+            0x4017 => {
+                let mask = self.input.driven_bits(Player::Two);
+                (self.input.peek(Player::Two, &self.ppu) & mask) | (self.open_bus & !mask)
+            }
+            0x2000 | 0x2001 | 0x2003 | 0x2005 | 0x2006 => self.ppu.open_bus.value(),
             0x0800..=0x1FFF => self.peek(addr & 0x07FF, _access), // WRAM Mirrors
             0x2008..=0x3FFF => self.peek(addr & 0x2007, _access), // Ppu Mirrors
             _ => self.open_bus,
@@ -239,13 +367,32 @@ impl Mem for Bus {
                             self.prg_ram[addr] = val;
                         }
                     }
-                    MappedWrite::PrgRamProtect(protect) => self.prg_ram_protect = protect,
-                    _ => (),
+                    MappedWrite::PrgRamProtect(protect) => {
+                        self.prg_ram_protect = protect;
+                        self.log_mapper_write(addr, val);
+                    }
+                    MappedWrite::PrgRom(mapped, val) => {
+                        if let Some(byte) = self.prg_rom.get_mut(mapped) {
+                            *byte &= val;
+                        }
+                        self.log_mapper_write(addr, val);
+                    }
+                    MappedWrite::PrgRomErase(start, len) => {
+                        let end = start.saturating_add(len).min(self.prg_rom.len());
+                        if let Some(rom) = self.prg_rom.get_mut(start..end) {
+                            rom.fill(0xFF);
+                        }
+                        self.log_mapper_write(addr, val);
+                    }
+                    _ => self.log_mapper_write(addr, val),
                 }
                 self.ppu.bus.update_mirroring();
             }
-            0x2000 => self.ppu.write_ctrl(val),
-            0x2001 => self.ppu.write_mask(val),
+            // RC2C05 PPUs swap PPUCTRL and PPUMASK
+            0x2000 | 0x2001 if (addr == 0x2000) == self.ppu.model.swaps_ctrl_mask() => {
+                self.ppu.write_mask(val);
+            }
+            0x2000 | 0x2001 => self.ppu.write_ctrl(val),
             0x2003 => self.ppu.write_oamaddr(val),
             0x2004 => self.ppu.write_oamdata(val),
             0x2005 => self.ppu.write_scroll(val),
@@ -269,11 +416,11 @@ impl Mem for Bus {
             0x4011 => self.apu.write_dmc_output(val),
             0x4012 => self.apu.write_dmc_addr(val),
             0x4013 => self.apu.write_length(Channel::Dmc, val),
-            0x4014 => Cpu::start_oam_dma(u16::from(val) << 8),
+            0x4014 => self.apu.dma.start_oam(u16::from(val) << 8),
             0x4015 => self.apu.write_status(val),
             0x4016 => self.input.write(val),
             0x4017 => self.apu.write_frame_counter(val),
-            0x2002 => self.ppu.open_bus = val,
+            0x2002 => self.ppu.write_open_bus(val),
             0x0800..=0x1FFF => return self.write(addr & 0x07FF, val, _access), // WRAM Mirrors
             0x2008..=0x3FFF => return self.write(addr & 0x2007, val, _access), // Ppu Mirrors
             _ => (),
@@ -306,18 +453,6 @@ impl Reset for Bus {
     }
 }
 
-impl Sram for Bus {
-    fn save(&self, dir: impl AsRef<Path>) -> fs::Result<()> {
-        fs::save(dir.as_ref().with_extension(".sram"), self.sram())?;
-        self.ppu.bus.mapper.save(dir)
-    }
-
-    fn load(&mut self, dir: impl AsRef<Path>) -> fs::Result<()> {
-        fs::load(dir.as_ref().with_extension(".sram")).map(|data| self.load_sram(data))?;
-        self.ppu.bus.mapper.load(dir)
-    }
-}
-
 impl core::fmt::Debug for Bus {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("Bus")
@@ -343,6 +478,14 @@ mod test {
     use super::*;
     use crate::mapper::Cnrom;
 
+    /// Read $2007 PPUDATA, clocking past the delay after the previous read.
+    fn read_data(bus: &mut Bus) -> u8 {
+        for _ in 0..6 {
+            bus.ppu.clock();
+        }
+        bus.read(0x2007, Access::Read)
+    }
+
     #[test]
     fn load_cart_values() {
         let mut bus = Bus::default();
@@ -381,12 +524,12 @@ mod test {
 
         bus.write(0x2006, 0x00, Access::Write);
         bus.write(0x2006, 0x00, Access::Write);
-        bus.read(0x2007, Access::Read);
-        assert_eq!(bus.read(0x2007, Access::Read), 0x66, "chr_rom start");
+        read_data(&mut bus);
+        assert_eq!(read_data(&mut bus), 0x66, "chr_rom start");
         bus.write(0x2006, 0x1F, Access::Write);
         bus.write(0x2006, 0xFF, Access::Write);
-        bus.read(0x2007, Access::Read);
-        assert_eq!(bus.read(0x2007, Access::Read), 0x66, "chr_rom end");
+        read_data(&mut bus);
+        assert_eq!(read_data(&mut bus), 0x66, "chr_rom end");
 
         // Writes disallowed
         bus.write(0x2006, 0x00, Access::Write);
@@ -395,8 +538,8 @@ mod test {
 
         bus.write(0x2006, 0x00, Access::Write);
         bus.write(0x2006, 0x10, Access::Write);
-        bus.read(0x2007, Access::Read);
-        assert_eq!(bus.read(0x2007, Access::Read), 0x66, "chr_rom read-only");
+        read_data(&mut bus);
+        assert_eq!(read_data(&mut bus), 0x66, "chr_rom read-only");
     }
 
     #[test]
@@ -408,12 +551,12 @@ mod test {
 
         bus.write(0x2006, 0x00, Access::Write);
         bus.write(0x2006, 0x00, Access::Write);
-        bus.read(0x2007, Access::Read);
-        assert_eq!(bus.read(0x2007, Access::Read), 0x66, "chr_ram start");
+        read_data(&mut bus);
+        assert_eq!(read_data(&mut bus), 0x66, "chr_ram start");
         bus.write(0x2006, 0x1F, Access::Write);
         bus.write(0x2006, 0xFF, Access::Write);
-        bus.read(0x2007, Access::Read);
-        assert_eq!(bus.read(0x2007, Access::Read), 0x66, "chr_ram end");
+        read_data(&mut bus);
+        assert_eq!(read_data(&mut bus), 0x66, "chr_ram end");
 
         // Writes allowed
         bus.write(0x2006, 0x10, Access::Write);
@@ -428,8 +571,8 @@ mod test {
         // PPU writes to $2006 are delayed by 2 PPU clocks
         bus.ppu.clock();
         bus.ppu.clock();
-        bus.read(0x2007, Access::Read);
-        assert_eq!(bus.read(0x2007, Access::Read), 0x77, "chr_ram write");
+        read_data(&mut bus);
+        assert_eq!(read_data(&mut bus), 0x77, "chr_ram write");
     }
 
     #[test]
@@ -0,0 +1,263 @@
+//! C API for embedding `tetanes-core` in C/C++ frontends or other languages with a C FFI.
+//!
+//! Enabled with the `capi` feature. The matching header is `include/tetanes.h`.
+//!
+//! All functions taking a `TetanesDeck` pointer require a valid pointer returned from
+//! [`tetanes_create`] that hasn't yet been passed to [`tetanes_destroy`]. Pointers returned by
+//! these functions are only valid until the next call that mutates the same deck.
+
+#![allow(unsafe_code)]
+
+use crate::{
+    common::{Reset, ResetKind},
+    control_deck::{ControlDeck, Error},
+    input::{JoypadBtnState, Player},
+};
+use alloc::{boxed::Box, ffi::CString, string::ToString};
+use core::ffi::{c_char, CStr};
+
+/// The operation succeeded.
+pub const TETANES_OK: i32 = 0;
+/// A required pointer argument was null or invalid.
+pub const TETANES_ERR_INVALID_ARGUMENT: i32 = -1;
+/// The ROM failed to load.
+pub const TETANES_ERR_LOAD_ROM: i32 = -2;
+/// No ROM is loaded.
+pub const TETANES_ERR_ROM_NOT_LOADED: i32 = -3;
+/// The CPU state is corrupted and emulation can't continue.
+pub const TETANES_ERR_CPU_CORRUPTED: i32 = -4;
+
+/// Opaque handle to a [`ControlDeck`].
+#[must_use]
+pub struct TetanesDeck {
+    deck: ControlDeck,
+    last_error: Option<CString>,
+}
+
+impl TetanesDeck {
+    fn set_error(&mut self, err: &Error) -> i32 {
+        let code = match err {
+            Error::RomNotLoaded => TETANES_ERR_ROM_NOT_LOADED,
+            Error::CpuCorrupted => TETANES_ERR_CPU_CORRUPTED,
+            _ => TETANES_ERR_LOAD_ROM,
+        };
+        self.last_error = CString::new(err.to_string()).ok();
+        code
+    }
+}
+
+/// Create a new deck with the default configuration. Must be freed with [`tetanes_destroy`].
+#[no_mangle]
+pub extern "C" fn tetanes_create() -> *mut TetanesDeck {
+    Box::into_raw(Box::new(TetanesDeck {
+        deck: ControlDeck::new(),
+        last_error: None,
+    }))
+}
+
+/// Free a deck created with [`tetanes_create`]. Passing null does nothing.
+///
+/// # Safety
+///
+/// `deck` must be null or a pointer returned by [`tetanes_create`] that hasn't already been
+/// destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn tetanes_destroy(deck: *mut TetanesDeck) {
+    if !deck.is_null() {
+        drop(Box::from_raw(deck));
+    }
+}
+
+/// Load an iNES or NES 2.0 ROM image of `len` bytes. `name` is a null-terminated UTF-8 string
+/// used to identify the ROM for battery-backed RAM, and may be null.
+///
+/// # Safety
+///
+/// `deck` must be a valid deck, `data` must point to at least `len` readable bytes and `name` must
+/// be null or a valid null-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn tetanes_load_rom(
+    deck: *mut TetanesDeck,
+    name: *const c_char,
+    data: *const u8,
+    len: usize,
+) -> i32 {
+    let Some(deck) = deck.as_mut() else {
+        return TETANES_ERR_INVALID_ARGUMENT;
+    };
+    if data.is_null() {
+        return TETANES_ERR_INVALID_ARGUMENT;
+    }
+    let name = if name.is_null() {
+        "rom".into()
+    } else {
+        CStr::from_ptr(name).to_string_lossy()
+    };
+    let mut rom = core::slice::from_raw_parts(data, len);
+    match deck.deck.load_rom(name, &mut rom) {
+        Ok(_) => TETANES_OK,
+        Err(err) => deck.set_error(&err),
+    }
+}
+
+/// Clock an entire frame.
+///
+/// # Safety
+///
+/// `deck` must be a valid deck.
+#[no_mangle]
+pub unsafe extern "C" fn tetanes_clock_frame(deck: *mut TetanesDeck) -> i32 {
+    let Some(deck) = deck.as_mut() else {
+        return TETANES_ERR_INVALID_ARGUMENT;
+    };
+    match deck.deck.clock_frame() {
+        Ok(_) => TETANES_OK,
+        Err(err) => deck.set_error(&err),
+    }
+}
+
+/// Returns the RGBA frame buffer of the last frame clocked, writing its length in bytes to `len`
+/// if not null. The buffer is `TETANES_FRAME_WIDTH` by `TETANES_FRAME_HEIGHT` pixels.
+///
+/// # Safety
+///
+/// `deck` must be a valid deck and `len` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tetanes_frame_buffer(
+    deck: *mut TetanesDeck,
+    len: *mut usize,
+) -> *const u8 {
+    let Some(deck) = deck.as_mut() else {
+        return core::ptr::null();
+    };
+    let frame = deck.deck.frame_buffer();
+    if let Some(len) = len.as_mut() {
+        *len = frame.len();
+    }
+    frame.as_ptr()
+}
+
+/// Returns the audio samples generated since they were last cleared, writing the number of
+/// samples to `len` if not null.
+///
+/// # Safety
+///
+/// `deck` must be a valid deck and `len` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tetanes_audio_samples(
+    deck: *const TetanesDeck,
+    len: *mut usize,
+) -> *const f32 {
+    let Some(deck) = deck.as_ref() else {
+        return core::ptr::null();
+    };
+    let samples = deck.deck.audio_samples();
+    if let Some(len) = len.as_mut() {
+        *len = samples.len();
+    }
+    samples.as_ptr()
+}
+
+/// Clear audio samples, which should be done after processing them each frame.
+///
+/// # Safety
+///
+/// `deck` must be a valid deck.
+#[no_mangle]
+pub unsafe extern "C" fn tetanes_clear_audio_samples(deck: *mut TetanesDeck) {
+    if let Some(deck) = deck.as_mut() {
+        deck.deck.clear_audio_samples();
+    }
+}
+
+/// Set the pressed `buttons` for `player` (`0`-`3`) as a bitmask of `TETANES_BUTTON_*` values.
+///
+/// # Safety
+///
+/// `deck` must be a valid deck.
+#[no_mangle]
+pub unsafe extern "C" fn tetanes_set_input(
+    deck: *mut TetanesDeck,
+    player: u32,
+    buttons: u16,
+) -> i32 {
+    let Some(deck) = deck.as_mut() else {
+        return TETANES_ERR_INVALID_ARGUMENT;
+    };
+    let player = match player {
+        0 => Player::One,
+        1 => Player::Two,
+        2 => Player::Three,
+        3 => Player::Four,
+        _ => return TETANES_ERR_INVALID_ARGUMENT,
+    };
+    deck.deck.joypad_mut(player).buttons = JoypadBtnState::from_bits_truncate(buttons);
+    TETANES_OK
+}
+
+/// Reset the console, emulating a power cycle if `hard` is true.
+///
+/// # Safety
+///
+/// `deck` must be a valid deck.
+#[no_mangle]
+pub unsafe extern "C" fn tetanes_reset(deck: *mut TetanesDeck, hard: bool) {
+    if let Some(deck) = deck.as_mut() {
+        deck.deck.reset(if hard {
+            ResetKind::Hard
+        } else {
+            ResetKind::Soft
+        });
+    }
+}
+
+/// Returns a null-terminated description of the last error, or null if there hasn't been one.
+///
+/// # Safety
+///
+/// `deck` must be a valid deck.
+#[no_mangle]
+pub unsafe extern "C" fn tetanes_last_error(deck: *const TetanesDeck) -> *const c_char {
+    deck.as_ref()
+        .and_then(|deck| deck.last_error.as_deref())
+        .map_or(core::ptr::null(), CStr::as_ptr)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capi_clock_frame() {
+        let rom = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_roms/spritecans.nes"
+        ))
+        .expect("valid rom");
+        unsafe {
+            let deck = tetanes_create();
+            assert_eq!(tetanes_clock_frame(deck), TETANES_ERR_ROM_NOT_LOADED);
+            assert!(!tetanes_last_error(deck).is_null());
+
+            let name = c"spritecans";
+            assert_eq!(
+                tetanes_load_rom(deck, name.as_ptr(), rom.as_ptr(), rom.len()),
+                TETANES_OK
+            );
+            assert_eq!(tetanes_set_input(deck, 0, 0x01), TETANES_OK);
+            assert_eq!(
+                tetanes_set_input(deck, 4, 0x01),
+                TETANES_ERR_INVALID_ARGUMENT
+            );
+            assert_eq!(tetanes_clock_frame(deck), TETANES_OK);
+
+            let mut len = 0;
+            assert!(!tetanes_frame_buffer(deck, &mut len).is_null());
+            assert_eq!(len, 256 * 240 * 4);
+            assert!(!tetanes_audio_samples(deck, &mut len).is_null());
+            assert!(len > 0);
+
+            tetanes_destroy(deck);
+        }
+    }
+}
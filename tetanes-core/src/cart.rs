@@ -1,17 +1,16 @@
 //! NES cartridge implementation.
 
 use crate::{
+    cart_db::{self, CartDbEntry},
     common::{NesRegion, Regional},
     fs,
+    input::VsSystem,
     io::{BufRead, Read},
-    mapper::{
-        m024_m026_vrc6::Revision as Vrc6Revision, m034_nina001::Nina001, Axrom, Bf909x, Bnrom,
-        Cnrom, ColorDreams, Exrom, Fxrom, Gxrom, Mapper, Mmc1Revision, Nrom, Pxrom, Sxrom, Txrom,
-        Uxrom, Vrc6,
-    },
+    mapper::{nsf::NsfHeader, registry, DipSwitches, Mapper, Nrom, Nsf},
     mem::RamState,
-    ppu::Mirroring,
-    BufReader, File, Path,
+    patch::CompatPatch,
+    ppu::{model::PpuModel, Mirroring},
+    rom_db, BufReader, File, Path,
 };
 use alloc::{
     format,
@@ -21,7 +20,7 @@ use alloc::{
 };
 use serde::{Deserialize, Serialize};
 use snafu::Snafu;
-use tracing::{debug, error, info};
+use tracing::{debug, info, warn};
 
 const PRG_ROM_BANK_SIZE: usize = 0x4000;
 const CHR_ROM_BANK_SIZE: usize = 0x2000;
@@ -37,11 +36,24 @@ pub enum Error {
         value: u8,
         message: String,
     },
+    #[snafu(display(
+        "{rom} size of {size} bytes exceeds the {max} bytes addressable by mapper {mapper_num}"
+    ))]
+    UnaddressableRom {
+        rom: &'static str,
+        size: usize,
+        max: usize,
+        mapper_num: u16,
+    },
     #[snafu(display("{context}: {inner:?}"))]
     Io {
         context: String,
         inner: crate::io::Error,
     },
+    #[snafu(display("invalid header fix on line {line}: {message}"))]
+    InvalidDatabase { line: usize, message: String },
+    #[snafu(display("invalid nsf file: {message}"))]
+    InvalidNsf { message: String },
 }
 
 impl Error {
@@ -60,13 +72,195 @@ pub struct GameRegion {
     pub region: NesRegion,
 }
 
+/// Where a [`Cart`]'s region was detected from.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+#[must_use]
+pub enum RegionSource {
+    /// The header timing field.
+    Header,
+    /// A game matching the ROM's CRC32 in the active [`RomDatabase`](crate::rom_db::RomDatabase).
+    RomDatabase,
+    /// Region tags in the ROM file name, e.g. `(E)`.
+    FileName,
+    /// No region was detected, so the default is used.
+    #[default]
+    Default,
+}
+
+impl RegionSource {
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Header => "Header",
+            Self::RomDatabase => "ROM Database",
+            Self::FileName => "File Name",
+            Self::Default => "Default",
+        }
+    }
+}
+
+impl core::fmt::Display for RegionSource {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Overrides for fields of a ROM's [`NesHeader`], e.g. to fix a bad dump.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+#[must_use]
+pub struct HeaderOverrides {
+    pub mapper_num: Option<u16>,
+    pub submapper_num: Option<u8>,
+    /// Hardwired nametable mirroring. Single-screen mirroring is selected by the mapper, so only
+    /// horizontal, vertical and four-screen mirroring can be overridden.
+    pub mirroring: Option<Mirroring>,
+    /// Number of 16KB PRG-ROM banks.
+    pub prg_rom_banks: Option<u16>,
+    /// Number of 8KB CHR-ROM banks.
+    pub chr_rom_banks: Option<u16>,
+    pub battery_backed: Option<bool>,
+}
+
+impl HeaderOverrides {
+    /// Whether no fields are overridden.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Apply the overridden fields to `header`.
+    pub fn apply(&self, header: &mut NesHeader) {
+        if let Some(mapper_num) = self.mapper_num {
+            header.mapper_num = mapper_num;
+        }
+        if let Some(submapper_num) = self.submapper_num {
+            header.submapper_num = submapper_num;
+        }
+        match self.mirroring {
+            Some(Mirroring::Horizontal) => header.flags &= !0x09,
+            Some(Mirroring::Vertical) => header.flags = (header.flags & !0x08) | 0x01,
+            Some(Mirroring::FourScreen) => header.flags |= 0x08,
+            Some(Mirroring::SingleScreenA | Mirroring::SingleScreenB) | None => (),
+        }
+        if let Some(prg_rom_banks) = self.prg_rom_banks {
+            header.prg_rom_banks = prg_rom_banks;
+        }
+        if let Some(chr_rom_banks) = self.chr_rom_banks {
+            header.chr_rom_banks = chr_rom_banks;
+        }
+        match self.battery_backed {
+            Some(true) => header.flags |= 0x02,
+            Some(false) => header.flags &= !0x02,
+            None => (),
+        }
+    }
+}
+
+/// A dump with a known-bad header and the overrides that fix it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct HeaderFix {
+    /// CRC32 of the ROM data following the 16-byte header.
+    pub crc32: u32,
+    /// Game title.
+    pub title: String,
+    pub overrides: HeaderOverrides,
+}
+
+impl HeaderFix {
+    /// Returns the header fix for ROM data matching `crc32` in the active
+    /// [`RomDatabase`](crate::rom_db::RomDatabase), if any.
+    pub fn lookup(crc32: u32) -> Option<Self> {
+        rom_db::active().lookup_header_fix(crc32).cloned()
+    }
+
+    /// Parse the header fix database. Each non-comment line has the format:
+    ///
+    /// `CRC32, "Title", KEY=VALUE [KEY=VALUE ...]`
+    ///
+    /// where `KEY` is one of `mapper`, `submapper`, `mirroring` (`horizontal`, `vertical` or
+    /// `four-screen`), `prg` (16KB banks), `chr` (8KB banks) or `battery` (`yes` or `no`).
+    ///
+    /// # Errors
+    ///
+    /// If any line is malformed, then an error is returned.
+    pub fn parse_database(database: &str) -> Result<Vec<Self>> {
+        database
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|(line, entry)| {
+                Self::parse_entry(entry).map_err(|message| Error::InvalidDatabase {
+                    line: line + 1,
+                    message,
+                })
+            })
+            .collect()
+    }
+
+    fn parse_entry(entry: &str) -> core::result::Result<Self, String> {
+        let (crc32, rest) = entry.split_once(',').ok_or("missing crc32")?;
+        let crc32 = u32::from_str_radix(crc32.trim(), 16).map_err(|err| err.to_string())?;
+        let rest = rest
+            .trim_start()
+            .strip_prefix('"')
+            .ok_or("expected quoted title")?;
+        let (title, rest) = rest.split_once('"').ok_or("unterminated title")?;
+        let rest = rest.trim_start().strip_prefix(',').ok_or("missing fixes")?;
+
+        let mut overrides = HeaderOverrides::default();
+        for fix in rest.split_whitespace() {
+            let (key, value) = fix.split_once('=').ok_or("expected KEY=VALUE")?;
+            let number = || value.parse::<u16>().map_err(|err| format!("{key}: {err}"));
+            match key {
+                "mapper" => overrides.mapper_num = Some(number()?),
+                "submapper" => {
+                    overrides.submapper_num =
+                        Some(value.parse().map_err(|err| format!("{key}: {err}"))?);
+                }
+                "mirroring" => {
+                    overrides.mirroring = Some(match value {
+                        "horizontal" => Mirroring::Horizontal,
+                        "vertical" => Mirroring::Vertical,
+                        "four-screen" => Mirroring::FourScreen,
+                        _ => return Err(format!("invalid mirroring `{value}`")),
+                    });
+                }
+                "prg" => overrides.prg_rom_banks = Some(number()?),
+                "chr" => overrides.chr_rom_banks = Some(number()?),
+                "battery" => {
+                    overrides.battery_backed = Some(match value {
+                        "yes" => true,
+                        "no" => false,
+                        _ => return Err(format!("invalid battery `{value}`")),
+                    });
+                }
+                _ => return Err(format!("unknown key `{key}`")),
+            }
+        }
+        if overrides.is_empty() {
+            return Err("missing fixes".to_string());
+        }
+        Ok(Self {
+            crc32,
+            title: title.to_string(),
+            overrides,
+        })
+    }
+}
+
 /// An NES cartridge.
 #[derive(Default)]
 #[must_use]
 pub struct Cart {
     name: String,
     header: NesHeader,
+    original_header: NesHeader,
+    header_repaired: bool,
+    db_entry: Option<CartDbEntry>,
     region: NesRegion,
+    region_source: RegionSource,
     ram_state: RamState,
     pub(crate) mapper: Mapper,
     pub(crate) chr_rom: Vec<u8>, // Character ROM
@@ -81,7 +275,11 @@ impl Cart {
         let mut empty = Self {
             name: "Empty Cart".to_string(),
             header: NesHeader::default(),
+            original_header: NesHeader::default(),
+            header_repaired: false,
+            db_entry: None,
             region: NesRegion::Ntsc,
+            region_source: RegionSource::Default,
             ram_state: RamState::default(),
             mapper: Mapper::none(),
             chr_rom: vec![0x00; CHR_ROM_BANK_SIZE],
@@ -115,13 +313,67 @@ impl Cart {
     ///
     /// If the NES header is invalid, or the ROM data does not match the header, then an error is
     /// returned.
-    pub fn from_rom<S, F>(name: S, mut rom_data: &mut F, ram_state: RamState) -> Result<Self>
+    pub fn from_rom<S, F>(name: S, rom_data: &mut F, ram_state: RamState) -> Result<Self>
+    where
+        S: ToString,
+        F: Read,
+    {
+        Self::load_with_overrides(name, rom_data, ram_state, &HeaderOverrides::default())
+    }
+
+    /// Load `Cart` from ROM data, repairing the header if it's a known-bad dump in the active
+    /// [`RomDatabase`](crate::rom_db::RomDatabase) or its PRG-ROM size doesn't fit the data,
+    /// filling in `iNES` headers from the installed [`CartDb`](crate::cart_db::CartDb), then
+    /// applying `overrides`. `NSF` and `NSFe` music files are loaded into the built-in [`Nsf`]
+    /// player, ignoring `overrides`.
+    ///
+    /// # Errors
+    ///
+    /// If the NES header is invalid, or the ROM data does not match the header, then an error is
+    /// returned.
+    pub fn load_with_overrides<S, F>(
+        name: S,
+        rom_data: &mut F,
+        ram_state: RamState,
+        overrides: &HeaderOverrides,
+    ) -> Result<Self>
     where
         S: ToString,
         F: Read,
     {
         let name = name.to_string();
-        let header = NesHeader::load(&mut rom_data)?;
+        let mut rom = vec![];
+        rom_data
+            .read_to_end(&mut rom)
+            .map_err(|err| Error::io(err, "failed to read rom data"))?;
+        if NsfHeader::is_nsf(&rom) {
+            return Self::from_nsf(name, &rom, ram_state);
+        }
+        let rom_data = &mut rom.as_slice();
+        let original_header = NesHeader::load(rom_data)?;
+        let data = rom_data.to_vec();
+
+        let mut header = original_header;
+        let crc32 = fs::compute_crc32(&data);
+        if let Some(fix) = HeaderFix::lookup(crc32) {
+            info!("repairing header of known bad dump: {}", fix.title);
+            fix.overrides.apply(&mut header);
+        }
+        header.repair_prg_rom_size(data.len());
+        let header_repaired = header != original_header;
+        let db_entry = cart_db::lookup(crc32, &data);
+        if let Some(entry) = &db_entry {
+            // NES 2.0 headers already describe the board, and may be more recent than the database
+            if header.variant != NesVariant::Nes2 {
+                info!("applying cart database entry: {entry:?}");
+                entry.apply(&mut header);
+            }
+        }
+        if !overrides.is_empty() {
+            info!("applying header overrides: {overrides:?}");
+            overrides.apply(&mut header);
+        }
+        let rom_data = &mut data.as_slice();
 
         let prg_rom_len = (header.prg_rom_banks as usize) * PRG_ROM_BANK_SIZE;
         let mut prg_rom = vec![0x00; prg_rom_len];
@@ -140,8 +392,7 @@ impl Cart {
             }
         })?;
 
-        let prg_ram_size = Self::calculate_ram_size(header.prg_ram_shift)?;
-        let prg_ram = RamState::filled(prg_ram_size, ram_state);
+        let prg_ram = RamState::filled(header.prg_ram_size() + header.prg_nvram_size(), ram_state);
 
         let mut chr_rom = vec![0x00; (header.chr_rom_banks as usize) * CHR_ROM_BANK_SIZE];
         let mut chr_ram = vec![];
@@ -152,8 +403,9 @@ impl Cart {
                         byte: 5,
                         value: header.chr_rom_banks as u8,
                         message: format!(
-                            "expected `{}` chr-rom banks ({prg_rom_len} total bytes)",
-                            header.chr_rom_banks
+                            "expected `{}` chr-rom banks ({} total bytes)",
+                            header.chr_rom_banks,
+                            chr_rom.len()
                         ),
                     }
                 } else {
@@ -161,27 +413,69 @@ impl Cart {
                 }
             })?;
         } else {
-            let chr_ram_size = Self::calculate_ram_size(header.chr_ram_shift)?;
+            let chr_ram_size = header.chr_ram_size() + header.chr_nvram_size();
             if chr_ram_size > 0 {
                 chr_ram.resize(chr_ram_size, 0x00);
                 RamState::fill(&mut chr_ram, ram_state);
             }
         }
 
-        let region = if matches!(header.variant, NesVariant::INes | NesVariant::Nes2) {
-            match header.tv_mode {
-                1 => NesRegion::Pal,
-                3 => NesRegion::Dendy,
-                _ => Self::lookup_region(&prg_rom, &chr_rom),
+        let board = registry::lookup(header.mapper_num, header.submapper_num);
+        if header.chr_rom_banks > 0 && !matches!(header.variant, NesVariant::Nes2) {
+            // Oversize hacks with an iNES header sometimes leave CHR-ROM banks past the declared
+            // size, either because they don't fit in the header or it wasn't updated.
+            let mut extra = vec![];
+            rom_data
+                .read_to_end(&mut extra)
+                .map_err(|err| Error::io(err, "failed to read trailing rom data"))?;
+            let chr_rom_len = chr_rom.len() + extra.len();
+            if !extra.is_empty()
+                && extra.len() % CHR_ROM_BANK_SIZE == 0
+                && board.is_some_and(|board| board.addresses_chr_rom(chr_rom_len))
+            {
+                warn!(
+                    "detected {} chr-rom bytes past the {} declared in the header",
+                    extra.len(),
+                    chr_rom.len()
+                );
+                chr_rom.extend_from_slice(&extra);
             }
-        } else {
-            Self::lookup_region(&prg_rom, &chr_rom)
-        };
+        }
+        if let Some(board) = board {
+            let limits = [
+                ("prg-rom", prg_rom.len(), board.max_prg_rom_size),
+                ("chr-rom", chr_rom.len(), board.max_chr_rom_size),
+            ];
+            for (rom, size, max) in limits {
+                if let Some(max) = max.filter(|&max| size > max) {
+                    return Err(Error::UnaddressableRom {
+                        rom,
+                        size,
+                        max,
+                        mapper_num: header.mapper_num,
+                    });
+                }
+            }
+        }
+
+        let (region, region_source) = header
+            .region()
+            .map(|region| (region, RegionSource::Header))
+            .or_else(|| {
+                Self::lookup_region(&prg_rom, &chr_rom)
+                    .map(|region| (region, RegionSource::RomDatabase))
+            })
+            .or_else(|| Self::filename_region(&name).map(|region| (region, RegionSource::FileName)))
+            .unwrap_or_default();
 
         let mut cart = Self {
             name,
             header,
+            original_header,
+            header_repaired,
+            db_entry,
             region,
+            region_source,
             ram_state,
             mapper: Mapper::none(),
             chr_rom,
@@ -190,31 +484,15 @@ impl Cart {
             prg_ram,
             ex_ram: vec![],
         };
-        cart.mapper = match cart.header.mapper_num {
-            0 => Nrom::load(&mut cart),
-            1 => Sxrom::load(&mut cart, Mmc1Revision::BC),
-            2 => Uxrom::load(&mut cart),
-            3 => Cnrom::load(&mut cart),
-            4 => Txrom::load(&mut cart),
-            5 => Exrom::load(&mut cart),
-            7 => Axrom::load(&mut cart),
-            9 => Pxrom::load(&mut cart),
-            10 => Fxrom::load(&mut cart),
-            11 => ColorDreams::load(&mut cart),
-            24 => Vrc6::load(&mut cart, Vrc6Revision::A),
-            26 => Vrc6::load(&mut cart, Vrc6Revision::B),
-            34 => {
-                // ≥ 16K implies NINA-001; ≤ 8K implies BNROM
-                if cart.has_chr_rom() && cart.chr_rom.len() >= 0x4000 {
-                    Nina001::load(&mut cart)
-                } else {
-                    Bnrom::load(&mut cart)
+        cart.mapper = match cart.board() {
+            Some(board) => {
+                let mut mapper = (board.load)(&mut cart);
+                if let Some(rev) = board.revision {
+                    mapper.set_revision(rev);
                 }
+                mapper
             }
-            66 => Gxrom::load(&mut cart),
-            71 => Bf909x::load(&mut cart),
-            155 => Sxrom::load(&mut cart, Mmc1Revision::A),
-            _ => Mapper::none(),
+            None => Mapper::none(),
         };
 
         info!("loaded ROM `{cart}`");
@@ -222,11 +500,111 @@ impl Cart {
         Ok(cart)
     }
 
+    /// Load `Cart` from an `NSF`, `NSF2` or `NSFe` music file, played by the built-in [`Nsf`]
+    /// player.
+    fn from_nsf(name: String, data: &[u8], ram_state: RamState) -> Result<Self> {
+        let (nsf, program) = NsfHeader::parse(data)?;
+        let mut prg_rom = nsf.prg_rom(program)?;
+        prg_rom.resize(prg_rom.len().next_multiple_of(PRG_ROM_BANK_SIZE), 0x00);
+        let header = NesHeader {
+            variant: NesVariant::Nsf,
+            prg_rom_banks: (prg_rom.len() / PRG_ROM_BANK_SIZE) as u16,
+            tv_mode: match nsf.region {
+                Some(NesRegion::Pal) => 1,
+                Some(_) => 0,
+                None => 2,
+            },
+            ..NesHeader::default()
+        };
+        let mut cart = Self {
+            name,
+            header,
+            original_header: header,
+            header_repaired: false,
+            db_entry: None,
+            region: nsf.region.unwrap_or_default(),
+            region_source: if nsf.region.is_some() {
+                RegionSource::Header
+            } else {
+                RegionSource::Default
+            },
+            ram_state,
+            mapper: Mapper::none(),
+            chr_rom: vec![],
+            chr_ram: vec![],
+            prg_rom,
+            prg_ram: vec![],
+            ex_ram: vec![],
+        };
+        cart.mapper = Nsf::load(&mut cart, &nsf);
+        if !nsf.expansion_audio.unsupported().is_empty() {
+            warn!(
+                "unsupported nsf expansion audio: {}",
+                nsf.expansion_audio.unsupported()
+            );
+        }
+
+        info!("loaded NSF `{cart}`");
+        debug!("{cart:?}");
+        Ok(cart)
+    }
+
     #[must_use]
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    /// Returns the parsed `NesHeader` for this Cart.
+    pub const fn header(&self) -> &NesHeader {
+        &self.header
+    }
+
+    /// Returns the `NesHeader` as stored in the ROM, before any repairs or overrides.
+    pub const fn original_header(&self) -> &NesHeader {
+        &self.original_header
+    }
+
+    /// Returns whether the header was repaired when loaded, either from the
+    /// [`RomDatabase`](crate::rom_db::RomDatabase) or because its PRG-ROM size didn't fit the ROM
+    /// data. Manual overrides aren't considered repairs.
+    #[must_use]
+    pub const fn header_repaired(&self) -> bool {
+        self.header_repaired
+    }
+
+    /// Returns the entry matching this Cart in the installed
+    /// [`CartDb`](crate::cart_db::CartDb), if any.
+    pub const fn db_entry(&self) -> Option<&CartDbEntry> {
+        self.db_entry.as_ref()
+    }
+
+    /// Returns where this Cart's region was detected from when loaded.
+    pub const fn region_source(&self) -> RegionSource {
+        self.region_source
+    }
+
+    /// Returns the compatibility patch for this Cart in the active
+    /// [`RomDatabase`](crate::rom_db::RomDatabase), if any.
+    pub fn compat_patch(&self) -> Option<CompatPatch> {
+        CompatPatch::lookup(self.crc32())
+    }
+
+    /// Returns the registered board for this Cart's mapper and submapper, if supported.
+    #[must_use]
+    pub fn board(&self) -> Option<&'static registry::Board> {
+        registry::lookup(self.header.mapper_num, self.header.submapper_num)
+    }
+
+    /// Returns the board's DIP switches, or the Vs. System DIP switches, if it has any.
+    #[must_use]
+    pub const fn dip_switches(&self) -> Option<DipSwitches> {
+        if self.header.is_vs_system() {
+            Some(DipSwitches::new(VsSystem::DIP_SWITCH_COUNT, 0x00))
+        } else {
+            self.mapper.dip_switches()
+        }
+    }
+
     #[must_use]
     pub fn chr_rom(&self) -> &[u8] {
         &self.chr_rom
@@ -275,6 +653,23 @@ impl Cart {
         matches!(self.header.variant, NesVariant::Nes2)
     }
 
+    /// Returns whether this is an `NSF` music file, played by the built-in [`Nsf`] player.
+    #[must_use]
+    pub const fn is_nsf(&self) -> bool {
+        self.header.is_nsf()
+    }
+
+    /// Returns whether this is a Vs. System arcade game.
+    #[must_use]
+    pub const fn is_vs_system(&self) -> bool {
+        self.header.is_vs_system()
+    }
+
+    /// Returns the `PpuModel` this game runs on.
+    pub const fn ppu_model(&self) -> PpuModel {
+        self.header.ppu_model()
+    }
+
     /// Returns whether this cartridge has battery-backed Save RAM.
     #[must_use]
     pub const fn battery_backed(&self) -> bool {
@@ -335,46 +730,81 @@ impl Cart {
         RamState::fill(&mut self.ex_ram, self.ram_state);
     }
 
-    fn calculate_ram_size(value: u8) -> Result<usize> {
-        if value > 0 {
-            64usize
-                .checked_shl(value.into())
-                .ok_or_else(|| Error::InvalidHeader {
-                    byte: 11,
-                    value,
-                    message: "header ram size larger than 64".to_string(),
-                })
-        } else {
-            Ok(0)
-        }
+    /// Returns the combined CRC32 of the PRG-ROM and CHR-ROM.
+    #[must_use]
+    pub fn crc32(&self) -> u32 {
+        Self::compute_crc32(&self.prg_rom, &self.chr_rom)
     }
 
-    fn lookup_region(prg_rom: &[u8], chr: &[u8]) -> NesRegion {
-        const GAME_REGIONS: &[u8] = include_bytes!("../game_regions.dat");
+    /// Returns the CRC32 of the PRG-ROM.
+    #[must_use]
+    pub fn prg_rom_crc32(&self) -> u32 {
+        fs::compute_crc32(&self.prg_rom)
+    }
 
-        let Ok(games) = fs::load_bytes::<Vec<GameRegion>>(GAME_REGIONS) else {
-            error!("failed to load `game_regions.dat`");
-            return NesRegion::Ntsc;
-        };
+    /// Returns the CRC32 of the CHR-ROM, if any.
+    #[must_use]
+    pub fn chr_rom_crc32(&self) -> Option<u32> {
+        self.has_chr_rom().then(|| fs::compute_crc32(&self.chr_rom))
+    }
 
+    /// Returns the combined SHA-1 of the PRG-ROM and CHR-ROM, as used by the `NES 2.0` XML
+    /// database.
+    #[must_use]
+    pub fn sha1(&self) -> [u8; 20] {
+        let mut data = Vec::with_capacity(self.prg_rom.len() + self.chr_rom.len());
+        data.extend_from_slice(&self.prg_rom);
+        data.extend_from_slice(&self.chr_rom);
+        fs::compute_sha1(&data)
+    }
+
+    fn compute_crc32(prg_rom: &[u8], chr: &[u8]) -> u32 {
         let mut crc32 = fs::compute_crc32(prg_rom);
         if !chr.is_empty() {
             crc32 = fs::compute_combine_crc32(crc32, chr);
         }
+        crc32
+    }
 
-        match games.binary_search_by(|game| game.crc32.cmp(&crc32)) {
-            Ok(index) => {
-                info!(
-                    "found game matching crc: {crc32:#010X}. region: {}",
-                    games[index].region
-                );
-                games[index].region
-            }
-            Err(_) => {
-                info!("no game found matching crc: {crc32:#010X}",);
-                NesRegion::Ntsc
+    fn lookup_region(prg_rom: &[u8], chr: &[u8]) -> Option<NesRegion> {
+        let crc32 = Self::compute_crc32(prg_rom, chr);
+        let region = rom_db::active().lookup_region(crc32);
+        match region {
+            Some(region) => info!("found game matching crc: {crc32:#010X}. region: {region}"),
+            None => info!("no game found matching crc: {crc32:#010X}"),
+        }
+        region
+    }
+
+    /// Guess the region from tags in a ROM file name, e.g. `(E)` or `(PAL)`. Returns `None` if
+    /// there are no region tags or they disagree, e.g. `(USA, Europe)`.
+    fn filename_region(name: &str) -> Option<NesRegion> {
+        let file_name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+        let tags = file_name
+            .split(['(', '['])
+            .skip(1)
+            .filter_map(|tag| tag.split_once([')', ']']).map(|(tag, _)| tag))
+            .flat_map(|tag| tag.split(','));
+        let mut detected = None;
+        for tag in tags {
+            let region = match tag.trim().to_ascii_lowercase().as_str() {
+                "u" | "usa" | "j" | "japan" | "ju" | "canada" | "ntsc" => NesRegion::Ntsc,
+                "e" | "europe" | "a" | "australia" | "g" | "germany" | "f" | "france" | "s"
+                | "spain" | "i" | "italy" | "sw" | "sweden" | "scandinavia" | "uk" | "pal" => {
+                    NesRegion::Pal
+                }
+                "dendy" => NesRegion::Dendy,
+                _ => continue,
+            };
+            if detected.is_some_and(|detected| detected != region) {
+                return None;
             }
+            detected = Some(region);
         }
+        if let Some(region) = detected {
+            info!("detected region from file name: {region}");
+        }
+        detected
     }
 }
 
@@ -411,7 +841,11 @@ impl core::fmt::Debug for Cart {
         f.debug_struct("Cart")
             .field("name", &self.name)
             .field("header", &self.header)
+            .field("original_header", &self.original_header)
+            .field("header_repaired", &self.header_repaired)
+            .field("db_entry", &self.db_entry)
             .field("region", &self.region)
+            .field("region_source", &self.region_source)
             .field("ram_state", &self.ram_state)
             .field("mapper", &self.mapper)
             .field("mirroring", &self.mirroring())
@@ -433,6 +867,8 @@ pub enum NesVariant {
     INes07,
     INes,
     Nes2,
+    /// `NSF`, `NSF2` or `NSFe` music file, which has no cartridge header.
+    Nsf,
 }
 
 /// An `iNES` or `NES 2.0` formatted header representing hardware specs of a given NES cartridge.
@@ -444,15 +880,20 @@ pub enum NesVariant {
 #[must_use]
 pub struct NesHeader {
     pub variant: NesVariant,
-    pub mapper_num: u16,    // The primary mapper number
-    pub submapper_num: u8,  // NES 2.0 https://wiki.nesdev.com/w/index.php/NES_2.0_submappers
-    pub flags: u8,          // Mirroring, Battery, Trainer, VS Unisystem, Playchoice-10, NES 2.0
-    pub prg_rom_banks: u16, // Number of 16KB PRG-ROM banks (Program ROM)
-    pub chr_rom_banks: u16, // Number of 8KB CHR-ROM banks (Character ROM)
-    pub prg_ram_shift: u8,  // NES 2.0 PRG-RAM
-    pub chr_ram_shift: u8,  // NES 2.0 CHR-RAM
-    pub tv_mode: u8,        // NES 2.0 NTSC/PAL indicator
-    pub vs_data: u8,        // NES 2.0 VS System data
+    pub mapper_num: u16,      // The primary mapper number
+    pub submapper_num: u8,    // NES 2.0 https://wiki.nesdev.com/w/index.php/NES_2.0_submappers
+    pub flags: u8,            // Mirroring, Battery, Trainer, VS Unisystem, Playchoice-10, NES 2.0
+    pub prg_rom_banks: u16,   // Number of 16KB PRG-ROM banks (Program ROM)
+    pub chr_rom_banks: u16,   // Number of 8KB CHR-ROM banks (Character ROM)
+    pub prg_ram_shift: u8,    // NES 2.0 PRG-RAM
+    pub prg_nvram_shift: u8,  // NES 2.0 PRG-NVRAM (battery-backed)
+    pub chr_ram_shift: u8,    // NES 2.0 CHR-RAM
+    pub chr_nvram_shift: u8,  // NES 2.0 CHR-NVRAM (battery-backed)
+    pub tv_mode: u8,          // NES 2.0 CPU/PPU timing: NTSC, PAL, Multi-region, or Dendy
+    pub console_type: u8,     // NES 2.0 NES/Famicom, Vs. System, Playchoice-10, or Extended
+    pub vs_data: u8,          // NES 2.0 VS System data
+    pub misc_roms: u8,        // NES 2.0 Number of miscellaneous ROMs
+    pub expansion_device: u8, // NES 2.0 Default expansion device
 }
 
 impl NesHeader {
@@ -498,12 +939,15 @@ impl NesHeader {
                 message: "nes header signature not found".to_string(),
             });
         }
-        if (header[7] & 0x0C) == 0x04 {
-            return Err(Error::InvalidHeader {
-                byte: 7,
-                value: header[7],
-                message: "header is corrupted by `DiskDude!`. repair and try again".to_string(),
-            });
+        // Archaic iNES only uses bytes 0-6. Old dumping tools often left garbage like `DiskDude!`
+        // in the rest, which would otherwise be read as the upper nibble of the mapper number.
+        let archaic = (header[7] & 0x0C) == 0x04;
+        if archaic {
+            warn!(
+                "ignoring archaic header bytes 7-15: {:?}",
+                String::from_utf8_lossy(&header[7..])
+            );
+            header[7..].fill(0x00);
         }
         if (header[7] & 0x0C) == 0x0C {
             return Err(Error::InvalidHeader {
@@ -523,9 +967,14 @@ impl NesHeader {
         // NES 2.0 Format
         let mut submapper_num = 0;
         let mut prg_ram_shift = 0;
+        let mut prg_nvram_shift = 0;
         let mut chr_ram_shift = 0;
+        let mut chr_nvram_shift = 0;
         let mut tv_mode = 0;
+        let mut console_type = 0;
         let mut vs_data = 0;
+        let mut misc_roms = 0;
+        let mut expansion_device = 0;
         // If D2..D3 of flag 7 == 2, then NES 2.0 (supports bytes 0-15)
         let variant = if header[7] & 0x0C == 0x08 {
             // lower 4 bits of flag 8 = D8..D11 of mapper num
@@ -536,53 +985,39 @@ impl NesHeader {
             prg_rom_banks |= u16::from(header[9] & 0x0F) << 8;
             // upper 4 bits of flag 9 = D8..D11 of chr_rom_size
             chr_rom_banks |= u16::from(header[9] & 0xF0) << 4;
-            prg_ram_shift = header[10];
-            chr_ram_shift = header[11];
-            tv_mode = header[12];
+            // lower 4 bits of flag 10 = PRG-RAM shift, upper 4 bits = PRG-NVRAM shift
+            prg_ram_shift = header[10] & 0x0F;
+            prg_nvram_shift = (header[10] & 0xF0) >> 4;
+            // lower 4 bits of flag 11 = CHR-RAM shift, upper 4 bits = CHR-NVRAM shift
+            chr_ram_shift = header[11] & 0x0F;
+            chr_nvram_shift = (header[11] & 0xF0) >> 4;
+            // lower 2 bits of flag 12 = CPU/PPU timing
+            tv_mode = header[12] & 0x03;
+            // lower 2 bits of flag 7 = console type
+            console_type = header[7] & 0x03;
             vs_data = header[13];
+            // lower 2 bits of flag 14 = number of miscellaneous ROMs
+            misc_roms = header[14] & 0x03;
+            // lower 6 bits of flag 15 = default expansion device
+            expansion_device = header[15] & 0x3F;
 
-            if prg_ram_shift & 0x0F == 0x0F || prg_ram_shift & 0xF0 == 0xF0 {
+            if prg_ram_shift == 0x0F || prg_nvram_shift == 0x0F {
                 return Err(Error::InvalidHeader {
                     byte: 10,
-                    value: prg_ram_shift,
+                    value: header[10],
                     message: "invalid prg-ram size in header".to_string(),
                 });
             }
-            if chr_ram_shift & 0x0F == 0x0F || chr_ram_shift & 0xF0 == 0xF0 {
+            if chr_ram_shift == 0x0F || chr_nvram_shift == 0x0F {
                 return Err(Error::InvalidHeader {
                     byte: 11,
-                    value: chr_ram_shift,
+                    value: header[11],
                     message: "invalid chr-ram size in header".to_string(),
                 });
             }
-            if chr_ram_shift & 0xF0 == 0xF0 {
-                return Err(Error::InvalidHeader {
-                    byte: 11,
-                    value: chr_ram_shift,
-                    message: "battery-backed chr-ram is currently not supported".to_string(),
-                });
-            }
-            if header[14] > 0 || header[15] > 0 {
-                return Err(Error::InvalidHeader {
-                    byte: 14,
-                    value: header[14],
-                    message: "unrecognized data found at header offsets 14-15".to_string(),
-                });
-            }
             NesVariant::Nes2
-        } else if header[7] & 0x0C == 0x04 {
-            // If D2..D3 of flag 7 == 1, then archaic iNES (supports bytes 0-7)
-            for (i, value) in header.iter().enumerate().take(16).skip(8) {
-                if *value > 0 {
-                    return Err(Error::InvalidHeader {
-                        byte: i as u8,
-                        value: *value,
-                        message: format!(
-                            "unrecogonized data found at header byte {i}. repair and try again"
-                        ),
-                    });
-                }
-            }
+        } else if archaic {
+            // If D2..D3 of flag 7 == 1, then archaic iNES (supports bytes 0-6)
             NesVariant::ArchaicINes
         } else if header[7] & 0x0C == 00 && header[12..=15].iter().all(|v| *v == 0) {
             // If D2..D3 of flag 7 == 0 and bytes 12-15 are all 0, then iNES (supports bytes 0-9)
@@ -609,14 +1044,121 @@ impl NesHeader {
             prg_rom_banks,
             chr_rom_banks,
             prg_ram_shift,
+            prg_nvram_shift,
             chr_ram_shift,
+            chr_nvram_shift,
             tv_mode,
+            console_type,
             vs_data,
+            misc_roms,
+            expansion_device,
         })
     }
 
+    /// Fix a PRG-ROM size that doesn't fit in the `data_len` bytes of ROM data following the
+    /// header, a common mistake in hand-edited headers, if the remaining data after CHR-ROM is a
+    /// whole number of PRG-ROM banks.
+    fn repair_prg_rom_size(&mut self, data_len: usize) {
+        let chr_rom_len = (self.chr_rom_banks as usize) * CHR_ROM_BANK_SIZE;
+        let prg_rom_len = (self.prg_rom_banks as usize) * PRG_ROM_BANK_SIZE;
+        if (self.prg_rom_banks > 0 && prg_rom_len + chr_rom_len <= data_len)
+            || data_len <= chr_rom_len
+            || (data_len - chr_rom_len) % PRG_ROM_BANK_SIZE != 0
+        {
+            return;
+        }
+        if let Ok(banks) = u16::try_from((data_len - chr_rom_len) / PRG_ROM_BANK_SIZE) {
+            warn!(
+                "repairing prg-rom size from {} to {banks} banks to fit rom data",
+                self.prg_rom_banks
+            );
+            self.prg_rom_banks = banks;
+        }
+    }
+
+    /// Returns the size of volatile PRG-RAM in bytes specified by the header.
+    #[must_use]
+    pub const fn prg_ram_size(&self) -> usize {
+        Self::ram_size(self.prg_ram_shift)
+    }
+
+    /// Returns the size of battery-backed PRG-NVRAM in bytes specified by the header.
+    #[must_use]
+    pub const fn prg_nvram_size(&self) -> usize {
+        Self::ram_size(self.prg_nvram_shift)
+    }
+
+    /// Returns the size of volatile CHR-RAM in bytes specified by the header.
+    #[must_use]
+    pub const fn chr_ram_size(&self) -> usize {
+        Self::ram_size(self.chr_ram_shift)
+    }
+
+    /// Returns the size of battery-backed CHR-NVRAM in bytes specified by the header.
+    #[must_use]
+    pub const fn chr_nvram_size(&self) -> usize {
+        Self::ram_size(self.chr_nvram_shift)
+    }
+
+    /// Returns the `NesRegion` specified by the header timing field, if any. Multi-region ROMs
+    /// and headers without timing information return `None`.
+    #[must_use]
+    pub const fn region(&self) -> Option<NesRegion> {
+        if matches!(
+            self.variant,
+            NesVariant::INes | NesVariant::Nes2 | NesVariant::Nsf
+        ) {
+            match self.tv_mode {
+                1 => Some(NesRegion::Pal),
+                3 => Some(NesRegion::Dendy),
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether the header describes an `NSF` music file rather than a cartridge.
+    #[must_use]
+    pub const fn is_nsf(&self) -> bool {
+        matches!(self.variant, NesVariant::Nsf)
+    }
+
+    /// Returns whether the header describes a Vs. System game, either by the NES 2.0 console type
+    /// or the `iNES` Vs. Unisystem flag.
+    #[must_use]
+    pub const fn is_vs_system(&self) -> bool {
+        match self.variant {
+            NesVariant::Nes2 => self.console_type == 1,
+            _ => self.console_type == 1 || self.flags & 0x10 == 0x10,
+        }
+    }
+
+    /// Returns the `PpuModel` specified by the header. Vs. System games without a NES 2.0 PPU type
+    /// default to the `RP2C03`, which uses the standard palette.
+    pub const fn ppu_model(&self) -> PpuModel {
+        if self.is_vs_system() {
+            PpuModel::from_vs_ppu_type(self.vs_data)
+        } else {
+            PpuModel::Rp2c02
+        }
+    }
+
+    /// NES 2.0 RAM sizes are stored as a shift count where the size is `64 << shift` bytes, with
+    /// `0` meaning no RAM.
+    const fn ram_size(shift: u8) -> usize {
+        if shift > 0 {
+            64 << shift
+        } else {
+            0
+        }
+    }
+
     #[must_use]
     pub const fn mapper_board(&self) -> &'static str {
+        if self.is_nsf() {
+            return "NSF Player";
+        }
         match self.mapper_num {
             0 => "Mapper 000 - NROM",
             1 => "Mapper 001 - SxROM/MMC1B/C",
@@ -675,10 +1217,10 @@ impl NesHeader {
             54 => "Mapper 054 - Novel Diamond",
             55 => "Mapper 055 - UNIF BTL-MARIO1-MALEE2",
             56 => "Mapper 056",
-            57 => "Mapper 057",
+            57 => "Mapper 057 - GK 6-in-1",
             58 => "Mapper 058",
             59 => "Mapper 059 - BMC T3H53/D1038",
-            60 => "Mapper 060",
+            60 => "Mapper 060 - Reset-based 4-in-1",
             61 => "Mapper 061",
             62 => "Mapper 062",
             63 => "Mapper 063",
@@ -874,6 +1416,7 @@ impl NesHeader {
             253 => "Mapper 253 - Wàixīng",
             254 => "Mapper 254 - MMC3",
             255 => "Mapper 255",
+            682 => "Mapper 682 - Rainbow",
             _ => "Invalid Mapper",
         }
     }
@@ -889,9 +1432,14 @@ impl core::fmt::Debug for NesHeader {
             .field("prg_rom_banks", &self.prg_rom_banks)
             .field("chr_rom_banks", &self.chr_rom_banks)
             .field("prg_ram_shift", &self.prg_ram_shift)
+            .field("prg_nvram_shift", &self.prg_nvram_shift)
             .field("chr_ram_shift", &self.chr_ram_shift)
+            .field("chr_nvram_shift", &self.chr_nvram_shift)
             .field("tv_mode", &self.tv_mode)
+            .field("console_type", &self.console_type)
             .field("vs_data", &self.vs_data)
+            .field("misc_roms", &self.misc_roms)
+            .field("expansion_device", &self.expansion_device)
             .finish()
     }
 }
@@ -900,6 +1448,188 @@ impl core::fmt::Debug for NesHeader {
 mod tests {
     use super::*;
 
+    fn rom(header: [u8; 16], len: usize) -> Vec<u8> {
+        let mut rom = header.to_vec();
+        rom.resize(16 + len, 0x00);
+        rom
+    }
+
+    #[test]
+    fn oversize_chr_rom_autodetect() {
+        // CNROM declaring 32K of CHR-ROM with 64K present
+        let data = rom(
+            *b"NES\x1a\x02\x04\x30\x00\x00\x00\x00\x00\x00\x00\x00\x00",
+            0x8000 + 0x10000,
+        );
+        let cart =
+            Cart::from_rom("cnrom", &mut data.as_slice(), RamState::default()).expect("valid cart");
+        assert_eq!(cart.chr_rom.len(), 0x10000);
+    }
+
+    #[test]
+    fn unaddressable_rom() {
+        // NROM declaring 64K of PRG-ROM
+        let data = rom(
+            *b"NES\x1a\x04\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00",
+            0x10000 + 0x2000,
+        );
+        let res = Cart::from_rom("nrom", &mut data.as_slice(), RamState::default());
+        assert!(matches!(
+            res,
+            Err(Error::UnaddressableRom {
+                rom: "prg-rom",
+                max: 0x8000,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn region_from_filename() {
+        // NROM without header timing or a database entry
+        let data = rom(
+            *b"NES\x1a\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00",
+            0x4000 + 0x2000,
+        );
+        for (name, region, source) in [
+            (
+                "roms/Game (E) [!].nes",
+                NesRegion::Pal,
+                RegionSource::FileName,
+            ),
+            (
+                "Game (Europe) (Rev 1).nes",
+                NesRegion::Pal,
+                RegionSource::FileName,
+            ),
+            ("Game (PAL).nes", NesRegion::Pal, RegionSource::FileName),
+            ("Game (Dendy).nes", NesRegion::Dendy, RegionSource::FileName),
+            ("Game (USA).nes", NesRegion::Ntsc, RegionSource::FileName),
+            (
+                "Game (USA, Europe).nes",
+                NesRegion::Ntsc,
+                RegionSource::Default,
+            ),
+            ("(E) Games/Game.nes", NesRegion::Ntsc, RegionSource::Default),
+            ("Game.nes", NesRegion::Ntsc, RegionSource::Default),
+        ] {
+            let cart = Cart::from_rom(name, &mut data.as_slice(), RamState::default())
+                .expect("valid cart");
+            assert_eq!(cart.region(), region, "{name}");
+            assert_eq!(cart.region_source(), source, "{name}");
+        }
+    }
+
+    #[test]
+    fn header_repairs_and_overrides() {
+        // NROM declaring 32K of PRG-ROM with only 16K present
+        let data = rom(
+            *b"NES\x1a\x02\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00",
+            0x4000 + 0x2000,
+        );
+        let cart =
+            Cart::from_rom("nrom", &mut data.as_slice(), RamState::default()).expect("valid cart");
+        assert!(cart.header_repaired());
+        assert_eq!(cart.original_header().prg_rom_banks, 2);
+        assert_eq!(cart.prg_rom.len(), 0x4000);
+        assert_eq!(cart.mirroring(), Mirroring::Horizontal);
+
+        let overrides = HeaderOverrides {
+            mapper_num: Some(3),
+            mirroring: Some(Mirroring::Vertical),
+            battery_backed: Some(true),
+            ..HeaderOverrides::default()
+        };
+        let cart = Cart::load_with_overrides(
+            "cnrom",
+            &mut data.as_slice(),
+            RamState::default(),
+            &overrides,
+        )
+        .expect("valid cart");
+        assert_eq!(cart.mapper_num(), 3);
+        assert_eq!(cart.mirroring(), Mirroring::Vertical);
+        assert!(cart.battery_backed());
+        assert_eq!(cart.original_header().mapper_num, 0);
+    }
+
+    #[test]
+    fn cart_database() {
+        // NROM with CHR-RAM the iNES header can't mark as battery-backed
+        let mut data = rom(
+            *b"NES\x1a\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00",
+            0x4000,
+        );
+        data[16..32].copy_from_slice(b"cart_database!!!");
+        let crc32 = fs::compute_crc32(&data[16..]);
+        let xml = format!(
+            r#"<game><rom crc32="{crc32:08X}"/><pcb mapper="0" submapper="0" battery="1"/><chrnvram size="8192"/><expansion type="8"/></game>"#
+        );
+        cart_db::install(cart_db::CartDb::parse(&xml).expect("valid database"));
+        let cart = Cart::from_rom("nrom", &mut data.as_slice(), RamState::default());
+        cart_db::clear();
+
+        let cart = cart.expect("valid cart");
+        assert_eq!(cart.db_entry().map(|entry| entry.crc32), Some(crc32));
+        assert!(!cart.header_repaired(), "database entries aren't repairs");
+        assert!(cart.battery_backed());
+        assert_eq!(cart.header().chr_nvram_size(), 0x2000);
+        assert_eq!(cart.header().expansion_device, 8);
+        assert_eq!(cart.chr_ram.len(), 0x2000);
+    }
+
+    #[test]
+    fn header_fix_database() {
+        let database = r#"
+            # comment
+            1A2B3C4D, "Game (USA)", mapper=4 mirroring=four-screen prg=8 battery=no
+        "#;
+        let fixes = HeaderFix::parse_database(database).expect("valid database");
+        assert_eq!(fixes.len(), 1);
+        assert_eq!(fixes[0].crc32, 0x1A2B_3C4D);
+        assert_eq!(fixes[0].title, "Game (USA)");
+        assert_eq!(
+            fixes[0].overrides,
+            HeaderOverrides {
+                mapper_num: Some(4),
+                mirroring: Some(Mirroring::FourScreen),
+                prg_rom_banks: Some(8),
+                battery_backed: Some(false),
+                ..HeaderOverrides::default()
+            }
+        );
+
+        assert!(HeaderFix::parse_database("1234, \"Game\"").is_err());
+        assert!(HeaderFix::parse_database("1234, \"Game\", mirroring=diagonal").is_err());
+    }
+
+    #[test]
+    fn load_header_fix() {
+        let _lock = rom_db::test_lock();
+        let mut rom = include_bytes!("../test_roms/spritecans.nes").to_vec();
+        let crc32 = fs::compute_crc32(&rom[16..]);
+        // Mapper 4 with vertical mirroring instead of NROM with horizontal mirroring
+        rom[6] = 0x41;
+        rom_db::install(
+            rom_db::RomDatabase::new(
+                2,
+                Vec::new(),
+                "",
+                format!("{crc32:08X}, \"spritecans\", mapper=0 mirroring=horizontal"),
+            )
+            .expect("valid database"),
+        );
+        let cart = Cart::from_rom("spritecans", &mut rom.as_slice(), RamState::AllZeros);
+        rom_db::reset();
+
+        let cart = cart.expect("loaded rom");
+        assert!(cart.header_repaired());
+        assert_eq!(cart.original_header().mapper_num, 4);
+        assert_eq!(cart.mapper_num(), 0);
+        assert_eq!(cart.mirroring(), Mirroring::Horizontal);
+        assert!(matches!(cart.mapper, Mapper::Nrom(_)));
+    }
+
     macro_rules! test_headers {
         ($(($test:ident, $data:expr, $header:expr$(,)?)),*$(,)?) => {$(
             #[test]
@@ -942,5 +1672,40 @@ mod tests {
                 ..NesHeader::default()
             },
         ),
+        (
+            mapper004_archaic_diskdude,
+            [0x4E, 0x45, 0x53, 0x1A,
+             0x08, 0x10, 0x41, b'D',
+             b'i', b's', b'k', b'D',
+             b'u', b'd', b'e', b'!'],
+            NesHeader {
+                variant: NesVariant::ArchaicINes,
+                mapper_num: 4,
+                flags: 0b0000_0001,
+                prg_rom_banks: 8,
+                chr_rom_banks: 16,
+                ..NesHeader::default()
+            },
+        ),
+        (
+            mapper004_nes2_submapper,
+            [0x4E, 0x45, 0x53, 0x1A,
+             0x10, 0x10, 0x42, 0x08,
+             0x30, 0x00, 0x70, 0x07,
+             0x01, 0x00, 0x00, 0x01],
+            NesHeader {
+                variant: NesVariant::Nes2,
+                mapper_num: 4,
+                submapper_num: 3,
+                flags: 0b1000_0010,
+                prg_rom_banks: 16,
+                chr_rom_banks: 16,
+                prg_nvram_shift: 7,
+                chr_ram_shift: 7,
+                tv_mode: 1,
+                expansion_device: 1,
+                ..NesHeader::default()
+            },
+        ),
     );
 }
@@ -0,0 +1,386 @@
+//! NES 2.0 XML database (`nes20db.xml`) lookup, used to fill in header fields missing from ROMs
+//! with `iNES` headers, such as submappers, RAM sizes and default input devices.
+//!
+//! The database is maintained separately from `TetaNES` and is several megabytes, so it isn't
+//! bundled. Frontends can load it with [`CartDb::parse`] and [`install`] it before loading ROMs.
+//!
+//! See: <https://forums.nesdev.org/viewtopic.php?t=19940>
+
+use crate::{cart::NesHeader, fs};
+use alloc::{
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use snafu::Snafu;
+use spin::RwLock;
+use tracing::info;
+
+static ACTIVE: RwLock<Option<Arc<CartDb>>> = RwLock::new(None);
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Snafu, Debug, PartialEq, Eq)]
+#[must_use]
+pub enum Error {
+    #[snafu(display("invalid cart database on line {line}: {message}"))]
+    InvalidXml { line: usize, message: String },
+}
+
+/// A game in the NES 2.0 XML database, describing the board its ROM data was dumped from.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct CartDbEntry {
+    /// Title from the comment preceding the entry, if any.
+    pub title: Option<String>,
+    /// CRC32 of the ROM data, excluding the header.
+    pub crc32: u32,
+    /// SHA-1 of the ROM data, excluding the header.
+    pub sha1: Option<[u8; 20]>,
+    pub mapper_num: u16,
+    pub submapper_num: u8,
+    pub battery_backed: bool,
+    /// Size of volatile PRG-RAM in bytes.
+    pub prg_ram_size: usize,
+    /// Size of battery-backed PRG-NVRAM in bytes.
+    pub prg_nvram_size: usize,
+    /// Size of volatile CHR-RAM in bytes.
+    pub chr_ram_size: usize,
+    /// Size of battery-backed CHR-NVRAM in bytes.
+    pub chr_nvram_size: usize,
+    /// NES 2.0 console type: NES/Famicom, Vs. System, Playchoice-10, or Extended.
+    pub console_type: u8,
+    /// NES 2.0 CPU/PPU timing: NTSC, PAL, Multi-region, or Dendy.
+    pub tv_mode: u8,
+    /// NES 2.0 Vs. System PPU type in the lower 4 bits and hardware type in the upper 4 bits.
+    pub vs_data: u8,
+    /// NES 2.0 default expansion device, e.g. `0x08` for the Zapper.
+    pub expansion_device: u8,
+}
+
+impl CartDbEntry {
+    /// Apply the database fields to `header`. Mirroring and ROM sizes are left as is.
+    pub fn apply(&self, header: &mut NesHeader) {
+        header.mapper_num = self.mapper_num;
+        header.submapper_num = self.submapper_num;
+        header.prg_ram_shift = Self::size_shift(self.prg_ram_size);
+        header.prg_nvram_shift = Self::size_shift(self.prg_nvram_size);
+        header.chr_ram_shift = Self::size_shift(self.chr_ram_size);
+        header.chr_nvram_shift = Self::size_shift(self.chr_nvram_size);
+        if self.battery_backed {
+            header.flags |= 0x02;
+        } else {
+            header.flags &= !0x02;
+        }
+        header.console_type = self.console_type;
+        header.tv_mode = self.tv_mode;
+        header.vs_data = self.vs_data;
+        header.expansion_device = self.expansion_device;
+    }
+
+    /// NES 2.0 RAM size shift count for `size` bytes, where the size is `64 << shift`.
+    const fn size_shift(size: usize) -> u8 {
+        if size == 0 {
+            0
+        } else {
+            (size.next_power_of_two().trailing_zeros() as u8).saturating_sub(6)
+        }
+    }
+
+    /// Set fields from the attributes of element `name`, ignoring elements and attributes that
+    /// aren't needed.
+    fn set(&mut self, name: &str, attrs: &[(&str, &str)]) -> core::result::Result<(), String> {
+        for &(key, value) in attrs {
+            match (name, key) {
+                ("rom", "crc32") => {
+                    self.crc32 = u32::from_str_radix(value, 16)
+                        .map_err(|_| format!("invalid crc32 `{value}`"))?;
+                }
+                ("rom", "sha1") => self.sha1 = Some(parse_sha1(value)?),
+                ("pcb", "mapper") => self.mapper_num = parse_num(key, value)?,
+                ("pcb", "submapper") => self.submapper_num = parse_num(key, value)?,
+                ("pcb", "battery") => self.battery_backed = parse_num::<u8>(key, value)? != 0,
+                ("prgram", "size") => self.prg_ram_size = parse_num(key, value)?,
+                ("prgnvram", "size") => self.prg_nvram_size = parse_num(key, value)?,
+                ("chrram", "size") => self.chr_ram_size = parse_num(key, value)?,
+                ("chrnvram", "size") => self.chr_nvram_size = parse_num(key, value)?,
+                ("console", "type") => self.console_type = parse_num(key, value)?,
+                ("console", "region") => self.tv_mode = parse_num(key, value)?,
+                ("vs", "ppu") => {
+                    self.vs_data = (self.vs_data & 0xF0) | (parse_num::<u8>(key, value)? & 0x0F);
+                }
+                ("vs", "hardware") => {
+                    self.vs_data = (self.vs_data & 0x0F) | (parse_num::<u8>(key, value)? << 4);
+                }
+                ("expansion", "type") => self.expansion_device = parse_num(key, value)?,
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A parsed NES 2.0 XML database.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct CartDb {
+    /// Sorted by CRC32.
+    entries: Vec<CartDbEntry>,
+}
+
+impl CartDb {
+    /// Parse a NES 2.0 XML database. Only the subset of XML used by the database is supported:
+    /// elements, attributes and comments.
+    ///
+    /// # Errors
+    ///
+    /// If the XML is malformed, or a `game` is missing its ROM CRC32, then an error is returned.
+    pub fn parse(xml: &str) -> Result<Self> {
+        let mut entries = Vec::new();
+        let mut game: Option<CartDbEntry> = None;
+        let mut has_crc32 = false;
+        let mut pos = 0;
+        while let Some(start) = xml[pos..].find('<').map(|offset| pos + offset) {
+            let error = |message: String| Error::InvalidXml {
+                line: xml[..start].matches('\n').count() + 1,
+                message,
+            };
+
+            if let Some(comment) = xml[start..].strip_prefix("<!--") {
+                let end = comment
+                    .find("-->")
+                    .ok_or_else(|| error("unterminated comment".to_string()))?;
+                if let Some(game) = game.as_mut() {
+                    game.title = parse_title(&comment[..end]);
+                }
+                pos = start + "<!--".len() + end + "-->".len();
+                continue;
+            }
+
+            let end = xml[start..]
+                .find('>')
+                .ok_or_else(|| error("unterminated element".to_string()))?;
+            let tag = &xml[start + 1..start + end];
+            pos = start + end + 1;
+            if tag.starts_with(['?', '!']) {
+                continue;
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                if name.trim() == "game" {
+                    let entry = game
+                        .take()
+                        .ok_or_else(|| error("unexpected `</game>`".to_string()))?;
+                    if !has_crc32 {
+                        return Err(error("game is missing a rom crc32".to_string()));
+                    }
+                    entries.push(entry);
+                }
+                continue;
+            }
+
+            let tag = tag.strip_suffix('/').unwrap_or(tag);
+            let (name, attrs) = tag
+                .trim()
+                .split_once(char::is_whitespace)
+                .unwrap_or((tag.trim(), ""));
+            let attrs = parse_attributes(attrs).map_err(error)?;
+            match (name, game.as_mut()) {
+                ("game", Some(_)) => return Err(error("nested `<game>`".to_string())),
+                ("game", None) => {
+                    game = Some(CartDbEntry::default());
+                    has_crc32 = false;
+                }
+                (_, Some(game)) => {
+                    has_crc32 |= name == "rom" && attrs.iter().any(|&(key, _)| key == "crc32");
+                    game.set(name, &attrs).map_err(error)?;
+                }
+                (_, None) => (),
+            }
+        }
+        if game.is_some() {
+            return Err(Error::InvalidXml {
+                line: xml.lines().count(),
+                message: "unterminated `<game>`".to_string(),
+            });
+        }
+
+        entries.sort_by_key(|entry| entry.crc32);
+        Ok(Self { entries })
+    }
+
+    /// Number of games in the database.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the database has no games.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the game matching `crc32`, if any. If `sha1` is provided, games with a different
+    /// SHA-1 are skipped, resolving CRC32 collisions.
+    pub fn lookup(&self, crc32: u32, sha1: Option<&[u8; 20]>) -> Option<&CartDbEntry> {
+        let start = self.entries.partition_point(|entry| entry.crc32 < crc32);
+        self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.crc32 == crc32)
+            .find(|entry| match (sha1, &entry.sha1) {
+                (Some(sha1), Some(entry_sha1)) => sha1 == entry_sha1,
+                _ => true,
+            })
+    }
+}
+
+/// Returns the active database used when loading ROMs, if one is installed.
+#[must_use]
+pub fn active() -> Option<Arc<CartDb>> {
+    ACTIVE.read().clone()
+}
+
+/// Install the database used when loading ROMs. Takes effect the next time a ROM is loaded.
+pub fn install(database: CartDb) {
+    info!("installed cart database with {} games", database.len());
+    *ACTIVE.write() = Some(Arc::new(database));
+}
+
+/// Remove the installed database.
+pub fn clear() {
+    *ACTIVE.write() = None;
+}
+
+/// Look up ROM `data` with CRC32 `crc32`, excluding the header, in the active database. SHA-1 is
+/// only computed if the CRC32 matches.
+pub(crate) fn lookup(crc32: u32, data: &[u8]) -> Option<CartDbEntry> {
+    let database = active()?;
+    database.lookup(crc32, None)?;
+    database
+        .lookup(crc32, Some(&fs::compute_sha1(data)))
+        .cloned()
+}
+
+/// Parse `key="value"` pairs, quoted with either `"` or `'`.
+fn parse_attributes(mut attrs: &str) -> core::result::Result<Vec<(&str, &str)>, String> {
+    let mut parsed = Vec::new();
+    attrs = attrs.trim();
+    while !attrs.is_empty() {
+        let (key, rest) = attrs
+            .split_once('=')
+            .ok_or_else(|| format!("invalid attribute `{attrs}`"))?;
+        let rest = rest.trim_start();
+        let quote = rest
+            .chars()
+            .next()
+            .filter(|c| matches!(c, '"' | '\''))
+            .ok_or_else(|| format!("unquoted attribute `{}`", key.trim()))?;
+        let (value, rest) = rest[1..]
+            .split_once(quote)
+            .ok_or_else(|| format!("unterminated attribute `{}`", key.trim()))?;
+        parsed.push((key.trim(), value));
+        attrs = rest.trim_start();
+    }
+    Ok(parsed)
+}
+
+fn parse_num<T: core::str::FromStr>(key: &str, value: &str) -> core::result::Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid {key} `{value}`"))
+}
+
+fn parse_sha1(value: &str) -> core::result::Result<[u8; 20], String> {
+    let invalid = || format!("invalid sha1 `{value}`");
+    if value.len() != 40 || !value.is_ascii() {
+        return Err(invalid());
+    }
+    let mut sha1 = [0x00; 20];
+    for (byte, hex) in sha1.iter_mut().zip(value.as_bytes().chunks_exact(2)) {
+        let hex = core::str::from_utf8(hex).map_err(|_| invalid())?;
+        *byte = u8::from_str_radix(hex, 16).map_err(|_| invalid())?;
+    }
+    Ok(sha1)
+}
+
+/// Parse a title from a comment, which names the dumped file, e.g. `Headered\Game (USA).nes`.
+fn parse_title(comment: &str) -> Option<String> {
+    let title = comment.trim();
+    let title = title.rsplit(['\\', '/']).next().unwrap_or(title);
+    let title = title.strip_suffix(".nes").unwrap_or(title).trim();
+    (!title.is_empty()).then(|| title.to_string())
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<nes20db date="2024-01-01">
+<game>
+  <!-- Headered ROMs\Game (USA).nes -->
+  <prgrom size="131072" crc32="11111111" sha1="0000000000000000000000000000000000000000"/>
+  <rom size="262144" crc32="1A2B3C4D" sha1="A9993E364706816ABA3E25717850C26C9CD0D89D"/>
+  <pcb mapper="4" submapper="1" mirroring="V" battery="1"/>
+  <prgnvram size="8192"/>
+  <chrram size="8192"/>
+  <console type="0" region="1"/>
+  <expansion type="8"/>
+</game>
+<game>
+  <rom size="40960" crc32="0000ABCD"/>
+  <pcb mapper="99" submapper="0" mirroring="4" battery="0"/>
+  <console type="1" region="0"/>
+  <vs hardware="0" ppu="5"/>
+</game>
+</nes20db>
+"#;
+
+    #[test]
+    fn parse_database() {
+        let database = CartDb::parse(XML).expect("valid database");
+        assert_eq!(database.len(), 2);
+
+        let entry = database.lookup(0x1A2B3C4D, None).expect("entry");
+        assert_eq!(entry.title.as_deref(), Some("Game (USA)"));
+        assert_eq!(entry.sha1, Some(fs::compute_sha1(b"abc")));
+        assert_eq!((entry.mapper_num, entry.submapper_num), (4, 1));
+        assert!(entry.battery_backed);
+        assert_eq!((entry.prg_ram_size, entry.prg_nvram_size), (0, 8192));
+        assert_eq!((entry.chr_ram_size, entry.chr_nvram_size), (8192, 0));
+        assert_eq!((entry.tv_mode, entry.expansion_device), (1, 8));
+        assert_eq!(
+            database.lookup(0x1A2B3C4D, Some(&fs::compute_sha1(b"abd"))),
+            None,
+            "sha1 mismatch"
+        );
+        assert_eq!(
+            database
+                .lookup(0x0000ABCD, Some(&fs::compute_sha1(b"abd")))
+                .map(|entry| entry.title.as_deref()),
+            Some(None),
+            "entries without sha1 match any"
+        );
+        let vs_entry = database.lookup(0x0000ABCD, None).expect("entry");
+        assert_eq!((vs_entry.console_type, vs_entry.vs_data), (1, 0x05));
+
+        let mut header = NesHeader::default();
+        entry.apply(&mut header);
+        assert_eq!(header.submapper_num, 1);
+        assert_eq!(header.prg_nvram_size(), 8192);
+        assert_eq!(header.chr_ram_size(), 8192);
+        assert_eq!(header.flags & 0x02, 0x02);
+
+        assert_eq!(
+            CartDb::parse("<game>\n<rom size=\"1\"/>\n</game>"),
+            Err(Error::InvalidXml {
+                line: 3,
+                message: "game is missing a rom crc32".to_string()
+            })
+        );
+        assert!(CartDb::parse("<game><rom crc32=\"XYZ\"/></game>").is_err());
+        assert!(CartDb::parse("<game><rom crc32=1/></game>").is_err());
+        assert!(CartDb::parse("<game>").is_err());
+    }
+}
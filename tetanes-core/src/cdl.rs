@@ -0,0 +1,144 @@
+//! Code/Data Logger (CDL) that records how each byte of PRG-ROM is accessed during execution.
+//!
+//! Logs are compatible with the `.cdl` files produced by FCEUX: one flag byte per PRG-ROM byte,
+//! followed by one flag byte per CHR-ROM byte. CHR-ROM accesses aren't logged, but any CHR-ROM
+//! flags loaded from an existing file are preserved when saved.
+//!
+//! See: <https://fceux.com/web/help/CodeDataLogger.html>
+
+use alloc::{vec, vec::Vec};
+use bitflags::bitflags;
+
+bitflags! {
+    /// How a PRG-ROM byte was accessed.
+    #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+    #[must_use]
+    pub struct CdlFlags: u8 {
+        /// Executed as part of an instruction.
+        const CODE = 0x01;
+        /// Read as data by an instruction.
+        const DATA = 0x02;
+        /// CPU address bank the byte was last accessed from, where `0` is `$8000-$9FFF` through
+        /// `3` for `$E000-$FFFF`.
+        const BANK = 0x0C;
+        /// Executed after an indirect jump, e.g. `JMP ($0200)`.
+        const INDIRECT_CODE = 0x10;
+        /// Read using indirect addressing, e.g. `LDA ($00),Y`.
+        const INDIRECT_DATA = 0x20;
+        /// Read by the APU DMC channel as PCM audio.
+        const PCM = 0x40;
+    }
+}
+
+impl CdlFlags {
+    /// Returns the [`CdlFlags::BANK`] bits for a CPU address.
+    pub const fn bank(addr: u16) -> Self {
+        Self::from_bits_truncate((((addr >> 13) & 0x03) as u8) << 2)
+    }
+
+    /// Whether the byte was only ever accessed as data and never executed.
+    #[must_use]
+    pub const fn is_data_only(&self) -> bool {
+        self.intersects(Self::DATA.union(Self::PCM)) && !self.contains(Self::CODE)
+    }
+}
+
+/// A Code/Data Log of the PRG-ROM and CHR-ROM of a cartridge.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct Cdl {
+    prg: Vec<u8>,
+    chr: Vec<u8>,
+}
+
+/// Number of PRG-ROM bytes logged as each kind of access.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct CdlStats {
+    pub code: usize,
+    pub data: usize,
+    pub unaccessed: usize,
+}
+
+impl Cdl {
+    /// Create an empty log for a cartridge with the given PRG-ROM and CHR-ROM sizes.
+    pub fn new(prg_len: usize, chr_len: usize) -> Self {
+        Self {
+            prg: vec![0x00; prg_len],
+            chr: vec![0x00; chr_len],
+        }
+    }
+
+    /// Create a log from the contents of a `.cdl` file. Returns `None` if `data` doesn't match the
+    /// given PRG-ROM and CHR-ROM sizes.
+    #[must_use]
+    pub fn from_bytes(data: &[u8], prg_len: usize, chr_len: usize) -> Option<Self> {
+        (data.len() == prg_len + chr_len).then(|| {
+            let (prg, chr) = data.split_at(prg_len);
+            Self {
+                prg: prg.to_vec(),
+                chr: chr.to_vec(),
+            }
+        })
+    }
+
+    /// Returns the contents of the `.cdl` file for this log.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.len());
+        data.extend_from_slice(&self.prg);
+        data.extend_from_slice(&self.chr);
+        data
+    }
+
+    /// Size of the log in bytes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.prg.len() + self.chr.len()
+    }
+
+    /// Whether the log has no PRG-ROM or CHR-ROM to track.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Mark the PRG-ROM byte at `offset`, accessed from CPU address `addr`, with `flags`.
+    #[inline]
+    pub fn mark(&mut self, offset: usize, addr: u16, flags: CdlFlags) {
+        if let Some(entry) = self.prg.get_mut(offset) {
+            let bank = CdlFlags::bank(addr).bits();
+            *entry = (*entry & !CdlFlags::BANK.bits()) | bank | flags.bits();
+        }
+    }
+
+    /// Returns the flags logged for the PRG-ROM byte at `offset`.
+    pub fn flags(&self, offset: usize) -> CdlFlags {
+        self.prg.get(offset).map_or(CdlFlags::empty(), |&flags| {
+            CdlFlags::from_bits_retain(flags)
+        })
+    }
+
+    /// Clear all logged accesses.
+    pub fn clear(&mut self) {
+        self.prg.fill(0x00);
+        self.chr.fill(0x00);
+    }
+
+    /// Returns the number of PRG-ROM bytes logged as code, data or not accessed at all. Bytes
+    /// logged as both code and data are counted as code.
+    pub fn stats(&self) -> CdlStats {
+        let mut stats = CdlStats::default();
+        for &flags in &self.prg {
+            let flags = CdlFlags::from_bits_retain(flags);
+            if flags.contains(CdlFlags::CODE) {
+                stats.code += 1;
+            } else if flags.intersects(CdlFlags::DATA | CdlFlags::PCM) {
+                stats.data += 1;
+            } else {
+                stats.unaccessed += 1;
+            }
+        }
+        stats
+    }
+}
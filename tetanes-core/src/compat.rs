@@ -0,0 +1,206 @@
+//! Compatibility sweeps that run ROMs headless for a number of frames and classify how well they
+//! run, e.g. to track regressions across releases.
+//!
+//! Black screens are detected with frame hash heuristics: a ROM that never renders a frame with
+//! more than one color is assumed to have failed to boot.
+
+use crate::{
+    control_deck::{Config, ControlDeck, Error},
+    io::Read,
+    mem::RamState,
+    time::{Duration, Instant},
+};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use serde::{Deserialize, Serialize};
+
+/// How a ROM fared during a compatibility check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub enum Status {
+    /// The ROM ran for every frame and rendered something.
+    Ok,
+    /// The ROM ran for every frame without rendering more than a single color.
+    BlackScreen,
+    /// The CPU crashed, e.g. from an invalid opcode.
+    Crashed(String),
+    /// The ROM uses a mapper that isn't implemented.
+    UnsupportedMapper(u16),
+    /// The ROM failed to load, e.g. from an invalid header.
+    LoadFailed(String),
+}
+
+impl Status {
+    /// Whether the ROM is considered compatible.
+    #[must_use]
+    pub const fn is_ok(&self) -> bool {
+        matches!(self, Self::Ok)
+    }
+
+    /// Short label for the status, suitable for grouping results.
+    #[must_use]
+    pub const fn label(&self) -> &'static str {
+        match self {
+            Self::Ok => "Ok",
+            Self::BlackScreen => "Black Screen",
+            Self::Crashed(_) => "Crashed",
+            Self::UnsupportedMapper(_) => "Unsupported Mapper",
+            Self::LoadFailed(_) => "Load Failed",
+        }
+    }
+}
+
+impl core::fmt::Display for Status {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Ok | Self::BlackScreen => write!(f, "{}", self.label()),
+            Self::Crashed(reason) | Self::LoadFailed(reason) => {
+                write!(f, "{}: {reason}", self.label())
+            }
+            Self::UnsupportedMapper(mapper) => write!(f, "{}: {mapper:03}", self.label()),
+        }
+    }
+}
+
+/// Result of running a single ROM.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub struct RomReport {
+    pub name: String,
+    pub status: Status,
+    /// Mapper board of the ROM, if it loaded.
+    pub mapper: Option<String>,
+    /// CRC32 of the ROM's PRG-ROM and CHR-ROM, if it loaded.
+    pub crc32: Option<u32>,
+    /// Frames clocked before finishing or crashing.
+    pub frames: u32,
+    /// Number of unique frames rendered.
+    pub distinct_frames: u32,
+    /// Hash of the last frame rendered, useful to spot rendering changes between releases.
+    pub frame_hash: u64,
+    /// Wall-clock time spent emulating.
+    pub elapsed: Duration,
+}
+
+impl RomReport {
+    /// A report for `name` that hasn't clocked any frames, e.g. because it failed to load.
+    pub fn new(name: impl ToString, status: Status) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            mapper: None,
+            crc32: None,
+            frames: 0,
+            distinct_frames: 0,
+            frame_hash: 0,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Average emulated frames per second.
+    #[must_use]
+    pub fn fps(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            f64::from(self.frames) / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Hash a frame of palette indices with FNV-1a.
+#[must_use]
+pub fn frame_hash(frame: &[u16]) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    frame.iter().fold(OFFSET, |hash, &pixel| {
+        (hash ^ u64::from(pixel)).wrapping_mul(PRIME)
+    })
+}
+
+/// Load `rom` and clock it for `frames` frames with no input, reporting how it ran.
+///
+/// Battery-backed RAM is never loaded from or saved to disk and random power-up RAM is seeded so
+/// that results are reproducible.
+pub fn check_rom(mut cfg: Config, name: &str, rom: &mut impl Read, frames: u32) -> RomReport {
+    cfg.data_dir = None;
+    if cfg.ram_state == RamState::Random {
+        cfg.ram_state = RamState::Seeded(0);
+    }
+    let mut deck = ControlDeck::with_config(cfg);
+    let loaded_rom = match deck.load_rom(name, rom) {
+        Ok(loaded_rom) => loaded_rom,
+        Err(Error::UnimplementedMapper { mapper }) => {
+            return RomReport::new(name, Status::UnsupportedMapper(mapper));
+        }
+        Err(err) => return RomReport::new(name, Status::LoadFailed(err.to_string())),
+    };
+
+    let mut report = RomReport::new(name, Status::Ok);
+    report.mapper = Some(loaded_rom.header.mapper_board().to_string());
+    report.crc32 = Some(loaded_rom.crc32);
+
+    let mut hashes = Vec::new();
+    let mut rendered = false;
+    let start = Instant::now();
+    for _ in 0..frames {
+        if let Err(err) = deck.clock_frame() {
+            report.status = Status::Crashed(err.to_string());
+            break;
+        }
+        report.frames += 1;
+        let frame = deck.frame_buffer_raw();
+        rendered |= frame.iter().any(|&pixel| pixel != frame[0]);
+        let hash = frame_hash(frame);
+        if let Err(index) = hashes.binary_search(&hash) {
+            hashes.insert(index, hash);
+        }
+        report.frame_hash = hash;
+    }
+    report.elapsed = start.elapsed();
+    report.distinct_frames = hashes.len() as u32;
+    if report.status.is_ok() && !rendered {
+        report.status = Status::BlackScreen;
+    }
+    report
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_roms() {
+        let rom = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_roms/spritecans.nes"
+        ))
+        .expect("valid rom");
+        let report = check_rom(Config::default(), "spritecans", &mut rom.as_slice(), 60);
+        assert_eq!(report.status, Status::Ok);
+        assert_eq!(report.frames, 60);
+        assert!(report.distinct_frames > 1);
+        assert!(report.mapper.is_some());
+        let rerun = check_rom(Config::default(), "spritecans", &mut rom.as_slice(), 60);
+        assert_eq!(
+            RomReport {
+                elapsed: report.elapsed,
+                ..rerun
+            },
+            report,
+            "results are reproducible"
+        );
+
+        let report = check_rom(
+            Config::default(),
+            "invalid",
+            &mut b"not a rom".as_slice(),
+            60,
+        );
+        assert!(matches!(report.status, Status::LoadFailed(_)));
+        assert_eq!(report.frames, 0);
+    }
+}
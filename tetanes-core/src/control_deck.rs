@@ -1,32 +1,50 @@
 //! Control Deck implementation. The primary entry-point for emulating the NES.
 
 use crate::{
-    apu::{Apu, Channel},
+    apu::{driver::SoundInfo, filter::FilterConfig, mixer::Mixer, Apu, Channel},
     bus::Bus,
-    cart::{self, Cart},
-    common::{Clock, NesRegion, Regional, Reset, ResetKind, Sram},
+    cart::{self, Cart, HeaderOverrides, NesHeader},
+    cdl::Cdl,
+    common::{Clock, NesRegion, Nvram, Regional, Reset, ResetKind, Sram},
     cpu::Cpu,
     fs,
-    genie::{self, GenieCode},
-    input::{FourPlayer, Joypad, Player},
-    mapper::{Bf909Revision, Mapper, MapperRevision, Mmc3Revision},
-    mem::RamState,
-    ppu::Ppu,
-    video::{Video, VideoFilter},
+    genie::{self, CheatMismatch, GenieCode},
+    input::{
+        expansion::{Expansion, ExpansionDevice},
+        FourPlayer, Joypad, Player, VausPaddle, VsSystem,
+    },
+    mapper::{
+        m682_rainbow::NetworkHook, registry, Bf909Revision, DipSwitches, Mapped, Mapper,
+        MapperInfo, MapperRegister, MapperRevision, MapperWrite, Mmc3Revision,
+    },
+    mem::{Access, Mem, MemorySpace, RamState},
+    patch::CompatPatch,
+    ppu::{hook::ScanlineHook, Ppu},
+    ram_search::RamSnapshot,
+    snapshot::Snapshot,
+    video::{
+        post_process::{self, PostProcessStage},
+        NtscConfig, Video, VideoFilter,
+    },
 };
 use crate::{io::Read, Path, PathBuf};
 use alloc::{
+    collections::{BTreeMap, BTreeSet},
     format,
     string::{String, ToString},
     vec,
     vec::Vec,
 };
-use bincode::serde::{BorrowCompat, Compat};
 use bitflags::bitflags;
+use core::{ops::Range, time::Duration};
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
+use spin::RwLock;
 use tracing::{error, info};
 
+/// Directory all TetaNES data is stored in when running in portable mode.
+static PORTABLE_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
 /// Result returned from [`ControlDeck`] methods.
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -43,6 +61,9 @@ pub enum Error {
     /// Save state error.
     #[snafu(display("save state error: {source:?}"))]
     SaveState { source: fs::Error },
+    /// Code/Data Log error.
+    #[snafu(display("cdl error: {source:?}"))]
+    Cdl { source: fs::Error },
     /// Operational error indicating a ROM must be loaded first.
     #[snafu(display("no rom is loaded"))]
     RomNotLoaded,
@@ -53,6 +74,14 @@ pub enum Error {
     /// Invalid Game Genie code error.
     #[snafu(display("{source}"))]
     InvalidGenieCode { source: genie::Error },
+    /// Battery-backed RAM provided to [`ControlDeck::load_sram`] doesn't match the size of the
+    /// loaded Cart RAM.
+    #[snafu(display("invalid sram size: expected {expected} bytes, found {found}"))]
+    InvalidSramSize { expected: usize, found: usize },
+    /// Code/Data Log provided to [`ControlDeck::load_cdl`] doesn't match the size of the loaded
+    /// PRG-ROM and CHR-ROM.
+    #[snafu(display("invalid cdl size: expected {expected} bytes, found {found}"))]
+    InvalidCdlSize { expected: usize, found: usize },
     /// Invalid file path.
     #[snafu(display("invalid file path {path:?}"))]
     InvalidFilePath { path: PathBuf },
@@ -120,26 +149,62 @@ pub struct Config {
     pub cycle_accurate: bool,
     /// Video filter.
     pub filter: VideoFilter,
-    /// NES region.
+    /// Ordered post-processing effects applied after the video filter.
+    pub post_process: Vec<PostProcessStage>,
+    /// NTSC video filter parameters.
+    pub ntsc: NtscConfig,
+    /// NES region. [`NesRegion::Auto`] detects the region of each ROM loaded.
     pub region: NesRegion,
+    /// Per-ROM region overrides, keyed by ROM name.
+    pub rom_regions: BTreeMap<String, NesRegion>,
     /// RAM initialization state.
     pub ram_state: RamState,
+    /// Per-ROM RAM initialization state overrides, keyed by ROM name.
+    pub rom_ram_states: BTreeMap<String, RamState>,
+    /// ROM names to apply a known [`CompatPatch`] to when loaded.
+    pub compat_patches: BTreeSet<String>,
+    /// Per-ROM header overrides, keyed by ROM name.
+    pub header_overrides: BTreeMap<String, HeaderOverrides>,
     /// Four player adapter.
     pub four_player: FourPlayer,
     /// Enable zapper gun.
     pub zapper: bool,
+    /// Enable the NES Arkanoid Vaus paddle, in addition to ROMs whose header specifies it.
+    pub vaus: bool,
+    /// Controller port the Super NES Mouse is connected to, if any.
+    pub mouse: Option<Player>,
+    /// Scale applied to host mouse motion for the Super NES Mouse.
+    pub mouse_sensitivity: f32,
+    /// Device connected to the Famicom expansion port, unless the ROM header specifies one.
+    pub expansion_device: ExpansionDevice,
+    /// Per-ROM expansion port device overrides, keyed by ROM name.
+    pub rom_expansion_devices: BTreeMap<String, ExpansionDevice>,
     /// Game Genie codes.
     pub genie_codes: Vec<GenieCode>,
+    /// Whether to apply the Game Genie codes recorded in save states and replays when loaded,
+    /// instead of keeping the currently active codes.
+    pub apply_recorded_cheats: bool,
     /// Whether to support concurrent D-Pad input which wasn't possible on the original NES.
     pub concurrent_dpad: bool,
     /// Apu channels enabled.
     pub channels_enabled: [bool; Apu::MAX_CHANNEL_COUNT],
+    /// Filters applied to audio output, emulating the console's analog output stage by default.
+    pub audio_filters: FilterConfig,
+    /// Per-channel and master audio volumes.
+    pub mixer: Mixer,
     /// Headless mode.
     pub headless_mode: HeadlessMode,
     /// Data directory for storing battery-backed RAM.
     pub data_dir: Option<String>,
+    /// How often to save battery-backed RAM to disk while a ROM is running, in emulated time.
+    /// Only saved if it changed. Battery-backed RAM is always saved when the ROM is unloaded.
+    pub sram_autosave_interval: Option<Duration>,
     /// Which mapper revisions to emulate for any ROM loaded that uses this mapper.
     pub mapper_revisions: MapperRevisionsConfig,
+    /// Per-ROM mapper revision overrides, keyed by ROM name.
+    pub rom_mapper_revisions: BTreeMap<String, MapperRevision>,
+    /// Per-ROM DIP switch settings for boards that have them, keyed by ROM name.
+    pub rom_dip_switches: BTreeMap<String, u8>,
     /// Whether to emulate PPU warmup where writes to certain registers are ignored. Can result in
     /// some games not working correctly.
     ///
@@ -152,14 +217,36 @@ impl Config {
     pub const BASE_DIR: &'static str = "tetanes";
     /// Directory for storing battery-backed Cart RAM.
     pub const SRAM_DIR: &'static str = "sram";
+    /// Directory for storing Code/Data Logs.
+    pub const CDL_DIR: &'static str = "cdl";
+    /// Directory within the portable directory for storing TetaNES data.
+    pub const PORTABLE_DATA_DIR: &'static str = "data";
+
+    /// Store TetaNES data in `dir` instead of the platform data directory, e.g. next to the
+    /// executable when running from a USB drive. Has to be set before creating a [`Config`], since
+    /// [`Config::data_dir`] defaults to it.
+    pub fn set_portable_dir(dir: Option<PathBuf>) {
+        *PORTABLE_DIR.write() = dir;
+    }
+
+    /// Returns the directory all TetaNES data is stored in when running in portable mode.
+    #[must_use]
+    pub fn portable_dir() -> Option<PathBuf> {
+        PORTABLE_DIR.read().clone()
+    }
 
     /// Returns the default directory where TetaNES data is stored.
     #[inline]
     #[must_use]
     pub fn default_data_dir() -> Option<PathBuf> {
+        if let Some(dir) = Self::portable_dir() {
+            return Some(dir.join(Self::PORTABLE_DATA_DIR));
+        }
         #[cfg(target_vendor = "vex")]
         return None;
-        #[cfg(not(target_vendor = "vex"))]
+        #[cfg(target_arch = "wasm32")]
+        return Some(PathBuf::from("/data").join(Self::BASE_DIR));
+        #[cfg(not(any(target_vendor = "vex", target_arch = "wasm32")))]
         return dirs::data_local_dir().map(|dir| dir.join(Self::BASE_DIR));
     }
 
@@ -171,6 +258,15 @@ impl Config {
             .as_ref()
             .map(|dir| PathBuf::from(dir).join(Self::SRAM_DIR))
     }
+
+    /// Returns the directory used to store Code/Data Logs.
+    #[inline]
+    #[must_use]
+    pub fn cdl_dir(&self) -> Option<PathBuf> {
+        self.data_dir
+            .as_ref()
+            .map(|dir| PathBuf::from(dir).join(Self::CDL_DIR))
+    }
 }
 
 impl Default for Config {
@@ -178,16 +274,33 @@ impl Default for Config {
         Self {
             cycle_accurate: true,
             filter: VideoFilter::default(),
+            post_process: post_process::default_chain(),
+            ntsc: NtscConfig::default(),
             region: NesRegion::Auto,
+            rom_regions: BTreeMap::new(),
             ram_state: RamState::Random,
+            rom_ram_states: BTreeMap::new(),
+            compat_patches: BTreeSet::new(),
+            header_overrides: BTreeMap::new(),
             four_player: FourPlayer::default(),
             zapper: false,
+            vaus: false,
+            mouse: None,
+            mouse_sensitivity: 1.0,
+            expansion_device: ExpansionDevice::default(),
+            rom_expansion_devices: BTreeMap::new(),
             genie_codes: vec![],
+            apply_recorded_cheats: false,
             concurrent_dpad: false,
             channels_enabled: [true; Apu::MAX_CHANNEL_COUNT],
+            audio_filters: FilterConfig::default(),
+            mixer: Mixer::new(),
             headless_mode: HeadlessMode::empty(),
             data_dir: Self::default_data_dir().map(|s| s.to_str().unwrap().to_string()),
+            sram_autosave_interval: Some(Duration::from_secs(30)),
             mapper_revisions: MapperRevisionsConfig::default(),
+            rom_mapper_revisions: BTreeMap::new(),
+            rom_dip_switches: BTreeMap::new(),
             emulate_ppu_warmup: false,
         }
     }
@@ -202,6 +315,22 @@ pub struct LoadedRom {
     pub battery_backed: bool,
     /// Auto-detected of the loaded Cart.
     pub region: NesRegion,
+    /// Parsed `iNES` or `NES 2.0` header of the loaded Cart, after any repairs or overrides.
+    pub header: NesHeader,
+    /// Header as stored in the ROM, before any repairs or overrides.
+    pub original_header: NesHeader,
+    /// Whether the header was repaired automatically when loaded.
+    pub header_repaired: bool,
+    /// CRC32 of the loaded Cart's PRG-ROM and CHR-ROM, prior to any patches.
+    pub crc32: u32,
+    /// Known compatibility patch for the loaded Cart, if any.
+    pub compat_patch: Option<CompatPatch>,
+    /// Whether `compat_patch` has been applied.
+    pub compat_patch_applied: bool,
+    /// Detected sound driver and music metadata, if any.
+    pub sound_info: Option<SoundInfo>,
+    /// DIP switches on the loaded Cart's board, if it has any.
+    pub dip_switches: Option<DipSwitches>,
 }
 
 /// Represents an NES Control Deck. Encapsulates the entire emulation state.
@@ -218,12 +347,56 @@ pub struct ControlDeck {
     loaded_rom: Option<LoadedRom>,
     /// Directory for storing battery-backed Cart RAM if a ROM is loaded.
     sram_dir: Option<PathBuf>,
+    /// How often to save battery-backed Cart RAM, in emulated time.
+    sram_autosave_interval: Option<Duration>,
+    /// Frame number battery-backed Cart RAM was last loaded or saved.
+    sram_saved_frame: u32,
+    /// CRC32 of battery-backed Cart RAM when it was last loaded or saved, used to skip saving
+    /// if it hasn't changed.
+    sram_crc32: Option<u32>,
+    /// Directory for storing Code/Data Logs if a ROM is loaded.
+    cdl_dir: Option<PathBuf>,
+    /// Whether to log PRG-ROM accesses to a Code/Data Log for any ROM loaded.
+    cdl_enabled: bool,
     /// Mapper revisions to emulate for any ROM loaded that matches the given mappers.
     mapper_revisions: MapperRevisionsConfig,
-    /// Whether to auto-detect the region based on the loaded Cart.
-    auto_detect_region: bool,
+    /// Per-ROM mapper revision overrides, keyed by ROM name.
+    rom_mapper_revisions: BTreeMap<String, MapperRevision>,
+    /// Per-ROM DIP switch settings, keyed by ROM name.
+    rom_dip_switches: BTreeMap<String, u8>,
+    /// Region used when a ROM has no override, auto-detected from the loaded Cart if
+    /// [`NesRegion::Auto`].
+    region: NesRegion,
+    /// Per-ROM region overrides, keyed by ROM name.
+    rom_regions: BTreeMap<String, NesRegion>,
+    /// RAM initialization state used when a ROM has no override.
+    ram_state: RamState,
+    /// Per-ROM RAM initialization state overrides, keyed by ROM name.
+    rom_ram_states: BTreeMap<String, RamState>,
+    /// Whether the NES Arkanoid Vaus paddle is connected for ROMs whose header doesn't specify
+    /// it.
+    vaus: bool,
+    /// Expansion port device used when a ROM has no override and its header doesn't specify a
+    /// supported device.
+    expansion_device: ExpansionDevice,
+    /// Per-ROM expansion port device overrides, keyed by ROM name.
+    rom_expansion_devices: BTreeMap<String, ExpansionDevice>,
+    /// Whether to apply the Game Genie codes recorded in save states and replays when loaded.
+    apply_recorded_cheats: bool,
+    /// ROM names to apply a known [`CompatPatch`] to when loaded.
+    compat_patches: BTreeSet<String>,
+    /// Per-ROM header overrides, keyed by ROM name.
+    header_overrides: BTreeMap<String, HeaderOverrides>,
+    /// Data of the loaded ROM, kept so it can be reloaded with different header overrides.
+    rom_data: Vec<u8>,
     /// Remaining CPU cycles to execute used to clock a given number of seconds.
     cycles_remaining: f32,
+    /// Snapshot of the current frame restored after clocking run-ahead frames.
+    run_ahead_state: Snapshot,
+    /// Labels of screenshots requested by scripts or achievements since they were last taken.
+    screenshot_requests: Vec<String>,
+    /// Network coprocessor messages are exchanged with for cartridges with Wi-Fi support.
+    network_hook: Option<NetworkHook>,
     /// NES CPU.
     cpu: Cpu,
 }
@@ -254,33 +427,84 @@ impl ControlDeck {
         cpu.bus.input.set_concurrent_dpad(cfg.concurrent_dpad);
         cpu.bus.input.set_four_player(cfg.four_player);
         cpu.bus.input.connect_zapper(cfg.zapper);
+        cpu.bus.input.connect_vaus(cfg.vaus);
+        cpu.bus.input.connect_mouse(cfg.mouse);
+        cpu.bus.input.mouse.sensitivity = cfg.mouse_sensitivity;
+        cpu.bus.input.connect_expansion(cfg.expansion_device);
         for (i, enabled) in cfg.channels_enabled.iter().enumerate() {
             cpu.bus
                 .apu
                 .set_channel_enabled(Channel::try_from(i).expect("valid APU channel"), *enabled);
         }
+        cpu.bus.apu.set_filter_config(cfg.audio_filters);
+        cpu.bus.apu.mixer = cfg.mixer;
         for genie_code in cfg.genie_codes.iter().cloned() {
             cpu.bus.add_genie_code(genie_code);
         }
-        let video = Video::with_filter(cfg.filter);
+        let mut video = Video::with_filter(cfg.filter);
+        video.set_post_process(cfg.post_process.clone());
+        video.set_ntsc_config(cfg.ntsc);
         Self {
             running: false,
             video,
             last_frame_number: 0,
             loaded_rom: None,
             sram_dir: cfg.sram_dir(),
+            sram_autosave_interval: cfg.sram_autosave_interval,
+            sram_saved_frame: 0,
+            sram_crc32: None,
+            cdl_dir: cfg.cdl_dir(),
+            cdl_enabled: false,
             mapper_revisions: cfg.mapper_revisions,
-            auto_detect_region: cfg.region.is_auto(),
+            rom_mapper_revisions: cfg.rom_mapper_revisions,
+            rom_dip_switches: cfg.rom_dip_switches,
+            region: cfg.region,
+            rom_regions: cfg.rom_regions,
+            ram_state: cfg.ram_state,
+            rom_ram_states: cfg.rom_ram_states,
+            vaus: cfg.vaus,
+            expansion_device: cfg.expansion_device,
+            rom_expansion_devices: cfg.rom_expansion_devices,
+            apply_recorded_cheats: cfg.apply_recorded_cheats,
+            compat_patches: cfg.compat_patches,
+            header_overrides: cfg.header_overrides,
+            rom_data: Vec::new(),
             cycles_remaining: 0.0,
+            run_ahead_state: Snapshot::new(),
+            screenshot_requests: Vec::new(),
+            network_hook: None,
             cpu,
         }
     }
 
-    /// Returns the path to the SRAM save file for a given ROM name which is used to store
-    /// battery-backed Cart RAM. Returns `None` when the current platform doesn't have a
-    /// `data` directory and no custom `data_dir` was configured.
-    pub fn sram_dir(&self, name: &str) -> Option<PathBuf> {
-        self.sram_dir.as_ref().map(|dir| dir.join(name))
+    /// Returns the path to the `.sav` file used to store battery-backed Cart RAM for the loaded
+    /// ROM, named by its CRC32. Returns `None` if no ROM is loaded or when the current platform
+    /// doesn't have a `data` directory and no custom `data_dir` was configured.
+    #[must_use]
+    pub fn sram_path(&self) -> Option<PathBuf> {
+        self.nvram_path(Nvram::PRG_RAM)
+    }
+
+    /// Returns the path to the file used to store the battery-backed memory region `key` for the
+    /// loaded ROM. PRG-RAM is stored in [`ControlDeck::sram_path`] and other regions alongside it,
+    /// e.g. `<CRC32>.chr.sav` for CHR-RAM.
+    #[must_use]
+    pub fn nvram_path(&self, key: &str) -> Option<PathBuf> {
+        let rom = self.loaded_rom.as_ref()?;
+        let filename = if key == Nvram::PRG_RAM {
+            format!("{:08X}.sav", rom.crc32)
+        } else {
+            format!("{:08X}.{key}.sav", rom.crc32)
+        };
+        self.sram_dir.as_ref().map(|dir| dir.join(filename))
+    }
+
+    /// Returns the path battery-backed Cart RAM was stored at for a ROM `name` before save files
+    /// were named by CRC32.
+    fn legacy_sram_path(&self, name: &str) -> Option<PathBuf> {
+        self.sram_dir
+            .as_ref()
+            .map(|dir| dir.join(name).with_extension(".sram"))
     }
 
     /// Loads a ROM cartridge into memory
@@ -291,34 +515,150 @@ impl ControlDeck {
     pub fn load_rom<S: ToString, F: Read>(&mut self, name: S, rom: &mut F) -> Result<LoadedRom> {
         let name = name.to_string();
         self.unload_rom()?;
-        let cart = Cart::from_rom(&name, rom, self.cpu.bus.ram_state).context(CartSnafu)?;
+        self.cpu.bus.ram_state = self.rom_ram_state(&name).unwrap_or(self.ram_state);
+        let mut rom_data = Vec::new();
+        rom.read_to_end(&mut rom_data)
+            .map_err(|err| Error::io(err, "failed to read rom"))?;
+        let overrides = self.header_overrides(&name).unwrap_or_default();
+        let mut cart = Cart::load_with_overrides(
+            &name,
+            &mut rom_data.as_slice(),
+            self.cpu.bus.ram_state,
+            &overrides,
+        )
+        .context(CartSnafu)?;
         if cart.mapper.is_none() {
             return UnimplementedMapperSnafu {
                 mapper: cart.mapper_num(),
             }
             .fail();
         }
+        let crc32 = cart.crc32();
+        let compat_patch = CompatPatch::lookup(crc32);
+        let compat_patch_applied = match &compat_patch {
+            Some(patch) if self.compat_patches.contains(&name) => {
+                info!("applying compatibility patch: {}", patch.description);
+                patch.apply(&mut cart.prg_rom, &mut cart.chr_rom);
+                true
+            }
+            _ => false,
+        };
         let loaded_rom = LoadedRom {
             name: name.clone(),
             battery_backed: cart.battery_backed(),
             region: cart.region(),
+            header: *cart.header(),
+            original_header: *cart.original_header(),
+            header_repaired: cart.header_repaired(),
+            crc32,
+            compat_patch,
+            compat_patch_applied,
+            // NSF metadata is in the file header, which isn't part of the player's PRG-ROM
+            sound_info: if cart.is_nsf() {
+                SoundInfo::detect(cart.mapper_num(), &rom_data)
+            } else {
+                SoundInfo::detect(cart.mapper_num(), cart.prg_rom())
+            },
+            dip_switches: cart.dip_switches().map(|dip_switches| {
+                let value = self.rom_dip_switches(&name).unwrap_or_default();
+                DipSwitches::new(dip_switches.count, value)
+            }),
         };
-        if self.auto_detect_region {
-            self.cpu.set_region(loaded_rom.region);
-        }
         self.cpu.bus.load_cart(cart);
+        self.loaded_rom = Some(loaded_rom.clone());
+        self.update_region();
+        self.update_expansion_device();
+        self.update_vaus();
+        self.rom_data = rom_data;
         self.update_mapper_revisions();
+        self.update_dip_switches();
         self.reset(ResetKind::Hard);
         self.running = true;
-        if let Some(dir) = self.sram_dir(&name) {
-            if let Err(err) = self.load_sram(dir) {
-                error!("failed to load SRAM: {err:?}");
+        self.mark_sram_saved();
+        if let Err(err) = self.load_sram_file(&name) {
+            error!("failed to load SRAM: {err:?}");
+        }
+        if self.cdl_enabled {
+            if let Err(err) = self.load_cdl_file() {
+                error!("failed to load CDL: {err:?}");
             }
         }
-        self.loaded_rom = Some(loaded_rom.clone());
         Ok(loaded_rom)
     }
 
+    /// Set whether to apply the known [`CompatPatch`] for the ROM `name` when loaded. If `name` is
+    /// currently loaded, the patch is applied immediately and the console is reset. Disabling an
+    /// applied patch reloads the ROM without it.
+    ///
+    /// # Errors
+    ///
+    /// If reloading the ROM fails, then an error is returned.
+    pub fn set_compat_patch(&mut self, name: &str, enabled: bool) -> Result<()> {
+        let Some(rom) = self.loaded_rom.as_mut().filter(|rom| rom.name == name) else {
+            if enabled {
+                self.compat_patches.insert(name.to_string());
+            } else {
+                self.compat_patches.remove(name);
+            }
+            return Ok(());
+        };
+        if !enabled {
+            self.compat_patches.remove(name);
+            if rom.compat_patch_applied {
+                info!("removing compatibility patch");
+                let rom_data = core::mem::take(&mut self.rom_data);
+                self.load_rom(name, &mut rom_data.as_slice())?;
+            }
+            return Ok(());
+        }
+        self.compat_patches.insert(name.to_string());
+        if let (Some(patch), false) = (&rom.compat_patch, rom.compat_patch_applied) {
+            info!("applying compatibility patch: {}", patch.description);
+            let bus = &mut self.cpu.bus;
+            patch.apply(&mut bus.prg_rom, &mut bus.ppu.bus.chr_rom);
+            rom.compat_patch_applied = true;
+            self.reset(ResetKind::Hard);
+        }
+        Ok(())
+    }
+
+    /// Returns whether the known [`CompatPatch`] for the ROM `name` is applied when loaded.
+    #[must_use]
+    pub fn compat_patch_enabled(&self, name: &str) -> bool {
+        self.compat_patches.contains(name)
+    }
+
+    /// Returns the header overrides for a given ROM name, if any.
+    #[must_use]
+    pub fn header_overrides(&self, name: &str) -> Option<HeaderOverrides> {
+        self.header_overrides.get(name).copied()
+    }
+
+    /// Set or clear the header overrides for a given ROM name. Takes effect the next time the ROM
+    /// is loaded, e.g. by loading [`ControlDeck::rom_data`] again.
+    pub fn set_header_overrides(
+        &mut self,
+        name: impl Into<String>,
+        overrides: Option<HeaderOverrides>,
+    ) {
+        let name = name.into();
+        match overrides.filter(|overrides| !overrides.is_empty()) {
+            Some(overrides) => {
+                self.header_overrides.insert(name, overrides);
+            }
+            None => {
+                self.header_overrides.remove(&name);
+            }
+        }
+    }
+
+    /// Returns the data of the loaded ROM, e.g. to reload it with different header overrides.
+    /// Empty if no ROM is loaded.
+    #[must_use]
+    pub fn rom_data(&self) -> &[u8] {
+        &self.rom_data
+    }
+
     /// Loads a ROM cartridge into memory from a path.
     ///
     /// # Errors
@@ -341,14 +681,17 @@ impl ControlDeck {
     ///
     /// If the loaded [`Cart`] is battery-backed and saving fails, then an error is returned.
     pub fn unload_rom(&mut self) -> Result<()> {
-        if let Some(rom) = &self.loaded_rom {
-            if let Some(dir) = self.sram_dir(&rom.name) {
-                if let Err(err) = self.save_sram(dir) {
-                    error!("failed to save SRAM: {err:?}");
-                }
-            }
+        if let Err(err) = self.save_sram() {
+            error!("failed to save SRAM: {err:?}");
         }
+        if let Err(err) = self.save_cdl() {
+            error!("failed to save CDL: {err:?}");
+        }
+        self.cpu.bus.cdl = None;
+        self.cpu.bus.ram_cheats.clear();
         self.loaded_rom = None;
+        self.rom_data = Vec::new();
+        self.screenshot_requests.clear();
         self.cpu.bus.unload_cart();
         self.running = false;
         Ok(())
@@ -360,6 +703,35 @@ impl ControlDeck {
         self.cpu.load(cpu);
     }
 
+    /// Load a CPU state recorded in a save state or replay. Recorded Game Genie codes replace the
+    /// active codes only if `apply_recorded_cheats` is enabled. Returns a [`CheatMismatch`] if the
+    /// recorded codes differ from those active before loading.
+    pub fn load_recorded_cpu(&mut self, mut cpu: Cpu) -> Option<CheatMismatch> {
+        let mismatch = CheatMismatch::between(
+            cpu.bus.genie_codes.values(),
+            self.cpu.bus.genie_codes.values(),
+        );
+        if !self.apply_recorded_cheats {
+            cpu.bus.genie_codes = self.cpu.bus.genie_codes.clone();
+        }
+        self.load_cpu(cpu);
+        mismatch
+    }
+
+    /// Returns whether Game Genie codes recorded in save states and replays are applied when
+    /// loaded.
+    #[inline]
+    #[must_use]
+    pub const fn apply_recorded_cheats(&self) -> bool {
+        self.apply_recorded_cheats
+    }
+
+    /// Set whether Game Genie codes recorded in save states and replays are applied when loaded.
+    #[inline]
+    pub fn set_apply_recorded_cheats(&mut self, enabled: bool) {
+        self.apply_recorded_cheats = enabled;
+    }
+
     /// Set the [`MapperRevision`] to emulate for the any ROM loaded that uses this mapper.
     #[inline]
     pub fn set_mapper_revision(&mut self, rev: MapperRevision) {
@@ -375,17 +747,115 @@ impl ControlDeck {
         self.update_mapper_revisions();
     }
 
+    /// Returns the mapper revision override for a given ROM name, if any.
+    #[must_use]
+    pub fn rom_mapper_revision(&self, name: &str) -> Option<MapperRevision> {
+        self.rom_mapper_revisions.get(name).copied()
+    }
+
+    /// Set or clear the mapper revision override for a given ROM name, which takes precedence
+    /// over the revision implied by the ROM header or set with
+    /// [`ControlDeck::set_mapper_revision`].
+    pub fn set_rom_mapper_revision(
+        &mut self,
+        name: impl Into<String>,
+        rev: Option<MapperRevision>,
+    ) {
+        let name = name.into();
+        match rev {
+            Some(rev) => {
+                self.rom_mapper_revisions.insert(name, rev);
+            }
+            None => {
+                self.rom_mapper_revisions.remove(&name);
+            }
+        }
+        self.update_mapper_revisions();
+    }
+
+    /// Returns the revision emulated by the loaded ROM mapper, if it has revisions.
+    #[inline]
+    #[must_use]
+    pub const fn mapper_revision(&self) -> Option<MapperRevision> {
+        self.cpu.bus.ppu.bus.mapper.revision()
+    }
+
     /// Internal method to update the loaded ROM mapper revision when `mapper_revisions` is
     /// updated.
     fn update_mapper_revisions(&mut self) {
-        match &mut self.cpu.bus.ppu.bus.mapper {
-            Mapper::Txrom(mapper) => {
-                mapper.set_revision(self.mapper_revisions.mmc3);
+        let mapper = &mut self.cpu.bus.ppu.bus.mapper;
+        mapper.set_revision(MapperRevision::Mmc3(self.mapper_revisions.mmc3));
+        mapper.set_revision(MapperRevision::Bf909(self.mapper_revisions.bf909));
+        // Revisions implied by the NES 2.0 submapper take precedence over configured revisions,
+        // and per-ROM overrides take precedence over both
+        let Some(rom) = self.loaded_rom.as_ref() else {
+            return;
+        };
+        let header_revision = registry::lookup(rom.header.mapper_num, rom.header.submapper_num)
+            .and_then(|board| board.revision);
+        let rom_revision = self.rom_mapper_revisions.get(&rom.name).copied();
+        if let Some(rev) = rom_revision.or(header_revision) {
+            mapper.set_revision(rev);
+        }
+    }
+
+    /// Returns the DIP switches of the loaded ROM's board, or the Vs. System DIP switches, if it
+    /// has any.
+    #[inline]
+    #[must_use]
+    pub const fn dip_switches(&self) -> Option<DipSwitches> {
+        let vs = &self.cpu.bus.input.vs;
+        if vs.enabled {
+            Some(DipSwitches::new(
+                VsSystem::DIP_SWITCH_COUNT,
+                vs.dip_switches,
+            ))
+        } else {
+            self.cpu.bus.ppu.bus.mapper.dip_switches()
+        }
+    }
+
+    /// Returns the DIP switch setting for a given ROM name, if any.
+    #[must_use]
+    pub fn rom_dip_switches(&self, name: &str) -> Option<u8> {
+        self.rom_dip_switches.get(name).copied()
+    }
+
+    /// Set or clear the DIP switch setting for a given ROM name, with switch `n` in bit `n`.
+    /// Applies to the loaded ROM immediately if it matches, though most games only read the
+    /// switches on reset.
+    pub fn set_rom_dip_switches(&mut self, name: impl Into<String>, value: Option<u8>) {
+        let name = name.into();
+        match value {
+            Some(value) => {
+                self.rom_dip_switches.insert(name, value);
             }
-            Mapper::Bf909x(mapper) => {
-                mapper.set_revision(self.mapper_revisions.bf909);
+            None => {
+                self.rom_dip_switches.remove(&name);
             }
-            _ => (),
+        }
+        self.update_dip_switches();
+    }
+
+    /// Internal method to update the loaded ROM DIP switches when `rom_dip_switches` is updated.
+    fn update_dip_switches(&mut self) {
+        let Some(rom) = self.loaded_rom.as_ref() else {
+            return;
+        };
+        let value = self
+            .rom_dip_switches
+            .get(&rom.name)
+            .copied()
+            .unwrap_or_default();
+        let vs = &mut self.cpu.bus.input.vs;
+        if vs.enabled {
+            vs.dip_switches = value;
+        } else {
+            self.cpu.bus.ppu.bus.mapper.set_dip_switches(value);
+        }
+        let dip_switches = self.dip_switches();
+        if let Some(rom) = self.loaded_rom.as_mut() {
+            rom.dip_switches = dip_switches;
         }
     }
 
@@ -402,10 +872,79 @@ impl ControlDeck {
         self.cpu.cycle_accurate = enabled;
     }
 
-    /// Set emulation RAM initialization state.
+    /// Set emulation RAM initialization state. ROMs with a RAM state override are unaffected.
     #[inline]
     pub fn set_ram_state(&mut self, ram_state: RamState) {
-        self.cpu.bus.ram_state = ram_state;
+        self.ram_state = ram_state;
+        let has_override = self
+            .loaded_rom
+            .as_ref()
+            .is_some_and(|rom| self.rom_ram_states.contains_key(&rom.name));
+        if !has_override {
+            self.cpu.bus.ram_state = ram_state;
+        }
+    }
+
+    /// Returns the RAM initialization state override for a given ROM name, if any.
+    #[must_use]
+    pub fn rom_ram_state(&self, name: &str) -> Option<RamState> {
+        self.rom_ram_states.get(name).copied()
+    }
+
+    /// Set or clear the RAM initialization state override for a given ROM name, which takes
+    /// precedence over the RAM state set with [`ControlDeck::set_ram_state`].
+    pub fn set_rom_ram_state(&mut self, name: impl Into<String>, ram_state: Option<RamState>) {
+        let name = name.into();
+        let is_loaded = self.loaded_rom.as_ref().is_some_and(|rom| rom.name == name);
+        match ram_state {
+            Some(ram_state) => {
+                self.rom_ram_states.insert(name, ram_state);
+            }
+            None => {
+                self.rom_ram_states.remove(&name);
+            }
+        }
+        if is_loaded {
+            self.cpu.bus.ram_state = ram_state.unwrap_or(self.ram_state);
+        }
+    }
+
+    /// Returns the region override for a given ROM name, if any.
+    #[must_use]
+    pub fn rom_region(&self, name: &str) -> Option<NesRegion> {
+        self.rom_regions.get(name).copied()
+    }
+
+    /// Set or clear the region override for a given ROM name, which takes precedence over the
+    /// region set with [`Regional::set_region`]. An override of [`NesRegion::Auto`] detects the
+    /// region of the ROM.
+    pub fn set_rom_region(&mut self, name: impl Into<String>, region: Option<NesRegion>) {
+        let name = name.into();
+        match region {
+            Some(region) => {
+                self.rom_regions.insert(name, region);
+            }
+            None => {
+                self.rom_regions.remove(&name);
+            }
+        }
+        self.update_region();
+    }
+
+    /// Emulate the region override for the loaded ROM, if any, otherwise the region set with
+    /// [`Regional::set_region`], detecting the region of the loaded ROM if
+    /// [`NesRegion::Auto`].
+    fn update_region(&mut self) {
+        let region = self
+            .loaded_rom
+            .as_ref()
+            .and_then(|rom| self.rom_region(&rom.name))
+            .unwrap_or(self.region);
+        let region = match region {
+            NesRegion::Auto => self.cart_region().unwrap_or_default(),
+            region => region,
+        };
+        self.cpu.set_region(region);
     }
 
     /// Set the headless mode which can increase performance when the frame and audio outputs are
@@ -461,54 +1000,428 @@ impl ControlDeck {
         self.cpu.bus.sram()
     }
 
-    /// Save battery-backed Save RAM to a file (if cartridge supports it)
+    /// Replace the battery-backed Save RAM (if cartridge supports it), e.g. for frontends that
+    /// manage their own storage.
     ///
     /// # Errors
     ///
-    /// If the file path is invalid or fails to save, then an error is returned.
-    pub fn save_sram(&self, path: impl AsRef<Path>) -> Result<()> {
-        if let Some(true) = self.cart_battery_backed() {
-            let path = path.as_ref();
-            #[cfg(not(target_vendor = "vex"))]
-            {
-                if path.is_dir() {
-                    return Err(Error::InvalidFilePath {
-                        path: path.to_path_buf(),
-                    });
+    /// If `sram` doesn't match the size of the loaded Cart RAM, then an error is returned.
+    pub fn load_sram(&mut self, sram: &[u8]) -> Result<()> {
+        self.load_nvram(Nvram::PRG_RAM, sram)
+    }
+
+    /// Returns all battery-backed memory regions of the loaded Cart: PRG-RAM, CHR-RAM and any
+    /// memory internal to the board. Empty if no ROM is loaded or it isn't battery-backed.
+    pub fn nvram(&self) -> Vec<Nvram<'_>> {
+        let mut nvram = Vec::new();
+        let Some(rom) = self.loaded_rom.as_ref() else {
+            return nvram;
+        };
+        let sram = self.cpu.bus.sram();
+        if rom.battery_backed && !sram.is_empty() {
+            nvram.push(Nvram {
+                key: Nvram::PRG_RAM,
+                data: sram,
+            });
+        }
+        let chr_ram = &self.cpu.bus.ppu.bus.chr_ram;
+        if rom.header.chr_nvram_size() > 0 && !chr_ram.is_empty() {
+            nvram.push(Nvram {
+                key: Nvram::CHR_RAM,
+                data: chr_ram,
+            });
+        }
+        if rom.battery_backed {
+            let mapper = &self.cpu.bus.ppu.bus.mapper;
+            if mapper.flashable() {
+                nvram.push(Nvram {
+                    key: Nvram::PRG_ROM,
+                    data: &self.cpu.bus.prg_rom,
+                });
+            }
+            nvram.extend(mapper.nvram());
+        }
+        nvram
+    }
+
+    /// Replace the battery-backed memory region `key` (if cartridge has it), e.g. for frontends
+    /// that manage their own storage. See [`ControlDeck::nvram`].
+    ///
+    /// # Errors
+    ///
+    /// If `data` doesn't match the size of the region, then an error is returned.
+    pub fn load_nvram(&mut self, key: &str, data: &[u8]) -> Result<()> {
+        let Some(expected) = self
+            .nvram()
+            .iter()
+            .find(|nvram| nvram.key == key)
+            .map(|nvram| nvram.data.len())
+        else {
+            return Ok(());
+        };
+        if data.len() != expected {
+            return InvalidSramSizeSnafu {
+                expected,
+                found: data.len(),
+            }
+            .fail();
+        }
+        match key {
+            Nvram::PRG_RAM => self.cpu.bus.load_sram(data.to_vec()),
+            Nvram::CHR_RAM => self.cpu.bus.ppu.bus.load_chr_ram(data.to_vec()),
+            Nvram::PRG_ROM => self.cpu.bus.prg_rom = data.to_vec(),
+            _ => self.cpu.bus.ppu.bus.mapper.load_nvram(key, data),
+        }
+        self.mark_sram_saved();
+        Ok(())
+    }
+
+    /// Returns the size of a [`MemorySpace`] in bytes.
+    #[must_use]
+    pub fn memory_len(&self, space: MemorySpace) -> usize {
+        let bus = &self.cpu.bus;
+        match space {
+            MemorySpace::Cpu => 0x10000,
+            MemorySpace::Ppu => 0x4000,
+            MemorySpace::Oam => bus.ppu.oamdata.len(),
+            MemorySpace::PrgRam => bus.prg_ram.len(),
+            MemorySpace::PrgRom => bus.prg_rom.len(),
+            MemorySpace::Chr => {
+                if bus.ppu.bus.chr_ram.is_empty() {
+                    bus.ppu.bus.chr_rom.len()
+                } else {
+                    bus.ppu.bus.chr_ram.len()
+                }
+            }
+        }
+    }
+
+    /// Returns the byte at `addr` in a [`MemorySpace`] without side effects, e.g. clearing PPU
+    /// status flags or clocking mapper IRQs, so emulation isn't disturbed. Returns `None` if
+    /// `addr` is out of range.
+    #[must_use]
+    pub fn peek_memory(&self, space: MemorySpace, addr: usize) -> Option<u8> {
+        let bus = &self.cpu.bus;
+        match space {
+            MemorySpace::Cpu => u16::try_from(addr)
+                .ok()
+                .map(|addr| bus.peek(addr, Access::Dummy)),
+            MemorySpace::Ppu => {
+                (addr < 0x4000).then(|| bus.ppu.bus.peek(addr as u16, Access::Dummy))
+            }
+            MemorySpace::Oam => bus.ppu.oamdata.get(addr).copied(),
+            MemorySpace::PrgRam => bus.prg_ram.get(addr).copied(),
+            MemorySpace::PrgRom => bus.prg_rom.get(addr).copied(),
+            MemorySpace::Chr => {
+                if bus.ppu.bus.chr_ram.is_empty() {
+                    bus.ppu.bus.chr_rom.get(addr).copied()
+                } else {
+                    bus.ppu.bus.chr_ram.get(addr).copied()
+                }
+            }
+        }
+    }
+
+    /// Write `val` to `addr` in a [`MemorySpace`] without side effects, e.g. mapper register
+    /// writes, so only the underlying RAM or ROM is changed. ROM and write-protected PRG-RAM can
+    /// be written. Returns `false` if `addr` is out of range or, for the CPU and PPU address
+    /// spaces, isn't mapped to memory, such as I/O registers.
+    pub fn poke_memory(&mut self, space: MemorySpace, addr: usize, val: u8) -> bool {
+        let bus = &mut self.cpu.bus;
+        let mem = match space {
+            MemorySpace::Cpu => {
+                return u16::try_from(addr).is_ok_and(|addr| bus.poke(addr, val));
+            }
+            MemorySpace::Ppu => {
+                return addr < 0x4000 && bus.ppu.bus.poke(addr as u16, val);
+            }
+            MemorySpace::Oam => bus.ppu.oamdata.get_mut(addr),
+            MemorySpace::PrgRam => bus.prg_ram.get_mut(addr),
+            MemorySpace::PrgRom => bus.prg_rom.get_mut(addr),
+            MemorySpace::Chr => {
+                if bus.ppu.bus.chr_ram.is_empty() {
+                    bus.ppu.bus.chr_rom.get_mut(addr)
+                } else {
+                    bus.ppu.bus.chr_ram.get_mut(addr)
                 }
             }
+        };
+        mem.map(|mem| *mem = val).is_some()
+    }
 
-            info!("saving SRAM...");
-            self.cpu.bus.save(path).context(SramSnafu)?;
+    /// Save battery-backed memory to [`ControlDeck::nvram_path`] (if cartridge supports it and
+    /// it changed since it was last loaded or saved). PRG-RAM is saved as a raw `.sav` file,
+    /// compatible with other emulators.
+    ///
+    /// # Errors
+    ///
+    /// If the file path is invalid or fails to save, then an error is returned.
+    pub fn save_sram(&mut self) -> Result<()> {
+        let writes = self.sram_writes()?;
+        for (path, data) in &writes {
+            info!("saving NVRAM to {path:?}...");
+            fs::save_raw(path, data).context(SramSnafu)?;
+        }
+        if !writes.is_empty() {
+            self.mark_sram_saved();
         }
         Ok(())
     }
 
-    /// Load battery-backed Save RAM from a file (if cartridge supports it)
+    /// Returns the paths and contents of battery-backed memory that changed since it was last
+    /// loaded or saved, marking it as saved, e.g. to write it on another thread. See
+    /// [`ControlDeck::save_sram`].
     ///
     /// # Errors
     ///
-    /// If the file path is invalid or fails to load, then an error is returned.
-    pub fn load_sram(&mut self, path: impl AsRef<Path>) -> Result<()> {
-        if let Some(true) = self.cart_battery_backed() {
-            let path = path.as_ref();
+    /// If a file path is invalid, then an error is returned.
+    pub fn take_sram_changes(&mut self) -> Result<Vec<(PathBuf, Vec<u8>)>> {
+        let changes = self
+            .sram_writes()?
+            .into_iter()
+            .map(|(path, data)| (path, data.to_vec()))
+            .collect::<Vec<_>>();
+        if !changes.is_empty() {
+            self.mark_sram_saved();
+        }
+        Ok(changes)
+    }
+
+    /// Paths and contents of battery-backed memory to save, if it changed.
+    fn sram_writes(&self) -> Result<Vec<(PathBuf, &[u8])>> {
+        if !self.sram_changed() {
+            return Ok(Vec::new());
+        }
+        let mut writes = Vec::new();
+        for nvram in self.nvram() {
+            let Some(path) = self.nvram_path(nvram.key) else {
+                return Ok(Vec::new());
+            };
+            #[cfg(not(target_vendor = "vex"))]
+            {
+                if path.is_dir() {
+                    return Err(Error::InvalidFilePath { path });
+                }
+            }
+            writes.push((path, nvram.data));
+        }
+        Ok(writes)
+    }
+
+    /// Load battery-backed memory from [`ControlDeck::nvram_path`] (if cartridge supports it),
+    /// falling back to the Save RAM file used by older versions for the ROM `name`.
+    fn load_sram_file(&mut self, name: &str) -> Result<()> {
+        let keys = self
+            .nvram()
+            .iter()
+            .map(|nvram| nvram.key)
+            .collect::<Vec<_>>();
+        let mut migrate = false;
+        for key in keys {
+            let Some(path) = self.nvram_path(key) else {
+                return Ok(());
+            };
             #[cfg(not(target_vendor = "vex"))]
             {
                 if path.is_dir() {
-                    return Err(Error::InvalidFilePath {
-                        path: path.to_path_buf(),
-                    });
+                    return Err(Error::InvalidFilePath { path });
                 }
                 if !path.is_file() {
-                    return Ok(());
+                    if let Some(legacy_path) = self
+                        .legacy_sram_path(name)
+                        .filter(|legacy_path| key == Nvram::PRG_RAM && legacy_path.is_file())
+                    {
+                        info!("loading SRAM from {legacy_path:?}...");
+                        let sram = fs::load::<Vec<u8>>(legacy_path).context(SramSnafu)?;
+                        self.load_sram(&sram)?;
+                        migrate = true;
+                    }
+                    continue;
                 }
             }
-            info!("loading SRAM...");
-            self.cpu.bus.load(path).context(SramSnafu)?;
+            info!("loading {key} NVRAM...");
+            let data = fs::load_raw(path).context(SramSnafu)?;
+            self.load_nvram(key, &data)?;
+        }
+        #[cfg(target_vendor = "vex")]
+        let _ = name;
+        if migrate {
+            // Migrate to the new save file the next time it's saved
+            self.sram_crc32 = None;
+        }
+        Ok(())
+    }
+
+    /// Returns the path to the `.cdl` file used to store the Code/Data Log for the loaded ROM,
+    /// named by its CRC32. Returns `None` if no ROM is loaded or when the current platform doesn't
+    /// have a `data` directory and no custom `data_dir` was configured.
+    #[must_use]
+    pub fn cdl_path(&self) -> Option<PathBuf> {
+        let rom = self.loaded_rom.as_ref()?;
+        self.cdl_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{:08X}.cdl", rom.crc32)))
+    }
+
+    /// Whether the Code/Data Logger is enabled.
+    #[inline]
+    #[must_use]
+    pub const fn cdl_enabled(&self) -> bool {
+        self.cdl_enabled
+    }
+
+    /// Enable or disable the Code/Data Logger, which marks each byte of PRG-ROM as code or data as
+    /// it's accessed. Logging continues from [`ControlDeck::cdl_path`] if it exists, and the log is
+    /// saved there when disabled or the ROM is unloaded. Stays enabled for any ROM loaded.
+    ///
+    /// # Errors
+    ///
+    /// If an existing log fails to load or the log fails to save, then an error is returned.
+    pub fn set_cdl_enabled(&mut self, enabled: bool) -> Result<()> {
+        if enabled == self.cdl_enabled {
+            return Ok(());
+        }
+        self.cdl_enabled = enabled;
+        if self.loaded_rom.is_none() {
+            return Ok(());
+        }
+        if enabled {
+            self.load_cdl_file()
+        } else {
+            let res = self.save_cdl();
+            self.cpu.bus.cdl = None;
+            res
+        }
+    }
+
+    /// Returns the Code/Data Log for the loaded ROM, if enabled.
+    #[inline]
+    #[must_use]
+    pub const fn cdl(&self) -> Option<&Cdl> {
+        self.cpu.bus.cdl.as_ref()
+    }
+
+    /// Clear all accesses logged in the Code/Data Log.
+    pub fn clear_cdl(&mut self) {
+        if let Some(cdl) = &mut self.cpu.bus.cdl {
+            cdl.clear();
         }
+    }
+
+    /// Replace the Code/Data Log with the contents of a `.cdl` file, enabling it if needed.
+    ///
+    /// # Errors
+    ///
+    /// If no ROM is loaded or `data` doesn't match the size of the loaded PRG-ROM and CHR-ROM,
+    /// then an error is returned.
+    pub fn load_cdl(&mut self, data: &[u8]) -> Result<()> {
+        if self.loaded_rom.is_none() {
+            return Err(Error::RomNotLoaded);
+        }
+        let bus = &self.cpu.bus;
+        let (prg_len, chr_len) = (bus.prg_rom.len(), bus.ppu.bus.chr_rom.len());
+        let Some(cdl) = Cdl::from_bytes(data, prg_len, chr_len) else {
+            return InvalidCdlSizeSnafu {
+                expected: prg_len + chr_len,
+                found: data.len(),
+            }
+            .fail();
+        };
+        self.cdl_enabled = true;
+        self.cpu.bus.cdl = Some(cdl);
         Ok(())
     }
 
+    /// Save the Code/Data Log to [`ControlDeck::cdl_path`], if enabled.
+    ///
+    /// # Errors
+    ///
+    /// If the file path is invalid or fails to save, then an error is returned.
+    pub fn save_cdl(&self) -> Result<()> {
+        let (Some(cdl), Some(path)) = (self.cdl(), self.cdl_path()) else {
+            return Ok(());
+        };
+        #[cfg(not(target_vendor = "vex"))]
+        {
+            if path.is_dir() {
+                return Err(Error::InvalidFilePath { path });
+            }
+        }
+        info!("saving CDL...");
+        fs::save_raw(path, &cdl.to_bytes()).context(CdlSnafu)
+    }
+
+    /// Start a Code/Data Log for the loaded ROM, continuing from [`ControlDeck::cdl_path`] if it
+    /// exists.
+    fn load_cdl_file(&mut self) -> Result<()> {
+        let bus = &self.cpu.bus;
+        self.cpu.bus.cdl = Some(Cdl::new(bus.prg_rom.len(), bus.ppu.bus.chr_rom.len()));
+        match self.cdl_path() {
+            Some(path) if fs::exists(&path) => {
+                info!("loading CDL from {path:?}...");
+                let data = fs::load_raw(path).context(CdlSnafu)?;
+                self.load_cdl(&data)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the loaded Cart has any battery-backed memory, without collecting the regions.
+    fn has_nvram(&self) -> bool {
+        self.loaded_rom
+            .as_ref()
+            .is_some_and(|rom| rom.battery_backed || rom.header.chr_nvram_size() > 0)
+    }
+
+    /// Whether battery-backed memory changed since it was last loaded or saved.
+    fn sram_changed(&self) -> bool {
+        self.sram_crc32 != Some(self.nvram_crc32())
+    }
+
+    fn mark_sram_saved(&mut self) {
+        self.sram_crc32 = Some(self.nvram_crc32());
+        self.sram_saved_frame = self.frame_number();
+    }
+
+    /// Combined CRC32 of all battery-backed memory regions.
+    fn nvram_crc32(&self) -> u32 {
+        self.nvram().iter().fold(0, |crc32, nvram| {
+            fs::compute_combine_crc32(crc32, nvram.data)
+        })
+    }
+
+    /// Set how often to save battery-backed Save RAM while a ROM is running, in emulated time.
+    /// `None` disables autosaving, only saving when the ROM is unloaded.
+    #[inline]
+    pub fn set_sram_autosave_interval(&mut self, interval: Option<Duration>) {
+        self.sram_autosave_interval = interval;
+    }
+
+    /// Save battery-backed Save RAM if the autosave interval has elapsed since it was last saved.
+    fn autosave_sram(&mut self) {
+        let Some(interval) = self.sram_autosave_interval else {
+            return;
+        };
+        // Don't persist RAM from frames that run-ahead discards
+        if !self.run_ahead_state.is_empty() || !self.has_nvram() {
+            return;
+        }
+        let frame = self.frame_number();
+        if frame < self.sram_saved_frame {
+            // Frame number was reset or an earlier state was loaded
+            self.sram_saved_frame = frame;
+            return;
+        }
+        let elapsed =
+            (frame - self.sram_saved_frame) as f32 / Ppu::region_frame_rate(self.region());
+        if elapsed >= interval.as_secs_f32() {
+            if let Err(err) = self.save_sram() {
+                error!("failed to autosave SRAM: {err:?}");
+            }
+            self.sram_saved_frame = frame;
+        }
+    }
+
     /// Save the current state of the console into a save file.
     ///
     /// # Errors
@@ -522,52 +1435,118 @@ impl ControlDeck {
         fs::save(path, &self.cpu).context(SaveStateSnafu)
     }
 
-    /// Load the console with data saved from a save state, if it exists.
+    /// Load the console with data saved from a save state, if it exists. Returns a
+    /// [`CheatMismatch`] if the Game Genie codes recorded in the save state differ from those
+    /// currently active.
     ///
     /// # Errors
     ///
     /// If there is an issue loading the save state, then an error is returned.
-    pub fn load_state(&mut self, path: impl AsRef<Path>) -> Result<()> {
+    pub fn load_state(&mut self, path: impl AsRef<Path>) -> Result<Option<CheatMismatch>> {
         if self.loaded_rom().is_none() {
             return Err(Error::RomNotLoaded);
         };
         let path = path.as_ref();
         #[cfg(not(target_vendor = "vex"))]
         {
-            if !path.exists() {
-                return Ok(());
+            if !fs::exists(path) {
+                return Ok(None);
             }
         }
         fs::load::<Cpu>(path)
             .context(SaveStateSnafu)
             .map(|mut cpu| {
                 cpu.bus.input.clear();
-                self.load_cpu(cpu)
+                self.load_recorded_cpu(cpu)
             })
     }
 
-    /// Load the raw underlying frame buffer from the PPU for further processing.
-    pub fn frame_buffer_raw(&mut self) -> &[u16] {
-        self.cpu.bus.ppu.frame_buffer()
-    }
+    /// Serialize the current state of the console, in the same format as
+    /// [`ControlDeck::save_state`].
+    ///
+    /// # Errors
+    ///
+    /// If there is an issue serializing the state, then an error is returned.
+    pub fn save_state_bytes(&self) -> Result<Vec<u8>> {
+        if self.loaded_rom().is_none() {
+            return Err(Error::RomNotLoaded);
+        };
+        fs::save_bytes(&self.cpu).context(SaveStateSnafu)
+    }
+
+    /// Load the console with state serialized by [`ControlDeck::save_state_bytes`]. Returns a
+    /// [`CheatMismatch`] if the Game Genie codes recorded in the state differ from those currently
+    /// active.
+    ///
+    /// # Errors
+    ///
+    /// If there is an issue deserializing the state, then an error is returned.
+    pub fn load_state_bytes(&mut self, bytes: &[u8]) -> Result<Option<CheatMismatch>> {
+        if self.loaded_rom().is_none() {
+            return Err(Error::RomNotLoaded);
+        };
+        fs::load_bytes::<Cpu>(bytes)
+            .context(SaveStateSnafu)
+            .map(|mut cpu| {
+                cpu.bus.input.clear();
+                self.load_recorded_cpu(cpu)
+            })
+    }
+
+    /// Copy the current state of the console into `snapshot` without serializing it, reusing
+    /// the snapshot's buffers. Much faster than [`ControlDeck::save_state_bytes`] for taking a
+    /// snapshot every frame, e.g. for run-ahead. The control deck is only borrowed mutably to
+    /// avoid copying ROM data and is left unchanged.
+    pub fn snapshot(&mut self, snapshot: &mut Snapshot) {
+        snapshot.take(&mut self.cpu);
+    }
+
+    /// Restore the console to the state copied into `snapshot` by [`ControlDeck::snapshot`]. The
+    /// same ROM must still be loaded. Returns `false` if `snapshot` is empty.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> bool {
+        snapshot.restore(&mut self.cpu)
+    }
+
+    /// Load the raw underlying frame buffer from the PPU for further processing.
+    pub fn frame_buffer_raw(&mut self) -> &[u16] {
+        self.cpu.bus.ppu.frame_buffer()
+    }
 
     /// Load a frame worth of pixels.
     #[inline]
     pub fn frame_buffer(&mut self) -> &[u8] {
+        self.frame_output().0
+    }
+
+    /// Load a frame worth of pixels along with the audio samples generated since they were last
+    /// cleared.
+    pub fn frame_output(&mut self) -> (&[u8], &[f32]) {
         // Avoid applying filter if the frame number hasn't changed
         let frame_number = self.cpu.bus.ppu.frame_number();
-        if self.last_frame_number == frame_number {
-            return &self.video.frame;
-        }
+        // Also re-apply if the filter changed the output size since the last call
+        let frame = if self.last_frame_number == frame_number
+            && self.video.frame.len() == self.video.filter.frame_len()
+        {
+            &self.video.frame
+        } else {
+            self.last_frame_number = frame_number;
+            self.video
+                .apply_filter(self.cpu.bus.ppu.frame_buffer(), frame_number)
+        };
+        (frame, self.cpu.bus.audio_samples())
+    }
 
-        self.last_frame_number = frame_number;
-        self.video
-            .apply_filter(self.cpu.bus.ppu.frame_buffer(), frame_number)
+    /// Width and height of the frame buffer output by the current [`VideoFilter`].
+    #[inline]
+    #[must_use]
+    pub const fn frame_size(&self) -> (u32, u32) {
+        self.video.filter.frame_size()
     }
 
-    /// Load a frame worth of pixels into the given buffer.
+    /// Load a frame worth of pixels into the given buffer, which must be
+    /// [`VideoFilter::frame_len`] bytes for the current filter.
     #[inline]
-    pub fn frame_buffer_into(&self, buffer: &mut [u8]) {
+    pub fn frame_buffer_into(&mut self, buffer: &mut [u8]) {
         self.video.apply_filter_into(
             self.cpu.bus.ppu.frame_buffer(),
             self.cpu.bus.ppu.frame_number(),
@@ -575,6 +1554,21 @@ impl ControlDeck {
         );
     }
 
+    /// Request a screenshot of the current frame with a `label`, e.g. when a script detects a
+    /// milestone or an achievement is unlocked. Requests are collected by the frontend with
+    /// [`ControlDeck::take_screenshot_requests`] after clocking.
+    pub fn request_screenshot(&mut self, label: impl ToString) {
+        if self.running {
+            self.screenshot_requests.push(label.to_string());
+        }
+    }
+
+    /// Take the labels of all screenshots requested since the last call.
+    #[must_use]
+    pub fn take_screenshot_requests(&mut self) -> Vec<String> {
+        core::mem::take(&mut self.screenshot_requests)
+    }
+
     /// Get the current frame number.
     #[inline]
     #[must_use]
@@ -595,6 +1589,123 @@ impl ControlDeck {
         self.cpu.bus.clear_audio_samples();
     }
 
+    /// Get audio samples for the last clocked frame at the nominal sample rate, if capturing is
+    /// enabled.
+    ///
+    /// Unlike [`ControlDeck::audio_samples`], these aren't affected by
+    /// [`ControlDeck::set_sample_rate_adjustment`], so they're suitable for recording while dynamic
+    /// rate control adjusts the audio sent to the speakers.
+    #[inline]
+    #[must_use]
+    pub fn record_samples(&self) -> &[f32] {
+        self.cpu
+            .bus
+            .apu
+            .record_samples
+            .as_deref()
+            .unwrap_or_default()
+    }
+
+    /// Enable or disable capturing nominal rate audio samples for recording.
+    #[inline]
+    pub fn set_capture_record_samples(&mut self, enabled: bool) {
+        self.cpu.bus.apu.set_capture_record_samples(enabled);
+    }
+
+    /// Get per-channel output levels for the last clocked frame, if capturing is enabled.
+    ///
+    /// Each entry contains an output level for every [`Channel`], indexed by `Channel as usize`,
+    /// and lines up with the corresponding entry in [`ControlDeck::record_samples`].
+    #[inline]
+    #[must_use]
+    pub fn channel_samples(&self) -> &[[f32; Apu::MAX_CHANNEL_COUNT]] {
+        self.cpu
+            .bus
+            .apu
+            .channel_samples
+            .as_deref()
+            .unwrap_or_default()
+    }
+
+    /// Enable or disable capturing per-channel output levels for visualization.
+    #[inline]
+    pub fn set_capture_channel_samples(&mut self, enabled: bool) {
+        self.cpu.bus.apu.set_capture_channel_samples(enabled);
+    }
+
+    /// Set a hook to modify the palette used to render each visible scanline, or `None` to remove
+    /// it. See [`ScanlineHook`].
+    #[inline]
+    pub fn set_scanline_hook(&mut self, hook: Option<ScanlineHook>) {
+        self.cpu.bus.ppu.set_scanline_hook(hook);
+    }
+
+    /// Set the network coprocessor for cartridges with Wi-Fi support, such as `Rainbow`, or `None`
+    /// to remove it. Messages are exchanged once per frame and any sent without a coprocessor
+    /// are dropped. See [`NetworkHook`].
+    #[inline]
+    pub fn set_network_hook(&mut self, hook: Option<NetworkHook>) {
+        self.network_hook = hook;
+    }
+
+    /// Exchange messages between the cartridge and its network coprocessor, if supported.
+    pub(crate) fn exchange_network_messages(&mut self) {
+        // Don't send messages from frames that run-ahead discards
+        if !self.run_ahead_state.is_empty() {
+            return;
+        }
+        if let Mapper::Rainbow(rainbow) = &mut self.cpu.bus.ppu.bus.mapper {
+            match &self.network_hook {
+                Some(hook) => hook.exchange(rainbow),
+                None => while rainbow.take_message().is_some() {},
+            }
+        }
+    }
+
+    /// Returns the `NSF` track being played, starting at `0`, if an `NSF` is loaded.
+    #[must_use]
+    pub const fn nsf_track(&self) -> Option<u8> {
+        match &self.cpu.bus.ppu.bus.mapper {
+            Mapper::Nsf(nsf) => Some(nsf.track),
+            _ => None,
+        }
+    }
+
+    /// Play `NSF` track `track` from the start, starting at `0`. Ignored unless an `NSF` is
+    /// loaded.
+    pub fn set_nsf_track(&mut self, track: u8) {
+        if let Mapper::Nsf(nsf) = &mut self.cpu.bus.ppu.bus.mapper {
+            nsf.set_track(track);
+            self.reset(ResetKind::Soft);
+        }
+    }
+
+    /// Returns how long the current `NSF` track has been playing, if an `NSF` is loaded.
+    #[must_use]
+    pub fn nsf_elapsed(&self) -> Option<Duration> {
+        match &self.cpu.bus.ppu.bus.mapper {
+            Mapper::Nsf(nsf) => Some(nsf.elapsed()),
+            _ => None,
+        }
+    }
+
+    /// Move on to the next `NSF` track once the current one has finished fading out, if its
+    /// length is known.
+    fn advance_nsf_track(&mut self) {
+        let Mapper::Nsf(nsf) = &self.cpu.bus.ppu.bus.mapper else {
+            return;
+        };
+        let length = self
+            .loaded_rom
+            .as_ref()
+            .and_then(|rom| rom.sound_info.as_ref())
+            .and_then(|info| info.track_length(nsf.track.into()));
+        if length.is_some_and(|length| nsf.elapsed() >= length) {
+            let next_track = nsf.track.wrapping_add(1);
+            self.set_nsf_track(next_track);
+        }
+    }
+
     /// CPU clock rate based on currently configured NES region.
     #[inline]
     #[must_use]
@@ -644,12 +1755,22 @@ impl ControlDeck {
         #[cfg(feature = "profiling")]
         puffin::profile_function!();
 
+        if let Some(record_samples) = &mut self.cpu.bus.apu.record_samples {
+            record_samples.clear();
+        }
+        if let Some(channel_samples) = &mut self.cpu.bus.apu.channel_samples {
+            channel_samples.clear();
+        }
+
         let mut total_cycles = 0;
         let frame = self.frame_number();
         while frame == self.frame_number() {
             total_cycles += self.clock_instr()?;
         }
         self.cpu.bus.apu.clock_flush();
+        self.autosave_sram();
+        self.exchange_network_messages();
+        self.advance_nsf_track();
 
         Ok(total_cycles)
     }
@@ -675,6 +1796,30 @@ impl ControlDeck {
         Ok(res)
     }
 
+    /// Steps the control deck an entire frame, calling `handle_output` with the `cycles`,
+    /// unfiltered `frame_buffer`, `frame_number` and `audio_samples` for that frame. Unlike
+    /// [`ControlDeck::clock_frame_output`], the [`VideoFilter`] isn't applied, so it can be applied
+    /// elsewhere, e.g. on another thread with a copy of [`ControlDeck::video`].
+    ///
+    /// # Errors
+    ///
+    /// If CPU encounters an invalid opcode, then an error is returned.
+    pub fn clock_frame_output_raw<T>(
+        &mut self,
+        handle_output: impl FnOnce(usize, &[u16], u32, &[f32]) -> T,
+    ) -> Result<T> {
+        let cycles = self.clock_frame()?;
+        let ppu = &self.cpu.bus.ppu;
+        let res = handle_output(
+            cycles,
+            ppu.frame_buffer(),
+            ppu.frame_number(),
+            self.cpu.bus.audio_samples(),
+        );
+        self.cpu.bus.clear_audio_samples();
+        Ok(res)
+    }
+
     /// Steps the control deck an entire frame, copying the `frame_buffer` and
     /// `audio_samples` for that frame into the provided buffers.
     ///
@@ -714,16 +1859,40 @@ impl ControlDeck {
         if run_ahead == 0 {
             return self.clock_frame_output(handle_output);
         }
+        self.clock_run_ahead(run_ahead, |deck| deck.clock_frame_output(handle_output))
+    }
+
+    /// Steps the control deck an entire frame with run-ahead frames to reduce input lag, like
+    /// [`ControlDeck::clock_frame_ahead`], but outputs the unfiltered frame buffer like
+    /// [`ControlDeck::clock_frame_output_raw`].
+    ///
+    /// # Errors
+    ///
+    /// If CPU encounters an invalid opcode, then an error is returned.
+    pub fn clock_frame_ahead_raw<T>(
+        &mut self,
+        run_ahead: usize,
+        handle_output: impl FnOnce(usize, &[u16], u32, &[f32]) -> T,
+    ) -> Result<T> {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        if run_ahead == 0 {
+            return self.clock_frame_output_raw(handle_output);
+        }
+        self.clock_run_ahead(run_ahead, |deck| deck.clock_frame_output_raw(handle_output))
+    }
 
+    /// Clocks the current frame and `run_ahead - 1` frames past it, then calls `output` to clock
+    /// and output the next frame before restoring back to the current frame.
+    fn clock_run_ahead<T>(
+        &mut self,
+        run_ahead: usize,
+        output: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
         // Clock current frame and save state so we can rewind
         self.clock_frame()?;
-        let frame = core::mem::take(&mut self.cpu.bus.ppu.frame.buffer);
-        // Save state so we can rewind
-        let state = bincode::encode_to_vec(BorrowCompat(&self.cpu), bincode::config::standard())
-            .map_err(|err| fs::Error::SerializationFailed {
-                inner: err.to_string(),
-            })
-            .context(FsSnafu)?;
+        self.run_ahead_state.take(&mut self.cpu);
 
         // Clock additional frames and discard video/audio
         self.cpu.bus.ppu.skip_rendering = true;
@@ -734,19 +1903,11 @@ impl ControlDeck {
 
         // Output the future frame video/audio
         self.clear_audio_samples();
-        let result = self.clock_frame_output(handle_output)?;
+        let result = output(self)?;
 
         // Restore back to current frame
-        let mut state =
-            bincode::decode_from_slice::<Compat<Cpu>, _>(&state, bincode::config::standard())
-                .map_err(|err| fs::Error::DeserializationFailed {
-                    inner: err.to_string(),
-                })
-                .context(FsSnafu)?
-                .0
-                 .0;
-        state.bus.ppu.frame.buffer = frame;
-        self.load_cpu(state);
+        self.run_ahead_state.restore(&mut self.cpu);
+        self.run_ahead_state.clear();
 
         Ok(result)
     }
@@ -771,13 +1932,7 @@ impl ControlDeck {
 
         // Clock current frame and save state so we can rewind
         self.clock_frame()?;
-        let frame = core::mem::take(&mut self.cpu.bus.ppu.frame.buffer);
-        // Save state so we can rewind
-        let state = bincode::encode_to_vec(BorrowCompat(&self.cpu), bincode::config::standard())
-            .map_err(|err| fs::Error::SerializationFailed {
-                inner: err.to_string(),
-            })
-            .context(FsSnafu)?;
+        self.run_ahead_state.take(&mut self.cpu);
 
         // Clock additional frames and discard video/audio
         for _ in 1..run_ahead {
@@ -789,16 +1944,8 @@ impl ControlDeck {
         let cycles = self.clock_frame_into(frame_buffer, audio_samples)?;
 
         // Restore back to current frame
-        let mut state =
-            bincode::decode_from_slice::<Compat<Cpu>, _>(&state, bincode::config::standard())
-                .map_err(|err| fs::Error::DeserializationFailed {
-                    inner: err.to_string(),
-                })
-                .context(FsSnafu)?
-                .0
-                 .0;
-        state.bus.ppu.frame.buffer = frame;
-        self.load_cpu(state);
+        self.run_ahead_state.restore(&mut self.cpu);
+        self.run_ahead_state.clear();
 
         Ok(cycles)
     }
@@ -888,6 +2035,37 @@ impl ControlDeck {
         &mut self.cpu.bus.ppu.bus.mapper
     }
 
+    /// Returns the current [`Mapper`] registers and bank mappings decoded for display, starting
+    /// with nametable mirroring.
+    #[must_use]
+    pub fn mapper_registers(&self) -> Vec<MapperRegister> {
+        let mapper = self.mapper();
+        let mut registers = vec![MapperRegister::new(
+            "Mirroring",
+            format!("{:?}", mapper.mirroring()),
+        )];
+        registers.extend(mapper.registers());
+        registers
+    }
+
+    /// Enable or disable logging writes to the mapper address space for debugging.
+    #[inline]
+    pub fn set_log_mapper_writes(&mut self, enabled: bool) {
+        self.cpu.bus.set_log_mapper_writes(enabled);
+    }
+
+    /// Takes the mapper writes logged since the last call, oldest first, if logging is enabled.
+    /// See [`ControlDeck::set_log_mapper_writes`].
+    #[must_use]
+    pub fn take_mapper_writes(&mut self) -> Vec<MapperWrite> {
+        self.cpu
+            .bus
+            .mapper_writes
+            .as_mut()
+            .map(|writes| writes.drain(..).collect())
+            .unwrap_or_default()
+    }
+
     /// Returns the current four player mode.
     #[inline]
     pub const fn four_player(&self) -> FourPlayer {
@@ -944,12 +2122,189 @@ impl ControlDeck {
         self.cpu.bus.input.zapper.aim(x, y);
     }
 
+    /// Set the [`Zapper`](crate::input::Zapper) light detection radius, in pixels.
+    #[inline]
+    pub fn set_zapper_detection_radius(&mut self, radius: u32) {
+        self.cpu.bus.input.zapper.set_detection_radius(radius);
+    }
+
+    /// Returns whether the NES [`VausPaddle`] is connected to controller port 2.
+    #[inline]
+    pub const fn vaus_connected(&self) -> bool {
+        self.cpu.bus.input.vaus.connected
+    }
+
+    /// Connect the NES [`VausPaddle`] to controller port 2. ROMs whose header specifies it always
+    /// connect it.
+    pub fn connect_vaus(&mut self, connected: bool) {
+        self.vaus = connected;
+        self.update_vaus();
+    }
+
+    /// Connect the NES [`VausPaddle`] if enabled or the loaded ROM header specifies it.
+    fn update_vaus(&mut self) {
+        let connected = self.vaus
+            || self
+                .loaded_rom
+                .as_ref()
+                .is_some_and(|rom| rom.header.expansion_device == VausPaddle::NES_DEVICE);
+        self.cpu.bus.input.connect_vaus(connected);
+    }
+
+    /// Turn any connected [`VausPaddle`], from `0.0` all the way left to `1.0` all the way right.
+    #[inline]
+    pub fn aim_vaus(&mut self, x: f32) {
+        self.cpu.bus.input.aim_vaus(x);
+    }
+
+    /// Press or release the button of any connected [`VausPaddle`].
+    #[inline]
+    pub fn set_vaus_button(&mut self, pressed: bool) {
+        self.cpu.bus.input.set_vaus_button(pressed);
+    }
+
+    /// Insert a coin into Vs. System coin slot `1` or `2`. Ignored unless a Vs. System game is
+    /// loaded.
+    #[inline]
+    pub fn insert_coin(&mut self, slot: u8) {
+        self.cpu.bus.input.vs.insert_coin(slot);
+    }
+
+    /// Press or release the Vs. System service button, which adds a credit.
+    #[inline]
+    pub fn set_vs_service_button(&mut self, pressed: bool) {
+        self.cpu.bus.input.vs.set_service(pressed);
+    }
+
+    /// Returns the controller port the [`Mouse`](crate::input::Mouse) is connected to, if any.
+    #[inline]
+    pub const fn mouse_port(&self) -> Option<Player> {
+        self.cpu.bus.input.mouse.port
+    }
+
+    /// Connect the [`Mouse`](crate::input::Mouse) to the controller port for `player`, replacing
+    /// its [`Joypad`], or disconnect it if `None`.
+    #[inline]
+    pub fn connect_mouse(&mut self, port: Option<Player>) {
+        self.cpu.bus.input.connect_mouse(port);
+    }
+
+    /// Set the scale applied to host mouse motion for the [`Mouse`](crate::input::Mouse).
+    #[inline]
+    pub fn set_mouse_sensitivity(&mut self, sensitivity: f32) {
+        self.cpu.bus.input.mouse.sensitivity = sensitivity;
+    }
+
+    /// Move the [`Mouse`](crate::input::Mouse) by host mouse motion.
+    #[inline]
+    pub fn move_mouse(&mut self, dx: f32, dy: f32) {
+        self.cpu.bus.input.mouse.move_by(dx, dy);
+    }
+
+    /// Set the [`Mouse`](crate::input::Mouse) button states.
+    #[inline]
+    pub fn set_mouse_buttons(&mut self, left: bool, right: bool) {
+        self.cpu.bus.input.mouse.set_buttons(left, right);
+    }
+
+    /// Returns the device connected to the Famicom expansion port.
+    #[inline]
+    pub const fn expansion_device(&self) -> ExpansionDevice {
+        self.cpu.bus.input.expansion.device
+    }
+
+    /// Set the device connected to the Famicom expansion port for ROMs without an override that
+    /// don't specify a supported device in their header.
+    pub fn set_expansion_device(&mut self, device: ExpansionDevice) {
+        self.expansion_device = device;
+        self.update_expansion_device();
+    }
+
+    /// Returns the expansion port device override for a given ROM name, if any.
+    #[must_use]
+    pub fn rom_expansion_device(&self, name: &str) -> Option<ExpansionDevice> {
+        self.rom_expansion_devices.get(name).copied()
+    }
+
+    /// Set or clear the expansion port device override for a given ROM name, which takes
+    /// precedence over the device specified by the ROM header or set with
+    /// [`ControlDeck::set_expansion_device`].
+    pub fn set_rom_expansion_device(
+        &mut self,
+        name: impl Into<String>,
+        device: Option<ExpansionDevice>,
+    ) {
+        let name = name.into();
+        match device {
+            Some(device) => {
+                self.rom_expansion_devices.insert(name, device);
+            }
+            None => {
+                self.rom_expansion_devices.remove(&name);
+            }
+        }
+        self.update_expansion_device();
+    }
+
+    /// Connect the expansion port device override for the loaded ROM, if any, otherwise the
+    /// device specified by its header or set with [`ControlDeck::set_expansion_device`].
+    fn update_expansion_device(&mut self) {
+        let device = self
+            .loaded_rom
+            .as_ref()
+            .and_then(|rom| {
+                self.rom_expansion_device(&rom.name)
+                    .or_else(|| ExpansionDevice::from_header(rom.header.expansion_device))
+            })
+            .unwrap_or(self.expansion_device);
+        if device != self.expansion_device() {
+            self.cpu.bus.input.connect_expansion(device);
+        }
+    }
+
+    /// Returns the Famicom expansion port and the state of its devices.
+    #[inline]
+    pub const fn expansion(&self) -> &Expansion {
+        &self.cpu.bus.input.expansion
+    }
+
+    /// Returns a mutable reference to the Famicom expansion port and the state of its devices.
+    #[inline]
+    pub fn expansion_mut(&mut self) -> &mut Expansion {
+        &mut self.cpu.bus.input.expansion
+    }
+
+    /// Returns the [`Video`] decoder applying the video filter and post-processing to frame
+    /// buffer output.
+    #[inline]
+    pub const fn video(&self) -> &Video {
+        &self.video
+    }
+
+    /// Returns the video filter for frame buffer output.
+    #[inline]
+    pub const fn filter(&self) -> VideoFilter {
+        self.video.filter
+    }
+
     /// Set the video filter for frame buffer output when calling [`ControlDeck::frame_buffer`].
     #[inline]
     pub fn set_filter(&mut self, filter: VideoFilter) {
         self.video.filter = filter;
     }
 
+    /// Set the ordered post-processing chain applied after the video filter.
+    #[inline]
+    pub fn set_post_process(&mut self, stages: Vec<PostProcessStage>) {
+        self.video.set_post_process(stages);
+    }
+
+    /// Set the [`VideoFilter::Ntsc`] filter parameters.
+    #[inline]
+    pub fn set_ntsc_config(&mut self, ntsc: NtscConfig) {
+        self.video.set_ntsc_config(ntsc);
+    }
+
     /// Set the [`Apu`] sample rate.
     #[inline]
     pub fn set_sample_rate(&mut self, sample_rate: f32) {
@@ -962,6 +2317,13 @@ impl ControlDeck {
         self.cpu.bus.apu.set_frame_speed(speed);
     }
 
+    /// Adjust the effective audio sample rate by a small `ratio` close to `1.0` for dynamic rate
+    /// control.
+    #[inline]
+    pub fn set_sample_rate_adjustment(&mut self, ratio: f32) {
+        self.cpu.bus.apu.set_sample_rate_adjustment(ratio);
+    }
+
     /// Add a NES Game Genie code.
     ///
     /// # Errors
@@ -987,6 +2349,53 @@ impl ControlDeck {
         self.cpu.bus.clear_genie_codes();
     }
 
+    /// Returns the active NES Game Genie codes.
+    #[inline]
+    pub fn genie_codes(&self) -> impl Iterator<Item = &GenieCode> {
+        self.cpu.bus.genie_codes.values()
+    }
+
+    /// Freeze the value read from Work RAM or PRG-RAM at CPU address `addr` to `val`, e.g. to
+    /// keep a lives counter from changing. Cleared when the ROM is unloaded.
+    #[inline]
+    pub fn add_ram_cheat(&mut self, addr: u16, val: u8) {
+        self.cpu.bus.add_ram_cheat(addr, val);
+    }
+
+    /// Remove the frozen value at CPU address `addr`.
+    #[inline]
+    pub fn remove_ram_cheat(&mut self, addr: u16) {
+        self.cpu.bus.remove_ram_cheat(addr);
+    }
+
+    /// Returns the active RAM cheats as CPU addresses and frozen values.
+    #[inline]
+    pub fn ram_cheats(&self) -> impl Iterator<Item = (u16, u8)> + '_ {
+        self.cpu
+            .bus
+            .ram_cheats
+            .iter()
+            .map(|(&addr, &val)| (addr, val))
+    }
+
+    /// Returns a copy of CPU Work RAM and PRG-RAM mapped at `$6000-$7FFF`, read without side
+    /// effects. Take snapshots between frames, e.g. after [`ControlDeck::clock_frame`], so values
+    /// are consistent for a [`RamSearch`](crate::ram_search::RamSearch).
+    pub fn ram_snapshot(&self) -> RamSnapshot {
+        let bus = &self.cpu.bus;
+        let peek = |addrs: Range<u16>| {
+            addrs
+                .map(|addr| bus.peek(addr, Access::Dummy))
+                .collect::<Vec<_>>()
+        };
+        let prg_ram = if bus.prg_ram.is_empty() {
+            Vec::new()
+        } else {
+            peek(RamSnapshot::PRG_RAM_START..0x8000)
+        };
+        RamSnapshot::new(self.frame_number(), peek(0x0000..0x0800), prg_ram)
+    }
+
     /// Returns whether a given [`Apu`] [`Channel`] is enabled.
     #[inline]
     #[must_use]
@@ -1000,6 +2409,31 @@ impl ControlDeck {
         self.cpu.bus.apu.set_channel_enabled(channel, enabled);
     }
 
+    /// Set the filters applied to [`Apu`] audio output.
+    #[inline]
+    pub fn set_audio_filters(&mut self, config: FilterConfig) {
+        self.cpu.bus.apu.set_filter_config(config);
+    }
+
+    /// Set the volume of a given [`Apu`] [`Channel`], where `1.0` is its original level.
+    #[inline]
+    pub fn set_apu_channel_volume(&mut self, channel: Channel, volume: f32) {
+        self.cpu.bus.apu.mixer.set_volume(channel, volume);
+    }
+
+    /// Set the volume of [`Apu`] audio output, where `1.0` is its original level.
+    #[inline]
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.cpu.bus.apu.mixer.set_master_volume(volume);
+    }
+
+    /// Set whether to mix [`Apu`] channels with the nonlinear DAC formulas instead of lookup
+    /// tables. See [`Mixer::accurate`].
+    #[inline]
+    pub fn set_accurate_mixing(&mut self, enabled: bool) {
+        self.cpu.bus.apu.mixer.accurate = enabled;
+    }
+
     /// Toggle a given [`Apu`] [`Channel`].
     #[inline]
     pub fn toggle_apu_channel(&mut self, channel: Channel) {
@@ -1029,12 +2463,8 @@ impl Regional for ControlDeck {
 
     /// Set the NES format for the emulation.
     fn set_region(&mut self, region: NesRegion) {
-        self.auto_detect_region = region.is_auto();
-        if self.auto_detect_region {
-            self.cpu.set_region(self.cart_region().unwrap_or_default());
-        } else {
-            self.cpu.set_region(region);
-        }
+        self.region = region;
+        self.update_region();
     }
 }
 
@@ -1047,3 +2477,646 @@ impl Reset for ControlDeck {
         }
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::cdl::CdlFlags;
+
+    #[test]
+    fn sram_autosave() {
+        let dir = std::env::temp_dir().join(format!("tetanes_sram_{}", std::process::id()));
+        let mut deck = ControlDeck::with_config(Config {
+            data_dir: Some(dir.to_string_lossy().into_owned()),
+            sram_autosave_interval: Some(Duration::from_secs(1)),
+            // Random RAM could already hold the value written, leaving nothing to save
+            ram_state: RamState::AllZeros,
+            ..Default::default()
+        });
+        let load_rom = |deck: &mut ControlDeck| {
+            let rom = include_bytes!("../test_roms/spritecans.nes");
+            deck.load_rom("spritecans", &mut rom.as_slice())
+                .expect("loaded rom");
+            // No test ROMs are battery-backed
+            deck.loaded_rom.as_mut().expect("loaded rom").battery_backed = true;
+            deck.load_sram_file("spritecans").expect("loaded sram");
+        };
+        load_rom(&mut deck);
+        let path = deck.sram_path().expect("sram path");
+        assert!(path.ends_with(format!("{:08X}.sav", deck.loaded_rom().unwrap().crc32)));
+        assert!(!deck.sram().is_empty(), "has prg-ram");
+
+        deck.bus_mut().write(0x6000, 0xA5, Access::Dummy);
+        deck.clock_frame().expect("clocked");
+        assert!(!path.exists(), "interval hasn't elapsed");
+        for _ in 0..60 {
+            deck.clock_frame().expect("clocked");
+        }
+        let sram = std::fs::read(&path).expect("autosaved");
+        assert_eq!(sram[0], 0xA5);
+
+        deck.bus_mut().write(0x6000, 0x5A, Access::Dummy);
+        deck.unload_rom().expect("unloaded");
+        assert_eq!(std::fs::read(&path).expect("saved")[0], 0x5A);
+        load_rom(&mut deck);
+        assert_eq!(deck.sram()[0], 0x5A, "loaded on start");
+
+        assert!(matches!(
+            deck.load_sram(&[0x00; 4]),
+            Err(Error::InvalidSramSize { found: 4, .. })
+        ));
+        let sram = vec![0x42; deck.sram().len()];
+        deck.load_sram(&sram).expect("loaded sram");
+        assert_eq!(deck.sram()[0], 0x42);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn chr_nvram() {
+        let dir = std::env::temp_dir().join(format!("tetanes_chr_nvram_{}", std::process::id()));
+        let mut deck = ControlDeck::with_config(Config {
+            data_dir: Some(dir.to_string_lossy().into_owned()),
+            // Random RAM could already hold the value written, leaving nothing to save
+            ram_state: RamState::AllZeros,
+            ..Default::default()
+        });
+        // NES 2.0 NROM with 8KB of battery-backed CHR-RAM and no PRG-RAM
+        let mut rom = b"NES\x1A\x01\x00\x02\x08\x00\x00\x00\x70\x00\x00\x00\x00".to_vec();
+        rom.resize(16 + 0x4000, 0x00);
+        let load_rom = |deck: &mut ControlDeck| {
+            deck.load_rom("chr_nvram", &mut rom.as_slice())
+                .expect("loaded rom");
+        };
+        load_rom(&mut deck);
+        let keys = deck
+            .nvram()
+            .iter()
+            .map(|nvram| nvram.key)
+            .collect::<Vec<_>>();
+        assert_eq!(keys, [Nvram::PRG_RAM, Nvram::CHR_RAM]);
+        let path = deck.nvram_path(Nvram::CHR_RAM).expect("chr nvram path");
+        assert!(path.ends_with(format!("{:08X}.chr.sav", deck.loaded_rom().unwrap().crc32)));
+
+        deck.bus_mut().ppu.bus.chr_ram[0x1000] = 0xA5;
+        deck.unload_rom().expect("unloaded");
+        assert_eq!(std::fs::read(&path).expect("saved").len(), 0x2000);
+        load_rom(&mut deck);
+        assert_eq!(
+            deck.cpu.bus.ppu.bus.chr_ram[0x1000], 0xA5,
+            "loaded on start"
+        );
+
+        assert!(matches!(
+            deck.load_nvram(Nvram::CHR_RAM, &[0x00; 4]),
+            Err(Error::InvalidSramSize { found: 4, .. })
+        ));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn nsf_player() {
+        // INIT at $8000 stores the track in $0200, PLAY at $8004 counts calls in $0201
+        let program = [0x8D, 0x00, 0x02, 0x60, 0xEE, 0x01, 0x02, 0x60];
+        let mut nsf = vec![0x00; 0x80];
+        nsf[..5].copy_from_slice(b"NESM\x1A");
+        nsf[0x05] = 1;
+        nsf[0x06] = 3;
+        nsf[0x07] = 2;
+        nsf[0x08..0x0E].copy_from_slice(&[0x00, 0x80, 0x00, 0x80, 0x04, 0x80]);
+        nsf[0x6E..0x70].copy_from_slice(&16_639u16.to_le_bytes());
+        nsf.extend_from_slice(&program);
+
+        let mut deck = ControlDeck::new();
+        let loaded_rom = deck
+            .load_rom("nsf", &mut nsf.as_slice())
+            .expect("loaded nsf");
+        assert!(loaded_rom.header.is_nsf());
+        let peek = |deck: &ControlDeck, addr| deck.bus().peek(addr, Access::Dummy);
+        for _ in 0..10 {
+            deck.clock_frame().expect("valid frame");
+        }
+        assert_eq!(deck.nsf_track(), Some(1), "starting track");
+        assert_eq!(peek(&deck, 0x0200), 1, "init track");
+        let calls = peek(&deck, 0x0201);
+        for _ in 0..60 {
+            deck.clock_frame().expect("valid frame");
+        }
+        let calls = peek(&deck, 0x0201).wrapping_sub(calls);
+        assert!((59..=61).contains(&calls), "play calls: {calls}");
+
+        deck.set_nsf_track(2);
+        for _ in 0..5 {
+            deck.clock_frame().expect("valid frame");
+        }
+        assert_eq!(peek(&deck, 0x0200), 2, "selected track");
+        assert!(peek(&deck, 0x0201) < 5, "ram cleared");
+
+        // NSFe with a 100ms first track
+        let mut nsfe = b"NSFE".to_vec();
+        let mut chunk = |id: &[u8], data: &[u8]| {
+            nsfe.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            nsfe.extend_from_slice(id);
+            nsfe.extend_from_slice(data);
+        };
+        chunk(
+            b"INFO",
+            &[0x00, 0x80, 0x00, 0x80, 0x04, 0x80, 0x00, 0x00, 2, 0],
+        );
+        chunk(b"DATA", &program);
+        chunk(b"tlbl", b"First\0Second\0");
+        chunk(b"time", &100i32.to_le_bytes());
+        chunk(b"NEND", &[]);
+        let loaded_rom = deck
+            .load_rom("nsfe", &mut nsfe.as_slice())
+            .expect("loaded nsfe");
+        let sound_info = loaded_rom.sound_info.expect("sound info");
+        assert_eq!(sound_info.track_name(1), Some("Second"));
+        for _ in 0..15 {
+            deck.clock_frame().expect("valid frame");
+        }
+        assert_eq!(deck.nsf_track(), Some(1), "advanced track");
+        assert_eq!(peek(&deck, 0x0200), 1, "init next track");
+    }
+
+    #[test]
+    fn mapper_writes() {
+        let mut deck = ControlDeck::new();
+        // UxROM with 64KB of PRG-ROM
+        let mut rom = b"NES\x1A\x04\x00\x20\x00\x00\x00\x00\x00\x00\x00\x00\x00".to_vec();
+        rom.resize(16 + 0x10000, 0x00);
+        deck.load_rom("uxrom", &mut rom.as_slice())
+            .expect("loaded rom");
+
+        deck.bus_mut().write(0x8000, 0x01, Access::Dummy);
+        assert!(deck.take_mapper_writes().is_empty(), "logging disabled");
+
+        deck.set_log_mapper_writes(true);
+        deck.bus_mut().write(0x8000, 0x02, Access::Dummy);
+        let writes = deck.take_mapper_writes();
+        assert_eq!(writes.len(), 1);
+        assert_eq!((writes[0].addr, writes[0].val), (0x8000, 0x02));
+        assert!(deck.take_mapper_writes().is_empty(), "drained");
+
+        let registers = deck.mapper_registers();
+        assert_eq!(registers[0].name, "Mirroring");
+        let bank = |name: &str| {
+            registers
+                .iter()
+                .find(|register| register.name == name)
+                .map(|register| register.value.as_str())
+        };
+        assert_eq!(bank("PRG-ROM $8000-$BFFF"), Some("Bank 2"));
+        assert_eq!(bank("PRG-ROM $C000-$FFFF"), Some("Bank 3"));
+    }
+
+    #[test]
+    fn peek_poke_memory() {
+        let mut deck = ControlDeck::new();
+        // UxROM with 64KB of PRG-ROM and 8KB of CHR-RAM
+        let mut rom = b"NES\x1A\x04\x00\x20\x00\x00\x00\x00\x00\x00\x00\x00\x00".to_vec();
+        rom.resize(16 + 0x10000, 0x00);
+        deck.load_rom("uxrom", &mut rom.as_slice())
+            .expect("loaded rom");
+        deck.bus_mut().write(0x8000, 0x02, Access::Dummy);
+
+        assert_eq!(deck.memory_len(MemorySpace::PrgRom), 0x10000);
+        assert!(
+            deck.poke_memory(MemorySpace::Cpu, 0x0810, 0x42),
+            "wram mirror"
+        );
+        assert_eq!(deck.wram()[0x0010], 0x42);
+        assert_eq!(deck.peek_memory(MemorySpace::Cpu, 0x1810), Some(0x42));
+        assert!(
+            !deck.poke_memory(MemorySpace::Cpu, 0x2000, 0x80),
+            "ppu register"
+        );
+        assert_eq!(deck.peek_memory(MemorySpace::Cpu, 0x10000), None);
+
+        // Mapped to bank 2 without writing to the bank select register
+        assert!(deck.poke_memory(MemorySpace::Cpu, 0x8001, 0x5A));
+        assert_eq!(deck.peek_memory(MemorySpace::PrgRom, 0x8001), Some(0x5A));
+        assert_eq!(deck.peek_memory(MemorySpace::Cpu, 0x8001), Some(0x5A));
+        let registers = deck.mapper_registers();
+        assert!(registers
+            .iter()
+            .any(|register| register.name == "PRG-ROM $8000-$BFFF" && register.value == "Bank 2"));
+
+        assert!(
+            deck.poke_memory(MemorySpace::Ppu, 0x3F10, 0x21),
+            "palette mirror"
+        );
+        assert_eq!(deck.peek_memory(MemorySpace::Ppu, 0x3F00), Some(0x21));
+        assert!(deck.poke_memory(MemorySpace::Ppu, 0x0010, 0xFF), "chr-ram");
+        assert_eq!(deck.peek_memory(MemorySpace::Chr, 0x0010), Some(0xFF));
+        assert!(deck.poke_memory(MemorySpace::Oam, 0x03, 0x80));
+        assert_eq!(deck.peek_memory(MemorySpace::Oam, 0x03), Some(0x80));
+        assert!(!deck.poke_memory(MemorySpace::Oam, 0x100, 0x80));
+    }
+
+    #[test]
+    fn ram_cheats() {
+        let mut deck = ControlDeck::new();
+        let rom = include_bytes!("../test_roms/spritecans.nes");
+        deck.load_rom("spritecans", &mut rom.as_slice())
+            .expect("loaded rom");
+
+        deck.add_ram_cheat(0x0075, 0x09);
+        deck.bus_mut().write(0x0075, 0x02, Access::Dummy);
+        assert_eq!(
+            deck.bus_mut().read(0x0875, Access::Dummy),
+            0x09,
+            "mirrored read"
+        );
+        assert_eq!(deck.wram()[0x0075], 0x02, "writes still update memory");
+
+        deck.clock_frame().expect("clocked");
+        let snapshot = deck.ram_snapshot();
+        assert_eq!(snapshot.frame, deck.frame_number());
+        assert_eq!(snapshot.value(0x0075), Some(0x09));
+        assert_eq!(deck.ram_cheats().collect::<Vec<_>>(), [(0x0075, 0x09)]);
+
+        deck.remove_ram_cheat(0x0075);
+        assert_eq!(deck.ram_snapshot().value(0x0075), Some(deck.wram()[0x0075]));
+        deck.add_ram_cheat(0x0075, 0x09);
+        deck.unload_rom().expect("unloaded rom");
+        assert_eq!(deck.ram_cheats().count(), 0);
+    }
+
+    #[test]
+    fn load_previous_save_versions() {
+        let rom = include_bytes!("../test_roms/spritecans.nes");
+        // Saved after 60 frames by each save format version
+        let states: [(&str, &[u8]); 2] = [
+            (
+                "v1",
+                include_bytes!("../test_roms/save_states/spritecans_v1.state"),
+            ),
+            (
+                "v2",
+                include_bytes!("../test_roms/save_states/spritecans_v2.state"),
+            ),
+        ];
+        for (version, state) in states {
+            let mut deck = ControlDeck::new();
+            deck.load_rom("spritecans", &mut rom.as_slice())
+                .expect("loaded rom");
+            deck.load_state_bytes(state)
+                .unwrap_or_else(|err| panic!("loaded {version} state: {err:?}"));
+            assert_eq!(deck.frame_number(), 60, "{version} frame number");
+
+            deck.clock_frame().expect("clocked");
+            let sum: u64 = deck.frame_buffer().iter().map(|&b| u64::from(b)).sum();
+            assert_eq!(sum, 17_931_689, "{version} frame matches a fresh run");
+        }
+    }
+
+    #[test]
+    fn load_previous_save_versions_mappers() {
+        // Two 32K PRG-ROM banks and `chr_banks` 8K CHR-ROM banks, each marked with their bank
+        // number, running a program that writes `bank_select` to the mapper and loops
+        fn bank_switch_rom(mapper: u8, chr_banks: u8, bank_select: u8) -> Vec<u8> {
+            let mut rom = vec![
+                b'N',
+                b'E',
+                b'S',
+                0x1A,
+                4,
+                chr_banks,
+                (mapper & 0x0F) << 4,
+                mapper & 0xF0,
+            ];
+            rom.resize(16, 0x00);
+            for bank in 0..2 {
+                let mut prg = vec![0xEA; 0x8000];
+                // LDA #bank_select; STA $8010; JMP $8005
+                prg[..8].copy_from_slice(&[0xA9, bank_select, 0x8D, 0x10, 0x80, 0x4C, 0x05, 0x80]);
+                // Avoid bus conflicts
+                prg[0x10] = bank_select;
+                prg[0x20] = bank;
+                prg[0x7FFA..].copy_from_slice(&[0x05, 0x80, 0x00, 0x80, 0x05, 0x80]);
+                rom.extend(prg);
+            }
+            for bank in 0..chr_banks {
+                rom.extend(vec![0x10 + bank; 0x2000]);
+            }
+            rom
+        }
+
+        // Saved after 60 frames by save format version 2, before any mappers were added
+        for (name, rom, state) in [
+            (
+                "bnrom",
+                bank_switch_rom(34, 0, 0x01),
+                &include_bytes!("../test_roms/save_states/bnrom_v2.state")[..],
+            ),
+            (
+                "gxrom",
+                bank_switch_rom(66, 2, 0x11),
+                &include_bytes!("../test_roms/save_states/gxrom_v2.state")[..],
+            ),
+        ] {
+            let mut deck = ControlDeck::new();
+            deck.load_rom(name, &mut rom.as_slice())
+                .expect("loaded rom");
+            deck.load_state_bytes(state)
+                .unwrap_or_else(|err| panic!("loaded {name} state: {err:?}"));
+            assert_eq!(deck.frame_number(), 60, "{name} frame number");
+            match (name, deck.mapper()) {
+                ("bnrom", Mapper::Bnrom(_)) => (),
+                ("gxrom", Mapper::Gxrom(_)) => {
+                    assert_eq!(
+                        deck.peek_memory(MemorySpace::Ppu, 0x0000),
+                        Some(0x11),
+                        "{name} chr bank"
+                    );
+                }
+                (_, mapper) => panic!("{name} loaded as {mapper:?}"),
+            }
+            assert_eq!(
+                deck.peek_memory(MemorySpace::Cpu, 0x8020),
+                Some(1),
+                "{name} prg bank"
+            );
+        }
+    }
+
+    #[test]
+    fn compat_patch_toggle() {
+        let _lock = crate::rom_db::test_lock();
+        let rom = include_bytes!("../test_roms/spritecans.nes");
+        let mut deck = ControlDeck::new();
+        let crc32 = deck
+            .load_rom("spritecans", &mut rom.as_slice())
+            .expect("loaded rom")
+            .crc32;
+        let original = deck.bus().prg_rom[0x10];
+        let database = format!(
+            "{crc32:08X}, NTSC, \"spritecans\", \"Test\", 0010:{:02X}",
+            !original
+        );
+        crate::rom_db::install(
+            crate::rom_db::RomDatabase::new(2, Vec::new(), database, "").expect("valid database"),
+        );
+        deck.load_rom("spritecans", &mut rom.as_slice())
+            .expect("loaded rom");
+        assert_eq!(deck.bus().prg_rom[0x10], original, "disabled by default");
+
+        deck.set_compat_patch("spritecans", true)
+            .expect("applied patch");
+        assert_eq!(deck.bus().prg_rom[0x10], !original, "applied");
+        assert!(deck
+            .loaded_rom()
+            .is_some_and(|rom| rom.compat_patch_applied));
+        deck.set_compat_patch("spritecans", false)
+            .expect("removed patch");
+        assert_eq!(deck.bus().prg_rom[0x10], original, "removed");
+        assert!(deck
+            .loaded_rom()
+            .is_some_and(|rom| !rom.compat_patch_applied));
+        crate::rom_db::reset();
+    }
+
+    #[test]
+    fn rom_mapper_revision_override() {
+        let mut deck = ControlDeck::new();
+        let rom = include_bytes!("../test_roms/mapper/m004_txrom/rev_a.nes");
+        deck.load_rom("rev_a", &mut rom.as_slice())
+            .expect("loaded rom");
+        assert_eq!(
+            deck.mapper_revision(),
+            Some(MapperRevision::Mmc3(Mmc3Revision::BC))
+        );
+
+        let rev = MapperRevision::Mmc3(Mmc3Revision::A);
+        deck.set_rom_mapper_revision("rev_a", Some(rev));
+        assert_eq!(deck.mapper_revision(), Some(rev));
+        deck.set_mapper_revision(MapperRevision::Mmc3(Mmc3Revision::Acc));
+        assert_eq!(
+            deck.mapper_revision(),
+            Some(rev),
+            "override takes precedence"
+        );
+        deck.load_rom("rev_a", &mut rom.as_slice())
+            .expect("loaded rom");
+        assert_eq!(
+            deck.mapper_revision(),
+            Some(rev),
+            "override applies when loaded"
+        );
+
+        deck.set_rom_mapper_revision("rev_a", None);
+        assert_eq!(
+            deck.mapper_revision(),
+            Some(MapperRevision::Mmc3(Mmc3Revision::Acc))
+        );
+        deck.set_mapper_revision(MapperRevision::Bf909(Bf909Revision::Bf9097));
+        assert_eq!(
+            deck.mapper_revision(),
+            Some(MapperRevision::Mmc3(Mmc3Revision::Acc)),
+            "ignores revisions for other mappers"
+        );
+    }
+
+    #[test]
+    fn rom_region_override() {
+        let mut deck = ControlDeck::new();
+        let rom = include_bytes!("../test_roms/spritecans.nes");
+        deck.load_rom("spritecans", &mut rom.as_slice())
+            .expect("loaded rom");
+        let detected = deck.cart_region().expect("loaded rom");
+        assert_eq!(deck.region(), detected);
+
+        deck.set_rom_region("spritecans", Some(NesRegion::Dendy));
+        assert_eq!(deck.region(), NesRegion::Dendy);
+        deck.set_region(NesRegion::Pal);
+        assert_eq!(deck.region(), NesRegion::Dendy, "override takes precedence");
+        deck.set_rom_region("spritecans", None);
+        assert_eq!(deck.region(), NesRegion::Pal);
+
+        deck.set_rom_region("spritecans", Some(NesRegion::Auto));
+        assert_eq!(deck.region(), detected, "auto override detects region");
+        deck.set_rom_region("spritecans", Some(NesRegion::Dendy));
+        deck.load_rom("spritecans", &mut rom.as_slice())
+            .expect("loaded rom");
+        assert_eq!(
+            deck.region(),
+            NesRegion::Dendy,
+            "override applies when loaded"
+        );
+    }
+
+    #[test]
+    fn snapshot_restore() {
+        let mut deck = ControlDeck::new();
+        let rom = include_bytes!("../test_roms/spritecans.nes");
+        deck.load_rom("spritecans", &mut rom.as_slice())
+            .expect("loaded rom");
+        for _ in 0..30 {
+            deck.clock_frame().expect("clocked");
+        }
+
+        let mut snapshot = Snapshot::new();
+        assert!(!deck.restore(&snapshot), "empty snapshot");
+        deck.snapshot(&mut snapshot);
+        assert_eq!(snapshot.frame_number(), Some(30));
+        deck.clear_audio_samples();
+        for _ in 0..10 {
+            deck.clock_frame().expect("clocked");
+        }
+        let expected_frame = deck.frame_buffer().to_vec();
+        let expected_wram = deck.wram().to_vec();
+
+        deck.add_ram_cheat(0x0700, 0x01);
+        assert!(deck.restore(&snapshot));
+        assert_eq!(deck.frame_number(), 30);
+        assert_eq!(deck.ram_cheats().count(), 1, "captures are kept");
+        deck.remove_ram_cheat(0x0700);
+        deck.clear_audio_samples();
+        for _ in 0..10 {
+            deck.clock_frame().expect("clocked");
+        }
+        assert_eq!(deck.frame_buffer(), expected_frame);
+        assert_eq!(deck.wram(), expected_wram);
+
+        // Snapshots match the serialized save state of the same frame
+        deck.snapshot(&mut snapshot);
+        let state = deck.save_state_bytes().expect("saved state");
+        deck.clock_frame().expect("clocked");
+        assert!(deck.restore(&snapshot));
+        assert_eq!(deck.save_state_bytes().expect("saved state"), state);
+    }
+
+    #[test]
+    fn clock_frame_output_raw() {
+        let rom = include_bytes!("../test_roms/spritecans.nes");
+        let load_deck = || {
+            let mut deck = ControlDeck::new();
+            deck.load_rom("spritecans", &mut rom.as_slice())
+                .expect("loaded rom");
+            deck.set_filter(VideoFilter::Ntsc);
+            deck
+        };
+        let mut deck = load_deck();
+        let mut raw_deck = load_deck();
+        let mut video = raw_deck.video().clone();
+        for run_ahead in [0, 0, 1, 2] {
+            let frame = deck
+                .clock_frame_ahead(run_ahead, |_, frame, _| frame.to_vec())
+                .expect("clocked");
+            let raw_frame = raw_deck
+                .clock_frame_ahead_raw(run_ahead, |_, buffer, frame_number, _| {
+                    video.apply_filter(buffer, frame_number).to_vec()
+                })
+                .expect("clocked");
+            assert_eq!(frame, raw_frame, "run ahead {run_ahead}");
+        }
+        assert_eq!(deck.frame_buffer(), raw_deck.frame_buffer());
+    }
+
+    #[test]
+    fn record_samples() {
+        let rom = include_bytes!("../test_roms/spritecans.nes");
+        let mut nominal = ControlDeck::new();
+        nominal
+            .load_rom("spritecans", &mut rom.as_slice())
+            .expect("loaded rom");
+        let mut adjusted = ControlDeck::new();
+        adjusted
+            .load_rom("spritecans", &mut rom.as_slice())
+            .expect("loaded rom");
+        adjusted.set_capture_record_samples(true);
+        adjusted.set_sample_rate_adjustment(1.005);
+
+        let (mut expected, mut recorded, mut played) = (Vec::new(), Vec::new(), 0);
+        for _ in 0..60 {
+            nominal.clock_frame().expect("clocked");
+            expected.extend_from_slice(nominal.audio_samples());
+            nominal.clear_audio_samples();
+
+            adjusted.clock_frame().expect("clocked");
+            recorded.extend_from_slice(adjusted.record_samples());
+            played += adjusted.audio_samples().len();
+            adjusted.clear_audio_samples();
+        }
+        assert!(played > expected.len(), "adjusted output rate");
+        assert_eq!(recorded.len(), expected.len());
+        assert_eq!(recorded, expected);
+    }
+
+    #[test]
+    fn code_data_log() {
+        let dir = std::env::temp_dir().join(format!("tetanes_cdl_{}", std::process::id()));
+        let mut deck = ControlDeck::with_config(Config {
+            data_dir: Some(dir.to_string_lossy().into_owned()),
+            ..Default::default()
+        });
+        // NROM with 16KB of PRG-ROM mirrored at $8000 and $C000
+        let mut rom = b"NES\x1A\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00".to_vec();
+        rom.resize(16 + 0x4000 + 0x2000, 0x00);
+        let prg = &mut rom[16..16 + 0x4000];
+        // $C000: LDA $C010; LDA ($00),Y; JMP $C000
+        prg[..8].copy_from_slice(&[0xAD, 0x10, 0xC0, 0xB1, 0x00, 0x4C, 0x00, 0xC0]);
+        prg[0x3FFC..0x3FFE].copy_from_slice(&[0x00, 0xC0]);
+        let load_rom = |deck: &mut ControlDeck| {
+            deck.load_rom("cdl", &mut rom.as_slice())
+                .expect("loaded rom");
+            // Point ($00),Y at $C020
+            deck.bus_mut().write(0x0000, 0x20, Access::Dummy);
+            deck.bus_mut().write(0x0001, 0xC0, Access::Dummy);
+        };
+
+        load_rom(&mut deck);
+        deck.clock_frame().expect("clocked");
+        assert!(deck.cdl().is_none(), "logging disabled");
+
+        deck.set_cdl_enabled(true).expect("enabled cdl");
+        deck.clock_frame().expect("clocked");
+        let cdl = deck.cdl().expect("cdl enabled");
+        assert_eq!(cdl.len(), 0x4000 + 0x2000);
+        let bank = CdlFlags::bank(0xC000);
+        for offset in 0..8 {
+            assert_eq!(cdl.flags(offset), CdlFlags::CODE | bank, "${offset:02X}");
+        }
+        assert_eq!(cdl.flags(0x08), CdlFlags::empty());
+        assert_eq!(cdl.flags(0x10), CdlFlags::DATA | bank);
+        assert_eq!(
+            cdl.flags(0x20),
+            CdlFlags::DATA | CdlFlags::INDIRECT_DATA | bank
+        );
+        assert_eq!(cdl.stats().code, 8);
+        assert_eq!(cdl.stats().data, 2);
+
+        let mut pc = 0xC010;
+        assert_eq!(
+            deck.cpu_mut().disassemble(&mut pc),
+            "$C010 $00         .db $00"
+        );
+        assert_eq!(pc, 0xC011);
+        let mut pc = 0xC000;
+        assert!(deck.cpu_mut().disassemble(&mut pc).contains("LDA $C010"));
+
+        deck.unload_rom().expect("unloaded");
+        load_rom(&mut deck);
+        let path = deck.cdl_path().expect("cdl path");
+        assert!(path.ends_with(format!("{:08X}.cdl", deck.loaded_rom().unwrap().crc32)));
+        assert_eq!(
+            deck.cdl().expect("cdl enabled").flags(0x10),
+            CdlFlags::DATA | bank,
+            "loaded on start"
+        );
+
+        assert!(matches!(
+            deck.load_cdl(&[0x00; 4]),
+            Err(Error::InvalidCdlSize { found: 4, .. })
+        ));
+        deck.clear_cdl();
+        assert_eq!(deck.cdl().expect("cdl enabled").stats().code, 0);
+
+        deck.set_cdl_enabled(false).expect("disabled cdl");
+        assert!(deck.cdl().is_none());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -4,42 +4,23 @@
 
 use crate::{
     bus::Bus,
+    cdl::CdlFlags,
     common::{Clock, ClockTo, NesRegion, Regional, Reset, ResetKind},
     mem::{Access, Mem},
-    RwLock,
 };
 use alloc::string::String;
 use bitflags::bitflags;
-use core::{
-    cell::Cell,
-    fmt::{self, Write},
-};
+use core::fmt::{self, Write};
 use instr::{
     AddrMode::{ABS, ABX, ABY, ACC, IDX, IDY, IMM, IMP, IND, REL, ZP0, ZPX, ZPY},
     Instr,
-    Operation::{
-        ADC, AHX, ALR, ANC, AND, ARR, ASL, AXS, BCC, BCS, BEQ, BIT, BMI, BNE, BPL, BRK, BVC, BVS,
-        CLC, CLD, CLI, CLV, CMP, CPX, CPY, DCP, DEC, DEX, DEY, EOR, IGN, INC, INX, INY, ISB, JMP,
-        JSR, LAS, LAX, LDA, LDX, LDY, LSR, NOP, ORA, PHA, PHP, PLA, PLP, RLA, ROL, ROR, RRA, RTI,
-        RTS, SAX, SBC, SEC, SED, SEI, SKB, SLO, SRE, STA, STX, STY, SXA, SYA, TAS, TAX, TAY, TSX,
-        TXA, TXS, TYA, XAA, XXX,
-    },
+    Operation::{AHX, JMP, JSR, SAX, STA, STX, STY, SXA, SYA, TAS},
 };
 use serde::{Deserialize, Serialize};
 use tracing::trace;
 
 pub mod instr;
 
-// FIXME: should switch back to thread locals
-// thread_local! {
-static NMI: RwLock<bool> = { RwLock::new(false) };
-static IRQS: RwLock<Irq> = { RwLock::new(Irq::empty()) };
-static DMAS: RwLock<Dma> = { RwLock::new(Dma::empty()) };
-static DMA_HALT: RwLock<bool> = { RwLock::new(false) };
-static DMA_DUMMY_READ: RwLock<bool> = { RwLock::new(false) };
-static DMA_OAM_ADDR: RwLock<u16> = { RwLock::new(0x0000) };
-// }
-
 bitflags! {
     #[derive(Default, Serialize, Deserialize, Debug, Copy, Clone)]
     #[must_use]
@@ -59,6 +40,66 @@ bitflags! {
     }
 }
 
+/// Direct memory access (DMA) requests, started by the APU DMC and writes to $4014 and run by
+/// the CPU.
+///
+/// See: <https://www.nesdev.org/wiki/DMA>
+#[derive(Default, Debug, Copy, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct DmaState {
+    pub dmas: Dma,
+    /// Whether the CPU has yet to halt for a DMA.
+    pub halt: bool,
+    /// Whether a DMC DMA has yet to do its dummy read.
+    pub dummy_read: bool,
+    /// Whether a DMC DMA was stopped after the CPU halted for it, which still takes a cycle.
+    pub abort: bool,
+    pub oam_addr: u16,
+}
+
+impl DmaState {
+    pub fn start_dmc(&mut self) {
+        self.dmas |= Dma::DMC;
+        self.halt = true;
+        self.dummy_read = true;
+    }
+
+    /// Cancel a pending DMC DMA after the DMC is disabled via $4015. If the CPU hasn't halted yet
+    /// the DMA is dropped, otherwise it's aborted after halting the CPU for a cycle.
+    ///
+    /// See: <https://www.nesdev.org/wiki/DMA#DMC_DMA_during_OAM_DMA>
+    pub fn stop_dmc(&mut self) {
+        if !self.dmas.contains(Dma::DMC) {
+            return;
+        }
+        if self.halt {
+            self.dmas &= !Dma::DMC;
+            self.dummy_read = false;
+            if !self.dmas.contains(Dma::OAM) {
+                self.halt = false;
+            }
+        } else {
+            self.abort = true;
+        }
+    }
+
+    pub fn start_oam(&mut self, addr: u16) {
+        self.dmas |= Dma::OAM;
+        self.halt = true;
+        self.oam_addr = addr;
+    }
+
+    /// Returns whether DMC and OAM DMA are running, if any are.
+    #[must_use]
+    pub const fn running(&self) -> Option<(bool, bool)> {
+        if self.dmas.is_empty() {
+            None
+        } else {
+            Some((self.dmas.contains(Dma::DMC), self.dmas.contains(Dma::OAM)))
+        }
+    }
+}
+
 // Status Registers
 // http://wiki.nesdev.com/w/index.php/Status_flags
 // 7654 3210
@@ -190,6 +231,30 @@ impl Cpu {
         if cpu.bus.ppu.bus.chr_rom.is_empty() {
             cpu.bus.ppu.bus.chr_rom = core::mem::take(&mut self.bus.ppu.bus.chr_rom);
         };
+        // Channel and record sample capture are frontend settings and aren't serialized, so keep
+        // them enabled
+        if cpu.bus.apu.channel_samples.is_none() {
+            cpu.bus.apu.channel_samples = self.bus.apu.channel_samples.take();
+        }
+        if cpu.bus.apu.record_samples.is_none() {
+            cpu.bus.apu.record_samples = self.bus.apu.record_samples.take();
+        }
+        if cpu.bus.mapper_writes.is_none() {
+            cpu.bus.mapper_writes = self.bus.mapper_writes.take();
+        }
+        if cpu.bus.cdl.is_none() {
+            cpu.bus.cdl = self.bus.cdl.take();
+        }
+        if cpu.bus.ram_cheats.is_empty() {
+            cpu.bus.ram_cheats = core::mem::take(&mut self.bus.ram_cheats);
+        }
+        if cpu.bus.ppu.scanline_hook.is_none() {
+            cpu.bus.ppu.scanline_hook = self.bus.ppu.scanline_hook.take();
+        }
+        cpu.bus.apu.mixer = self.bus.apu.mixer;
+        if cpu.bus.apu.filter_config != self.bus.apu.filter_config {
+            cpu.bus.apu.set_filter_config(self.bus.apu.filter_config);
+        }
         *self = cpu;
     }
 
@@ -218,113 +283,6 @@ impl Cpu {
         Cpu::INSTRUCTIONS[opcode as usize]
     }
 
-    #[inline]
-    #[must_use]
-    pub fn nmi_pending() -> bool {
-        *NMI.read()
-    }
-
-    #[inline]
-    pub fn set_nmi() {
-        *NMI.write() = true;
-    }
-
-    #[inline]
-    pub fn clear_nmi() {
-        *NMI.write() = false;
-    }
-
-    #[inline]
-    pub fn irqs() -> Irq {
-        // IRQS.get()
-        *IRQS.read()
-    }
-
-    #[inline]
-    #[must_use]
-    pub fn has_irq(irq: Irq) -> bool {
-        // IRQS.get().contains(irq)
-        IRQS.read().contains(irq)
-    }
-
-    #[inline]
-    pub fn set_irq(irq: Irq) {
-        // IRQS.set(IRQS.get() | irq);
-        *IRQS.write() |= irq;
-    }
-
-    #[inline]
-    pub fn clear_irq(irq: Irq) {
-        // IRQS.set(IRQS.get() & !irq);
-        *IRQS.write() &= !irq;
-    }
-
-    #[inline]
-    pub fn start_dmc_dma() {
-        // DMAS.set(DMAS.get() | Dma::DMC);
-        // DMA_HALT.set(true);
-        // DMA_DUMMY_READ.set(true);
-        *DMAS.write() |= Dma::DMC;
-        *DMA_HALT.write() = true;
-        *DMA_DUMMY_READ.write() = true;
-    }
-
-    #[inline]
-    pub fn start_oam_dma(addr: u16) {
-        // DMAS.set(DMAS.get() | Dma::OAM);
-        // DMA_HALT.set(true);
-        // DMA_OAM_ADDR.set(addr);
-        *DMAS.write() |= Dma::OAM;
-        *DMA_HALT.write() = true;
-        *DMA_OAM_ADDR.write() = addr;
-    }
-
-    #[inline]
-    #[must_use]
-    pub fn halt_for_dma() -> bool {
-        // DMA_HALT.get()
-        *DMA_HALT.read()
-    }
-
-    #[inline]
-    pub fn dma_oam_addr() -> u16 {
-        // DMA_OAM_ADDR.get()
-        *DMA_OAM_ADDR.read()
-    }
-
-    #[inline]
-    #[must_use]
-    pub fn dmas_running() -> Option<(bool, bool)> {
-        // let dmas = DMAS.get();
-        // (!dmas.is_empty()).then_some((dmas.contains(Dma::DMC), dmas.contains(Dma::OAM)))
-        let dmas = *DMAS.read();
-        (!dmas.is_empty()).then_some((dmas.contains(Dma::DMC), dmas.contains(Dma::OAM)))
-    }
-
-    #[inline]
-    pub fn clear_dma(dma: Dma) {
-        // DMAS.set(DMAS.get() & !dma);
-        *DMAS.write() &= !dma;
-    }
-
-    #[inline]
-    pub fn clear_dma_halt() {
-        // DMA_HALT.set(false);
-        *DMA_HALT.write() = false;
-    }
-
-    #[inline]
-    pub fn dma_dummy_read() -> bool {
-        // DMA_DUMMY_READ.get()
-        *DMA_DUMMY_READ.read()
-    }
-
-    #[inline]
-    pub fn clear_dma_dummy_read() {
-        // DMA_DUMMY_READ.set(false);
-        *DMA_DUMMY_READ.write() = false;
-    }
-
     /// Process an interrupted request.
     ///
     /// <http://wiki.nesdev.com/w/index.php/IRQ>
@@ -388,14 +346,14 @@ impl Cpu {
         // during the second half of each cycle, hence here in `end_cycle`) and raises an internal
         // signal if the input goes from being high during one cycle to being low during the
         // next.
-        let nmi_pending = Self::nmi_pending();
+        let nmi_pending = self.bus.ppu.nmi_pending;
         self.nmi |= !self.prev_nmi_pending && nmi_pending;
         self.prev_nmi_pending = nmi_pending;
 
         // The IRQ status at the end of the second-to-last cycle is what matters,
         // so keep the second-to-last status.
         self.prev_run_irq = self.run_irq;
-        let irqs = Self::irqs();
+        let irqs = self.bus.irqs();
         self.run_irq = !irqs.is_empty() && !self.status.intersects(Status::I);
         if !self.prev_run_irq && self.run_irq {
             trace!("IRQs: {:?} - CYC:{}", irqs, self.cycle);
@@ -426,57 +384,123 @@ impl Cpu {
 
     /// Start a direct-memory access (DMA) cycle.
     fn start_dma_cycle(&mut self) {
-        // OAM DMA cycles count as halt/dummy reads for DMC DMA when both run at the same time
-        if Self::halt_for_dma() {
-            Self::clear_dma_halt();
+        // An aborted DMC DMA still takes this cycle. OAM DMA cycles count as halt/dummy reads for
+        // DMC DMA when both run at the same time
+        let dma = &mut self.bus.apu.dma;
+        if core::mem::take(&mut dma.abort) {
+            dma.dmas &= !Dma::DMC;
+            dma.dummy_read = false;
+        } else if dma.halt {
+            dma.halt = false;
         } else {
-            Self::clear_dma_dummy_read();
+            dma.dummy_read = false;
         }
         self.start_cycle(self.read_cycles.start);
     }
 
+    /// Read a byte for a DMA while the CPU is halted on a read of `halt_addr`.
+    ///
+    /// When halted on a read of an APU or I/O register ($4000-$401F), the 2A03 also reads its
+    /// internal register matching the low bits of the DMA address, which can corrupt the byte
+    /// read, clear the frame counter IRQ or clock the controllers.
+    ///
+    /// See: <https://www.nesdev.org/wiki/DMA#Register_conflicts>
+    fn dma_read(&mut self, addr: u16, halt_addr: u16, prev_reg_addr: &mut u16) -> u8 {
+        if halt_addr & 0xFFE0 != 0x4000 {
+            // Internal registers aren't visible to DMA on the external bus
+            return if addr & 0xFFE0 == 0x4000 {
+                self.bus.open_bus
+            } else {
+                self.bus.read(addr, Access::Dummy)
+            };
+        }
+
+        let reg_addr = 0x4000 | (addr & 0x1F);
+        let val = match reg_addr {
+            0x4015 => {
+                let status = self.bus.read(reg_addr, Access::Dummy);
+                if reg_addr == addr {
+                    status
+                } else {
+                    // Bit 5 of $4015 isn't driven
+                    let external = self.bus.read(addr, Access::Dummy);
+                    (external & 0x20) | (status & !0x20)
+                }
+            }
+            0x4016 | 0x4017 => {
+                // Controllers are only clocked on the first of consecutive reads of the same
+                // register since /OE stays asserted
+                let input = if *prev_reg_addr == reg_addr {
+                    self.bus.open_bus
+                } else {
+                    self.bus.read(reg_addr, Access::Dummy)
+                };
+                if reg_addr == addr {
+                    input
+                } else {
+                    // Only D0-D4 are driven by the controller ports and conflicting bits are
+                    // ANDed together
+                    let external = self.bus.read(addr, Access::Dummy);
+                    (external & 0xE0) | (external & input & 0x1F)
+                }
+            }
+            _ => self.bus.read(addr, Access::Dummy),
+        };
+        *prev_reg_addr = reg_addr;
+        val
+    }
+
     /// Handle a direct-memory access (DMA) request.
+    ///
+    /// The CPU is halted by pulling the RDY line low, which only takes effect on a read cycle.
+    /// DMC DMA needs a halt cycle and a dummy cycle before reading its byte on a get (even) cycle,
+    /// while OAM DMA alternates reads on get cycles and writes on put (odd) cycles, with OAM DMA
+    /// cycles counting as halt and dummy cycles for DMC DMA when both run at the same time.
+    ///
+    /// See: <https://www.nesdev.org/wiki/DMA>
     fn handle_dma(&mut self, addr: u16) {
         trace!("Starting DMA - CYC:{}", self.cycle);
 
         self.start_cycle(self.read_cycles.start);
         self.bus.read(addr, Access::Dummy);
         self.end_cycle(self.read_cycles.end);
-        Self::clear_dma_halt();
+        self.bus.apu.dma.halt = false;
 
         let skip_dummy_reads = addr == 0x4016 || addr == 0x4017;
 
         let mut oam_offset = 0;
         let mut oam_dma_count = 0;
         let mut read_val = 0;
+        let mut prev_reg_addr = 0x4000 | (addr & 0x1F);
 
-        while let Some((dmc_dma, oam_dma)) = Self::dmas_running() {
+        while let Some((dmc_dma, oam_dma)) = self.bus.apu.dma.running() {
             if self.cycle & 0x01 == 0x00 {
-                if dmc_dma && !Self::halt_for_dma() && !Self::dma_dummy_read() {
+                if dmc_dma && !self.bus.apu.dma.halt && !self.bus.apu.dma.dummy_read {
                     // DMC DMA ready to read a byte (halt and dummy read done before)
                     self.start_dma_cycle();
                     let dma_addr = self.bus.apu.dmc.dma_addr();
-                    read_val = self.bus.read(dma_addr, Access::Dummy);
+                    read_val = self.dma_read(dma_addr, addr, &mut prev_reg_addr);
                     trace!(
                         "Loaded DMC DMA byte. ${dma_addr:04X}: {read_val} - CYC:{}",
                         self.cycle
                     );
                     self.end_cycle(self.read_cycles.end);
                     self.bus.apu.dmc.load_buffer(read_val);
-                    Self::clear_dma(Dma::DMC);
+                    self.bus.log_cdl(dma_addr, CdlFlags::PCM);
+                    self.bus.apu.dma.dmas &= !Dma::DMC;
+                    self.bus.apu.dma.abort = false;
                 } else if oam_dma {
                     // DMC DMA not running or ready, run OAM DMA
                     self.start_dma_cycle();
-                    read_val = self
-                        .bus
-                        .read(Self::dma_oam_addr() + oam_offset, Access::Dummy);
+                    let oam_addr = self.bus.apu.dma.oam_addr;
+                    read_val = self.dma_read(oam_addr + oam_offset, addr, &mut prev_reg_addr);
                     self.end_cycle(self.read_cycles.end);
                     oam_offset += 1;
                     oam_dma_count += 1;
                 } else {
                     // DMC DMA running, but not ready yet (needs to halt, or dummy read) and OAM
                     // DMA isn't running
-                    debug_assert!(Self::halt_for_dma() || Self::dma_dummy_read());
+                    debug_assert!(self.bus.apu.dma.halt || self.bus.apu.dma.dummy_read);
                     self.start_dma_cycle();
                     if !skip_dummy_reads {
                         self.bus.read(addr, Access::Dummy); // throw away
@@ -490,7 +514,7 @@ impl Cpu {
                 self.end_cycle(self.read_cycles.end);
                 oam_dma_count += 1;
                 if oam_dma_count == 0x200 {
-                    Self::clear_dma(Dma::OAM);
+                    self.bus.apu.dma.dmas &= !Dma::OAM;
                 }
             } else {
                 // Align to read cycle before starting OAM DMA (or align to perform DMC read)
@@ -677,7 +701,8 @@ impl Cpu {
         u16::from_le_bytes([lo, hi])
     }
 
-    /// Disassemble the instruction at the given program counter.
+    /// Disassemble the instruction at the given program counter. If the Code/Data Log is enabled,
+    /// bytes logged only as data are disassembled as a `.db` directive instead.
     pub fn disassemble(&mut self, pc: &mut u16) -> &str {
         let opcode = self.peek(*pc, Access::Dummy);
         let instr = Cpu::INSTRUCTIONS[opcode as usize];
@@ -686,6 +711,19 @@ impl Cpu {
         let _ = write!(self.disasm, "${pc:04X} ${opcode:02X} ");
         let mut addr = pc.wrapping_add(1);
 
+        // Bytes only ever read as data according to the Code/Data Log aren't decoded, unless
+        // they're about to be executed
+        if *pc != self.pc
+            && self
+                .bus
+                .cdl_flags(*pc)
+                .is_some_and(|flags| flags.is_data_only())
+        {
+            let _ = write!(self.disasm, "        .db ${opcode:02X}");
+            *pc = addr;
+            return &self.disasm;
+        }
+
         match instr.addr_mode() {
             IMM => {
                 let byte = self.peek(addr, Access::Dummy);
@@ -815,6 +853,39 @@ impl Cpu {
         &self.disasm
     }
 
+    /// Marks the bytes of the instruction about to be executed as code in the Code/Data Log.
+    /// Logged before execution, since the instruction may switch the PRG-ROM bank it's in.
+    fn log_cdl_instr(&mut self) {
+        let opcode = self.peek(self.pc, Access::Dummy);
+        let len = match Cpu::INSTRUCTIONS[opcode as usize].addr_mode() {
+            ACC | IMP => 1,
+            IMM | ZP0 | ZPX | ZPY | IDX | IDY | REL => 2,
+            ABS | ABX | ABY | IND => 3,
+        };
+        for offset in 0..len {
+            self.bus
+                .log_cdl(self.pc.wrapping_add(offset), CdlFlags::CODE);
+        }
+    }
+
+    /// Marks the memory read by the last executed instruction as data in the Code/Data Log, or
+    /// the target of an indirect jump as indirectly accessed code.
+    fn log_cdl_operand(&mut self) {
+        match (self.instr.addr_mode(), self.instr.op()) {
+            (IND, JMP) => self
+                .bus
+                .log_cdl(self.pc, CdlFlags::CODE | CdlFlags::INDIRECT_CODE),
+            (_, JMP | JSR | STA | STX | STY | SAX | AHX | SXA | SYA | TAS) => (),
+            (IDX | IDY, _) => self
+                .bus
+                .log_cdl(self.abs_addr, CdlFlags::DATA | CdlFlags::INDIRECT_DATA),
+            (ZP0 | ZPX | ZPY | ABS | ABX | ABY, _) => {
+                self.bus.log_cdl(self.abs_addr, CdlFlags::DATA);
+            }
+            _ => (),
+        }
+    }
+
     /// Logs the disassembled instruction being executed.
     pub fn trace_instr(&mut self) {
         let mut pc = self.pc;
@@ -854,103 +925,15 @@ impl Clock for Cpu {
 
         self.trace_instr();
 
-        let opcode = self.read_instr(); // Cycle 1 of instruction
-        self.instr = Cpu::INSTRUCTIONS[opcode as usize];
-
-        match self.instr.addr_mode() {
-            IMM => self.imm(),
-            ZP0 => self.zp0(),
-            ZPX => self.zpx(),
-            ZPY => self.zpy(),
-            ABS => self.abs(),
-            ABX => self.abx(),
-            ABY => self.aby(),
-            IND => self.ind(),
-            IDX => self.idx(),
-            IDY => self.idy(),
-            REL => self.rel(),
-            ACC => self.acc(),
-            IMP => self.imp(),
-        };
+        let cdl_enabled = self.bus.cdl.is_some();
+        if cdl_enabled {
+            self.log_cdl_instr();
+        }
 
-        match self.instr.op() {
-            ADC => self.adc(), // ADd with Carry M with A
-            AND => self.and(), // AND M with A
-            ASL => self.asl(), // Arithmatic Shift Left M or A
-            BCC => self.bcc(), // Branch on Carry Clear
-            BCS => self.bcs(), // Branch if Carry Set
-            BEQ => self.beq(), // Branch if EQual to zero
-            BIT => self.bit(), // Test BITs of M with A (Affects N, V and Z)
-            BMI => self.bmi(), // Branch on MInus (negative)
-            BNE => self.bne(), // Branch if Not Equal to zero
-            BPL => self.bpl(), // Branch on PLus (positive)
-            BRK => self.brk(), // BReaK (forced interrupt)
-            BVC => self.bvc(), // Branch if no oVerflow Set
-            BVS => self.bvs(), // Branch on oVerflow Set
-            CLC => self.clc(), // CLear Carry flag
-            CLD => self.cld(), // CLear Decimal mode
-            CLI => self.cli(), // CLear Interrupt disable
-            CLV => self.clv(), // CLear oVerflow flag
-            CMP => self.cmp(), // CoMPare
-            CPX => self.cpx(), // ComPare with X
-            CPY => self.cpy(), // ComPare with Y
-            DEC => self.dec(), // DECrement M or A
-            DEX => self.dex(), // DEcrement X
-            DEY => self.dey(), // DEcrement Y
-            EOR => self.eor(), // Exclusive-OR M with A
-            INC => self.inc(), // INCrement M or A
-            INX => self.inx(), // INcrement X
-            INY => self.iny(), // INcrement Y
-            JMP => self.jmp(), // JuMP - safe to unwrap because JMP is Absolute
-            JSR => self.jsr(), // Jump and Save Return addr - safe to unwrap because JSR is Absolute
-            LDA => self.lda(), // LoaD A with M
-            LDX => self.ldx(), // LoaD X with M
-            LDY => self.ldy(), // LoaD Y with M
-            LSR => self.lsr(), // Logical Shift Right M or A
-            NOP => self.nop(), // NO oPeration
-            SKB => self.skb(), // Like NOP, but issues a dummy read
-            IGN => self.ign(), // Like NOP, but issues a dummy read
-            ORA => self.ora(), // OR with A
-            PHA => self.pha(), // PusH A to the stack
-            PHP => self.php(), // PusH Processor status to the stack
-            PLA => self.pla(), // PulL A from the stack
-            PLP => self.plp(), // PulL Processor status from the stack
-            ROL => self.rol(), // ROtate Left M or A
-            ROR => self.ror(), // ROtate Right M or A
-            RTI => self.rti(), // ReTurn from Interrupt
-            RTS => self.rts(), // ReTurn from Subroutine
-            SBC => self.sbc(), // Subtract M from A with carry
-            SEC => self.sec(), // SEt Carry flag
-            SED => self.sed(), // SEt Decimal mode
-            SEI => self.sei(), // SEt Interrupt disable
-            STA => self.sta(), // STore A into M
-            STX => self.stx(), // STore X into M
-            STY => self.sty(), // STore Y into M
-            TAX => self.tax(), // Transfer A to X
-            TAY => self.tay(), // Transfer A to Y
-            TSX => self.tsx(), // Transfer SP to X
-            TXA => self.txa(), // TRansfer X to A
-            TXS => self.txs(), // Transfer X to SP
-            TYA => self.tya(), // Transfer Y to A
-            ISB => self.isb(), // INC & SBC
-            DCP => self.dcp(), // DEC & CMP
-            AXS => self.axs(), // (A & X) - val into X
-            LAS => self.las(), // LDA & TSX
-            LAX => self.lax(), // LDA & TAX
-            AHX => self.ahx(), // Store A & X & H in M
-            SAX => self.sax(), // Sotre A & X in M
-            XAA => self.xaa(), // TXA & AND
-            SXA => self.sxa(), // Store X & H in M
-            RRA => self.rra(), // ROR & ADC
-            TAS => self.tas(), // STA & TXS
-            SYA => self.sya(), // Store Y & H in M
-            ARR => self.arr(), // AND #imm & ROR
-            SRE => self.sre(), // LSR & EOR
-            ALR => self.alr(), // AND #imm & LSR
-            RLA => self.rla(), // ROL & AND
-            ANC => self.anc(), // AND #imm
-            SLO => self.slo(), // ASL & ORA
-            XXX => self.xxx(), // Unimplemented opcode
+        let opcode = self.read_instr(); // Cycle 1 of instruction
+        Cpu::DECODE_TABLE[opcode as usize](self);
+        if cdl_enabled {
+            self.log_cdl_operand();
         }
 
         if self.prev_run_irq || self.prev_nmi {
@@ -972,7 +955,7 @@ impl Clock for Cpu {
 
 impl Mem for Cpu {
     fn read(&mut self, addr: u16, access: Access) -> u8 {
-        if Self::halt_for_dma() {
+        if self.bus.apu.dma.halt {
             self.handle_dma(addr);
         }
 
@@ -1010,7 +993,7 @@ impl Regional for Cpu {
             end: end_cycles + 1,
         };
         self.write_cycles = Cycle {
-            start: end_cycles + 1,
+            start: start_cycles + 1,
             end: end_cycles - 1,
         };
         self.bus.set_region(region);
@@ -1051,11 +1034,6 @@ impl Reset for Cpu {
         self.prev_nmi = false;
         self.prev_nmi_pending = false;
         self.corrupted = false;
-        Self::clear_nmi();
-        Self::clear_irq(Irq::all());
-        Self::clear_dma_halt();
-        Self::clear_dma(Dma::all());
-        Self::clear_dma_dummy_read();
 
         // Read directly from bus so as to not clock other components during reset
         let lo = self.bus.read(Self::RESET_VECTOR, Access::Read);
@@ -1088,7 +1066,7 @@ impl fmt::Debug for Cpu {
             .field("abs_addr", &format_args!("${:04X}", self.abs_addr))
             .field("rel_addr", &format_args!("${:04X}", self.rel_addr))
             .field("fetched_data", &format_args!("${:02X}", self.fetched_data))
-            .field("irqs", &Self::irqs())
+            .field("irqs", &self.bus.irqs())
             .field("nmi", &self.nmi)
             .field("prev_nmi", &self.prev_nmi)
             .field("prev_nmi_pending", &self.prev_nmi_pending)
@@ -1101,7 +1079,9 @@ impl fmt::Debug for Cpu {
 
 #[cfg(all(test, feature = "std"))]
 mod tests {
+    use super::*;
     use crate::cart::Cart;
+    use instr::Operation::{BCC, BNE, BPL, BVC, XXX};
 
     #[test]
     fn cycle_timing() {
@@ -1137,4 +1117,163 @@ mod tests {
             );
         }
     }
+    #[test]
+    fn dma_conflicts() {
+        use crate::{
+            control_deck::{Config, ControlDeck},
+            fs::compute_crc32,
+            mem::{MemorySpace, RamState},
+            video::VideoFilter,
+        };
+        use alloc::{format, string::String, vec::Vec};
+
+        fn run(path: &str, frames: u32) -> ControlDeck {
+            let mut deck = ControlDeck::with_config(Config {
+                ram_state: RamState::AllZeros,
+                filter: VideoFilter::Pixellate,
+                ..Default::default()
+            });
+            let rom = std::fs::read(format!(
+                "{}/test_roms/{path}.nes",
+                env!("CARGO_MANIFEST_DIR")
+            ))
+            .expect("valid rom");
+            deck.load_rom(path, &mut rom.as_slice()).expect("valid rom");
+            while deck.frame_number() < frames {
+                deck.clock_frame().expect("valid frame clock");
+            }
+            deck
+        }
+
+        // Non-empty lines of text printed to the first nametable
+        fn screen_text(deck: &ControlDeck) -> Vec<String> {
+            (0..30)
+                .map(|row| {
+                    (0..32)
+                        .map(|col| {
+                            deck.peek_memory(MemorySpace::Ppu, 0x2000 + row * 32 + col)
+                                .map_or(' ', char::from)
+                        })
+                        .collect::<String>()
+                        .trim()
+                        .into()
+                })
+                .filter(|line: &String| !line.is_empty())
+                .collect()
+        }
+
+        for (path, frames) in [
+            ("apu/dmc_dma_2007_write", 35),
+            ("apu/dmc_dma_4016_read", 20),
+            ("apu/dmc_dma_read_write_2007", 25),
+        ] {
+            let deck = run(path, frames);
+            let text = screen_text(&deck);
+            assert_eq!(text.last().map(String::as_str), Some("Passed"), "{path}");
+        }
+
+        // These tests print the values read followed by their CRC32, which varies with CPU/PPU
+        // alignment on power-up. Any of the CRCs listed in their readme is a pass.
+        for (path, frames, expected) in [
+            ("apu/dmc_dma_2007_read", 30, &[0x159A_7A8F, 0x5E3D_F9C4][..]),
+            (
+                "apu/dmc_dma_double_2007_read",
+                20,
+                &[0x85CF_D627, 0xF018_C287, 0x440E_F923, 0xE52F_41A5][..],
+            ),
+        ] {
+            let deck = run(path, frames);
+            let mut text = screen_text(&deck);
+            let printed_crc = text
+                .pop()
+                .and_then(|crc| u32::from_str_radix(&crc, 16).ok())
+                .expect("printed crc");
+            let values = text
+                .iter()
+                .flat_map(|line| line.split_whitespace())
+                .map(|val| u8::from_str_radix(val, 16).expect("printed value"))
+                .collect::<Vec<_>>();
+            let crc = compute_crc32(&values);
+            assert_eq!(crc, printed_crc, "{path}: {text:?}");
+            assert!(expected.contains(&crc), "{path}: {crc:08X} {text:?}");
+        }
+
+        for path in ["cpu/sprdma_and_dmc_dma", "cpu/sprdma_and_dmc_dma_512"] {
+            let deck = run(path, 145);
+            let status =
+                [0x6000, 0x6001, 0x6002, 0x6003].map(|addr| deck.cpu().peek(addr, Access::Dummy));
+            assert_eq!(status, [0x00, 0xDE, 0xB0, 0x61], "{path}");
+        }
+    }
+
+    #[test]
+    fn dmc_dma_abort() {
+        use crate::apu::dmc::{DmaRequest, Dmc};
+
+        // Disabled before the CPU halts, so the DMA never happens
+        let mut dma = DmaState::default();
+        dma.start_dmc();
+        dma.stop_dmc();
+        assert!(dma.running().is_none());
+        assert!(!dma.halt);
+        assert!(!dma.abort);
+
+        // Disabled after the CPU halts, so the DMA is aborted
+        dma.start_dmc();
+        dma.halt = false;
+        dma.stop_dmc();
+        assert!(dma.abort);
+
+        // Disabling the DMC takes effect after a delay
+        let mut dmc = Dmc::new(NesRegion::Ntsc);
+        dmc.set_enabled(true, 0);
+        assert!(dmc.bytes_remaining > 0);
+        dmc.set_enabled(false, 0);
+        assert!(dmc.bytes_remaining > 0);
+        let _ = dmc.should_clock();
+        assert!(dmc.bytes_remaining > 0);
+        let _ = dmc.should_clock();
+        assert_eq!(dmc.bytes_remaining, 0);
+        assert_eq!(dmc.dma_request, Some(DmaRequest::Stop));
+    }
+
+    #[test]
+    fn region_frame_timing() {
+        use crate::{common::Regional, control_deck::ControlDeck, ppu::Ppu};
+
+        let rom = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_roms/spritecans.nes"
+        ))
+        .expect("valid rom");
+        for (region, cycles_per_frame) in [
+            (NesRegion::Ntsc, 29_780.5),
+            (NesRegion::Pal, 33_247.5),
+            (NesRegion::Dendy, 35_464.0),
+        ] {
+            let mut deck = ControlDeck::new();
+            deck.load_rom("spritecans", &mut rom.as_slice())
+                .expect("valid rom");
+            deck.set_region(region);
+            // Skip power-up frames so rendering is enabled when measuring
+            for _ in 0..10 {
+                deck.clock_frame().expect("valid frame clock");
+            }
+            let start = deck.cpu().cycle;
+            let frames = 100;
+            for _ in 0..frames {
+                deck.clock_frame().expect("valid frame clock");
+            }
+            let actual = (deck.cpu().cycle - start) as f32 / frames as f32;
+            assert!(
+                (actual - cycles_per_frame).abs() < 1.0,
+                "{region:?} cpu cycles per frame: {actual}, expected {cycles_per_frame}"
+            );
+            let frame_rate = Cpu::region_clock_rate(region) / cycles_per_frame;
+            assert!(
+                (Ppu::region_frame_rate(region) - frame_rate).abs() < 0.001,
+                "{region:?} frame rate"
+            );
+        }
+    }
 }
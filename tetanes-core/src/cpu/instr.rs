@@ -70,6 +70,16 @@ impl Instr {
     }
 }
 
+/// Handler that executes a decoded instruction.
+pub(crate) type InstrFn = fn(&mut Cpu);
+
+/// Builds [`Cpu::DECODE_TABLE`] from a list of opcodes.
+macro_rules! decode_table {
+    ($($opcode:literal)*) => {
+        [$(Cpu::exec::<$opcode>),*]
+    };
+}
+
 /// CPU Addressing Modes
 ///
 /// The 6502 can address 64KB from 0x0000 - 0xFFFF. The high byte is usually the page and the
@@ -96,6 +106,141 @@ impl Cpu {
         Instr(0xF0, REL, BEQ, 2), Instr(0xF1, IDY, SBC, 5), Instr(0xF2, IMP, XXX, 2), Instr(0xF3, IDY, ISB, 8), Instr(0xF4, ZPX, NOP, 4), Instr(0xF5, ZPX, SBC, 4), Instr(0xF6, ZPX, INC, 6), Instr(0xF7, ZPX, ISB, 6), Instr(0xF8, IMP, SED, 2), Instr(0xF9, ABY, SBC, 4), Instr(0xFA, IMP, NOP, 2), Instr(0xFB, ABY, ISB, 7), Instr(0xFC, ABX, IGN, 4), Instr(0xFD, ABX, SBC, 4), Instr(0xFE, ABX, INC, 7), Instr(0xFF, ABX, ISB, 7),
     ];
 
+    /// Decoded instruction handlers indexed by opcode, laid out the same as
+    /// [`Cpu::INSTRUCTIONS`].
+    ///
+    /// Each handler has its addressing mode and operation resolved at compile time, so executing
+    /// an instruction is a single indirect call instead of matching on the addressing mode and
+    /// operation every time an opcode is fetched. Memory accesses still clock the bus every
+    /// cycle.
+    #[rustfmt::skip]
+    pub(crate) const DECODE_TABLE: [InstrFn; 256] = decode_table!(
+        0x00 0x01 0x02 0x03 0x04 0x05 0x06 0x07 0x08 0x09 0x0A 0x0B 0x0C 0x0D 0x0E 0x0F
+        0x10 0x11 0x12 0x13 0x14 0x15 0x16 0x17 0x18 0x19 0x1A 0x1B 0x1C 0x1D 0x1E 0x1F
+        0x20 0x21 0x22 0x23 0x24 0x25 0x26 0x27 0x28 0x29 0x2A 0x2B 0x2C 0x2D 0x2E 0x2F
+        0x30 0x31 0x32 0x33 0x34 0x35 0x36 0x37 0x38 0x39 0x3A 0x3B 0x3C 0x3D 0x3E 0x3F
+        0x40 0x41 0x42 0x43 0x44 0x45 0x46 0x47 0x48 0x49 0x4A 0x4B 0x4C 0x4D 0x4E 0x4F
+        0x50 0x51 0x52 0x53 0x54 0x55 0x56 0x57 0x58 0x59 0x5A 0x5B 0x5C 0x5D 0x5E 0x5F
+        0x60 0x61 0x62 0x63 0x64 0x65 0x66 0x67 0x68 0x69 0x6A 0x6B 0x6C 0x6D 0x6E 0x6F
+        0x70 0x71 0x72 0x73 0x74 0x75 0x76 0x77 0x78 0x79 0x7A 0x7B 0x7C 0x7D 0x7E 0x7F
+        0x80 0x81 0x82 0x83 0x84 0x85 0x86 0x87 0x88 0x89 0x8A 0x8B 0x8C 0x8D 0x8E 0x8F
+        0x90 0x91 0x92 0x93 0x94 0x95 0x96 0x97 0x98 0x99 0x9A 0x9B 0x9C 0x9D 0x9E 0x9F
+        0xA0 0xA1 0xA2 0xA3 0xA4 0xA5 0xA6 0xA7 0xA8 0xA9 0xAA 0xAB 0xAC 0xAD 0xAE 0xAF
+        0xB0 0xB1 0xB2 0xB3 0xB4 0xB5 0xB6 0xB7 0xB8 0xB9 0xBA 0xBB 0xBC 0xBD 0xBE 0xBF
+        0xC0 0xC1 0xC2 0xC3 0xC4 0xC5 0xC6 0xC7 0xC8 0xC9 0xCA 0xCB 0xCC 0xCD 0xCE 0xCF
+        0xD0 0xD1 0xD2 0xD3 0xD4 0xD5 0xD6 0xD7 0xD8 0xD9 0xDA 0xDB 0xDC 0xDD 0xDE 0xDF
+        0xE0 0xE1 0xE2 0xE3 0xE4 0xE5 0xE6 0xE7 0xE8 0xE9 0xEA 0xEB 0xEC 0xED 0xEE 0xEF
+        0xF0 0xF1 0xF2 0xF3 0xF4 0xF5 0xF6 0xF7 0xF8 0xF9 0xFA 0xFB 0xFC 0xFD 0xFE 0xFF
+    );
+
+    /// Execute the instruction for `OPCODE`, whose opcode byte has already been fetched.
+    fn exec<const OPCODE: u8>(&mut self) {
+        self.execute(Self::INSTRUCTIONS[OPCODE as usize]);
+    }
+
+    /// Execute `instr`. Inlined into each [`Cpu::DECODE_TABLE`] handler so that `instr` is known
+    /// at compile time and the addressing mode and operation dispatch is optimized away.
+    #[inline(always)]
+    fn execute(&mut self, instr: Instr) {
+        self.instr = instr;
+
+        match instr.addr_mode() {
+            IMM => self.imm(),
+            ZP0 => self.zp0(),
+            ZPX => self.zpx(),
+            ZPY => self.zpy(),
+            ABS => self.abs(),
+            ABX => self.abx(),
+            ABY => self.aby(),
+            IND => self.ind(),
+            IDX => self.idx(),
+            IDY => self.idy(),
+            REL => self.rel(),
+            ACC => self.acc(),
+            IMP => self.imp(),
+        };
+
+        match instr.op() {
+            ADC => self.adc(), // ADd with Carry M with A
+            AND => self.and(), // AND M with A
+            ASL => self.asl(), // Arithmatic Shift Left M or A
+            BCC => self.bcc(), // Branch on Carry Clear
+            BCS => self.bcs(), // Branch if Carry Set
+            BEQ => self.beq(), // Branch if EQual to zero
+            BIT => self.bit(), // Test BITs of M with A (Affects N, V and Z)
+            BMI => self.bmi(), // Branch on MInus (negative)
+            BNE => self.bne(), // Branch if Not Equal to zero
+            BPL => self.bpl(), // Branch on PLus (positive)
+            BRK => self.brk(), // BReaK (forced interrupt)
+            BVC => self.bvc(), // Branch if no oVerflow Set
+            BVS => self.bvs(), // Branch on oVerflow Set
+            CLC => self.clc(), // CLear Carry flag
+            CLD => self.cld(), // CLear Decimal mode
+            CLI => self.cli(), // CLear Interrupt disable
+            CLV => self.clv(), // CLear oVerflow flag
+            CMP => self.cmp(), // CoMPare
+            CPX => self.cpx(), // ComPare with X
+            CPY => self.cpy(), // ComPare with Y
+            DEC => self.dec(), // DECrement M or A
+            DEX => self.dex(), // DEcrement X
+            DEY => self.dey(), // DEcrement Y
+            EOR => self.eor(), // Exclusive-OR M with A
+            INC => self.inc(), // INCrement M or A
+            INX => self.inx(), // INcrement X
+            INY => self.iny(), // INcrement Y
+            JMP => self.jmp(), // JuMP - safe to unwrap because JMP is Absolute
+            JSR => self.jsr(), // Jump and Save Return addr - safe to unwrap because JSR is Absolute
+            LDA => self.lda(), // LoaD A with M
+            LDX => self.ldx(), // LoaD X with M
+            LDY => self.ldy(), // LoaD Y with M
+            LSR => self.lsr(), // Logical Shift Right M or A
+            NOP => self.nop(), // NO oPeration
+            SKB => self.skb(), // Like NOP, but issues a dummy read
+            IGN => self.ign(), // Like NOP, but issues a dummy read
+            ORA => self.ora(), // OR with A
+            PHA => self.pha(), // PusH A to the stack
+            PHP => self.php(), // PusH Processor status to the stack
+            PLA => self.pla(), // PulL A from the stack
+            PLP => self.plp(), // PulL Processor status from the stack
+            ROL => self.rol(), // ROtate Left M or A
+            ROR => self.ror(), // ROtate Right M or A
+            RTI => self.rti(), // ReTurn from Interrupt
+            RTS => self.rts(), // ReTurn from Subroutine
+            SBC => self.sbc(), // Subtract M from A with carry
+            SEC => self.sec(), // SEt Carry flag
+            SED => self.sed(), // SEt Decimal mode
+            SEI => self.sei(), // SEt Interrupt disable
+            STA => self.sta(), // STore A into M
+            STX => self.stx(), // STore X into M
+            STY => self.sty(), // STore Y into M
+            TAX => self.tax(), // Transfer A to X
+            TAY => self.tay(), // Transfer A to Y
+            TSX => self.tsx(), // Transfer SP to X
+            TXA => self.txa(), // TRansfer X to A
+            TXS => self.txs(), // Transfer X to SP
+            TYA => self.tya(), // Transfer Y to A
+            ISB => self.isb(), // INC & SBC
+            DCP => self.dcp(), // DEC & CMP
+            AXS => self.axs(), // (A & X) - val into X
+            LAS => self.las(), // LDA & TSX
+            LAX => self.lax(), // LDA & TAX
+            AHX => self.ahx(), // Store A & X & H in M
+            SAX => self.sax(), // Sotre A & X in M
+            XAA => self.xaa(), // TXA & AND
+            SXA => self.sxa(), // Store X & H in M
+            RRA => self.rra(), // ROR & ADC
+            TAS => self.tas(), // STA & TXS
+            SYA => self.sya(), // Store Y & H in M
+            ARR => self.arr(), // AND #imm & ROR
+            SRE => self.sre(), // LSR & EOR
+            ALR => self.alr(), // AND #imm & LSR
+            RLA => self.rla(), // ROL & AND
+            ANC => self.anc(), // AND #imm
+            SLO => self.slo(), // ASL & ORA
+            XXX => self.xxx(), // Unimplemented opcode
+        }
+    }
+
     /// Accumulator
     /// No additional data is required, but the default target will be the accumulator.
     //  ASL, ROL, LSR, ROR
@@ -9,18 +9,50 @@ use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use alloc::{format, vec};
 use bincode::config::Configuration;
-use bincode::serde::{BorrowCompat, Compat};
+use bincode::serde::BorrowCompat;
 use miniz_oxide::inflate::stream::InflateState;
 use miniz_oxide::inflate::{decompress_to_vec, DecompressError};
 // use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
-use serde::{de::DeserializeOwned, Serialize};
+use core::{fmt, marker::PhantomData};
+use serde::{
+    de::{
+        value::U8Deserializer, DeserializeOwned, DeserializeSeed, EnumAccess, MapAccess, SeqAccess,
+        VariantAccess, Visitor,
+    },
+    Deserialize, Deserializer, Serialize,
+};
 use snafu::{ResultExt, Snafu};
 use tracing::warn;
 
+#[cfg(target_arch = "wasm32")]
+pub use crate::sys::fs::load_storage;
+
 const SAVE_FILE_MAGIC_LEN: usize = 8;
 const SAVE_FILE_MAGIC: [u8; SAVE_FILE_MAGIC_LEN] = *b"TETANES\x1a";
-// Keep this separate from Semver because breaking API changes may not invalidate the save format.
-const SAVE_VERSION: &str = "1";
+
+/// Version of the save format, written after the magic as a single byte.
+///
+/// Keep this separate from Semver because breaking API changes may not invalidate the save format.
+/// Increment it whenever the serialized layout of saved data changes, tagging new fields with
+/// [`added_in`] or migrating changed fields with [`changed_in`], so data saved by previous versions
+/// keeps loading.
+///
+/// - `1`: Initial format.
+/// - `2`: Added `Apu::record_sample_counter`, `Dmc::disable_delay` and `Ppu::odd_frame`, and
+///   `Ppu::open_bus` tracks decay per bit.
+/// - `3`: Added `Input::expansion`.
+/// - `4`: Added `Input::vaus`, and `VausPaddle::connected` to the Famicom paddle.
+/// - `5`: Added `Input::mouse`.
+/// - `6`: Added `Input::vs` and `Ppu::model`.
+/// - `7`: Added `Joypad::shift_register`.
+/// - `8`: Added `Apu::dma`.
+/// - `9`: Moved pending NMI and IRQ lines onto the PPU, APU and mappers.
+pub const SAVE_VERSION: u8 = 9;
+/// Oldest save format version that can still be loaded.
+pub const MIN_SAVE_VERSION: u8 = 1;
+
+/// Version byte written by the initial format, which stored the version as an ASCII digit.
+const LEGACY_SAVE_VERSION: u8 = b'1';
 
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -74,15 +106,15 @@ impl Error {
 /// If the header fails to write to disk, then an error is returned.
 pub(crate) fn write_header(f: &mut impl Write) -> crate::io::Result<()> {
     f.write_all(&SAVE_FILE_MAGIC)?;
-    f.write_all(SAVE_VERSION.as_bytes())
+    f.write_all(&[SAVE_VERSION])
 }
 
-/// Verifies a `TetaNES` saved state header.
+/// Verifies a `TetaNES` saved state header, returning the save format version.
 ///
 /// # Errors
 ///
-/// If the header fails to validate, then an error is returned.
-pub(crate) fn validate_header(f: &mut impl Read) -> Result<()> {
+/// If the header fails to validate or the version isn't supported, then an error is returned.
+pub(crate) fn validate_header(f: &mut impl Read) -> Result<u8> {
     let mut magic = [0u8; SAVE_FILE_MAGIC_LEN];
     f.read_exact(&mut magic).map_err(|s| {
         InvalidHeaderSnafu {
@@ -104,16 +136,472 @@ pub(crate) fn validate_header(f: &mut impl Read) -> Result<()> {
         }
         .build()
     })?;
-    if version == SAVE_VERSION.as_bytes() {
-        Ok(())
+    let version = match version[0] {
+        LEGACY_SAVE_VERSION => 1,
+        version => version,
+    };
+    if (MIN_SAVE_VERSION..=SAVE_VERSION).contains(&version) {
+        Ok(version)
     } else {
         InvalidHeaderSnafu {
-            inner: format!("invalid version (expected {SAVE_VERSION:?}, found: {version:?}",),
+            inner: format!(
+                "unsupported version (expected {MIN_SAVE_VERSION} to {SAVE_VERSION}, found: {version})",
+            ),
         }
         .fail()
     }
 }
 
+/// Deserialize a field added in save format `VERSION`, defaulting it when loading data saved by a
+/// previous version that doesn't contain it.
+///
+/// ```ignore
+/// #[serde(deserialize_with = "crate::fs::added_in::<2, _, _>")]
+/// pub odd_frame: bool,
+/// ```
+///
+/// # Errors
+///
+/// If the field fails to deserialize, then an error is returned.
+pub fn added_in<'de, const VERSION: u8, D, T>(deserializer: D) -> core::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    changed_in::<VERSION, D, (), T>(deserializer, |()| T::default())
+}
+
+/// Deserialize a field whose layout changed in save format `VERSION`, deserializing it as `Old`
+/// and converting it with `migrate` when loading data saved by a previous version.
+///
+/// Data not loaded with [`load`] or [`load_bytes`], such as snapshots, is always in the current
+/// format.
+///
+/// ```ignore
+/// fs::changed_in::<2, _, _, _>(deserializer, |value: u8| Self { value, refreshed: [0; 8] })
+/// ```
+///
+/// # Errors
+///
+/// If the field fails to deserialize, then an error is returned.
+pub fn changed_in<'de, const VERSION: u8, D, Old, T>(
+    deserializer: D,
+    migrate: fn(Old) -> T,
+) -> core::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    Old: Deserialize<'de>,
+    T: Deserialize<'de>,
+{
+    deserializer.deserialize_newtype_struct(
+        VERSIONED_FIELD,
+        VersionedFieldVisitor::<VERSION, Old, T> { migrate },
+    )
+}
+
+/// Newtype struct name intercepted by [`Versioned`] to provide the save format version to
+/// [`changed_in`].
+const VERSIONED_FIELD: &str = "$tetanes::fs::VersionedField";
+
+/// Deserializes a field based on the save format version, provided by [`Versioned`] as a sequence
+/// of the version followed by the field. Other deserializers treat [`VERSIONED_FIELD`] as a plain
+/// newtype struct, which is read in the current format.
+struct VersionedFieldVisitor<const VERSION: u8, Old, T> {
+    migrate: fn(Old) -> T,
+}
+
+impl<'de, const VERSION: u8, Old, T> Visitor<'de> for VersionedFieldVisitor<VERSION, Old, T>
+where
+    Old: Deserialize<'de>,
+    T: Deserialize<'de>,
+{
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a field changed in save format {VERSION}")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> core::result::Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> core::result::Result<T, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let missing = || serde::de::Error::custom("missing versioned field");
+        let version = seq.next_element::<u8>()?.ok_or_else(missing)?;
+        let value = if version < VERSION {
+            seq.next_element::<Old>()?.map(self.migrate)
+        } else {
+            seq.next_element::<T>()?
+        };
+        value.ok_or_else(missing)
+    }
+}
+
+/// Wraps a deserializer and everything it hands out to `Deserialize` implementations, carrying
+/// the save format version of the data being loaded down to [`changed_in`].
+struct Versioned<T> {
+    inner: T,
+    version: u8,
+}
+
+impl<T> Versioned<T> {
+    const fn wrap<U>(&self, inner: U) -> Versioned<U> {
+        Versioned {
+            inner,
+            version: self.version,
+        }
+    }
+}
+
+/// Provides the save format version followed by the field to [`VersionedFieldVisitor`].
+struct VersionedField<D> {
+    version: Option<u8>,
+    deserializer: Option<Versioned<D>>,
+}
+
+impl<'de, D> SeqAccess<'de> for VersionedField<D>
+where
+    D: Deserializer<'de>,
+{
+    type Error = D::Error;
+
+    fn next_element_seed<S>(&mut self, seed: S) -> core::result::Result<Option<S::Value>, D::Error>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        if let Some(version) = self.version.take() {
+            seed.deserialize(U8Deserializer::new(version)).map(Some)
+        } else if let Some(deserializer) = self.deserializer.take() {
+            seed.deserialize(deserializer).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+macro_rules! forward_deserialize {
+    ($($method:ident$(($($arg:ident: $ty:ty),*))?),* $(,)?) => {$(
+        fn $method<V>(
+            self,
+            $($($arg: $ty,)*)?
+            visitor: V,
+        ) -> core::result::Result<V::Value, D::Error>
+        where
+            V: Visitor<'de>,
+        {
+            let visitor = self.wrap(visitor);
+            self.inner.$method($($($arg,)*)? visitor)
+        }
+    )*};
+}
+
+impl<'de, D> Deserializer<'de> for Versioned<D>
+where
+    D: Deserializer<'de>,
+{
+    type Error = D::Error;
+
+    forward_deserialize!(
+        deserialize_any,
+        deserialize_bool,
+        deserialize_i8,
+        deserialize_i16,
+        deserialize_i32,
+        deserialize_i64,
+        deserialize_i128,
+        deserialize_u8,
+        deserialize_u16,
+        deserialize_u32,
+        deserialize_u64,
+        deserialize_u128,
+        deserialize_f32,
+        deserialize_f64,
+        deserialize_char,
+        deserialize_str,
+        deserialize_string,
+        deserialize_bytes,
+        deserialize_byte_buf,
+        deserialize_option,
+        deserialize_unit,
+        deserialize_unit_struct(name: &'static str),
+        deserialize_seq,
+        deserialize_tuple(len: usize),
+        deserialize_tuple_struct(name: &'static str, len: usize),
+        deserialize_map,
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]),
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]),
+        deserialize_identifier,
+        deserialize_ignored_any,
+    );
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> core::result::Result<V::Value, D::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if name == VERSIONED_FIELD {
+            visitor.visit_seq(VersionedField {
+                version: Some(self.version),
+                deserializer: Some(self),
+            })
+        } else {
+            let visitor = self.wrap(visitor);
+            self.inner.deserialize_newtype_struct(name, visitor)
+        }
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+macro_rules! forward_visit {
+    ($($method:ident($ty:ty)),* $(,)?) => {$(
+        fn $method<E>(self, value: $ty) -> core::result::Result<V::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            self.inner.$method(value)
+        }
+    )*};
+}
+
+impl<'de, V> Visitor<'de> for Versioned<V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.expecting(f)
+    }
+
+    forward_visit!(
+        visit_bool(bool),
+        visit_i8(i8),
+        visit_i16(i16),
+        visit_i32(i32),
+        visit_i64(i64),
+        visit_i128(i128),
+        visit_u8(u8),
+        visit_u16(u16),
+        visit_u32(u32),
+        visit_u64(u64),
+        visit_u128(u128),
+        visit_f32(f32),
+        visit_f64(f64),
+        visit_char(char),
+        visit_str(&str),
+        visit_borrowed_str(&'de str),
+        visit_string(String),
+        visit_bytes(&[u8]),
+        visit_borrowed_bytes(&'de [u8]),
+        visit_byte_buf(Vec<u8>),
+    );
+
+    fn visit_none<E>(self) -> core::result::Result<V::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.inner.visit_none()
+    }
+
+    fn visit_unit<E>(self) -> core::result::Result<V::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.inner.visit_unit()
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> core::result::Result<V::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let deserializer = self.wrap(deserializer);
+        self.inner.visit_some(deserializer)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> core::result::Result<V::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let deserializer = self.wrap(deserializer);
+        self.inner.visit_newtype_struct(deserializer)
+    }
+
+    fn visit_seq<A>(self, seq: A) -> core::result::Result<V::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let seq = self.wrap(seq);
+        self.inner.visit_seq(seq)
+    }
+
+    fn visit_map<A>(self, map: A) -> core::result::Result<V::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let map = self.wrap(map);
+        self.inner.visit_map(map)
+    }
+
+    fn visit_enum<A>(self, data: A) -> core::result::Result<V::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        let data = self.wrap(data);
+        self.inner.visit_enum(data)
+    }
+}
+
+impl<'de, S> DeserializeSeed<'de> for Versioned<S>
+where
+    S: DeserializeSeed<'de>,
+{
+    type Value = S::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> core::result::Result<S::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let deserializer = self.wrap(deserializer);
+        self.inner.deserialize(deserializer)
+    }
+}
+
+impl<'de, A> SeqAccess<'de> for Versioned<A>
+where
+    A: SeqAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_element_seed<S>(&mut self, seed: S) -> core::result::Result<Option<S::Value>, A::Error>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        let seed = self.wrap(seed);
+        self.inner.next_element_seed(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+impl<'de, A> MapAccess<'de> for Versioned<A>
+where
+    A: MapAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> core::result::Result<Option<K::Value>, A::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let seed = self.wrap(seed);
+        self.inner.next_key_seed(seed)
+    }
+
+    fn next_value_seed<S>(&mut self, seed: S) -> core::result::Result<S::Value, A::Error>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        let seed = self.wrap(seed);
+        self.inner.next_value_seed(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+impl<'de, A> EnumAccess<'de> for Versioned<A>
+where
+    A: EnumAccess<'de>,
+{
+    type Error = A::Error;
+    type Variant = Versioned<A::Variant>;
+
+    fn variant_seed<S>(self, seed: S) -> core::result::Result<(S::Value, Self::Variant), A::Error>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        let seed = self.wrap(seed);
+        let (value, variant) = self.inner.variant_seed(seed)?;
+        Ok((
+            value,
+            Versioned {
+                inner: variant,
+                version: self.version,
+            },
+        ))
+    }
+}
+
+impl<'de, A> VariantAccess<'de> for Versioned<A>
+where
+    A: VariantAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn unit_variant(self) -> core::result::Result<(), A::Error> {
+        self.inner.unit_variant()
+    }
+
+    fn newtype_variant_seed<S>(self, seed: S) -> core::result::Result<S::Value, A::Error>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        let seed = self.wrap(seed);
+        self.inner.newtype_variant_seed(seed)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> core::result::Result<V::Value, A::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let visitor = self.wrap(visitor);
+        self.inner.tuple_variant(len, visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> core::result::Result<V::Value, A::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let visitor = self.wrap(visitor);
+        self.inner.struct_variant(fields, visitor)
+    }
+}
+
+/// Deserialize `data` saved with save format `version`.
+fn deserialize<T>(data: &[u8], version: u8) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let seed = Versioned {
+        inner: PhantomData::<T>,
+        version,
+    };
+    bincode::serde::decode_seed_from_slice(seed, data, bincode::config::standard()).map_err(|err| {
+        Error::DeserializationFailed {
+            inner: err.to_string(),
+        }
+    })
+}
+
 pub fn encode(mut writer: &mut impl Write, data: &[u8]) -> crate::io::Result<()> {
     let vec = miniz_oxide::deflate::compress_to_vec(data, 6);
     writer.write_all(&vec)?;
@@ -130,7 +618,26 @@ pub fn decode(data: impl Read) -> Result<Vec<u8>> {
     Ok(decoded)
 }
 
+/// Serialize `value` with a `TetaNES` header and save it to `path`. The file is replaced
+/// atomically where supported, so an interrupted save keeps the previous contents.
+///
+/// # Errors
+///
+/// If the value fails to serialize or compress, or the file fails to save, then an error is
+/// returned.
 pub fn save<T>(path: impl AsRef<Path>, value: &T) -> Result<()>
+where
+    T: Serialize + ?Sized,
+{
+    fs::write_atomic_impl(path, &save_bytes(value)?)
+}
+
+/// Serialize `value` with a `TetaNES` header, compressed the same as [`save`].
+///
+/// # Errors
+///
+/// If the value fails to serialize or compress, then an error is returned.
+pub fn save_bytes<T>(value: &T) -> Result<Vec<u8>>
 where
     T: Serialize + ?Sized,
 {
@@ -139,18 +646,19 @@ where
             inner: err.to_string(),
         },
     )?;
-    let mut writer = fs::writer_impl(path)?;
-    write_header(&mut writer).map_err(|inner| Error::WriteHeaderFailed { inner })?;
-    encode(&mut writer, &data).map_err(|inner| Error::EncodingFailed { inner })?;
-    Ok(())
+    let mut bytes = Vec::new();
+    write_header(&mut bytes).map_err(|inner| Error::WriteHeaderFailed { inner })?;
+    encode(&mut bytes, &data).map_err(|inner| Error::EncodingFailed { inner })?;
+    Ok(bytes)
 }
 
+/// Save raw bytes to `path`, replacing the file atomically where supported.
+///
+/// # Errors
+///
+/// If the file fails to save, then an error is returned.
 pub fn save_raw(path: impl AsRef<Path>, value: &[u8]) -> Result<()> {
-    let mut writer = fs::writer_impl(path)?;
-    writer
-        .write_all(value)
-        .map_err(|err| Error::io(err, "failed to save data"))?;
-    Ok(())
+    fs::write_atomic_impl(path, value)
 }
 
 pub fn load<T>(path: impl AsRef<Path>) -> Result<T>
@@ -158,16 +666,9 @@ where
     T: DeserializeOwned,
 {
     let mut reader = fs::reader_impl(path)?;
-    validate_header(&mut reader)?;
+    let version = validate_header(&mut reader)?;
     let data = decode(&mut reader)?;
-    Ok(
-        bincode::decode_from_slice::<Compat<T>, _>(&data, bincode::config::standard())
-            .map_err(|err| Error::DeserializationFailed {
-                inner: err.to_string(),
-            })?
-            .0
-             .0,
-    )
+    deserialize(&data, version)
 }
 
 pub fn load_bytes<T>(bytes: &[u8]) -> Result<T>
@@ -175,16 +676,9 @@ where
     T: DeserializeOwned,
 {
     let mut reader = Cursor::new(bytes);
-    validate_header(&mut reader)?;
+    let version = validate_header(&mut reader)?;
     let data = decode(&mut reader)?;
-    Ok(
-        bincode::decode_from_slice::<Compat<T>, _>(&data, bincode::config::standard())
-            .map_err(|err| Error::SerializationFailed {
-                inner: err.to_string(),
-            })?
-            .0
-             .0,
-    )
+    deserialize(&data, version)
 }
 
 pub fn load_raw(path: impl AsRef<Path>) -> Result<Vec<u8>> {
@@ -200,6 +694,11 @@ pub fn clear_dir(path: impl AsRef<Path>) -> Result<()> {
     fs::clear_dir_impl(path)
 }
 
+/// Whether a file or directory exists at `path`.
+pub fn exists(path: impl AsRef<Path>) -> bool {
+    fs::exists_impl(path)
+}
+
 pub fn filename(path: &Path) -> &str {
     path.file_name()
         .and_then(|s| s.to_str())
@@ -259,6 +758,61 @@ const CRC_TABLE: [u32; 256] = [
     0xB3667A2E, 0xC4614AB8, 0x5D681B02, 0x2A6F2B94, 0xB40BBE37, 0xC30C8EA1, 0x5A05DF1B, 0x2D02EF8D,
 ];
 
+/// Compute the SHA-1 digest of `data`, used to identify ROMs in databases.
+///
+/// See: <https://datatracker.ietf.org/doc/html/rfc3174>
+#[must_use]
+pub fn compute_sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0x00);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, w) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*w);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (state, val) in state.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(val);
+        }
+    }
+
+    let mut digest = [0x00; 20];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
 #[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
@@ -267,10 +821,79 @@ mod tests {
     fn save_header() {
         let mut file = Vec::new();
         assert!(write_header(&mut file).is_ok(), "write header");
-        assert!(
-            validate_header(&mut file.as_slice()).is_ok(),
+        assert_eq!(
+            validate_header(&mut file.as_slice()).ok(),
+            Some(SAVE_VERSION),
             "validate header"
         );
+
+        let mut file = SAVE_FILE_MAGIC.to_vec();
+        file.push(SAVE_VERSION - 1);
+        assert_eq!(
+            validate_header(&mut file.as_slice()).ok(),
+            Some(SAVE_VERSION - 1),
+            "previous version"
+        );
+
+        let mut file = SAVE_FILE_MAGIC.to_vec();
+        file.push(LEGACY_SAVE_VERSION);
+        assert_eq!(
+            validate_header(&mut file.as_slice()).ok(),
+            Some(1),
+            "legacy version"
+        );
+
+        let mut file = SAVE_FILE_MAGIC.to_vec();
+        file.push(SAVE_VERSION + 1);
+        assert!(
+            validate_header(&mut file.as_slice()).is_err(),
+            "newer version"
+        );
+    }
+
+    #[test]
+    fn versioned_fields() {
+        fn doubled<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> core::result::Result<u16, D::Error> {
+            changed_in::<2, _, _, _>(deserializer, |value: u8| u16::from(value) * 2)
+        }
+
+        #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+        struct Data {
+            #[serde(deserialize_with = "doubled")]
+            value: u16,
+            #[serde(deserialize_with = "added_in::<2, _, _>")]
+            added: Vec<u8>,
+        }
+
+        let data = vec![Some(Data {
+            value: 300,
+            added: vec![1, 2],
+        })];
+        let bytes = save_bytes(&data).expect("saved");
+        assert_eq!(
+            load_bytes::<Vec<Option<Data>>>(&bytes).expect("loaded"),
+            data,
+            "current version"
+        );
+
+        let mut bytes = SAVE_FILE_MAGIC.to_vec();
+        bytes.push(LEGACY_SAVE_VERSION);
+        let old = bincode::encode_to_vec(
+            BorrowCompat(&vec![Some((5u8,))]),
+            bincode::config::standard(),
+        )
+        .expect("serialized");
+        encode(&mut bytes, &old).expect("encoded");
+        assert_eq!(
+            load_bytes::<Vec<Option<Data>>>(&bytes).expect("loaded"),
+            [Some(Data {
+                value: 10,
+                added: vec![],
+            })],
+            "previous version"
+        );
     }
 
     #[test]
@@ -278,4 +901,43 @@ mod tests {
         let s = "Lorem ipsum dolor sit amet, consectetur adipisicing elit";
         assert_eq!(compute_crc32(s.as_bytes()), 0xb9b4cbd5);
     }
+
+    #[test]
+    fn sha1() {
+        let hex = |digest: [u8; 20]| {
+            digest
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>()
+        };
+        assert_eq!(
+            hex(compute_sha1(b"")),
+            "da39a3ee5e6b4b0d3255bfef95601890afd80709"
+        );
+        assert_eq!(
+            hex(compute_sha1(b"abc")),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            hex(compute_sha1(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            )),
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+        );
+    }
+
+    #[test]
+    fn save_atomic() {
+        let path = std::env::temp_dir()
+            .join(format!("tetanes_save_atomic_{}", std::process::id()))
+            .join("data.sav");
+        save(&path, &vec![1u8, 2, 3]).expect("saved");
+        save(&path, &vec![4u8, 5, 6]).expect("saved");
+        assert_eq!(load::<Vec<u8>>(&path).expect("loaded"), [4, 5, 6]);
+        assert!(
+            !path.with_file_name("data.sav.tmp").exists(),
+            "temp file renamed"
+        );
+        let _ = std::fs::remove_dir_all(path.parent().expect("parent dir"));
+    }
 }
@@ -6,6 +6,11 @@ use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use snafu::Snafu;
 
+/// Game Genie letters, indexed by their binary representation.
+const GENIE_LETTERS: [char; 16] = [
+    'A', 'P', 'Z', 'L', 'G', 'I', 'T', 'Y', 'E', 'O', 'X', 'U', 'K', 'S', 'V', 'N',
+];
+
 lazy_static! {
     static ref GENIE_MAP: HashMap<char, u8> = GenieCode::generate_genie_map();
 }
@@ -39,6 +44,8 @@ pub enum ErrorKind {
     InvalidLength { inner: usize },
     #[snafu(display("invalid character: `{inner}`"))]
     InvalidCharacter { inner: char },
+    #[snafu(display("address must be in the range $8000-$FFFF. found `${inner:04X}`"))]
+    InvalidAddress { inner: u16 },
 }
 
 /// Game Genie Code
@@ -90,6 +97,40 @@ impl GenieCode {
         }
     }
 
+    /// Creates a new `GenieCode` instance by encoding a raw `addr`, `data`, and optional
+    /// `compare` patch. Patches with a `compare` value are encoded as 8 letter codes.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `addr` is not within PRG-ROM (`$8000-$FFFF`).
+    pub fn encode(addr: u16, data: u8, compare: Option<u8>) -> Result<Self> {
+        if addr < 0x8000 {
+            return Err(Error::new(
+                alloc::format!("{addr:04X}:{data:02X}"),
+                ErrorKind::InvalidAddress { inner: addr },
+            ));
+        }
+        let mut hex = Vec::with_capacity(8);
+        hex.push((data & 7) | ((data >> 4) & 8));
+        hex.push(((data >> 4) & 7) | ((addr >> 4) as u8 & 8));
+        hex.push((addr >> 4) as u8 & 7);
+        hex.push(((addr >> 12) as u8 & 7) | (addr as u8 & 8));
+        hex.push((addr as u8 & 7) | ((addr >> 8) as u8 & 8));
+        hex.push((addr >> 8) as u8 & 7);
+        match compare {
+            Some(compare) => {
+                // The high bit of the third letter flags an 8 letter code
+                hex[2] |= 8;
+                hex[5] |= compare & 8;
+                hex.push((compare & 7) | ((compare >> 4) & 8));
+                hex.push(((compare >> 4) & 7) | (data & 8));
+            }
+            None => hex[5] |= data & 8,
+        }
+        let code = hex.iter().map(|&h| GENIE_LETTERS[usize::from(h)]).collect();
+        Ok(Self::from_raw(code, hex))
+    }
+
     fn generate_genie_map() -> HashMap<char, u8> {
         // Game genie maps these letters to binary representations as a form of code obfuscation
         HashMap::from([
@@ -140,6 +181,16 @@ impl GenieCode {
         self.addr
     }
 
+    #[must_use]
+    pub const fn data(&self) -> u8 {
+        self.data
+    }
+
+    #[must_use]
+    pub const fn compare(&self) -> Option<u8> {
+        self.compare
+    }
+
     #[must_use]
     pub const fn read(&self, val: u8) -> u8 {
         if let Some(compare) = self.compare {
@@ -154,8 +205,108 @@ impl GenieCode {
     }
 }
 
+/// Difference between the Game Genie codes recorded in a save state or replay and the codes
+/// currently active.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct CheatMismatch {
+    /// Codes recorded but not currently active.
+    pub recorded: Vec<String>,
+    /// Codes currently active but not recorded.
+    pub active: Vec<String>,
+}
+
+impl CheatMismatch {
+    /// Compare `recorded` codes against `active` codes, returning `None` if they match.
+    pub fn between<'a>(
+        recorded: impl IntoIterator<Item = &'a GenieCode>,
+        active: impl IntoIterator<Item = &'a GenieCode> + Clone,
+    ) -> Option<Self> {
+        let recorded = recorded.into_iter().collect::<Vec<_>>();
+        let mut mismatch = Self {
+            recorded: recorded
+                .iter()
+                .filter(|code| !active.clone().into_iter().any(|active| active == **code))
+                .map(|code| code.code().into())
+                .collect(),
+            active: active
+                .into_iter()
+                .filter(|code| !recorded.contains(code))
+                .map(|code| code.code().into())
+                .collect(),
+        };
+        if mismatch.recorded.is_empty() && mismatch.active.is_empty() {
+            return None;
+        }
+        mismatch.recorded.sort();
+        mismatch.active.sort();
+        Some(mismatch)
+    }
+}
+
+impl core::fmt::Display for CheatMismatch {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let none = || String::from("none");
+        write!(
+            f,
+            "recorded cheats: {}. active cheats: {}",
+            if self.recorded.is_empty() {
+                none()
+            } else {
+                self.recorded.join(", ")
+            },
+            if self.active.is_empty() {
+                none()
+            } else {
+                self.active.join(", ")
+            },
+        )
+    }
+}
+
 impl core::fmt::Display for GenieCode {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", &self.code)
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn encode_decode() {
+        let code = GenieCode::new("AATOZE".to_string()).expect("valid code");
+        assert_eq!(code.addr(), 0x906A);
+        assert_eq!(code.data(), 0x08);
+        assert_eq!(code.compare(), None);
+        let encoded =
+            GenieCode::encode(code.addr(), code.data(), code.compare()).expect("valid patch");
+        assert_eq!(encoded, code);
+
+        let code = GenieCode::encode(0xD1DD, 0x14, Some(0x03)).expect("valid patch");
+        assert_eq!(code.code().len(), 8);
+        let decoded = GenieCode::new(code.code().to_string()).expect("valid code");
+        assert_eq!(decoded.addr(), 0xD1DD);
+        assert_eq!(decoded.data(), 0x14);
+        assert_eq!(decoded.compare(), Some(0x03));
+
+        assert!(matches!(
+            GenieCode::encode(0x0700, 0x00, None).map_err(|err| err.kind()),
+            Err(ErrorKind::InvalidAddress { inner: 0x0700 })
+        ));
+    }
+
+    #[test]
+    fn cheat_mismatch() {
+        let code = |code: &str| GenieCode::new(code.to_string()).expect("valid code");
+        let recorded = [code("AATOZE"), code("SXIOPO")];
+        let active = [code("SXIOPO"), code("GOSSIP")];
+
+        assert_eq!(CheatMismatch::between(&recorded, &recorded), None);
+        let mismatch = CheatMismatch::between(&recorded, &active).expect("mismatch");
+        assert_eq!(mismatch.recorded, ["AATOZE"]);
+        assert_eq!(mismatch.active, ["GOSSIP"]);
+    }
+}
@@ -0,0 +1,328 @@
+//! Headless environment for driving emulation programmatically one step at a time, e.g. as a
+//! gym-style environment for reinforcement learning.
+//!
+//! ```rust no_run
+//! use tetanes_core::{headless::Environment, input::JoypadBtnState, prelude::*};
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let rom = std::fs::read("some_awesome_game.nes")?;
+//!     let mut env = Environment::new(Config::default(), "some_awesome_game", rom)?;
+//!     env.set_frame_skip(4);
+//!     env.set_max_frames(Some(60 * 60));
+//!     // Game over when lives at $075A reach zero
+//!     env.set_done_condition(|deck| deck.wram()[0x075A] == 0);
+//!
+//!     env.reset(42)?;
+//!     loop {
+//!         let step = env.step(JoypadBtnState::RIGHT | JoypadBtnState::A)?;
+//!         // Feed `step.frame` and `step.audio` to an agent
+//!         if step.done {
+//!             break;
+//!         }
+//!     }
+//!     println!("score: {}", env.ram_bcd(0x07DD, 6));
+//!
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Each [`Environment`] owns all of its emulation state, so several can run side by side, e.g. one
+//! per thread. Only the ROM databases used when loading ROMs are shared across the process.
+
+use crate::{
+    control_deck::{Config, ControlDeck, Result},
+    input::{JoypadBtnState, Player},
+    mem::{Access, Mem, RamState},
+    ppu::Ppu,
+};
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// Condition checked after every step to determine whether an episode is done.
+pub type DoneCondition = Box<dyn Fn(&ControlDeck) -> bool + Send>;
+
+/// Output of a single [`Environment::step`].
+#[derive(Debug)]
+#[must_use]
+pub struct Step<'a> {
+    /// RGBA frame buffer of the last frame clocked.
+    pub frame: &'a [u8],
+    /// Audio samples generated across all frames clocked.
+    pub audio: &'a [f32],
+    /// Frames clocked since the last [`Environment::reset`].
+    pub frames: u32,
+    /// Whether the episode is done, either from reaching the maximum number of frames or from the
+    /// [`DoneCondition`] being met.
+    pub done: bool,
+}
+
+/// A headless emulation environment that clocks a [`ControlDeck`] in discrete steps with
+/// deterministic, seeded resets.
+#[must_use]
+pub struct Environment {
+    deck: ControlDeck,
+    name: String,
+    rom: Vec<u8>,
+    seed: u64,
+    frame_skip: u32,
+    max_frames: Option<u32>,
+    frames: u32,
+    done_condition: Option<DoneCondition>,
+}
+
+impl core::fmt::Debug for Environment {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Environment")
+            .field("name", &self.name)
+            .field("seed", &self.seed)
+            .field("frame_skip", &self.frame_skip)
+            .field("max_frames", &self.max_frames)
+            .field("frames", &self.frames)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Environment {
+    /// Width of frames returned by [`Environment::step`].
+    pub const FRAME_WIDTH: u32 = Ppu::WIDTH;
+    /// Height of frames returned by [`Environment::step`].
+    pub const FRAME_HEIGHT: u32 = Ppu::HEIGHT;
+
+    /// Create a new environment with the given ROM loaded and seeded with `0`.
+    ///
+    /// Battery-backed RAM is never loaded from or saved to disk so that episodes are reproducible.
+    ///
+    /// # Errors
+    ///
+    /// If the ROM fails to load, then an error is returned.
+    pub fn new(mut cfg: Config, name: impl ToString, rom: Vec<u8>) -> Result<Self> {
+        cfg.data_dir = None;
+        let mut env = Self {
+            deck: ControlDeck::with_config(cfg),
+            name: name.to_string(),
+            rom,
+            seed: 0,
+            frame_skip: 1,
+            max_frames: None,
+            frames: 0,
+            done_condition: None,
+        };
+        env.reset(0)?;
+        Ok(env)
+    }
+
+    /// Reload the ROM with RAM initialized from `seed`, starting a new episode. Resetting with the
+    /// same seed and stepping with the same actions always produces the same output.
+    ///
+    /// # Errors
+    ///
+    /// If the ROM fails to load, then an error is returned.
+    pub fn reset(&mut self, seed: u64) -> Result<()> {
+        self.seed = seed;
+        self.frames = 0;
+        self.deck
+            .set_rom_ram_state(self.name.clone(), Some(RamState::Seeded(seed)));
+        self.deck.load_rom(&self.name, &mut self.rom.as_slice())?;
+        Ok(())
+    }
+
+    /// Press `buttons` for player one and clock [`Environment::frame_skip`] frames.
+    ///
+    /// # Errors
+    ///
+    /// If the CPU encounters an invalid opcode, then an error is returned.
+    pub fn step(&mut self, buttons: JoypadBtnState) -> Result<Step<'_>> {
+        self.step_players(&[buttons])
+    }
+
+    /// Press `buttons` for each player, in order starting with player one, and clock
+    /// [`Environment::frame_skip`] frames.
+    ///
+    /// # Errors
+    ///
+    /// If the CPU encounters an invalid opcode, then an error is returned.
+    pub fn step_players(&mut self, buttons: &[JoypadBtnState]) -> Result<Step<'_>> {
+        let players = [Player::One, Player::Two, Player::Three, Player::Four];
+        for (player, buttons) in players.into_iter().zip(buttons) {
+            self.deck.joypad_mut(player).buttons = *buttons;
+        }
+
+        self.deck.clear_audio_samples();
+        for _ in 0..self.frame_skip {
+            self.deck.clock_frame()?;
+            self.frames += 1;
+        }
+
+        let done = self.is_done();
+        let (frame, audio) = self.deck.frame_output();
+        Ok(Step {
+            frame,
+            audio,
+            frames: self.frames,
+            done,
+        })
+    }
+
+    /// Whether the current episode is done.
+    #[must_use]
+    pub fn is_done(&self) -> bool {
+        self.max_frames.is_some_and(|max| self.frames >= max)
+            || self
+                .done_condition
+                .as_ref()
+                .is_some_and(|done| done(&self.deck))
+            || !self.deck.is_running()
+    }
+
+    /// The seed used for the last [`Environment::reset`].
+    #[must_use]
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Number of frames clocked with the same buttons for each [`Environment::step`].
+    #[must_use]
+    pub const fn frame_skip(&self) -> u32 {
+        self.frame_skip
+    }
+
+    /// Set the number of frames clocked with the same buttons for each [`Environment::step`].
+    pub fn set_frame_skip(&mut self, frame_skip: u32) {
+        self.frame_skip = frame_skip.max(1);
+    }
+
+    /// Set the maximum number of frames before an episode is done.
+    pub fn set_max_frames(&mut self, max_frames: Option<u32>) {
+        self.max_frames = max_frames;
+    }
+
+    /// Set a condition checked after each step to determine whether an episode is done, e.g. by
+    /// inspecting RAM for a game over.
+    pub fn set_done_condition(&mut self, done: impl Fn(&ControlDeck) -> bool + Send + 'static) {
+        self.done_condition = Some(Box::new(done));
+    }
+
+    /// Internal CPU RAM (`$0000-$07FF`).
+    #[must_use]
+    pub fn ram(&self) -> &[u8] {
+        self.deck.wram()
+    }
+
+    /// Read a byte from the CPU address space without side effects.
+    #[must_use]
+    pub fn peek(&self, addr: u16) -> u8 {
+        self.deck.bus().peek(addr, Access::Dummy)
+    }
+
+    /// Read a little-endian word from the CPU address space without side effects.
+    #[must_use]
+    pub fn peek_u16(&self, addr: u16) -> u16 {
+        self.deck.bus().peek_u16(addr, Access::Dummy)
+    }
+
+    /// Decode `len` binary-coded decimal digits stored one per byte starting at `addr`, most
+    /// significant digit first, as commonly used for scores.
+    #[must_use]
+    pub fn ram_bcd(&self, addr: u16, len: u16) -> u64 {
+        (addr..addr.saturating_add(len)).fold(0, |value, addr| {
+            value * 10 + u64::from(self.peek(addr) & 0x0F)
+        })
+    }
+
+    /// The underlying [`ControlDeck`].
+    pub const fn deck(&self) -> &ControlDeck {
+        &self.deck
+    }
+
+    /// The underlying mutable [`ControlDeck`].
+    pub fn deck_mut(&mut self) -> &mut ControlDeck {
+        &mut self.deck
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    fn env() -> Environment {
+        let rom = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_roms/spritecans.nes"
+        ))
+        .expect("valid rom");
+        Environment::new(Config::default(), "spritecans", rom).expect("valid environment")
+    }
+
+    #[test]
+    fn environment_deterministic() {
+        let run = |seed| {
+            let mut env = env();
+            env.set_frame_skip(2);
+            env.set_max_frames(Some(20));
+            env.reset(seed).expect("valid reset");
+            let mut steps = 0;
+            loop {
+                steps += 1;
+                let step = env.step(JoypadBtnState::A).expect("valid step");
+                assert!(!step.audio.is_empty());
+                if step.done {
+                    assert_eq!(step.frames, 20);
+                    return (steps, step.frame.to_vec(), env.ram().to_vec());
+                }
+            }
+        };
+
+        let (steps, frame, ram) = run(42);
+        assert_eq!(steps, 10);
+        assert_eq!(run(42), (steps, frame, ram));
+    }
+
+    #[test]
+    fn environment_seeded_ram() {
+        let mut env = env();
+        env.reset(7).expect("valid reset");
+        let seeded = RamState::filled(0x0800, RamState::Seeded(7));
+        // The reset vector may have written to RAM, but most of it should be untouched
+        let matching = env
+            .ram()
+            .iter()
+            .zip(&seeded)
+            .filter(|(a, b)| a == b)
+            .count();
+        assert!(matching > 0x0700);
+        assert_eq!(env.seed(), 7);
+    }
+
+    #[test]
+    fn environments_independent() {
+        let run = |seed| {
+            let mut env = env();
+            env.reset(seed).expect("valid reset");
+            (0..10)
+                .map(|_| {
+                    env.step(JoypadBtnState::START)
+                        .expect("valid step")
+                        .frame
+                        .to_vec()
+                })
+                .collect::<Vec<_>>()
+        };
+        let expected = (run(1), run(2));
+
+        // Interleave steps so any state shared between environments would leak across them
+        let (mut first, mut second) = (env(), env());
+        first.reset(1).expect("valid reset");
+        second.reset(2).expect("valid reset");
+        let mut frames = (Vec::new(), Vec::new());
+        for _ in 0..10 {
+            let step = first.step(JoypadBtnState::START).expect("valid step");
+            frames.0.push(step.frame.to_vec());
+            let step = second.step(JoypadBtnState::START).expect("valid step");
+            frames.1.push(step.frame.to_vec());
+        }
+        assert_eq!(frames, expected);
+    }
+}
@@ -1,8 +1,9 @@
-//! [`Joypad`] and [`Zapper`] implementation.
+//! [`Joypad`], [`Zapper`], [`VausPaddle`], [`Mouse`] and [`VsSystem`] implementation.
 
 use crate::{
     common::{Clock, NesRegion, Reset, ResetKind},
     cpu::Cpu,
+    input::expansion::{Expansion, ExpansionDevice},
     ppu::Ppu,
 };
 use bitflags::bitflags;
@@ -11,6 +12,8 @@ use serde::{Deserialize, Serialize};
 use snafu::Snafu;
 use tracing::trace;
 
+pub mod expansion;
+
 #[derive(Snafu, Debug)]
 #[must_use]
 #[snafu(display("failed to parse `Player`"))]
@@ -74,13 +77,25 @@ pub trait InputRegisters {
 pub enum FourPlayer {
     #[default]
     Disabled,
+    /// NES Four Score. Controllers 3 and 4 are reported on D0 after controllers 1 and 2, followed
+    /// by a signature.
     FourScore,
+    /// Famicom expansion port. Controllers 3 and 4 are reported on D1 alongside controllers 1 and
+    /// 2.
     Satellite,
+    /// Hori 4 Players Adapter for the Famicom expansion port. Controllers 3 and 4 are reported on
+    /// D1 after controllers 1 and 2, followed by a signature swapped from the Four Score.
+    FamicomAdapter,
 }
 
 impl FourPlayer {
     pub const fn as_slice() -> &'static [Self] {
-        &[Self::Disabled, Self::FourScore, Self::Satellite]
+        &[
+            Self::Disabled,
+            Self::FourScore,
+            Self::Satellite,
+            Self::FamicomAdapter,
+        ]
     }
 
     pub const fn as_str(&self) -> &'static str {
@@ -88,6 +103,7 @@ impl FourPlayer {
             Self::Disabled => "disabled",
             Self::FourScore => "four-score",
             Self::Satellite => "satellite",
+            Self::FamicomAdapter => "famicom-adapter",
         }
     }
 }
@@ -104,6 +120,7 @@ impl core::fmt::Display for FourPlayer {
             Self::Disabled => "Disabled",
             Self::FourScore => "FourScore",
             Self::Satellite => "Satellite",
+            Self::FamicomAdapter => "FamicomAdapter",
         };
         write!(f, "{s}")
     }
@@ -116,8 +133,9 @@ impl FromStr for FourPlayer {
             "disabled" => Ok(Self::Disabled),
             "four-score" => Ok(Self::FourScore),
             "satellite" => Ok(Self::Satellite),
+            "famicom-adapter" => Ok(Self::FamicomAdapter),
             _ => Err(
-                "invalid FourPlayer value. valid options: `disabled`, `four-score`, or `satellite`",
+                "invalid FourPlayer value. valid options: `disabled`, `four-score`, `satellite`, or `famicom-adapter`",
             ),
         }
     }
@@ -129,8 +147,17 @@ pub struct Input {
     pub joypads: [Joypad; 4],
     pub signatures: [Joypad; 2],
     pub zapper: Zapper,
+    /// Arkanoid Vaus paddle for the NES, connected to controller port 2.
+    #[serde(deserialize_with = "crate::fs::added_in::<4, _, _>")]
+    pub vaus: VausPaddle,
+    #[serde(deserialize_with = "crate::fs::added_in::<5, _, _>")]
+    pub mouse: Mouse,
+    #[serde(deserialize_with = "crate::fs::added_in::<6, _, _>")]
+    pub vs: VsSystem,
     pub turbo_timer: u32,
     pub four_player: FourPlayer,
+    #[serde(deserialize_with = "crate::fs::added_in::<3, _, _>")]
+    pub expansion: Expansion,
 }
 
 impl Input {
@@ -143,8 +170,12 @@ impl Input {
                 Joypad::from_bytes(0b0000_0100),
             ],
             zapper: Zapper::new(region),
+            vaus: VausPaddle::new(),
+            mouse: Mouse::new(),
+            vs: VsSystem::new(region),
             turbo_timer: 30,
             four_player: FourPlayer::default(),
+            expansion: Expansion::new(region),
         }
     }
 
@@ -158,6 +189,21 @@ impl Input {
 
     pub fn set_region(&mut self, region: NesRegion) {
         self.zapper.trigger_release_delay = Cpu::region_clock_rate(region) / 10.0;
+        self.vs.set_region(region);
+        self.expansion.set_region(region);
+    }
+
+    /// Returns the bits of `$4016` or `$4017` driven by input devices for `player`. The remaining
+    /// bits are open bus.
+    #[must_use]
+    pub const fn driven_bits(&self, player: Player) -> u8 {
+        if !self.vs.enabled {
+            0x1F
+        } else if matches!(player, Player::One) {
+            0x7F
+        } else {
+            0xFF
+        }
     }
 
     pub fn set_concurrent_dpad(&mut self, enabled: bool) {
@@ -170,16 +216,47 @@ impl Input {
         self.zapper.connected = connected;
     }
 
+    pub fn connect_vaus(&mut self, connected: bool) {
+        self.vaus.connected = connected;
+    }
+
+    /// Turn any connected Arkanoid Vaus paddle, from `0.0` all the way left to `1.0` all the way
+    /// right.
+    pub fn aim_vaus(&mut self, x: f32) {
+        self.vaus.aim(x);
+        self.expansion.vaus.aim(x);
+    }
+
+    /// Press or release the button of any connected Arkanoid Vaus paddle.
+    pub fn set_vaus_button(&mut self, pressed: bool) {
+        self.vaus.set_button(pressed);
+        self.expansion.vaus.set_button(pressed);
+    }
+
+    /// Connect the [`Mouse`] to the controller port for `player`, replacing its [`Joypad`], or
+    /// disconnect it if `None`. Only [`Player::One`] and [`Player::Two`] have a controller port.
+    pub fn connect_mouse(&mut self, port: Option<Player>) {
+        self.mouse.port = port.filter(|player| matches!(player, Player::One | Player::Two));
+    }
+
     pub fn set_four_player(&mut self, four_player: FourPlayer) {
         self.four_player = four_player;
         self.reset(ResetKind::Hard);
     }
 
+    pub fn connect_expansion(&mut self, device: ExpansionDevice) {
+        self.expansion.connect(device);
+    }
+
     pub fn clear(&mut self) {
         for pad in &mut self.joypads {
             pad.clear();
         }
         self.zapper.clear();
+        self.vaus.clear();
+        self.mouse.clear();
+        self.vs.clear();
+        self.expansion.clear();
     }
 }
 
@@ -188,11 +265,30 @@ impl InputRegisters for Input {
         // Read $4016/$4017 D0 8x for controller #1/#2.
         // Read $4016/$4017 D0 8x for controller #3/#4.
         // Read $4016/$4017 D0 8x for signature: 0b00010000/0b00100000
-        let zapper = if player == Player::Two {
+        // Famicom adapter reports on D1 with signature: 0b00100000/0b00010000
+        let vs = if self.vs.enabled {
+            // The Vs. Zapper replaces the controller on $4016
+            if player == Player::One && self.zapper.connected {
+                return self.vs.read(player) | self.vs.read_zapper(&self.zapper, ppu);
+            }
+            self.vs.read(player)
+        } else {
+            0x00
+        };
+        let zapper = if player == Player::Two && !self.vs.enabled {
             self.zapper.read(ppu)
         } else {
             0x00
         };
+        let vaus = if player == Player::Two {
+            self.vaus.read_nes()
+        } else {
+            0x00
+        };
+        let expansion = self.expansion.read(player);
+        if self.mouse.port == Some(player) {
+            return vs | zapper | vaus | expansion | self.mouse.read();
+        }
 
         let player = player as usize;
         assert!(player < 4);
@@ -212,20 +308,52 @@ impl InputRegisters for Input {
             FourPlayer::Satellite => {
                 self.joypads[player].read() | (self.joypads[player + 2].read() << 1)
             }
+            // Signatures are swapped from the Four Score
+            FourPlayer::FamicomAdapter => {
+                let val = if self.joypads[player].index() < 8 {
+                    self.joypads[player].read()
+                } else if self.joypads[player + 2].index() < 8 {
+                    self.joypads[player + 2].read()
+                } else if self.signatures[player ^ 1].index() < 8 {
+                    self.signatures[player ^ 1].read()
+                } else {
+                    0x01
+                };
+                val << 1
+            }
         };
 
-        zapper | val | 0x40
+        vs | zapper | vaus | expansion | val
     }
 
     fn peek(&self, player: Player, ppu: &Ppu) -> u8 {
         // Read $4016/$4017 D0 8x for controller #1/#2.
         // Read $4016/$4017 D0 8x for controller #3/#4.
         // Read $4016/$4017 D0 8x for signature: 0b00010000/0b00100000
-        let zapper = if player == Player::Two {
+        // Famicom adapter reports on D1 with signature: 0b00100000/0b00010000
+        let vs = if self.vs.enabled {
+            // The Vs. Zapper replaces the controller on $4016
+            if player == Player::One && self.zapper.connected {
+                return self.vs.read(player) | self.vs.peek_zapper(&self.zapper, ppu);
+            }
+            self.vs.read(player)
+        } else {
+            0x00
+        };
+        let zapper = if player == Player::Two && !self.vs.enabled {
             self.zapper.read(ppu)
         } else {
             0x00
         };
+        let vaus = if player == Player::Two {
+            self.vaus.peek_nes()
+        } else {
+            0x00
+        };
+        let expansion = self.expansion.peek(player);
+        if self.mouse.port == Some(player) {
+            return vs | zapper | vaus | expansion | self.mouse.peek();
+        }
 
         let player = player as usize;
         assert!(player < 4);
@@ -245,9 +373,22 @@ impl InputRegisters for Input {
             FourPlayer::Satellite => {
                 self.joypads[player].peek() | (self.joypads[player + 2].peek() << 1)
             }
+            // Signatures are swapped from the Four Score
+            FourPlayer::FamicomAdapter => {
+                let val = if self.joypads[player].index() < 8 {
+                    self.joypads[player].peek()
+                } else if self.joypads[player + 2].index() < 8 {
+                    self.joypads[player + 2].peek()
+                } else if self.signatures[player ^ 1].index() < 8 {
+                    self.signatures[player ^ 1].peek()
+                } else {
+                    0x01
+                };
+                val << 1
+            }
         };
 
-        zapper | val | 0x40
+        vs | zapper | vaus | expansion | val
     }
 
     fn write(&mut self, val: u8) {
@@ -257,12 +398,18 @@ impl InputRegisters for Input {
         for sig in &mut self.signatures {
             sig.write(val);
         }
+        self.vaus.write(val);
+        self.mouse.write(val);
+        self.vs.write(val);
+        self.expansion.write(val);
     }
 }
 
 impl Clock for Input {
     fn clock(&mut self) -> usize {
         self.zapper.clock();
+        self.vs.clock();
+        self.expansion.clock();
         if self.turbo_timer > 0 {
             self.turbo_timer -= 1;
         }
@@ -292,6 +439,10 @@ impl Reset for Input {
         self.signatures[0] = Joypad::from_bytes(0b0000_1000);
         self.signatures[1] = Joypad::from_bytes(0b0000_0100);
         self.zapper.reset(kind);
+        self.vaus.reset(kind);
+        self.mouse.reset(kind);
+        self.vs.reset(kind);
+        self.expansion.reset(kind);
     }
 }
 
@@ -377,6 +528,10 @@ pub struct Joypad {
     pub concurrent_dpad: bool,
     pub index: u8,
     pub strobe: bool,
+    /// `4021` shift register, loaded with the button states while strobe is high and shifted
+    /// out on each read after that, filling with `1`s.
+    #[serde(deserialize_with = "crate::fs::added_in::<7, _, _>")]
+    pub shift_register: u8,
 }
 
 impl Joypad {
@@ -386,6 +541,7 @@ impl Joypad {
             concurrent_dpad: false,
             index: 0,
             strobe: false,
+            shift_register: 0x00,
         }
     }
 
@@ -416,32 +572,43 @@ impl Joypad {
             concurrent_dpad: false,
             index: 0,
             strobe: false,
+            shift_register: 0x00,
         }
     }
 
+    /// Read the next button state, clocking the shift register unless strobe is high.
     #[must_use]
     pub fn read(&mut self) -> u8 {
         let val = self.peek();
-        if !self.strobe && self.index < 8 {
-            self.index += 1;
+        if !self.strobe {
+            // Official controllers shift in `1`s, so reads past the 8th button return `1`
+            self.shift_register = (self.shift_register >> 1) | 0x80;
+            if self.index < 8 {
+                self.index += 1;
+            }
         }
         val
     }
 
+    /// Peek at the next button state. While strobe is high, the shift register is continuously
+    /// reloaded, so only the current state of `A` is returned.
     #[must_use]
     pub const fn peek(&self) -> u8 {
-        if self.index < 8 {
-            ((self.buttons.bits() as u8) & (1 << self.index)) >> self.index
+        if self.strobe {
+            (self.buttons.bits() as u8) & 0x01
         } else {
-            0x01
+            self.shift_register & 0x01
         }
     }
 
     pub fn write(&mut self, val: u8) {
         let prev_strobe = self.strobe;
         self.strobe = val & 0x01 == 0x01;
-        if prev_strobe && !self.strobe {
+        // The shift register is reloaded while strobe is high and keeps the last button states
+        // loaded once it goes low
+        if prev_strobe || self.strobe {
             self.index = 0;
+            self.shift_register = self.buttons.bits() as u8;
         }
     }
 
@@ -460,6 +627,7 @@ impl Reset for Joypad {
         self.buttons = JoypadBtnState::empty();
         self.index = 0;
         self.strobe = false;
+        self.shift_register = 0x00;
     }
 }
 
@@ -485,6 +653,17 @@ impl Zapper {
         self.y
     }
 
+    /// Returns the radius, in pixels, around the aim position sensed by the photodiode.
+    #[must_use]
+    pub const fn detection_radius(&self) -> u32 {
+        self.radius
+    }
+
+    /// Set the radius, in pixels, around the aim position sensed by the photodiode.
+    pub fn set_detection_radius(&mut self, radius: u32) {
+        self.radius = radius;
+    }
+
     pub fn trigger(&mut self) {
         if self.triggered <= 0.0 {
             self.triggered = self.trigger_release_delay;
@@ -505,6 +684,11 @@ impl Zapper {
 }
 
 impl Zapper {
+    /// Number of scanlines the photodiode continues to sense light after a pixel is drawn.
+    const LIGHT_SENSE_SCANLINES: u32 = 20;
+    /// Minimum average luminance within the detection radius to register as light.
+    const LIGHT_THRESHOLD: u32 = 0x60;
+
     fn new(region: NesRegion) -> Self {
         Self {
             triggered: 0.0,
@@ -534,27 +718,47 @@ impl Zapper {
         }
     }
 
+    /// Samples the photodiode, returning `0x00` when light is detected and `0x08` otherwise.
+    ///
+    /// The photodiode sees every pixel within the detection radius, but only pixels drawn by the
+    /// PPU within the last [`Zapper::LIGHT_SENSE_SCANLINES`] scanlines are still bright enough to
+    /// register. Pixels not yet drawn this frame, or drawn too long ago, contribute no light.
     fn light_sense(&self, ppu: &Ppu) -> u8 {
-        let width = Ppu::WIDTH;
-        let height = Ppu::HEIGHT;
-        let scanline = ppu.scanline;
-        let cycle = ppu.cycle;
-        let min_y = self.y.saturating_sub(self.radius);
-        let max_y = (self.y + self.radius).min(height - 1);
-        let min_x = self.x.saturating_sub(self.radius);
-        let max_x = (self.x + self.radius).min(width - 1);
-        for y in min_y..=max_y {
-            for x in min_x..=max_x {
-                let behind_ppu =
-                    scanline >= y && (scanline - y) <= 20 && (scanline != y || cycle > x);
-                let brightness = ppu.pixel_brightness(x, y);
-                if behind_ppu && brightness >= 85 {
-                    trace!("zapper light: {brightness}");
-                    return 0x00;
+        let (scanline, cycle) = (ppu.scanline, ppu.cycle);
+        let radius = self.radius as i32;
+        let (mut total, mut luminance) = (0u32, 0u32);
+        for dy in -radius..=radius {
+            let y = self.y as i32 + dy;
+            if !(0..Ppu::HEIGHT as i32).contains(&y) {
+                continue;
+            }
+            let y = y as u32;
+            for dx in -radius..=radius {
+                let x = self.x as i32 + dx;
+                if dx * dx + dy * dy > radius * radius || !(0..Ppu::WIDTH as i32).contains(&x) {
+                    continue;
+                }
+                let x = x as u32;
+                total += 1;
+                // Pixel `x` is output on cycle `x + 1`
+                let drawn = scanline > y || (scanline == y && cycle > x);
+                if drawn && scanline - y <= Self::LIGHT_SENSE_SCANLINES {
+                    luminance += Self::luminance(ppu.frame.pixel(x, y));
                 }
             }
         }
-        0x08
+        if total > 0 && luminance / total >= Self::LIGHT_THRESHOLD {
+            trace!("zapper light: {}", luminance / total);
+            0x00
+        } else {
+            0x08
+        }
+    }
+
+    /// Perceived luminance of a palette `pixel` in the range `0..=255`.
+    fn luminance(pixel: u16) -> u32 {
+        let (red, green, blue) = Ppu::system_palette(pixel);
+        (54 * u32::from(red) + 183 * u32::from(green) + 19 * u32::from(blue)) >> 8
     }
 }
 
@@ -574,3 +778,744 @@ impl Reset for Zapper {
         self.triggered = 0.0;
     }
 }
+
+/// Arkanoid Vaus paddle controller, for the NES on controller port 2 or the Famicom expansion
+/// port.
+///
+/// The potentiometer position is latched while D0 written to `$4016` is set and shifted out of
+/// `$4017` inverted, most significant bit first: on D4 for the NES, with the button on D3, or on
+/// D1 for the Famicom, with the button on D1 of `$4016`.
+///
+/// See: <https://www.nesdev.org/wiki/Arkanoid_controller>
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct VausPaddle {
+    pub position: u8,
+    pub button: bool,
+    pub shift: u8,
+    pub strobe: bool,
+    /// Whether the NES variant is connected to controller port 2.
+    #[serde(deserialize_with = "crate::fs::added_in::<4, _, _>")]
+    pub connected: bool,
+}
+
+impl Default for VausPaddle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VausPaddle {
+    /// Potentiometer position with the paddle turned all the way left.
+    pub const MIN: u8 = 0x62;
+    /// Potentiometer position with the paddle turned all the way right.
+    pub const MAX: u8 = 0xF2;
+    /// NES 2.0 header default expansion device for the NES variant.
+    pub const NES_DEVICE: u8 = 0x0F;
+
+    pub const fn new() -> Self {
+        Self {
+            position: Self::MIN + (Self::MAX - Self::MIN) / 2,
+            button: false,
+            shift: 0x00,
+            strobe: false,
+            connected: false,
+        }
+    }
+
+    /// Turn the paddle to `x`, from `0.0` all the way left to `1.0` all the way right.
+    pub fn aim(&mut self, x: f32) {
+        let range = f32::from(Self::MAX - Self::MIN);
+        let position = Self::MIN + libm::roundf(x.clamp(0.0, 1.0) * range) as u8;
+        if position != self.position {
+            trace!("vaus position: ${position:02X}");
+        }
+        self.position = position;
+    }
+
+    pub fn set_button(&mut self, pressed: bool) {
+        self.button = pressed;
+    }
+
+    /// Read the NES variant from `$4017`.
+    #[must_use]
+    pub fn read_nes(&mut self) -> u8 {
+        let val = self.peek_nes();
+        if self.connected {
+            self.shift_position();
+        }
+        val
+    }
+
+    #[must_use]
+    pub const fn peek_nes(&self) -> u8 {
+        if self.connected {
+            (self.button as u8) << 3 | self.position_bit() << 4
+        } else {
+            0x00
+        }
+    }
+
+    /// Read the Famicom variant from `$4016` for [`Player::One`] or `$4017` for [`Player::Two`].
+    #[must_use]
+    pub fn read_famicom(&mut self, player: Player) -> u8 {
+        let val = self.peek_famicom(player);
+        if player == Player::Two {
+            self.shift_position();
+        }
+        val
+    }
+
+    #[must_use]
+    pub const fn peek_famicom(&self, player: Player) -> u8 {
+        match player {
+            Player::One => (self.button as u8) << 1,
+            Player::Two => self.position_bit() << 1,
+            _ => 0x00,
+        }
+    }
+
+    const fn position_bit(&self) -> u8 {
+        self.shift >> 7
+    }
+
+    fn shift_position(&mut self) {
+        if !self.strobe {
+            self.shift <<= 1;
+        }
+    }
+
+    pub fn write(&mut self, val: u8) {
+        self.strobe = val & 0x01 == 0x01;
+        if self.strobe {
+            self.shift = !self.position;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.button = false;
+    }
+}
+
+impl Reset for VausPaddle {
+    fn reset(&mut self, _kind: ResetKind) {
+        self.clear();
+        self.shift = 0x00;
+        self.strobe = false;
+    }
+}
+
+/// Super NES Mouse, or the Hori Track trackball, using the same protocol.
+///
+/// Motion since the last report and the button states are latched while D0 written to `$4016`
+/// is set, then read as a 32-bit report on D0, most significant bit first: 8 zero bits, the right
+/// and left buttons, the speed and a `0001` signature, then the vertical and horizontal motion as
+/// a direction bit, `1` for up or left, followed by 7 bits of magnitude. Reading while D0 is set
+/// cycles through three speeds, scaling motion from low to high.
+///
+/// See: <https://www.nesdev.org/wiki/Super_NES_Mouse>
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct Mouse {
+    /// Controller port the mouse is connected to, if any.
+    pub port: Option<Player>,
+    /// Scale applied to host mouse motion before the speed selected by the game.
+    pub sensitivity: f32,
+    pub left: bool,
+    pub right: bool,
+    /// Speed selected by the game, from `0` for low to `2` for high.
+    pub speed: u8,
+    /// Motion not yet reported.
+    pub dx: f32,
+    pub dy: f32,
+    pub report: u32,
+    pub index: u8,
+    pub strobe: bool,
+}
+
+impl Default for Mouse {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mouse {
+    /// Motion scale for each speed.
+    const SPEED_SCALE: [f32; 3] = [1.0, 1.5, 2.0];
+    const MAX_MOTION: f32 = 127.0;
+
+    pub const fn new() -> Self {
+        Self {
+            port: None,
+            sensitivity: 1.0,
+            left: false,
+            right: false,
+            speed: 0,
+            dx: 0.0,
+            dy: 0.0,
+            report: 0x0000_0000,
+            index: 32,
+            strobe: false,
+        }
+    }
+
+    /// Move the mouse by host motion `dx` and `dy`, scaled by the sensitivity.
+    pub fn move_by(&mut self, dx: f32, dy: f32) {
+        if dx.is_finite() && dy.is_finite() {
+            self.dx += dx * self.sensitivity;
+            self.dy += dy * self.sensitivity;
+        }
+    }
+
+    pub fn set_buttons(&mut self, left: bool, right: bool) {
+        self.left = left;
+        self.right = right;
+    }
+
+    #[must_use]
+    pub fn read(&mut self) -> u8 {
+        let val = self.peek();
+        if self.strobe {
+            self.speed = (self.speed + 1) % Self::SPEED_SCALE.len() as u8;
+            trace!("mouse speed: {}", self.speed);
+        } else if self.index < 32 {
+            self.index += 1;
+        }
+        val
+    }
+
+    #[must_use]
+    pub const fn peek(&self) -> u8 {
+        if self.index < 32 {
+            ((self.report >> (31 - self.index)) & 0x01) as u8
+        } else {
+            0x01
+        }
+    }
+
+    pub fn write(&mut self, val: u8) {
+        self.strobe = val & 0x01 == 0x01;
+        if self.strobe {
+            self.latch();
+        }
+    }
+
+    /// Latch the buttons and motion since the last report, keeping any motion beyond the maximum
+    /// that can be reported for the next report.
+    fn latch(&mut self) {
+        let scale = Self::SPEED_SCALE[self.speed as usize % Self::SPEED_SCALE.len()];
+        let motion = |delta: &mut f32| {
+            let motion = libm::truncf(*delta * scale).clamp(-Self::MAX_MOTION, Self::MAX_MOTION);
+            *delta -= motion / scale;
+            // Direction bit is set for up or left
+            let direction = if motion < 0.0 { 0x80 } else { 0x00 };
+            direction | libm::fabsf(motion) as u32
+        };
+        let y = motion(&mut self.dy);
+        let x = motion(&mut self.dx);
+        let buttons = (u32::from(self.right) << 7) | (u32::from(self.left) << 6);
+        let status = buttons | (u32::from(self.speed) << 4) | 0x01;
+        self.report = (status << 16) | (y << 8) | x;
+        self.index = 0;
+    }
+
+    pub fn clear(&mut self) {
+        self.left = false;
+        self.right = false;
+        self.dx = 0.0;
+        self.dy = 0.0;
+    }
+}
+
+impl Reset for Mouse {
+    fn reset(&mut self, _kind: ResetKind) {
+        self.clear();
+        self.speed = 0;
+        self.report = 0x0000_0000;
+        self.index = 32;
+        self.strobe = false;
+    }
+}
+
+/// Vs. System coin slots, service button and DIP switches, read from bits of `$4016` and `$4017`
+/// that are unused on the NES:
+///
+/// - `$4016` D2: Service button.
+/// - `$4016` D3-D4: DIP switches 1-2.
+/// - `$4016` D5-D6: Coin slots 1-2.
+/// - `$4017` D2-D7: DIP switches 3-8.
+///
+/// The Vs. Zapper replaces the controller on `$4016` and is read as an 8-bit report on D0, least
+/// significant bit first, after D0 written to `$4016` is cleared: `0x10` is always set, `0x40`
+/// is set when light is detected and `0x80` while the trigger is pulled.
+///
+/// See: <https://www.nesdev.org/wiki/Vs._System>
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct VsSystem {
+    /// Whether the loaded ROM is a Vs. System game.
+    pub enabled: bool,
+    /// DIP switch 1 is bit 0, through DIP switch 8 in bit 7.
+    pub dip_switches: u8,
+    pub service: bool,
+    /// CPU cycles remaining that each coin switch stays closed after inserting a coin.
+    pub coins: [u32; 2],
+    pub coin_duration: u32,
+    pub zapper_report: u8,
+    pub zapper_index: u8,
+    pub strobe: bool,
+}
+
+impl Default for VsSystem {
+    fn default() -> Self {
+        Self::new(NesRegion::Ntsc)
+    }
+}
+
+impl VsSystem {
+    pub const COIN_SLOTS: u8 = 2;
+    pub const DIP_SWITCH_COUNT: u8 = 8;
+
+    pub fn new(region: NesRegion) -> Self {
+        let mut vs = Self {
+            enabled: false,
+            dip_switches: 0x00,
+            service: false,
+            coins: [0; 2],
+            coin_duration: 0,
+            zapper_report: 0x00,
+            zapper_index: 8,
+            strobe: false,
+        };
+        vs.set_region(region);
+        vs
+    }
+
+    pub fn set_region(&mut self, region: NesRegion) {
+        // Coin switches close for ~50ms as the coin drops
+        self.coin_duration = (Cpu::region_clock_rate(region) / 20.0) as u32;
+    }
+
+    /// Insert a coin into coin slot `1` or `2`.
+    pub fn insert_coin(&mut self, slot: u8) {
+        if let Some(coin) = self.coins.get_mut(usize::from(slot.wrapping_sub(1))) {
+            *coin = self.coin_duration;
+        }
+    }
+
+    pub fn set_service(&mut self, pressed: bool) {
+        self.service = pressed;
+    }
+
+    #[must_use]
+    pub const fn read(&self, player: Player) -> u8 {
+        match player {
+            Player::One => {
+                (self.service as u8) << 2
+                    | (self.dip_switches & 0x03) << 3
+                    | ((self.coins[0] > 0) as u8) << 5
+                    | ((self.coins[1] > 0) as u8) << 6
+            }
+            Player::Two => self.dip_switches & 0xFC,
+            Player::Three | Player::Four => 0x00,
+        }
+    }
+
+    #[must_use]
+    pub fn read_zapper(&mut self, zapper: &Zapper, ppu: &Ppu) -> u8 {
+        if self.strobe || self.zapper_index == 0 {
+            self.zapper_report = Self::zapper_report(zapper, ppu);
+        }
+        let val = self.peek_zapper(zapper, ppu);
+        if !self.strobe && self.zapper_index < 8 {
+            self.zapper_index += 1;
+        }
+        val
+    }
+
+    #[must_use]
+    pub fn peek_zapper(&self, zapper: &Zapper, ppu: &Ppu) -> u8 {
+        let report = if self.strobe || self.zapper_index == 0 {
+            Self::zapper_report(zapper, ppu)
+        } else {
+            self.zapper_report
+        };
+        if self.zapper_index < 8 {
+            (report >> self.zapper_index) & 0x01
+        } else {
+            0x00
+        }
+    }
+
+    pub fn write(&mut self, val: u8) {
+        self.strobe = val & 0x01 == 0x01;
+        if self.strobe {
+            self.zapper_index = 0;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.service = false;
+        self.coins = [0; 2];
+    }
+
+    fn zapper_report(zapper: &Zapper, ppu: &Ppu) -> u8 {
+        let light = if zapper.light_sense(ppu) == 0x00 {
+            0x40
+        } else {
+            0x00
+        };
+        let trigger = if zapper.triggered() > 0 { 0x80 } else { 0x00 };
+        0x10 | light | trigger
+    }
+}
+
+impl Clock for VsSystem {
+    fn clock(&mut self) -> usize {
+        for coin in &mut self.coins {
+            *coin = coin.saturating_sub(1);
+        }
+        1
+    }
+}
+
+impl Reset for VsSystem {
+    fn reset(&mut self, _kind: ResetKind) {
+        // DIP switches are external and keep their setting
+        self.clear();
+        self.zapper_report = 0x00;
+        self.zapper_index = 8;
+        self.strobe = false;
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::control_deck::ControlDeck;
+
+    fn light_rom() -> ControlDeck {
+        let mut deck = ControlDeck::default();
+        deck.load_rom_path(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_roms/input/zapper_light.nes"
+        ))
+        .expect("valid rom");
+        deck.connect_zapper(true);
+        // Draws a white box from (64, 64) to (191, 175) on a black background
+        for _ in 0..5 {
+            deck.clock_frame().expect("valid frame");
+        }
+        deck
+    }
+
+    fn sense(deck: &mut ControlDeck, (x, y): (u32, u32), (scanline, cycle): (u32, u32)) -> u8 {
+        deck.aim_zapper(x, y);
+        let cpu = deck.cpu_mut();
+        cpu.bus.ppu.scanline = scanline;
+        cpu.bus.ppu.cycle = cycle;
+        cpu.bus.input.zapper.read(&cpu.bus.ppu) & 0x08
+    }
+
+    #[test]
+    fn zapper_light_detection() {
+        let mut deck = light_rom();
+
+        assert_eq!(sense(&mut deck, (128, 120), (122, 0)), 0x00, "inside box");
+        assert_eq!(sense(&mut deck, (10, 10), (12, 0)), 0x08, "outside box");
+        assert_eq!(sense(&mut deck, (128, 120), (118, 0)), 0x08, "before beam");
+        assert_eq!(sense(&mut deck, (128, 120), (150, 0)), 0x08, "after decay");
+
+        // Only detected once enough of the radius has been drawn
+        assert_eq!(sense(&mut deck, (128, 120), (120, 100)), 0x08);
+        assert_eq!(sense(&mut deck, (128, 120), (120, 200)), 0x00);
+    }
+
+    #[test]
+    fn zapper_detection_radius() {
+        let mut deck = light_rom();
+
+        // Top-left corner of the box
+        let aim = (64, 64);
+        assert_eq!(sense(&mut deck, aim, (74, 0)), 0x08);
+        deck.cpu_mut().bus.input.zapper.set_detection_radius(0);
+        assert_eq!(deck.cpu().bus.input.zapper.detection_radius(), 0);
+        assert_eq!(sense(&mut deck, aim, (74, 0)), 0x00);
+
+        // Left edge of the box
+        deck.cpu_mut().bus.input.zapper.set_detection_radius(3);
+        assert_eq!(sense(&mut deck, (64, 120), (130, 0)), 0x00);
+        assert_eq!(sense(&mut deck, (63, 120), (130, 0)), 0x08);
+    }
+
+    fn reports(input: &mut Input, player: Player, ppu: &Ppu) -> [u8; 24] {
+        input.write(0x01);
+        input.write(0x00);
+        core::array::from_fn(|_| input.read(player, ppu) & 0x03)
+    }
+
+    #[test]
+    fn joypad_strobe_and_shift_register() {
+        let mut pad = Joypad::new();
+        pad.set_button(JoypadBtnState::A, true);
+        pad.set_button(JoypadBtnState::START, true);
+
+        // Strobe high keeps reloading, so only A is returned without clocking the register
+        pad.write(0x01);
+        assert_eq!(
+            [pad.read(), pad.read(), pad.read()],
+            [1, 1, 1],
+            "A while strobe high"
+        );
+        pad.set_button(JoypadBtnState::A, false);
+        assert_eq!(pad.read(), 0, "A reloaded while strobe high");
+        pad.set_button(JoypadBtnState::A, true);
+
+        // Button states are latched when strobe goes low
+        pad.write(0x00);
+        pad.set_button(JoypadBtnState::SELECT, true);
+        let reads: [u8; 10] = core::array::from_fn(|_| pad.read());
+        assert_eq!(reads, [1, 0, 0, 1, 0, 0, 0, 0, 1, 1], "A, Start, then 1s");
+
+        // Writing strobe low again doesn't reload
+        pad.write(0x00);
+        assert_eq!(pad.read(), 1, "no reload without strobe");
+    }
+
+    #[test]
+    fn controller_port_open_bus() {
+        use crate::{
+            bus::Bus,
+            mem::{Access, Mem},
+        };
+
+        let mut bus = Bus::default();
+        bus.input.joypads[0].set_button(JoypadBtnState::A, true);
+        bus.write(0x4016, 0x01, Access::Write);
+        bus.write(0x4016, 0x00, Access::Write);
+        // `LDA $4016` leaves the high byte of the address on the bus
+        bus.open_bus = 0x40;
+        let reads: [u8; 3] = core::array::from_fn(|_| bus.read(0x4016, Access::Read));
+        assert_eq!(reads, [0x41, 0x40, 0x40], "$4016 upper bits are open bus");
+        bus.open_bus = 0x40;
+        assert_eq!(
+            bus.read(0x4017, Access::Read),
+            0x40,
+            "$4017 upper bits are open bus"
+        );
+    }
+
+    #[test]
+    fn four_player_reports() {
+        let ppu = Ppu::new(NesRegion::Ntsc);
+        let mut input = Input::new(NesRegion::Ntsc);
+        let set_four_player = |input: &mut Input, four_player| {
+            input.set_four_player(four_player);
+            input.joypads[0].set_button(JoypadBtnState::A, true);
+            input.joypads[2].set_button(JoypadBtnState::START, true);
+        };
+
+        set_four_player(&mut input, FourPlayer::FourScore);
+        let one = reports(&mut input, Player::One, &ppu);
+        assert_eq!(one[0], 0x01, "player 1 A");
+        assert_eq!(one[8 + 3], 0x01, "player 3 Start");
+        assert_eq!(one[16..], [0, 0, 0, 1, 0, 0, 0, 0], "$4016 signature");
+        let two = reports(&mut input, Player::Two, &ppu);
+        assert_eq!(two[16..], [0, 0, 1, 0, 0, 0, 0, 0], "$4017 signature");
+
+        set_four_player(&mut input, FourPlayer::FamicomAdapter);
+        let one = reports(&mut input, Player::One, &ppu);
+        assert_eq!(one[0], 0x02, "player 1 A on D1");
+        assert_eq!(one[8 + 3], 0x02, "player 3 Start on D1");
+        assert_eq!(
+            one[16..]
+                .iter()
+                .map(|val| val >> 1)
+                .collect::<alloc::vec::Vec<_>>(),
+            [0, 0, 1, 0, 0, 0, 0, 0],
+            "$4016 signature"
+        );
+        let two = reports(&mut input, Player::Two, &ppu);
+        assert_eq!(
+            two[16..]
+                .iter()
+                .map(|val| val >> 1)
+                .collect::<alloc::vec::Vec<_>>(),
+            [0, 0, 0, 1, 0, 0, 0, 0],
+            "$4017 signature"
+        );
+
+        set_four_player(&mut input, FourPlayer::Satellite);
+        let one = reports(&mut input, Player::One, &ppu);
+        assert_eq!(one[0], 0x01, "player 1 A on D0");
+        assert_eq!(one[3], 0x02, "player 3 Start on D1");
+    }
+
+    #[test]
+    fn family_keyboard_matrix() {
+        use expansion::FamilyKey;
+
+        let ppu = Ppu::new(NesRegion::Ntsc);
+        let mut input = Input::new(NesRegion::Ntsc);
+        input.connect_expansion(ExpansionDevice::FamilyKeyboard);
+        input.expansion.keyboard.set_key(FamilyKey::Return, true);
+        input.expansion.keyboard.set_key(FamilyKey::Space, true);
+
+        // Reset to row 0, column 0 and enable the keyboard
+        input.write(0x05);
+        assert_eq!(input.read(Player::Two, &ppu), 0x1E & !0x08, "RETURN on D3");
+        input.write(0x06);
+        assert_eq!(input.read(Player::Two, &ppu), 0x1E, "row 0, column 1");
+        // Advance through rows 1-7 to row 8
+        for _ in 1..8 {
+            input.write(0x04);
+            input.write(0x06);
+        }
+        input.write(0x04);
+        assert_eq!(input.read(Player::Two, &ppu), 0x1E, "row 8, column 0");
+        input.write(0x06);
+        assert_eq!(input.read(Player::Two, &ppu), 0x1E & !0x08, "SPACE on D3");
+
+        input.write(0x02);
+        assert_eq!(input.read(Player::Two, &ppu), 0x00, "disabled");
+    }
+
+    #[test]
+    fn vaus_paddle_serial() {
+        let ppu = Ppu::new(NesRegion::Ntsc);
+        let mut input = Input::new(NesRegion::Ntsc);
+        input.connect_expansion(ExpansionDevice::Vaus);
+        input.expansion.vaus.aim(1.0);
+        input.expansion.vaus.set_button(true);
+
+        input.write(0x01);
+        input.write(0x00);
+        assert_eq!(input.read(Player::One, &ppu), 0x02, "button on $4016 D1");
+        let position = (0..8).fold(0x00, |position, _| {
+            (position << 1) | (input.read(Player::Two, &ppu) >> 1)
+        });
+        assert_eq!(!position, VausPaddle::MAX, "inverted position");
+    }
+
+    #[test]
+    fn vaus_paddle_nes() {
+        let ppu = Ppu::new(NesRegion::Ntsc);
+        let mut input = Input::new(NesRegion::Ntsc);
+        input.connect_vaus(true);
+        input.aim_vaus(0.0);
+        input.set_vaus_button(true);
+
+        input.write(0x01);
+        input.write(0x00);
+        let reads: [u8; 8] = core::array::from_fn(|_| input.read(Player::Two, &ppu));
+        assert!(reads.iter().all(|val| val & 0x08 == 0x08), "button on D3");
+        let position = reads
+            .iter()
+            .fold(0x00, |position, val| (position << 1) | ((val >> 4) & 0x01));
+        assert_eq!(!position, VausPaddle::MIN, "inverted position on D4");
+        assert_eq!(
+            input.read(Player::One, &ppu) & 0x18,
+            0x00,
+            "nothing on $4016"
+        );
+    }
+
+    #[test]
+    fn mouse_report() {
+        let ppu = Ppu::new(NesRegion::Ntsc);
+        let mut input = Input::new(NesRegion::Ntsc);
+        input.connect_mouse(Some(Player::One));
+        input.mouse.set_buttons(true, false);
+        input.mouse.move_by(-3.0, 200.0);
+
+        let read_report = |input: &mut Input| {
+            input.write(0x01);
+            input.write(0x00);
+            (0..32).fold(0u32, |report, _| {
+                (report << 1) | u32::from(input.read(Player::One, &ppu) & 0x01)
+            })
+        };
+        assert_eq!(
+            read_report(&mut input),
+            0x0041_7F83,
+            "left button, down 127, left 3"
+        );
+        assert_eq!(
+            read_report(&mut input),
+            0x0041_4900,
+            "remaining motion down 73"
+        );
+        assert_eq!(
+            input.read(Player::One, &ppu) & 0x01,
+            0x01,
+            "1s after report"
+        );
+
+        // Reading while strobed cycles speed
+        input.write(0x01);
+        let _ = input.read(Player::One, &ppu);
+        input.write(0x00);
+        assert_eq!(input.mouse.speed, 1);
+        assert_eq!(read_report(&mut input) >> 16, 0x0051, "medium speed");
+
+        input.connect_mouse(Some(Player::Three));
+        assert_eq!(input.mouse.port, None, "no controller port for player 3");
+    }
+
+    #[test]
+    fn power_pad_rows() {
+        let ppu = Ppu::new(NesRegion::Ntsc);
+        let mut input = Input::new(NesRegion::Ntsc);
+        input.connect_expansion(ExpansionDevice::PowerPad);
+        input.expansion.power_pad.set_button(0, true);
+        input.expansion.power_pad.set_button(6, true);
+
+        input.write(0x03);
+        assert_eq!(
+            input.read(Player::Two, &ppu),
+            0x1E & !0x10,
+            "button 1 on D4"
+        );
+        input.write(0x05);
+        assert_eq!(
+            input.read(Player::Two, &ppu),
+            0x1E & !0x04,
+            "button 7 on D2"
+        );
+        input.write(0x06);
+        assert_eq!(input.read(Player::Two, &ppu), 0x1E, "last row");
+    }
+
+    #[test]
+    fn barcode_battler_serial() {
+        let ppu = Ppu::new(NesRegion::Ntsc);
+        let mut input = Input::new(NesRegion::Ntsc);
+        input.connect_expansion(ExpansionDevice::BarcodeBattler);
+        assert!(input.expansion.barcode_battler.scan("1234").is_err());
+        input
+            .expansion
+            .barcode_battler
+            .scan("12345678")
+            .expect("valid barcode");
+
+        let cycles_per_bit = input.expansion.barcode_battler.cycles_per_bit;
+        let mut data = alloc::vec::Vec::new();
+        while input.expansion.barcode_battler.scanning() {
+            let mut byte = 0x00;
+            for bit in 0..10 {
+                let val = (input.read(Player::Two, &ppu) >> 2) & 0x01;
+                match bit {
+                    0 => assert_eq!(val, 0x01, "start bit"),
+                    9 => assert_eq!(val, 0x00, "stop bit"),
+                    bit => byte |= (val ^ 0x01) << (bit - 1),
+                }
+                for _ in 0..cycles_per_bit {
+                    input.clock();
+                }
+            }
+            data.push(byte);
+        }
+        assert_eq!(data, b"0000012345678EPOCH\r\n");
+        assert_eq!(input.read(Player::Two, &ppu) & 0x04, 0x00, "idle");
+    }
+}
@@ -0,0 +1,548 @@
+//! Devices connected to the Famicom expansion port.
+//!
+//! The expansion port shares `$4016` and `$4017` with the controller ports. Devices see the bits
+//! written to `$4016` and report on D1-D4 of reads from `$4016` and `$4017`.
+//!
+//! See: <https://www.nesdev.org/wiki/Expansion_port>
+
+use crate::{
+    common::{Clock, NesRegion, Reset, ResetKind},
+    cpu::Cpu,
+    input::{Player, VausPaddle},
+};
+use core::str::FromStr;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use tracing::debug;
+
+#[derive(Snafu, Debug)]
+#[must_use]
+#[snafu(display("invalid barcode: expected 8 or 13 digits"))]
+pub struct InvalidBarcodeError;
+
+/// Device connected to the Famicom expansion port.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[must_use]
+pub enum ExpansionDevice {
+    #[default]
+    None,
+    /// Family BASIC Keyboard.
+    FamilyKeyboard,
+    /// Arkanoid Vaus paddle controller for the Famicom.
+    Vaus,
+    /// Family Trainer Mat, the Famicom version of the Power Pad.
+    PowerPad,
+    /// Barcode Battler II, connected through the Barcode Battler interface.
+    BarcodeBattler,
+}
+
+impl ExpansionDevice {
+    pub const fn as_slice() -> &'static [Self] {
+        &[
+            Self::None,
+            Self::FamilyKeyboard,
+            Self::Vaus,
+            Self::PowerPad,
+            Self::BarcodeBattler,
+        ]
+    }
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::FamilyKeyboard => "family-keyboard",
+            Self::Vaus => "vaus",
+            Self::PowerPad => "power-pad",
+            Self::BarcodeBattler => "barcode-battler",
+        }
+    }
+
+    /// Returns the device for an NES 2.0 header default expansion device, if it's a supported
+    /// expansion port device.
+    ///
+    /// See: <https://www.nesdev.org/wiki/NES_2.0#Default_Expansion_Device>
+    pub const fn from_header(device: u8) -> Option<Self> {
+        match device {
+            // Family Trainer, side A or B
+            0x0D | 0x0E => Some(Self::PowerPad),
+            // Arkanoid Vaus, Famicom
+            0x10 => Some(Self::Vaus),
+            0x18 => Some(Self::BarcodeBattler),
+            0x23 => Some(Self::FamilyKeyboard),
+            _ => None,
+        }
+    }
+}
+
+impl AsRef<str> for ExpansionDevice {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl core::fmt::Display for ExpansionDevice {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let s = match self {
+            Self::None => "None",
+            Self::FamilyKeyboard => "Family BASIC Keyboard",
+            Self::Vaus => "Arkanoid Vaus",
+            Self::PowerPad => "Family Trainer",
+            Self::BarcodeBattler => "Barcode Battler",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl FromStr for ExpansionDevice {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "family-keyboard" => Ok(Self::FamilyKeyboard),
+            "vaus" => Ok(Self::Vaus),
+            "power-pad" => Ok(Self::PowerPad),
+            "barcode-battler" => Ok(Self::BarcodeBattler),
+            _ => Err(
+                "invalid ExpansionDevice value. valid options: `none`, `family-keyboard`, `vaus`, `power-pad`, or `barcode-battler`",
+            ),
+        }
+    }
+}
+
+/// Family BASIC Keyboard keys, in keyboard matrix order: each row has two columns of four keys.
+///
+/// See: <https://www.nesdev.org/wiki/Family_BASIC_Keyboard>
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[must_use]
+pub enum FamilyKey {
+    RightBracket,
+    LeftBracket,
+    Return,
+    F8,
+    Stop,
+    Yen,
+    RightShift,
+    Kana,
+    Semicolon,
+    Colon,
+    At,
+    F7,
+    Caret,
+    Minus,
+    Slash,
+    Underscore,
+    K,
+    L,
+    O,
+    F6,
+    Num0,
+    P,
+    Comma,
+    Period,
+    J,
+    U,
+    I,
+    F5,
+    Num8,
+    Num9,
+    N,
+    M,
+    H,
+    G,
+    Y,
+    F4,
+    Num6,
+    Num7,
+    V,
+    B,
+    D,
+    R,
+    T,
+    F3,
+    Num4,
+    Num5,
+    C,
+    F,
+    A,
+    S,
+    W,
+    F2,
+    Num3,
+    E,
+    Z,
+    X,
+    Ctrl,
+    Q,
+    Escape,
+    F1,
+    Num2,
+    Num1,
+    Grph,
+    LeftShift,
+    Left,
+    Right,
+    Up,
+    ClrHome,
+    Ins,
+    Del,
+    Space,
+    Down,
+}
+
+/// Family BASIC Keyboard.
+///
+/// Writes to `$4016` select a row and column of the key matrix: D0 resets to the first row, D1
+/// selects the column, advancing to the next row when it changes from `1` to `0`, and D2 enables
+/// the keyboard. Reads from `$4017` report the four keys of the selected column on D1-D4, `0` when
+/// pressed.
+#[derive(Default, Debug, Copy, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct FamilyKeyboard {
+    /// Pressed keys for each row, one bit per key in matrix order.
+    pub keys: [u8; Self::ROWS],
+    pub row: u8,
+    pub column: u8,
+    pub enabled: bool,
+}
+
+impl FamilyKeyboard {
+    pub const ROWS: usize = 9;
+
+    pub const fn new() -> Self {
+        Self {
+            keys: [0x00; Self::ROWS],
+            row: 0,
+            column: 0,
+            enabled: false,
+        }
+    }
+
+    #[must_use]
+    pub const fn key(&self, key: FamilyKey) -> bool {
+        let key = key as usize;
+        self.keys[key / 8] & (1 << (key % 8)) != 0
+    }
+
+    pub fn set_key(&mut self, key: FamilyKey, pressed: bool) {
+        let key = key as usize;
+        if pressed {
+            self.keys[key / 8] |= 1 << (key % 8);
+        } else {
+            self.keys[key / 8] &= !(1 << (key % 8));
+        }
+    }
+
+    #[must_use]
+    pub const fn read(&self) -> u8 {
+        if !self.enabled {
+            0x00
+        } else if (self.row as usize) < Self::ROWS {
+            let keys = self.keys[self.row as usize] >> (4 * self.column);
+            (!keys & 0x0F) << 1
+        } else {
+            0x1E
+        }
+    }
+
+    pub fn write(&mut self, val: u8) {
+        let column = (val >> 1) & 0x01;
+        if val & 0x01 == 0x01 {
+            self.row = 0;
+        } else if self.column == 1 && column == 0 {
+            self.row = self.row.saturating_add(1);
+        }
+        self.column = column;
+        self.enabled = val & 0x04 == 0x04;
+    }
+
+    pub fn clear(&mut self) {
+        self.keys = [0x00; Self::ROWS];
+    }
+}
+
+impl Reset for FamilyKeyboard {
+    fn reset(&mut self, _kind: ResetKind) {
+        self.clear();
+        self.row = 0;
+        self.column = 0;
+        self.enabled = false;
+    }
+}
+
+/// Family Trainer Mat, the Famicom version of the Power Pad.
+///
+/// D0-D2 written to `$4016` each mask out a row of four buttons when set. Reads from `$4017`
+/// report the four buttons of the selected rows on D4-D1, `0` when pressed. Buttons are numbered
+/// `0..12` as printed on side B of the mat, left to right and top to bottom.
+///
+/// See: <https://www.nesdev.org/wiki/Power_Pad>
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct PowerPad {
+    /// Pressed buttons, one bit per button.
+    pub buttons: u16,
+    pub row_mask: u8,
+}
+
+impl Default for PowerPad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PowerPad {
+    pub const BUTTONS: usize = 12;
+
+    pub const fn new() -> Self {
+        Self {
+            buttons: 0x0000,
+            row_mask: 0x07,
+        }
+    }
+
+    #[must_use]
+    pub const fn button(&self, button: usize) -> bool {
+        button < Self::BUTTONS && self.buttons & (1 << button) != 0
+    }
+
+    pub fn set_button(&mut self, button: usize, pressed: bool) {
+        if button >= Self::BUTTONS {
+            return;
+        }
+        if pressed {
+            self.buttons |= 1 << button;
+        } else {
+            self.buttons &= !(1 << button);
+        }
+    }
+
+    #[must_use]
+    pub const fn read(&self) -> u8 {
+        let mut pressed = 0x00;
+        let mut row = 0;
+        while row < 3 {
+            // D2 masks out the first row, D0 the last
+            if self.row_mask & (0x04 >> row) == 0 {
+                pressed |= (self.buttons >> (4 * row)) as u8 & 0x0F;
+            }
+            row += 1;
+        }
+        // The first button of each row is reported on D4
+        (!pressed.reverse_bits() >> 3) & 0x1E
+    }
+
+    pub fn write(&mut self, val: u8) {
+        self.row_mask = val & 0x07;
+    }
+
+    pub fn clear(&mut self) {
+        self.buttons = 0x0000;
+    }
+}
+
+impl Reset for PowerPad {
+    fn reset(&mut self, _kind: ResetKind) {
+        self.clear();
+        self.row_mask = 0x07;
+    }
+}
+
+/// Barcode Battler II, connected through the Barcode Battler interface.
+///
+/// Scanned barcodes are sent as 13 digits, padded with leading zeros, followed by `EPOCH` and a
+/// line break, at 1200 baud on D2 of `$4017`. Each byte is sent as a start bit, eight inverted
+/// data bits, least significant bit first, and a stop bit.
+///
+/// See: <https://www.nesdev.org/wiki/Barcode_Battler>
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct BarcodeBattler {
+    pub data: [u8; Self::DATA_LEN],
+    /// Bit being sent, or [`BarcodeBattler::BITS`] when idle.
+    pub bit: usize,
+    pub cycles: u32,
+    pub cycles_per_bit: u32,
+}
+
+impl Default for BarcodeBattler {
+    fn default() -> Self {
+        Self::new(NesRegion::Ntsc)
+    }
+}
+
+impl BarcodeBattler {
+    const DIGITS: usize = 13;
+    const DATA_LEN: usize = Self::DIGITS + 7;
+    const BITS_PER_BYTE: usize = 10;
+    const BITS: usize = Self::DATA_LEN * Self::BITS_PER_BYTE;
+    const BAUD_RATE: f32 = 1200.0;
+
+    pub fn new(region: NesRegion) -> Self {
+        let mut data = [b'0'; Self::DATA_LEN];
+        data[Self::DIGITS..].copy_from_slice(b"EPOCH\r\n");
+        Self {
+            data,
+            bit: Self::BITS,
+            cycles: 0,
+            cycles_per_bit: Self::cycles_per_bit(region),
+        }
+    }
+
+    fn cycles_per_bit(region: NesRegion) -> u32 {
+        (Cpu::region_clock_rate(region) / Self::BAUD_RATE) as u32
+    }
+
+    pub fn set_region(&mut self, region: NesRegion) {
+        self.cycles_per_bit = Self::cycles_per_bit(region);
+    }
+
+    /// Whether a scanned barcode is still being sent.
+    #[must_use]
+    pub const fn scanning(&self) -> bool {
+        self.bit < Self::BITS
+    }
+
+    /// Scan a `barcode` of 8 or 13 digits.
+    ///
+    /// # Errors
+    ///
+    /// If the barcode isn't 8 or 13 digits, then an error is returned.
+    pub fn scan(&mut self, barcode: &str) -> Result<(), InvalidBarcodeError> {
+        let barcode = barcode.trim();
+        if !matches!(barcode.len(), 8 | Self::DIGITS)
+            || !barcode.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(InvalidBarcodeError);
+        }
+        debug!("scanning barcode: {barcode}");
+        let padding = Self::DIGITS - barcode.len();
+        self.data[..padding].fill(b'0');
+        self.data[padding..Self::DIGITS].copy_from_slice(barcode.as_bytes());
+        self.bit = 0;
+        self.cycles = self.cycles_per_bit;
+        Ok(())
+    }
+
+    #[must_use]
+    pub const fn read(&self) -> u8 {
+        if self.bit >= Self::BITS {
+            return 0x00;
+        }
+        let byte = self.data[self.bit / Self::BITS_PER_BYTE];
+        let bit = match self.bit % Self::BITS_PER_BYTE {
+            // Start bit
+            0 => 0x01,
+            // Stop bit
+            9 => 0x00,
+            bit => !(byte >> (bit - 1)) & 0x01,
+        };
+        bit << 2
+    }
+}
+
+impl Clock for BarcodeBattler {
+    fn clock(&mut self) -> usize {
+        if self.bit < Self::BITS {
+            self.cycles = self.cycles.saturating_sub(1);
+            if self.cycles == 0 {
+                self.bit += 1;
+                self.cycles = self.cycles_per_bit;
+            }
+        }
+        1
+    }
+}
+
+impl Reset for BarcodeBattler {
+    fn reset(&mut self, _kind: ResetKind) {
+        self.bit = Self::BITS;
+        self.cycles = 0;
+    }
+}
+
+/// The Famicom expansion port and the state of each device that can be connected to it.
+#[derive(Default, Debug, Copy, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct Expansion {
+    pub device: ExpansionDevice,
+    pub keyboard: FamilyKeyboard,
+    pub vaus: VausPaddle,
+    pub power_pad: PowerPad,
+    pub barcode_battler: BarcodeBattler,
+}
+
+impl Expansion {
+    pub fn new(region: NesRegion) -> Self {
+        Self {
+            device: ExpansionDevice::default(),
+            keyboard: FamilyKeyboard::new(),
+            vaus: VausPaddle::new(),
+            power_pad: PowerPad::new(),
+            barcode_battler: BarcodeBattler::new(region),
+        }
+    }
+
+    pub fn set_region(&mut self, region: NesRegion) {
+        self.barcode_battler.set_region(region);
+    }
+
+    pub fn connect(&mut self, device: ExpansionDevice) {
+        if device != self.device {
+            debug!("expansion device: {device}");
+        }
+        self.device = device;
+        self.reset(ResetKind::Hard);
+    }
+
+    #[must_use]
+    pub fn read(&mut self, player: Player) -> u8 {
+        match (self.device, player) {
+            (ExpansionDevice::Vaus, _) => self.vaus.read_famicom(player),
+            _ => self.peek(player),
+        }
+    }
+
+    #[must_use]
+    pub const fn peek(&self, player: Player) -> u8 {
+        match (self.device, player) {
+            (ExpansionDevice::FamilyKeyboard, Player::Two) => self.keyboard.read(),
+            (ExpansionDevice::Vaus, _) => self.vaus.peek_famicom(player),
+            (ExpansionDevice::PowerPad, Player::Two) => self.power_pad.read(),
+            (ExpansionDevice::BarcodeBattler, Player::Two) => self.barcode_battler.read(),
+            _ => 0x00,
+        }
+    }
+
+    pub fn write(&mut self, val: u8) {
+        match self.device {
+            ExpansionDevice::FamilyKeyboard => self.keyboard.write(val),
+            ExpansionDevice::Vaus => self.vaus.write(val),
+            ExpansionDevice::PowerPad => self.power_pad.write(val),
+            ExpansionDevice::None | ExpansionDevice::BarcodeBattler => (),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.keyboard.clear();
+        self.vaus.clear();
+        self.power_pad.clear();
+    }
+}
+
+impl Clock for Expansion {
+    fn clock(&mut self) -> usize {
+        if self.device == ExpansionDevice::BarcodeBattler {
+            self.barcode_battler.clock();
+        }
+        1
+    }
+}
+
+impl Reset for Expansion {
+    fn reset(&mut self, kind: ResetKind) {
+        self.keyboard.reset(kind);
+        self.vaus.reset(kind);
+        self.power_pad.reset(kind);
+        self.barcode_battler.reset(kind);
+    }
+}
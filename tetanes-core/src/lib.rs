@@ -12,20 +12,33 @@ extern crate std;
 
 pub mod action;
 pub mod apu;
+pub mod autosplit;
 pub mod bus;
+#[cfg(feature = "capi")]
+pub mod capi;
 pub mod cart;
+pub mod cart_db;
+pub mod cdl;
 pub mod fs;
 pub mod time;
 #[macro_use]
 pub mod common;
+pub mod compat;
 pub mod control_deck;
 pub mod cpu;
 pub mod error;
 pub mod genie;
+pub mod headless;
 pub mod input;
 pub mod mapper;
 pub mod mem;
+pub mod movie;
+pub mod patch;
+pub mod playlist;
 pub mod ppu;
+pub mod ram_search;
+pub mod rom_db;
+pub mod snapshot;
 pub mod sys;
 pub mod video;
 
@@ -41,7 +54,6 @@ pub(crate) use {
     vexide_core::io,
 };
 
-pub(crate) use spin::RwLock;
 #[cfg(not(target_vendor = "vex"))]
 /// File Shim
 pub(crate) use std::fs::File;
@@ -3,9 +3,15 @@
 //! <http://wiki.nesdev.com/w/index.php/Mapper>
 
 use crate::{
-    common::{Clock, Regional, Reset, Sram},
+    common::{Clock, Nvram, Regional, Reset, Sram},
+    mem::MemBanks,
     ppu::Mirroring,
 };
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
 use enum_dispatch::enum_dispatch;
 use serde::{Deserialize, Serialize};
 
@@ -20,11 +26,23 @@ pub use m009_pxrom::Pxrom;
 pub use m010_fxrom::Fxrom;
 pub use m011_color_dreams::ColorDreams;
 pub use m024_m026_vrc6::Vrc6;
+pub use m028_action53::Action53;
+pub use m030_unrom512::Unrom512;
 pub use m034_bnrom::Bnrom;
 pub use m034_nina001::Nina001;
+pub use m041_caltron6in1::Caltron6in1;
+pub use m044_super_big_7in1::SuperBig7in1;
+pub use m049_super_hik_4in1::SuperHik4in1;
+pub use m057_gk6in1::Gk6in1;
+pub use m060_reset4in1::Reset4in1;
 pub use m066_gxrom::Gxrom;
 pub use m071_bf909x::{Bf909x, Revision as Bf909Revision};
+pub use m099_vs_unisystem::VsUnisystem;
+pub use m111_gtrom::Gtrom;
+pub use m682_rainbow::Rainbow;
+pub use nsf::Nsf;
 
+pub mod flash;
 pub mod m000_nrom;
 pub mod m001_sxrom;
 pub mod m002_uxrom;
@@ -36,10 +54,22 @@ pub mod m009_pxrom;
 pub mod m010_fxrom;
 pub mod m011_color_dreams;
 pub mod m024_m026_vrc6;
+pub mod m028_action53;
+pub mod m030_unrom512;
 pub mod m034_bnrom;
 pub mod m034_nina001;
+pub mod m041_caltron6in1;
+pub mod m044_super_big_7in1;
+pub mod m049_super_hik_4in1;
+pub mod m057_gk6in1;
+pub mod m060_reset4in1;
 pub mod m066_gxrom;
 pub mod m071_bf909x;
+pub mod m099_vs_unisystem;
+pub mod m111_gtrom;
+pub mod m682_rainbow;
+pub mod nsf;
+pub mod registry;
 pub mod vrc_irq;
 
 /// Allow user-controlled mapper revision for mappers that are difficult to auto-detect correctly.
@@ -57,7 +87,7 @@ impl core::fmt::Display for MapperRevision {
             MapperRevision::Mmc3(rev) => match rev {
                 Mmc3Revision::A => "MMC3A",
                 Mmc3Revision::BC => "MMC3B/C",
-                Mmc3Revision::Acc => "MMC3Acc",
+                Mmc3Revision::Acc => "MC-ACC",
             },
             MapperRevision::Bf909(rev) => match rev {
                 Bf909Revision::Bf909x => "BF909x",
@@ -89,6 +119,18 @@ pub enum Mapper {
     Nina001,
     Gxrom,
     Bf909x,
+    // Variants are serialized by index, so new mappers must be added at the end
+    Unrom512,
+    Action53,
+    Gtrom,
+    Rainbow,
+    Caltron6in1,
+    SuperBig7in1,
+    SuperHik4in1,
+    Gk6in1,
+    Reset4in1,
+    VsUnisystem,
+    Nsf,
 }
 
 impl Mapper {
@@ -99,6 +141,66 @@ impl Mapper {
     pub const fn is_none(&self) -> bool {
         matches!(self, Self::None(_))
     }
+
+    /// Set the revision for mappers that support it, ignoring revisions that don't apply to this
+    /// mapper.
+    pub fn set_revision(&mut self, rev: MapperRevision) {
+        match (self, rev) {
+            (Self::Txrom(mapper), MapperRevision::Mmc3(rev)) => mapper.set_revision(rev),
+            (Self::Bf909x(mapper), MapperRevision::Bf909(rev)) => mapper.set_revision(rev),
+            _ => (),
+        }
+    }
+
+    /// Returns the emulated revision for mappers that support it.
+    #[must_use]
+    pub const fn revision(&self) -> Option<MapperRevision> {
+        match self {
+            Self::Txrom(mapper) => Some(MapperRevision::Mmc3(mapper.revision)),
+            Self::Bf909x(mapper) => Some(MapperRevision::Bf909(mapper.revision)),
+            _ => Option::None,
+        }
+    }
+
+    /// Returns the board's DIP switches, if it has any.
+    #[must_use]
+    pub const fn dip_switches(&self) -> Option<DipSwitches> {
+        match self {
+            Self::Gk6in1(mapper) => Some(mapper.dip_switches()),
+            _ => Option::None,
+        }
+    }
+
+    /// Set the board's DIP switches, with switch `n` in bit `n`, ignored if it has none. Games
+    /// usually only read them on reset.
+    pub fn set_dip_switches(&mut self, value: u8) {
+        if let Self::Gk6in1(mapper) = self {
+            mapper.set_dip_switches(value);
+        }
+    }
+}
+
+/// DIP switches or solder pads on a cartridge board, commonly used by multicarts to select which
+/// menu is shown.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub struct DipSwitches {
+    /// Number of switches.
+    pub count: u8,
+    /// Switch settings, with switch `n` on when bit `n` is set.
+    pub value: u8,
+}
+
+impl DipSwitches {
+    pub const fn new(count: u8, value: u8) -> Self {
+        Self { count, value }
+    }
+
+    /// Returns whether switch `n` is on.
+    #[must_use]
+    pub const fn is_on(&self, n: u8) -> bool {
+        self.value & (1 << n) != 0
+    }
 }
 
 impl Default for Mapper {
@@ -129,6 +231,10 @@ pub enum MappedWrite {
     ExRam(usize, u8),
     PrgRam(usize, u8),
     PrgRamProtect(bool),
+    /// Program a byte of self-flashable PRG-ROM, which can only clear bits until erased.
+    PrgRom(usize, u8),
+    /// Erase the self-flashable PRG-ROM range `start..start + len`, clamped to its size, to `0xFF`.
+    PrgRomErase(usize, usize),
 }
 
 #[enum_dispatch(Mapper)]
@@ -156,6 +262,59 @@ pub trait Mapped {
     fn ppu_bus_write(&mut self, _addr: u16, _val: u8) {}
     fn cpu_bus_read(&mut self, _addr: u16) {}
     fn cpu_bus_write(&mut self, _addr: u16, _val: u8) {}
+    /// Whether the mapper is requesting an IRQ, which the CPU polls every cycle.
+    fn irq_pending(&self) -> bool {
+        false
+    }
+}
+
+/// A mapper register or bank window decoded into human-readable form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct MapperRegister {
+    pub name: String,
+    pub value: String,
+}
+
+impl MapperRegister {
+    pub fn new(name: impl Into<String>, value: impl ToString) -> Self {
+        Self {
+            name: name.into(),
+            value: value.to_string(),
+        }
+    }
+
+    /// Describes each window of `banks` and the bank currently mapped into it, e.g.
+    /// `PRG-ROM $8000-$BFFF: Bank 3`.
+    pub fn banks<'a>(kind: &'a str, banks: &'a MemBanks) -> impl Iterator<Item = Self> + 'a {
+        banks.windows().map(move |(start, end, bank)| {
+            Self::new(
+                format!("{kind} ${start:04X}-${end:04X}"),
+                format!("Bank {bank}"),
+            )
+        })
+    }
+}
+
+/// Exposes mapper state for debugging.
+#[enum_dispatch(Mapper)]
+pub trait MapperInfo {
+    /// Returns the current register values and bank mappings.
+    fn registers(&self) -> Vec<MapperRegister> {
+        Vec::new()
+    }
+}
+
+/// A CPU write to the mapper address space, excluding PRG-RAM, stamped with the PPU position at
+/// the time of the write.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct MapperWrite {
+    pub frame: u32,
+    pub scanline: u32,
+    pub cycle: u32,
+    pub addr: u16,
+    pub val: u8,
 }
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
@@ -168,3 +327,4 @@ impl Clock for None {}
 impl Regional for None {}
 impl Reset for None {}
 impl Sram for None {}
+impl MapperInfo for None {}
@@ -0,0 +1,89 @@
+//! `SST39SF040` flash memory used as self-flashable PRG-ROM by homebrew boards.
+//!
+//! <https://www.nesdev.org/wiki/UNROM_512#Flash_save>
+
+use crate::mapper::MappedWrite;
+use serde::{Deserialize, Serialize};
+
+/// State of the `SST39SF040` command sequence used to program and erase PRG-ROM.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub enum FlashMode {
+    #[default]
+    Command,
+    Program,
+    Erase,
+}
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub struct Flash {
+    pub mode: FlashMode,
+    /// Number of bytes of the current command sequence written so far.
+    pub cycle: u8,
+    /// Whether reads return the manufacturer and device IDs instead of PRG-ROM.
+    pub software_id: bool,
+}
+
+impl Flash {
+    const MANUFACTURER_ID: u8 = 0xBF;
+    const DEVICE_ID: u8 = 0xB7;
+    const SECTOR_SIZE: usize = 4 * 1024;
+
+    /// Returns the software ID at the flash offset `addr`, if in software ID mode.
+    #[must_use]
+    pub const fn read_id(&self, addr: usize) -> Option<u8> {
+        if !self.software_id {
+            return None;
+        }
+        Some(match addr & 0x01FF {
+            0x0000 => Self::MANUFACTURER_ID,
+            0x0001 => Self::DEVICE_ID,
+            _ => 0xFF,
+        })
+    }
+
+    /// Write `val` to the flash offset `addr`, returning the PRG-ROM change to make, if any.
+    pub fn write(&mut self, addr: usize, val: u8) -> MappedWrite {
+        // Commands only decode the lower 15 address lines
+        let cmd_addr = addr & 0x7FFF;
+        match (self.mode, self.cycle, cmd_addr, val) {
+            (FlashMode::Command, 0, 0x5555, 0xAA)
+            | (FlashMode::Command, 1, 0x2AAA, 0x55)
+            | (FlashMode::Erase, 3, 0x5555, 0xAA)
+            | (FlashMode::Erase, 4, 0x2AAA, 0x55) => self.cycle += 1,
+            (FlashMode::Command, 2, 0x5555, _) => {
+                self.cycle += 1;
+                match val {
+                    0x80 => self.mode = FlashMode::Erase,
+                    0x90 => {
+                        self.software_id = true;
+                        self.cycle = 0;
+                    }
+                    0xA0 => self.mode = FlashMode::Program,
+                    0xF0 => *self = Self::default(),
+                    _ => self.cycle = 0,
+                }
+            }
+            (FlashMode::Program, ..) => {
+                *self = Self::default();
+                return MappedWrite::PrgRom(addr, val);
+            }
+            (FlashMode::Erase, 5, 0x5555, 0x10) => {
+                *self = Self::default();
+                return MappedWrite::PrgRomErase(0, usize::MAX);
+            }
+            (FlashMode::Erase, 5, _, 0x30) => {
+                *self = Self::default();
+                let sector = addr & !(Self::SECTOR_SIZE - 1);
+                return MappedWrite::PrgRomErase(sector, Self::SECTOR_SIZE);
+            }
+            (FlashMode::Command, _, _, 0xF0) => *self = Self::default(),
+            _ => {
+                self.mode = FlashMode::Command;
+                self.cycle = 0;
+            }
+        }
+        MappedWrite::None
+    }
+}
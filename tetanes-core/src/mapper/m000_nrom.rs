@@ -5,7 +5,7 @@
 use crate::{
     cart::Cart,
     common::{Clock, Regional, Reset, Sram},
-    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MemMap},
+    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MemMap},
     ppu::Mirroring,
 };
 use serde::{Deserialize, Serialize};
@@ -79,3 +79,4 @@ impl Clock for Nrom {}
 impl Regional for Nrom {}
 impl Reset for Nrom {}
 impl Sram for Nrom {}
+impl MapperInfo for Nrom {}
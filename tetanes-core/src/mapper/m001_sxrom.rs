@@ -6,10 +6,11 @@
 use crate::{
     cart::Cart,
     common::{Clock, Regional, Reset, ResetKind, Sram},
-    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MemMap},
+    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap},
     mem::MemBanks,
     ppu::Mirroring,
 };
+use alloc::{format, vec, vec::Vec};
 use serde::{Deserialize, Serialize};
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -299,6 +300,38 @@ impl Reset for Sxrom {
 impl Regional for Sxrom {}
 impl Sram for Sxrom {}
 
+impl MapperInfo for Sxrom {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let prg_mode = match (self.regs.control & Self::PRG_MODE_MASK) >> 2 {
+            0 | 1 => "32K",
+            2 => "Fix first bank, switch $C000",
+            _ => "Switch $8000, fix last bank",
+        };
+        let chr_mode = if self.regs.control & Self::CHR_MODE_MASK == Self::CHR_MODE_MASK {
+            "4K"
+        } else {
+            "8K"
+        };
+        let mut registers = vec![
+            MapperRegister::new("Control ($8000)", format!("${:02X}", self.regs.control)),
+            MapperRegister::new("PRG-ROM Mode", prg_mode),
+            MapperRegister::new("CHR Mode", chr_mode),
+            MapperRegister::new("CHR Bank 0 ($A000)", format!("${:02X}", self.regs.chr0)),
+            MapperRegister::new("CHR Bank 1 ($C000)", format!("${:02X}", self.regs.chr1)),
+            MapperRegister::new("PRG Bank ($E000)", format!("${:02X}", self.regs.prg)),
+            MapperRegister::new("PRG-RAM Enabled", self.prg_ram_enabled()),
+            MapperRegister::new(
+                "Shift Register",
+                format!("%{:05b}", self.regs.shift_register),
+            ),
+        ];
+        registers.extend(MapperRegister::banks("PRG-RAM", &self.prg_ram_banks));
+        registers.extend(MapperRegister::banks("PRG-ROM", &self.prg_rom_banks));
+        registers.extend(MapperRegister::banks("CHR", &self.chr_banks));
+        registers
+    }
+}
+
 impl core::fmt::Debug for Sxrom {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("SxRom")
@@ -5,10 +5,11 @@
 use crate::{
     cart::Cart,
     common::{Clock, Regional, Reset, Sram},
-    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MemMap},
+    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap},
     mem::MemBanks,
     ppu::Mirroring,
 };
+use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -75,3 +76,9 @@ impl Clock for Uxrom {}
 impl Regional for Uxrom {}
 impl Reset for Uxrom {}
 impl Sram for Uxrom {}
+
+impl MapperInfo for Uxrom {
+    fn registers(&self) -> Vec<MapperRegister> {
+        MapperRegister::banks("PRG-ROM", &self.prg_rom_banks).collect()
+    }
+}
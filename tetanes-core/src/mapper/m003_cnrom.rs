@@ -6,10 +6,11 @@
 use crate::{
     cart::Cart,
     common::{Clock, Regional, Reset, Sram},
-    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MemMap},
+    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap},
     mem::MemBanks,
     ppu::Mirroring,
 };
+use alloc::{format, vec, vec::Vec};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,3 +73,13 @@ impl Clock for Cnrom {}
 impl Regional for Cnrom {}
 impl Reset for Cnrom {}
 impl Sram for Cnrom {}
+
+impl MapperInfo for Cnrom {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let bank = self.chr_banks.translate(0x0000) / Self::CHR_ROM_WINDOW;
+        vec![MapperRegister::new(
+            "CHR-ROM $0000-$1FFF",
+            format!("Bank {bank}"),
+        )]
+    }
+}
@@ -6,11 +6,11 @@
 use crate::{
     cart::Cart,
     common::{Clock, Regional, Reset, ResetKind, Sram},
-    cpu::{Cpu, Irq},
-    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MemMap},
+    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap},
     mem::MemBanks,
     ppu::Mirroring,
 };
+use alloc::{format, vec, vec::Vec};
 use serde::{Deserialize, Serialize};
 
 // MMC3 Revision
@@ -29,13 +29,16 @@ use serde::{Deserialize, Serialize};
 // Crystalis (MMC3B 9024KPO53)
 // Legacy of the Wizard (MMC3A 8940EP)
 //
-// Only major difference is the IRQ counter
+// Only major difference is the IRQ counter. Since revisions can't be detected from the ROM, they
+// can be overridden per ROM.
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[must_use]
 pub enum Revision {
-    /// MMC3 Revision A
+    /// MMC3 Revision A, and some non-Sharp MMC3B - only triggers an IRQ when the counter reaches
+    /// 0 by decrementing or by a reload requested with $C001
     A,
-    /// MMC3 Revisions B & C
+    /// MMC3 Revisions B & C - triggers an IRQ whenever the counter is 0 after being clocked,
+    /// even if it's reloaded with 0
     #[default]
     BC,
     /// Acclaims MMC3 clone - clocks on falling edge
@@ -51,15 +54,41 @@ pub struct Regs {
     pub irq_counter: u8,
     pub irq_enabled: bool,
     pub irq_reload: bool,
+    #[serde(deserialize_with = "crate::fs::added_in::<9, _, _>")]
+    pub irq_pending: bool,
     pub last_clock: u16,
 }
 
+/// Outer bank selected by multicart boards built around the MMC3, combined with the PRG-ROM and
+/// CHR banks selected by the MMC3 registers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub struct OuterBank {
+    pub prg_base: usize,
+    pub prg_mask: usize,
+    pub chr_base: usize,
+    pub chr_mask: usize,
+}
+
+impl OuterBank {
+    #[must_use]
+    pub const fn prg(&self, bank: usize) -> usize {
+        self.prg_base | (bank & self.prg_mask)
+    }
+
+    #[must_use]
+    pub const fn chr(&self, bank: usize) -> usize {
+        self.chr_base | (bank & self.chr_mask)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[must_use]
 pub struct Txrom {
     pub regs: Regs,
     pub mirroring: Mirroring,
     pub revision: Revision,
+    pub outer_bank: Option<OuterBank>,
     pub chr_banks: MemBanks,
     pub prg_ram_banks: MemBanks,
     pub prg_rom_banks: MemBanks,
@@ -77,6 +106,10 @@ impl Txrom {
     const CHR_INVERSION_MASK: u8 = 0x80; // Bit 7 of bank select
 
     pub fn load(cart: &mut Cart) -> Mapper {
+        Self::new(cart).into()
+    }
+
+    pub fn new(cart: &mut Cart) -> Self {
         cart.add_prg_ram(Self::PRG_RAM_SIZE);
         if cart.mirroring() == Mirroring::FourScreen {
             cart.add_exram(Self::FOUR_SCREEN_RAM_SIZE);
@@ -93,6 +126,7 @@ impl Txrom {
             regs: Regs::default(),
             mirroring: cart.mirroring(),
             revision: Revision::BC, // TODO compare to known games
+            outer_bank: None,
             chr_banks: MemBanks::new(0x0000, 0x1FFF, chr_len, Self::CHR_WINDOW),
             prg_ram_banks: MemBanks::new(0x6000, 0x7FFF, cart.prg_ram.len(), Self::PRG_WINDOW),
             prg_rom_banks: MemBanks::new(0x8000, 0xFFFF, cart.prg_rom.len(), Self::PRG_WINDOW),
@@ -100,17 +134,27 @@ impl Txrom {
         let last_bank = txrom.prg_rom_banks.last();
         txrom.prg_rom_banks.set(2, last_bank - 1);
         txrom.prg_rom_banks.set(3, last_bank);
-        txrom.into()
+        txrom
     }
 
     pub fn set_revision(&mut self, rev: Revision) {
         self.revision = rev;
     }
 
+    /// Set the outer bank selected by a multicart board, or `None` to address the whole ROM.
+    pub fn set_outer_bank(&mut self, outer_bank: Option<OuterBank>) {
+        self.outer_bank = outer_bank;
+        self.update_banks();
+    }
+
     pub fn update_banks(&mut self) {
-        let prg_last = self.prg_rom_banks.last();
-        let prg_lo = self.regs.bank_values[6] as usize;
-        let prg_hi = self.regs.bank_values[7] as usize;
+        let outer = self.outer_bank;
+        let prg = |bank: u8| outer.map_or(bank.into(), |outer| outer.prg(bank.into()));
+        let chr = |bank: u8| outer.map_or(bank.into(), |outer| outer.chr(bank.into()));
+
+        let prg_last = outer.map_or(self.prg_rom_banks.last(), |outer| outer.prg(0xFF));
+        let prg_lo = prg(self.regs.bank_values[6]);
+        let prg_hi = prg(self.regs.bank_values[7]);
         if self.regs.bank_select & Self::PRG_MODE_MASK == Self::PRG_MODE_MASK {
             self.prg_rom_banks.set(0, prg_last - 1);
             self.prg_rom_banks.set(1, prg_hi);
@@ -124,21 +168,21 @@ impl Txrom {
 
         // 1: two 2K banks at $1000-$1FFF, four 1 KB banks at $0000-$0FFF
         // 0: two 2K banks at $0000-$0FFF, four 1 KB banks at $1000-$1FFF
-        let chr = self.regs.bank_values;
+        let banks = self.regs.bank_values;
         if self.regs.bank_select & Self::CHR_INVERSION_MASK == Self::CHR_INVERSION_MASK {
-            self.chr_banks.set(0, chr[2] as usize);
-            self.chr_banks.set(1, chr[3] as usize);
-            self.chr_banks.set(2, chr[4] as usize);
-            self.chr_banks.set(3, chr[5] as usize);
-            self.chr_banks.set_range(4, 5, (chr[0] & 0xFE) as usize);
-            self.chr_banks.set_range(6, 7, (chr[1] & 0xFE) as usize);
+            self.chr_banks.set(0, chr(banks[2]));
+            self.chr_banks.set(1, chr(banks[3]));
+            self.chr_banks.set(2, chr(banks[4]));
+            self.chr_banks.set(3, chr(banks[5]));
+            self.chr_banks.set_range(4, 5, chr(banks[0] & 0xFE));
+            self.chr_banks.set_range(6, 7, chr(banks[1] & 0xFE));
         } else {
-            self.chr_banks.set_range(0, 1, (chr[0] & 0xFE) as usize);
-            self.chr_banks.set_range(2, 3, (chr[1] & 0xFE) as usize);
-            self.chr_banks.set(4, chr[2] as usize);
-            self.chr_banks.set(5, chr[3] as usize);
-            self.chr_banks.set(6, chr[4] as usize);
-            self.chr_banks.set(7, chr[5] as usize);
+            self.chr_banks.set_range(0, 1, chr(banks[0] & 0xFE));
+            self.chr_banks.set_range(2, 3, chr(banks[1] & 0xFE));
+            self.chr_banks.set(4, chr(banks[2]));
+            self.chr_banks.set(5, chr(banks[3]));
+            self.chr_banks.set(6, chr(banks[4]));
+            self.chr_banks.set(7, chr(banks[5]));
         }
     }
 
@@ -161,7 +205,7 @@ impl Txrom {
                     && self.regs.irq_counter == 0
                     && self.regs.irq_enabled
                 {
-                    Cpu::set_irq(Irq::MAPPER);
+                    self.regs.irq_pending = true;
                 }
                 self.regs.irq_reload = false;
             }
@@ -186,6 +230,10 @@ impl Mapped for Txrom {
     fn ppu_bus_write(&mut self, addr: u16, _val: u8) {
         self.clock_irq(addr);
     }
+
+    fn irq_pending(&self) -> bool {
+        self.regs.irq_pending
+    }
 }
 
 impl MemMap for Txrom {
@@ -277,7 +325,7 @@ impl MemMap for Txrom {
                     0xC000 => self.regs.irq_latch = val,
                     0xC001 => self.regs.irq_reload = true,
                     0xE000 => {
-                        Cpu::clear_irq(Irq::MAPPER);
+                        self.regs.irq_pending = false;
                         self.regs.irq_enabled = false;
                     }
                     0xE001 => self.regs.irq_enabled = true,
@@ -300,3 +348,38 @@ impl Reset for Txrom {
 impl Clock for Txrom {}
 impl Regional for Txrom {}
 impl Sram for Txrom {}
+
+impl MapperInfo for Txrom {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let regs = &self.regs;
+        let prg_mode = if regs.bank_select & Self::PRG_MODE_MASK == Self::PRG_MODE_MASK {
+            "Switch $C000"
+        } else {
+            "Switch $8000"
+        };
+        let mut registers = vec![
+            MapperRegister::new("Bank Select ($8000)", format!("${:02X}", regs.bank_select)),
+            MapperRegister::new("PRG-ROM Mode", prg_mode),
+            MapperRegister::new(
+                "CHR A12 Inversion",
+                regs.bank_select & Self::CHR_INVERSION_MASK == Self::CHR_INVERSION_MASK,
+            ),
+        ];
+        registers.extend(
+            regs.bank_values
+                .iter()
+                .enumerate()
+                .map(|(i, val)| MapperRegister::new(format!("R{i}"), format!("${val:02X}"))),
+        );
+        registers.extend([
+            MapperRegister::new("IRQ Latch ($C000)", regs.irq_latch),
+            MapperRegister::new("IRQ Counter", regs.irq_counter),
+            MapperRegister::new("IRQ Reload", regs.irq_reload),
+            MapperRegister::new("IRQ Enabled", regs.irq_enabled),
+        ]);
+        registers.extend(MapperRegister::banks("PRG-RAM", &self.prg_ram_banks));
+        registers.extend(MapperRegister::banks("PRG-ROM", &self.prg_rom_banks));
+        registers.extend(MapperRegister::banks("CHR", &self.chr_banks));
+        registers
+    }
+}
@@ -11,12 +11,12 @@ use crate::{
     },
     cart::Cart,
     common::{Clock, NesRegion, Regional, Reset, ResetKind, Sample, Sram},
-    cpu::{Cpu, Irq},
-    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MemMap},
+    cpu::Cpu,
+    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap},
     mem::MemBanks,
     ppu::{bus::PpuAddr, Mirroring, Ppu},
 };
-use alloc::{vec, vec::Vec};
+use alloc::{format, string::ToString, vec, vec::Vec};
 use bitflags::bitflags;
 use serde::{Deserialize, Serialize};
 use tracing::warn;
@@ -553,6 +553,10 @@ impl Mapped for Exrom {
             _ => (),
         }
     }
+
+    fn irq_pending(&self) -> bool {
+        self.irq_state.pending && self.regs.irq_enabled
+    }
 }
 
 impl Regional for Exrom {
@@ -653,9 +657,6 @@ impl MemMap for Exrom {
                             status.scanline += 1;
                             if status.scanline == self.regs.irq_scanline {
                                 irq_state.pending = true;
-                                if self.regs.irq_enabled {
-                                    Cpu::set_irq(Irq::MAPPER);
-                                }
                             }
                         } else {
                             irq_state.in_frame = true;
@@ -672,7 +673,6 @@ impl MemMap for Exrom {
                 self.irq_state.in_frame = false; // NMI clears in_frame
                 self.irq_state.prev_addr = None;
                 self.irq_state.pending = false;
-                Cpu::clear_irq(Irq::MAPPER);
             }
             _ => (),
         }
@@ -680,9 +680,8 @@ impl MemMap for Exrom {
         match addr {
             0x5204 => {
                 self.irq_state.pending = false;
-                Cpu::clear_irq(Irq::MAPPER);
             }
-            0x5010 => Cpu::clear_irq(Irq::DMC),
+            0x5010 => self.dmc.irq_pending = false,
             _ => (),
         }
         val
@@ -744,8 +743,7 @@ impl MemMap for Exrom {
                 // [I... ...M] DMC
                 // I = IRQ (0 = No IRQ triggered. 1 = IRQ was triggered.) Reading $5010 acknowledges the IRQ and clears this flag.
                 // M = Mode select (0 = write mode. 1 = read mode.)
-                let irq = Cpu::has_irq(Irq::DMC);
-                MappedRead::Data(u8::from(irq) << 7 | self.dmc_mode)
+                MappedRead::Data(u8::from(self.dmc.irq_pending) << 7 | self.dmc_mode)
             }
             0x5100 => MappedRead::Data(self.regs.prg_mode as u8),
             0x5101 => MappedRead::Data(self.regs.chr_mode as u8),
@@ -780,7 +778,7 @@ impl MemMap for Exrom {
                 //   P = IRQ currently pending
                 //   I = "In Frame" signal
 
-                let irq_pending = Cpu::has_irq(Irq::MAPPER);
+                let irq_pending = self.irq_pending();
                 // Reading $5204 will clear the pending flag (acknowledging the IRQ).
                 // Clearing is done in the read() function
                 MappedRead::Data(
@@ -973,14 +971,7 @@ impl MemMap for Exrom {
             0x5201 => self.regs.vsplit.scroll = val, // [YYYY YYYY]  Split Y scroll
             0x5202 => self.regs.vsplit.bank = val,   // [CCCC CCCC]  4k CHR Page for split
             0x5203 => self.regs.irq_scanline = u16::from(val), // [IIII IIII]  IRQ Target
-            0x5204 => {
-                self.regs.irq_enabled = val & 0x80 > 0; // [E... ....] IRQ Enable (0=disabled, 1=enabled)
-                if !self.regs.irq_enabled {
-                    Cpu::clear_irq(Irq::MAPPER);
-                } else if self.irq_state.pending {
-                    Cpu::set_irq(Irq::MAPPER);
-                }
-            }
+            0x5204 => self.regs.irq_enabled = val & 0x80 > 0, // [E... ....] IRQ Enable (0=disabled, 1=enabled)
             0x5205 => {
                 self.regs.multiplicand = val;
                 self.regs.mult_result =
@@ -1057,11 +1048,70 @@ impl Reset for Exrom {
     fn reset(&mut self, _kind: ResetKind) {
         self.regs.prg_mode = PrgMode::Bank8k;
         self.regs.chr_mode = ChrMode::Bank1k;
+        self.irq_state.pending = false;
     }
 }
 
 impl Sram for Exrom {}
 
+impl MapperInfo for Exrom {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let regs = &self.regs;
+        let vsplit = if regs.vsplit.enabled {
+            format!("{:?} of tile {}", regs.vsplit.side, regs.vsplit.tile)
+        } else {
+            "Disabled".to_string()
+        };
+        let mut registers = vec![
+            MapperRegister::new("PRG Mode ($5100)", format!("{:?}", regs.prg_mode)),
+            MapperRegister::new("CHR Mode ($5101)", format!("{:?}", regs.chr_mode)),
+            MapperRegister::new(
+                "PRG-RAM Protect ($5102-$5103)",
+                format!(
+                    "${:02X} ${:02X}",
+                    regs.prg_ram_protect[0], regs.prg_ram_protect[1]
+                ),
+            ),
+            MapperRegister::new("ExRAM Mode ($5104)", regs.exram_mode.bits),
+            MapperRegister::new(
+                "Nametables ($5105)",
+                format!("{:?}", regs.nametable_mapping.select),
+            ),
+            MapperRegister::new("Fill Tile ($5106)", format!("${:02X}", regs.fill.tile)),
+            MapperRegister::new("Fill Attribute ($5107)", regs.fill.attr),
+        ];
+        registers.extend(regs.prg_banks.iter().enumerate().map(|(i, bank)| {
+            MapperRegister::new(
+                format!("PRG Bank (${:04X})", 0x5113 + i),
+                format!("${bank:02X}"),
+            )
+        }));
+        registers.extend(regs.chr_banks.iter().enumerate().map(|(i, bank)| {
+            MapperRegister::new(
+                format!("CHR Bank (${:04X})", 0x5120 + i),
+                format!("${bank:02X}"),
+            )
+        }));
+        registers.extend([
+            MapperRegister::new("CHR Upper Bits ($5130)", regs.chr_hi),
+            MapperRegister::new("Vertical Split ($5200)", vsplit),
+            MapperRegister::new("Split Scroll ($5201)", regs.vsplit.scroll),
+            MapperRegister::new("Split Bank ($5202)", regs.vsplit.bank),
+            MapperRegister::new("IRQ Scanline ($5203)", regs.irq_scanline),
+            MapperRegister::new("IRQ Enabled ($5204)", regs.irq_enabled),
+            MapperRegister::new(
+                "Multiplier ($5205-$5206)",
+                format!(
+                    "{} * {} = {}",
+                    regs.multiplicand, regs.multiplier, regs.mult_result
+                ),
+            ),
+        ]);
+        registers.extend(MapperRegister::banks("CHR", &self.chr_banks));
+        registers
+    }
+}
+
 impl core::fmt::Debug for Exrom {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("Exrom")
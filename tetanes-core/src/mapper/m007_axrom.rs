@@ -5,10 +5,11 @@
 use crate::{
     cart::Cart,
     common::{Clock, Regional, Reset, Sram},
-    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MemMap},
+    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap},
     mem::MemBanks,
     ppu::Mirroring,
 };
+use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,3 +79,9 @@ impl Clock for Axrom {}
 impl Regional for Axrom {}
 impl Reset for Axrom {}
 impl Sram for Axrom {}
+
+impl MapperInfo for Axrom {
+    fn registers(&self) -> Vec<MapperRegister> {
+        MapperRegister::banks("PRG-ROM", &self.prg_rom_banks).collect()
+    }
+}
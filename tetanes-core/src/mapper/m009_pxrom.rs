@@ -5,9 +5,12 @@
 use crate::{
     cart::Cart,
     common::{Clock, Regional, Reset, ResetKind, Sram},
-    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MemMap, Mirroring},
+    mapper::{
+        Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap, Mirroring,
+    },
     mem::MemBanks,
 };
+use alloc::{format, vec, vec::Vec};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -137,3 +140,28 @@ impl Reset for Pxrom {
 impl Clock for Pxrom {}
 impl Regional for Pxrom {}
 impl Sram for Pxrom {}
+
+impl MapperInfo for Pxrom {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let mut registers = vec![
+            MapperRegister::new("Latch 0", if self.latch[0] == 0 { "$FD" } else { "$FE" }),
+            MapperRegister::new("Latch 1", if self.latch[1] == 0 { "$FD" } else { "$FE" }),
+        ];
+        registers.extend(
+            [
+                "$FD/0000 ($B000)",
+                "$FE/0000 ($C000)",
+                "$FD/1000 ($D000)",
+                "$FE/1000 ($E000)",
+            ]
+            .into_iter()
+            .zip(self.latch_banks)
+            .map(|(name, bank)| {
+                MapperRegister::new(format!("CHR-ROM {name}"), format!("${bank:02X}"))
+            }),
+        );
+        registers.extend(MapperRegister::banks("PRG-ROM", &self.prg_rom_banks));
+        registers.extend(MapperRegister::banks("CHR-ROM", &self.chr_banks));
+        registers
+    }
+}
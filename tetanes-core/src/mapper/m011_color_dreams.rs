@@ -5,9 +5,12 @@
 use crate::{
     cart::Cart,
     common::{Clock, Regional, Reset, Sram},
-    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MemMap, Mirroring},
+    mapper::{
+        Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap, Mirroring,
+    },
     mem::MemBanks,
 };
+use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,3 +75,12 @@ impl Clock for ColorDreams {}
 impl Regional for ColorDreams {}
 impl Reset for ColorDreams {}
 impl Sram for ColorDreams {}
+
+impl MapperInfo for ColorDreams {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let mut registers = Vec::new();
+        registers.extend(MapperRegister::banks("PRG-ROM", &self.prg_rom_banks));
+        registers.extend(MapperRegister::banks("CHR-ROM", &self.chr_banks));
+        registers
+    }
+}
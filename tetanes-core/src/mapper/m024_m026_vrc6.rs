@@ -6,10 +6,14 @@ use crate::{
     apu::PULSE_TABLE,
     cart::Cart,
     common::{Clock, Regional, Reset, ResetKind, Sample, Sram},
-    mapper::{vrc_irq::VrcIrq, Mapped, MappedRead, MappedWrite, Mapper, MemMap},
+    mapper::{
+        vrc_irq::VrcIrq, Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister,
+        MemMap,
+    },
     mem::MemBanks,
     ppu::Mirroring,
 };
+use alloc::{format, vec, vec::Vec};
 use serde::{Deserialize, Serialize};
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -229,6 +233,10 @@ impl Mapped for Vrc6 {
     fn set_mirroring(&mut self, mirroring: Mirroring) {
         self.mirroring = mirroring;
     }
+
+    fn irq_pending(&self) -> bool {
+        self.irq.pending
+    }
 }
 
 impl MemMap for Vrc6 {
@@ -349,6 +357,43 @@ impl Reset for Vrc6 {
 impl Regional for Vrc6 {}
 impl Sram for Vrc6 {}
 
+impl MapperInfo for Vrc6 {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let mut registers = vec![
+            MapperRegister::new("Revision", format!("{:?}", self.revision)),
+            MapperRegister::new(
+                "Banking Mode ($B003)",
+                format!("${:02X}", self.regs.banking_mode),
+            ),
+            MapperRegister::new("PRG-RAM Enabled", self.prg_ram_enabled()),
+        ];
+        registers.extend(
+            self.regs
+                .chr
+                .iter()
+                .enumerate()
+                .map(|(i, val)| MapperRegister::new(format!("CHR R{i}"), format!("${val:02X}"))),
+        );
+        registers.extend([
+            MapperRegister::new("IRQ Reload", self.irq.reload),
+            MapperRegister::new("IRQ Counter", self.irq.counter),
+            MapperRegister::new("IRQ Enabled", self.irq.enabled),
+            MapperRegister::new(
+                "IRQ Mode",
+                if self.irq.cycle_mode {
+                    "Cycle"
+                } else {
+                    "Scanline"
+                },
+            ),
+            MapperRegister::new("Nametable Pages", format!("{:?}", self.nt_banks)),
+        ]);
+        registers.extend(MapperRegister::banks("PRG-ROM", &self.prg_rom_banks));
+        registers.extend(MapperRegister::banks("CHR", &self.chr_banks));
+        registers
+    }
+}
+
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 #[must_use]
 pub struct Vrc6Audio {
@@ -367,7 +412,7 @@ impl Default for Vrc6Audio {
 }
 
 impl Vrc6Audio {
-    const fn new() -> Self {
+    pub const fn new() -> Self {
         Self {
             pulse1: Vrc6Pulse::new(),
             pulse2: Vrc6Pulse::new(),
@@ -379,12 +424,12 @@ impl Vrc6Audio {
     }
 
     #[must_use]
-    fn output(&self) -> f32 {
+    pub fn output(&self) -> f32 {
         let pulse_scale = PULSE_TABLE[PULSE_TABLE.len() - 1] / 15.0;
         pulse_scale * self.out
     }
 
-    fn write_register(&mut self, addr: u16, val: u8) {
+    pub fn write_register(&mut self, addr: u16, val: u8) {
         // Only A0, A1 and A12-15 are used for registers, remaining addresses are mirrored.
         match addr & 0xF003 {
             0x9000..=0x9002 => self.pulse1.write_register(addr, val),
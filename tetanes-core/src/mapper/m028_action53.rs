@@ -0,0 +1,269 @@
+//! `Action 53` (Mapper 028)
+//!
+//! <https://www.nesdev.org/wiki/Action_53_mapper>
+
+use crate::{
+    cart::Cart,
+    common::{Clock, Regional, Reset, ResetKind, Sram},
+    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap},
+    mem::MemBanks,
+    ppu::Mirroring,
+};
+use alloc::{format, vec, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub struct Regs {
+    /// Register selected by writes to $5000-$5FFF.
+    pub select: u8,
+    pub chr_bank: u8,
+    pub inner_bank: u8,
+    pub mode: u8,
+    pub outer_bank: u8,
+}
+
+impl Default for Regs {
+    fn default() -> Self {
+        // Power on with the last 32K of PRG-ROM mapped so the menu starts
+        Self {
+            select: 0x00,
+            chr_bank: 0x00,
+            inner_bank: 0x00,
+            mode: 0x00,
+            outer_bank: 0xFF,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct Action53 {
+    pub regs: Regs,
+    pub mirroring: Mirroring,
+    pub chr_banks: MemBanks,
+    pub prg_rom_banks: MemBanks,
+}
+
+impl Action53 {
+    const PRG_ROM_WINDOW: usize = 16 * 1024;
+    const CHR_WINDOW: usize = 8 * 1024;
+    const CHR_RAM_SIZE: usize = 32 * 1024;
+
+    const CHR_BANK: u8 = 0x00;
+    const INNER_BANK: u8 = 0x01;
+    const MODE: u8 = 0x80;
+    const OUTER_BANK: u8 = 0x81;
+
+    const MIRRORING_MASK: u8 = 0x03;
+    const ONE_SCREEN_B: u8 = 0x10; // Bit 4 of CHR and inner bank
+
+    pub fn load(cart: &mut Cart) -> Mapper {
+        if !cart.has_chr_rom() && cart.chr_ram.is_empty() {
+            cart.add_chr_ram(Self::CHR_RAM_SIZE);
+        }
+        let chr_len = if cart.has_chr_rom() {
+            cart.chr_rom.len()
+        } else {
+            cart.chr_ram.len()
+        };
+        let mut action53 = Self {
+            regs: Regs::default(),
+            mirroring: cart.mirroring(),
+            chr_banks: MemBanks::new(0x0000, 0x1FFF, chr_len, Self::CHR_WINDOW),
+            prg_rom_banks: MemBanks::new(0x8000, 0xFFFF, cart.prg_rom.len(), Self::PRG_ROM_WINDOW),
+        };
+        action53.update_banks();
+        action53.into()
+    }
+
+    /// Returns the 16K PRG-ROM bank mapped at `$8000` when `a14` is `0`, or `$C000` when `1`.
+    #[must_use]
+    pub const fn prg_bank(&self, a14: usize) -> usize {
+        let regs = &self.regs;
+        // Game size selects how many low bits of the 16K bank come from the inner bank,
+        // with the rest from the outer bank
+        let game_size = ((regs.mode >> 4) & 0x03) as usize;
+        let inner_mask = (2 << game_size) - 1;
+        let outer = (regs.outer_bank as usize) << 1;
+        let inner = regs.inner_bank as usize & 0x0F;
+        let inner = match (regs.mode >> 2) & 0x03 {
+            // 32K
+            0 | 1 => (inner << 1) | a14,
+            // 16K with $8000 fixed
+            2 if a14 == 0 => outer | a14,
+            // 16K with $C000 fixed
+            3 if a14 == 1 => outer | a14,
+            _ => inner,
+        };
+        (outer & !inner_mask) | (inner & inner_mask)
+    }
+
+    pub fn update_banks(&mut self) {
+        self.prg_rom_banks.set(0, self.prg_bank(0));
+        self.prg_rom_banks.set(1, self.prg_bank(1));
+        self.chr_banks.set(0, (self.regs.chr_bank & 0x03).into());
+        self.mirroring = match self.regs.mode & Self::MIRRORING_MASK {
+            0 => Mirroring::SingleScreenA,
+            1 => Mirroring::SingleScreenB,
+            2 => Mirroring::Vertical,
+            3 => Mirroring::Horizontal,
+            _ => unreachable!("impossible mirroring"),
+        };
+    }
+
+    fn write_register(&mut self, val: u8) {
+        match self.regs.select {
+            Self::CHR_BANK | Self::INNER_BANK => {
+                if self.regs.select == Self::CHR_BANK {
+                    self.regs.chr_bank = val;
+                } else {
+                    self.regs.inner_bank = val;
+                }
+                // One-screen mirroring can also be switched by CHR and inner bank writes
+                if self.regs.mode & 0x02 == 0x00 {
+                    self.regs.mode = (self.regs.mode & !0x01) | ((val & Self::ONE_SCREEN_B) >> 4);
+                }
+            }
+            Self::MODE => self.regs.mode = val,
+            Self::OUTER_BANK => self.regs.outer_bank = val,
+            _ => unreachable!("impossible register"),
+        }
+        self.update_banks();
+    }
+}
+
+impl Mapped for Action53 {
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn set_mirroring(&mut self, mirroring: Mirroring) {
+        self.mirroring = mirroring;
+    }
+}
+
+impl MemMap for Action53 {
+    // PPU $0000..=$1FFF 8K CHR-RAM Bank Switchable
+    // CPU $5000..=$5FFF Register Select
+    // CPU $8000..=$BFFF 16K PRG-ROM Bank Switchable or Fixed
+    // CPU $C000..=$FFFF 16K PRG-ROM Bank Switchable or Fixed
+
+    fn map_peek(&self, addr: u16) -> MappedRead {
+        match addr {
+            0x0000..=0x1FFF => MappedRead::Chr(self.chr_banks.translate(addr)),
+            0x8000..=0xFFFF => MappedRead::PrgRom(self.prg_rom_banks.translate(addr)),
+            _ => MappedRead::Bus,
+        }
+    }
+
+    fn map_write(&mut self, addr: u16, val: u8) -> MappedWrite {
+        match addr {
+            0x0000..=0x1FFF => return MappedWrite::Chr(self.chr_banks.translate(addr), val),
+            // 7  bit  0
+            // ---- ----
+            // S... ...R
+            // |       +- Select CHR/inner bank (0) or mode/outer bank (1)
+            // +--------- Select CHR/mode (0) or inner/outer bank (1)
+            0x5000..=0x5FFF => self.regs.select = val & 0x81,
+            0x8000..=0xFFFF => self.write_register(val),
+            _ => (),
+        }
+        MappedWrite::Bus
+    }
+}
+
+impl Reset for Action53 {
+    fn reset(&mut self, kind: ResetKind) {
+        // Registers keep their values on reset so the selected game restarts
+        if kind == ResetKind::Hard {
+            self.regs = Regs::default();
+            self.update_banks();
+        }
+    }
+}
+
+impl Clock for Action53 {}
+impl Regional for Action53 {}
+impl Sram for Action53 {}
+
+impl MapperInfo for Action53 {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let regs = &self.regs;
+        let prg_mode = match (regs.mode >> 2) & 0x03 {
+            0 | 1 => "32K",
+            2 => "16K, $8000 Fixed",
+            _ => "16K, $C000 Fixed",
+        };
+        let mut registers = vec![
+            MapperRegister::new("Register Select ($5000)", format!("${:02X}", regs.select)),
+            MapperRegister::new("CHR Bank ($00)", format!("${:02X}", regs.chr_bank)),
+            MapperRegister::new("Inner Bank ($01)", format!("${:02X}", regs.inner_bank)),
+            MapperRegister::new("Mode ($80)", format!("${:02X}", regs.mode)),
+            MapperRegister::new("Outer Bank ($81)", format!("${:02X}", regs.outer_bank)),
+            MapperRegister::new("PRG-ROM Mode", prg_mode),
+            MapperRegister::new("Game Size", format!("{}K", 32 << ((regs.mode >> 4) & 0x03))),
+        ];
+        registers.extend(MapperRegister::banks("PRG-ROM", &self.prg_rom_banks));
+        registers.extend(MapperRegister::banks("CHR", &self.chr_banks));
+        registers
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{
+        control_deck::ControlDeck,
+        mem::{Access, Mem},
+    };
+
+    #[test]
+    fn banking() {
+        let mut deck = ControlDeck::new();
+        // Action 53 with 128KB of PRG-ROM, each 16KB bank filled with its bank number
+        let mut rom = b"NES\x1A\x08\x00\xC0\x10\x00\x00\x00\x00\x00\x00\x00\x00".to_vec();
+        for bank in 0..8 {
+            rom.resize(rom.len() + 0x4000, bank);
+        }
+        deck.load_rom("action53", &mut rom.as_slice())
+            .expect("loaded rom");
+        let banks = |deck: &ControlDeck| {
+            (
+                deck.bus().peek(0x8000, Access::Dummy),
+                deck.bus().peek(0xC000, Access::Dummy),
+            )
+        };
+        let write = |deck: &mut ControlDeck, reg: u8, val: u8| {
+            deck.bus_mut().write(0x5000, reg, Access::Dummy);
+            deck.bus_mut().write(0x8000, val, Access::Dummy);
+        };
+        assert_eq!(banks(&deck), (6, 7), "last 32K on power on");
+
+        // 128K game in the second half of the ROM, 16K banks with $C000 fixed
+        write(&mut deck, 0x80, 0x2E);
+        write(&mut deck, 0x81, 0x03);
+        write(&mut deck, 0x01, 0x02);
+        assert_eq!(banks(&deck), (2, 7));
+        // 16K banks with $8000 fixed
+        write(&mut deck, 0x80, 0x2A);
+        assert_eq!(banks(&deck), (6, 2));
+        // 32K banks
+        write(&mut deck, 0x80, 0x22);
+        write(&mut deck, 0x01, 0x01);
+        assert_eq!(banks(&deck), (2, 3));
+        // 32K game only switches A14
+        write(&mut deck, 0x80, 0x02);
+        assert_eq!(banks(&deck), (6, 7));
+        assert_eq!(deck.mapper().mirroring(), Mirroring::Vertical);
+
+        // One-screen mirroring can be switched by inner bank writes
+        write(&mut deck, 0x80, 0x00);
+        assert_eq!(deck.mapper().mirroring(), Mirroring::SingleScreenA);
+        write(&mut deck, 0x01, 0x10);
+        assert_eq!(deck.mapper().mirroring(), Mirroring::SingleScreenB);
+
+        deck.reset(ResetKind::Soft);
+        assert_eq!(banks(&deck), (6, 7), "keeps banks on reset");
+    }
+}
@@ -0,0 +1,272 @@
+//! `UNROM 512` (Mapper 030)
+//!
+//! <https://www.nesdev.org/wiki/UNROM_512>
+
+use crate::{
+    cart::Cart,
+    common::{Clock, Regional, Reset, ResetKind, Sram},
+    mapper::{
+        flash::Flash, Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap,
+    },
+    mem::MemBanks,
+    ppu::Mirroring,
+};
+use alloc::{format, vec, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct Unrom512 {
+    pub mirroring: Mirroring,
+    /// Whether mirroring is switched between single-screen nametables by the bank register.
+    pub one_screen: bool,
+    pub bank_select: u8,
+    /// Self-flashable PRG-ROM, if the ROM header is battery-backed.
+    pub flash: Option<Flash>,
+    pub chr_banks: MemBanks,
+    pub prg_rom_banks: MemBanks,
+}
+
+impl Unrom512 {
+    const PRG_ROM_WINDOW: usize = 16 * 1024;
+    const CHR_WINDOW: usize = 8 * 1024;
+    const CHR_RAM_SIZE: usize = 32 * 1024;
+    const FOUR_SCREEN_RAM_SIZE: usize = 4 * 1024;
+
+    const PRG_BANK_MASK: u8 = 0x1F;
+    const CHR_BANK_MASK: u8 = 0x60;
+    const SINGLE_SCREEN_B: u8 = 0x80;
+
+    pub fn load(cart: &mut Cart) -> Mapper {
+        if !cart.has_chr_rom() && cart.chr_ram.is_empty() {
+            cart.add_chr_ram(Self::CHR_RAM_SIZE);
+        }
+        // Flags 6 bits 3 and 0 select horizontal, vertical, one-screen or four-screen mirroring
+        let one_screen = cart.header().flags & 0x09 == 0x08;
+        let mirroring = if one_screen {
+            Mirroring::SingleScreenA
+        } else {
+            cart.mirroring()
+        };
+        if mirroring == Mirroring::FourScreen {
+            // Nametables are stored in the last CHR-RAM bank, which games don't otherwise use
+            cart.add_exram(Self::FOUR_SCREEN_RAM_SIZE);
+        }
+        let chr_len = if cart.has_chr_rom() {
+            cart.chr_rom.len()
+        } else {
+            cart.chr_ram.len()
+        };
+        let mut unrom512 = Self {
+            mirroring,
+            one_screen,
+            bank_select: 0x00,
+            flash: cart.battery_backed().then(Flash::default),
+            chr_banks: MemBanks::new(0x0000, 0x1FFF, chr_len, Self::CHR_WINDOW),
+            prg_rom_banks: MemBanks::new(0x8000, 0xFFFF, cart.prg_rom.len(), Self::PRG_ROM_WINDOW),
+        };
+        let last_bank = unrom512.prg_rom_banks.last();
+        unrom512.prg_rom_banks.set(1, last_bank);
+        unrom512.into()
+    }
+
+    pub fn update_banks(&mut self) {
+        // 7  bit  0
+        // ---- ----
+        // MCCP PPPP
+        // |||+-++++- Select 16K PRG-ROM bank at $8000
+        // |++------- Select 8K CHR-RAM bank at PPU $0000
+        // +--------- Select single-screen nametable, if one-screen mirroring
+        self.prg_rom_banks
+            .set(0, (self.bank_select & Self::PRG_BANK_MASK).into());
+        self.chr_banks
+            .set(0, ((self.bank_select & Self::CHR_BANK_MASK) >> 5).into());
+        if self.one_screen {
+            self.mirroring = if self.bank_select & Self::SINGLE_SCREEN_B == Self::SINGLE_SCREEN_B {
+                Mirroring::SingleScreenB
+            } else {
+                Mirroring::SingleScreenA
+            };
+        }
+    }
+}
+
+impl Mapped for Unrom512 {
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn set_mirroring(&mut self, mirroring: Mirroring) {
+        self.mirroring = mirroring;
+    }
+}
+
+impl MemMap for Unrom512 {
+    // PPU $0000..=$1FFF 8K CHR-RAM Bank Switchable
+    // PPU $2000..=$3EFF FourScreen Mirroring (optional)
+    // CPU $8000..=$BFFF 16K PRG-ROM Bank Switchable
+    // CPU $C000..=$FFFF 16K PRG-ROM Fixed to Last Bank
+
+    fn map_peek(&self, addr: u16) -> MappedRead {
+        match addr {
+            0x0000..=0x1FFF => MappedRead::Chr(self.chr_banks.translate(addr)),
+            0x2000..=0x3EFF if self.mirroring == Mirroring::FourScreen => {
+                MappedRead::ExRam((addr & 0x0FFF) as usize)
+            }
+            0x8000..=0xFFFF => {
+                let addr = self.prg_rom_banks.translate(addr);
+                match self.flash.and_then(|flash| flash.read_id(addr)) {
+                    Some(id) => MappedRead::Data(id),
+                    None => MappedRead::PrgRom(addr),
+                }
+            }
+            _ => MappedRead::Bus,
+        }
+    }
+
+    fn map_write(&mut self, addr: u16, val: u8) -> MappedWrite {
+        match addr {
+            0x0000..=0x1FFF => MappedWrite::Chr(self.chr_banks.translate(addr), val),
+            0x2000..=0x3EFF if self.mirroring == Mirroring::FourScreen => {
+                MappedWrite::ExRam((addr & 0x0FFF) as usize, val)
+            }
+            // Self-flashable boards only decode the bank register at $C000-$FFFF
+            0x8000..=0xBFFF if self.flash.is_some() => {
+                let addr = self.prg_rom_banks.translate(addr);
+                self.flash
+                    .as_mut()
+                    .map_or(MappedWrite::Bus, |flash| flash.write(addr, val))
+            }
+            0x8000..=0xFFFF => {
+                self.bank_select = val;
+                self.update_banks();
+                MappedWrite::Bus
+            }
+            _ => MappedWrite::Bus,
+        }
+    }
+}
+
+impl Reset for Unrom512 {
+    fn reset(&mut self, _kind: ResetKind) {
+        self.bank_select = 0x00;
+        if let Some(flash) = &mut self.flash {
+            *flash = Flash::default();
+        }
+        self.update_banks();
+    }
+}
+
+impl Sram for Unrom512 {
+    fn flashable(&self) -> bool {
+        self.flash.is_some()
+    }
+}
+
+impl Clock for Unrom512 {}
+impl Regional for Unrom512 {}
+
+impl MapperInfo for Unrom512 {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let mut registers = vec![MapperRegister::new(
+            "Bank Select ($C000)",
+            format!("${:02X}", self.bank_select),
+        )];
+        if let Some(flash) = self.flash {
+            registers.extend([
+                MapperRegister::new("Flash Mode", format!("{:?}", flash.mode)),
+                MapperRegister::new("Flash Software ID", flash.software_id),
+            ]);
+        }
+        registers.extend(MapperRegister::banks("PRG-ROM", &self.prg_rom_banks));
+        registers.extend(MapperRegister::banks("CHR", &self.chr_banks));
+        registers
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{
+        common::Nvram,
+        control_deck::{Config, ControlDeck},
+        mem::{Access, Mem},
+    };
+
+    #[test]
+    fn flash() {
+        let dir = std::env::temp_dir().join(format!("tetanes_flash_{}", std::process::id()));
+        let mut deck = ControlDeck::with_config(Config {
+            data_dir: Some(dir.to_string_lossy().into_owned()),
+            ..Default::default()
+        });
+        // Battery-backed UNROM 512 with 32KB of erased PRG-ROM
+        let mut rom = b"NES\x1A\x02\x00\xE2\x10\x00\x00\x00\x00\x00\x00\x00\x00".to_vec();
+        rom.resize(16 + 0x8000, 0xFF);
+        let load_rom = |deck: &mut ControlDeck| {
+            deck.load_rom("unrom512", &mut rom.as_slice())
+                .expect("loaded rom");
+        };
+        // Commands are written to flash addresses $5555 and $2AAA, selected by the bank register
+        let command = |deck: &mut ControlDeck, cmds: &[u8]| {
+            for (i, &cmd) in cmds.iter().enumerate() {
+                let (bank, addr) = if i % 3 == 1 { (0, 0xAAAA) } else { (1, 0x9555) };
+                deck.bus_mut().write(0xC000, bank, Access::Dummy);
+                deck.bus_mut().write(addr, cmd, Access::Dummy);
+            }
+            deck.bus_mut().write(0xC000, 0x00, Access::Dummy);
+        };
+        load_rom(&mut deck);
+        let keys = deck
+            .nvram()
+            .iter()
+            .map(|nvram| nvram.key)
+            .collect::<Vec<_>>();
+        assert_eq!(keys, [Nvram::PRG_ROM]);
+
+        command(&mut deck, &[0xAA, 0x55, 0xA0]);
+        deck.bus_mut().write(0x8010, 0x5A, Access::Dummy);
+        assert_eq!(deck.bus().peek(0x8010, Access::Dummy), 0x5A, "programmed");
+        command(&mut deck, &[0xAA, 0x55, 0xA0]);
+        deck.bus_mut().write(0x8010, 0xA5, Access::Dummy);
+        assert_eq!(
+            deck.bus().peek(0x8010, Access::Dummy),
+            0x00,
+            "only clears bits"
+        );
+
+        command(&mut deck, &[0xAA, 0x55, 0x90]);
+        assert_eq!(
+            deck.bus().peek(0x8000, Access::Dummy),
+            0xBF,
+            "manufacturer id"
+        );
+        assert_eq!(deck.bus().peek(0x8001, Access::Dummy), 0xB7, "device id");
+        deck.bus_mut().write(0x8000, 0xF0, Access::Dummy);
+        assert_eq!(
+            deck.bus().peek(0x8000, Access::Dummy),
+            0xFF,
+            "exited software id"
+        );
+
+        command(&mut deck, &[0xAA, 0x55, 0xA0]);
+        deck.bus_mut().write(0x8020, 0x3C, Access::Dummy);
+        deck.unload_rom().expect("unloaded");
+        load_rom(&mut deck);
+        assert_eq!(
+            deck.bus().peek(0x8020, Access::Dummy),
+            0x3C,
+            "loaded on start"
+        );
+
+        command(&mut deck, &[0xAA, 0x55, 0x80, 0xAA, 0x55]);
+        deck.bus_mut().write(0x8000, 0x30, Access::Dummy);
+        assert_eq!(
+            deck.bus().peek(0x8020, Access::Dummy),
+            0xFF,
+            "sector erased"
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -5,10 +5,11 @@
 use crate::{
     cart::Cart,
     common::{Clock, Regional, Reset, Sram},
-    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MemMap},
+    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap},
     mem::MemBanks,
     ppu::Mirroring,
 };
+use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,3 +70,9 @@ impl Clock for Bnrom {}
 impl Regional for Bnrom {}
 impl Reset for Bnrom {}
 impl Sram for Bnrom {}
+
+impl MapperInfo for Bnrom {
+    fn registers(&self) -> Vec<MapperRegister> {
+        MapperRegister::banks("PRG-ROM", &self.prg_rom_banks).collect()
+    }
+}
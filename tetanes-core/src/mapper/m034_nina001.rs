@@ -5,10 +5,11 @@
 use crate::{
     cart::Cart,
     common::{Clock, Regional, Reset, Sram},
-    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MemMap},
+    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap},
     mem::MemBanks,
     ppu::Mirroring,
 };
+use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,3 +79,12 @@ impl Clock for Nina001 {}
 impl Regional for Nina001 {}
 impl Reset for Nina001 {}
 impl Sram for Nina001 {}
+
+impl MapperInfo for Nina001 {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let mut registers = Vec::new();
+        registers.extend(MapperRegister::banks("PRG-ROM", &self.prg_rom_banks));
+        registers.extend(MapperRegister::banks("CHR-ROM", &self.chr_banks));
+        registers
+    }
+}
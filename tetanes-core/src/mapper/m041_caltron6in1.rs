@@ -0,0 +1,136 @@
+//! `Caltron 6-in-1` (Mapper 041)
+//!
+//! <https://www.nesdev.org/wiki/INES_Mapper_041>
+
+use crate::{
+    cart::Cart,
+    common::{Clock, Regional, Reset, ResetKind, Sram},
+    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap},
+    mem::MemBanks,
+    ppu::Mirroring,
+};
+use alloc::{format, vec, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct Caltron6in1 {
+    pub outer_bank: u8,
+    pub inner_chr_bank: u8,
+    pub mirroring: Mirroring,
+    pub chr_banks: MemBanks,
+    pub prg_rom_banks: MemBanks,
+}
+
+impl Caltron6in1 {
+    const PRG_ROM_WINDOW: usize = 32 * 1024;
+    const CHR_WINDOW: usize = 8 * 1024;
+
+    const PRG_BANK_MASK: u8 = 0x07;
+    const INNER_CHR_ENABLE: u8 = 0x04;
+    const OUTER_CHR_MASK: u8 = 0x18;
+    const HORIZONTAL: u8 = 0x20;
+
+    pub fn load(cart: &mut Cart) -> Mapper {
+        let chr_len = if cart.has_chr_rom() {
+            cart.chr_rom.len()
+        } else {
+            cart.chr_ram.len()
+        };
+        let mut caltron = Self {
+            outer_bank: 0x00,
+            inner_chr_bank: 0x00,
+            mirroring: Mirroring::Vertical,
+            chr_banks: MemBanks::new(0x0000, 0x1FFF, chr_len, Self::CHR_WINDOW),
+            prg_rom_banks: MemBanks::new(0x8000, 0xFFFF, cart.prg_rom.len(), Self::PRG_ROM_WINDOW),
+        };
+        caltron.update_banks();
+        caltron.into()
+    }
+
+    pub fn update_banks(&mut self) {
+        self.prg_rom_banks
+            .set(0, (self.outer_bank & Self::PRG_BANK_MASK).into());
+        let chr_bank = ((self.outer_bank & Self::OUTER_CHR_MASK) >> 1) | self.inner_chr_bank;
+        self.chr_banks.set(0, chr_bank.into());
+        self.mirroring = if self.outer_bank & Self::HORIZONTAL == Self::HORIZONTAL {
+            Mirroring::Horizontal
+        } else {
+            Mirroring::Vertical
+        };
+    }
+}
+
+impl Mapped for Caltron6in1 {
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn set_mirroring(&mut self, mirroring: Mirroring) {
+        self.mirroring = mirroring;
+    }
+}
+
+impl MemMap for Caltron6in1 {
+    // PPU $0000..=$1FFF 8K CHR-ROM Bank Switchable
+    // CPU $6000..=$67FF Outer Bank Select (address latch)
+    // CPU $8000..=$FFFF 32K PRG-ROM Bank Switchable, Inner CHR Bank Select
+
+    fn map_peek(&self, addr: u16) -> MappedRead {
+        match addr {
+            0x0000..=0x1FFF => MappedRead::Chr(self.chr_banks.translate(addr)),
+            0x8000..=0xFFFF => MappedRead::PrgRom(self.prg_rom_banks.translate(addr)),
+            _ => MappedRead::Bus,
+        }
+    }
+
+    fn map_write(&mut self, addr: u16, val: u8) -> MappedWrite {
+        match addr {
+            0x0000..=0x1FFF => return MappedWrite::Chr(self.chr_banks.translate(addr), val),
+            // 7  bit  0 (address)
+            // ---- ----
+            // ..MC CPPP
+            //   || |+++- Select 32K PRG-ROM bank, with bit 2 enabling the inner CHR bank
+            //   |+-+---- Select upper 2 bits of 8K CHR-ROM bank
+            //   +------- Mirroring (0: vertical; 1: horizontal)
+            0x6000..=0x67FF => {
+                self.outer_bank = (addr & 0xFF) as u8;
+                self.update_banks();
+            }
+            // Inner CHR bank is only writable while the selected PRG-ROM bank has bit 2 set
+            0x8000..=0xFFFF
+                if self.outer_bank & Self::INNER_CHR_ENABLE == Self::INNER_CHR_ENABLE =>
+            {
+                self.inner_chr_bank = val & 0x03;
+                self.update_banks();
+            }
+            _ => (),
+        }
+        MappedWrite::Bus
+    }
+}
+
+impl Reset for Caltron6in1 {
+    fn reset(&mut self, _kind: ResetKind) {
+        // Resetting returns to the menu
+        self.outer_bank = 0x00;
+        self.inner_chr_bank = 0x00;
+        self.update_banks();
+    }
+}
+
+impl Clock for Caltron6in1 {}
+impl Regional for Caltron6in1 {}
+impl Sram for Caltron6in1 {}
+
+impl MapperInfo for Caltron6in1 {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let mut registers = vec![
+            MapperRegister::new("Outer Bank ($6000)", format!("${:02X}", self.outer_bank)),
+            MapperRegister::new("Inner CHR Bank ($8000)", self.inner_chr_bank),
+        ];
+        registers.extend(MapperRegister::banks("PRG-ROM", &self.prg_rom_banks));
+        registers.extend(MapperRegister::banks("CHR", &self.chr_banks));
+        registers
+    }
+}
@@ -0,0 +1,152 @@
+//! `Super Big 7-in-1` MMC3 multicart (Mapper 044)
+//!
+//! <https://www.nesdev.org/wiki/INES_Mapper_044>
+
+use crate::{
+    cart::Cart,
+    common::{Clock, Regional, Reset, ResetKind, Sram},
+    mapper::{
+        m004_txrom::OuterBank, Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister,
+        MemMap, Txrom,
+    },
+    ppu::Mirroring,
+};
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct SuperBig7in1 {
+    /// Game block selected by writes to `$A001`, replacing the MMC3 PRG-RAM protect register.
+    pub block: u8,
+    pub txrom: Txrom,
+}
+
+impl SuperBig7in1 {
+    const BLOCK_MASK: u8 = 0x07;
+
+    pub fn load(cart: &mut Cart) -> Mapper {
+        let mut super_big = Self {
+            block: 0x00,
+            txrom: Txrom::new(cart),
+        };
+        super_big.update_banks();
+        super_big.into()
+    }
+
+    pub fn update_banks(&mut self) {
+        // Blocks 0-5 are 128K PRG-ROM/128K CHR-ROM, and blocks 6 and 7 are both the last
+        // 256K PRG-ROM/256K CHR-ROM
+        let block = usize::from(self.block);
+        let (prg_mask, chr_mask) = if block >= 6 {
+            (0x1F, 0xFF)
+        } else {
+            (0x0F, 0x7F)
+        };
+        self.txrom.set_outer_bank(Some(OuterBank {
+            prg_base: block << 4,
+            prg_mask,
+            chr_base: block << 7,
+            chr_mask,
+        }));
+    }
+}
+
+impl Mapped for SuperBig7in1 {
+    fn mirroring(&self) -> Mirroring {
+        self.txrom.mirroring()
+    }
+
+    fn set_mirroring(&mut self, mirroring: Mirroring) {
+        self.txrom.set_mirroring(mirroring);
+    }
+
+    fn ppu_bus_read(&mut self, addr: u16) {
+        self.txrom.ppu_bus_read(addr);
+    }
+
+    fn ppu_bus_write(&mut self, addr: u16, val: u8) {
+        self.txrom.ppu_bus_write(addr, val);
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.txrom.irq_pending()
+    }
+}
+
+impl MemMap for SuperBig7in1 {
+    // Same as MMC3, with $A001 selecting the game block instead of protecting PRG-RAM
+
+    fn map_read(&mut self, addr: u16) -> MappedRead {
+        self.txrom.map_read(addr)
+    }
+
+    fn map_peek(&self, addr: u16) -> MappedRead {
+        self.txrom.map_peek(addr)
+    }
+
+    fn map_write(&mut self, addr: u16, val: u8) -> MappedWrite {
+        if addr & 0xE001 == 0xA001 {
+            self.block = val & Self::BLOCK_MASK;
+            self.update_banks();
+            MappedWrite::Bus
+        } else {
+            self.txrom.map_write(addr, val)
+        }
+    }
+}
+
+impl Reset for SuperBig7in1 {
+    fn reset(&mut self, kind: ResetKind) {
+        // Resetting returns to the menu in the first block
+        self.block = 0x00;
+        self.txrom.reset(kind);
+        self.update_banks();
+    }
+}
+
+impl Clock for SuperBig7in1 {}
+impl Regional for SuperBig7in1 {}
+impl Sram for SuperBig7in1 {}
+
+impl MapperInfo for SuperBig7in1 {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let mut registers = self.txrom.registers();
+        registers.insert(0, MapperRegister::new("Block ($A001)", self.block));
+        registers
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{
+        control_deck::ControlDeck,
+        mem::{Access, Mem},
+    };
+
+    #[test]
+    fn banking() {
+        let mut deck = ControlDeck::new();
+        // Super Big 7-in-1 with 1MB of PRG-ROM, each 8KB bank filled with its bank number
+        let mut rom = b"NES\x1A\x40\x00\xC0\x20\x00\x00\x00\x00\x00\x00\x00\x00".to_vec();
+        for bank in 0..128 {
+            rom.resize(rom.len() + 0x2000, bank);
+        }
+        deck.load_rom("super_big_7in1", &mut rom.as_slice())
+            .expect("loaded rom");
+        let banks = |deck: &ControlDeck| {
+            (
+                deck.bus().peek(0x8000, Access::Dummy),
+                deck.bus().peek(0xE000, Access::Dummy),
+            )
+        };
+        assert_eq!(banks(&deck), (0, 15));
+        deck.bus_mut().write(0xA001, 0x02, Access::Dummy);
+        assert_eq!(banks(&deck), (32, 47), "128K block");
+        deck.bus_mut().write(0xA001, 0x06, Access::Dummy);
+        assert_eq!(banks(&deck), (96, 127), "256K block");
+        deck.reset(ResetKind::Soft);
+        assert_eq!(banks(&deck), (0, 15), "back to menu");
+    }
+}
@@ -0,0 +1,150 @@
+//! `Super HIK 4-in-1` MMC3 multicart (Mapper 049)
+//!
+//! <https://www.nesdev.org/wiki/INES_Mapper_049>
+
+use crate::{
+    cart::Cart,
+    common::{Clock, Regional, Reset, ResetKind, Sram},
+    mapper::{
+        m004_txrom::OuterBank, Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister,
+        MemMap, Txrom,
+    },
+    ppu::Mirroring,
+};
+use alloc::{format, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct SuperHik4in1 {
+    /// Outer bank register at `$6000-$7FFF`.
+    pub outer_bank: u8,
+    /// Last value written to the MMC3 PRG-RAM protect register, which enables writes to the
+    /// outer bank register.
+    pub ram_protect: u8,
+    pub txrom: Txrom,
+}
+
+impl SuperHik4in1 {
+    const PRG_ROM_WINDOW_PAGES: usize = 4; // 32K of 8K pages
+    const MMC3_MODE: u8 = 0x01;
+    const OUTER_BANK_WRITABLE: u8 = 0x80;
+
+    pub fn load(cart: &mut Cart) -> Mapper {
+        let mut super_hik = Self {
+            outer_bank: 0x00,
+            ram_protect: 0x00,
+            txrom: Txrom::new(cart),
+        };
+        super_hik.update_banks();
+        super_hik.into()
+    }
+
+    pub fn update_banks(&mut self) {
+        // 7  bit  0
+        // ---- ----
+        // BBPP ...M
+        // ||||    +- PRG-ROM mode (0: 32K selected by BBPP; 1: MMC3)
+        // ||++------ Select 32K PRG-ROM bank within the block, if not MMC3 mode
+        // ++-------- Select 128K PRG-ROM/CHR-ROM block
+        let block = usize::from(self.outer_bank >> 6);
+        self.txrom.set_outer_bank(Some(OuterBank {
+            prg_base: block << 4,
+            prg_mask: 0x0F,
+            chr_base: block << 7,
+            chr_mask: 0x7F,
+        }));
+        if self.outer_bank & Self::MMC3_MODE == 0 {
+            let bank = usize::from(self.outer_bank >> 4) * Self::PRG_ROM_WINDOW_PAGES;
+            self.txrom.prg_rom_banks.set_range(0, 3, bank);
+        }
+    }
+}
+
+impl Mapped for SuperHik4in1 {
+    fn mirroring(&self) -> Mirroring {
+        self.txrom.mirroring()
+    }
+
+    fn set_mirroring(&mut self, mirroring: Mirroring) {
+        self.txrom.set_mirroring(mirroring);
+    }
+
+    fn ppu_bus_read(&mut self, addr: u16) {
+        self.txrom.ppu_bus_read(addr);
+    }
+
+    fn ppu_bus_write(&mut self, addr: u16, val: u8) {
+        self.txrom.ppu_bus_write(addr, val);
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.txrom.irq_pending()
+    }
+}
+
+impl MemMap for SuperHik4in1 {
+    // Same as MMC3, with $6000-$7FFF as the outer bank register instead of PRG-RAM
+    // CPU $8000..=$FFFF 32K PRG-ROM Bank Switchable, if not MMC3 mode
+
+    fn map_read(&mut self, addr: u16) -> MappedRead {
+        match addr {
+            0x6000..=0x7FFF => MappedRead::Bus,
+            _ => self.txrom.map_read(addr),
+        }
+    }
+
+    fn map_peek(&self, addr: u16) -> MappedRead {
+        match addr {
+            0x6000..=0x7FFF => MappedRead::Bus,
+            _ => self.txrom.map_peek(addr),
+        }
+    }
+
+    fn map_write(&mut self, addr: u16, val: u8) -> MappedWrite {
+        match addr {
+            0x6000..=0x7FFF => {
+                if self.ram_protect & Self::OUTER_BANK_WRITABLE == Self::OUTER_BANK_WRITABLE {
+                    self.outer_bank = val;
+                    self.update_banks();
+                }
+                MappedWrite::Bus
+            }
+            0x8000..=0xFFFF => {
+                if addr & 0xE001 == 0xA001 {
+                    self.ram_protect = val;
+                }
+                let write = self.txrom.map_write(addr, val);
+                // MMC3 bank updates don't know about 32K mode
+                self.update_banks();
+                write
+            }
+            _ => self.txrom.map_write(addr, val),
+        }
+    }
+}
+
+impl Reset for SuperHik4in1 {
+    fn reset(&mut self, kind: ResetKind) {
+        // Resetting returns to the menu in the first 32K bank
+        self.outer_bank = 0x00;
+        self.ram_protect = 0x00;
+        self.txrom.reset(kind);
+        self.update_banks();
+    }
+}
+
+impl Clock for SuperHik4in1 {}
+impl Regional for SuperHik4in1 {}
+impl Sram for SuperHik4in1 {}
+
+impl MapperInfo for SuperHik4in1 {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let mut registers = self.txrom.registers();
+        registers.insert(
+            0,
+            MapperRegister::new("Outer Bank ($6000)", format!("${:02X}", self.outer_bank)),
+        );
+        registers
+    }
+}
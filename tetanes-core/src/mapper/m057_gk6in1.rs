@@ -0,0 +1,219 @@
+//! `GK 6-in-1` (Mapper 057)
+//!
+//! <https://www.nesdev.org/wiki/INES_Mapper_057>
+
+use crate::{
+    cart::Cart,
+    common::{Clock, Regional, Reset, ResetKind, Sram},
+    mapper::{
+        DipSwitches, Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap,
+    },
+    mem::MemBanks,
+    ppu::Mirroring,
+};
+use alloc::{format, vec, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct Gk6in1 {
+    pub chr_select: u8,
+    pub mode_select: u8,
+    /// Menu DIP switches, which select the number of games the menu lists.
+    pub dip_switches: u8,
+    pub mirroring: Mirroring,
+    pub chr_banks: MemBanks,
+    pub prg_rom_banks: MemBanks,
+}
+
+impl Gk6in1 {
+    const PRG_ROM_WINDOW: usize = 16 * 1024;
+    const CHR_WINDOW: usize = 8 * 1024;
+    const DIP_SWITCH_COUNT: u8 = 2;
+
+    const CHR_BANK_MASK: u8 = 0x07;
+    const CHR_BANK_HI: u8 = 0x40;
+    const PRG_32K: u8 = 0x10;
+    const HORIZONTAL: u8 = 0x08;
+
+    pub fn load(cart: &mut Cart) -> Mapper {
+        let chr_len = if cart.has_chr_rom() {
+            cart.chr_rom.len()
+        } else {
+            cart.chr_ram.len()
+        };
+        let mut gk6in1 = Self {
+            chr_select: 0x00,
+            mode_select: 0x00,
+            dip_switches: 0x00,
+            mirroring: Mirroring::Vertical,
+            chr_banks: MemBanks::new(0x0000, 0x1FFF, chr_len, Self::CHR_WINDOW),
+            prg_rom_banks: MemBanks::new(0x8000, 0xFFFF, cart.prg_rom.len(), Self::PRG_ROM_WINDOW),
+        };
+        gk6in1.update_banks();
+        gk6in1.into()
+    }
+
+    pub const fn dip_switches(&self) -> DipSwitches {
+        DipSwitches::new(Self::DIP_SWITCH_COUNT, self.dip_switches)
+    }
+
+    pub fn set_dip_switches(&mut self, value: u8) {
+        self.dip_switches = value & ((1 << Self::DIP_SWITCH_COUNT) - 1);
+    }
+
+    pub fn update_banks(&mut self) {
+        let mode = self.mode_select;
+        if mode & Self::PRG_32K == Self::PRG_32K {
+            let bank = usize::from(mode >> 6) << 1;
+            self.prg_rom_banks.set(0, bank);
+            self.prg_rom_banks.set(1, bank | 1);
+        } else {
+            // 16K mirrored at $8000 and $C000
+            let bank = usize::from(mode >> 5);
+            self.prg_rom_banks.set(0, bank);
+            self.prg_rom_banks.set(1, bank);
+        }
+        let chr_bank = (mode & Self::CHR_BANK_MASK)
+            | (self.chr_select & Self::CHR_BANK_MASK)
+            | ((self.chr_select & Self::CHR_BANK_HI) >> 3);
+        self.chr_banks.set(0, chr_bank.into());
+        self.mirroring = if mode & Self::HORIZONTAL == Self::HORIZONTAL {
+            Mirroring::Horizontal
+        } else {
+            Mirroring::Vertical
+        };
+    }
+}
+
+impl Mapped for Gk6in1 {
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn set_mirroring(&mut self, mirroring: Mirroring) {
+        self.mirroring = mirroring;
+    }
+}
+
+impl MemMap for Gk6in1 {
+    // PPU $0000..=$1FFF 8K CHR-ROM Bank Switchable
+    // CPU $6000..=$7FFF Menu DIP Switches
+    // CPU $8000..=$FFFF 16K or 32K PRG-ROM Bank Switchable
+    // CPU $8000..=$FFFF CHR Select ($8000) or Mode Select ($8800), decoded by A11
+
+    fn map_peek(&self, addr: u16) -> MappedRead {
+        match addr {
+            0x0000..=0x1FFF => MappedRead::Chr(self.chr_banks.translate(addr)),
+            0x6000..=0x7FFF => MappedRead::Data(self.dip_switches),
+            0x8000..=0xFFFF => MappedRead::PrgRom(self.prg_rom_banks.translate(addr)),
+            _ => MappedRead::Bus,
+        }
+    }
+
+    fn map_write(&mut self, addr: u16, val: u8) -> MappedWrite {
+        match addr {
+            0x0000..=0x1FFF => return MappedWrite::Chr(self.chr_banks.translate(addr), val),
+            // 7  bit  0
+            // ---- ----
+            // PPPO MCCC
+            // |||| |+++- Select 8K CHR-ROM bank, ORed with $8000
+            // |||| +---- Mirroring (0: vertical; 1: horizontal)
+            // |||+------ PRG-ROM mode (0: 16K mirrored; 1: 32K)
+            // +++------- Select 16K PRG-ROM bank, or 32K bank with bits 7-6
+            0x8000..=0xFFFF if addr & 0x0800 == 0x0800 => {
+                self.mode_select = val;
+                self.update_banks();
+            }
+            // 7  bit  0
+            // ---- ----
+            // .H.. .CCC
+            //  |    +++- Select 8K CHR-ROM bank, ORed with $8800
+            //  +-------- Select bit 3 of 8K CHR-ROM bank
+            0x8000..=0xFFFF => {
+                self.chr_select = val;
+                self.update_banks();
+            }
+            _ => (),
+        }
+        MappedWrite::Bus
+    }
+}
+
+impl Reset for Gk6in1 {
+    fn reset(&mut self, _kind: ResetKind) {
+        // DIP switches are external to the registers and keep their setting
+        self.chr_select = 0x00;
+        self.mode_select = 0x00;
+        self.update_banks();
+    }
+}
+
+impl Clock for Gk6in1 {}
+impl Regional for Gk6in1 {}
+impl Sram for Gk6in1 {}
+
+impl MapperInfo for Gk6in1 {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let mut registers = vec![
+            MapperRegister::new("CHR Select ($8000)", format!("${:02X}", self.chr_select)),
+            MapperRegister::new("Mode Select ($8800)", format!("${:02X}", self.mode_select)),
+            MapperRegister::new("DIP Switches ($6000)", format!("{:02b}", self.dip_switches)),
+        ];
+        registers.extend(MapperRegister::banks("PRG-ROM", &self.prg_rom_banks));
+        registers.extend(MapperRegister::banks("CHR", &self.chr_banks));
+        registers
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{
+        control_deck::ControlDeck,
+        mem::{Access, Mem},
+    };
+
+    #[test]
+    fn dip_switches() {
+        let mut deck = ControlDeck::new();
+        // GK 6-in-1 with 128KB of PRG-ROM, each 16KB bank filled with its bank number
+        let mut rom = b"NES\x1A\x08\x10\x90\x30\x00\x00\x00\x00\x00\x00\x00\x00".to_vec();
+        for bank in 0..8 {
+            rom.resize(rom.len() + 0x4000, bank);
+        }
+        rom.resize(rom.len() + 0x20000, 0x00);
+        deck.load_rom("gk6in1", &mut rom.as_slice())
+            .expect("loaded rom");
+        let peek = |deck: &ControlDeck, addr| deck.bus().peek(addr, Access::Dummy);
+        assert_eq!(deck.dip_switches(), Some(DipSwitches::new(2, 0)));
+        assert_eq!(peek(&deck, 0x6000), 0x00);
+
+        deck.set_rom_dip_switches("gk6in1", Some(0x02));
+        assert_eq!(peek(&deck, 0x6000), 0x02, "menu dip switches");
+        assert_eq!(
+            deck.loaded_rom().and_then(|rom| rom.dip_switches),
+            deck.dip_switches()
+        );
+
+        deck.bus_mut().write(0x8800, 0x40, Access::Dummy);
+        assert_eq!(
+            (peek(&deck, 0x8000), peek(&deck, 0xC000)),
+            (2, 2),
+            "16K mirrored"
+        );
+        deck.bus_mut().write(0x8800, 0x50, Access::Dummy);
+        assert_eq!((peek(&deck, 0x8000), peek(&deck, 0xC000)), (2, 3), "32K");
+
+        deck.reset(ResetKind::Soft);
+        assert_eq!(
+            (peek(&deck, 0x8000), peek(&deck, 0x6000)),
+            (0, 0x02),
+            "back to menu"
+        );
+        deck.unload_rom().expect("unloaded rom");
+        deck.load_rom("gk6in1", &mut rom.as_slice())
+            .expect("loaded rom");
+        assert_eq!(peek(&deck, 0x6000), 0x02, "per-rom setting");
+    }
+}
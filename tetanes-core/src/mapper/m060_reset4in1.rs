@@ -0,0 +1,143 @@
+//! `Reset-based NROM-128 4-in-1` (Mapper 060)
+//!
+//! <https://www.nesdev.org/wiki/NES_2.0_Mapper_060>
+
+use crate::{
+    cart::Cart,
+    common::{Clock, Regional, Reset, ResetKind, Sram},
+    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap},
+    mem::MemBanks,
+    ppu::Mirroring,
+};
+use alloc::{vec, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct Reset4in1 {
+    /// Game selected by counting resets since power on.
+    pub game: u8,
+    pub mirroring: Mirroring,
+    pub chr_banks: MemBanks,
+    pub prg_rom_banks: MemBanks,
+}
+
+impl Reset4in1 {
+    const PRG_ROM_WINDOW: usize = 16 * 1024;
+    const CHR_WINDOW: usize = 8 * 1024;
+    const GAME_COUNT: u8 = 4;
+
+    pub fn load(cart: &mut Cart) -> Mapper {
+        let chr_len = if cart.has_chr_rom() {
+            cart.chr_rom.len()
+        } else {
+            cart.chr_ram.len()
+        };
+        let mut reset4in1 = Self {
+            game: 0,
+            mirroring: cart.mirroring(),
+            chr_banks: MemBanks::new(0x0000, 0x1FFF, chr_len, Self::CHR_WINDOW),
+            prg_rom_banks: MemBanks::new(0x8000, 0xFFFF, cart.prg_rom.len(), Self::PRG_ROM_WINDOW),
+        };
+        reset4in1.update_banks();
+        reset4in1.into()
+    }
+
+    pub fn update_banks(&mut self) {
+        // Each game is NROM-128, mirrored at $8000 and $C000
+        self.prg_rom_banks.set(0, self.game.into());
+        self.prg_rom_banks.set(1, self.game.into());
+        self.chr_banks.set(0, self.game.into());
+    }
+}
+
+impl Mapped for Reset4in1 {
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn set_mirroring(&mut self, mirroring: Mirroring) {
+        self.mirroring = mirroring;
+    }
+}
+
+impl MemMap for Reset4in1 {
+    // PPU $0000..=$1FFF 8K CHR-ROM Bank Selected by Reset
+    // CPU $8000..=$BFFF 16K PRG-ROM Bank Selected by Reset
+    // CPU $C000..=$FFFF 16K PRG-ROM Bank Mirror of $8000
+
+    fn map_peek(&self, addr: u16) -> MappedRead {
+        match addr {
+            0x0000..=0x1FFF => MappedRead::Chr(self.chr_banks.translate(addr)),
+            0x8000..=0xFFFF => MappedRead::PrgRom(self.prg_rom_banks.translate(addr)),
+            _ => MappedRead::Bus,
+        }
+    }
+
+    fn map_write(&mut self, addr: u16, val: u8) -> MappedWrite {
+        match addr {
+            0x0000..=0x1FFF => MappedWrite::Chr(self.chr_banks.translate(addr), val),
+            _ => MappedWrite::Bus,
+        }
+    }
+}
+
+impl Reset for Reset4in1 {
+    fn reset(&mut self, kind: ResetKind) {
+        // Pressing reset starts the next game, while power cycling starts the first
+        self.game = match kind {
+            ResetKind::Soft => (self.game + 1) % Self::GAME_COUNT,
+            ResetKind::Hard => 0,
+        };
+        self.update_banks();
+    }
+}
+
+impl Clock for Reset4in1 {}
+impl Regional for Reset4in1 {}
+impl Sram for Reset4in1 {}
+
+impl MapperInfo for Reset4in1 {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let mut registers = vec![MapperRegister::new("Game", self.game)];
+        registers.extend(MapperRegister::banks("PRG-ROM", &self.prg_rom_banks));
+        registers.extend(MapperRegister::banks("CHR", &self.chr_banks));
+        registers
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{
+        control_deck::ControlDeck,
+        mem::{Access, Mem},
+    };
+
+    #[test]
+    fn reset_cycling() {
+        let mut deck = ControlDeck::new();
+        // Reset-based 4-in-1 with 64KB of PRG-ROM, each 16KB bank filled with its bank number
+        let mut rom = b"NES\x1A\x04\x04\xC0\x30\x00\x00\x00\x00\x00\x00\x00\x00".to_vec();
+        for bank in 0..4 {
+            rom.resize(rom.len() + 0x4000, bank);
+        }
+        rom.resize(rom.len() + 0x8000, 0x00);
+        deck.load_rom("reset4in1", &mut rom.as_slice())
+            .expect("loaded rom");
+        let banks = |deck: &ControlDeck| {
+            (
+                deck.bus().peek(0x8000, Access::Dummy),
+                deck.bus().peek(0xC000, Access::Dummy),
+            )
+        };
+        assert_eq!(banks(&deck), (0, 0));
+        for game in [1, 2, 3, 0] {
+            deck.reset(ResetKind::Soft);
+            assert_eq!(banks(&deck), (game, game), "next game on reset");
+        }
+        deck.reset(ResetKind::Soft);
+        deck.reset(ResetKind::Hard);
+        assert_eq!(banks(&deck), (0, 0), "first game on power on");
+    }
+}
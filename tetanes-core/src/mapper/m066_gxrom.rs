@@ -5,10 +5,11 @@
 use crate::{
     cart::Cart,
     common::{Clock, Regional, Reset, Sram},
-    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MemMap},
+    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap},
     mem::MemBanks,
     ppu::Mirroring,
 };
+use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,3 +73,12 @@ impl Clock for Gxrom {}
 impl Regional for Gxrom {}
 impl Reset for Gxrom {}
 impl Sram for Gxrom {}
+
+impl MapperInfo for Gxrom {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let mut registers = Vec::new();
+        registers.extend(MapperRegister::banks("PRG-ROM", &self.prg_rom_banks));
+        registers.extend(MapperRegister::banks("CHR-ROM", &self.chr_banks));
+        registers
+    }
+}
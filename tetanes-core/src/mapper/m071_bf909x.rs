@@ -5,10 +5,11 @@
 use crate::{
     cart::Cart,
     common::{Clock, Regional, Reset, Sram},
-    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MemMap},
+    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap},
     mem::MemBanks,
     ppu::Mirroring,
 };
+use alloc::{format, vec, vec::Vec};
 use serde::{Deserialize, Serialize};
 
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -106,3 +107,14 @@ impl Clock for Bf909x {}
 impl Regional for Bf909x {}
 impl Reset for Bf909x {}
 impl Sram for Bf909x {}
+
+impl MapperInfo for Bf909x {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let mut registers = vec![MapperRegister::new(
+            "Revision",
+            format!("{:?}", self.revision),
+        )];
+        registers.extend(MapperRegister::banks("PRG-ROM", &self.prg_rom_banks));
+        registers
+    }
+}
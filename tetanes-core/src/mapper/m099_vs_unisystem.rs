@@ -0,0 +1,184 @@
+//! `Vs. Unisystem` (Mapper 099)
+//!
+//! <https://www.nesdev.org/wiki/INES_Mapper_099>
+
+use crate::{
+    cart::Cart,
+    common::{Clock, Regional, Reset, ResetKind, Sram},
+    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap},
+    mem::MemBanks,
+    ppu::Mirroring,
+};
+use alloc::{vec, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct VsUnisystem {
+    /// Bank select from D2 written to `$4016`, which also strobes the controllers.
+    pub bank_select: bool,
+    pub mirroring: Mirroring,
+    pub chr_banks: MemBanks,
+    pub prg_rom_banks: MemBanks,
+}
+
+impl VsUnisystem {
+    const PRG_RAM_SIZE: usize = 2 * 1024;
+    const PRG_ROM_WINDOW: usize = 8 * 1024;
+    const CHR_WINDOW: usize = 8 * 1024;
+
+    const BANK_SELECT: u8 = 0x04;
+
+    pub fn load(cart: &mut Cart) -> Mapper {
+        cart.add_prg_ram(Self::PRG_RAM_SIZE);
+        let chr_len = if cart.has_chr_rom() {
+            cart.chr_rom.len()
+        } else {
+            cart.chr_ram.len()
+        };
+        let mut vs = Self {
+            bank_select: false,
+            mirroring: cart.mirroring(),
+            chr_banks: MemBanks::new(0x0000, 0x1FFF, chr_len, Self::CHR_WINDOW),
+            prg_rom_banks: MemBanks::new(0x8000, 0xFFFF, cart.prg_rom.len(), Self::PRG_ROM_WINDOW),
+        };
+        vs.prg_rom_banks.set_range(0, 3, 0);
+        vs.update_banks();
+        vs.into()
+    }
+
+    pub fn update_banks(&mut self) {
+        let bank = usize::from(self.bank_select);
+        self.chr_banks.set(0, bank);
+        // Only 40K PRG-ROM games, like Vs. Gumshoe, switch the first 8K PRG-ROM bank to the extra
+        // bank past the first 32K
+        if self.prg_rom_banks.page_count() > 4 {
+            self.prg_rom_banks.set(0, bank * 4);
+        }
+    }
+}
+
+impl Mapped for VsUnisystem {
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn set_mirroring(&mut self, mirroring: Mirroring) {
+        self.mirroring = mirroring;
+    }
+
+    fn cpu_bus_write(&mut self, addr: u16, val: u8) {
+        if addr == 0x4016 {
+            self.bank_select = val & Self::BANK_SELECT == Self::BANK_SELECT;
+            self.update_banks();
+        }
+    }
+}
+
+impl MemMap for VsUnisystem {
+    // PPU $0000..=$1FFF 8K CHR-ROM Bank Switchable
+    // CPU $6000..=$7FFF 2K PRG-RAM, mirrored
+    // CPU $8000..=$9FFF 8K PRG-ROM Bank Switchable for 40K PRG-ROM
+    // CPU $A000..=$FFFF 24K PRG-ROM Fixed
+
+    fn map_peek(&self, addr: u16) -> MappedRead {
+        match addr {
+            0x0000..=0x1FFF => MappedRead::Chr(self.chr_banks.translate(addr)),
+            0x6000..=0x7FFF => MappedRead::PrgRam(usize::from(addr) & (Self::PRG_RAM_SIZE - 1)),
+            0x8000..=0xFFFF => MappedRead::PrgRom(self.prg_rom_banks.translate(addr)),
+            _ => MappedRead::Bus,
+        }
+    }
+
+    fn map_write(&mut self, addr: u16, val: u8) -> MappedWrite {
+        match addr {
+            0x0000..=0x1FFF => MappedWrite::Chr(self.chr_banks.translate(addr), val),
+            0x6000..=0x7FFF => {
+                MappedWrite::PrgRam(usize::from(addr) & (Self::PRG_RAM_SIZE - 1), val)
+            }
+            _ => MappedWrite::Bus,
+        }
+    }
+}
+
+impl Reset for VsUnisystem {
+    fn reset(&mut self, _kind: ResetKind) {
+        self.bank_select = false;
+        self.update_banks();
+    }
+}
+
+impl Clock for VsUnisystem {}
+impl Regional for VsUnisystem {}
+impl Sram for VsUnisystem {}
+
+impl MapperInfo for VsUnisystem {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let mut registers = vec![MapperRegister::new(
+            "Bank Select ($4016)",
+            u8::from(self.bank_select),
+        )];
+        registers.extend(MapperRegister::banks("PRG-ROM", &self.prg_rom_banks));
+        registers.extend(MapperRegister::banks("CHR", &self.chr_banks));
+        registers
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{
+        control_deck::ControlDeck,
+        mapper::DipSwitches,
+        mem::{Access, Mem},
+        ppu::model::PpuModel,
+    };
+
+    #[test]
+    fn vs_system() {
+        let mut deck = ControlDeck::new();
+        // NES 2.0 Vs. System with an RC2C05-01 PPU, 32KB of PRG-ROM and two 8KB CHR-ROM banks
+        // filled with their bank number
+        let mut rom = b"NES\x1A\x02\x02\x38\x69\x00\x00\x00\x00\x00\x08\x00\x00".to_vec();
+        // Loops at $8000, which every vector points to
+        let mut prg_rom = vec![0xEA; 0x8000];
+        prg_rom[..3].copy_from_slice(&[0x4C, 0x00, 0x80]);
+        prg_rom[0x7FFA..].copy_from_slice(&[0x00, 0x80, 0x00, 0x80, 0x00, 0x80]);
+        rom.extend_from_slice(&prg_rom);
+        for bank in 0..2 {
+            rom.resize(rom.len() + 0x2000, bank);
+        }
+        deck.load_rom("vs", &mut rom.as_slice())
+            .expect("loaded rom");
+        let peek = |deck: &ControlDeck, addr| deck.bus().peek(addr, Access::Dummy);
+        assert_eq!(deck.ppu().model, PpuModel::Rc2c05_01);
+        assert_eq!(peek(&deck, 0x2002) & 0x3F, 0x1B, "ppu id");
+        deck.bus_mut().write(0x2001, 0x80, Access::Dummy);
+        assert!(deck.ppu().ctrl.nmi_enabled, "swapped ppuctrl");
+
+        assert_eq!(deck.dip_switches(), Some(DipSwitches::new(8, 0x00)));
+        deck.set_rom_dip_switches("vs", Some(0xA5));
+        assert_eq!(peek(&deck, 0x4016) & 0x18, 0x08, "dip switches 1-2");
+        assert_eq!(peek(&deck, 0x4017) & 0xFC, 0xA4, "dip switches 3-8");
+
+        deck.insert_coin(2);
+        assert_eq!(peek(&deck, 0x4016) & 0x60, 0x40, "coin 2 inserted");
+        for _ in 0..5 {
+            deck.clock_frame().expect("valid frame");
+        }
+        assert_eq!(peek(&deck, 0x4016) & 0x60, 0x00, "coin switch released");
+
+        deck.bus_mut().write(0x4016, 0x04, Access::Dummy);
+        assert_eq!(
+            deck.bus().ppu.bus.peek(0x0000, Access::Dummy),
+            0x01,
+            "chr bank"
+        );
+        deck.reset(ResetKind::Soft);
+        assert_eq!(
+            peek(&deck, 0x4017) & 0xFC,
+            0xA4,
+            "dip switches kept on reset"
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! `GTROM`/`Cheapocabra` (Mapper 111)
+//!
+//! <https://www.nesdev.org/wiki/GTROM>
+
+use crate::{
+    cart::Cart,
+    common::{Clock, Regional, Reset, ResetKind, Sram},
+    mapper::{
+        flash::Flash, Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap,
+    },
+    mem::MemBanks,
+    ppu::Mirroring,
+};
+use alloc::{format, vec, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct Gtrom {
+    pub bank_select: u8,
+    pub flash: Flash,
+    pub chr_banks: MemBanks,
+    pub prg_rom_banks: MemBanks,
+}
+
+impl Gtrom {
+    const PRG_ROM_WINDOW: usize = 32 * 1024;
+    const CHR_WINDOW: usize = 8 * 1024;
+    const CHR_RAM_SIZE: usize = 16 * 1024;
+    const NAMETABLE_WINDOW: usize = 8 * 1024;
+    const NAMETABLE_RAM_SIZE: usize = 16 * 1024;
+
+    const PRG_BANK_MASK: u8 = 0x0F;
+    const CHR_BANK: u8 = 0x10;
+    const NAMETABLE_BANK: u8 = 0x20;
+    // LEDs are lit when their bit is clear
+    const RED_LED: u8 = 0x40;
+    const GREEN_LED: u8 = 0x80;
+
+    pub fn load(cart: &mut Cart) -> Mapper {
+        if cart.chr_ram.is_empty() {
+            cart.add_chr_ram(Self::CHR_RAM_SIZE);
+        }
+        cart.add_exram(Self::NAMETABLE_RAM_SIZE);
+        let gtrom = Self {
+            bank_select: 0x00,
+            flash: Flash::default(),
+            chr_banks: MemBanks::new(0x0000, 0x1FFF, cart.chr_ram.len(), Self::CHR_WINDOW),
+            prg_rom_banks: MemBanks::new(0x8000, 0xFFFF, cart.prg_rom.len(), Self::PRG_ROM_WINDOW),
+        };
+        gtrom.into()
+    }
+
+    pub fn update_banks(&mut self) {
+        // 7  bit  0
+        // ---- ----
+        // GRNC PPPP
+        // |||| ++++- Select 32K PRG-ROM bank at $8000
+        // |||+------ Select 8K CHR-RAM bank at PPU $0000
+        // ||+------- Select 8K nametable RAM bank at PPU $2000
+        // |+-------- Red LED (0: on)
+        // +--------- Green LED (0: on)
+        self.prg_rom_banks
+            .set(0, (self.bank_select & Self::PRG_BANK_MASK).into());
+        self.chr_banks.set(
+            0,
+            usize::from(self.bank_select & Self::CHR_BANK == Self::CHR_BANK),
+        );
+    }
+
+    const fn nametable_addr(&self, addr: u16) -> usize {
+        let bank = (self.bank_select & Self::NAMETABLE_BANK == Self::NAMETABLE_BANK) as usize;
+        bank * Self::NAMETABLE_WINDOW + (addr & 0x1FFF) as usize
+    }
+}
+
+impl Mapped for Gtrom {
+    fn mirroring(&self) -> Mirroring {
+        Mirroring::FourScreen
+    }
+}
+
+impl MemMap for Gtrom {
+    // PPU $0000..=$1FFF 8K CHR-RAM Bank Switchable
+    // PPU $2000..=$3EFF 8K Nametable RAM Bank Switchable
+    // CPU $5000..=$5FFF Bank Select
+    // CPU $7000..=$7FFF Bank Select
+    // CPU $8000..=$FFFF 32K PRG-ROM Bank Switchable
+
+    fn map_peek(&self, addr: u16) -> MappedRead {
+        match addr {
+            0x0000..=0x1FFF => MappedRead::Chr(self.chr_banks.translate(addr)),
+            0x2000..=0x3EFF => MappedRead::ExRam(self.nametable_addr(addr)),
+            0x8000..=0xFFFF => {
+                let addr = self.prg_rom_banks.translate(addr);
+                match self.flash.read_id(addr) {
+                    Some(id) => MappedRead::Data(id),
+                    None => MappedRead::PrgRom(addr),
+                }
+            }
+            _ => MappedRead::Bus,
+        }
+    }
+
+    fn map_write(&mut self, addr: u16, val: u8) -> MappedWrite {
+        match addr {
+            0x0000..=0x1FFF => MappedWrite::Chr(self.chr_banks.translate(addr), val),
+            0x2000..=0x3EFF => MappedWrite::ExRam(self.nametable_addr(addr), val),
+            0x5000..=0x5FFF | 0x7000..=0x7FFF => {
+                self.bank_select = val;
+                self.update_banks();
+                MappedWrite::Bus
+            }
+            0x8000..=0xFFFF => {
+                let addr = self.prg_rom_banks.translate(addr);
+                self.flash.write(addr, val)
+            }
+            _ => MappedWrite::Bus,
+        }
+    }
+}
+
+impl Reset for Gtrom {
+    fn reset(&mut self, _kind: ResetKind) {
+        self.bank_select = 0x00;
+        self.flash = Flash::default();
+        self.update_banks();
+    }
+}
+
+impl Sram for Gtrom {
+    fn flashable(&self) -> bool {
+        true
+    }
+}
+
+impl Clock for Gtrom {}
+impl Regional for Gtrom {}
+
+impl MapperInfo for Gtrom {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let led = |mask| {
+            if self.bank_select & mask == mask {
+                "Off"
+            } else {
+                "On"
+            }
+        };
+        let mut registers = vec![
+            MapperRegister::new("Bank Select ($5000)", format!("${:02X}", self.bank_select)),
+            MapperRegister::new(
+                "Nametable Bank",
+                u8::from(self.bank_select & Self::NAMETABLE_BANK == Self::NAMETABLE_BANK),
+            ),
+            MapperRegister::new("Red LED", led(Self::RED_LED)),
+            MapperRegister::new("Green LED", led(Self::GREEN_LED)),
+            MapperRegister::new("Flash Mode", format!("{:?}", self.flash.mode)),
+            MapperRegister::new("Flash Software ID", self.flash.software_id),
+        ];
+        registers.extend(MapperRegister::banks("PRG-ROM", &self.prg_rom_banks));
+        registers.extend(MapperRegister::banks("CHR", &self.chr_banks));
+        registers
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::{
+        common::Nvram,
+        control_deck::ControlDeck,
+        mem::{Access, Mem},
+    };
+
+    #[test]
+    fn banking() {
+        let mut deck = ControlDeck::new();
+        // Battery-backed GTROM with 64KB of PRG-ROM, each 32KB bank filled with $F0 + bank number
+        let mut rom = b"NES\x1A\x04\x00\xF2\x60\x00\x00\x00\x00\x00\x00\x00\x00".to_vec();
+        rom.resize(16 + 0x8000, 0xF0);
+        rom.resize(16 + 0x10000, 0xF1);
+        deck.load_rom("gtrom", &mut rom.as_slice())
+            .expect("loaded rom");
+        assert_eq!(deck.bus().peek(0x8000, Access::Dummy), 0xF0);
+        deck.bus_mut().write(0x7000, 0x01, Access::Dummy);
+        assert_eq!(deck.bus().peek(0x8000, Access::Dummy), 0xF1, "prg bank");
+
+        // Flash commands are written to $D555 and $AAAA in any bank
+        for (addr, cmd) in [
+            (0xD555, 0xAA),
+            (0xAAAA, 0x55),
+            (0xD555, 0xA0),
+            (0x8004, 0x31),
+        ] {
+            deck.bus_mut().write(addr, cmd, Access::Dummy);
+        }
+        assert_eq!(deck.bus().peek(0x8004, Access::Dummy), 0x31, "programmed");
+        assert_eq!(deck.nvram()[0].key, Nvram::PRG_ROM);
+
+        deck.bus_mut().write(0x5000, 0x20, Access::Dummy);
+        deck.bus_mut().ppu.bus.write(0x2400, 0xA5, Access::Dummy);
+        assert_eq!(
+            deck.bus().ppu.bus.exram[0x2400],
+            0xA5,
+            "four-screen nametable bank"
+        );
+    }
+}
@@ -0,0 +1,617 @@
+//! `Rainbow` (Mapper 682)
+//!
+//! <https://www.nesdev.org/wiki/NES_2.0_Mapper_682>
+//!
+//! Emulates PRG-ROM, PRG-RAM, CHR and nametable banking along with the FPGA-RAM message buffers
+//! used to talk to the on-board ESP Wi-Fi coprocessor. Messages are forwarded to a
+//! [`NetworkCoprocessor`] set on the control deck, which frontends can wire up to real sockets.
+//! Scanline/CPU cycle IRQs, expansion audio and FPGA-RAM CHR are not yet supported.
+
+use crate::{
+    cart::Cart,
+    common::{Clock, Regional, Reset, ResetKind, Sram},
+    mapper::{Mapped, MappedRead, MappedWrite, Mapper, MapperInfo, MapperRegister, MemMap},
+    mem::MemBanks,
+    ppu::Mirroring,
+};
+use alloc::{collections::VecDeque, format, sync::Arc, vec, vec::Vec};
+use serde::{Deserialize, Serialize};
+use spin::Mutex;
+use tracing::warn;
+
+/// The cartridge's ESP coprocessor, which exchanges messages with the running game.
+///
+/// Messages start with a command byte followed by its data, excluding the length byte the game
+/// stores in FPGA-RAM. Interpreting commands, such as opening a socket to a game server, is left
+/// to the implementation.
+pub trait NetworkCoprocessor: Send {
+    /// Handle a message sent by the game.
+    fn send(&mut self, message: &[u8]);
+
+    /// Returns the next message to deliver to the game, if any.
+    fn receive(&mut self) -> Option<Vec<u8>>;
+}
+
+/// Shared handle to a [`NetworkCoprocessor`] that can be set on the control deck.
+#[derive(Clone)]
+#[must_use]
+pub struct NetworkHook(Arc<Mutex<dyn NetworkCoprocessor>>);
+
+impl NetworkHook {
+    pub fn new(coprocessor: impl NetworkCoprocessor + 'static) -> Self {
+        Self(Arc::new(Mutex::new(coprocessor)))
+    }
+
+    /// Forward messages sent by `rainbow` to the coprocessor and deliver any replies.
+    pub fn exchange(&self, rainbow: &mut Rainbow) {
+        let mut coprocessor = self.0.lock();
+        while let Some(message) = rainbow.take_message() {
+            coprocessor.send(&message);
+        }
+        while let Some(message) = coprocessor.receive() {
+            rainbow.receive_message(message);
+        }
+    }
+}
+
+impl core::fmt::Debug for NetworkHook {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("NetworkHook").finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub struct Regs {
+    pub prg_mode: u8,
+    pub prg_rom: [u16; 8],
+    pub prg_ram: [u8; 2],
+    pub chr_mode: u8,
+    pub chr: [u16; 8],
+    pub nametable_bank: [u8; 4],
+    pub nametable_control: [u8; 4],
+    pub fpga_ram_bank: u8,
+    pub esp_config: u8,
+    pub rx_page: u8,
+    pub tx_page: u8,
+}
+
+impl Default for Regs {
+    fn default() -> Self {
+        Self {
+            prg_mode: 0x00,
+            prg_rom: [0x00; 8],
+            prg_ram: [0x00, 0x01],
+            chr_mode: 0x00,
+            chr: [0x00; 8],
+            // Vertical mirroring
+            nametable_bank: [0x00, 0x01, 0x00, 0x01],
+            nametable_control: [0x00; 4],
+            fpga_ram_bank: 0x00,
+            esp_config: 0x00,
+            rx_page: 0x00,
+            tx_page: 0x00,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct Rainbow {
+    pub regs: Regs,
+    /// Nametable mirroring from the ROM header, restored on reset.
+    pub header_mirroring: Mirroring,
+    pub fpga_ram: Vec<u8>,
+    /// Whether a received message is waiting in FPGA-RAM for the game to acknowledge.
+    pub data_ready: bool,
+    /// Whether a received message is requesting an IRQ.
+    #[serde(deserialize_with = "crate::fs::added_in::<9, _, _>")]
+    pub irq_pending: bool,
+    /// Messages sent by the game, waiting to be forwarded to the coprocessor.
+    pub tx_queue: VecDeque<Vec<u8>>,
+    /// Messages from the coprocessor, waiting to be delivered to the game.
+    pub rx_queue: VecDeque<Vec<u8>>,
+    pub prg_rom_banks: MemBanks,
+    pub prg_ram_banks: MemBanks,
+    pub chr_banks: MemBanks,
+}
+
+impl Rainbow {
+    const PRG_ROM_WINDOW: usize = 4 * 1024;
+    const PRG_RAM_WINDOW: usize = 4 * 1024;
+    const PRG_RAM_SIZE: usize = 32 * 1024;
+    const CHR_WINDOW: usize = 1024;
+    const CHR_RAM_SIZE: usize = 32 * 1024;
+    const FPGA_RAM_SIZE: usize = 8 * 1024;
+    const FPGA_RAM_WINDOW: usize = 2 * 1024;
+    const FPGA_RAM_PAGE: usize = 256;
+
+    const VERSION: u8 = 0x20;
+    const MAX_QUEUED: usize = 64;
+
+    const PRG_RAM_4K: u8 = 0x80;
+    const ESP_ENABLE: u8 = 0x01;
+    const ESP_IRQ_ENABLE: u8 = 0x02;
+    const DATA_READY: u8 = 0x80;
+    const NAMETABLE_FPGA_RAM: u8 = 0x80;
+
+    pub fn load(cart: &mut Cart) -> Mapper {
+        if cart.prg_ram.is_empty() {
+            cart.add_prg_ram(Self::PRG_RAM_SIZE);
+        }
+        if !cart.has_chr_rom() && cart.chr_ram.is_empty() {
+            cart.add_chr_ram(Self::CHR_RAM_SIZE);
+        }
+        let chr_len = if cart.has_chr_rom() {
+            cart.chr_rom.len()
+        } else {
+            cart.chr_ram.len()
+        };
+        let mut rainbow = Self {
+            regs: Regs::default(),
+            header_mirroring: cart.mirroring(),
+            fpga_ram: vec![0x00; Self::FPGA_RAM_SIZE],
+            data_ready: false,
+            irq_pending: false,
+            tx_queue: VecDeque::new(),
+            rx_queue: VecDeque::new(),
+            prg_rom_banks: MemBanks::new(0x8000, 0xFFFF, cart.prg_rom.len(), Self::PRG_ROM_WINDOW),
+            prg_ram_banks: MemBanks::new(0x6000, 0x7FFF, cart.prg_ram.len(), Self::PRG_RAM_WINDOW),
+            chr_banks: MemBanks::new(0x0000, 0x1FFF, chr_len, Self::CHR_WINDOW),
+        };
+        rainbow.set_mirroring(cart.mirroring());
+        rainbow.power_on();
+        rainbow.into()
+    }
+
+    fn power_on(&mut self) {
+        // Power on with the last 32K of PRG-ROM mapped so the reset vector is reachable
+        let last_32k = (self.prg_rom_banks.last() / 8) as u16;
+        self.regs.prg_rom[0] = last_32k;
+        self.update_prg_banks();
+        self.update_chr_banks();
+    }
+
+    /// Returns the next message sent by the game, if any.
+    pub fn take_message(&mut self) -> Option<Vec<u8>> {
+        self.tx_queue.pop_front()
+    }
+
+    /// Queue a message to be delivered to the game once it has acknowledged any prior message.
+    pub fn receive_message(&mut self, message: Vec<u8>) {
+        if self.rx_queue.len() >= Self::MAX_QUEUED {
+            warn!("rainbow: dropping message, receive queue is full");
+            return;
+        }
+        self.rx_queue.push_back(message);
+        self.deliver_message();
+    }
+
+    /// Copy the next queued message into the FPGA-RAM receive buffer as a length byte followed by
+    /// the message.
+    fn deliver_message(&mut self) {
+        if self.data_ready || self.regs.esp_config & Self::ESP_ENABLE == 0 {
+            return;
+        }
+        let Some(message) = self.rx_queue.pop_front() else {
+            return;
+        };
+        let len = message.len().min(0xFF);
+        let start = usize::from(self.regs.rx_page) * Self::FPGA_RAM_PAGE;
+        self.fpga_ram[start % Self::FPGA_RAM_SIZE] = len as u8;
+        for (i, &val) in message[..len].iter().enumerate() {
+            self.fpga_ram[(start + 1 + i) % Self::FPGA_RAM_SIZE] = val;
+        }
+        self.data_ready = true;
+        if self.regs.esp_config & Self::ESP_IRQ_ENABLE == Self::ESP_IRQ_ENABLE {
+            self.irq_pending = true;
+        }
+    }
+
+    /// Queue the message in the FPGA-RAM transmit buffer to be sent to the coprocessor.
+    fn send_message(&mut self) {
+        if self.regs.esp_config & Self::ESP_ENABLE == 0 {
+            return;
+        }
+        if self.tx_queue.len() >= Self::MAX_QUEUED {
+            warn!("rainbow: dropping message, send queue is full");
+            return;
+        }
+        let start = usize::from(self.regs.tx_page) * Self::FPGA_RAM_PAGE;
+        let len = usize::from(self.fpga_ram[start % Self::FPGA_RAM_SIZE]);
+        let message = (1..=len)
+            .map(|i| self.fpga_ram[(start + i) % Self::FPGA_RAM_SIZE])
+            .collect();
+        self.tx_queue.push_back(message);
+    }
+
+    fn acknowledge_message(&mut self) {
+        self.data_ready = false;
+        self.irq_pending = false;
+        self.deliver_message();
+    }
+
+    pub fn update_prg_banks(&mut self) {
+        // PRG-ROM banks are numbered in units of the selected window size, set as 4K pages
+        let regs = &self.regs;
+        let windows: &[(usize, usize)] = match regs.prg_mode & 0x07 {
+            // 32K
+            0 => &[(0, 8)],
+            // 2x16K
+            1 => &[(0, 4), (4, 4)],
+            // 16K + 2x8K
+            2 => &[(0, 4), (4, 2), (6, 2)],
+            // 4x8K
+            3 => &[(0, 2), (2, 2), (4, 2), (6, 2)],
+            // 8x4K
+            _ => &[
+                (0, 1),
+                (1, 1),
+                (2, 1),
+                (3, 1),
+                (4, 1),
+                (5, 1),
+                (6, 1),
+                (7, 1),
+            ],
+        };
+        for &(slot, pages) in windows {
+            let bank = usize::from(regs.prg_rom[slot]) * pages;
+            self.prg_rom_banks.set_range(slot, slot + pages - 1, bank);
+        }
+        if regs.prg_mode & Self::PRG_RAM_4K == Self::PRG_RAM_4K {
+            self.prg_ram_banks.set(0, regs.prg_ram[0].into());
+            self.prg_ram_banks.set(1, regs.prg_ram[1].into());
+        } else {
+            self.prg_ram_banks
+                .set_range(0, 1, usize::from(regs.prg_ram[0]) * 2);
+        }
+    }
+
+    pub fn update_chr_banks(&mut self) {
+        // CHR banks are numbered in units of the selected window size, set as 1K pages
+        let pages = 8 >> (self.regs.chr_mode & 0x03);
+        for slot in (0..8).step_by(pages) {
+            let bank = usize::from(self.regs.chr[slot]) * pages;
+            self.chr_banks.set_range(slot, slot + pages - 1, bank);
+        }
+    }
+
+    fn nametable_read(&self, addr: u16) -> MappedRead {
+        let slot = usize::from((addr >> 10) & 0x03);
+        let bank = usize::from(self.regs.nametable_bank[slot]);
+        let offset = usize::from(addr & 0x03FF);
+        if self.regs.nametable_control[slot] & Self::NAMETABLE_FPGA_RAM == Self::NAMETABLE_FPGA_RAM
+        {
+            MappedRead::Data(self.fpga_ram[((bank & 0x07) << 10) | offset])
+        } else {
+            MappedRead::CIRam(((bank & 0x01) << 10) | offset)
+        }
+    }
+
+    fn nametable_write(&mut self, addr: u16, val: u8) -> MappedWrite {
+        let slot = usize::from((addr >> 10) & 0x03);
+        let bank = usize::from(self.regs.nametable_bank[slot]);
+        let offset = usize::from(addr & 0x03FF);
+        if self.regs.nametable_control[slot] & Self::NAMETABLE_FPGA_RAM == Self::NAMETABLE_FPGA_RAM
+        {
+            self.fpga_ram[((bank & 0x07) << 10) | offset] = val;
+            MappedWrite::None
+        } else {
+            MappedWrite::CIRam(((bank & 0x01) << 10) | offset, val)
+        }
+    }
+
+    const fn fpga_ram_addr(&self, addr: u16) -> usize {
+        (self.regs.fpga_ram_bank as usize & 0x03) * Self::FPGA_RAM_WINDOW
+            + (addr as usize & (Self::FPGA_RAM_WINDOW - 1))
+    }
+
+    const fn read_register(&self, addr: u16) -> MappedRead {
+        let regs = &self.regs;
+        let val = match addr {
+            0x4100 => regs.prg_mode,
+            0x4115 => regs.fpga_ram_bank,
+            0x4120 => regs.chr_mode,
+            0x4160 => Self::VERSION,
+            0x4170 => regs.esp_config,
+            0x4171 if self.data_ready => Self::DATA_READY,
+            0x4171 => 0x00,
+            // Sent messages are queued, so the coprocessor is always ready for another
+            0x4172 => Self::DATA_READY,
+            0x4173 => regs.rx_page,
+            0x4174 => regs.tx_page,
+            _ => return MappedRead::Bus,
+        };
+        MappedRead::Data(val)
+    }
+
+    fn write_register(&mut self, addr: u16, val: u8) {
+        let regs = &mut self.regs;
+        match addr {
+            // 7  bit  0
+            // ---- ----
+            // A... .OOO
+            // |     +++- PRG-ROM mode: 32K, 2x16K, 16K + 2x8K, 4x8K or 8x4K
+            // +--------- PRG-RAM mode: 8K or 2x4K
+            0x4100 => regs.prg_mode = val,
+            0x4108..=0x410F => {
+                let slot = usize::from(addr & 0x07);
+                regs.prg_rom[slot] = (regs.prg_rom[slot] & 0x00FF) | (u16::from(val) << 8);
+            }
+            0x4115 => regs.fpga_ram_bank = val,
+            0x4116..=0x4117 => regs.prg_ram[usize::from(addr & 0x01)] = val,
+            0x4118..=0x411F => {
+                let slot = usize::from(addr & 0x07);
+                regs.prg_rom[slot] = (regs.prg_rom[slot] & 0xFF00) | u16::from(val);
+            }
+            // 7  bit  0
+            // ---- ----
+            // .... ..MM
+            //        ++- CHR mode: 8K, 2x4K, 4x2K or 8x1K
+            0x4120 => regs.chr_mode = val,
+            0x4126..=0x4129 => regs.nametable_bank[usize::from(addr - 0x4126)] = val,
+            // 7  bit  0
+            // ---- ----
+            // F... ....
+            // +--------- Nametable source: CIRAM (0) or FPGA-RAM (1)
+            0x412A..=0x412D => regs.nametable_control[usize::from(addr - 0x412A)] = val,
+            0x4130..=0x4137 => {
+                let slot = usize::from(addr & 0x07);
+                regs.chr[slot] = (regs.chr[slot] & 0x00FF) | (u16::from(val) << 8);
+            }
+            0x4140..=0x4147 => {
+                let slot = usize::from(addr & 0x07);
+                regs.chr[slot] = (regs.chr[slot] & 0xFF00) | u16::from(val);
+            }
+            // 7  bit  0
+            // ---- ----
+            // .... ..IE
+            //        |+- ESP enable
+            //        +-- ESP message IRQ enable
+            0x4170 => {
+                regs.esp_config = val;
+                if val & Self::ESP_IRQ_ENABLE == 0 {
+                    self.irq_pending = false;
+                }
+                self.deliver_message();
+            }
+            0x4171 => self.acknowledge_message(),
+            0x4172 => self.send_message(),
+            0x4173 => regs.rx_page = val & 0x1F,
+            0x4174 => regs.tx_page = val & 0x1F,
+            _ => (),
+        }
+        self.update_prg_banks();
+        self.update_chr_banks();
+    }
+}
+
+impl Mapped for Rainbow {
+    fn mirroring(&self) -> Mirroring {
+        let regs = &self.regs;
+        if regs
+            .nametable_control
+            .iter()
+            .any(|&control| control & Self::NAMETABLE_FPGA_RAM == Self::NAMETABLE_FPGA_RAM)
+        {
+            return Mirroring::FourScreen;
+        }
+        match regs.nametable_bank.map(|bank| bank & 0x01) {
+            [0, 0, 0, 0] => Mirroring::SingleScreenA,
+            [1, 1, 1, 1] => Mirroring::SingleScreenB,
+            [0, 0, 1, 1] => Mirroring::Horizontal,
+            [0, 1, 0, 1] => Mirroring::Vertical,
+            _ => Mirroring::FourScreen,
+        }
+    }
+
+    fn set_mirroring(&mut self, mirroring: Mirroring) {
+        let regs = &mut self.regs;
+        match mirroring {
+            Mirroring::Horizontal => regs.nametable_bank = [0, 0, 1, 1],
+            Mirroring::Vertical => regs.nametable_bank = [0, 1, 0, 1],
+            Mirroring::SingleScreenA => regs.nametable_bank = [0, 0, 0, 0],
+            Mirroring::SingleScreenB => regs.nametable_bank = [1, 1, 1, 1],
+            Mirroring::FourScreen => {
+                // CIRAM only holds two nametables, so the others come from FPGA-RAM
+                regs.nametable_bank = [0, 1, 2, 3];
+                regs.nametable_control = [0x00, 0x00, 0x80, 0x80];
+                return;
+            }
+        }
+        regs.nametable_control = [0x00; 4];
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.irq_pending
+    }
+}
+
+impl MemMap for Rainbow {
+    // PPU $0000..=$1FFF 8K, 2x4K, 4x2K or 8x1K CHR Bank Switchable
+    // PPU $2000..=$3EFF 4x1K Nametable Bank Switchable from CIRAM or FPGA-RAM
+    // CPU $4100..=$41FF Registers
+    // CPU $4800..=$4FFF 2K FPGA-RAM Bank Switchable
+    // CPU $6000..=$7FFF 8K or 2x4K PRG-RAM Bank Switchable
+    // CPU $8000..=$FFFF 32K, 16K, 8K or 4K PRG-ROM Bank Switchable
+
+    fn map_peek(&self, addr: u16) -> MappedRead {
+        match addr {
+            0x0000..=0x1FFF => MappedRead::Chr(self.chr_banks.translate(addr)),
+            0x2000..=0x3EFF => self.nametable_read(addr),
+            0x4100..=0x41FF => self.read_register(addr),
+            0x4800..=0x4FFF => MappedRead::Data(self.fpga_ram[self.fpga_ram_addr(addr)]),
+            0x6000..=0x7FFF => MappedRead::PrgRam(self.prg_ram_banks.translate(addr)),
+            0x8000..=0xFFFF => MappedRead::PrgRom(self.prg_rom_banks.translate(addr)),
+            _ => MappedRead::Bus,
+        }
+    }
+
+    fn map_write(&mut self, addr: u16, val: u8) -> MappedWrite {
+        match addr {
+            0x0000..=0x1FFF => MappedWrite::Chr(self.chr_banks.translate(addr), val),
+            0x2000..=0x3EFF => self.nametable_write(addr, val),
+            0x4100..=0x41FF => {
+                self.write_register(addr, val);
+                MappedWrite::Bus
+            }
+            0x4800..=0x4FFF => {
+                let addr = self.fpga_ram_addr(addr);
+                self.fpga_ram[addr] = val;
+                MappedWrite::None
+            }
+            0x6000..=0x7FFF => MappedWrite::PrgRam(self.prg_ram_banks.translate(addr), val),
+            _ => MappedWrite::Bus,
+        }
+    }
+}
+
+impl Reset for Rainbow {
+    fn reset(&mut self, _kind: ResetKind) {
+        self.regs = Regs::default();
+        self.set_mirroring(self.header_mirroring);
+        self.data_ready = false;
+        self.irq_pending = false;
+        self.tx_queue.clear();
+        self.rx_queue.clear();
+        self.power_on();
+    }
+}
+
+impl Clock for Rainbow {}
+impl Regional for Rainbow {}
+impl Sram for Rainbow {}
+
+impl MapperInfo for Rainbow {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let regs = &self.regs;
+        let prg_mode = match regs.prg_mode & 0x07 {
+            0 => "32K",
+            1 => "2x16K",
+            2 => "16K + 2x8K",
+            3 => "4x8K",
+            _ => "8x4K",
+        };
+        let mut registers = vec![
+            MapperRegister::new("PRG Mode ($4100)", format!("${:02X}", regs.prg_mode)),
+            MapperRegister::new("PRG-ROM Mode", prg_mode),
+            MapperRegister::new("CHR Mode ($4120)", format!("${:02X}", regs.chr_mode)),
+            MapperRegister::new("FPGA-RAM Bank ($4115)", regs.fpga_ram_bank & 0x03),
+            MapperRegister::new("ESP Config ($4170)", format!("${:02X}", regs.esp_config)),
+            MapperRegister::new("ESP RX Page ($4173)", regs.rx_page),
+            MapperRegister::new("ESP TX Page ($4174)", regs.tx_page),
+            MapperRegister::new("ESP Data Ready", self.data_ready),
+            MapperRegister::new(
+                "ESP Queued RX/TX",
+                format!("{}/{}", self.rx_queue.len(), self.tx_queue.len()),
+            ),
+        ];
+        for (slot, (bank, control)) in regs
+            .nametable_bank
+            .iter()
+            .zip(regs.nametable_control)
+            .enumerate()
+        {
+            let source = if control & Self::NAMETABLE_FPGA_RAM == Self::NAMETABLE_FPGA_RAM {
+                "FPGA-RAM"
+            } else {
+                "CIRAM"
+            };
+            registers.push(MapperRegister::new(
+                format!("Nametable {slot}"),
+                format!("{source} Bank {bank}"),
+            ));
+        }
+        registers.extend(MapperRegister::banks("PRG-ROM", &self.prg_rom_banks));
+        registers.extend(MapperRegister::banks("PRG-RAM", &self.prg_ram_banks));
+        registers.extend(MapperRegister::banks("CHR", &self.chr_banks));
+        registers
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{
+        control_deck::ControlDeck,
+        mem::{Access, Mem},
+    };
+
+    #[test]
+    fn banking() {
+        use crate::mapper::m682_rainbow::NetworkCoprocessor;
+        use alloc::{collections::VecDeque, sync::Arc};
+
+        /// Coprocessor that replies to each message with its bytes reversed.
+        struct Echo(Arc<spin::Mutex<Vec<Vec<u8>>>>, VecDeque<Vec<u8>>);
+
+        impl NetworkCoprocessor for Echo {
+            fn send(&mut self, message: &[u8]) {
+                self.0.lock().push(message.to_vec());
+                self.1.push_back(message.iter().rev().copied().collect());
+            }
+
+            fn receive(&mut self) -> Option<Vec<u8>> {
+                self.1.pop_front()
+            }
+        }
+
+        let mut deck = ControlDeck::new();
+        // NES 2.0 Rainbow with 64KB of PRG-ROM, each 4KB bank filled with its bank number
+        let mut rom = b"NES\x1A\x04\x00\xA0\xA8\x02\x00\x00\x00\x00\x00\x00\x00".to_vec();
+        for bank in 0..16 {
+            rom.resize(rom.len() + 0x1000, bank);
+        }
+        deck.load_rom("rainbow", &mut rom.as_slice())
+            .expect("loaded rom");
+        let peek = |deck: &ControlDeck, addr| deck.bus().peek(addr, Access::Dummy);
+        let write =
+            |deck: &mut ControlDeck, addr, val| deck.bus_mut().write(addr, val, Access::Dummy);
+        assert_eq!(
+            (peek(&deck, 0x8000), peek(&deck, 0xF000)),
+            (8, 15),
+            "last 32K on power on"
+        );
+
+        // 8x4K banks
+        write(&mut deck, 0x4100, 0x04);
+        write(&mut deck, 0x4118, 0x03);
+        write(&mut deck, 0x411F, 0x01);
+        assert_eq!((peek(&deck, 0x8000), peek(&deck, 0xF000)), (3, 1));
+        // 2x16K banks
+        write(&mut deck, 0x4100, 0x01);
+        write(&mut deck, 0x4118, 0x00);
+        write(&mut deck, 0x411C, 0x03);
+        assert_eq!((peek(&deck, 0x8000), peek(&deck, 0xC000)), (0, 12));
+
+        // Send a message from the FPGA-RAM transmit buffer at page 1
+        let sent = Arc::new(spin::Mutex::new(Vec::new()));
+        deck.set_network_hook(Some(NetworkHook::new(Echo(
+            Arc::clone(&sent),
+            VecDeque::new(),
+        ))));
+        write(&mut deck, 0x4170, 0x01);
+        write(&mut deck, 0x4174, 0x01);
+        for (addr, val) in [(0x4900, 0x02), (0x4901, 0xAA), (0x4902, 0xBB)] {
+            write(&mut deck, addr, val);
+        }
+        write(&mut deck, 0x4172, 0x00);
+        assert_eq!(peek(&deck, 0x4171), 0x00);
+        deck.exchange_network_messages();
+        assert_eq!(*sent.lock(), [vec![0xAA, 0xBB]]);
+
+        // Reply is received at page 0
+        assert_eq!(peek(&deck, 0x4171), 0x80, "data ready");
+        assert_eq!(
+            (
+                peek(&deck, 0x4800),
+                peek(&deck, 0x4801),
+                peek(&deck, 0x4802)
+            ),
+            (0x02, 0xBB, 0xAA)
+        );
+        write(&mut deck, 0x4171, 0x00);
+        assert_eq!(peek(&deck, 0x4171), 0x00, "acknowledged");
+    }
+}
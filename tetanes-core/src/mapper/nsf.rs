@@ -0,0 +1,635 @@
+//! `NSF` music player.
+//!
+//! Plays `NSF`, `NSF2` and `NSFe` files by mapping their program data into PRG-ROM and running a
+//! small built-in driver that calls the `INIT` routine for the selected track, then the `PLAY`
+//! routine at the rate requested by the file.
+//!
+//! <https://www.nesdev.org/wiki/NSF>
+//! <https://www.nesdev.org/wiki/NSFe>
+
+use crate::{
+    apu::{
+        dmc::Dmc,
+        driver::{self, ExpansionAudio, SoundInfo},
+        pulse::{OutputFreq, Pulse, PulseChannel},
+        PULSE_TABLE, TND_TABLE,
+    },
+    cart::{Cart, Error, Result},
+    common::{Clock, NesRegion, Regional, Reset, ResetKind, Sample, Sram},
+    cpu::Cpu,
+    mapper::{
+        m024_m026_vrc6::Vrc6Audio, Mapped, MappedRead, MappedWrite, Mapper, MapperInfo,
+        MapperRegister, MemMap,
+    },
+    mem::MemBanks,
+    ppu::Mirroring,
+};
+use alloc::{format, string::ToString, vec, vec::Vec};
+use core::time::Duration;
+use serde::{Deserialize, Serialize};
+
+/// Playback addresses, banks and timing from an `NSF`, `NSF2` or `NSFe` file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct NsfHeader {
+    pub load_addr: u16,
+    pub init_addr: u16,
+    pub play_addr: u16,
+    /// Initial 4K PRG-ROM banks at `$8000..=$FFFF`, if the program is bankswitched.
+    pub banks: Option<[u8; 8]>,
+    /// `PLAY` routine period in microseconds on NTSC.
+    pub ntsc_speed: u16,
+    /// `PLAY` routine period in microseconds on PAL.
+    pub pal_speed: u16,
+    /// Region the music was made for, or `None` if it plays on either.
+    pub region: Option<NesRegion>,
+    pub expansion_audio: ExpansionAudio,
+    pub track_count: u8,
+    /// First track to play, starting at `0`.
+    pub starting_track: u8,
+}
+
+impl Default for NsfHeader {
+    fn default() -> Self {
+        Self {
+            load_addr: 0x8000,
+            init_addr: 0x8000,
+            play_addr: 0x8000,
+            banks: None,
+            ntsc_speed: Self::NTSC_SPEED,
+            pal_speed: Self::PAL_SPEED,
+            region: Some(NesRegion::Ntsc),
+            expansion_audio: ExpansionAudio::empty(),
+            track_count: 1,
+            starting_track: 0,
+        }
+    }
+}
+
+impl NsfHeader {
+    const NTSC_SPEED: u16 = 16_639;
+    const PAL_SPEED: u16 = 19_997;
+    const BANK_SIZE: usize = 4 * 1024;
+
+    /// Whether `data` starts with an `NSF` or `NSFe` header.
+    #[must_use]
+    pub fn is_nsf(data: &[u8]) -> bool {
+        data.starts_with(SoundInfo::NSF_MAGIC) || data.starts_with(SoundInfo::NSFE_MAGIC)
+    }
+
+    /// Parse the header of an `NSF`, `NSF2` or `NSFe` file, returning it along with the program
+    /// data.
+    ///
+    /// # Errors
+    ///
+    /// If the header is truncated or missing required chunks, then an error is returned.
+    pub fn parse(data: &[u8]) -> Result<(Self, &[u8])> {
+        if let Some(chunks) = data.strip_prefix(SoundInfo::NSFE_MAGIC) {
+            Self::parse_nsfe(chunks)
+        } else if data.starts_with(SoundInfo::NSF_MAGIC) {
+            Self::parse_nsf(data)
+        } else {
+            Err(invalid("missing `NESM` or `NSFE` magic"))
+        }
+    }
+
+    /// See: <https://www.nesdev.org/wiki/NSF#Header_Overview>
+    fn parse_nsf(data: &[u8]) -> Result<(Self, &[u8])> {
+        let header = data
+            .get(..SoundInfo::NSF_HEADER_SIZE)
+            .ok_or_else(|| invalid("header is truncated"))?;
+        let word = |offset: usize| u16::from_le_bytes([header[offset], header[offset + 1]]);
+        let mut banks = [0x00; 8];
+        banks.copy_from_slice(&header[0x70..0x78]);
+        let mut program = &data[SoundInfo::NSF_HEADER_SIZE..];
+        if let Some(program_len) = driver::nsf2_program_len(header) {
+            program = program.get(..program_len).unwrap_or(program);
+        }
+        let nsf = Self {
+            load_addr: word(0x08),
+            init_addr: word(0x0A),
+            play_addr: word(0x0C),
+            banks: banks.iter().any(|&bank| bank != 0).then_some(banks),
+            ntsc_speed: word(0x6E),
+            pal_speed: word(0x78),
+            region: Self::region(header[0x7A]),
+            expansion_audio: ExpansionAudio::from_bits_truncate(header[0x7B]),
+            track_count: header[0x06],
+            starting_track: header[0x07].saturating_sub(1),
+        };
+        Ok((nsf, program))
+    }
+
+    /// See: <https://www.nesdev.org/wiki/NSFe>
+    fn parse_nsfe(chunks: &[u8]) -> Result<(Self, &[u8])> {
+        let mut nsf = Self::default();
+        let mut info = false;
+        let mut program = Option::None;
+        for (id, data) in driver::nsfe_chunks(chunks) {
+            let word = |offset: usize| {
+                data.get(offset..offset + 2)
+                    .map(|word| u16::from_le_bytes([word[0], word[1]]))
+            };
+            match id {
+                b"INFO" => {
+                    let (Some(load_addr), Some(init_addr)) = (word(0), word(2)) else {
+                        return Err(invalid("`INFO` chunk is truncated"));
+                    };
+                    nsf.load_addr = load_addr;
+                    nsf.init_addr = init_addr;
+                    nsf.play_addr = word(4).unwrap_or(init_addr);
+                    nsf.region = Self::region(data.get(6).copied().unwrap_or_default());
+                    nsf.expansion_audio = ExpansionAudio::from_bits_truncate(
+                        data.get(7).copied().unwrap_or_default(),
+                    );
+                    nsf.track_count = data.get(8).copied().unwrap_or(1);
+                    nsf.starting_track = data.get(9).copied().unwrap_or_default();
+                    info = true;
+                }
+                b"DATA" => program = Some(data),
+                b"BANK" => {
+                    let mut banks = [0x00; 8];
+                    let len = data.len().min(banks.len());
+                    banks[..len].copy_from_slice(&data[..len]);
+                    nsf.banks = Some(banks);
+                }
+                b"RATE" => {
+                    nsf.ntsc_speed = word(0).unwrap_or(Self::NTSC_SPEED);
+                    nsf.pal_speed = word(2).unwrap_or(Self::PAL_SPEED);
+                }
+                _ => (),
+            }
+        }
+        match (info, program) {
+            (true, Some(program)) => Ok((nsf, program)),
+            _ => Err(invalid("missing `INFO` or `DATA` chunk")),
+        }
+    }
+
+    const fn region(flags: u8) -> Option<NesRegion> {
+        match flags & 0x03 {
+            0x00 => Some(NesRegion::Ntsc),
+            0x01 => Some(NesRegion::Pal),
+            _ => None,
+        }
+    }
+
+    /// Lay out `program` in 4K PRG-ROM banks as it's loaded at `load_addr`.
+    ///
+    /// # Errors
+    ///
+    /// If the program isn't bankswitched and loads below `$8000`, then an error is returned.
+    pub fn prg_rom(&self, program: &[u8]) -> Result<Vec<u8>> {
+        let prg_rom = if self.banks.is_some() {
+            let padding = usize::from(self.load_addr) % Self::BANK_SIZE;
+            let mut prg_rom = vec![0x00; padding];
+            prg_rom.extend_from_slice(program);
+            prg_rom.resize(prg_rom.len().next_multiple_of(Self::BANK_SIZE), 0x00);
+            prg_rom
+        } else {
+            let offset = self
+                .load_addr
+                .checked_sub(0x8000)
+                .map(usize::from)
+                .ok_or_else(|| {
+                    invalid(format!("unsupported load address ${:04X}", self.load_addr))
+                })?;
+            let mut prg_rom = vec![0x00; 0x8000];
+            let len = program.len().min(prg_rom.len() - offset);
+            prg_rom[offset..offset + len].copy_from_slice(&program[..len]);
+            prg_rom
+        };
+        Ok(prg_rom)
+    }
+}
+
+fn invalid(message: impl ToString) -> Error {
+    Error::InvalidNsf {
+        message: message.to_string(),
+    }
+}
+
+/// `MMC5` pulse and PCM channels.
+///
+/// <https://www.nesdev.org/wiki/MMC5_audio>
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct Mmc5Audio {
+    pub pulse1: Pulse,
+    pub pulse2: Pulse,
+    pub pcm: Dmc,
+    pub pulse_timer: f32,
+}
+
+impl Mmc5Audio {
+    const fn new(region: NesRegion) -> Self {
+        Self {
+            pulse1: Pulse::new(PulseChannel::One, OutputFreq::Ultrasonic),
+            pulse2: Pulse::new(PulseChannel::Two, OutputFreq::Ultrasonic),
+            pcm: Dmc::new(region),
+            pulse_timer: 0.0,
+        }
+    }
+
+    fn write_register(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x5000 => self.pulse1.write_ctrl(val),
+            0x5002 => self.pulse1.write_timer_lo(val),
+            0x5003 => self.pulse1.write_timer_hi(val),
+            0x5004 => self.pulse2.write_ctrl(val),
+            0x5006 => self.pulse2.write_timer_lo(val),
+            0x5007 => self.pulse2.write_timer_hi(val),
+            // Writing $00 has no effect
+            0x5011 if val != 0x00 => self.pcm.write_output(val),
+            0x5015 => {
+                self.pulse1.set_enabled(val & 0x01 == 0x01);
+                self.pulse2.set_enabled(val & 0x02 == 0x02);
+            }
+            _ => (),
+        }
+    }
+
+    fn clock(&mut self, region: NesRegion) {
+        self.pulse1.clock();
+        self.pulse2.clock();
+        self.pulse_timer -= 1.0;
+        if self.pulse_timer <= 0.0 {
+            self.pulse1.clock_half_frame();
+            self.pulse2.clock_half_frame();
+            self.pulse_timer = Cpu::region_clock_rate(region) / 240.0;
+        }
+        self.pulse1.length.reload();
+        self.pulse2.length.reload();
+    }
+}
+
+impl Sample for Mmc5Audio {
+    fn output(&self) -> f32 {
+        let pulse = PULSE_TABLE[(self.pulse1.output() + self.pulse2.output()) as usize];
+        let pcm = TND_TABLE[self.pcm.output() as usize];
+        -(pulse + pcm)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct Nsf {
+    pub init_addr: u16,
+    pub play_addr: u16,
+    pub ntsc_speed: u16,
+    pub pal_speed: u16,
+    pub region: NesRegion,
+    /// Track being played, starting at `0`.
+    pub track: u8,
+    pub track_count: u8,
+    pub starting_track: u8,
+    pub initial_banks: [u8; 8],
+    pub banks: [u8; 8],
+    pub prg_rom_banks: MemBanks,
+    /// CPU cycles between calls to the `PLAY` routine.
+    pub play_period: u32,
+    pub play_timer: u32,
+    pub play_enabled: bool,
+    pub play_pending: bool,
+    /// CPU cycles since the track started.
+    pub elapsed_cycles: u64,
+    pub expansion_audio: ExpansionAudio,
+    pub vrc6: Vrc6Audio,
+    pub mmc5: Mmc5Audio,
+    pub mmc5_multiplier: [u8; 2],
+    pub mmc5_ex_ram: Vec<u8>,
+    /// Driver calling `INIT` and `PLAY`, mapped at [`Nsf::DRIVER_ADDR`].
+    pub driver: Vec<u8>,
+}
+
+impl Nsf {
+    const PRG_RAM_SIZE: usize = 8 * 1024;
+    const CHR_RAM_SIZE: usize = 8 * 1024;
+    const PRG_ROM_WINDOW: usize = 4 * 1024;
+    const MMC5_EX_RAM_SIZE: usize = 1024;
+
+    /// Address of the driver, in a range unused by the program or expansion audio registers.
+    const DRIVER_ADDR: u16 = 0x4100;
+    const TRACK_REG: u16 = 0x41F0;
+    const REGION_REG: u16 = 0x41F1;
+    /// Written to start the `PLAY` timer, and read to acknowledge its IRQ.
+    const PLAY_REG: u16 = 0x41F2;
+
+    const RESET_OFFSET: u16 = 0x00;
+    const IRQ_OFFSET: u16 = 0x59;
+    const NMI_OFFSET: u16 = 0x6B;
+
+    pub fn load(cart: &mut Cart, header: &NsfHeader) -> Mapper {
+        cart.add_prg_ram(Self::PRG_RAM_SIZE);
+        cart.add_chr_ram(Self::CHR_RAM_SIZE);
+        let initial_banks = header.banks.unwrap_or([0, 1, 2, 3, 4, 5, 6, 7]);
+        let region = cart.region();
+        let mut nsf = Self {
+            init_addr: header.init_addr,
+            play_addr: header.play_addr,
+            ntsc_speed: header.ntsc_speed,
+            pal_speed: header.pal_speed,
+            region,
+            track: header.starting_track,
+            track_count: header.track_count.max(1),
+            starting_track: header.starting_track,
+            initial_banks,
+            banks: initial_banks,
+            prg_rom_banks: MemBanks::new(0x8000, 0xFFFF, cart.prg_rom.len(), Self::PRG_ROM_WINDOW),
+            play_period: 0,
+            play_timer: 0,
+            play_enabled: false,
+            play_pending: false,
+            elapsed_cycles: 0,
+            expansion_audio: header.expansion_audio,
+            vrc6: Vrc6Audio::new(),
+            mmc5: Mmc5Audio::new(region),
+            mmc5_multiplier: [0xFF; 2],
+            mmc5_ex_ram: vec![0x00; Self::MMC5_EX_RAM_SIZE],
+            driver: Self::driver(header.init_addr, header.play_addr),
+        };
+        nsf.update_banks();
+        nsf.update_play_period();
+        nsf.into()
+    }
+
+    /// Assemble the driver, which clears RAM, silences the APU and calls `INIT` with the track in
+    /// `A` and region in `X`, then calls `PLAY` whenever the `PLAY` timer IRQ fires.
+    #[rustfmt::skip]
+    fn driver(init_addr: u16, play_addr: u16) -> Vec<u8> {
+        let [init_lo, init_hi] = init_addr.to_le_bytes();
+        let [play_lo, play_hi] = play_addr.to_le_bytes();
+        vec![
+            // Reset: $4100
+            0x78,             // SEI
+            0xD8,             // CLD
+            0xA2, 0xFF,       // LDX #$FF
+            0x9A,             // TXS
+            0xA9, 0x00,       // LDA #$00
+            0xAA,             // TAX
+            0x9D, 0x00, 0x00, // STA $0000,X
+            0x9D, 0x00, 0x01, // STA $0100,X
+            0x9D, 0x00, 0x02, // STA $0200,X
+            0x9D, 0x00, 0x03, // STA $0300,X
+            0x9D, 0x00, 0x04, // STA $0400,X
+            0x9D, 0x00, 0x05, // STA $0500,X
+            0x9D, 0x00, 0x06, // STA $0600,X
+            0x9D, 0x00, 0x07, // STA $0700,X
+            0xE8,             // INX
+            0xD0, 0xE5,       // BNE $4108
+            0xA0, 0x60,       // LDY #$60
+            0x84, 0x01,       // STY $01
+            0xA8,             // TAY
+            0x91, 0x00,       // STA ($00),Y
+            0xC8,             // INY
+            0xD0, 0xFB,       // BNE $4128
+            0xE6, 0x01,       // INC $01
+            0xA6, 0x01,       // LDX $01
+            0xE0, 0x80,       // CPX #$80
+            0xD0, 0xF3,       // BNE $4128
+            0x85, 0x01,       // STA $01
+            0xA2, 0x13,       // LDX #$13
+            0x9D, 0x00, 0x40, // STA $4000,X
+            0xCA,             // DEX
+            0x10, 0xFA,       // BPL $4139
+            0xA9, 0x0F,       // LDA #$0F
+            0x8D, 0x15, 0x40, // STA $4015
+            0xA9, 0x40,       // LDA #$40
+            0x8D, 0x17, 0x40, // STA $4017
+            0xAD, 0xF0, 0x41, // LDA TRACK_REG
+            0xAE, 0xF1, 0x41, // LDX REGION_REG
+            0x20, init_lo, init_hi, // JSR INIT
+            0x8D, 0xF2, 0x41, // STA PLAY_REG
+            0x58,             // CLI
+            0x4C, 0x56, 0x41, // JMP $4156
+            // IRQ: $4159
+            0x48,             // PHA
+            0x8A,             // TXA
+            0x48,             // PHA
+            0x98,             // TYA
+            0x48,             // PHA
+            0xAD, 0xF2, 0x41, // LDA PLAY_REG
+            0x10, 0x03,       // BPL $4166
+            0x20, play_lo, play_hi, // JSR PLAY
+            0x68,             // PLA
+            0xA8,             // TAY
+            0x68,             // PLA
+            0xAA,             // TAX
+            0x68,             // PLA
+            // NMI: $416B
+            0x40,             // RTI
+        ]
+    }
+
+    /// Select the track to play, starting at `0`, which takes effect on the next reset.
+    pub fn set_track(&mut self, track: u8) {
+        self.track = track % self.track_count;
+    }
+
+    /// Time since the track started.
+    #[must_use]
+    pub fn elapsed(&self) -> Duration {
+        let clock_rate = f64::from(Cpu::region_clock_rate(self.region));
+        Duration::from_secs_f64(self.elapsed_cycles as f64 / clock_rate)
+    }
+
+    pub fn update_banks(&mut self) {
+        for (slot, bank) in self.banks.into_iter().enumerate() {
+            self.prg_rom_banks.set(slot, bank.into());
+        }
+    }
+
+    fn update_play_period(&mut self) {
+        let speed = match self.region {
+            NesRegion::Pal | NesRegion::Dendy => self.pal_speed,
+            NesRegion::Auto | NesRegion::Ntsc => self.ntsc_speed,
+        };
+        let clock_rate = f64::from(Cpu::region_clock_rate(self.region));
+        self.play_period = (f64::from(speed) * clock_rate / 1_000_000.0) as u32;
+    }
+
+    const fn vector(offset: u16) -> u16 {
+        Self::DRIVER_ADDR + offset
+    }
+}
+
+impl Mapped for Nsf {
+    fn mirroring(&self) -> Mirroring {
+        Mirroring::Vertical
+    }
+
+    fn irq_pending(&self) -> bool {
+        self.play_pending
+    }
+}
+
+impl MemMap for Nsf {
+    // PPU $0000..=$1FFF 8K CHR-RAM, unused
+    // CPU $4100..=$41FF Driver and registers
+    // CPU $5000..=$5015 MMC5 audio
+    // CPU $5205..=$5206 MMC5 multiplier
+    // CPU $5C00..=$5FF5 MMC5 ExRAM
+    // CPU $5FF8..=$5FFF 4K PRG-ROM bank select
+    // CPU $6000..=$7FFF 8K PRG-RAM
+    // CPU $8000..=$FFFF 4K PRG-ROM Banks Switchable, with the driver's interrupt vectors
+    // CPU $9000..=$B002 VRC6 audio
+
+    fn map_read(&mut self, addr: u16) -> MappedRead {
+        if addr == Self::PLAY_REG {
+            let pending = self.play_pending;
+            self.play_pending = false;
+            return MappedRead::Data(u8::from(pending) << 7);
+        }
+        self.map_peek(addr)
+    }
+
+    fn map_peek(&self, addr: u16) -> MappedRead {
+        match addr {
+            0x0000..=0x1FFF => MappedRead::Chr(addr.into()),
+            Self::TRACK_REG => MappedRead::Data(self.track),
+            Self::REGION_REG => MappedRead::Data(u8::from(self.region.is_pal())),
+            Self::PLAY_REG => MappedRead::Data(u8::from(self.play_pending) << 7),
+            0x4100..=0x41FF => {
+                let offset = usize::from(addr - Self::DRIVER_ADDR);
+                MappedRead::Data(self.driver.get(offset).copied().unwrap_or_default())
+            }
+            0x5205 if self.expansion_audio.contains(ExpansionAudio::MMC5) => {
+                let [lo, _] = self.mmc5_product().to_le_bytes();
+                MappedRead::Data(lo)
+            }
+            0x5206 if self.expansion_audio.contains(ExpansionAudio::MMC5) => {
+                let [_, hi] = self.mmc5_product().to_le_bytes();
+                MappedRead::Data(hi)
+            }
+            0x5C00..=0x5FF5 if self.expansion_audio.contains(ExpansionAudio::MMC5) => {
+                MappedRead::Data(self.mmc5_ex_ram[usize::from(addr - 0x5C00)])
+            }
+            0x6000..=0x7FFF => MappedRead::PrgRam((addr & 0x1FFF).into()),
+            0xFFFA..=0xFFFF => {
+                let vector = match addr & !0x01 {
+                    0xFFFA => Self::vector(Self::NMI_OFFSET),
+                    0xFFFC => Self::vector(Self::RESET_OFFSET),
+                    _ => Self::vector(Self::IRQ_OFFSET),
+                };
+                let [lo, hi] = vector.to_le_bytes();
+                MappedRead::Data(if addr & 0x01 == 0x00 { lo } else { hi })
+            }
+            0x8000..=0xFFFF => MappedRead::PrgRom(self.prg_rom_banks.translate(addr)),
+            _ => MappedRead::Bus,
+        }
+    }
+
+    fn map_write(&mut self, addr: u16, val: u8) -> MappedWrite {
+        match addr {
+            0x0000..=0x1FFF => return MappedWrite::Chr(addr.into(), val),
+            Self::PLAY_REG => {
+                self.play_enabled = true;
+                self.play_timer = self.play_period;
+            }
+            0x5000..=0x5015 if self.expansion_audio.contains(ExpansionAudio::MMC5) => {
+                self.mmc5.write_register(addr, val);
+            }
+            0x5205 | 0x5206 if self.expansion_audio.contains(ExpansionAudio::MMC5) => {
+                self.mmc5_multiplier[usize::from(addr - 0x5205)] = val;
+            }
+            0x5C00..=0x5FF5 if self.expansion_audio.contains(ExpansionAudio::MMC5) => {
+                self.mmc5_ex_ram[usize::from(addr - 0x5C00)] = val;
+            }
+            0x5FF8..=0x5FFF => {
+                self.banks[usize::from(addr - 0x5FF8)] = val;
+                self.update_banks();
+            }
+            0x6000..=0x7FFF => return MappedWrite::PrgRam((addr & 0x1FFF).into(), val),
+            0x9000..=0x9003 | 0xA000..=0xA002 | 0xB000..=0xB002
+                if self.expansion_audio.contains(ExpansionAudio::VRC6) =>
+            {
+                self.vrc6.write_register(addr, val);
+            }
+            _ => (),
+        }
+        MappedWrite::Bus
+    }
+}
+
+impl Nsf {
+    const fn mmc5_product(&self) -> u16 {
+        self.mmc5_multiplier[0] as u16 * self.mmc5_multiplier[1] as u16
+    }
+}
+
+impl Sample for Nsf {
+    fn output(&self) -> f32 {
+        let mut output = 0.0;
+        if self.expansion_audio.contains(ExpansionAudio::VRC6) {
+            output += self.vrc6.output();
+        }
+        if self.expansion_audio.contains(ExpansionAudio::MMC5) {
+            output += self.mmc5.output();
+        }
+        output
+    }
+}
+
+impl Clock for Nsf {
+    fn clock(&mut self) -> usize {
+        self.elapsed_cycles += 1;
+        if self.play_enabled {
+            self.play_timer = self.play_timer.saturating_sub(1);
+            if self.play_timer == 0 {
+                self.play_timer = self.play_period;
+                self.play_pending = true;
+            }
+        }
+        if self.expansion_audio.contains(ExpansionAudio::VRC6) {
+            self.vrc6.clock();
+        }
+        if self.expansion_audio.contains(ExpansionAudio::MMC5) {
+            self.mmc5.clock(self.region);
+        }
+        1
+    }
+}
+
+impl Regional for Nsf {
+    fn region(&self) -> NesRegion {
+        self.region
+    }
+
+    fn set_region(&mut self, region: NesRegion) {
+        self.region = region;
+        self.update_play_period();
+    }
+}
+
+impl Reset for Nsf {
+    fn reset(&mut self, kind: ResetKind) {
+        if kind == ResetKind::Hard {
+            self.track = self.starting_track;
+        }
+        self.banks = self.initial_banks;
+        self.update_banks();
+        self.play_enabled = false;
+        self.play_pending = false;
+        self.elapsed_cycles = 0;
+        self.vrc6 = Vrc6Audio::new();
+        self.mmc5 = Mmc5Audio::new(self.region);
+        self.mmc5_multiplier = [0xFF; 2];
+        self.mmc5_ex_ram.fill(0x00);
+    }
+}
+
+impl Sram for Nsf {}
+
+impl MapperInfo for Nsf {
+    fn registers(&self) -> Vec<MapperRegister> {
+        let mut registers = vec![
+            MapperRegister::new("Track", format!("{}/{}", self.track + 1, self.track_count)),
+            MapperRegister::new("INIT", format!("${:04X}", self.init_addr)),
+            MapperRegister::new("PLAY", format!("${:04X}", self.play_addr)),
+            MapperRegister::new("PLAY Period", self.play_period),
+            MapperRegister::new("Expansion Audio", self.expansion_audio),
+        ];
+        registers.extend(MapperRegister::banks("PRG-ROM", &self.prg_rom_banks));
+        registers
+    }
+}
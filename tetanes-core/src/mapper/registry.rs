@@ -0,0 +1,174 @@
+//! Registry of supported boards keyed by mapper and submapper number.
+//!
+//! <https://wiki.nesdev.com/w/index.php/NES_2.0_submappers>
+
+use crate::{
+    cart::Cart,
+    mapper::{
+        m024_m026_vrc6::Revision as Vrc6Revision, Action53, Axrom, Bf909Revision, Bf909x, Bnrom,
+        Caltron6in1, Cnrom, ColorDreams, Exrom, Fxrom, Gk6in1, Gtrom, Gxrom, Mapper,
+        MapperRevision, Mmc1Revision, Mmc3Revision, Nina001, Nrom, Pxrom, Rainbow, Reset4in1,
+        SuperBig7in1, SuperHik4in1, Sxrom, Txrom, Unrom512, Uxrom, Vrc6, VsUnisystem,
+    },
+};
+
+/// Function that loads a board variant for a given [`Cart`].
+pub type LoadFn = fn(&mut Cart) -> Mapper;
+
+/// A supported board variant.
+#[derive(Debug, Copy, Clone)]
+#[must_use]
+pub struct Board {
+    /// Primary mapper number.
+    pub mapper_num: u16,
+    /// `NES 2.0` submapper number. `None` matches any submapper not otherwise registered.
+    pub submapper_num: Option<u8>,
+    /// Mapper revision implied by the submapper, which takes precedence over any configured
+    /// revision.
+    pub revision: Option<MapperRevision>,
+    /// Largest PRG-ROM size in bytes the board's bank registers can address, if limited.
+    pub max_prg_rom_size: Option<usize>,
+    /// Largest CHR-ROM size in bytes the board's bank registers can address, if limited.
+    pub max_chr_rom_size: Option<usize>,
+    /// Loads the board.
+    pub load: LoadFn,
+}
+
+impl Board {
+    const fn new(mapper_num: u16, load: LoadFn) -> Self {
+        Self {
+            mapper_num,
+            submapper_num: None,
+            revision: None,
+            max_prg_rom_size: None,
+            max_chr_rom_size: None,
+            load,
+        }
+    }
+
+    const fn with_submapper(mapper_num: u16, submapper_num: u8, load: LoadFn) -> Self {
+        Self {
+            mapper_num,
+            submapper_num: Some(submapper_num),
+            revision: None,
+            max_prg_rom_size: None,
+            max_chr_rom_size: None,
+            load,
+        }
+    }
+
+    const fn with_revision(mut self, revision: MapperRevision) -> Self {
+        self.revision = Some(revision);
+        self
+    }
+
+    /// Limit the PRG-ROM and CHR-ROM sizes in KB to what the board can address. Oversize
+    /// homebrew and hacks are supported up to these limits.
+    const fn with_limits(mut self, prg_rom_kb: usize, chr_rom_kb: usize) -> Self {
+        self.max_prg_rom_size = Some(prg_rom_kb * 1024);
+        self.max_chr_rom_size = Some(chr_rom_kb * 1024);
+        self
+    }
+
+    /// Returns whether the board can address a PRG-ROM of `size` bytes.
+    #[must_use]
+    pub fn addresses_prg_rom(&self, size: usize) -> bool {
+        self.max_prg_rom_size.map_or(true, |max| size <= max)
+    }
+
+    /// Returns whether the board can address a CHR-ROM of `size` bytes.
+    #[must_use]
+    pub fn addresses_chr_rom(&self, size: usize) -> bool {
+        self.max_chr_rom_size.map_or(true, |max| size <= max)
+    }
+}
+
+/// All supported boards. Boards with a specific submapper must be listed before the catch-all
+/// board for the same mapper.
+pub const BOARDS: &[Board] = &[
+    Board::new(0, Nrom::load).with_limits(32, 8),
+    Board::new(1, |cart| Sxrom::load(cart, Mmc1Revision::BC)).with_limits(512, 128),
+    Board::new(2, Uxrom::load).with_limits(4096, 8),
+    Board::new(3, Cnrom::load).with_limits(32, 2048),
+    Board::with_submapper(4, 3, Txrom::load)
+        .with_revision(MapperRevision::Mmc3(Mmc3Revision::Acc))
+        .with_limits(2048, 256),
+    Board::with_submapper(4, 4, Txrom::load)
+        .with_revision(MapperRevision::Mmc3(Mmc3Revision::A))
+        .with_limits(2048, 256),
+    Board::new(4, Txrom::load).with_limits(2048, 256),
+    Board::new(5, Exrom::load).with_limits(1024, 1024),
+    Board::new(7, Axrom::load).with_limits(512, 8),
+    Board::new(9, Pxrom::load).with_limits(128, 128),
+    Board::new(10, Fxrom::load).with_limits(256, 128),
+    Board::new(11, ColorDreams::load).with_limits(128, 128),
+    Board::new(24, |cart| Vrc6::load(cart, Vrc6Revision::A)).with_limits(256, 256),
+    Board::new(26, |cart| Vrc6::load(cart, Vrc6Revision::B)).with_limits(256, 256),
+    Board::new(28, Action53::load).with_limits(8192, 32),
+    Board::new(30, Unrom512::load).with_limits(512, 32),
+    Board::with_submapper(34, 1, Nina001::load).with_limits(64, 64),
+    Board::with_submapper(34, 2, Bnrom::load).with_limits(8192, 8),
+    Board::new(34, |cart| {
+        // ≥ 16K implies NINA-001; ≤ 8K implies BNROM
+        if cart.has_chr_rom() && cart.chr_rom.len() >= 0x4000 {
+            Nina001::load(cart)
+        } else {
+            Bnrom::load(cart)
+        }
+    }),
+    Board::new(41, Caltron6in1::load).with_limits(256, 128),
+    Board::new(44, SuperBig7in1::load).with_limits(1024, 1024),
+    Board::new(49, SuperHik4in1::load).with_limits(512, 512),
+    Board::new(57, Gk6in1::load).with_limits(128, 128),
+    Board::new(60, Reset4in1::load).with_limits(64, 32),
+    Board::new(66, Gxrom::load).with_limits(128, 128),
+    Board::with_submapper(71, 1, Bf909x::load)
+        .with_revision(MapperRevision::Bf909(Bf909Revision::Bf9097))
+        .with_limits(4096, 8),
+    Board::new(71, Bf909x::load).with_limits(4096, 8),
+    Board::new(99, VsUnisystem::load).with_limits(40, 16),
+    Board::new(111, Gtrom::load).with_limits(512, 16),
+    Board::new(155, |cart| Sxrom::load(cart, Mmc1Revision::A)).with_limits(512, 128),
+    Board::new(682, Rainbow::load).with_limits(8192, 8192),
+];
+
+/// Find the board matching the given mapper and submapper number, falling back to the
+/// catch-all board for the mapper.
+#[must_use]
+pub fn lookup(mapper_num: u16, submapper_num: u8) -> Option<&'static Board> {
+    BOARDS
+        .iter()
+        .filter(|board| board.mapper_num == mapper_num)
+        .find(|board| board.submapper_num.map_or(true, |num| num == submapper_num))
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_submapper() {
+        let board = lookup(4, 3).expect("valid board");
+        assert_eq!(board.submapper_num, Some(3));
+        assert_eq!(
+            board.revision,
+            Some(MapperRevision::Mmc3(Mmc3Revision::Acc))
+        );
+
+        let board = lookup(4, 1).expect("valid board");
+        assert_eq!(board.submapper_num, None);
+        assert_eq!(board.revision, None);
+
+        assert!(lookup(6, 0).is_none());
+    }
+
+    #[test]
+    fn board_limits() {
+        let board = lookup(2, 0).expect("valid board");
+        assert!(board.addresses_prg_rom(2 * 1024 * 1024));
+        assert!(!board.addresses_chr_rom(16 * 1024));
+
+        let board = lookup(34, 0).expect("valid board");
+        assert!(board.addresses_prg_rom(usize::MAX));
+    }
+}
@@ -2,10 +2,7 @@
 //!
 //! <https://www.nesdev.org/wiki/VRC_IRQ>
 
-use crate::{
-    common::{Clock, Reset, ResetKind},
-    cpu::{Cpu, Irq},
-};
+use crate::common::{Clock, Reset, ResetKind};
 use serde::{Deserialize, Serialize};
 
 #[derive(Default, Debug, Copy, Clone, Serialize, Deserialize)]
@@ -17,6 +14,8 @@ pub struct VrcIrq {
     pub enabled: bool,
     pub enabled_after_ack: bool,
     pub cycle_mode: bool,
+    #[serde(deserialize_with = "crate::fs::added_in::<9, _, _>")]
+    pub pending: bool,
 }
 
 impl VrcIrq {
@@ -34,12 +33,12 @@ impl VrcIrq {
             self.prescalar_counter = 341;
         }
 
-        Cpu::clear_irq(Irq::MAPPER);
+        self.pending = false;
     }
 
     pub fn acknowledge(&mut self) {
         self.enabled = self.enabled_after_ack;
-        Cpu::clear_irq(Irq::MAPPER);
+        self.pending = false;
     }
 }
 
@@ -50,7 +49,7 @@ impl Clock for VrcIrq {
             if self.cycle_mode || self.prescalar_counter <= 0 {
                 if self.counter == 0xFF {
                     self.counter = self.reload;
-                    Cpu::set_irq(Irq::MAPPER);
+                    self.pending = true;
                 } else {
                     self.counter += 1;
                 }
@@ -71,5 +70,6 @@ impl Reset for VrcIrq {
         self.enabled = false;
         self.enabled_after_ack = false;
         self.cycle_mode = false;
+        self.pending = false;
     }
 }
@@ -42,6 +42,7 @@ pub trait Mem {
     }
 }
 
+/// Power-up RAM pattern.
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[must_use]
 pub enum RamState {
@@ -49,9 +50,15 @@ pub enum RamState {
     AllZeros,
     AllOnes,
     Random,
+    /// Alternating pages of `$00` and `$FF`, similar to patterns seen on real hardware.
+    Alternating,
+    /// Pseudo-random values generated from a seed, reproducible across runs and platforms.
+    Seeded(u64),
 }
 
 impl RamState {
+    const PAGE_SIZE: usize = 0x100;
+
     #[must_use]
     pub fn filled(capacity: usize, state: Self) -> Vec<u8> {
         let mut ram = vec![0x00; capacity];
@@ -60,7 +67,13 @@ impl RamState {
     }
 
     pub const fn as_slice() -> &'static [Self] {
-        &[Self::AllZeros, Self::AllOnes, Self::Random]
+        &[
+            Self::AllZeros,
+            Self::AllOnes,
+            Self::Random,
+            Self::Alternating,
+            Self::Seeded(0),
+        ]
     }
 
     pub const fn as_str(&self) -> &'static str {
@@ -68,6 +81,8 @@ impl RamState {
             Self::AllZeros => "all-zeros",
             Self::AllOnes => "all-ones",
             Self::Random => "random",
+            Self::Alternating => "alternating",
+            Self::Seeded(_) => "seeded",
         }
     }
 
@@ -75,12 +90,29 @@ impl RamState {
         match state {
             RamState::AllZeros => ram.fill(0x00),
             RamState::AllOnes => ram.fill(0xFF),
+            RamState::Alternating => {
+                for (page, chunk) in ram.chunks_mut(Self::PAGE_SIZE).enumerate() {
+                    chunk.fill(if page & 1 == 0 { 0x00 } else { 0xFF });
+                }
+            }
             RamState::Random => {
                 let mut rng = crate::sys::rand::rng();
                 for val in ram {
                     *val = rng.gen_range(0x00..=0xFF);
                 }
             }
+            RamState::Seeded(seed) => {
+                // SplitMix64, so patterns don't change with `rand` versions or platforms
+                let mut state = seed;
+                for chunk in ram.chunks_mut(8) {
+                    state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+                    let mut z = state;
+                    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+                    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+                    z ^= z >> 31;
+                    chunk.copy_from_slice(&z.to_le_bytes()[..chunk.len()]);
+                }
+            }
         }
     }
 }
@@ -90,6 +122,7 @@ impl From<usize> for RamState {
         match value {
             0 => Self::AllZeros,
             1 => Self::AllOnes,
+            3 => Self::Alternating,
             _ => Self::Random,
         }
     }
@@ -103,12 +136,13 @@ impl AsRef<str> for RamState {
 
 impl core::fmt::Display for RamState {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        let s = match self {
-            Self::AllZeros => "All $00",
-            Self::AllOnes => "All $FF",
-            Self::Random => "Random",
-        };
-        write!(f, "{s}")
+        match self {
+            Self::AllZeros => write!(f, "All $00"),
+            Self::AllOnes => write!(f, "All $FF"),
+            Self::Alternating => write!(f, "Alternating $00/$FF"),
+            Self::Random => write!(f, "Random"),
+            Self::Seeded(seed) => write!(f, "Seeded ({seed})"),
+        }
     }
 }
 
@@ -118,12 +152,80 @@ impl FromStr for RamState {
         match s {
             "all-zeros" => Ok(Self::AllZeros),
             "all-ones" => Ok(Self::AllOnes),
+            "alternating" => Ok(Self::Alternating),
             "random" => Ok(Self::Random),
-            _ => Err("invalid RamState value. valid options: `all-zeros`, `all-ones`, or `random`"),
+            _ => match s.strip_prefix("seeded:").map(str::parse) {
+                Some(Ok(seed)) => Ok(Self::Seeded(seed)),
+                _ => Err("invalid RamState value. valid options: `all-zeros`, `all-ones`, `alternating`, `random`, or `seeded:<seed>`"),
+            },
         }
     }
 }
 
+/// A region of memory that can be inspected and edited by debugging tools, e.g. with
+/// `ControlDeck::peek_memory` and `ControlDeck::poke_memory`.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub enum MemorySpace {
+    /// CPU address space, `$0000-$FFFF`.
+    #[default]
+    Cpu,
+    /// PPU address space, `$0000-$3FFF`, including pattern tables, nametables and palette.
+    Ppu,
+    /// PPU Object Attribute Memory.
+    Oam,
+    /// Cartridge PRG-RAM.
+    PrgRam,
+    /// Raw cartridge PRG-ROM.
+    PrgRom,
+    /// Raw cartridge CHR-ROM or CHR-RAM.
+    Chr,
+}
+
+impl MemorySpace {
+    pub const fn as_slice() -> &'static [Self] {
+        &[
+            Self::Cpu,
+            Self::Ppu,
+            Self::Oam,
+            Self::PrgRam,
+            Self::PrgRom,
+            Self::Chr,
+        ]
+    }
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cpu => "cpu",
+            Self::Ppu => "ppu",
+            Self::Oam => "oam",
+            Self::PrgRam => "prg-ram",
+            Self::PrgRom => "prg-rom",
+            Self::Chr => "chr",
+        }
+    }
+}
+
+impl AsRef<str> for MemorySpace {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl core::fmt::Display for MemorySpace {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let s = match self {
+            Self::Cpu => "CPU",
+            Self::Ppu => "PPU",
+            Self::Oam => "OAM",
+            Self::PrgRam => "PRG-RAM",
+            Self::PrgRom => "PRG-ROM",
+            Self::Chr => "CHR",
+        };
+        write!(f, "{s}")
+    }
+}
+
 #[derive(Default, Clone, Serialize, Deserialize)]
 #[must_use]
 pub struct MemBanks {
@@ -151,7 +253,7 @@ impl MemBanks {
             size,
             window,
             shift: window.trailing_zeros() as usize,
-            mask: page_count - 1,
+            mask: page_count.next_power_of_two() - 1,
             banks,
             page_count,
         }
@@ -159,17 +261,26 @@ impl MemBanks {
 
     pub fn set(&mut self, slot: usize, bank: usize) {
         assert!(slot < self.banks.len());
-        self.banks[slot] = (bank & self.mask) << self.shift;
+        self.banks[slot] = self.page(bank) << self.shift;
         debug_assert!(self.banks[slot] < self.page_count * self.window);
     }
 
     pub fn set_range(&mut self, start: usize, end: usize, bank: usize) {
-        let mut new_addr = (bank & self.mask) << self.shift;
-        for slot in start..=end {
+        for (i, slot) in (start..=end).enumerate() {
             assert!(slot < self.banks.len());
-            self.banks[slot] = new_addr;
+            self.banks[slot] = self.page(bank + i) << self.shift;
             debug_assert!(self.banks[slot] < self.page_count * self.window);
-            new_addr += self.window;
+        }
+    }
+
+    /// Maps a bank number to an available page. Oversize or non-power-of-two ROMs mirror any
+    /// pages past the end of the data back to the start, like incomplete address decoding would.
+    const fn page(&self, bank: usize) -> usize {
+        let page = bank & self.mask;
+        if page < self.page_count {
+            page
+        } else {
+            page % self.page_count
         }
     }
 
@@ -222,6 +333,15 @@ impl MemBanks {
     pub const fn page_count(&self) -> usize {
         self.page_count
     }
+
+    /// Returns the `(start, end, bank)` of each window, where `bank` is the bank currently mapped
+    /// into it.
+    pub fn windows(&self) -> impl Iterator<Item = (usize, usize, usize)> + '_ {
+        self.banks.iter().enumerate().map(|(slot, page)| {
+            let start = self.start + slot * self.window;
+            (start, start + self.window - 1, page >> self.shift)
+        })
+    }
 }
 
 impl core::fmt::Debug for MemBanks {
@@ -232,7 +352,7 @@ impl core::fmt::Debug for MemBanks {
             .field("size", &format_args!("${:04X}", self.size))
             .field("window", &format_args!("${:04X}", self.window))
             .field("shift", &self.shift)
-            .field("mask", &self.shift)
+            .field("mask", &self.mask)
             .field("banks", &self.banks)
             .field("page_count", &self.page_count)
             .finish()
@@ -275,4 +395,36 @@ mod tests {
         banks.set(0, banks.last());
         assert_eq!(banks.translate(0x8000), 0x1E000);
     }
+
+    #[test]
+    fn bank_translate_non_power_of_two() {
+        let size = 48 * 1024;
+        let mut banks = MemBanks::new(0x8000, 0xFFFF, size, 0x4000);
+        assert_eq!(banks.last(), 2, "bank count");
+
+        banks.set(0, 2);
+        assert_eq!(banks.translate(0x8000), 0x8000);
+        banks.set(0, 3);
+        assert_eq!(banks.translate(0x8000), 0x0000);
+        banks.set(0, 5);
+        assert_eq!(banks.translate(0x8000), 0x4000);
+        banks.set_range(0, 1, 2);
+        assert_eq!(banks.translate(0x8000), 0x8000);
+        assert_eq!(banks.translate(0xC000), 0x0000);
+    }
+
+    #[test]
+    fn ram_state_patterns() {
+        let ram = RamState::filled(0x800, RamState::Alternating);
+        assert_eq!(ram[0x00FF], 0x00);
+        assert_eq!(ram[0x0100], 0xFF);
+        assert_eq!(ram[0x0200], 0x00);
+
+        let seeded = RamState::filled(0x801, RamState::Seeded(42));
+        assert_eq!(seeded, RamState::filled(0x801, RamState::Seeded(42)));
+        assert_ne!(seeded, RamState::filled(0x801, RamState::Seeded(43)));
+
+        assert_eq!("seeded:42".parse(), Ok(RamState::Seeded(42)));
+        assert!("seeded:".parse::<RamState>().is_err());
+    }
 }
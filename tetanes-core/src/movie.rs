@@ -0,0 +1,410 @@
+//! TAS movie playback from FCEUX `.fm2` input logs, e.g. to verify a movie still syncs after
+//! emulator updates.
+//!
+//! See: <https://fceux.com/web/help/fm2.html>
+
+use crate::{
+    common::{NesRegion, Reset, ResetKind},
+    compat,
+    control_deck::{self, Config, ControlDeck},
+    input::{FourPlayer, JoypadBtnState, Player},
+    io::Read,
+    mem::RamState,
+    time::{Duration, Instant},
+};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use bitflags::bitflags;
+use snafu::Snafu;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Snafu, Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub enum Error {
+    #[snafu(display("invalid movie header on line {line}: {message}"))]
+    InvalidHeader { line: usize, message: String },
+    #[snafu(display("invalid movie input on line {line}: {message}"))]
+    InvalidInput { line: usize, message: String },
+    #[snafu(display("unsupported movie: {message}"))]
+    Unsupported { message: String },
+}
+
+bitflags! {
+    /// Commands issued at the start of a movie frame.
+    #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+    #[must_use]
+    pub struct Commands: u8 {
+        const SOFT_RESET = 0x01;
+        const HARD_RESET = 0x02;
+        const FDS_INSERT = 0x04;
+        const FDS_SELECT = 0x08;
+        const INSERT_COIN = 0x10;
+    }
+}
+
+/// Input for a single movie frame.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct MovieFrame {
+    pub commands: Commands,
+    pub joypads: [JoypadBtnState; 4],
+}
+
+/// A parsed FCEUX `.fm2` movie.
+///
+/// Only text movies that start from power-on with standard controllers, optionally through a
+/// Four Score, are supported.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct Movie {
+    /// Name of the ROM the movie was recorded with.
+    pub rom_filename: Option<String>,
+    /// Base64 MD5 checksum of the ROM the movie was recorded with.
+    pub rom_checksum: Option<String>,
+    pub rerecord_count: u32,
+    pub pal: bool,
+    pub four_score: bool,
+    pub frames: Vec<MovieFrame>,
+}
+
+impl Movie {
+    /// Button order of each controller field, from left to right.
+    const BUTTONS: [JoypadBtnState; 8] = [
+        JoypadBtnState::RIGHT,
+        JoypadBtnState::LEFT,
+        JoypadBtnState::DOWN,
+        JoypadBtnState::UP,
+        JoypadBtnState::START,
+        JoypadBtnState::SELECT,
+        JoypadBtnState::B,
+        JoypadBtnState::A,
+    ];
+
+    /// Parse an `.fm2` movie.
+    ///
+    /// # Errors
+    ///
+    /// If the movie is malformed or uses features that aren't supported, such as binary input
+    /// logs, starting from a save state or input devices other than standard controllers, then an
+    /// error is returned.
+    pub fn parse(movie: &str) -> Result<Self> {
+        let mut parsed = Self::default();
+        let mut has_version = false;
+        for (index, line) in movie.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('|') {
+                let frame = parsed
+                    .parse_frame(line)
+                    .map_err(|message| Error::InvalidInput {
+                        line: line_number,
+                        message,
+                    })?;
+                parsed.frames.push(frame);
+                continue;
+            }
+
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let invalid = |message: String| Error::InvalidHeader {
+                line: line_number,
+                message,
+            };
+            let flag = |value: &str| match value {
+                "0" => Ok(false),
+                "1" => Ok(true),
+                _ => Err(invalid(format!(
+                    "expected `0` or `1` for `{key}`, found `{value}`"
+                ))),
+            };
+            match key {
+                "version" => {
+                    if value != "3" {
+                        return Err(invalid(format!("unsupported version `{value}`")));
+                    }
+                    has_version = true;
+                }
+                "romFilename" => parsed.rom_filename = Some(value.to_string()),
+                "romChecksum" => parsed.rom_checksum = Some(value.to_string()),
+                "rerecordCount" => {
+                    parsed.rerecord_count = value
+                        .parse()
+                        .map_err(|_| invalid(format!("invalid rerecord count `{value}`")))?;
+                }
+                "palFlag" => parsed.pal = flag(value)?,
+                "fourscore" => parsed.four_score = flag(value)?,
+                "binary" if flag(value)? => {
+                    return Err(Error::Unsupported {
+                        message: "binary input logs".to_string(),
+                    });
+                }
+                "savestate" => {
+                    return Err(Error::Unsupported {
+                        message: "movies starting from a save state".to_string(),
+                    });
+                }
+                "FDS" if flag(value)? => {
+                    return Err(Error::Unsupported {
+                        message: "Famicom Disk System movies".to_string(),
+                    });
+                }
+                // 0 is no device and 1 is a standard controller
+                "port0" | "port1" if !matches!(value, "0" | "1") => {
+                    return Err(Error::Unsupported {
+                        message: format!("input device `{value}` on `{key}`"),
+                    });
+                }
+                "port2" if value != "0" => {
+                    return Err(Error::Unsupported {
+                        message: format!("expansion port device `{value}`"),
+                    });
+                }
+                _ => (),
+            }
+        }
+        if !has_version {
+            return Err(Error::InvalidHeader {
+                line: 1,
+                message: "missing `version`".to_string(),
+            });
+        }
+        Ok(parsed)
+    }
+
+    /// Parse an input log line, e.g. `|0|R......A|........||`.
+    fn parse_frame(&self, line: &str) -> core::result::Result<MovieFrame, String> {
+        let mut fields = line.split('|').skip(1);
+        let commands = fields.next().unwrap_or_default();
+        let mut frame = MovieFrame {
+            commands: Commands::from_bits_truncate(
+                commands
+                    .parse()
+                    .map_err(|_| format!("invalid commands `{commands}`"))?,
+            ),
+            ..Default::default()
+        };
+        let pad_count = if self.four_score { 4 } else { 2 };
+        for (pad, buttons) in frame.joypads.iter_mut().zip(fields.take(pad_count)) {
+            if buttons.is_empty() {
+                continue;
+            }
+            if buttons.len() != Self::BUTTONS.len() {
+                return Err(format!("invalid controller input `{buttons}`"));
+            }
+            for (button, state) in buttons.chars().zip(Self::BUTTONS) {
+                pad.set(state, !matches!(button, '.' | ' '));
+            }
+        }
+        Ok(frame)
+    }
+
+    /// Load `rom` and play back every frame of the movie as fast as possible, hashing each frame
+    /// rendered.
+    ///
+    /// Battery-backed RAM is never loaded from or saved to disk and random power-up RAM is seeded
+    /// so that results are reproducible.
+    ///
+    /// # Errors
+    ///
+    /// If the ROM fails to load or the CPU crashes during playback, then an error is returned.
+    pub fn play(
+        &self,
+        mut cfg: Config,
+        name: &str,
+        rom: &mut impl Read,
+    ) -> control_deck::Result<MovieReport> {
+        cfg.data_dir = None;
+        if cfg.ram_state == RamState::Random {
+            cfg.ram_state = RamState::Seeded(0);
+        }
+        cfg.region = if self.pal {
+            NesRegion::Pal
+        } else {
+            NesRegion::Ntsc
+        };
+        cfg.rom_regions.clear();
+        cfg.four_player = if self.four_score {
+            FourPlayer::FourScore
+        } else {
+            FourPlayer::Disabled
+        };
+        cfg.zapper = false;
+        cfg.headless_mode = control_deck::HeadlessMode::NO_AUDIO;
+
+        let mut deck = ControlDeck::with_config(cfg);
+        deck.load_rom(name, rom)?;
+
+        let mut frame_hashes = Vec::with_capacity(self.frames.len());
+        let start = Instant::now();
+        for frame in &self.frames {
+            if frame.commands.contains(Commands::HARD_RESET) {
+                deck.reset(ResetKind::Hard);
+            } else if frame.commands.contains(Commands::SOFT_RESET) {
+                deck.reset(ResetKind::Soft);
+            }
+            if frame.commands.contains(Commands::INSERT_COIN) {
+                deck.insert_coin(1);
+            }
+            let players = [Player::One, Player::Two, Player::Three, Player::Four];
+            for (player, buttons) in players.into_iter().zip(frame.joypads) {
+                // Set buttons directly so recorded input isn't altered, e.g. by blocking opposing
+                // directions
+                deck.joypad_mut(player).buttons = buttons;
+            }
+            deck.clock_frame()?;
+            frame_hashes.push(compat::frame_hash(deck.frame_buffer_raw()));
+        }
+        let elapsed = start.elapsed();
+
+        Ok(MovieReport {
+            frames: frame_hashes.len() as u32,
+            end_hash: end_state_hash(&mut deck),
+            frame_hashes,
+            elapsed,
+        })
+    }
+}
+
+/// Hash the last frame rendered along with Work RAM with FNV-1a.
+fn end_state_hash(deck: &mut ControlDeck) -> u64 {
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let hash = compat::frame_hash(deck.frame_buffer_raw());
+    deck.wram().iter().fold(hash, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// Result of playing back a movie.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct MovieReport {
+    /// Frames played back.
+    pub frames: u32,
+    /// Hash of the last frame rendered and Work RAM after the final frame.
+    pub end_hash: u64,
+    /// Hash of each frame rendered, in order.
+    pub frame_hashes: Vec<u64>,
+    /// Wall-clock time spent emulating.
+    pub elapsed: Duration,
+}
+
+impl MovieReport {
+    /// Returns the first frame that differs from the frame hashes of a previous playback, if any.
+    ///
+    /// A movie that ends early compared to the baseline is considered desynced on the first
+    /// missing frame.
+    #[must_use]
+    pub fn desync_frame(&self, baseline: &[u64]) -> Option<u32> {
+        self.frame_hashes
+            .iter()
+            .zip(baseline)
+            .position(|(hash, expected)| hash != expected)
+            .or_else(|| {
+                (self.frame_hashes.len() < baseline.len()).then_some(self.frame_hashes.len())
+            })
+            .map(|frame| frame as u32)
+    }
+
+    /// Average emulated frames per second.
+    #[must_use]
+    pub fn fps(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            f64::from(self.frames) / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    const MOVIE: &str = "version 3
+emuVersion 22020
+rerecordCount 42
+palFlag 0
+romFilename spritecans
+romChecksum base64:AAAAAAAAAAAAAAAAAAAAAA==
+guid 00000000-0000-0000-0000-000000000000
+fourscore 0
+port0 1
+port1 1
+port2 0
+|2|........|........||
+|0|....T...|........||
+|0|R......A|.L....B.||
+|1|........|........||
+";
+
+    #[test]
+    fn parse_movie() {
+        let movie = Movie::parse(MOVIE).expect("valid movie");
+        assert_eq!(movie.rom_filename.as_deref(), Some("spritecans"));
+        assert_eq!(movie.rerecord_count, 42);
+        assert!(!movie.pal);
+        assert_eq!(movie.frames.len(), 4);
+        assert_eq!(movie.frames[0].commands, Commands::HARD_RESET);
+        assert_eq!(movie.frames[1].joypads[0], JoypadBtnState::START);
+        assert_eq!(
+            movie.frames[2].joypads[0],
+            JoypadBtnState::RIGHT | JoypadBtnState::A
+        );
+        assert_eq!(
+            movie.frames[2].joypads[1],
+            JoypadBtnState::LEFT | JoypadBtnState::B
+        );
+        assert_eq!(movie.frames[3].commands, Commands::SOFT_RESET);
+
+        assert!(matches!(
+            Movie::parse("version 3\n|0|R.|........||"),
+            Err(Error::InvalidInput { line: 2, .. })
+        ));
+        assert!(matches!(
+            Movie::parse("version 3\nport0 2"),
+            Err(Error::Unsupported { .. })
+        ));
+        assert!(matches!(
+            Movie::parse("|0|........|........||"),
+            Err(Error::InvalidHeader { .. })
+        ));
+    }
+
+    #[test]
+    fn play_movie() {
+        let rom = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_roms/spritecans.nes"
+        ))
+        .expect("valid rom");
+        let mut movie = Movie::parse(MOVIE).expect("valid movie");
+        movie.frames.resize(60, MovieFrame::default());
+
+        let report = movie
+            .play(Config::default(), "spritecans", &mut rom.as_slice())
+            .expect("valid playback");
+        assert_eq!(report.frames, 60);
+        assert_eq!(report.frame_hashes.len(), 60);
+        assert_eq!(report.desync_frame(&report.frame_hashes), None);
+
+        let rerun = movie
+            .play(Config::default(), "spritecans", &mut rom.as_slice())
+            .expect("valid playback");
+        assert_eq!(rerun.end_hash, report.end_hash, "playback is reproducible");
+        assert_eq!(rerun.desync_frame(&report.frame_hashes), None);
+
+        let mut baseline = report.frame_hashes.clone();
+        baseline[30] ^= 1;
+        assert_eq!(report.desync_frame(&baseline), Some(30));
+        baseline.push(0);
+        baseline[30] ^= 1;
+        assert_eq!(report.desync_frame(&baseline), Some(60));
+    }
+}
@@ -0,0 +1,741 @@
+//! ROM patching and the compatibility patch database.
+
+use crate::{common::NesRegion, fs};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use snafu::Snafu;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Snafu, Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub enum Error {
+    #[snafu(display("invalid patch header"))]
+    InvalidHeader,
+    #[snafu(display("unexpected end of patch at byte {offset}"))]
+    UnexpectedEof { offset: usize },
+    #[snafu(display("invalid number in patch at byte {offset}"))]
+    InvalidNumber { offset: usize },
+    #[snafu(display("patch copies from outside the rom at byte {offset}"))]
+    OutOfBounds { offset: usize },
+    #[snafu(display("patch expects a {expected} byte rom, not {actual} bytes"))]
+    SourceSize { expected: usize, actual: usize },
+    #[snafu(display("patch expects a rom with CRC32 {expected:08X}, not {actual:08X}"))]
+    SourceChecksum { expected: u32, actual: u32 },
+    #[snafu(display("patched rom CRC32 {actual:08X} doesn't match the expected {expected:08X}"))]
+    TargetChecksum { expected: u32, actual: u32 },
+    #[snafu(display(
+        "corrupted patch, CRC32 {actual:08X} doesn't match the expected {expected:08X}"
+    ))]
+    PatchChecksum { expected: u32, actual: u32 },
+    #[snafu(display("invalid compatibility patch on line {line}: {message}"))]
+    InvalidDatabase { line: usize, message: String },
+}
+
+/// A single IPS patch record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub enum IpsRecord {
+    /// Copy `data` starting at `offset`.
+    Bytes { offset: u32, data: Vec<u8> },
+    /// Fill `len` bytes with `value` starting at `offset`.
+    Rle { offset: u32, len: u16, value: u8 },
+}
+
+/// An International Patching System (IPS) patch.
+///
+/// See: <https://zerosoft.zophar.net/ips.php>
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct Ips {
+    pub records: Vec<IpsRecord>,
+    /// Optional size to truncate the patched data to.
+    pub truncate: Option<u32>,
+}
+
+impl Ips {
+    const HEADER: &'static [u8] = b"PATCH";
+    const EOF: &'static [u8] = b"EOF";
+
+    /// Parse an IPS patch.
+    ///
+    /// # Errors
+    ///
+    /// If the patch is missing its header or is truncated, then an error is returned.
+    pub fn parse(patch: &[u8]) -> Result<Self> {
+        let read = |offset: usize, len: usize| {
+            patch
+                .get(offset..offset + len)
+                .ok_or(Error::UnexpectedEof { offset })
+        };
+        if read(0, Self::HEADER.len()).ok() != Some(Self::HEADER) {
+            return Err(Error::InvalidHeader);
+        }
+
+        let mut ips = Self::default();
+        let mut pos = Self::HEADER.len();
+        loop {
+            let offset = read(pos, 3)?;
+            if offset == Self::EOF {
+                pos += 3;
+                break;
+            }
+            let offset = u32::from_be_bytes([0, offset[0], offset[1], offset[2]]);
+            let size = read(pos + 3, 2)?;
+            let size = u16::from_be_bytes([size[0], size[1]]);
+            pos += 5;
+            if size == 0 {
+                let len = read(pos, 2)?;
+                let value = read(pos + 2, 1)?[0];
+                ips.records.push(IpsRecord::Rle {
+                    offset,
+                    len: u16::from_be_bytes([len[0], len[1]]),
+                    value,
+                });
+                pos += 3;
+            } else {
+                let data = read(pos, usize::from(size))?.to_vec();
+                ips.records.push(IpsRecord::Bytes { offset, data });
+                pos += usize::from(size);
+            }
+        }
+        if let Ok(size) = read(pos, 3) {
+            ips.truncate = Some(u32::from_be_bytes([0, size[0], size[1], size[2]]));
+        }
+        Ok(ips)
+    }
+
+    /// Apply the patch to `data`, growing it if records extend past the end.
+    pub fn apply(&self, data: &mut Vec<u8>) {
+        for record in &self.records {
+            let (offset, len) = match record {
+                IpsRecord::Bytes { offset, data } => (*offset as usize, data.len()),
+                IpsRecord::Rle { offset, len, .. } => (*offset as usize, usize::from(*len)),
+            };
+            if data.len() < offset + len {
+                data.resize(offset + len, 0x00);
+            }
+            match record {
+                IpsRecord::Bytes { data: bytes, .. } => {
+                    data[offset..offset + len].copy_from_slice(bytes);
+                }
+                IpsRecord::Rle { value, .. } => data[offset..offset + len].fill(*value),
+            }
+        }
+        if let Some(size) = self.truncate {
+            data.truncate(size as usize);
+        }
+    }
+}
+
+/// A single BPS patch action, writing `len` bytes to the end of the patched data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub enum BpsAction {
+    /// Copy bytes from the same offset in the original data.
+    SourceRead { len: usize },
+    /// Copy `data` stored in the patch.
+    TargetRead { data: Vec<u8> },
+    /// Copy bytes from the original data, moving the source offset by `offset` first.
+    SourceCopy { offset: isize, len: usize },
+    /// Copy bytes already written to the patched data, moving the target offset by `offset`
+    /// first.
+    TargetCopy { offset: isize, len: usize },
+}
+
+/// A Beat Patching System (BPS) patch, validated against the CRC32 of the original and patched
+/// data.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct Bps {
+    pub source_size: usize,
+    pub target_size: usize,
+    /// Optional metadata, usually XML.
+    pub metadata: String,
+    pub actions: Vec<BpsAction>,
+    pub source_crc32: u32,
+    pub target_crc32: u32,
+}
+
+impl Bps {
+    const HEADER: &'static [u8] = b"BPS1";
+
+    /// Parse a BPS patch.
+    ///
+    /// # Errors
+    ///
+    /// If the patch is missing its header, is truncated or doesn't match its CRC32, then an error
+    /// is returned.
+    pub fn parse(patch: &[u8]) -> Result<Self> {
+        let (patch, source_crc32, target_crc32) = parse_footer(patch, Self::HEADER)?;
+        let mut pos = Self::HEADER.len();
+        let mut bps = Self {
+            source_size: read_number(patch, &mut pos)?,
+            target_size: read_number(patch, &mut pos)?,
+            source_crc32,
+            target_crc32,
+            ..Self::default()
+        };
+        let metadata_size = read_number(patch, &mut pos)?;
+        let metadata = read_bytes(patch, pos, metadata_size)?;
+        bps.metadata = String::from_utf8_lossy(metadata).into_owned();
+        pos += metadata_size;
+
+        while pos < patch.len() {
+            let data = read_number(patch, &mut pos)?;
+            let len = (data >> 2) + 1;
+            let action = match data & 0x03 {
+                0 => BpsAction::SourceRead { len },
+                1 => {
+                    let data = read_bytes(patch, pos, len)?.to_vec();
+                    pos += len;
+                    BpsAction::TargetRead { data }
+                }
+                command => {
+                    let start = pos;
+                    let offset = read_number(patch, &mut pos)?;
+                    let magnitude = isize::try_from(offset >> 1)
+                        .map_err(|_| Error::InvalidNumber { offset: start })?;
+                    let offset = if offset & 0x01 == 0x01 {
+                        -magnitude
+                    } else {
+                        magnitude
+                    };
+                    if command == 2 {
+                        BpsAction::SourceCopy { offset, len }
+                    } else {
+                        BpsAction::TargetCopy { offset, len }
+                    }
+                }
+            };
+            bps.actions.push(action);
+        }
+        Ok(bps)
+    }
+
+    /// Apply the patch to `source`, returning the patched data.
+    ///
+    /// # Errors
+    ///
+    /// If `source` or the patched data don't match the recorded sizes and CRC32s, or an action
+    /// copies from outside the data, then an error is returned.
+    pub fn apply(&self, source: &[u8]) -> Result<Vec<u8>> {
+        if source.len() != self.source_size {
+            return Err(Error::SourceSize {
+                expected: self.source_size,
+                actual: source.len(),
+            });
+        }
+        let crc32 = fs::compute_crc32(source);
+        if crc32 != self.source_crc32 {
+            return Err(Error::SourceChecksum {
+                expected: self.source_crc32,
+                actual: crc32,
+            });
+        }
+
+        let mut target = Vec::new();
+        let mut source_offset = 0isize;
+        let mut target_offset = 0isize;
+        for action in &self.actions {
+            let out_of_bounds = Error::OutOfBounds {
+                offset: target.len(),
+            };
+            match action {
+                BpsAction::SourceRead { len } => {
+                    let start = target.len();
+                    let bytes = source.get(start..start + len).ok_or(out_of_bounds)?;
+                    target.extend_from_slice(bytes);
+                }
+                BpsAction::TargetRead { data } => target.extend_from_slice(data),
+                BpsAction::SourceCopy { offset, len } => {
+                    source_offset += offset;
+                    let bytes = usize::try_from(source_offset)
+                        .ok()
+                        .and_then(|start| source.get(start..start + len))
+                        .ok_or(out_of_bounds)?;
+                    target.extend_from_slice(bytes);
+                    source_offset += *len as isize;
+                }
+                BpsAction::TargetCopy { offset, len } => {
+                    target_offset += offset;
+                    // Copied a byte at a time since the copy can overlap the bytes being written
+                    let start = usize::try_from(target_offset)
+                        .ok()
+                        .filter(|&start| start < target.len())
+                        .ok_or(out_of_bounds)?;
+                    for i in start..start + len {
+                        target.push(target[i]);
+                    }
+                    target_offset += *len as isize;
+                }
+            }
+        }
+
+        let crc32 = fs::compute_crc32(&target);
+        if target.len() != self.target_size || crc32 != self.target_crc32 {
+            return Err(Error::TargetChecksum {
+                expected: self.target_crc32,
+                actual: crc32,
+            });
+        }
+        Ok(target)
+    }
+}
+
+/// A run of bytes in a UPS patch, XOR'd with the original data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct UpsHunk {
+    /// Number of bytes left unchanged since the end of the last hunk.
+    pub skip: usize,
+    /// Bytes to XOR with the original data, not including the terminating `0`.
+    pub xor: Vec<u8>,
+}
+
+/// A Universal Patching System (UPS) patch, validated against the CRC32 of the original and
+/// patched data. Patches can also be applied to the patched data to restore the original.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct Ups {
+    pub source_size: usize,
+    pub target_size: usize,
+    pub hunks: Vec<UpsHunk>,
+    pub source_crc32: u32,
+    pub target_crc32: u32,
+}
+
+impl Ups {
+    const HEADER: &'static [u8] = b"UPS1";
+
+    /// Parse a UPS patch.
+    ///
+    /// # Errors
+    ///
+    /// If the patch is missing its header, is truncated or doesn't match its CRC32, then an error
+    /// is returned.
+    pub fn parse(patch: &[u8]) -> Result<Self> {
+        let (patch, source_crc32, target_crc32) = parse_footer(patch, Self::HEADER)?;
+        let mut pos = Self::HEADER.len();
+        let mut ups = Self {
+            source_size: read_number(patch, &mut pos)?,
+            target_size: read_number(patch, &mut pos)?,
+            source_crc32,
+            target_crc32,
+            ..Self::default()
+        };
+        while pos < patch.len() {
+            let skip = read_number(patch, &mut pos)?;
+            let len = patch[pos..]
+                .iter()
+                .position(|&byte| byte == 0x00)
+                .ok_or(Error::UnexpectedEof { offset: pos })?;
+            let xor = patch[pos..pos + len].to_vec();
+            pos += len + 1;
+            ups.hunks.push(UpsHunk { skip, xor });
+        }
+        Ok(ups)
+    }
+
+    /// Apply the patch to `source`, returning the patched data. If `source` is already patched,
+    /// the original data is returned instead.
+    ///
+    /// # Errors
+    ///
+    /// If `source` or the patched data don't match the recorded CRC32s, then an error is
+    /// returned.
+    pub fn apply(&self, source: &[u8]) -> Result<Vec<u8>> {
+        let crc32 = fs::compute_crc32(source);
+        let (target_size, target_crc32) = if crc32 == self.source_crc32 {
+            (self.target_size, self.target_crc32)
+        } else if crc32 == self.target_crc32 {
+            (self.source_size, self.source_crc32)
+        } else {
+            return Err(Error::SourceChecksum {
+                expected: self.source_crc32,
+                actual: crc32,
+            });
+        };
+
+        let mut target = source.to_vec();
+        target.resize(target_size, 0x00);
+        let mut offset = 0;
+        for hunk in &self.hunks {
+            offset += hunk.skip;
+            for xor in &hunk.xor {
+                if let Some(byte) = target.get_mut(offset) {
+                    *byte ^= xor;
+                }
+                offset += 1;
+            }
+            // Skip the terminating `0`, which leaves its byte unchanged
+            offset += 1;
+        }
+
+        let crc32 = fs::compute_crc32(&target);
+        if crc32 != target_crc32 {
+            return Err(Error::TargetChecksum {
+                expected: target_crc32,
+                actual: crc32,
+            });
+        }
+        Ok(target)
+    }
+}
+
+/// Check the header and CRC32 of a BPS or UPS patch, returning the patch without its footer and
+/// the recorded CRC32s of the original and patched data.
+fn parse_footer<'a>(patch: &'a [u8], header: &[u8]) -> Result<(&'a [u8], u32, u32)> {
+    if !patch.starts_with(header) {
+        return Err(Error::InvalidHeader);
+    }
+    // Source, target and patch CRC32s
+    const FOOTER_LEN: usize = 12;
+    if patch.len() < header.len() + FOOTER_LEN {
+        return Err(Error::UnexpectedEof {
+            offset: patch.len(),
+        });
+    }
+    let (patch, footer) = patch.split_at(patch.len() - FOOTER_LEN);
+    let crc32 =
+        |i: usize| u32::from_le_bytes([footer[i], footer[i + 1], footer[i + 2], footer[i + 3]]);
+    let expected = crc32(8);
+    let actual = fs::compute_crc32(&[patch, &footer[..8]].concat());
+    if actual != expected {
+        return Err(Error::PatchChecksum { expected, actual });
+    }
+    Ok((patch, crc32(0), crc32(4)))
+}
+
+/// Read `len` bytes of a patch starting at `offset`.
+fn read_bytes(patch: &[u8], offset: usize, len: usize) -> Result<&[u8]> {
+    offset
+        .checked_add(len)
+        .and_then(|end| patch.get(offset..end))
+        .ok_or(Error::UnexpectedEof { offset })
+}
+
+/// Read a variable-length number used by BPS and UPS patches, advancing `pos` past it.
+fn read_number(patch: &[u8], pos: &mut usize) -> Result<usize> {
+    let start = *pos;
+    let invalid = Error::InvalidNumber { offset: start };
+    let mut number = 0usize;
+    let mut shift = 1usize;
+    loop {
+        let byte = *patch
+            .get(*pos)
+            .ok_or(Error::UnexpectedEof { offset: *pos })?;
+        *pos += 1;
+        number = usize::from(byte & 0x7F)
+            .checked_mul(shift)
+            .and_then(|value| number.checked_add(value))
+            .ok_or(invalid.clone())?;
+        if byte & 0x80 == 0x80 {
+            return Ok(number);
+        }
+        shift = shift.checked_shl(7).ok_or(invalid.clone())?;
+        number = number.checked_add(shift).ok_or(invalid.clone())?;
+    }
+}
+
+/// A ROM patch in any of the supported formats, detected from its header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub enum Patch {
+    Ips(Ips),
+    Bps(Bps),
+    Ups(Ups),
+}
+
+impl Patch {
+    /// File extensions of the supported patch formats.
+    pub const EXTENSIONS: [&'static str; 3] = ["ips", "bps", "ups"];
+
+    /// Parse a patch in any of the supported formats.
+    ///
+    /// # Errors
+    ///
+    /// If the patch format isn't recognized or the patch is invalid, then an error is returned.
+    pub fn parse(patch: &[u8]) -> Result<Self> {
+        if patch.starts_with(Ips::HEADER) {
+            Ips::parse(patch).map(Self::Ips)
+        } else if patch.starts_with(Bps::HEADER) {
+            Bps::parse(patch).map(Self::Bps)
+        } else if patch.starts_with(Ups::HEADER) {
+            Ups::parse(patch).map(Self::Ups)
+        } else {
+            Err(Error::InvalidHeader)
+        }
+    }
+
+    /// Apply the patch to a copy of `rom`, returning the patched ROM.
+    ///
+    /// # Errors
+    ///
+    /// If a BPS or UPS patch doesn't match `rom` or the patched ROM, then an error is returned.
+    pub fn apply(&self, rom: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Ips(ips) => {
+                let mut data = rom.to_vec();
+                ips.apply(&mut data);
+                Ok(data)
+            }
+            Self::Bps(bps) => bps.apply(rom),
+            Self::Ups(ups) => ups.apply(rom),
+        }
+    }
+}
+
+/// A curated patch fixing region checks or timing assumptions so a game runs correctly on a
+/// region it wasn't released for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct CompatPatch {
+    /// CRC32 of the PRG-ROM and CHR-ROM the patch applies to.
+    pub crc32: u32,
+    /// Region the patched game targets.
+    pub region: NesRegion,
+    /// Game title.
+    pub title: String,
+    /// What the patch changes.
+    pub description: String,
+    /// Patch applied to the combined PRG-ROM and CHR-ROM.
+    pub patch: Ips,
+}
+
+impl CompatPatch {
+    /// Returns the compatibility patch for a game matching `crc32` in the active
+    /// [`RomDatabase`](crate::rom_db::RomDatabase), if any.
+    pub fn lookup(crc32: u32) -> Option<Self> {
+        crate::rom_db::active().lookup_compat_patch(crc32).cloned()
+    }
+
+    /// Apply the patch to the given PRG-ROM and CHR-ROM.
+    pub fn apply(&self, prg_rom: &mut Vec<u8>, chr_rom: &mut Vec<u8>) {
+        let prg_len = prg_rom.len();
+        let chr_len = chr_rom.len();
+        let mut image = core::mem::take(prg_rom);
+        image.append(chr_rom);
+        self.patch.apply(&mut image);
+        image.resize(prg_len + chr_len, 0x00);
+        *chr_rom = image.split_off(prg_len);
+        *prg_rom = image;
+    }
+
+    /// Parse the compatibility patch database. Each non-comment line has the format:
+    ///
+    /// `CRC32, Region, "Title", "Description", OFFSET:BYTES [OFFSET:BYTES ...]`
+    ///
+    /// where `OFFSET` and `BYTES` are hex, relative to the start of PRG-ROM.
+    ///
+    /// # Errors
+    ///
+    /// If any line is malformed, then an error is returned.
+    pub fn parse_database(database: &str) -> Result<Vec<Self>> {
+        database
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|(line, entry)| {
+                Self::parse_entry(entry).map_err(|message| Error::InvalidDatabase {
+                    line: line + 1,
+                    message,
+                })
+            })
+            .collect()
+    }
+
+    fn parse_entry(entry: &str) -> core::result::Result<Self, String> {
+        let (crc32, rest) = entry.split_once(',').ok_or("missing crc32")?;
+        let crc32 = u32::from_str_radix(crc32.trim(), 16).map_err(|err| err.to_string())?;
+        let (region, rest) = rest.split_once(',').ok_or("missing region")?;
+        let region = NesRegion::try_from(region.trim().to_lowercase().as_str())
+            .map_err(|err| err.to_string())?;
+        let quoted = |s: &str| -> core::result::Result<(String, String), String> {
+            let s = s
+                .trim_start()
+                .strip_prefix('"')
+                .ok_or("expected quoted string")?;
+            let (value, rest) = s.split_once('"').ok_or("unterminated string")?;
+            let rest = rest.trim_start().strip_prefix(',').ok_or("missing field")?;
+            Ok((value.to_string(), rest.to_string()))
+        };
+        let (title, rest) = quoted(rest)?;
+        let (description, rest) = quoted(&rest)?;
+        let records = rest
+            .split_whitespace()
+            .map(|record| {
+                let (offset, bytes) = record.split_once(':').ok_or("expected OFFSET:BYTES")?;
+                let offset = u32::from_str_radix(offset, 16).map_err(|err| err.to_string())?;
+                if bytes.is_empty() || bytes.len() % 2 != 0 || !bytes.is_ascii() {
+                    return Err(alloc::format!("invalid bytes `{bytes}`"));
+                }
+                let data = (0..bytes.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&bytes[i..i + 2], 16))
+                    .collect::<core::result::Result<Vec<_>, _>>()
+                    .map_err(|err| err.to_string())?;
+                Ok(IpsRecord::Bytes { offset, data })
+            })
+            .collect::<core::result::Result<Vec<_>, String>>()?;
+        if records.is_empty() {
+            return Err("missing patch records".to_string());
+        }
+        Ok(Self {
+            crc32,
+            region,
+            title,
+            description,
+            patch: Ips {
+                records,
+                truncate: None,
+            },
+        })
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn ips_apply() {
+        let mut patch = b"PATCH".to_vec();
+        patch.extend([0x00, 0x00, 0x01, 0x00, 0x02, 0xAA, 0xBB]);
+        patch.extend([0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x03, 0xCC]);
+        patch.extend(b"EOF");
+        let ips = Ips::parse(&patch).expect("valid patch");
+
+        let mut data = vec![0x00; 4];
+        ips.apply(&mut data);
+        assert_eq!(data, [0x00, 0xAA, 0xBB, 0x00, 0xCC, 0xCC, 0xCC]);
+
+        assert_eq!(
+            Ips::parse(b"PATCH\x00\x00"),
+            Err(Error::UnexpectedEof { offset: 5 })
+        );
+        assert_eq!(Ips::parse(b"NOPE"), Err(Error::InvalidHeader));
+    }
+
+    /// Encode a variable-length number used by BPS and UPS patches.
+    fn push_number(patch: &mut Vec<u8>, mut number: usize) {
+        loop {
+            let byte = (number & 0x7F) as u8;
+            number >>= 7;
+            if number == 0 {
+                patch.push(byte | 0x80);
+                break;
+            }
+            patch.push(byte);
+            number -= 1;
+        }
+    }
+
+    /// Append the source, target and patch CRC32s.
+    fn push_footer(patch: &mut Vec<u8>, source: &[u8], target: &[u8]) {
+        patch.extend(fs::compute_crc32(source).to_le_bytes());
+        patch.extend(fs::compute_crc32(target).to_le_bytes());
+        patch.extend(fs::compute_crc32(patch).to_le_bytes());
+    }
+
+    #[test]
+    fn bps_apply() {
+        let source = b"ABCDEFGH";
+        let target = b"ABCDxyxyxyEF";
+        let mut patch = b"BPS1".to_vec();
+        push_number(&mut patch, source.len());
+        push_number(&mut patch, target.len());
+        push_number(&mut patch, 0);
+        // SourceRead 4
+        push_number(&mut patch, 3 << 2);
+        // TargetRead "xy"
+        push_number(&mut patch, (1 << 2) | 1);
+        patch.extend(b"xy");
+        // TargetCopy 4 from target offset 4
+        push_number(&mut patch, (3 << 2) | 3);
+        push_number(&mut patch, 4 << 1);
+        // SourceCopy 2 from source offset 4
+        push_number(&mut patch, (1 << 2) | 2);
+        push_number(&mut patch, 4 << 1);
+        push_footer(&mut patch, source, target);
+
+        let patch = Patch::parse(&patch).expect("valid patch");
+        assert!(matches!(patch, Patch::Bps(_)));
+        assert_eq!(patch.apply(source).expect("patched"), target);
+        assert!(matches!(
+            patch.apply(b"ABCDEFGX"),
+            Err(Error::SourceChecksum { .. })
+        ));
+        assert!(matches!(
+            patch.apply(b"ABCD"),
+            Err(Error::SourceSize {
+                expected: 8,
+                actual: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn ups_apply() {
+        let source = b"ABCDEFGH";
+        let target = b"ABxDEFGHIJ";
+        let mut patch = b"UPS1".to_vec();
+        push_number(&mut patch, source.len());
+        push_number(&mut patch, target.len());
+        push_number(&mut patch, 2);
+        patch.extend([b'C' ^ b'x', 0x00]);
+        push_number(&mut patch, 4);
+        patch.extend([b'I', b'J', 0x00]);
+        push_footer(&mut patch, source, target);
+
+        let mut corrupted = patch.clone();
+        corrupted[5] ^= 0xFF;
+        assert!(matches!(
+            Patch::parse(&corrupted),
+            Err(Error::PatchChecksum { .. })
+        ));
+
+        let patch = Patch::parse(&patch).expect("valid patch");
+        assert!(matches!(patch, Patch::Ups(_)));
+        assert_eq!(patch.apply(source).expect("patched"), target);
+        assert_eq!(patch.apply(target).expect("unpatched"), source);
+    }
+
+    #[test]
+    fn compat_patch_database() {
+        let database = r#"
+            # comment
+            1A2B3C4D, PAL, "Game (Europe)", "Skip region check", 0002:EAEA 4001:FF
+        "#;
+        let patches = CompatPatch::parse_database(database).expect("valid database");
+        assert_eq!(patches.len(), 1);
+        let patch = &patches[0];
+        assert_eq!(patch.crc32, 0x1A2B_3C4D);
+        assert_eq!(patch.region, NesRegion::Pal);
+        assert_eq!(patch.title, "Game (Europe)");
+
+        let mut prg_rom = vec![0x00; 0x4000];
+        let mut chr_rom = vec![0x00; 0x2000];
+        patch.apply(&mut prg_rom, &mut chr_rom);
+        assert_eq!(prg_rom[2..4], [0xEA, 0xEA]);
+        assert_eq!(chr_rom[1], 0xFF);
+        assert_eq!((prg_rom.len(), chr_rom.len()), (0x4000, 0x2000));
+
+        assert!(CompatPatch::parse_database("1234, PAL, \"Game\"").is_err());
+    }
+
+    #[test]
+    fn compat_patch_database_multibyte() {
+        let patches = CompatPatch::parse_database("1234, PAL, \"Pokémon\", \"Über\", 0010:EA")
+            .expect("valid database");
+        assert_eq!(patches[0].title, "Pokémon");
+
+        // Multibyte characters must not be split when parsing patch bytes
+        assert!(CompatPatch::parse_database("1234, PAL, \"Game\", \"Desc\", 0010:Aé0").is_err());
+        assert!(CompatPatch::parse_database("1234, PAL, \"Game\", \"Desc\", 0010:éé").is_err());
+        assert!(CompatPatch::parse_database("1234, PAL, \"Game\", \"Desc\", 001é:EA").is_err());
+    }
+}
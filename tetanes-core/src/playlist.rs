@@ -0,0 +1,316 @@
+//! Playlists of ROMs played back to back, e.g. for relay races, game-club marathons and automated
+//! compatibility sweeps.
+//!
+//! Each [`PlaylistEntry`] ends when its completion [`RamCondition`] is met or its time limit runs
+//! out, after which the next entry is loaded. A [`PlaylistRun`] tracks progress through a
+//! [`Playlist`] and records a [`PlaylistResult`] for every entry played.
+
+use crate::{
+    control_deck::ControlDeck,
+    mem::{Access, Mem},
+    Path,
+};
+use alloc::{string::String, vec::Vec};
+use core::time::Duration;
+use serde::{Deserialize, Serialize};
+
+/// An ordered list of ROMs to play.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+#[serde(default)]
+pub struct Playlist {
+    pub name: String,
+    pub entries: Vec<PlaylistEntry>,
+}
+
+/// A ROM in a [`Playlist`] and the conditions that end it.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+#[serde(default)]
+pub struct PlaylistEntry {
+    /// Path to the ROM, kept as a string so playlists can be shared between platforms.
+    pub path: String,
+    /// Emulated time after which the entry times out.
+    pub time_limit: Option<Duration>,
+    /// Memory condition that marks the entry as completed, e.g. a "game cleared" flag.
+    pub completion: Option<RamCondition>,
+}
+
+impl PlaylistEntry {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Display name of the entry, based on the ROM filename.
+    #[must_use]
+    pub fn name(&self) -> String {
+        Path::new(&self.path)
+            .file_stem()
+            .map_or_else(|| self.path.clone(), |name| name.to_string_lossy().into())
+    }
+}
+
+/// How a [`RamCondition`] compares the value in memory.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub enum Comparison {
+    #[default]
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+}
+
+impl Comparison {
+    pub const fn as_slice() -> &'static [Self] {
+        &[Self::Equal, Self::NotEqual, Self::Less, Self::Greater]
+    }
+
+    #[must_use]
+    pub const fn compare(&self, lhs: u8, rhs: u8) -> bool {
+        match self {
+            Self::Equal => lhs == rhs,
+            Self::NotEqual => lhs != rhs,
+            Self::Less => lhs < rhs,
+            Self::Greater => lhs > rhs,
+        }
+    }
+}
+
+impl AsRef<str> for Comparison {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::Less => "<",
+            Self::Greater => ">",
+        }
+    }
+}
+
+/// A condition on a byte of CPU memory, checked at the end of each frame.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub struct RamCondition {
+    pub addr: u16,
+    pub comparison: Comparison,
+    pub value: u8,
+}
+
+impl RamCondition {
+    /// Whether the condition is met, reading memory without side effects.
+    #[must_use]
+    pub fn is_met(&self, deck: &ControlDeck) -> bool {
+        let value = deck.cpu().bus.peek(self.addr, Access::Dummy);
+        self.comparison.compare(value, self.value)
+    }
+}
+
+impl core::fmt::Display for RamCondition {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "${:04X} {} ${:02X}",
+            self.addr,
+            self.comparison.as_ref(),
+            self.value
+        )
+    }
+}
+
+/// How a [`PlaylistEntry`] ended.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub enum Outcome {
+    /// The completion condition was met.
+    Completed,
+    /// The time limit ran out.
+    TimedOut,
+    /// The entry was skipped before finishing.
+    Skipped,
+    /// The ROM failed to load or crashed.
+    Failed(String),
+}
+
+impl core::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Completed => write!(f, "Completed"),
+            Self::TimedOut => write!(f, "Timed Out"),
+            Self::Skipped => write!(f, "Skipped"),
+            Self::Failed(reason) => write!(f, "Failed: {reason}"),
+        }
+    }
+}
+
+/// Result of playing a [`PlaylistEntry`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub struct PlaylistResult {
+    pub name: String,
+    pub outcome: Outcome,
+    /// Frames clocked before the entry ended.
+    pub frames: u32,
+    /// Emulated time before the entry ended.
+    pub elapsed: Duration,
+}
+
+/// Progress through a [`Playlist`].
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct PlaylistRun {
+    playlist: Playlist,
+    current: usize,
+    frames: u32,
+    elapsed: Duration,
+    results: Vec<PlaylistResult>,
+}
+
+impl PlaylistRun {
+    /// Start a run at the first entry of `playlist`.
+    pub const fn new(playlist: Playlist) -> Self {
+        Self {
+            playlist,
+            current: 0,
+            frames: 0,
+            elapsed: Duration::ZERO,
+            results: Vec::new(),
+        }
+    }
+
+    /// The playlist being run.
+    pub const fn playlist(&self) -> &Playlist {
+        &self.playlist
+    }
+
+    /// Index of the entry being played.
+    #[must_use]
+    pub const fn current_index(&self) -> usize {
+        self.current
+    }
+
+    /// The entry being played, or `None` if the run is finished.
+    #[must_use]
+    pub fn current(&self) -> Option<&PlaylistEntry> {
+        self.playlist.entries.get(self.current)
+    }
+
+    /// Whether every entry has been played.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.current >= self.playlist.entries.len()
+    }
+
+    /// Emulated time spent on the current entry.
+    #[must_use]
+    pub const fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Results of the entries played so far.
+    pub fn results(&self) -> &[PlaylistResult] {
+        &self.results
+    }
+
+    /// Account for a clocked frame lasting `frame_duration` of emulated time, returning an
+    /// [`Outcome`] if the current entry has ended. Call [`PlaylistRun::advance`] afterwards to
+    /// move to the next entry.
+    pub fn clock_frame(&mut self, deck: &ControlDeck, frame_duration: Duration) -> Option<Outcome> {
+        let entry = self.current()?;
+        let completed = entry
+            .completion
+            .is_some_and(|condition| condition.is_met(deck));
+        let time_limit = entry.time_limit;
+        self.frames += 1;
+        self.elapsed += frame_duration;
+        if completed {
+            Some(Outcome::Completed)
+        } else if time_limit.is_some_and(|limit| self.elapsed >= limit) {
+            Some(Outcome::TimedOut)
+        } else {
+            None
+        }
+    }
+
+    /// Record `outcome` for the current entry and move to the next one, returning it if the run
+    /// isn't finished.
+    pub fn advance(&mut self, outcome: Outcome) -> Option<&PlaylistEntry> {
+        if let Some(entry) = self.current() {
+            self.results.push(PlaylistResult {
+                name: entry.name(),
+                outcome,
+                frames: self.frames,
+                elapsed: self.elapsed,
+            });
+            self.current += 1;
+            self.frames = 0;
+            self.elapsed = Duration::ZERO;
+        }
+        self.current()
+    }
+
+    /// End the run early, recording the current entry as skipped. Remaining entries have no
+    /// result.
+    pub fn stop(&mut self) {
+        self.advance(Outcome::Skipped);
+        self.current = self.playlist.entries.len();
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn playlist_run() {
+        let frame = Duration::from_millis(10);
+        let deck = ControlDeck::new();
+        let playlist = Playlist {
+            name: "Marathon".into(),
+            entries: vec![
+                PlaylistEntry {
+                    path: "first.nes".into(),
+                    time_limit: Some(frame * 3),
+                    completion: None,
+                },
+                PlaylistEntry {
+                    path: "second.nes".into(),
+                    time_limit: None,
+                    completion: Some(RamCondition {
+                        addr: 0x0010,
+                        comparison: Comparison::Equal,
+                        value: deck.wram()[0x0010],
+                    }),
+                },
+                PlaylistEntry::new("third.nes"),
+            ],
+        };
+        let mut run = PlaylistRun::new(playlist);
+
+        assert_eq!(run.clock_frame(&deck, frame), None);
+        assert_eq!(run.clock_frame(&deck, frame), None);
+        assert_eq!(run.clock_frame(&deck, frame), Some(Outcome::TimedOut));
+        let next = run.advance(Outcome::TimedOut).map(PlaylistEntry::name);
+        assert_eq!(next.as_deref(), Some("second"));
+
+        assert_eq!(run.clock_frame(&deck, frame), Some(Outcome::Completed));
+        run.advance(Outcome::Completed);
+        assert_eq!(run.clock_frame(&deck, frame), None);
+        run.stop();
+        assert!(run.is_finished());
+        assert_eq!(run.advance(Outcome::Skipped), None);
+        assert_eq!(run.clock_frame(&deck, frame), None);
+
+        let results = run.results();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].frames, 3);
+        assert_eq!(results[0].elapsed, frame * 3);
+        assert_eq!(results[1].name, "second");
+        assert_eq!(results[1].outcome, Outcome::Completed);
+        assert_eq!(results[2].outcome, Outcome::Skipped);
+    }
+}
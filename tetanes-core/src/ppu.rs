@@ -5,7 +5,13 @@ use crate::{
     cpu::Cpu,
     mapper::{Mapped, Mapper},
     mem::{Access, Mem},
-    ppu::{bus::Bus, frame::Frame},
+    ppu::{
+        bus::Bus,
+        frame::Frame,
+        hook::{ScanlineHook, ScanlinePalette},
+        model::PpuModel,
+        open_bus::OpenBus,
+    },
 };
 use alloc::{vec, vec::Vec};
 use core::cmp::Ordering;
@@ -20,7 +26,10 @@ use tracing::trace;
 pub mod bus;
 pub mod ctrl;
 pub mod frame;
+pub mod hook;
 pub mod mask;
+pub mod model;
+pub mod open_bus;
 pub mod scroll;
 pub mod sprite;
 pub mod status;
@@ -127,6 +136,13 @@ pub struct Ppu {
     pub spr_count: usize,
     /// $2007 PPUDATA buffer.
     pub vram_buffer: u8,
+    /// PPU cycles remaining during which a $2007 read is ignored. Reads on consecutive CPU cycles,
+    /// e.g. from a DMA halting on `LDA $2007`, only affect the PPU once. Always zero between
+    /// frames, so it's not saved.
+    ///
+    /// See: <https://www.nesdev.org/wiki/PPU_registers#The_PPUDATA_read_buffer>
+    #[serde(skip)]
+    pub data_read_delay: u8,
 
     /// $2004 Object Attribute Memory (OAM) data (read/write).
     pub oamdata: Vec<u8>,
@@ -138,18 +154,41 @@ pub struct Ppu {
     pub spr_present: Vec<bool>,
 
     pub prevent_vbl: bool,
+    /// Whether the PPU is pulling /NMI low, which the CPU polls every cycle.
+    #[serde(deserialize_with = "crate::fs::added_in::<9, _, _>")]
+    pub nmi_pending: bool,
     pub frame: Frame,
+    /// Whether the current frame is odd, which shortens the pre-render scanline by one cycle on
+    /// NTSC while rendering. Toggled every frame independently of the frame number and cleared on
+    /// power-up and reset.
+    ///
+    /// See: <https://www.nesdev.org/wiki/PPU_frame_timing#Even/Odd_Frames>
+    #[serde(deserialize_with = "crate::fs::added_in::<2, _, _>")]
+    pub odd_frame: bool,
 
     pub region: NesRegion,
+    /// PPU model, which differs for Vs. System arcade boards.
+    #[serde(deserialize_with = "crate::fs::added_in::<6, _, _>")]
+    pub model: PpuModel,
     pub cycle_count: usize,
-    /// Internal signal that clears status registers and prevents writes and cleared at the end of
-    /// VBlank.
+    /// Internal signal set on power-up and reset that causes writes to `PPUCTRL`, `PPUMASK`,
+    /// `PPUSCROLL` and `PPUADDR` to be ignored. Cleared at the end of the first VBlank, ~29658 CPU
+    /// cycles later on NTSC and ~33132 on PAL.
     ///
     /// See: <https://www.nesdev.org/wiki/PPU_power_up_state>
     pub reset_signal: bool,
+    /// Whether to emulate the `reset_signal` warm-up period.
     pub emulate_warmup: bool,
 
-    pub open_bus: u8,
+    /// I/O latch returned for undriven bits when reading PPU registers.
+    #[serde(deserialize_with = "OpenBus::deserialize_versioned")]
+    pub open_bus: OpenBus,
+    /// Frontend hook that modifies the palette used to render each visible scanline.
+    #[serde(skip)]
+    pub scanline_hook: Option<ScanlineHook>,
+    /// Palette returned by `scanline_hook` for the current scanline.
+    #[serde(skip)]
+    pub scanline_palette: Option<ScanlinePalette>,
 }
 
 impl Default for Ppu {
@@ -262,6 +301,7 @@ impl Ppu {
             spr_zero_visible: false,
             spr_count: 0,
             vram_buffer: 0x00,
+            data_read_delay: 0,
 
             oamdata: vec![0xFF; Self::OAM_SIZE],
             secondary_oamdata: [0xFF; Self::SECONDARY_OAM_SIZE],
@@ -269,13 +309,18 @@ impl Ppu {
             spr_present: vec![false; Self::VISIBLE_END as usize],
 
             prevent_vbl: false,
+            nmi_pending: false,
             frame: Frame::new(),
+            odd_frame: false,
 
             region,
+            model: PpuModel::Rp2c02,
             cycle_count: 0,
             reset_signal: false,
             emulate_warmup: false,
-            open_bus: 0x00,
+            open_bus: OpenBus::new(),
+            scanline_hook: None,
+            scanline_palette: None,
         };
         ppu.set_region(ppu.region);
         ppu
@@ -288,6 +333,23 @@ impl Ppu {
         Self::SYSTEM_PALETTE[(pixel as usize) & (Self::SYSTEM_PALETTE.len() - 1)]
     }
 
+    /// Returns the number of frames rendered per second based on [`NesRegion`], e.g. ~60.0988 for
+    /// NTSC and ~50.0070 for PAL and Dendy.
+    #[must_use]
+    pub fn region_frame_rate(region: NesRegion) -> f32 {
+        let (dots_per_cpu_cycle, scanlines) = match region {
+            NesRegion::Auto | NesRegion::Ntsc => (3.0, 262.0),
+            NesRegion::Pal => (3.2, 312.0),
+            NesRegion::Dendy => (3.0, 312.0),
+        };
+        let mut dots = scanlines * (Self::CYCLE_END + 1) as f32;
+        if region.is_ntsc() {
+            // Odd frames skip a cycle while rendering
+            dots -= 0.5;
+        }
+        Cpu::region_clock_rate(region) * dots_per_cpu_cycle / dots
+    }
+
     /// Return the current frame buffer.
     #[inline]
     #[must_use]
@@ -309,12 +371,42 @@ impl Ppu {
         self.frame.pixel_brightness(x, y)
     }
 
+    /// Return the `(x, y)` scroll origin of the current frame within the logical nametables.
+    #[inline]
+    #[must_use]
+    pub const fn scroll_origin(&self) -> (u16, u16) {
+        self.scroll.origin()
+    }
+
     /// Load a Mapper into the PPU.
     #[inline]
     pub fn load_mapper(&mut self, mapper: Mapper) {
         self.bus.mapper = mapper;
         self.bus.update_mirroring();
     }
+
+    /// Set the [`PpuModel`], e.g. for the scrambled palettes of Vs. System PPUs.
+    pub fn set_model(&mut self, model: PpuModel) {
+        self.model = model;
+    }
+
+    /// Set a hook to modify the palette used to render each visible scanline.
+    pub fn set_scanline_hook(&mut self, hook: Option<ScanlineHook>) {
+        self.scanline_hook = hook;
+        self.scanline_palette = None;
+    }
+
+    /// Refresh the bits set in `mask` of the PPU I/O latch with `val`.
+    pub fn refresh_open_bus(&mut self, mask: u8, val: u8) {
+        let frame = self.frame_number();
+        self.open_bus.refresh(mask, val, frame);
+    }
+
+    /// Refresh the PPU I/O latch with a value written to any PPU register, including read-only
+    /// ones.
+    pub fn write_open_bus(&mut self, val: u8) {
+        self.refresh_open_bus(0xFF, val);
+    }
 }
 
 impl Ppu {
@@ -336,7 +428,7 @@ impl Ppu {
         if !self.prevent_vbl {
             self.status.set_in_vblank(true);
             if self.ctrl.nmi_enabled {
-                Cpu::set_nmi();
+                self.nmi_pending = true;
                 trace!("VBL NMI - PPU:{:3},{:3}", self.cycle, self.scanline,);
             }
         }
@@ -355,8 +447,8 @@ impl Ppu {
         self.status.set_spr_overflow(false);
         self.status.reset_in_vblank();
         self.reset_signal = false;
-        Cpu::clear_nmi();
-        self.open_bus = 0; // Clear open bus every frame
+        self.nmi_pending = false;
+        self.open_bus.decay(self.frame_number());
         let val = self.peek_status();
         self.bus.mapper.ppu_bus_write(0x2002, val);
     }
@@ -668,16 +760,20 @@ impl Ppu {
         let y = self.scanline;
         let addr = self.scroll.addr();
 
-        let color =
+        let palette_addr =
             if self.mask.rendering_enabled || (addr & Self::PALETTE_START) != Self::PALETTE_START {
                 let color = u16::from(self.pixel_color());
-                self.bus.read_palette(
-                    Self::PALETTE_START + (color & 0x03 > 0) as u16 * color,
-                    Access::Read,
-                )
+                Self::PALETTE_START + (color & 0x03 > 0) as u16 * color
             } else {
-                self.bus.read_palette(addr, Access::Read)
+                addr
             };
+        let mut color = self.bus.read_palette(palette_addr, Access::Read);
+        if let Some(palette) = &self.scanline_palette {
+            color = palette[self.bus.palette_mirror(palette_addr.into())];
+        }
+        if let Some(lut) = self.model.palette_lut() {
+            color = lut[usize::from(color & 0x3F)];
+        }
 
         self.frame.set_pixel(
             x,
@@ -777,7 +873,7 @@ impl Ppu {
 
                 if region.is_ntsc()
                     && prerender_scanline
-                    && self.frame_number() & 0x01 == 0x01
+                    && self.odd_frame
                     && cycle == Self::ODD_SKIP
                 {
                     // NTSC behavior while rendering - each odd PPU frame is one clock shorter
@@ -835,10 +931,10 @@ impl Registers for Ppu {
     //       |   6 | Hit Switch, 1 = generate interrupts on Hit (incorrect ???)
     //       |   7 | VBlank Switch, 1 = generate interrupts on VBlank
     fn write_ctrl(&mut self, val: u8) {
+        self.write_open_bus(val);
         if self.reset_signal && self.emulate_warmup {
             return;
         }
-        self.open_bus = val;
         self.ctrl.write(val);
         self.scroll.write_nametable_select(val);
 
@@ -852,14 +948,14 @@ impl Registers for Ppu {
         // By toggling NMI (bit 7) during VBlank without reading $2002, /NMI can be pulled low
         // multiple times, causing multiple NMIs to be generated.
         if !self.ctrl.nmi_enabled {
-            Cpu::clear_nmi();
+            self.nmi_pending = false;
         } else if self.status.in_vblank {
             trace!(
                 "$2000 NMI During VBL - PPU:{:3},{:3}",
                 self.cycle,
                 self.scanline
             );
-            Cpu::set_nmi();
+            self.nmi_pending = true;
         }
     }
 
@@ -871,10 +967,10 @@ impl Registers for Ppu {
     //       |   4 | Sprites Switch, 1 = show sprites, 0 = hide sprites
     //       | 5-7 | Unknown (???)
     fn write_mask(&mut self, val: u8) {
+        self.write_open_bus(val);
         if self.reset_signal && self.emulate_warmup {
             return;
         }
-        self.open_bus = val;
         self.mask.write(val);
     }
 
@@ -886,10 +982,10 @@ impl Registers for Ppu {
     //       |     | This flag resets to 0 when VBlank ends, or CPU reads $2002
     fn read_status(&mut self) -> u8 {
         let status = self.peek_status();
-        if Cpu::nmi_pending() {
+        if self.nmi_pending {
             trace!("$2002 NMI Ack - PPU:{:3},{:3}", self.cycle, self.scanline,);
         }
-        Cpu::clear_nmi();
+        self.nmi_pending = false;
         self.status.reset_in_vblank();
         self.scroll.reset_latch();
 
@@ -903,7 +999,8 @@ impl Registers for Ppu {
             );
             self.prevent_vbl = true;
         }
-        self.open_bus |= status & 0xE0;
+        // Only the status bits refresh the latch
+        self.refresh_open_bus(0xE0, status);
         self.bus.mapper.ppu_bus_write(0x2002, status);
         status
     }
@@ -917,8 +1014,12 @@ impl Registers for Ppu {
     //
     // Non-mutating version of `read_status`.
     fn peek_status(&self) -> u8 {
-        // Only upper 3 bits are connected for this register
-        (self.status.read() & 0xE0) | (self.open_bus & 0x1F)
+        match self.model.status_id() {
+            // RC2C05 PPUs return an ID in place of sprite overflow and open bus
+            Some(id) => (self.status.read() & 0xC0) | id,
+            // Only upper 3 bits are connected for this register
+            None => (self.status.read() & 0xE0) | (self.open_bus.value() & 0x1F),
+        }
     }
 
     // $2003 | W   | OAMADDR
@@ -927,7 +1028,7 @@ impl Registers for Ppu {
     //       |     | each access to $2004. The Sprite Memory contains coordinates,
     //       |     | colors, and other attributes of the sprites.
     fn write_oamaddr(&mut self, val: u8) {
-        self.open_bus = val;
+        self.write_open_bus(val);
         self.oamaddr = val;
     }
 
@@ -939,7 +1040,7 @@ impl Registers for Ppu {
     #[must_use]
     fn read_oamdata(&mut self) -> u8 {
         let val = self.peek_oamdata();
-        self.open_bus = val;
+        self.refresh_open_bus(0xFF, val);
         val
     }
 
@@ -968,7 +1069,7 @@ impl Registers for Ppu {
     //       |     | contains coordinates, colors, and other attributes of the
     //       |     | sprites.
     fn write_oamdata(&mut self, mut val: u8) {
-        self.open_bus = val;
+        self.write_open_bus(val);
         if self.mask.rendering_enabled
             && (self.scanline <= Self::VISIBLE_SCANLINE_END
                 || self.scanline == self.prerender_scanline
@@ -1006,19 +1107,19 @@ impl Registers for Ppu {
     //       |     | Remember, though, that because of the mirroring, there are
     //       |     | only 2 real Name Tables, not 4.
     fn write_scroll(&mut self, val: u8) {
+        self.write_open_bus(val);
         if self.reset_signal && self.emulate_warmup {
             return;
         }
-        self.open_bus = val;
         self.scroll.write(val);
     }
 
     // $2006 | W   | PPUADDR
     fn write_addr(&mut self, val: u8) {
+        self.write_open_bus(val);
         if self.reset_signal && self.emulate_warmup {
             return;
         }
-        self.open_bus = val;
         self.scroll.write_addr(val);
         // MMC3 clocks using A12
         self.bus.mapper.ppu_bus_write(self.scroll.addr(), val);
@@ -1027,10 +1128,12 @@ impl Registers for Ppu {
     // $2007 | RW  | PPUDATA
     #[must_use]
     fn read_data(&mut self) -> u8 {
-        if self.reset_signal && self.emulate_warmup {
-            self.open_bus = 0x00;
-            return 0x00;
+        if self.data_read_delay > 0 {
+            // Too soon after the last read to be seen by the PPU, so the bus still holds the
+            // last value read
+            return self.open_bus.value();
         }
+        self.data_read_delay = 6;
 
         let addr = self.scroll.addr();
         self.increment_vram_addr();
@@ -1038,20 +1141,20 @@ impl Registers for Ppu {
         // Buffering quirk resulting in a dummy read for the CPU
         // for reading pre-palette data in $0000 - $3EFF
         let val = self.bus.read(addr, Access::Read);
-        let val = if addr < Self::PALETTE_START {
+        let (val, mask) = if addr < Self::PALETTE_START {
             let buffer = self.vram_buffer;
             self.vram_buffer = val;
-            buffer
+            (buffer, 0xFF)
         } else {
             // Set internal buffer with mirrors of nametable when reading palettes
             // Since we're reading from > $3EFF subtract $1000 to fill
             // buffer with nametable mirror data
             self.vram_buffer = self.bus.read(addr - 0x1000, Access::Dummy);
             // Hi 2 bits of palette should be open bus
-            val | (self.open_bus & 0xC0)
+            (val | (self.open_bus.value() & 0xC0), 0x3F)
         };
 
-        self.open_bus = val;
+        self.refresh_open_bus(mask, val);
         // MMC3 clocks using A12
         self.bus.mapper.ppu_bus_read(self.scroll.addr());
 
@@ -1074,13 +1177,13 @@ impl Registers for Ppu {
             self.vram_buffer
         } else {
             // Hi 2 bits of palette should be open bus
-            self.bus.peek(addr, Access::Dummy) | (self.open_bus & 0xC0)
+            self.bus.peek(addr, Access::Dummy) | (self.open_bus.value() & 0xC0)
         }
     }
 
     // $2007 | RW  | PPUDATA
     fn write_data(&mut self, val: u8) {
-        self.open_bus = val;
+        self.write_open_bus(val);
         let addr = self.scroll.addr();
         trace!(
             "PPU $2007 write: ${addr:04X} -> {val:02X} - PPU:{:3},{:3}",
@@ -1104,10 +1207,20 @@ impl Clock for Ppu {
             // Post-render line
             if self.scanline == self.vblank_scanline - 1 {
                 self.frame.increment();
+                self.odd_frame = !self.odd_frame;
             } else {
                 // Wrap scanline back to 0
                 self.scanline *= (self.scanline <= self.prerender_scanline) as u32;
             }
+            if let Some(hook) = &self.scanline_hook {
+                if self.scanline <= Self::VISIBLE_SCANLINE_END {
+                    let mut palette = self.bus.palette;
+                    hook.call(self.scanline, &mut palette);
+                    self.scanline_palette = Some(palette);
+                } else {
+                    self.scanline_palette = None;
+                }
+            }
         } else {
             // cycle > 0
             self.cycle += 1;
@@ -1123,6 +1236,7 @@ impl Clock for Ppu {
         }
 
         self.cycle_count = self.cycle_count.wrapping_add(1);
+        self.data_read_delay = self.data_read_delay.saturating_sub(1);
 
         1
     }
@@ -1174,11 +1288,14 @@ impl Reset for Ppu {
         }
         self.secondary_oamaddr = 0x0000;
         self.vram_buffer = 0x00;
+        self.data_read_delay = 0;
         self.cycle = 0;
         self.scanline = 0;
         self.master_clock = 0;
         self.prevent_vbl = false;
+        self.nmi_pending = false;
         self.frame.reset(kind);
+        self.odd_frame = false;
         self.oam_fetch = 0x00;
         self.oam_eval_done = false;
         self.overflow_count = 0;
@@ -1188,7 +1305,7 @@ impl Reset for Ppu {
         self.spr_count = 0;
         self.sprites = [Sprite::new(); 8];
         self.spr_present.fill(false);
-        self.open_bus = 0x00;
+        self.open_bus = OpenBus::new();
         self.bus.reset(kind);
     }
 }
@@ -1197,6 +1314,7 @@ impl core::fmt::Debug for Ppu {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("Ppu")
             .field("region", &self.region)
+            .field("model", &self.model)
             .field("cycle_count", &self.cycle_count)
             .field("bus", &self.bus)
             .field("ctrl", &self.ctrl)
@@ -1210,6 +1328,7 @@ impl core::fmt::Debug for Ppu {
             .field("secondary_oamdata_len", &self.secondary_oamdata.len())
             .field("scroll", &self.scroll)
             .field("vram_buffer", &self.vram_buffer)
+            .field("data_read_delay", &self.data_read_delay)
             .field("cycle", &self.cycle)
             .field("scanline", &self.scanline)
             .field("master_clock", &self.master_clock)
@@ -1218,7 +1337,9 @@ impl core::fmt::Debug for Ppu {
             .field("prerender_scanline", &self.prerender_scanline)
             .field("pal_spr_eval_scanline", &self.pal_spr_eval_scanline)
             .field("prevent_vbl", &self.prevent_vbl)
+            .field("nmi_pending", &self.nmi_pending)
             .field("frame", &self.frame)
+            .field("odd_frame", &self.odd_frame)
             .field("tile_shift_lo", &self.tile_shift_lo)
             .field("tile_shift_hi", &self.tile_shift_hi)
             .field("tile_lo", &self.tile_lo)
@@ -1237,6 +1358,7 @@ impl core::fmt::Debug for Ppu {
             .field("sprites", &self.sprites)
             .field("spr_present_len", &self.spr_present.len())
             .field("open_bus", &self.open_bus)
+            .field("scanline_hook", &self.scanline_hook)
             .finish()
     }
 }
@@ -1249,6 +1371,14 @@ mod tests {
         mapper::{Mmc1Revision, Sxrom},
     };
 
+    /// Read $2007 PPUDATA, clocking past the delay after the previous read.
+    fn read_data(ppu: &mut Ppu) -> u8 {
+        for _ in 0..6 {
+            ppu.clock();
+        }
+        ppu.read_data()
+    }
+
     #[test]
     fn vram_writes() {
         let mut ppu = Ppu::default();
@@ -1262,6 +1392,30 @@ mod tests {
         assert_eq!(ppu.bus.read_ciram(0x2305, Access::Read), 0x66);
     }
 
+    #[test]
+    fn scanline_hook() {
+        let mut ppu = Ppu::default();
+        ppu.bus.write(0x3F00, 0x0F, Access::Write);
+        ppu.set_scanline_hook(Some(ScanlineHook::new(|scanline, palette| {
+            if scanline >= 120 {
+                palette[0] = 0x21;
+            }
+        })));
+        let frame = ppu.frame_number();
+        while ppu.frame_number() == frame {
+            ppu.clock();
+        }
+
+        assert_eq!(ppu.frame.pixel(0, 119), 0x0F);
+        assert_eq!(ppu.frame.pixel(0, 120), 0x21);
+        assert_eq!(ppu.frame.pixel(255, 239), 0x21);
+        assert_eq!(
+            ppu.bus.read(0x3F00, Access::Dummy),
+            0x0F,
+            "palette RAM is unchanged"
+        );
+    }
+
     #[test]
     fn vram_reads() {
         let mut ppu = Ppu::default();
@@ -1273,9 +1427,9 @@ mod tests {
         // PPU writes to $2006 are delayed by 2 PPU clocks
         ppu.clock();
         ppu.clock();
-        ppu.read_data(); // buffer read
+        read_data(&mut ppu); // buffer read
         assert_eq!(ppu.scroll.addr(), 0x2306);
-        assert_eq!(ppu.read_data(), 0x66);
+        assert_eq!(read_data(&mut ppu), 0x66);
         assert_eq!(ppu.scroll.addr(), 0x2307);
     }
 
@@ -1291,9 +1445,9 @@ mod tests {
         // PPU writes to $2006 are delayed by 2 PPU clocks
         ppu.clock();
         ppu.clock();
-        ppu.read_data(); // buffer read
-        assert_eq!(ppu.read_data(), 0x66);
-        assert_eq!(ppu.read_data(), 0x77);
+        read_data(&mut ppu); // buffer read
+        assert_eq!(read_data(&mut ppu), 0x66);
+        assert_eq!(read_data(&mut ppu), 0x77);
     }
 
     #[test]
@@ -1309,10 +1463,10 @@ mod tests {
         // PPU writes to $2006 are delayed by 2 PPU clocks
         ppu.clock();
         ppu.clock();
-        ppu.read_data(); // buffer read
-        assert_eq!(ppu.read_data(), 0x66);
-        assert_eq!(ppu.read_data(), 0x77);
-        assert_eq!(ppu.read_data(), 0x88);
+        read_data(&mut ppu); // buffer read
+        assert_eq!(read_data(&mut ppu), 0x66);
+        assert_eq!(read_data(&mut ppu), 0x77);
+        assert_eq!(read_data(&mut ppu), 0x88);
     }
 
     // Horizontal: https://wiki.nesdev.com/w/index.php/Mirroring
@@ -1340,16 +1494,16 @@ mod tests {
         // PPU writes to $2006 are delayed by 2 PPU clocks
         ppu.clock();
         ppu.clock();
-        ppu.read_data(); // buffer read
-        assert_eq!(ppu.read_data(), 0x66); // read A from $2005
+        read_data(&mut ppu); // buffer read
+        assert_eq!(read_data(&mut ppu), 0x66); // read A from $2005
 
         ppu.write_addr(0x2C);
         ppu.write_addr(0x05);
         // PPU writes to $2006 are delayed by 2 PPU clocks
         ppu.clock();
         ppu.clock();
-        ppu.read_data(); // buffer read
-        assert_eq!(ppu.read_data(), 0x77); // read b from $2C05
+        read_data(&mut ppu); // buffer read
+        assert_eq!(read_data(&mut ppu), 0x77); // read b from $2C05
     }
 
     // Vertical: https://wiki.nesdev.com/w/index.php/Mirroring
@@ -1382,16 +1536,16 @@ mod tests {
         // PPU writes to $2006 are delayed by 2 PPU clocks
         ppu.clock();
         ppu.clock();
-        ppu.read_data(); // buffer read
-        assert_eq!(ppu.read_data(), 0x66); // read a from $2805
+        read_data(&mut ppu); // buffer read
+        assert_eq!(read_data(&mut ppu), 0x66); // read a from $2805
 
         ppu.write_addr(0x24);
         ppu.write_addr(0x05);
         // PPU writes to $2006 are delayed by 2 PPU clocks
         ppu.clock();
         ppu.clock();
-        ppu.read_data(); // buffer read
-        assert_eq!(ppu.read_data(), 0x77); // read B from $2405
+        read_data(&mut ppu); // buffer read
+        assert_eq!(read_data(&mut ppu), 0x77); // read B from $2405
     }
 
     #[test]
@@ -1405,8 +1559,8 @@ mod tests {
         ppu.clock();
         ppu.clock();
         ppu.write_addr(0x05);
-        ppu.read_data(); // buffer read
-        assert_ne!(ppu.read_data(), 0x66);
+        read_data(&mut ppu); // buffer read
+        assert_ne!(read_data(&mut ppu), 0x66);
 
         ppu.read_status();
 
@@ -1415,8 +1569,8 @@ mod tests {
         // PPU writes to $2006 are delayed by 2 PPU clocks
         ppu.clock();
         ppu.clock();
-        ppu.read_data(); // buffer read
-        assert_eq!(ppu.read_data(), 0x66);
+        read_data(&mut ppu); // buffer read
+        assert_eq!(read_data(&mut ppu), 0x66);
     }
 
     #[test]
@@ -1430,9 +1584,9 @@ mod tests {
         // PPU writes to $2006 are delayed by 2 PPU clocks
         ppu.clock();
         ppu.clock();
-        ppu.read_data(); // buffer read
+        read_data(&mut ppu); // buffer read
         assert_eq!(ppu.scroll.addr(), 0x2306);
-        assert_eq!(ppu.read_data(), 0x66);
+        assert_eq!(read_data(&mut ppu), 0x66);
         assert_eq!(ppu.scroll.addr(), 0x2307);
     }
 
@@ -1459,4 +1613,52 @@ mod tests {
         ppu.write_oamaddr(0x11);
         assert_eq!(ppu.read_oamdata(), 0x77);
     }
+
+    #[test]
+    fn warmup() {
+        let mut ppu = Ppu {
+            emulate_warmup: true,
+            ..Ppu::default()
+        };
+        ppu.reset(ResetKind::Hard);
+
+        ppu.write_ctrl(0x04);
+        assert_eq!(ppu.ctrl.vram_increment, 1, "write ignored during warm-up");
+
+        while ppu.reset_signal {
+            ppu.clock();
+        }
+        let cpu_cycles = ppu.cycle_count / 3;
+        assert!((29_600..29_700).contains(&cpu_cycles), "{cpu_cycles}");
+        ppu.write_ctrl(0x04);
+        assert_eq!(ppu.ctrl.vram_increment, 32, "write accepted after warm-up");
+    }
+
+    #[test]
+    fn odd_frame_skip() {
+        const FRAME_CYCLES: usize = 262 * 341;
+
+        fn clock_frame(ppu: &mut Ppu) -> usize {
+            let frame = ppu.frame_number();
+            let start = ppu.cycle_count;
+            while frame == ppu.frame_number() {
+                ppu.clock();
+            }
+            ppu.cycle_count - start
+        }
+
+        let mut ppu = Ppu::default();
+        ppu.bus.load_chr_ram(vec![0x00; 0x2000]);
+        for _ in 0..2 {
+            ppu.write_mask(0x18);
+            clock_frame(&mut ppu);
+            assert!(ppu.odd_frame);
+            assert_eq!(clock_frame(&mut ppu), FRAME_CYCLES - 1, "odd frame");
+            assert_eq!(clock_frame(&mut ppu), FRAME_CYCLES, "even frame");
+            clock_frame(&mut ppu);
+            // Alignment restarts on reset regardless of the previous frame
+            ppu.reset(ResetKind::Soft);
+            assert!(!ppu.odd_frame);
+        }
+    }
 }
@@ -48,7 +48,7 @@ impl Default for Bus {
 
 impl Bus {
     const VRAM_SIZE: usize = 0x0800; // Two 1k Nametables
-    const PALETTE_SIZE: usize = 32; // 32 possible colors at a time
+    pub const PALETTE_SIZE: usize = 32; // 32 possible colors at a time
 
     pub fn new() -> Self {
         Self {
@@ -106,7 +106,7 @@ impl Bus {
         nametable | (!nametable & addr & 0x03FF)
     }
 
-    const fn palette_mirror(&self, addr: usize) -> usize {
+    pub(crate) const fn palette_mirror(&self, addr: usize) -> usize {
         let addr = addr & 0x001F;
         if addr >= 16 && addr.trailing_zeros() >= 2 {
             addr - 16
@@ -155,6 +155,41 @@ impl Bus {
         self.open_bus = val;
         val
     }
+
+    /// Write `val` to the CHR, nametable or palette memory mapped at `addr` without side effects,
+    /// e.g. mapper IRQ counters or open bus. CHR-ROM can be written. Returns `false` if `addr`
+    /// isn't mapped to memory.
+    pub fn poke(&mut self, addr: u16, val: u8) -> bool {
+        let mem = match addr {
+            0x0000..=0x1FFF => {
+                let addr = if let MappedRead::Chr(addr) = self.mapper.map_peek(addr) {
+                    addr
+                } else {
+                    addr.into()
+                };
+                if self.chr_ram.is_empty() {
+                    self.chr_rom.get_mut(addr)
+                } else {
+                    self.chr_ram.get_mut(addr)
+                }
+            }
+            0x2000..=0x3EFF => match self.mapper.map_peek(addr) {
+                MappedRead::Bus => {
+                    let addr = self.ciram_mirror(addr as usize);
+                    self.ciram.get_mut(addr)
+                }
+                MappedRead::CIRam(addr) => self.ciram.get_mut(addr & 0x07FF),
+                MappedRead::ExRam(addr) => self.exram.get_mut(addr),
+                _ => None,
+            },
+            0x3F00..=0x3FFF => {
+                let addr = self.palette_mirror(addr as usize);
+                self.palette.get_mut(addr)
+            }
+            _ => None,
+        };
+        mem.map(|mem| *mem = val).is_some()
+    }
 }
 
 impl Mem for Bus {
@@ -225,6 +260,12 @@ impl Mem for Bus {
                 MappedWrite::PrgRamProtect(val) => {
                     panic!("unexpected mapped PRG-RAM Protect write at ${addr:04X} with {val}");
                 }
+                MappedWrite::PrgRom(mapped, val) => {
+                    panic!("unexpected mapped PRG-ROM write at ${addr:04X} for ${mapped:04X} with ${val:02X}");
+                }
+                MappedWrite::PrgRomErase(start, _) => {
+                    panic!("unexpected mapped PRG-ROM erase at ${addr:04X} for ${start:04X}");
+                }
                 MappedWrite::None => (),
             },
             0x0000..=0x1FFF => {
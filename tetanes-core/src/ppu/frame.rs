@@ -9,11 +9,21 @@ use core::ops::{Deref, DerefMut};
 use serde::{Deserialize, Serialize};
 
 /// PPU frame.
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 #[serde(transparent)]
 #[must_use]
 pub struct Buffer(Vec<u16>);
 
+impl Clone for Buffer {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.0.clone_from(&source.0);
+    }
+}
+
 impl core::fmt::Debug for Buffer {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "Buffer({} elements)", self.0.len())
@@ -26,6 +36,13 @@ impl Default for Buffer {
     }
 }
 
+impl Buffer {
+    /// An unallocated buffer, used as a placeholder while a buffer is moved out of a [`Frame`].
+    pub(crate) const fn empty() -> Self {
+        Self(Vec::new())
+    }
+}
+
 impl Deref for Buffer {
     type Target = [u16];
     fn deref(&self) -> &Self::Target {
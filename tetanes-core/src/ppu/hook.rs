@@ -0,0 +1,36 @@
+//! Scanline palette hook implementation.
+
+use crate::ppu::bus::Bus;
+use alloc::sync::Arc;
+
+/// Palette used to render a scanline, indexed the same as palette RAM at `$3F00-$3F1F`.
+pub type ScanlinePalette = [u8; Bus::PALETTE_SIZE];
+
+type HookFn = dyn Fn(u32, &mut ScanlinePalette) + Send + Sync;
+
+/// Callback run at the start of each visible scanline that can modify the palette used to render
+/// it, allowing raster color effects and colorization hacks without patching the PPU.
+///
+/// The callback receives the scanline and a copy of palette RAM. Any changes only affect the
+/// rendered output for that scanline and aren't visible to the running game. Palette writes made
+/// by the game mid-scanline take effect on the next scanline while a hook is set.
+#[derive(Clone)]
+#[must_use]
+pub struct ScanlineHook(Arc<HookFn>);
+
+impl ScanlineHook {
+    pub fn new(hook: impl Fn(u32, &mut ScanlinePalette) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(hook))
+    }
+
+    /// Run the hook for `scanline`.
+    pub fn call(&self, scanline: u32, palette: &mut ScanlinePalette) {
+        (self.0)(scanline, palette);
+    }
+}
+
+impl core::fmt::Debug for ScanlineHook {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ScanlineHook").finish_non_exhaustive()
+    }
+}
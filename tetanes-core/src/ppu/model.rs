@@ -0,0 +1,164 @@
+//! PPU model differences for Vs. System and PlayChoice-10 RGB PPUs.
+//!
+//! <https://www.nesdev.org/wiki/PPU_variants>
+
+use serde::{Deserialize, Serialize};
+
+/// PPU model, which changes the palette and register behavior of RGB PPUs used by arcade boards.
+///
+/// Vs. System games check for the PPU they shipped with, either by palette or by the
+/// `PPUSTATUS` ID of the `RC2C05`, as a form of copy protection.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[must_use]
+pub enum PpuModel {
+    /// Standard NES/Famicom PPU.
+    #[default]
+    Rp2c02,
+    /// RGB PPU with the standard palette.
+    Rp2c03,
+    /// RGB PPU with a scrambled palette.
+    Rp2c04_0001,
+    Rp2c04_0002,
+    Rp2c04_0003,
+    Rp2c04_0004,
+    /// RGB PPU with `PPUCTRL` and `PPUMASK` swapped, returning an ID in the lower bits of
+    /// `PPUSTATUS`.
+    Rc2c05_01,
+    Rc2c05_02,
+    Rc2c05_03,
+    Rc2c05_04,
+    Rc2c05_05,
+}
+
+impl PpuModel {
+    /// Mapping from `RP2C04-0001` palette indexes to standard palette indexes.
+    #[rustfmt::skip]
+    const RP2C04_0001_PALETTE: [u8; 64] = [
+        0x35, 0x23, 0x16, 0x22, 0x1C, 0x09, 0x1D, 0x15,
+        0x20, 0x00, 0x27, 0x05, 0x04, 0x28, 0x08, 0x20,
+        0x21, 0x3E, 0x1F, 0x29, 0x3C, 0x32, 0x36, 0x12,
+        0x3F, 0x2B, 0x2E, 0x1E, 0x3D, 0x2D, 0x24, 0x01,
+        0x0E, 0x31, 0x33, 0x2A, 0x2C, 0x0C, 0x1B, 0x14,
+        0x2E, 0x07, 0x34, 0x06, 0x13, 0x02, 0x26, 0x2E,
+        0x2E, 0x19, 0x10, 0x0A, 0x39, 0x03, 0x37, 0x17,
+        0x0F, 0x11, 0x0B, 0x0D, 0x38, 0x25, 0x18, 0x3A,
+    ];
+    /// Mapping from `RP2C04-0002` palette indexes to standard palette indexes.
+    #[rustfmt::skip]
+    const RP2C04_0002_PALETTE: [u8; 64] = [
+        0x2E, 0x27, 0x18, 0x39, 0x3A, 0x25, 0x1C, 0x31,
+        0x16, 0x13, 0x38, 0x34, 0x20, 0x23, 0x3C, 0x0B,
+        0x0F, 0x21, 0x06, 0x3D, 0x1B, 0x29, 0x1E, 0x22,
+        0x1D, 0x24, 0x0E, 0x2B, 0x32, 0x08, 0x2E, 0x03,
+        0x04, 0x36, 0x26, 0x33, 0x11, 0x1F, 0x10, 0x02,
+        0x14, 0x3F, 0x00, 0x09, 0x12, 0x2E, 0x28, 0x20,
+        0x3E, 0x0D, 0x2A, 0x17, 0x0C, 0x01, 0x15, 0x19,
+        0x2E, 0x2C, 0x07, 0x37, 0x35, 0x05, 0x0A, 0x2D,
+    ];
+    /// Mapping from `RP2C04-0003` palette indexes to standard palette indexes.
+    #[rustfmt::skip]
+    const RP2C04_0003_PALETTE: [u8; 64] = [
+        0x14, 0x25, 0x3A, 0x10, 0x0B, 0x20, 0x31, 0x09,
+        0x01, 0x2E, 0x36, 0x08, 0x15, 0x3D, 0x3E, 0x3C,
+        0x22, 0x1C, 0x05, 0x12, 0x19, 0x18, 0x17, 0x1B,
+        0x00, 0x03, 0x2E, 0x02, 0x16, 0x06, 0x34, 0x35,
+        0x23, 0x0F, 0x0E, 0x37, 0x0D, 0x27, 0x26, 0x20,
+        0x29, 0x04, 0x21, 0x24, 0x11, 0x2D, 0x2E, 0x1F,
+        0x2C, 0x1E, 0x39, 0x33, 0x07, 0x2A, 0x28, 0x1D,
+        0x0A, 0x2E, 0x32, 0x38, 0x13, 0x2B, 0x3F, 0x0C,
+    ];
+    /// Mapping from `RP2C04-0004` palette indexes to standard palette indexes.
+    #[rustfmt::skip]
+    const RP2C04_0004_PALETTE: [u8; 64] = [
+        0x18, 0x03, 0x1C, 0x28, 0x2E, 0x35, 0x01, 0x17,
+        0x10, 0x1F, 0x2A, 0x0E, 0x36, 0x37, 0x0B, 0x39,
+        0x25, 0x1E, 0x12, 0x34, 0x2E, 0x1D, 0x06, 0x26,
+        0x3E, 0x1B, 0x22, 0x19, 0x04, 0x2E, 0x3A, 0x21,
+        0x05, 0x0A, 0x07, 0x02, 0x13, 0x14, 0x00, 0x15,
+        0x0C, 0x3D, 0x11, 0x0F, 0x0D, 0x38, 0x2D, 0x24,
+        0x33, 0x20, 0x08, 0x16, 0x3F, 0x2B, 0x20, 0x3C,
+        0x2E, 0x27, 0x23, 0x31, 0x29, 0x32, 0x2C, 0x09,
+    ];
+
+    /// Returns the PPU model for the NES 2.0 Vs. System PPU type in the lower 4 bits of header
+    /// byte 13.
+    pub const fn from_vs_ppu_type(ppu_type: u8) -> Self {
+        match ppu_type & 0x0F {
+            0x02 => Self::Rp2c04_0001,
+            0x03 => Self::Rp2c04_0002,
+            0x04 => Self::Rp2c04_0003,
+            0x05 => Self::Rp2c04_0004,
+            0x08 => Self::Rc2c05_01,
+            0x09 => Self::Rc2c05_02,
+            0x0A => Self::Rc2c05_03,
+            0x0B => Self::Rc2c05_04,
+            0x0C => Self::Rc2c05_05,
+            // RP2C03B, RP2C03G, RC2C03B and RC2C03C all use the standard palette
+            _ => Self::Rp2c03,
+        }
+    }
+
+    /// Returns the mapping from palette indexes to standard palette indexes, if this PPU has a
+    /// scrambled palette.
+    #[must_use]
+    pub const fn palette_lut(&self) -> Option<&'static [u8; 64]> {
+        match self {
+            Self::Rp2c04_0001 => Some(&Self::RP2C04_0001_PALETTE),
+            Self::Rp2c04_0002 => Some(&Self::RP2C04_0002_PALETTE),
+            Self::Rp2c04_0003 => Some(&Self::RP2C04_0003_PALETTE),
+            Self::Rp2c04_0004 => Some(&Self::RP2C04_0004_PALETTE),
+            _ => None,
+        }
+    }
+
+    /// Whether `PPUCTRL` and `PPUMASK` are swapped, so `$2000` writes `PPUMASK` and `$2001`
+    /// writes `PPUCTRL`.
+    #[must_use]
+    pub const fn swaps_ctrl_mask(&self) -> bool {
+        matches!(
+            self,
+            Self::Rc2c05_01 | Self::Rc2c05_02 | Self::Rc2c05_03 | Self::Rc2c05_04 | Self::Rc2c05_05
+        )
+    }
+
+    /// Returns the ID read from the lower 6 bits of `PPUSTATUS` in place of sprite overflow and
+    /// open bus, if any.
+    #[must_use]
+    pub const fn status_id(&self) -> Option<u8> {
+        match self {
+            Self::Rc2c05_01 | Self::Rc2c05_04 => Some(0x1B),
+            Self::Rc2c05_02 => Some(0x3D),
+            Self::Rc2c05_03 => Some(0x1C),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Rp2c02 => "RP2C02",
+            Self::Rp2c03 => "RP2C03",
+            Self::Rp2c04_0001 => "RP2C04-0001",
+            Self::Rp2c04_0002 => "RP2C04-0002",
+            Self::Rp2c04_0003 => "RP2C04-0003",
+            Self::Rp2c04_0004 => "RP2C04-0004",
+            Self::Rc2c05_01 => "RC2C05-01",
+            Self::Rc2c05_02 => "RC2C05-02",
+            Self::Rc2c05_03 => "RC2C05-03",
+            Self::Rc2c05_04 => "RC2C05-04",
+            Self::Rc2c05_05 => "RC2C05-05",
+        }
+    }
+}
+
+impl AsRef<str> for PpuModel {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl core::fmt::Display for PpuModel {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
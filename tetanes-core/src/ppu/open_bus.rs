@@ -0,0 +1,96 @@
+//! PPU I/O latch implementation.
+//!
+//! See: <https://www.nesdev.org/wiki/Open_bus_behavior#PPU_open_bus>
+
+use crate::fs;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// PPU I/O latch, also known as the decay register, returned for bits not driven when reading PPU
+/// registers.
+///
+/// Writing any PPU register refreshes every bit with the value written, while reads only refresh
+/// the bits driven by that register. Bits that aren't refreshed with a `1` decay to `0` after about
+/// 600ms.
+///
+/// See: <https://www.nesdev.org/wiki/Open_bus_behavior#PPU_open_bus>
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub struct OpenBus {
+    value: u8,
+    /// Frame number each bit was last refreshed with a `1`.
+    refreshed: [u32; 8],
+}
+
+impl OpenBus {
+    /// Number of frames before a bit decays to `0`, roughly 600ms.
+    pub const DECAY_FRAMES: u32 = 36;
+
+    pub const fn new() -> Self {
+        Self {
+            value: 0x00,
+            refreshed: [0; 8],
+        }
+    }
+
+    /// Deserialize the latch, migrating the plain value saved before save format `2`. Bits
+    /// restored from an older save decay as if they were last refreshed at power-up.
+    ///
+    /// # Errors
+    ///
+    /// If the latch fails to deserialize, then an error is returned.
+    pub fn deserialize_versioned<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        fs::changed_in::<2, _, _, _>(deserializer, |value: u8| Self {
+            value,
+            refreshed: [0; 8],
+        })
+    }
+
+    /// Current value of the latch.
+    #[must_use]
+    pub const fn value(&self) -> u8 {
+        self.value
+    }
+
+    /// Refresh the bits set in `mask` with `val` during `frame`.
+    pub fn refresh(&mut self, mask: u8, val: u8, frame: u32) {
+        self.value = (self.value & !mask) | (val & mask);
+        for (bit, refreshed) in self.refreshed.iter_mut().enumerate() {
+            if (mask & val) & (1 << bit) != 0 {
+                *refreshed = frame;
+            }
+        }
+    }
+
+    /// Decay any bits that haven't been refreshed with a `1` within [`OpenBus::DECAY_FRAMES`] of
+    /// `frame`.
+    pub fn decay(&mut self, frame: u32) {
+        for (bit, refreshed) in self.refreshed.iter().enumerate() {
+            if frame.wrapping_sub(*refreshed) >= Self::DECAY_FRAMES {
+                self.value &= !(1 << bit);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decay() {
+        let mut open_bus = OpenBus::new();
+        open_bus.refresh(0xFF, 0xFF, 10);
+        open_bus.refresh(0xE0, 0x20, 20);
+        assert_eq!(open_bus.value(), 0x3F, "only masked bits refresh");
+
+        open_bus.decay(10 + OpenBus::DECAY_FRAMES - 1);
+        assert_eq!(open_bus.value(), 0x3F);
+        open_bus.decay(10 + OpenBus::DECAY_FRAMES);
+        assert_eq!(open_bus.value(), 0x20, "stale bits decay");
+        open_bus.decay(20 + OpenBus::DECAY_FRAMES);
+        assert_eq!(open_bus.value(), 0x00);
+    }
+}
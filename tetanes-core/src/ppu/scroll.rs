@@ -227,6 +227,25 @@ impl Scroll {
         }
     }
 
+    /// Returns the top-left `(x, y)` pixel position of the scroll origin within the four
+    /// logical nametables (512x480), based on the temporary VRAM address and fine X.
+    #[must_use]
+    pub const fn origin(&self) -> (u16, u16) {
+        let nt_x = if self.t & Self::NT_X_MASK == Self::NT_X_MASK {
+            256
+        } else {
+            0
+        };
+        let nt_y = if self.t & Self::NT_Y_MASK == Self::NT_Y_MASK {
+            240
+        } else {
+            0
+        };
+        let x = nt_x + (self.t & Self::COARSE_X_MASK) * 8 + self.fine_x;
+        let y = nt_y + ((self.t & Self::COARSE_Y_MASK) >> 5) * 8 + (self.t >> 12);
+        (x, y)
+    }
+
     pub fn reset_latch(&mut self) {
         self.write_latch = false;
     }
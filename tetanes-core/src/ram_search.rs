@@ -0,0 +1,172 @@
+//! RAM search for finding where a game stores values like lives, health or timers.
+//!
+//! A [`RamSearch`] starts with every byte of CPU RAM as a candidate. Each [`SearchFilter`] applied
+//! compares a new [`RamSnapshot`] against the previous one, narrowing down the candidates as the
+//! value changes in game, e.g. filtering for values that decreased by 1 after losing a life.
+
+use crate::playlist::Comparison;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// A copy of CPU RAM, taken between frames.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct RamSnapshot {
+    /// Frame number the snapshot was taken after.
+    pub frame: u32,
+    /// Work RAM at `$0000-$07FF`.
+    wram: Vec<u8>,
+    /// PRG-RAM mapped at `$6000-$7FFF`. Empty if the cartridge has no PRG-RAM.
+    prg_ram: Vec<u8>,
+}
+
+impl RamSnapshot {
+    pub const PRG_RAM_START: u16 = 0x6000;
+
+    pub const fn new(frame: u32, wram: Vec<u8>, prg_ram: Vec<u8>) -> Self {
+        Self {
+            frame,
+            wram,
+            prg_ram,
+        }
+    }
+
+    /// Returns the value at CPU address `addr`, if it's part of the snapshot.
+    #[must_use]
+    pub fn value(&self, addr: u16) -> Option<u8> {
+        if addr >= Self::PRG_RAM_START {
+            self.prg_ram
+                .get(usize::from(addr - Self::PRG_RAM_START))
+                .copied()
+        } else {
+            self.wram.get(usize::from(addr)).copied()
+        }
+    }
+
+    /// Returns the CPU addresses included in the snapshot.
+    pub fn addrs(&self) -> impl Iterator<Item = u16> {
+        let wram = 0..self.wram.len() as u16;
+        let prg_ram = Self::PRG_RAM_START..Self::PRG_RAM_START + self.prg_ram.len() as u16;
+        wram.chain(prg_ram)
+    }
+}
+
+/// How candidates are narrowed down by a [`RamSearch`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub enum SearchFilter {
+    /// Compare each value with its previous value, e.g. [`Comparison::Greater`] for values that
+    /// increased.
+    Previous(Comparison),
+    /// Compare each value with a specific value.
+    Value(Comparison, u8),
+    /// Values that changed by exactly this amount since the previous snapshot, wrapping around.
+    ChangedBy(i16),
+}
+
+impl Default for SearchFilter {
+    fn default() -> Self {
+        Self::Previous(Comparison::NotEqual)
+    }
+}
+
+impl SearchFilter {
+    /// Whether a value changing from `previous` to `current` matches the filter.
+    #[must_use]
+    pub const fn matches(&self, previous: u8, current: u8) -> bool {
+        match self {
+            Self::Previous(comparison) => comparison.compare(current, previous),
+            Self::Value(comparison, value) => comparison.compare(current, *value),
+            Self::ChangedBy(delta) => current.wrapping_sub(previous) == *delta as u8,
+        }
+    }
+}
+
+/// A RAM search narrowing down candidate addresses over a series of snapshots.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct RamSearch {
+    /// Addresses matching every filter applied so far.
+    candidates: Vec<u16>,
+    /// Snapshot the next filter is compared against.
+    previous: RamSnapshot,
+}
+
+impl RamSearch {
+    /// Start a new search with every address in `snapshot` as a candidate.
+    pub fn new(snapshot: RamSnapshot) -> Self {
+        Self {
+            candidates: snapshot.addrs().collect(),
+            previous: snapshot,
+        }
+    }
+
+    /// Returns the addresses matching every filter applied so far.
+    #[must_use]
+    pub fn candidates(&self) -> &[u16] {
+        &self.candidates
+    }
+
+    /// Returns the snapshot the next filter is compared against.
+    pub const fn previous(&self) -> &RamSnapshot {
+        &self.previous
+    }
+
+    /// Keep only the candidates whose value in `snapshot` matches `filter` when compared against
+    /// the previous snapshot, then make `snapshot` the new previous snapshot.
+    pub fn filter(&mut self, snapshot: RamSnapshot, filter: SearchFilter) {
+        let previous = &self.previous;
+        self.candidates
+            .retain(|&addr| match (previous.value(addr), snapshot.value(addr)) {
+                (Some(previous), Some(current)) => filter.matches(previous, current),
+                _ => false,
+            });
+        self.previous = snapshot;
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn narrow_candidates() {
+        let snapshot = |frame, lives: u8, timer: u8| {
+            let mut wram = vec![0x00; 0x0800];
+            wram[0x0075] = lives;
+            wram[0x0100] = lives;
+            let mut prg_ram = vec![0x00; 0x2000];
+            prg_ram[0x0010] = timer;
+            RamSnapshot::new(frame, wram, prg_ram)
+        };
+
+        let mut search = RamSearch::new(snapshot(0, 3, 0x00));
+        assert_eq!(search.candidates().len(), 0x0800 + 0x2000);
+
+        search.filter(snapshot(1, 2, 0xFF), SearchFilter::ChangedBy(-1));
+        assert_eq!(search.candidates(), [0x0075, 0x0100, 0x6010]);
+
+        search.filter(
+            snapshot(2, 2, 0xFE),
+            SearchFilter::Previous(Comparison::Equal),
+        );
+        assert_eq!(search.candidates(), [0x0075, 0x0100]);
+
+        search.filter(
+            snapshot(3, 9, 0xFD),
+            SearchFilter::Value(Comparison::Greater, 8),
+        );
+        assert_eq!(search.candidates(), [0x0075, 0x0100]);
+        assert_eq!(search.previous().frame, 3);
+        assert_eq!(search.previous().value(0x6010), Some(0xFD));
+
+        let mut search = RamSearch::new(RamSnapshot::new(0, vec![0x00; 0x0800], vec![]));
+        assert_eq!(search.previous().value(0x6000), None);
+        search.filter(
+            RamSnapshot::new(1, vec![0x00; 0x0800], vec![]),
+            SearchFilter::Previous(Comparison::Equal),
+        );
+        assert_eq!(search.candidates().len(), 0x0800);
+    }
+}
@@ -0,0 +1,294 @@
+//! ROM database used to detect the region, compatibility patches and header fixes of loaded
+//! games.
+//!
+//! A database is bundled with each release. Frontends can replace it at runtime with a newer
+//! version, e.g. one downloaded from an online source and cached locally, so new homebrew and
+//! corrected hashes don't require a new release.
+
+use crate::{
+    cart::{self, GameRegion, HeaderFix},
+    common::NesRegion,
+    fs,
+    patch::{self, CompatPatch},
+};
+use alloc::{string::String, sync::Arc, vec::Vec};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use spin::RwLock;
+use tracing::{error, info};
+
+lazy_static! {
+    static ref ACTIVE: RwLock<Arc<RomDatabase>> = RwLock::new(Arc::new(RomDatabase::bundled()));
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Snafu, Debug)]
+#[must_use]
+pub enum Error {
+    #[snafu(display("invalid rom database: {source:?}"))]
+    InvalidFormat { source: fs::Error },
+    #[snafu(display("invalid rom database: {source}"))]
+    InvalidPatches { source: patch::Error },
+    #[snafu(display("invalid rom database: {source}"))]
+    InvalidHeaderFixes { source: cart::Error },
+}
+
+/// Serialized form of a [`RomDatabase`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RomDatabaseFile {
+    version: u32,
+    game_regions: Vec<GameRegion>,
+    compat_patches: String,
+    header_fixes: String,
+}
+
+/// Serialized form of a [`RomDatabase`] published before header fixes were added.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RomDatabaseFileV1 {
+    version: u32,
+    game_regions: Vec<GameRegion>,
+    compat_patches: String,
+}
+
+impl From<RomDatabaseFileV1> for RomDatabaseFile {
+    fn from(file: RomDatabaseFileV1) -> Self {
+        Self {
+            version: file.version,
+            game_regions: file.game_regions,
+            compat_patches: file.compat_patches,
+            header_fixes: String::new(),
+        }
+    }
+}
+
+/// A versioned database of known games.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct RomDatabase {
+    version: u32,
+    /// Sorted by CRC32.
+    game_regions: Vec<GameRegion>,
+    compat_patches: Vec<CompatPatch>,
+    compat_patches_source: String,
+    header_fixes: Vec<HeaderFix>,
+    header_fixes_source: String,
+}
+
+impl RomDatabase {
+    /// Version of the database bundled with this release.
+    pub const BUNDLED_VERSION: u32 = 1;
+
+    /// Create a database from `game_regions`, a compatibility patch database in the format parsed
+    /// by [`CompatPatch::parse_database`] and a header fix database in the format parsed by
+    /// [`HeaderFix::parse_database`].
+    ///
+    /// # Errors
+    ///
+    /// If the compatibility patches or header fixes fail to parse, then an error is returned.
+    pub fn new(
+        version: u32,
+        mut game_regions: Vec<GameRegion>,
+        compat_patches: impl Into<String>,
+        header_fixes: impl Into<String>,
+    ) -> Result<Self> {
+        let compat_patches_source = compat_patches.into();
+        let compat_patches =
+            CompatPatch::parse_database(&compat_patches_source).context(InvalidPatchesSnafu)?;
+        let header_fixes_source = header_fixes.into();
+        let header_fixes =
+            HeaderFix::parse_database(&header_fixes_source).context(InvalidHeaderFixesSnafu)?;
+        game_regions.sort_by_key(|game| game.crc32);
+        Ok(Self {
+            version,
+            game_regions,
+            compat_patches,
+            compat_patches_source,
+            header_fixes,
+            header_fixes_source,
+        })
+    }
+
+    /// The database bundled with this release.
+    pub fn bundled() -> Self {
+        const GAME_REGIONS: &[u8] = include_bytes!("../game_regions.dat");
+        const COMPAT_PATCHES: &str = include_str!("../compat_patches.txt");
+
+        let game_regions = fs::load_bytes::<Vec<GameRegion>>(GAME_REGIONS).unwrap_or_else(|err| {
+            error!("failed to load `game_regions.dat`: {err:?}");
+            Vec::new()
+        });
+        // No header fixes are bundled since there's no verified list of bad dumps to build them
+        // from. They can still be delivered by a database update.
+        Self::new(Self::BUNDLED_VERSION, game_regions, COMPAT_PATCHES, "").unwrap_or_else(|err| {
+            error!("failed to load bundled rom database: {err}");
+            Self {
+                version: Self::BUNDLED_VERSION,
+                game_regions: Vec::new(),
+                compat_patches: Vec::new(),
+                compat_patches_source: String::new(),
+                header_fixes: Vec::new(),
+                header_fixes_source: String::new(),
+            }
+        })
+    }
+
+    /// Load a database serialized with [`RomDatabase::to_bytes`].
+    ///
+    /// # Errors
+    ///
+    /// If the data is not a valid database, then an error is returned.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let file = fs::load_bytes::<RomDatabaseFile>(bytes)
+            .or_else(|err| {
+                fs::load_bytes::<RomDatabaseFileV1>(bytes)
+                    .map(RomDatabaseFile::from)
+                    .map_err(|_| err)
+            })
+            .context(InvalidFormatSnafu)?;
+        Self::new(
+            file.version,
+            file.game_regions,
+            file.compat_patches,
+            file.header_fixes,
+        )
+    }
+
+    /// Serialize the database, e.g. to publish or cache it.
+    ///
+    /// # Errors
+    ///
+    /// If the database fails to serialize, then an error is returned.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        fs::save_bytes(&RomDatabaseFile {
+            version: self.version,
+            game_regions: self.game_regions.clone(),
+            compat_patches: self.compat_patches_source.clone(),
+            header_fixes: self.header_fixes_source.clone(),
+        })
+        .context(InvalidFormatSnafu)
+    }
+
+    /// Database version, which increases with each published update.
+    #[must_use]
+    pub const fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns the region of a game matching `crc32`, if any.
+    #[must_use]
+    pub fn lookup_region(&self, crc32: u32) -> Option<NesRegion> {
+        self.game_regions
+            .binary_search_by_key(&crc32, |game| game.crc32)
+            .ok()
+            .map(|index| self.game_regions[index].region)
+    }
+
+    /// Returns the compatibility patch for a game matching `crc32`, if any.
+    pub fn lookup_compat_patch(&self, crc32: u32) -> Option<&CompatPatch> {
+        self.compat_patches
+            .iter()
+            .find(|patch| patch.crc32 == crc32)
+    }
+
+    /// Returns the header fix for ROM data matching `crc32`, if any.
+    pub fn lookup_header_fix(&self, crc32: u32) -> Option<&HeaderFix> {
+        self.header_fixes.iter().find(|fix| fix.crc32 == crc32)
+    }
+}
+
+/// Returns the active database used when loading ROMs.
+#[must_use]
+pub fn active() -> Arc<RomDatabase> {
+    Arc::clone(&ACTIVE.read())
+}
+
+/// Replace the active database used when loading ROMs. Takes effect the next time a ROM is
+/// loaded.
+pub fn install(database: RomDatabase) {
+    info!("installed rom database version {}", database.version);
+    *ACTIVE.write() = Arc::new(database);
+}
+
+/// Restore the database bundled with this release.
+pub fn reset() {
+    *ACTIVE.write() = Arc::new(RomDatabase::bundled());
+}
+
+/// Serializes tests that [`install`] a database, since the active database is shared between
+/// threads.
+#[cfg(all(test, feature = "std"))]
+pub(crate) fn test_lock() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    LOCK.lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn rom_database_round_trip() {
+        assert_eq!(
+            RomDatabase::bundled().version(),
+            RomDatabase::BUNDLED_VERSION
+        );
+
+        let database = RomDatabase::new(
+            2,
+            vec![
+                GameRegion {
+                    crc32: 0x2222,
+                    region: NesRegion::Pal,
+                },
+                GameRegion {
+                    crc32: 0x1111,
+                    region: NesRegion::Dendy,
+                },
+            ],
+            "# Comment\n1111, PAL, \"Game\", \"Skip region check\", 10:EAEA\n",
+            "3333, \"Bad Dump\", mapper=4 mirroring=vertical\n",
+        )
+        .expect("valid database");
+        let database = RomDatabase::from_bytes(&database.to_bytes().expect("serialized"))
+            .expect("valid database");
+        assert_eq!(database.version(), 2);
+        assert_eq!(database.lookup_region(0x1111), Some(NesRegion::Dendy));
+        assert_eq!(database.lookup_region(0x2222), Some(NesRegion::Pal));
+        assert_eq!(database.lookup_region(0x3333), None);
+        assert_eq!(
+            database
+                .lookup_compat_patch(0x1111)
+                .map(|patch| &*patch.title),
+            Some("Game")
+        );
+
+        assert_eq!(
+            database
+                .lookup_header_fix(0x3333)
+                .and_then(|fix| fix.overrides.mapper_num),
+            Some(4)
+        );
+
+        let legacy = fs::save_bytes(&RomDatabaseFileV1 {
+            version: 1,
+            game_regions: vec![],
+            compat_patches: String::new(),
+        })
+        .expect("serialized");
+        assert_eq!(
+            RomDatabase::from_bytes(&legacy)
+                .ok()
+                .map(|database| database.version()),
+            Some(1),
+            "databases without header fixes still load"
+        );
+
+        assert!(RomDatabase::from_bytes(b"not a database").is_err());
+        assert!(RomDatabase::new(3, Vec::new(), "1111, PAL", "").is_err());
+        assert!(RomDatabase::new(3, Vec::new(), "", "1111, \"Game\"").is_err());
+    }
+}
@@ -0,0 +1,663 @@
+//! In-memory snapshots of emulator state.
+//!
+//! A [`Snapshot`] copies state without serializing, reusing its buffers between snapshots, for
+//! run-ahead which takes a snapshot every frame and restores it shortly after.
+//!
+//! A [`SnapshotRing`] keeps a history of serialized snapshots for rewind. Snapshots are stored as
+//! a full keyframe every `keyframe_interval` entries with the entries in between stored as a
+//! compact delta against the most recent keyframe. Keyframes are run-length encoded and deltas
+//! are the run-length encoded XOR against their keyframe, so a typical rewind buffer takes roughly
+//! a tenth of the memory of storing every snapshot in full.
+
+use crate::{
+    apu::{mixer::Mixer, Apu},
+    cdl::Cdl,
+    cpu::Cpu,
+    fs::{Error, Result},
+    mapper::MapperWrite,
+    ppu::{frame::Buffer, hook::ScanlineHook, Ppu},
+};
+use alloc::{collections::VecDeque, string::ToString, vec::Vec};
+use bincode::serde::{BorrowCompat, Compat};
+use core::mem;
+use hashbrown::HashMap;
+
+/// A copy of emulator state taken with [`ControlDeck::snapshot`] and restored with
+/// [`ControlDeck::restore`].
+///
+/// Unlike save states, snapshots aren't serialized. Memory is copied into buffers allocated by
+/// the first snapshot taken, so reuse a `Snapshot` instead of creating a new one each frame. ROM
+/// data isn't copied, so a snapshot can only be restored with the same ROM loaded.
+///
+/// [`ControlDeck::snapshot`]: crate::control_deck::ControlDeck::snapshot
+/// [`ControlDeck::restore`]: crate::control_deck::ControlDeck::restore
+#[derive(Default, Debug, Clone)]
+#[must_use]
+pub struct Snapshot {
+    /// State without its [`Buffers`], which are stored separately so they're copied in place.
+    cpu: Option<Cpu>,
+    buffers: Buffers,
+}
+
+impl Snapshot {
+    /// Create an empty `Snapshot`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a snapshot has been taken.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.cpu.is_none()
+    }
+
+    /// Frame number the snapshot was taken on, if one has been taken.
+    #[must_use]
+    pub fn frame_number(&self) -> Option<u32> {
+        self.cpu.as_ref().map(|cpu| cpu.bus.ppu.frame_number())
+    }
+
+    /// Discard the snapshot, keeping its buffers allocated for the next one.
+    pub fn clear(&mut self) {
+        self.cpu = None;
+    }
+
+    /// Copy the state of `cpu` into this snapshot. `cpu` is only borrowed mutably so its ROM data
+    /// and buffers can be moved out while it's cloned, and is left unchanged.
+    pub(crate) fn take(&mut self, cpu: &mut Cpu) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        let kept = Kept::take(cpu);
+        let buffers = Buffers::take(cpu);
+        self.cpu = Some(cpu.clone());
+        self.buffers.clone_from(&buffers);
+        buffers.put(cpu);
+        kept.put(cpu);
+    }
+
+    /// Restore the state of `cpu` from this snapshot, keeping its own ROM data, captures and
+    /// output samples. Returns `false` if the snapshot is empty.
+    pub(crate) fn restore(&self, cpu: &mut Cpu) -> bool {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        let Some(state) = &self.cpu else {
+            return false;
+        };
+        let kept = Kept::take(cpu);
+        let mut buffers = Buffers::take(cpu);
+        *cpu = state.clone();
+        buffers.clone_from(&self.buffers);
+        buffers.put(cpu);
+        kept.put(cpu);
+        true
+    }
+}
+
+/// Memory stored separately from a [`Snapshot`]'s state, so it's copied into existing
+/// allocations instead of being reallocated every snapshot.
+#[derive(Debug)]
+struct Buffers {
+    wram: Vec<u8>,
+    prg_ram: Vec<u8>,
+    chr_ram: Vec<u8>,
+    ciram: Vec<u8>,
+    exram: Vec<u8>,
+    oamdata: Vec<u8>,
+    spr_present: Vec<bool>,
+    frame_buffer: Buffer,
+}
+
+impl Default for Buffers {
+    fn default() -> Self {
+        Self {
+            wram: Vec::new(),
+            prg_ram: Vec::new(),
+            chr_ram: Vec::new(),
+            ciram: Vec::new(),
+            exram: Vec::new(),
+            oamdata: Vec::new(),
+            spr_present: Vec::new(),
+            frame_buffer: Buffer::empty(),
+        }
+    }
+}
+
+impl Clone for Buffers {
+    fn clone(&self) -> Self {
+        let mut buffers = Self::default();
+        buffers.clone_from(self);
+        buffers
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.wram.clone_from(&source.wram);
+        self.prg_ram.clone_from(&source.prg_ram);
+        self.chr_ram.clone_from(&source.chr_ram);
+        self.ciram.clone_from(&source.ciram);
+        self.exram.clone_from(&source.exram);
+        self.oamdata.clone_from(&source.oamdata);
+        self.spr_present.clone_from(&source.spr_present);
+        self.frame_buffer.clone_from(&source.frame_buffer);
+    }
+}
+
+impl Buffers {
+    fn take(cpu: &mut Cpu) -> Self {
+        let bus = &mut cpu.bus;
+        let ppu = &mut bus.ppu;
+        Self {
+            wram: mem::take(&mut bus.wram),
+            prg_ram: mem::take(&mut bus.prg_ram),
+            chr_ram: mem::take(&mut ppu.bus.chr_ram),
+            ciram: mem::take(&mut ppu.bus.ciram),
+            exram: mem::take(&mut ppu.bus.exram),
+            oamdata: mem::take(&mut ppu.oamdata),
+            spr_present: mem::take(&mut ppu.spr_present),
+            frame_buffer: mem::replace(&mut ppu.frame.buffer, Buffer::empty()),
+        }
+    }
+
+    fn put(self, cpu: &mut Cpu) {
+        let bus = &mut cpu.bus;
+        let ppu = &mut bus.ppu;
+        bus.wram = self.wram;
+        bus.prg_ram = self.prg_ram;
+        ppu.bus.chr_ram = self.chr_ram;
+        ppu.bus.ciram = self.ciram;
+        ppu.bus.exram = self.exram;
+        ppu.oamdata = self.oamdata;
+        ppu.spr_present = self.spr_present;
+        ppu.frame.buffer = self.frame_buffer;
+    }
+}
+
+/// Data that isn't emulation state, so it's never copied into a [`Snapshot`] and a [`Cpu`] keeps
+/// its own when a snapshot is restored, like [`Cpu::load`] does for save states.
+struct Kept {
+    prg_rom: Vec<u8>,
+    chr_rom: Vec<u8>,
+    audio_samples: Vec<f32>,
+    record_samples: Option<Vec<f32>>,
+    channel_samples: Option<Vec<[f32; Apu::MAX_CHANNEL_COUNT]>>,
+    mixer: Mixer,
+    cdl: Option<Cdl>,
+    mapper_writes: Option<VecDeque<MapperWrite>>,
+    ram_cheats: HashMap<u16, u8>,
+    scanline_hook: Option<ScanlineHook>,
+}
+
+impl Kept {
+    fn take(cpu: &mut Cpu) -> Self {
+        let bus = &mut cpu.bus;
+        Self {
+            prg_rom: mem::take(&mut bus.prg_rom),
+            chr_rom: mem::take(&mut bus.ppu.bus.chr_rom),
+            audio_samples: mem::take(&mut bus.apu.audio_samples),
+            record_samples: bus.apu.record_samples.take(),
+            channel_samples: bus.apu.channel_samples.take(),
+            mixer: bus.apu.mixer,
+            cdl: bus.cdl.take(),
+            mapper_writes: bus.mapper_writes.take(),
+            ram_cheats: mem::take(&mut bus.ram_cheats),
+            scanline_hook: bus.ppu.scanline_hook.take(),
+        }
+    }
+
+    fn put(self, cpu: &mut Cpu) {
+        let bus = &mut cpu.bus;
+        bus.prg_rom = self.prg_rom;
+        bus.ppu.bus.chr_rom = self.chr_rom;
+        bus.apu.audio_samples = self.audio_samples;
+        bus.apu.record_samples = self.record_samples;
+        bus.apu.channel_samples = self.channel_samples;
+        bus.apu.mixer = self.mixer;
+        bus.cdl = self.cdl;
+        bus.mapper_writes = self.mapper_writes;
+        bus.ram_cheats = self.ram_cheats;
+        bus.ppu.scanline_hook = self.scanline_hook;
+    }
+}
+
+/// Serialized [`Cpu`] state, either stored in full or as a delta against the nearest preceding
+/// keyframe. Both are encoded with [`encode_delta`], keyframes against an empty base.
+#[derive(Debug, Clone)]
+enum Stored {
+    Keyframe(Vec<u8>),
+    Delta(Vec<u8>),
+}
+
+#[derive(Debug, Clone)]
+struct Entry<T> {
+    meta: T,
+    snapshot: Stored,
+}
+
+/// Bounded ring of [`Cpu`] snapshots, oldest first. Each snapshot carries caller-provided
+/// metadata of type `T`.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct SnapshotRing<T = ()> {
+    capacity: usize,
+    keyframe_interval: usize,
+    keyframe_counter: usize,
+    /// Decoded copy of the most recent keyframe, which new deltas are encoded against.
+    keyframe: Vec<u8>,
+    entries: VecDeque<Entry<T>>,
+}
+
+impl<T> Default for SnapshotRing<T> {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl<T> SnapshotRing<T> {
+    /// Default number of snapshots between each full keyframe.
+    pub const KEYFRAME_INTERVAL: usize = 30;
+
+    /// Create a new `SnapshotRing` holding at most `capacity` snapshots.
+    pub const fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            keyframe_interval: Self::KEYFRAME_INTERVAL,
+            keyframe_counter: 0,
+            keyframe: Vec::new(),
+            entries: VecDeque::new(),
+        }
+    }
+
+    /// Set the number of snapshots between each full keyframe. An interval of `1` stores every
+    /// snapshot in full.
+    pub const fn with_keyframe_interval(mut self, interval: usize) -> Self {
+        self.keyframe_interval = if interval == 0 { 1 } else { interval };
+        self
+    }
+
+    /// Maximum number of snapshots held.
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Set the maximum number of snapshots held, evicting the oldest if needed.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.entries.len() > self.capacity {
+            self.evict_oldest();
+        }
+    }
+
+    /// Number of snapshots held.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the ring holds no snapshots.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Total size in bytes of all stored snapshots.
+    #[must_use]
+    pub fn size_in_bytes(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|entry| match &entry.snapshot {
+                Stored::Keyframe(data) | Stored::Delta(data) => data.len(),
+            })
+            .sum()
+    }
+
+    /// Returns the metadata for each snapshot, oldest first.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        self.entries.iter().map(|entry| &entry.meta)
+    }
+
+    /// Returns the metadata for the snapshot at `index`.
+    #[must_use]
+    pub fn meta(&self, index: usize) -> Option<&T> {
+        self.entries.get(index).map(|entry| &entry.meta)
+    }
+
+    /// Remove all snapshots.
+    pub fn clear(&mut self) {
+        self.keyframe_counter = 0;
+        self.keyframe.clear();
+        self.entries.clear();
+    }
+
+    /// Keep the first `len` snapshots, discarding the rest.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.entries.len() {
+            self.entries.truncate(len);
+            // Force a keyframe, since the previous one may have been discarded
+            self.keyframe_counter = 0;
+        }
+    }
+
+    /// Snapshot `cpu` along with its frame buffer, evicting the oldest snapshot if full.
+    ///
+    /// # Errors
+    ///
+    /// If the CPU fails to serialize, an error is returned.
+    pub fn push(&mut self, cpu: &Cpu, meta: T) -> Result<()> {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        if self.capacity == 0 {
+            return Ok(());
+        }
+        // Evict before encoding, since eviction may promote a new keyframe
+        if self.entries.len() >= self.capacity {
+            self.evict_oldest();
+        }
+
+        let data = encode_cpu(cpu)?;
+        let snapshot = if self.keyframe_counter == 0 || self.entries.is_empty() {
+            let snapshot = Stored::Keyframe(encode_delta(&[], &data));
+            self.keyframe = data;
+            snapshot
+        } else {
+            Stored::Delta(encode_delta(&self.keyframe, &data))
+        };
+        self.keyframe_counter = (self.keyframe_counter + 1) % self.keyframe_interval;
+        self.entries.push_back(Entry { meta, snapshot });
+
+        Ok(())
+    }
+
+    /// Remove and decode the newest snapshot.
+    ///
+    /// # Errors
+    ///
+    /// If the snapshot fails to deserialize, an error is returned.
+    pub fn pop(&mut self) -> Option<Result<Cpu>> {
+        let index = self.entries.len().checked_sub(1)?;
+        let cpu = self.get(index);
+        self.entries.pop_back();
+        // The next push needs a fresh keyframe since the last one may have been popped
+        self.keyframe_counter = 0;
+        cpu
+    }
+
+    /// Decode the snapshot at `index` without removing it.
+    ///
+    /// # Errors
+    ///
+    /// If the snapshot fails to deserialize, an error is returned.
+    pub fn get(&self, index: usize) -> Option<Result<Cpu>> {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        let keyframe =
+            self.entries
+                .range(..=index)
+                .rev()
+                .find_map(|entry| match &entry.snapshot {
+                    Stored::Keyframe(data) => Some(data),
+                    Stored::Delta(_) => None,
+                })?;
+        let keyframe = decode_delta(&[], keyframe);
+        Some(match &self.entries[index].snapshot {
+            Stored::Keyframe(_) => decode_cpu(&keyframe),
+            Stored::Delta(delta) => decode_cpu(&decode_delta(&keyframe, delta)),
+        })
+    }
+
+    /// Remove the oldest snapshot. If it was a keyframe, the following snapshot is promoted to a
+    /// keyframe and any deltas depending on the evicted keyframe are re-encoded against it.
+    fn evict_oldest(&mut self) {
+        let Some(oldest) = self.entries.pop_front() else {
+            return;
+        };
+        let Stored::Keyframe(evicted) = oldest.snapshot else {
+            return;
+        };
+        let evicted = decode_delta(&[], &evicted);
+        let mut entries = self.entries.iter_mut();
+        let Some(front) = entries.next() else {
+            return;
+        };
+        let Stored::Delta(delta) = &front.snapshot else {
+            return;
+        };
+        let keyframe = decode_delta(&evicted, delta);
+        let mut has_later_keyframe = false;
+        for entry in entries {
+            match &mut entry.snapshot {
+                Stored::Keyframe(_) => {
+                    has_later_keyframe = true;
+                    break;
+                }
+                Stored::Delta(delta) => {
+                    *delta = encode_delta(&keyframe, &decode_delta(&evicted, delta));
+                }
+            }
+        }
+        front.snapshot = Stored::Keyframe(encode_delta(&[], &keyframe));
+        if !has_later_keyframe {
+            self.keyframe = keyframe;
+        }
+    }
+}
+
+/// Serialize `cpu` along with its frame buffer, which isn't otherwise serialized.
+///
+/// # Errors
+///
+/// If the CPU fails to serialize, an error is returned.
+pub fn encode_cpu(cpu: &Cpu) -> Result<Vec<u8>> {
+    let mut data =
+        bincode::encode_to_vec(BorrowCompat(cpu), bincode::config::standard()).map_err(|err| {
+            Error::SerializationFailed {
+                inner: err.to_string(),
+            }
+        })?;
+    // Store the low and high bytes of each pixel as separate planes, so changed pixels form
+    // contiguous runs and the mostly unchanging high bytes compress away
+    data.reserve(Ppu::SIZE * 2);
+    let buffer = &cpu.bus.ppu.frame.buffer;
+    data.extend(buffer.iter().map(|pixel| pixel.to_le_bytes()[0]));
+    data.extend(buffer.iter().map(|pixel| pixel.to_le_bytes()[1]));
+    Ok(data)
+}
+
+/// Deserialize a [`Cpu`] and its frame buffer serialized with [`encode_cpu`].
+///
+/// # Errors
+///
+/// If the CPU fails to deserialize, an error is returned.
+pub fn decode_cpu(data: &[u8]) -> Result<Cpu> {
+    let (state, buffer) = data.split_at(data.len().saturating_sub(Ppu::SIZE * 2));
+    let mut cpu = bincode::decode_from_slice::<Compat<Cpu>, _>(state, bincode::config::standard())
+        .map_err(|err| Error::DeserializationFailed {
+            inner: err.to_string(),
+        })?
+        .0
+         .0;
+    let mut frame_buffer = Buffer::default();
+    let (lo, hi) = buffer.split_at(buffer.len() / 2);
+    for ((pixel, lo), hi) in frame_buffer.iter_mut().zip(lo).zip(hi) {
+        *pixel = u16::from_le_bytes([*lo, *hi]);
+    }
+    cpu.bus.ppu.frame.buffer = frame_buffer;
+    Ok(cpu)
+}
+
+/// Write `value` as a LEB128 variable-length integer.
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Read a LEB128 variable-length integer, advancing `data`.
+fn read_varint(data: &mut &[u8]) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+    while let Some((&byte, rest)) = data.split_first() {
+        *data = rest;
+        value |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    value
+}
+
+/// Encode `data` as the XOR against `base`, run-length encoding unchanged bytes.
+///
+/// The output is the length of `data` followed by pairs of unchanged byte runs and changed
+/// literal bytes. Bytes past the end of `base` are compared against zero, so an empty `base`
+/// run-length encodes runs of zeros.
+#[must_use]
+pub fn encode_delta(base: &[u8], data: &[u8]) -> Vec<u8> {
+    let (head, tail) = data.split_at(base.len().min(data.len()));
+    let mut xor = Vec::with_capacity(data.len());
+    xor.extend(head.iter().zip(base).map(|(data, base)| data ^ base));
+    xor.extend_from_slice(tail);
+
+    let mut out = Vec::new();
+    write_varint(&mut out, xor.len());
+    let mut i = 0;
+    while i < xor.len() {
+        let unchanged = zero_run(&xor[i..]);
+        write_varint(&mut out, unchanged);
+        i += unchanged;
+        let changed = xor[i..]
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(xor.len() - i);
+        write_varint(&mut out, changed);
+        out.extend_from_slice(&xor[i..i + changed]);
+        i += changed;
+    }
+    out
+}
+
+/// Length of the run of zeros at the start of `data`.
+fn zero_run(data: &[u8]) -> usize {
+    const WORD: usize = core::mem::size_of::<u64>();
+
+    let words = data
+        .chunks_exact(WORD)
+        .take_while(|word| word.iter().all(|&b| b == 0))
+        .count();
+    let rest = &data[words * WORD..];
+    words * WORD + rest.iter().position(|&b| b != 0).unwrap_or(rest.len())
+}
+
+/// Decode a delta produced by [`encode_delta`] against the same `base`.
+#[must_use]
+pub fn decode_delta(base: &[u8], mut delta: &[u8]) -> Vec<u8> {
+    let len = read_varint(&mut delta);
+    let mut data = Vec::with_capacity(len);
+    data.extend_from_slice(&base[..len.min(base.len())]);
+    data.resize(len, 0);
+    let mut i = 0;
+    while i < len && !delta.is_empty() {
+        i += read_varint(&mut delta);
+        let literal_len = read_varint(&mut delta).min(delta.len());
+        let (literal, rest) = delta.split_at(literal_len);
+        for (byte, xor) in data[i.min(len)..].iter_mut().zip(literal) {
+            *byte ^= xor;
+        }
+        i += literal_len;
+        delta = rest;
+    }
+    data
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn delta_roundtrip() {
+        let base = vec![0, 1, 2, 3, 4, 5, 6, 7];
+        let data = vec![0, 1, 9, 3, 4, 5, 8, 8, 8, 8];
+        let delta = encode_delta(&base, &data);
+        assert_eq!(decode_delta(&base, &delta), data);
+
+        let data = vec![0, 1, 2];
+        assert_eq!(decode_delta(&base, &encode_delta(&base, &data)), data);
+
+        // An empty base run-length encodes zeros
+        let data = [vec![0; 100], vec![7; 3], vec![0; 100]].concat();
+        let rle = encode_delta(&[], &data);
+        assert!(rle.len() < 10, "zero runs are compressed");
+        assert_eq!(decode_delta(&[], &rle), data);
+    }
+
+    #[test]
+    fn ring_restores_frame_buffer() {
+        let mut ring = SnapshotRing::new(2).with_keyframe_interval(2);
+        let mut cpu = Cpu::default();
+        ring.push(&cpu, ()).expect("valid push");
+        cpu.bus.ppu.frame.buffer[10] = 0x01C5;
+        cpu.bus.ppu.frame.buffer[Ppu::SIZE - 1] = 0x0030;
+        ring.push(&cpu, ()).expect("valid push");
+        ring.push(&cpu, ()).expect("valid push");
+        for index in 0..ring.len() {
+            let restored = ring.get(index).expect("valid index").expect("valid cpu");
+            assert_eq!(
+                restored.bus.ppu.frame.buffer[..],
+                cpu.bus.ppu.frame.buffer[..]
+            );
+        }
+    }
+
+    #[test]
+    fn restores_pending_dma_abort() {
+        let mut cpu = Cpu::default();
+        // Stopping a DMC DMA after the CPU halted for it aborts it on the next DMA cycle
+        cpu.bus.apu.dma.start_dmc();
+        cpu.bus.apu.dma.halt = false;
+        cpu.bus.apu.dma.stop_dmc();
+        assert!(cpu.bus.apu.dma.abort, "abort pending");
+
+        let mut snapshot = Snapshot::new();
+        snapshot.take(&mut cpu);
+        let mut ring = SnapshotRing::new(1);
+        ring.push(&cpu, ()).expect("valid push");
+
+        cpu.bus.apu.dma.abort = false;
+        assert!(snapshot.restore(&mut cpu), "restored snapshot");
+        assert!(cpu.bus.apu.dma.abort, "snapshot restored abort");
+        let restored = ring.get(0).expect("valid index").expect("valid cpu");
+        assert!(restored.bus.apu.dma.abort, "ring restored abort");
+    }
+
+    #[test]
+    fn ring_evicts_and_restores() {
+        let mut ring = SnapshotRing::new(3).with_keyframe_interval(2);
+        let mut cpu = Cpu::default();
+        for pc in 0..5 {
+            cpu.pc = pc;
+            ring.push(&cpu, pc).expect("valid push");
+        }
+        assert_eq!(ring.len(), 3);
+        assert!(ring.iter().copied().eq([2, 3, 4]));
+        for (index, pc) in [2, 3, 4].into_iter().enumerate() {
+            let cpu = ring.get(index).expect("valid index").expect("valid cpu");
+            assert_eq!(cpu.pc, pc);
+        }
+
+        let popped = ring.pop().expect("valid pop").expect("valid cpu");
+        assert_eq!(popped.pc, 4);
+
+        // Pushing after a pop starts from a fresh keyframe
+        cpu.pc = 10;
+        ring.push(&cpu, 10).expect("valid push");
+        let cpu = ring.get(2).expect("valid index").expect("valid cpu");
+        assert_eq!(cpu.pc, 10);
+    }
+}
@@ -4,12 +4,12 @@ use alloc::format;
 
 use crate::fs::{Error, Result};
 use std::{
-    fs::{create_dir_all, remove_dir_all, File},
+    fs::{create_dir_all, remove_dir_all, rename, File},
     io::{Read, Write},
     path::Path,
 };
 
-pub fn writer_impl(path: impl AsRef<Path>) -> Result<impl Write> {
+pub fn writer_impl(path: impl AsRef<Path>) -> Result<File> {
     let path = path.as_ref();
     let Some(directory) = path.parent() else {
         return Err(Error::InvalidPath {
@@ -24,6 +24,27 @@ pub fn writer_impl(path: impl AsRef<Path>) -> Result<impl Write> {
         .map_err(|source| Error::io(source, format!("failed to create file {path:?}")))
 }
 
+/// Writes `data` to a temporary file next to `path` and renames it into place once synced to
+/// disk, so an interrupted write never leaves a truncated file behind.
+pub fn write_atomic_impl(path: impl AsRef<Path>, data: &[u8]) -> Result<()> {
+    let path = path.as_ref();
+    let Some(filename) = path.file_name() else {
+        return Err(Error::InvalidPath {
+            inner: path.to_path_buf(),
+        });
+    };
+    let mut tmp_filename = filename.to_os_string();
+    tmp_filename.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_filename);
+
+    let mut file = writer_impl(&tmp_path)?;
+    file.write_all(data)
+        .and_then(|_| file.sync_all())
+        .map_err(|err| Error::io(err, format!("failed to write file {tmp_path:?}")))?;
+    rename(&tmp_path, path)
+        .map_err(|err| Error::io(err, format!("failed to rename {tmp_path:?} to {path:?}")))
+}
+
 pub fn reader_impl(path: impl AsRef<Path>) -> Result<impl Read> {
     let path = path.as_ref();
     File::open(path).map_err(|source| Error::io(source, format!("failed to open file {path:?}")))
@@ -34,3 +55,7 @@ pub fn clear_dir_impl(path: impl AsRef<Path>) -> Result<()> {
     remove_dir_all(path)
         .map_err(|source| Error::io(source, format!("failed to remove directory {path:?}")))
 }
+
+pub fn exists_impl(path: impl AsRef<Path>) -> bool {
+    path.as_ref().exists()
+}
@@ -13,6 +13,11 @@ pub fn writer_impl(_path: impl AsRef<Path>) -> Result<impl Write> {
     Err::<&'static mut [u8], _>(Error::custom("not implemented: wasm write"))
 }
 
+pub fn write_atomic_impl(_path: impl AsRef<Path>, _data: &[u8]) -> Result<()> {
+    // TODO: provide file download
+    Err::<(), _>(Error::custom("not implemented: wasm write"))
+}
+
 pub fn reader_impl(_path: impl AsRef<Path>) -> Result<impl Read> {
     // TODO: provide file upload?
     Err::<&'static [u8], _>(Error::custom("not implemented: wasm read"))
@@ -22,3 +27,7 @@ pub fn clear_dir_impl(_path: impl AsRef<Path>) -> Result<()> {
     // TODO: clear storage
     Err::<(), _>(Error::custom("not implemented: wasm clear dir"))
 }
+
+pub fn exists_impl(_path: impl AsRef<Path>) -> bool {
+    false
+}
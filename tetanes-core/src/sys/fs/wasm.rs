@@ -1,22 +1,257 @@
 //! Web-specific filesystem operations.
+//!
+//! Files are kept in memory and mirrored to an `IndexedDB` object store so they persist across
+//! sessions. `IndexedDB` is callback-based, so stored files are loaded into memory up front with
+//! [`load_storage`] and writes are persisted in the background, keeping the rest of the filesystem
+//! API synchronous.
 
 use crate::fs::{Error, Result};
-use core::{
-    io::{Empty, Read, Write},
-    path::Path,
+use alloc::format;
+use core::cell::RefCell;
+use std::{
+    collections::HashMap,
+    io::{self, Cursor, Read, Write},
+    path::{Path, PathBuf},
 };
+use tracing::{error, warn};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{
+    js_sys::{Array, Promise, Uint8Array},
+    IdbDatabase, IdbObjectStore, IdbRequest, IdbTransactionMode,
+};
+
+const DB_NAME: &str = "tetanes";
+const DB_VERSION: u32 = 1;
+const STORE_NAME: &str = "files";
+
+thread_local! {
+    static DB: RefCell<Option<IdbDatabase>> = const { RefCell::new(None) };
+    static FILES: RefCell<HashMap<PathBuf, Vec<u8>>> = RefCell::new(HashMap::new());
+}
+
+/// Open the `IndexedDB` database and load all stored files into memory. Must be awaited before
+/// reading any files, otherwise they'll appear to be missing.
+///
+/// # Errors
+///
+/// If `IndexedDB` is unavailable, e.g. in private browsing, or the stored files fail to load, then
+/// an error is returned. Files written afterwards are still available until the page is closed.
+pub async fn load_storage() -> Result<()> {
+    let db = open_db().await?;
+    let store = object_store(&db, IdbTransactionMode::Readonly)?;
+    let keys = store
+        .get_all_keys()
+        .map_err(|err| js_error("failed to read stored files", err))?;
+    let values = store
+        .get_all()
+        .map_err(|err| js_error("failed to read stored files", err))?;
+    let keys = Array::from(&request(&keys).await?);
+    let values = Array::from(&request(&values).await?);
+
+    FILES.with_borrow_mut(|files| {
+        for (key, value) in keys.iter().zip(values.iter()) {
+            match key.as_string() {
+                Some(path) => {
+                    files.insert(PathBuf::from(path), Uint8Array::new(&value).to_vec());
+                }
+                None => warn!("skipping stored file with invalid key: {key:?}"),
+            }
+        }
+    });
+    DB.set(Some(db));
+
+    Ok(())
+}
+
+/// Buffers written data and stores it once flushed or dropped.
+#[must_use]
+struct FileWriter {
+    path: PathBuf,
+    data: Vec<u8>,
+    dirty: bool,
+}
+
+impl Write for FileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.data.extend_from_slice(buf);
+        self.dirty = true;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.dirty {
+            store_file(&self.path, self.data.clone());
+            self.dirty = false;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for FileWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+pub fn writer_impl(path: impl AsRef<Path>) -> Result<impl Write> {
+    let path = path.as_ref().to_path_buf();
+    if path.file_name().is_none() {
+        return Err(Error::InvalidPath { inner: path });
+    }
+    Ok(FileWriter {
+        path,
+        data: Vec::new(),
+        // Creating a file truncates it, even if nothing gets written
+        dirty: true,
+    })
+}
+
+/// Files are replaced in a single `IndexedDB` transaction, so writes are already atomic.
+pub fn write_atomic_impl(path: impl AsRef<Path>, data: &[u8]) -> Result<()> {
+    let path = path.as_ref();
+    if path.file_name().is_none() {
+        return Err(Error::InvalidPath {
+            inner: path.to_path_buf(),
+        });
+    }
+    store_file(path, data.to_vec());
+    Ok(())
+}
+
+pub fn reader_impl(path: impl AsRef<Path>) -> Result<impl Read> {
+    let path = path.as_ref();
+    FILES
+        .with_borrow(|files| files.get(path).cloned())
+        .map(Cursor::new)
+        .ok_or_else(|| {
+            Error::io(
+                io::ErrorKind::NotFound.into(),
+                format!("failed to open file {path:?}"),
+            )
+        })
+}
+
+pub fn clear_dir_impl(path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    let removed = FILES.with_borrow_mut(|files| {
+        let removed = files
+            .keys()
+            .filter(|file| file.starts_with(path))
+            .cloned()
+            .collect::<Vec<_>>();
+        for file in &removed {
+            files.remove(file);
+        }
+        removed
+    });
+    for file in removed {
+        persist(&file, None);
+    }
+    Ok(())
+}
+
+pub fn exists_impl(path: impl AsRef<Path>) -> bool {
+    let path = path.as_ref();
+    FILES.with_borrow(|files| files.keys().any(|file| file.starts_with(path)))
+}
+
+/// Store `data` in memory and persist it to `IndexedDB`.
+fn store_file(path: &Path, data: Vec<u8>) {
+    persist(path, Some(&data));
+    FILES.with_borrow_mut(|files| files.insert(path.to_path_buf(), data));
+}
+
+/// Write `data` to `IndexedDB`, or delete it if `None`. The transaction is created immediately so
+/// writes to the same file are applied in order.
+fn persist(path: &Path, data: Option<&[u8]>) {
+    let Some(db) = DB.with_borrow(Clone::clone) else {
+        warn!("storage not loaded, {path:?} will not persist");
+        return;
+    };
+    let key = JsValue::from_str(&path.to_string_lossy());
+    let req = object_store(&db, IdbTransactionMode::Readwrite).and_then(|store| {
+        match data {
+            Some(data) => store.put_with_key(&Uint8Array::from(data), &key),
+            None => store.delete(&key),
+        }
+        .map_err(|err| js_error("failed to create request", err))
+    });
+    let path = path.to_path_buf();
+    spawn_local(async move {
+        let res = match req {
+            Ok(req) => request(&req).await,
+            Err(err) => Err(err),
+        };
+        if let Err(err) = res {
+            error!("failed to persist {path:?}: {err:?}");
+        }
+    });
+}
+
+async fn open_db() -> Result<IdbDatabase> {
+    let factory = web_sys::window()
+        .and_then(|window| window.indexed_db().ok().flatten())
+        .ok_or_else(|| Error::custom("IndexedDB is not available"))?;
+    let open = factory
+        .open_with_u32(DB_NAME, DB_VERSION)
+        .map_err(|err| js_error("failed to open database", err))?;
+    let on_upgrade = Closure::<dyn FnMut()>::new({
+        let open = open.clone();
+        move || {
+            let db = open.result().and_then(JsCast::dyn_into::<IdbDatabase>);
+            if let Err(err) = db.and_then(|db| db.create_object_store(STORE_NAME)) {
+                error!("failed to create object store: {err:?}");
+            }
+        }
+    });
+    open.set_onupgradeneeded(Some(on_upgrade.as_ref().unchecked_ref()));
+    let db = request(&open).await;
+    open.set_onupgradeneeded(None);
+    db?.dyn_into::<IdbDatabase>()
+        .map_err(|err| js_error("invalid database", err))
+}
 
-pub fn writer_impl(_path: impl AsRef<Path>) -> Result<impl Write> {
-    // TODO: provide file download
-    Err::<Empty, _>(Error::custom("not implemented: wasm write"))
+fn object_store(db: &IdbDatabase, mode: IdbTransactionMode) -> Result<IdbObjectStore> {
+    db.transaction_with_str_and_mode(STORE_NAME, mode)
+        .and_then(|transaction| transaction.object_store(STORE_NAME))
+        .map_err(|err| js_error("failed to start transaction", err))
 }
 
-pub fn reader_impl(_path: impl AsRef<Path>) -> Result<impl Read> {
-    // TODO: provide file upload?
-    Err::<Empty, _>(Error::custom("not implemented: wasm read"))
+/// Wait for `req` to complete, returning its result.
+async fn request(req: &IdbRequest) -> Result<JsValue> {
+    // Callbacks are dropped once the request completes
+    let mut callbacks = None;
+    let promise = Promise::new(&mut |resolve, reject| {
+        let on_success = Closure::<dyn FnMut()>::new(move || {
+            let _ = resolve.call0(&JsValue::NULL);
+        });
+        let on_error = Closure::<dyn FnMut()>::new(move || {
+            let _ = reject.call0(&JsValue::NULL);
+        });
+        req.set_onsuccess(Some(on_success.as_ref().unchecked_ref()));
+        req.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+        callbacks = Some((on_success, on_error));
+    });
+    let res = JsFuture::from(promise).await;
+    req.set_onsuccess(None);
+    req.set_onerror(None);
+    drop(callbacks);
+
+    match res {
+        Ok(_) => req
+            .result()
+            .map_err(|err| js_error("failed to get result", err)),
+        Err(_) => Err(js_error(
+            "request failed",
+            req.error()
+                .ok()
+                .flatten()
+                .map_or(JsValue::UNDEFINED, JsValue::from),
+        )),
+    }
 }
 
-pub fn clear_dir_impl(_path: impl AsRef<Path>) -> Result<()> {
-    // TODO: clear storage
-    Err::<(), _>(Error::custom("not implemented: wasm clear dir"))
+fn js_error(context: &str, err: JsValue) -> Error {
+    Error::custom(format!("{context}: {err:?}"))
 }
@@ -1,13 +1,21 @@
 //! Video output and filtering.
 
 use crate::ppu::Ppu;
-use alloc::{vec, vec::Vec};
+use alloc::{sync::Arc, vec, vec::Vec};
 use core::{
     f64::consts::PI,
     ops::{Deref, DerefMut},
 };
 use lazy_static::lazy_static;
+use post_process::{PostProcessChain, PostProcessStage};
 use serde::{Deserialize, Serialize};
+use spin::Once;
+use upscale::Upscaler;
+
+pub mod post_process;
+pub mod ripper;
+pub mod stitch;
+pub mod upscale;
 
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[must_use]
@@ -15,11 +23,47 @@ pub enum VideoFilter {
     Pixellate,
     #[default]
     Ntsc,
+    /// NTSC, with scanlines and screen curvature mimicking a CRT television applied by a shader
+    /// in the renderer. Renderers without shader support can enable the
+    /// [`PostProcess::Scanlines`](post_process::PostProcess::Scanlines) and
+    /// [`PostProcess::Curvature`](post_process::PostProcess::Curvature) stages instead.
+    Crt,
+    /// 2x upscaling smoothing diagonal edges, inspired by HQ2X.
+    Smooth2x,
+    /// 2x edge-directed upscaling, inspired by xBR.
+    Edge2x,
 }
 
 impl VideoFilter {
     pub const fn as_slice() -> &'static [Self] {
-        &[Self::Pixellate, Self::Ntsc]
+        &[
+            Self::Pixellate,
+            Self::Ntsc,
+            Self::Crt,
+            Self::Smooth2x,
+            Self::Edge2x,
+        ]
+    }
+
+    /// Factor the filter scales the frame width and height by.
+    #[must_use]
+    pub const fn scale(&self) -> u32 {
+        match self {
+            Self::Pixellate | Self::Ntsc | Self::Crt => 1,
+            Self::Smooth2x | Self::Edge2x => upscale::SCALE as u32,
+        }
+    }
+
+    /// Width and height of frames output by the filter.
+    #[must_use]
+    pub const fn frame_size(&self) -> (u32, u32) {
+        (Ppu::WIDTH * self.scale(), Ppu::HEIGHT * self.scale())
+    }
+
+    /// Length in bytes of RGBA frames output by the filter.
+    #[must_use]
+    pub const fn frame_len(&self) -> usize {
+        Frame::SIZE * (self.scale() * self.scale()) as usize
     }
 }
 
@@ -28,16 +72,63 @@ impl AsRef<str> for VideoFilter {
         match self {
             Self::Pixellate => "Pixellate",
             Self::Ntsc => "NTSC",
+            Self::Crt => "CRT",
+            Self::Smooth2x => "Smooth 2x",
+            Self::Edge2x => "Edge 2x",
         }
     }
 }
 
 impl From<usize> for VideoFilter {
     fn from(value: usize) -> Self {
-        if value == 1 {
-            Self::Ntsc
-        } else {
-            Self::Pixellate
+        match value {
+            1 => Self::Ntsc,
+            2 => Self::Crt,
+            3 => Self::Smooth2x,
+            4 => Self::Edge2x,
+            _ => Self::Pixellate,
+        }
+    }
+}
+
+/// Tunable parameters for the [`VideoFilter::Ntsc`] filter. The defaults match the original
+/// palette.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[must_use]
+#[serde(default)]
+pub struct NtscConfig {
+    /// Luma edge enhancement against the previous pixel, from `-1.0` (soft) to `1.0` (sharp).
+    pub sharpness: f32,
+    /// Chroma bleeding from the previous pixel, from `0.0` to `1.0`.
+    pub fringing: f32,
+    /// Luma artifacts from the previous pixel, from `0.0` to `1.0`.
+    pub artifacts: f32,
+    /// Hue rotation in degrees, from `-45.0` to `45.0`.
+    pub hue: f32,
+    /// Color saturation, from `0.0` to `2.0`.
+    pub saturation: f32,
+    /// Assumed display gamma, from `1.0` to `3.0`.
+    pub gamma: f32,
+}
+
+impl NtscConfig {
+    pub const SHARPNESS_RANGE: core::ops::RangeInclusive<f32> = -1.0..=1.0;
+    pub const FRINGING_RANGE: core::ops::RangeInclusive<f32> = 0.0..=1.0;
+    pub const ARTIFACTS_RANGE: core::ops::RangeInclusive<f32> = 0.0..=1.0;
+    pub const HUE_RANGE: core::ops::RangeInclusive<f32> = -45.0..=45.0;
+    pub const SATURATION_RANGE: core::ops::RangeInclusive<f32> = 0.0..=2.0;
+    pub const GAMMA_RANGE: core::ops::RangeInclusive<f32> = 1.0..=3.0;
+}
+
+impl Default for NtscConfig {
+    fn default() -> Self {
+        Self {
+            sharpness: 0.0,
+            fringing: 1.0,
+            artifacts: 1.0,
+            hue: 0.0,
+            saturation: 1.0,
+            gamma: 2.0,
         }
     }
 }
@@ -84,7 +175,14 @@ impl DerefMut for Frame {
 #[must_use]
 pub struct Video {
     pub filter: VideoFilter,
+    pub post_process: PostProcessChain,
     pub frame: Frame,
+    // Native resolution frame for filters that upscale
+    source: Frame,
+    upscaler: Upscaler,
+    ntsc: NtscConfig,
+    // Generated on first use after the NTSC parameters change
+    ntsc_palette: Arc<Once<Vec<u32>>>,
 }
 
 impl Default for Video {
@@ -103,31 +201,87 @@ impl Video {
     pub fn with_filter(filter: VideoFilter) -> Self {
         Self {
             filter,
+            post_process: PostProcessChain::default(),
             frame: Frame::new(),
+            source: Frame::new(),
+            upscaler: Upscaler::new(),
+            ntsc: NtscConfig::default(),
+            ntsc_palette: Arc::new(Once::new()),
+        }
+    }
+
+    /// Returns the [`VideoFilter::Ntsc`] filter parameters.
+    pub const fn ntsc_config(&self) -> &NtscConfig {
+        &self.ntsc
+    }
+
+    /// Set the [`VideoFilter::Ntsc`] filter parameters. The palette is regenerated the next time
+    /// the filter is applied.
+    pub fn set_ntsc_config(&mut self, ntsc: NtscConfig) {
+        if self.ntsc != ntsc {
+            self.ntsc = ntsc;
+            self.ntsc_palette = Arc::new(Once::new());
         }
     }
 
+    /// Set the ordered post-processing chain applied after the video filter.
+    pub fn set_post_process(&mut self, stages: Vec<PostProcessStage>) {
+        self.post_process.stages = stages;
+    }
+
     /// Applies the given filter to the given video buffer and returns the result.
     pub fn apply_filter(&mut self, buffer: &[u16], frame_number: u32) -> &[u8] {
         #[cfg(feature = "profiling")]
         puffin::profile_function!();
 
-        match self.filter {
-            VideoFilter::Pixellate => Self::decode_buffer(buffer, &mut self.frame),
-            VideoFilter::Ntsc => Self::apply_ntsc_filter(buffer, frame_number, &mut self.frame),
+        let palette = ntsc_palette(&self.ntsc, &self.ntsc_palette);
+        self.frame.resize(self.filter.frame_len(), 0xFF);
+        if self.filter.scale() == 1 {
+            Self::decode_filtered(self.filter, palette, buffer, frame_number, &mut self.frame);
+            self.post_process.apply(&mut self.frame);
+        } else {
+            // Post-processing effects apply to the native resolution frame before scaling
+            Self::decode_filtered(self.filter, palette, buffer, frame_number, &mut self.source);
+            self.post_process.apply(&mut self.source);
+            self.upscaler
+                .upscale(self.filter, &self.source, &mut self.frame);
         }
 
         &self.frame
     }
 
-    /// Applies the given filter to the given video buffer by coping into the provided buffer.
-    pub fn apply_filter_into(&self, buffer: &[u16], frame_number: u32, output: &mut [u8]) {
+    /// Applies the given filter to the given video buffer by coping into the provided buffer,
+    /// which must be [`VideoFilter::frame_len`] bytes.
+    pub fn apply_filter_into(&mut self, buffer: &[u16], frame_number: u32, output: &mut [u8]) {
         #[cfg(feature = "profiling")]
         puffin::profile_function!();
 
-        match self.filter {
-            VideoFilter::Pixellate => Self::decode_buffer(buffer, output),
-            VideoFilter::Ntsc => Self::apply_ntsc_filter(buffer, frame_number, output),
+        let palette = ntsc_palette(&self.ntsc, &self.ntsc_palette);
+        if self.filter.scale() == 1 {
+            Self::decode_filtered(self.filter, palette, buffer, frame_number, output);
+            self.post_process.apply(output);
+        } else {
+            Self::decode_filtered(self.filter, palette, buffer, frame_number, &mut self.source);
+            self.post_process.apply(&mut self.source);
+            self.upscaler.upscale(self.filter, &self.source, output);
+        }
+    }
+
+    /// Decodes the video buffer at native resolution with the base colors of `filter`.
+    fn decode_filtered(
+        filter: VideoFilter,
+        palette: &[u32],
+        buffer: &[u16],
+        frame_number: u32,
+        output: &mut [u8],
+    ) {
+        match filter {
+            VideoFilter::Pixellate | VideoFilter::Smooth2x | VideoFilter::Edge2x => {
+                Self::decode_buffer(buffer, output);
+            }
+            VideoFilter::Ntsc | VideoFilter::Crt => {
+                Self::apply_ntsc_filter(palette, buffer, frame_number, output);
+            }
         }
     }
 
@@ -143,13 +297,19 @@ impl Video {
         }
     }
 
-    /// Applies the NTSC filter to the given video buffer.
+    /// Applies the NTSC filter to the given video buffer using a `palette` generated by
+    /// [`generate_ntsc_palette`].
     ///
     /// Amazing implementation Bisqwit! Much faster than my original, but boy what a pain
     /// to translate it to Rust
     /// Source: <https://bisqwit.iki.fi/jutut/kuvat/programming_examples/nesemu1/nesemu1.cc>
     /// See also: <http://wiki.nesdev.com/w/index.php/NTSC_video>
-    pub fn apply_ntsc_filter(buffer: &[u16], frame_number: u32, output: &mut [u8]) {
+    pub fn apply_ntsc_filter(
+        palette: &[u32],
+        buffer: &[u16],
+        frame_number: u32,
+        output: &mut [u8],
+    ) {
         let mut prev_pixel = 0;
         for (idx, (pixel, colors)) in buffer.iter().zip(output.chunks_exact_mut(4)).enumerate() {
             let x = idx % 256;
@@ -160,8 +320,7 @@ impl Video {
                 let y = idx / 256;
                 let even_phase = if frame_number & 0x01 == 0x01 { 0 } else { 1 };
                 let phase = (2 + y * 341 + x + even_phase) % 3;
-                NTSC_PALETTE
-                    [phase + ((prev_pixel & 0x3F) as usize) * 3 + (*pixel as usize) * 3 * 64]
+                palette[phase + ((prev_pixel & 0x3F) as usize) * 3 + (*pixel as usize) * 3 * 64]
             };
             prev_pixel = u32::from(*pixel);
             assert!(colors.len() > 2);
@@ -177,14 +336,28 @@ impl core::fmt::Debug for Video {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("Video")
             .field("filter", &self.filter)
+            .field("post_process", &self.post_process.stages)
+            .field("ntsc", &self.ntsc)
             .finish()
     }
 }
 
 lazy_static! {
-    pub static ref NTSC_PALETTE: Vec<u32> = generate_ntsc_palette();
+    pub static ref NTSC_PALETTE: Vec<u32> = generate_ntsc_palette(&NtscConfig::default());
+}
+
+/// Returns the default palette or lazily generates one for custom parameters.
+fn ntsc_palette<'a>(ntsc: &NtscConfig, palette: &'a Once<Vec<u32>>) -> &'a [u32] {
+    if *ntsc == NtscConfig::default() {
+        &NTSC_PALETTE
+    } else {
+        palette.call_once(|| generate_ntsc_palette(ntsc))
+    }
 }
-fn generate_ntsc_palette() -> Vec<u32> {
+
+/// Generate the NTSC filter palette indexed by the current pixel with emphasis, the previous
+/// pixel and the color subcarrier phase.
+pub fn generate_ntsc_palette(ntsc: &NtscConfig) -> Vec<u32> {
     // NOTE: There's lot's to clean up here -- too many magic numbers and duplication but
     // I'm afraid to touch it now that it works
     // Source: https://bisqwit.iki.fi/jutut/kuvat/programming_examples/nesemu1/nesemu1.cc
@@ -198,7 +371,7 @@ fn generate_ntsc_palette() -> Vec<u32> {
     let mut ntsc_palette = vec![0; 512 * 64 * 3];
 
     // Helper functions for converting YIQ to RGB
-    let gamma = 2.0; // Assumed display gamma
+    let gamma = f64::from(ntsc.gamma); // Assumed display gamma
     let gammafix = |color: f64| {
         if color <= 0.0 {
             0.0
@@ -206,16 +379,41 @@ fn generate_ntsc_palette() -> Vec<u32> {
             libm::pow(color, 2.2 / gamma)
         }
     };
+    let sharpness = f64::from(ntsc.sharpness);
+    let fringing = f64::from(ntsc.fringing);
+    let artifacts = f64::from(ntsc.artifacts);
+    let saturation = f64::from(ntsc.saturation);
+    let (hue_sin, hue_cos) = libm::sincos(f64::from(ntsc.hue).to_radians());
     let yiq_divider = f64::from(9 * 10u32.pow(6));
     for palette_offset in 0..3 {
         for channel in 0..3 {
             for color0_offset in 0..512 {
                 let emphasis = color0_offset / 64;
+                // Signal level of a pixel sample
+                let level = |pixel: usize, sample: usize| {
+                    // Decode the color index.
+                    let chroma = pixel & 0x0F;
+                    // Forces luma to 0, 4, 8, or 12 for easy lookup
+                    let luma = if chroma < 0x0E { (pixel / 4) & 12 } else { 4 };
+                    // NES NTSC modulator (square wave between up to four voltage levels):
+                    let limit = if (chroma + 8 + sample) % 12 < 6 {
+                        12
+                    } else {
+                        0
+                    };
+                    let high = if chroma > limit { 1 } else { 0 };
+                    let emp_effect = if (152_278 >> (sample / 2 * 3)) & emphasis > 0 {
+                        0
+                    } else {
+                        2
+                    };
+                    40 + VOLTAGES[high + emp_effect + luma]
+                };
 
                 for color1_offset in 0..64 {
-                    let mut y = 0;
-                    let mut i = 0;
-                    let mut q = 0;
+                    // YIQ with artifacts from the previous pixel, without them, and of the
+                    // previous pixel alone
+                    let mut yiq = [[0i32; 3]; 3];
                     // 12 samples of NTSC signal constitute a color.
                     for sample in 0..12 {
                         let noise = (sample + palette_offset * 4) % 12;
@@ -226,34 +424,32 @@ fn generate_ntsc_palette() -> Vec<u32> {
                         } else {
                             color1_offset
                         };
-
-                        // Decode the color index.
-                        let chroma = pixel & 0x0F;
-                        // Forces luma to 0, 4, 8, or 12 for easy lookup
-                        let luma = if chroma < 0x0E { (pixel / 4) & 12 } else { 4 };
-                        // NES NTSC modulator (square wave between up to four voltage levels):
-                        let limit = if (chroma + 8 + sample) % 12 < 6 {
-                            12
-                        } else {
-                            0
-                        };
-                        let high = if chroma > limit { 1 } else { 0 };
-                        let emp_effect = if (152_278 >> (sample / 2 * 3)) & emphasis > 0 {
-                            0
-                        } else {
-                            2
-                        };
-                        let level = 40 + VOLTAGES[high + emp_effect + luma];
                         // Ideal TV NTSC demodulator:
                         let (sin, cos) = libm::sincos(PI * sample as f64 / 6.0);
-                        y += level;
-                        i += level * (cos * 5909.0) as i32;
-                        q += level * (sin * 5909.0) as i32;
+                        let (sin, cos) = ((sin * 5909.0) as i32, (cos * 5909.0) as i32);
+                        for (yiq, pixel) in
+                            yiq.iter_mut().zip([pixel, color0_offset, color1_offset])
+                        {
+                            let level = level(pixel, sample);
+                            yiq[0] += level;
+                            yiq[1] += level * cos;
+                            yiq[2] += level * sin;
+                        }
                     }
+                    let [artifact, clean, prev] = yiq.map(|yiq| yiq.map(f64::from));
+                    let y = libm::fma(artifacts, artifact[0] - clean[0], clean[0])
+                        + sharpness * (clean[0] - prev[0]) / 2.0;
+                    let i = libm::fma(fringing, artifact[1] - clean[1], clean[1]);
+                    let q = libm::fma(fringing, artifact[2] - clean[2], clean[2]);
+
                     // Store color at subpixel precision
-                    let y = f64::from(y) / 1980.0;
-                    let i = f64::from(i) / yiq_divider;
-                    let q = f64::from(q) / yiq_divider;
+                    let y = y / 1980.0;
+                    let i = i / yiq_divider;
+                    let q = q / yiq_divider;
+                    let (i, q) = (
+                        (i * hue_cos - q * hue_sin) * saturation,
+                        (i * hue_sin + q * hue_cos) * saturation,
+                    );
                     let idx = palette_offset + color0_offset * 3 * 64 + color1_offset * 3;
                     match channel {
                         2 => {
@@ -280,3 +476,55 @@ fn generate_ntsc_palette() -> Vec<u32> {
 
     ntsc_palette
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::fs::compute_crc32;
+
+    fn palette_crc(palette: &[u32]) -> u32 {
+        let bytes = palette
+            .iter()
+            .flat_map(|color| color.to_le_bytes())
+            .collect::<Vec<_>>();
+        compute_crc32(&bytes)
+    }
+
+    #[test]
+    fn ntsc_palette_default_params() {
+        // Checksum of the palette before its parameters were configurable
+        assert_eq!(palette_crc(&NTSC_PALETTE), 0x85E5_192B);
+
+        let ntsc = NtscConfig {
+            artifacts: 0.0,
+            saturation: 0.0,
+            ..NtscConfig::default()
+        };
+        let mut video = Video::new();
+        video.set_ntsc_config(ntsc);
+        let palette = ntsc_palette(video.ntsc_config(), &video.ntsc_palette);
+        assert_ne!(palette_crc(palette), 0x85E5_192B);
+        // Without artifacts or saturation, every color is gray
+        assert!(palette.iter().all(|color| {
+            let [b, g, r, _] = color.to_le_bytes();
+            r.abs_diff(g) <= 1 && g.abs_diff(b) <= 1
+        }));
+    }
+
+    #[test]
+    fn filter_frame_size() {
+        let buffer = vec![0x0F; Ppu::SIZE];
+        let mut video = Video::new();
+        for &filter in VideoFilter::as_slice() {
+            video.filter = filter;
+            let len = video.apply_filter(&buffer, 0).len();
+            assert_eq!(len, filter.frame_len(), "{filter:?}");
+            let (width, height) = filter.frame_size();
+            assert_eq!(len, (width * height * 4) as usize, "{filter:?}");
+
+            let mut output = vec![0xFF; filter.frame_len()];
+            video.apply_filter_into(&buffer, 0, &mut output);
+            assert_eq!(output, video.frame.as_slice(), "{filter:?}");
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! Post-processing effects applied to decoded video frames.
+
+use crate::ppu::Ppu;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// A post-processing effect applied to a decoded RGBA frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[must_use]
+pub enum PostProcess {
+    /// Darken every other line to mimic CRT scanlines.
+    Scanlines,
+    /// Barrel distort the frame to mimic a curved CRT screen.
+    Curvature,
+    /// Darken the edges of the frame.
+    Vignette,
+}
+
+impl PostProcess {
+    pub const fn as_slice() -> &'static [Self] {
+        &[Self::Scanlines, Self::Curvature, Self::Vignette]
+    }
+}
+
+impl AsRef<str> for PostProcess {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Scanlines => "Scanlines",
+            Self::Curvature => "Curvature",
+            Self::Vignette => "Vignette",
+        }
+    }
+}
+
+/// A single stage in a post-processing chain.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub struct PostProcessStage {
+    pub effect: PostProcess,
+    pub enabled: bool,
+}
+
+impl PostProcessStage {
+    pub const fn new(effect: PostProcess) -> Self {
+        Self {
+            effect,
+            enabled: false,
+        }
+    }
+}
+
+/// Default post-processing chain containing every effect, disabled.
+#[must_use]
+pub fn default_chain() -> Vec<PostProcessStage> {
+    PostProcess::as_slice()
+        .iter()
+        .copied()
+        .map(PostProcessStage::new)
+        .collect()
+}
+
+/// Ordered post-processing chain applied to RGBA frames.
+#[derive(Default, Debug, Clone)]
+#[must_use]
+pub struct PostProcessChain {
+    pub stages: Vec<PostProcessStage>,
+    scratch: Vec<u8>,
+}
+
+impl PostProcessChain {
+    const WIDTH: usize = Ppu::WIDTH as usize;
+    const HEIGHT: usize = Ppu::HEIGHT as usize;
+    const SCANLINE_INTENSITY: f32 = 0.7;
+    const CURVATURE: f32 = 0.08;
+    const VIGNETTE_STRENGTH: f32 = 0.35;
+
+    pub const fn new(stages: Vec<PostProcessStage>) -> Self {
+        Self {
+            stages,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Whether any stage in the chain is enabled.
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.stages.iter().any(|stage| stage.enabled)
+    }
+
+    /// Apply each enabled stage in order to an RGBA `frame`.
+    pub fn apply(&mut self, frame: &mut [u8]) {
+        for stage in self.stages.iter().filter(|stage| stage.enabled) {
+            match stage.effect {
+                PostProcess::Scanlines => Self::scanlines(frame),
+                PostProcess::Curvature => Self::curvature(frame, &mut self.scratch),
+                PostProcess::Vignette => Self::vignette(frame),
+            }
+        }
+    }
+
+    fn scanlines(frame: &mut [u8]) {
+        for row in frame.chunks_exact_mut(Self::WIDTH * 4).skip(1).step_by(2) {
+            for color in row.chunks_exact_mut(4) {
+                for channel in &mut color[..3] {
+                    *channel = (f32::from(*channel) * Self::SCANLINE_INTENSITY) as u8;
+                }
+            }
+        }
+    }
+
+    fn curvature(frame: &mut [u8], scratch: &mut Vec<u8>) {
+        scratch.clear();
+        scratch.extend_from_slice(frame);
+        let (width, height) = (Self::WIDTH as f32, Self::HEIGHT as f32);
+        for (idx, color) in frame.chunks_exact_mut(4).enumerate() {
+            // Map to -1.0..=1.0 and push coordinates outwards based on distance from center
+            let u = (idx % Self::WIDTH) as f32 / width * 2.0 - 1.0;
+            let v = (idx / Self::WIDTH) as f32 / height * 2.0 - 1.0;
+            let u = u * (1.0 + v * v * Self::CURVATURE);
+            let v = v * (1.0 + u * u * Self::CURVATURE);
+            let x = ((u + 1.0) / 2.0 * width) as isize;
+            let y = ((v + 1.0) / 2.0 * height) as isize;
+            if (0..Self::WIDTH as isize).contains(&x) && (0..Self::HEIGHT as isize).contains(&y) {
+                let src = (y as usize * Self::WIDTH + x as usize) * 4;
+                color[..3].copy_from_slice(&scratch[src..src + 3]);
+            } else {
+                color[..3].fill(0);
+            }
+        }
+    }
+
+    fn vignette(frame: &mut [u8]) {
+        let (width, height) = (Self::WIDTH as f32, Self::HEIGHT as f32);
+        for (idx, color) in frame.chunks_exact_mut(4).enumerate() {
+            let u = (idx % Self::WIDTH) as f32 / width * 2.0 - 1.0;
+            let v = (idx / Self::WIDTH) as f32 / height * 2.0 - 1.0;
+            let falloff = 1.0 - Self::VIGNETTE_STRENGTH * (u * u + v * v) / 2.0;
+            for channel in &mut color[..3] {
+                *channel = (f32::from(*channel) * falloff) as u8;
+            }
+        }
+    }
+}
+
+impl From<Vec<PostProcessStage>> for PostProcessChain {
+    fn from(stages: Vec<PostProcessStage>) -> Self {
+        Self::new(stages)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn scanlines_darken_odd_rows() {
+        let mut chain = PostProcessChain::new(vec![PostProcessStage {
+            effect: PostProcess::Scanlines,
+            enabled: true,
+        }]);
+        let mut frame = vec![200; Ppu::SIZE * 4];
+        chain.apply(&mut frame);
+
+        let row_len = Ppu::WIDTH as usize * 4;
+        assert_eq!(frame[0], 200);
+        assert_eq!(frame[row_len], 140);
+        // Alpha is untouched
+        assert_eq!(frame[row_len + 3], 200);
+    }
+}
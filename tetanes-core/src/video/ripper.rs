@@ -0,0 +1,383 @@
+//! Sprite ripping for exporting all sprites seen during a session.
+//!
+//! Sprites are read from OAM at the end of each frame and de-duplicated by their pattern data
+//! and palette. Sprites that are adjacent on screen in the same frame can optionally be tracked
+//! as groups, which usually correspond to the metasprites a game uses for characters and objects.
+//!
+//! Pattern data is read using the CHR banks active at the end of the frame, so games that
+//! switch sprite CHR banks mid-frame may produce some incorrect tiles.
+
+use crate::{
+    mem::{Access, Mem},
+    ppu::Ppu,
+};
+use alloc::{vec, vec::Vec};
+use hashbrown::HashMap;
+
+/// A unique sprite tile and palette combination.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct RippedSprite {
+    /// Pattern table data. 8x8 sprites only use the first 16 bytes.
+    pub pattern: [u8; 32],
+    /// Sprite height, either 8 or 16.
+    pub height: u32,
+    /// System palette indexes for each of the 4 pixel values. Value `0` is transparent.
+    pub colors: [u8; 4],
+    /// Frame number this sprite was first seen.
+    pub first_frame: u32,
+    /// Number of times this sprite was seen on screen.
+    pub times_seen: u32,
+}
+
+/// A sprite placed within a [`SpriteGroup`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[must_use]
+pub struct GroupPart {
+    /// Offset from the top of the group.
+    pub y: u32,
+    /// Offset from the left of the group.
+    pub x: u32,
+    /// Index into [`SpriteRipper::sprites`].
+    pub sprite: usize,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+}
+
+/// A set of sprites that were adjacent on screen, e.g. a metasprite.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct SpriteGroup {
+    pub parts: Vec<GroupPart>,
+    pub width: u32,
+    pub height: u32,
+    /// Frame number this group was first seen.
+    pub first_frame: u32,
+    /// Number of times this group was seen on screen.
+    pub times_seen: u32,
+}
+
+/// An RGBA sprite sheet image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct SpriteSheet {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl SpriteSheet {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0x00; (width * height * 4) as usize],
+        }
+    }
+
+    fn draw(&mut self, sprite: &RippedSprite, x: u32, y: u32, flip_h: bool, flip_v: bool) {
+        for row in 0..sprite.height {
+            let src_row = if flip_v { sprite.height - 1 - row } else { row };
+            // 8x16 sprites are two consecutive tiles
+            let offset = (((src_row & 0x08) << 1) | (src_row & 0x07)) as usize;
+            let lo = sprite.pattern[offset];
+            let hi = sprite.pattern[offset + 8];
+            for col in 0..8 {
+                let shift = if flip_h { col } else { 7 - col };
+                let value = (((hi >> shift) & 0x01) << 1) | ((lo >> shift) & 0x01);
+                if value == 0 {
+                    continue;
+                }
+                let color = sprite.colors[value as usize] as usize * 3;
+                let idx = (((y + row) * self.width + x + col) * 4) as usize;
+                self.pixels[idx] = Ppu::NTSC_PALETTE[color];
+                self.pixels[idx + 1] = Ppu::NTSC_PALETTE[color + 1];
+                self.pixels[idx + 2] = Ppu::NTSC_PALETTE[color + 2];
+                self.pixels[idx + 3] = 0xFF;
+            }
+        }
+    }
+}
+
+type SpriteKey = ([u8; 32], u32, [u8; 4]);
+
+/// Tracks unique sprites rendered across frames.
+#[derive(Default, Debug, Clone)]
+#[must_use]
+pub struct SpriteRipper {
+    /// Whether to track groups of adjacent sprites.
+    pub group_sprites: bool,
+    sprites: Vec<RippedSprite>,
+    sprite_index: HashMap<SpriteKey, usize>,
+    groups: Vec<SpriteGroup>,
+    group_index: HashMap<Vec<GroupPart>, usize>,
+}
+
+impl SpriteRipper {
+    const OAM_SPRITES: usize = 64;
+    const PADDING: u32 = 1;
+    const GROUP_SHEET_WIDTH: u32 = 256;
+
+    /// Create a new `SpriteRipper`.
+    pub fn new(group_sprites: bool) -> Self {
+        Self {
+            group_sprites,
+            ..Default::default()
+        }
+    }
+
+    /// Clear all captured sprites.
+    pub fn clear(&mut self) {
+        *self = Self::new(self.group_sprites);
+    }
+
+    /// Unique sprites captured so far.
+    pub fn sprites(&self) -> &[RippedSprite] {
+        &self.sprites
+    }
+
+    /// Unique sprite groups captured so far.
+    pub fn groups(&self) -> &[SpriteGroup] {
+        &self.groups
+    }
+
+    /// Capture all visible sprites in OAM for the current frame.
+    pub fn capture(&mut self, ppu: &Ppu) {
+        if !ppu.mask.show_spr {
+            return;
+        }
+
+        let frame = ppu.frame_number();
+        let height = ppu.ctrl.spr_height;
+        // (x, y, sprite index, flip_h, flip_v) for each visible sprite this frame
+        let mut visible = Vec::with_capacity(Self::OAM_SPRITES);
+        for entry in ppu.oamdata.chunks_exact(4).take(Self::OAM_SPRITES) {
+            let [y, tile, attr, x] = [entry[0], entry[1], entry[2], entry[3]];
+            // Sprites are delayed by one scanline, so anything at $EF or greater is hidden
+            if y >= 0xEF {
+                continue;
+            }
+
+            let mut pattern = [0x00; 32];
+            let tile_addr = if height == 16 {
+                (u16::from(tile & 0x01) * 0x1000) | (u16::from(tile & 0xFE) << 4)
+            } else {
+                ppu.ctrl.spr_select | (u16::from(tile) << 4)
+            };
+            let len = if height == 16 { 32 } else { 16 };
+            for (offset, byte) in pattern.iter_mut().take(len).enumerate() {
+                *byte = ppu.bus.peek(tile_addr + offset as u16, Access::Dummy);
+            }
+            if pattern.iter().all(|byte| *byte == 0x00) {
+                continue;
+            }
+
+            let palette = 0x3F10 + (u16::from(attr & 0x03) << 2);
+            let mut colors = [0x00; 4];
+            for (i, color) in colors.iter_mut().enumerate().skip(1) {
+                *color = ppu.bus.peek(palette + i as u16, Access::Dummy) & 0x3F;
+            }
+
+            let key = (pattern, height, colors);
+            let index = match self.sprite_index.get(&key) {
+                Some(&index) => {
+                    self.sprites[index].times_seen += 1;
+                    index
+                }
+                None => {
+                    let index = self.sprites.len();
+                    self.sprites.push(RippedSprite {
+                        pattern,
+                        height,
+                        colors,
+                        first_frame: frame,
+                        times_seen: 1,
+                    });
+                    self.sprite_index.insert(key, index);
+                    index
+                }
+            };
+            visible.push((
+                u32::from(x),
+                u32::from(y) + 1,
+                index,
+                attr & 0x40 == 0x40,
+                attr & 0x80 == 0x80,
+            ));
+        }
+
+        if self.group_sprites {
+            self.capture_groups(&visible, height, frame);
+        }
+    }
+
+    /// Groups visible sprites whose bounding boxes touch or overlap.
+    fn capture_groups(
+        &mut self,
+        visible: &[(u32, u32, usize, bool, bool)],
+        height: u32,
+        frame: u32,
+    ) {
+        // Union-find over at most 64 sprites
+        let mut parent = (0..visible.len()).collect::<Vec<_>>();
+        fn find(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+        for a in 0..visible.len() {
+            for b in a + 1..visible.len() {
+                let (ax, ay, ..) = visible[a];
+                let (bx, by, ..) = visible[b];
+                if ax <= bx + 8 && bx <= ax + 8 && ay <= by + height && by <= ay + height {
+                    let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+                    parent[ra] = rb;
+                }
+            }
+        }
+
+        let mut members = HashMap::<usize, Vec<usize>>::new();
+        for i in 0..visible.len() {
+            let root = find(&mut parent, i);
+            members.entry(root).or_default().push(i);
+        }
+
+        for indexes in members.values().filter(|indexes| indexes.len() > 1) {
+            let left = indexes
+                .iter()
+                .map(|&i| visible[i].0)
+                .min()
+                .unwrap_or_default();
+            let top = indexes
+                .iter()
+                .map(|&i| visible[i].1)
+                .min()
+                .unwrap_or_default();
+            let right = indexes
+                .iter()
+                .map(|&i| visible[i].0 + 8)
+                .max()
+                .unwrap_or_default();
+            let bottom = indexes
+                .iter()
+                .map(|&i| visible[i].1 + height)
+                .max()
+                .unwrap_or_default();
+            let mut parts = indexes
+                .iter()
+                .map(|&i| {
+                    let (x, y, sprite, flip_horizontal, flip_vertical) = visible[i];
+                    GroupPart {
+                        y: y - top,
+                        x: x - left,
+                        sprite,
+                        flip_horizontal,
+                        flip_vertical,
+                    }
+                })
+                .collect::<Vec<_>>();
+            parts.sort_unstable();
+            parts.dedup();
+
+            match self.group_index.get(&parts) {
+                Some(&index) => self.groups[index].times_seen += 1,
+                None => {
+                    self.group_index.insert(parts.clone(), self.groups.len());
+                    self.groups.push(SpriteGroup {
+                        parts,
+                        width: right - left,
+                        height: bottom - top,
+                        first_frame: frame,
+                        times_seen: 1,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Render all unique sprites into a sprite sheet with `columns` sprites per row, in the
+    /// order they were first seen. Transparent pixels have an alpha of `0`.
+    pub fn sprite_sheet(&self, columns: u32) -> SpriteSheet {
+        let columns = columns.max(1);
+        let cell_width = 8 + Self::PADDING;
+        let cell_height = self
+            .sprites
+            .iter()
+            .map(|sprite| sprite.height)
+            .max()
+            .unwrap_or(8)
+            + Self::PADDING;
+        let rows = (self.sprites.len() as u32).div_ceil(columns);
+        let mut sheet = SpriteSheet::new(columns * cell_width, rows * cell_height);
+        for (i, sprite) in self.sprites.iter().enumerate() {
+            let x = (i as u32 % columns) * cell_width;
+            let y = (i as u32 / columns) * cell_height;
+            sheet.draw(sprite, x, y, false, false);
+        }
+        sheet
+    }
+
+    /// Render all unique sprite groups into a sprite sheet, in the order they were first seen.
+    /// Transparent pixels have an alpha of `0`.
+    pub fn group_sheet(&self) -> SpriteSheet {
+        // Simple shelf packing
+        let padding = 2 * Self::PADDING;
+        let mut positions = Vec::with_capacity(self.groups.len());
+        let (mut x, mut y, mut shelf_height, mut width) = (0, 0, 0, 0);
+        for group in &self.groups {
+            if x > 0 && x + group.width > Self::GROUP_SHEET_WIDTH {
+                x = 0;
+                y += shelf_height + padding;
+                shelf_height = 0;
+            }
+            positions.push((x, y));
+            x += group.width + padding;
+            width = width.max(x);
+            shelf_height = shelf_height.max(group.height);
+        }
+
+        let mut sheet = SpriteSheet::new(width, y + shelf_height);
+        for (group, (x, y)) in self.groups.iter().zip(positions) {
+            for part in &group.parts {
+                sheet.draw(
+                    &self.sprites[part.sprite],
+                    x + part.x,
+                    y + part.y,
+                    part.flip_horizontal,
+                    part.flip_vertical,
+                );
+            }
+        }
+        sheet
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draw_flipped_sprite() {
+        let mut pattern = [0x00; 32];
+        // Top-left pixel set to color 1
+        pattern[0] = 0x80;
+        let sprite = RippedSprite {
+            pattern,
+            height: 8,
+            colors: [0x00, 0x16, 0x27, 0x18],
+            first_frame: 0,
+            times_seen: 1,
+        };
+
+        let mut sheet = SpriteSheet::new(8, 8);
+        sheet.draw(&sprite, 0, 0, false, false);
+        assert_eq!(sheet.pixels[3], 0xFF);
+
+        let mut sheet = SpriteSheet::new(8, 8);
+        sheet.draw(&sprite, 0, 0, true, true);
+        assert_eq!(sheet.pixels[3], 0x00);
+        assert_eq!(sheet.pixels[(7 * 8 + 7) * 4 + 3], 0xFF);
+    }
+}
@@ -0,0 +1,372 @@
+//! Map stitching for assembling full-level images from scrolling frames.
+//!
+//! Frames are placed onto a growable canvas by tracking the PPU scroll origin between frames.
+//! Pixels are stored as raw palette indexes so that frames can be compared against what has
+//! already been captured, skipping frames whose palette differs too much (e.g. fades or flashes).
+
+use crate::ppu::Ppu;
+use alloc::{vec, vec::Vec};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Map stitching configuration.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+#[must_use]
+pub struct StitchConfig {
+    /// Number of scanlines to ignore at the top of each frame (e.g. overscan or status bars).
+    pub crop_top: u32,
+    /// Number of scanlines to ignore at the bottom of each frame.
+    pub crop_bottom: u32,
+    /// Number of columns to ignore at the left of each frame, which is often garbage while
+    /// scrolling horizontally.
+    pub crop_left: u32,
+    /// Number of columns to ignore at the right of each frame.
+    pub crop_right: u32,
+    /// Maximum ratio of mismatched palette indexes in the overlapping area before a frame is
+    /// skipped.
+    pub max_mismatch: f32,
+    /// Maximum width or height of the stitched map in pixels.
+    pub max_dimension: u32,
+}
+
+impl Default for StitchConfig {
+    fn default() -> Self {
+        Self {
+            crop_top: 8,
+            crop_bottom: 8,
+            crop_left: 8,
+            crop_right: 0,
+            max_mismatch: 0.25,
+            max_dimension: 8192,
+        }
+    }
+}
+
+/// Result of capturing a frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[must_use]
+pub enum Capture {
+    /// Frame was placed onto the map.
+    Stitched,
+    /// Rendering was disabled for the frame.
+    RenderingDisabled,
+    /// Frame palette didn't match what was previously captured.
+    PaletteMismatch,
+    /// Frame would grow the map beyond the maximum dimensions.
+    TooLarge,
+}
+
+/// Assembles a full map image from consecutive frames.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct MapStitcher {
+    pub cfg: StitchConfig,
+    last_origin: Option<(u16, u16)>,
+    // Absolute position of the current frame
+    x: i32,
+    y: i32,
+    // Absolute position of the top-left of the canvas
+    left: i32,
+    top: i32,
+    width: u32,
+    height: u32,
+    pixels: Vec<u16>,
+    frames_stitched: usize,
+    frames_skipped: usize,
+}
+
+impl Default for MapStitcher {
+    fn default() -> Self {
+        Self::new(StitchConfig::default())
+    }
+}
+
+impl MapStitcher {
+    const EMPTY: u16 = u16::MAX;
+    const SCROLL_WIDTH: i32 = 2 * Ppu::WIDTH as i32;
+    const SCROLL_HEIGHT: i32 = 2 * Ppu::HEIGHT as i32;
+
+    /// Create a new `MapStitcher` with the given configuration.
+    pub const fn new(cfg: StitchConfig) -> Self {
+        Self {
+            cfg,
+            last_origin: None,
+            x: 0,
+            y: 0,
+            left: 0,
+            top: 0,
+            width: 0,
+            height: 0,
+            pixels: Vec::new(),
+            frames_stitched: 0,
+            frames_skipped: 0,
+        }
+    }
+
+    /// Clear all captured frames.
+    pub fn clear(&mut self) {
+        *self = Self::new(self.cfg);
+    }
+
+    /// Dimensions of the stitched map in pixels.
+    #[must_use]
+    pub const fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Number of frames placed onto the map.
+    #[must_use]
+    pub const fn frames_stitched(&self) -> usize {
+        self.frames_stitched
+    }
+
+    /// Number of frames skipped.
+    #[must_use]
+    pub const fn frames_skipped(&self) -> usize {
+        self.frames_skipped
+    }
+
+    /// Whether any frames have been captured.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pixels.is_empty()
+    }
+
+    /// Capture the current PPU frame, placing it relative to previous frames based on the change
+    /// in scroll origin.
+    pub fn capture(&mut self, ppu: &Ppu) -> Capture {
+        if !ppu.mask.rendering_enabled {
+            self.frames_skipped += 1;
+            return Capture::RenderingDisabled;
+        }
+        self.capture_buffer(ppu.scroll_origin(), ppu.frame_buffer())
+    }
+
+    /// Capture a raw frame `buffer` of palette indexes scrolled to `origin`.
+    pub fn capture_buffer(&mut self, origin: (u16, u16), buffer: &[u16]) -> Capture {
+        let (x, y) = match self.last_origin {
+            Some((last_x, last_y)) => (
+                self.x + Self::scroll_delta(last_x, origin.0, Self::SCROLL_WIDTH),
+                self.y + Self::scroll_delta(last_y, origin.1, Self::SCROLL_HEIGHT),
+            ),
+            None => (0, 0),
+        };
+
+        let res = self.place(x, y, buffer);
+        match res {
+            Capture::Stitched => self.frames_stitched += 1,
+            _ => self.frames_skipped += 1,
+        }
+        // Always track scroll so that skipped frames don't lose our position
+        self.last_origin = Some(origin);
+        self.x = x;
+        self.y = y;
+        res
+    }
+
+    /// Decode the stitched map into RGBA pixels. Areas not captured are transparent.
+    #[must_use]
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let mut rgba = vec![0x00; self.pixels.len() * 4];
+        for (pixel, color) in self.pixels.iter().zip(rgba.chunks_exact_mut(4)) {
+            if *pixel != Self::EMPTY {
+                let index = (*pixel as usize) * 3;
+                color[0] = Ppu::NTSC_PALETTE[index];
+                color[1] = Ppu::NTSC_PALETTE[index + 1];
+                color[2] = Ppu::NTSC_PALETTE[index + 2];
+                color[3] = 0xFF;
+            }
+        }
+        rgba
+    }
+
+    /// Shortest signed distance between two wrapping scroll positions.
+    fn scroll_delta(prev: u16, next: u16, wrap: i32) -> i32 {
+        let delta = (i32::from(next) - i32::from(prev)).rem_euclid(wrap);
+        if delta >= wrap / 2 {
+            delta - wrap
+        } else {
+            delta
+        }
+    }
+
+    fn place(&mut self, x: i32, y: i32, buffer: &[u16]) -> Capture {
+        let StitchConfig {
+            crop_top,
+            crop_bottom,
+            crop_left,
+            crop_right,
+            ..
+        } = self.cfg;
+        let frame_width = Ppu::WIDTH.saturating_sub(crop_left + crop_right);
+        let frame_height = Ppu::HEIGHT.saturating_sub(crop_top + crop_bottom);
+        if frame_width == 0 || frame_height == 0 {
+            return Capture::TooLarge;
+        }
+        let frame_left = x + crop_left as i32;
+        let frame_top = y + crop_top as i32;
+
+        if !self.pixels.is_empty() && self.mismatch(frame_left, frame_top, buffer) {
+            return Capture::PaletteMismatch;
+        }
+        if !self.grow(frame_left, frame_top, frame_width, frame_height) {
+            return Capture::TooLarge;
+        }
+
+        for row in 0..frame_height {
+            let src_y = row + crop_top;
+            let dst_y = (frame_top - self.top) as u32 + row;
+            let src = (src_y * Ppu::WIDTH + crop_left) as usize;
+            let dst = (dst_y * self.width) as usize + (frame_left - self.left) as usize;
+            let len = frame_width as usize;
+            self.pixels[dst..dst + len].copy_from_slice(&buffer[src..src + len]);
+        }
+
+        Capture::Stitched
+    }
+
+    /// Compares the overlapping area of an incoming frame against the canvas, returning `true`
+    /// if too many palette indexes differ.
+    fn mismatch(&self, frame_left: i32, frame_top: i32, buffer: &[u16]) -> bool {
+        let mut overlap = 0u32;
+        let mut mismatched = 0u32;
+        let StitchConfig {
+            crop_top,
+            crop_bottom,
+            crop_left,
+            crop_right,
+            ..
+        } = self.cfg;
+        // Sample every other pixel, which is plenty to detect fades and flashes
+        for row in (0..Ppu::HEIGHT - crop_top - crop_bottom).step_by(2) {
+            let canvas_y = frame_top + row as i32 - self.top;
+            if canvas_y < 0 || canvas_y >= self.height as i32 {
+                continue;
+            }
+            for col in (0..Ppu::WIDTH - crop_left - crop_right).step_by(2) {
+                let canvas_x = frame_left + col as i32 - self.left;
+                if canvas_x < 0 || canvas_x >= self.width as i32 {
+                    continue;
+                }
+                let existing =
+                    self.pixels[(canvas_y as u32 * self.width + canvas_x as u32) as usize];
+                if existing == Self::EMPTY {
+                    continue;
+                }
+                overlap += 1;
+                let pixel = buffer[((row + crop_top) * Ppu::WIDTH + col + crop_left) as usize];
+                if pixel != existing {
+                    mismatched += 1;
+                }
+            }
+        }
+        overlap > 0 && mismatched as f32 / overlap as f32 > self.cfg.max_mismatch
+    }
+
+    /// Grows the canvas to fit the given area, returning `false` if the maximum dimensions would
+    /// be exceeded.
+    fn grow(&mut self, left: i32, top: i32, width: u32, height: u32) -> bool {
+        if self.pixels.is_empty() {
+            self.left = left;
+            self.top = top;
+            self.width = width;
+            self.height = height;
+            self.pixels = vec![Self::EMPTY; (width * height) as usize];
+            return true;
+        }
+
+        let new_left = self.left.min(left);
+        let new_top = self.top.min(top);
+        let new_right = (self.left + self.width as i32).max(left + width as i32);
+        let new_bottom = (self.top + self.height as i32).max(top + height as i32);
+        let new_width = (new_right - new_left) as u32;
+        let new_height = (new_bottom - new_top) as u32;
+        if new_width == self.width && new_height == self.height {
+            return true;
+        }
+        if new_width > self.cfg.max_dimension || new_height > self.cfg.max_dimension {
+            return false;
+        }
+
+        debug!("growing map canvas to {new_width}x{new_height}");
+        let mut pixels = vec![Self::EMPTY; (new_width * new_height) as usize];
+        let offset_x = (self.left - new_left) as usize;
+        let offset_y = (self.top - new_top) as usize;
+        for (row, src) in self.pixels.chunks_exact(self.width as usize).enumerate() {
+            let dst = (offset_y + row) * new_width as usize + offset_x;
+            pixels[dst..dst + src.len()].copy_from_slice(src);
+        }
+        self.pixels = pixels;
+        self.left = new_left;
+        self.top = new_top;
+        self.width = new_width;
+        self.height = new_height;
+        true
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    fn frame(color: u16) -> Vec<u16> {
+        vec![color; Ppu::SIZE]
+    }
+
+    #[test]
+    fn scroll_delta_wraps() {
+        assert_eq!(MapStitcher::scroll_delta(500, 4, 512), 16);
+        assert_eq!(MapStitcher::scroll_delta(4, 500, 512), -16);
+        assert_eq!(MapStitcher::scroll_delta(0, 0, 480), 0);
+    }
+
+    #[test]
+    fn stitch_horizontal_scroll() {
+        let mut stitcher = MapStitcher::new(StitchConfig {
+            crop_top: 0,
+            crop_bottom: 0,
+            crop_left: 0,
+            crop_right: 0,
+            ..Default::default()
+        });
+        assert_eq!(
+            stitcher.capture_buffer((0, 0), &frame(0x01)),
+            Capture::Stitched
+        );
+        assert_eq!(
+            stitcher.capture_buffer((128, 0), &frame(0x01)),
+            Capture::Stitched
+        );
+        // Wraps past the second nametable and continues growing to the right
+        assert_eq!(
+            stitcher.capture_buffer((256, 0), &frame(0x01)),
+            Capture::Stitched
+        );
+        assert_eq!(
+            stitcher.capture_buffer((384, 0), &frame(0x01)),
+            Capture::Stitched
+        );
+        assert_eq!(
+            stitcher.capture_buffer((0, 0), &frame(0x01)),
+            Capture::Stitched
+        );
+        assert_eq!(stitcher.dimensions(), (512 + 256, 240));
+        assert_eq!(stitcher.to_rgba().len(), 768 * 240 * 4);
+    }
+
+    #[test]
+    fn skip_palette_mismatch() {
+        let mut stitcher = MapStitcher::default();
+        assert_eq!(
+            stitcher.capture_buffer((0, 0), &frame(0x01)),
+            Capture::Stitched
+        );
+        assert_eq!(
+            stitcher.capture_buffer((8, 0), &frame(0x0F)),
+            Capture::PaletteMismatch
+        );
+        assert_eq!(stitcher.frames_stitched(), 1);
+        assert_eq!(stitcher.frames_skipped(), 1);
+    }
+}
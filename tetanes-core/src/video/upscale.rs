@@ -0,0 +1,263 @@
+//! CPU-side pixel art upscalers for platforms without custom shader support.
+//!
+//! Each scaler reads an RGBA frame and writes one [`SCALE`] times as wide and tall, smoothing
+//! diagonal edges while keeping flat areas and straight lines crisp. Output alpha is always opaque.
+
+use crate::{ppu::Ppu, video::VideoFilter};
+use alloc::vec::Vec;
+
+/// Output scale factor of the upscalers.
+pub const SCALE: usize = 2;
+
+/// Quadrants of an output pixel, as directions from the center of the source pixel.
+const CORNERS: [(isize, isize); 4] = [(-1, -1), (1, -1), (-1, 1), (1, 1)];
+
+#[derive(Debug, Copy, Clone)]
+struct Pixel {
+    rgb: [u8; 3],
+    yuv: [i32; 3],
+}
+
+impl Pixel {
+    fn new(rgb: [u8; 3]) -> Self {
+        let [r, g, b] = rgb.map(i32::from);
+        let y = (r * 299 + g * 587 + b * 114) / 1000;
+        let u = (b - y) * 492 / 1000;
+        let v = (r - y) * 877 / 1000;
+        Self {
+            rgb,
+            yuv: [y, u, v],
+        }
+    }
+
+    /// Whether the colors are perceptually different, using the HQ2X luma and chroma thresholds.
+    const fn differs(self, other: Self) -> bool {
+        let [y, u, v] = self.delta(other);
+        y > 48 || u > 7 || v > 6
+    }
+
+    /// Weighted color distance, favoring luma.
+    const fn distance(self, other: Self) -> i32 {
+        let [y, u, v] = self.delta(other);
+        48 * y + 7 * u + 6 * v
+    }
+
+    const fn delta(self, other: Self) -> [i32; 3] {
+        [
+            (self.yuv[0] - other.yuv[0]).abs(),
+            (self.yuv[1] - other.yuv[1]).abs(),
+            (self.yuv[2] - other.yuv[2]).abs(),
+        ]
+    }
+}
+
+/// Blend pixels by integer weight.
+fn blend(pixels: &[(Pixel, u32)]) -> [u8; 3] {
+    let total = pixels.iter().map(|(_, weight)| weight).sum::<u32>();
+    let mut rgb = [0; 3];
+    for (channel, value) in rgb.iter_mut().enumerate() {
+        let sum = pixels
+            .iter()
+            .map(|(pixel, weight)| u32::from(pixel.rgb[channel]) * weight)
+            .sum::<u32>();
+        *value = (sum / total) as u8;
+    }
+    rgb
+}
+
+struct Source<'a> {
+    pixels: &'a [Pixel],
+    width: usize,
+    height: usize,
+}
+
+impl Source<'_> {
+    /// Returns the pixel offset from `x` and `y`, clamped to the frame edges.
+    fn at(&self, x: usize, y: usize, dx: isize, dy: isize) -> Pixel {
+        let x = x.saturating_add_signed(dx).min(self.width - 1);
+        let y = y.saturating_add_signed(dy).min(self.height - 1);
+        self.pixels[y * self.width + x]
+    }
+
+    /// Calls `scale_corner` for each quadrant of every source pixel, writing the result to
+    /// `output`.
+    fn scale(
+        &self,
+        output: &mut [u8],
+        mut scale_corner: impl FnMut(usize, usize, isize, isize) -> [u8; 3],
+    ) {
+        let out_width = self.width * SCALE;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                for (sx, sy) in CORNERS {
+                    let out_x = x * SCALE + usize::from(sx > 0);
+                    let out_y = y * SCALE + usize::from(sy > 0);
+                    let idx = (out_y * out_width + out_x) * 4;
+                    let [r, g, b] = scale_corner(x, y, sx, sy);
+                    output[idx..idx + 4].copy_from_slice(&[r, g, b, 0xFF]);
+                }
+            }
+        }
+    }
+}
+
+/// Upscales frames, reusing the decoded source pixels between frames to avoid allocating.
+#[derive(Default, Debug, Clone)]
+#[must_use]
+pub struct Upscaler {
+    pixels: Vec<Pixel>,
+}
+
+impl Upscaler {
+    pub const fn new() -> Self {
+        Self { pixels: Vec::new() }
+    }
+
+    fn source(&mut self, input: &[u8], width: usize, height: usize) -> Source<'_> {
+        self.pixels.clear();
+        self.pixels.extend(
+            input
+                .chunks_exact(4)
+                .take(width * height)
+                .map(|color| Pixel::new([color[0], color[1], color[2]])),
+        );
+        Source {
+            pixels: &self.pixels,
+            width,
+            height,
+        }
+    }
+
+    /// Scales a native resolution frame with the upscaler for `filter`, copying it unchanged
+    /// for filters that don't upscale.
+    pub fn upscale(&mut self, filter: VideoFilter, source: &[u8], output: &mut [u8]) {
+        let (width, height) = (Ppu::WIDTH as usize, Ppu::HEIGHT as usize);
+        match filter {
+            VideoFilter::Smooth2x => self.smooth2x(source, width, height, output),
+            VideoFilter::Edge2x => self.edge2x(source, width, height, output),
+            VideoFilter::Pixellate | VideoFilter::Ntsc | VideoFilter::Crt => {
+                output.copy_from_slice(source);
+            }
+        }
+    }
+
+    /// Upscale `input` by 2x, smoothing diagonal edges between similar colors. Inspired by
+    /// HQ2X, but only blends each quadrant with its horizontal, vertical and diagonal neighbors
+    /// instead of using HQ2X's full interpolation table.
+    ///
+    /// # Panics
+    ///
+    /// If `input` holds fewer than `width * height` pixels or `output` is smaller than the
+    /// scaled frame.
+    pub fn smooth2x(&mut self, input: &[u8], width: usize, height: usize, output: &mut [u8]) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        let source = self.source(input, width, height);
+        source.scale(output, |x, y, sx, sy| {
+            let center = source.at(x, y, 0, 0);
+            let horizontal = source.at(x, y, sx, 0);
+            let vertical = source.at(x, y, 0, sy);
+            let diagonal = source.at(x, y, sx, sy);
+            if center.differs(horizontal) && center.differs(vertical) {
+                if horizontal.differs(vertical) {
+                    blend(&[(center, 6), (horizontal, 1), (vertical, 1)])
+                } else if center.differs(diagonal) {
+                    blend(&[(center, 2), (horizontal, 3), (vertical, 3)])
+                } else {
+                    blend(&[(center, 2), (horizontal, 1), (vertical, 1)])
+                }
+            } else {
+                center.rgb
+            }
+        });
+    }
+
+    /// Upscale `input` by 2x with edge-directed interpolation. Uses the edge detection rule of
+    /// xBR, without xBR's multiple edge angles or xBRZ's color distance tuning.
+    ///
+    /// Each output corner compares the color gradients along both diagonals of the surrounding
+    /// 4x4 area and blends towards the closest neighbor when an edge runs across the corner.
+    ///
+    /// # Panics
+    ///
+    /// If `input` holds fewer than `width * height` pixels or `output` is smaller than the
+    /// scaled frame.
+    pub fn edge2x(&mut self, input: &[u8], width: usize, height: usize, output: &mut [u8]) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        let source = self.source(input, width, height);
+        source.scale(output, |x, y, sx, sy| {
+            // Neighbors are named as if scaling the bottom-right corner and mirrored for the others:
+            //
+            //     B
+            //  D  E  F  F4
+            //     H  I  I4
+            //  G  H5 I5
+            let px = |dx: isize, dy: isize| source.at(x, y, dx * sx, dy * sy);
+            let (e, f, h, i) = (px(0, 0), px(1, 0), px(0, 1), px(1, 1));
+            if e.rgb == f.rgb || e.rgb == h.rgb {
+                return e.rgb;
+            }
+            let (b, c, d, g) = (px(0, -1), px(1, -1), px(-1, 0), px(-1, 1));
+            let (f4, i4, h5, i5) = (px(2, 0), px(2, 1), px(0, 2), px(1, 2));
+            let edge =
+                e.distance(c) + e.distance(g) + i.distance(f4) + i.distance(h5) + 4 * h.distance(f);
+            let cross =
+                h.distance(d) + h.distance(i5) + f.distance(i4) + f.distance(b) + 4 * e.distance(i);
+            if edge < cross {
+                let closest = if e.distance(f) <= e.distance(h) { f } else { h };
+                blend(&[(e, 1), (closest, 1)])
+            } else {
+                e.rgb
+            }
+        });
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    const SIZE: usize = 8;
+
+    /// A frame that's black above the anti-diagonal and white below it.
+    fn diagonal_frame() -> Vec<u8> {
+        (0..SIZE * SIZE)
+            .flat_map(|idx| {
+                let color = if idx % SIZE + idx / SIZE >= SIZE {
+                    0xFF
+                } else {
+                    0x00
+                };
+                [color, color, color, 0xFF]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn upscalers() {
+        let mut upscaler = Upscaler::new();
+        for upscale in [Upscaler::smooth2x, Upscaler::edge2x] {
+            let flat = vec![0x40; SIZE * SIZE * 4];
+            let mut output = vec![0; flat.len() * SCALE * SCALE];
+            upscale(&mut upscaler, &flat, SIZE, SIZE, &mut output);
+            assert!(output
+                .chunks_exact(4)
+                .all(|color| color == [0x40, 0x40, 0x40, 0xFF]));
+
+            upscale(&mut upscaler, &diagonal_frame(), SIZE, SIZE, &mut output);
+            assert!(
+                output
+                    .chunks_exact(4)
+                    .any(|color| color[0] != 0x00 && color[0] != 0xFF),
+                "diagonal edge is smoothed"
+            );
+            // Corners far from the edge are unchanged
+            assert_eq!(output[..4], [0x00, 0x00, 0x00, 0xFF]);
+            assert_eq!(output[output.len() - 4..], [0xFF; 4]);
+        }
+    }
+}
@@ -0,0 +1,27 @@
+// Built-in shader for the CRT video filter, mimicking a curved CRT television with scanlines.
+//
+// #pragma parameter CRT_CURVATURE "Screen Curvature" 0.08 0.0 0.3 0.01
+// #pragma parameter CRT_SCANLINES "Scanline Intensity" 0.3 0.0 1.0 0.05
+// #pragma parameter CRT_VIGNETTE "Vignette" 0.35 0.0 1.0 0.05
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Map to -1.0..=1.0 and push coordinates outwards based on distance from center
+    var pos = in.uv * 2.0 - 1.0;
+    pos = pos * (1.0 + pos.yx * pos.yx * param(0u));
+    let uv = pos * 0.5 + 0.5;
+
+    // Sampled before any branching, as sampling requires uniform control flow
+    var color = textureSample(source, source_sampler, uv).rgb;
+
+    // Darken towards the edges of each source line
+    let line = fract(uv.y * uniforms.source_size.y);
+    color *= 1.0 - param(1u) * (0.5 + 0.5 * cos(line * 6.2831855));
+
+    let edge = uv * (1.0 - uv);
+    color *= mix(1.0, sqrt(clamp(edge.x * edge.y * 16.0, 0.0, 1.0)), param(2u));
+
+    // Outside the curved screen
+    let inside = all(uv >= vec2<f32>(0.0)) && all(uv <= vec2<f32>(1.0));
+    return vec4<f32>(select(vec3<f32>(0.0), color, inside), 1.0);
+}
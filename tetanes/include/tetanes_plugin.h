@@ -0,0 +1,106 @@
+/*
+ * Plugin ABI for the TetaNES frontend.
+ *
+ * Plugins are dynamic libraries placed in the `plugins` directory of the TetaNES configuration
+ * directory. Each one exports `tetanes_plugin_init`, which fills in a `TetanesPlugin` and returns
+ * `true` to be loaded. Callbacks run on the emulation thread. The `TetanesHost` pointer passed to
+ * callbacks is only valid for the duration of that callback.
+ *
+ * Example plugin showing the frame number as an overlay:
+ *
+ *     static const TetanesHostApi *api;
+ *     static char text[32];
+ *
+ *     static void on_event(void *user_data, TetanesHost *host, const TetanesPluginEvent *event) {
+ *         if (event->kind == TETANES_PLUGIN_EVENT_FRAME) {
+ *             snprintf(text, sizeof(text), "Frame %u", event->frame);
+ *             api->set_overlay(host, text);
+ *         }
+ *     }
+ *
+ *     bool tetanes_plugin_init(const TetanesHostApi *host_api, TetanesHost *host,
+ *                              TetanesPlugin *plugin) {
+ *         api = host_api;
+ *         plugin->api_version = TETANES_PLUGIN_API_VERSION;
+ *         plugin->name = "Frame Counter";
+ *         plugin->on_event = on_event;
+ *         return true;
+ *     }
+ */
+
+#ifndef TETANES_PLUGIN_H
+#define TETANES_PLUGIN_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Only incremented for breaking changes. New host functions are appended to `TetanesHostApi` and
+ * new callbacks to `TetanesPlugin`. Plugins reporting a different version are unloaded without
+ * calling any of their callbacks, including `destroy`, so `tetanes_plugin_init` should check
+ * `api->api_version` and return `false` before allocating anything if it doesn't match.
+ */
+#define TETANES_PLUGIN_API_VERSION 1
+
+/* Event kinds. */
+#define TETANES_PLUGIN_EVENT_ROM_LOADED 0   /* `rom_name` is set. */
+#define TETANES_PLUGIN_EVENT_ROM_UNLOADED 1
+#define TETANES_PLUGIN_EVENT_FRAME 2        /* `frame` is set. */
+#define TETANES_PLUGIN_EVENT_RESET 3
+#define TETANES_PLUGIN_EVENT_PAUSED 4
+#define TETANES_PLUGIN_EVENT_RESUMED 5
+
+/* Opaque host handle. */
+typedef struct TetanesHost TetanesHost;
+
+typedef struct TetanesPluginEvent {
+    uint32_t kind;
+    uint32_t frame;
+    const char *rom_name;
+} TetanesPluginEvent;
+
+typedef struct TetanesHostApi {
+    uint32_t api_version;
+    /* Read a byte from the CPU address space without side effects. */
+    uint8_t (*peek)(TetanesHost *host, uint16_t addr);
+    /* Current frame number. */
+    uint32_t (*frame_number)(TetanesHost *host);
+    /* Add a menu item, or rename it if `id` is already registered. */
+    void (*add_menu_item)(TetanesHost *host, uint32_t id, const char *label);
+    /* Set the overlay text drawn over the NES frame. NULL or empty clears it. */
+    void (*set_overlay)(TetanesHost *host, const char *text);
+    /* Show a message notification. */
+    void (*show_message)(TetanesHost *host, const char *text);
+} TetanesHostApi;
+
+/* Filled in by `tetanes_plugin_init`. Callbacks may be NULL. */
+typedef struct TetanesPlugin {
+    /* `TETANES_PLUGIN_API_VERSION` the plugin was built against. */
+    uint32_t api_version;
+    /* Plugin name, valid until `destroy` is called. */
+    const char *name;
+    /* Plugin state passed to every callback. */
+    void *user_data;
+    void (*on_event)(void *user_data, TetanesHost *host, const TetanesPluginEvent *event);
+    void (*on_menu_item)(void *user_data, TetanesHost *host, uint32_t id);
+    void (*destroy)(void *user_data);
+    /*
+     * Called at the start of each visible scanline (0-239) with the 32 byte palette used to render
+     * it, indexed like palette RAM at $3F00-$3F1F. Changes only affect the rendered output. Runs
+     * mid-frame, so `host` functions aren't available.
+     */
+    void (*on_scanline)(void *user_data, uint32_t scanline, uint8_t *palette);
+} TetanesPlugin;
+
+/* Exported by plugins. */
+bool tetanes_plugin_init(const TetanesHostApi *api, TetanesHost *host, TetanesPlugin *plugin);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TETANES_PLUGIN_H */
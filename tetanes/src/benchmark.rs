@@ -0,0 +1,69 @@
+//! Headless benchmark that runs a ROM for a number of frames as fast as possible and reports the
+//! average speed and checksums, e.g. to compare machines or bisect performance regressions.
+
+use anyhow::Context;
+use std::{path::Path, time::Instant};
+use tetanes::nes::config::Config;
+use tetanes_core::{
+    common::Regional,
+    compat,
+    control_deck::{ControlDeck, HeadlessMode},
+    fs,
+    mem::RamState,
+    ppu::Ppu,
+};
+
+/// Frames run when `--frames` isn't provided.
+pub const DEFAULT_FRAMES: u32 = 600;
+
+/// Run the ROM at `path` for `frames` frames with the deck settings from `cfg` and print the
+/// results.
+///
+/// Audio is still mixed unless it's disabled, e.g. with `--silent`, so results reflect the full
+/// cost of emulation. Battery-backed RAM is never loaded or saved and random power-up RAM is seeded
+/// so that checksums are reproducible.
+pub fn run(path: &Path, frames: u32, cfg: Config) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        path.is_file(),
+        "headless mode requires a ROM file, found {path:?}"
+    );
+
+    let mut deck_cfg = cfg.deck;
+    deck_cfg.data_dir = None;
+    if deck_cfg.ram_state == RamState::Random {
+        deck_cfg.ram_state = RamState::Seeded(0);
+    }
+    if !cfg.audio.enabled {
+        deck_cfg.headless_mode |= HeadlessMode::NO_AUDIO;
+    }
+    let mut deck = ControlDeck::with_config(deck_cfg);
+    let rom = deck
+        .load_rom_path(path)
+        .with_context(|| format!("failed to load rom {path:?}"))?;
+    println!("Running {} for {frames} frames...", rom.name);
+
+    let start = Instant::now();
+    for frame in 0..frames {
+        deck.clock_frame()
+            .with_context(|| format!("failed to clock frame {frame}"))?;
+        deck.clear_audio_samples();
+    }
+    let elapsed = start.elapsed();
+
+    let secs = elapsed.as_secs_f64();
+    let fps = if secs > 0.0 {
+        f64::from(frames) / secs
+    } else {
+        0.0
+    };
+    let realtime = fps / f64::from(Ppu::region_frame_rate(deck.region()));
+    println!("Elapsed: {secs:.3}s");
+    println!("Average FPS: {fps:.1} ({realtime:.2}x realtime)");
+    println!(
+        "Frame Hash: {:016X}",
+        compat::frame_hash(deck.frame_buffer_raw())
+    );
+    println!("RAM CRC32: {:08X}", fs::compute_crc32(deck.wram()));
+
+    Ok(())
+}
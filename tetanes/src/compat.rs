@@ -0,0 +1,318 @@
+//! Compatibility sweep that runs every ROM in a directory headless and writes an HTML and JSON
+//! report, e.g. to track regressions across releases.
+
+use anyhow::Context;
+use chrono::Local;
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fmt::Write,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+use tetanes::nes::config::Config;
+use tetanes_core::{
+    compat::{self, RomReport, Status},
+    control_deck,
+};
+
+/// Compatibility sweep options.
+#[derive(Args, Debug)]
+#[must_use]
+pub struct CompatOpts {
+    /// Directory searched recursively for `.nes` ROM files.
+    pub(crate) dir: PathBuf,
+    /// Number of frames to run each ROM for.
+    #[arg(short = 'n', long, default_value_t = 600)]
+    pub(crate) frames: u32,
+    /// Report path, without extension. Writes `<OUTPUT>.html` and `<OUTPUT>.json`.
+    #[arg(short, long, default_value = "compat_report")]
+    pub(crate) output: PathBuf,
+    /// JSON report from a previous sweep to compare against, highlighting regressions.
+    #[arg(short, long)]
+    pub(crate) baseline: Option<PathBuf>,
+}
+
+/// How a ROM's result differs from the baseline report.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub enum Change {
+    /// Worked in the baseline but no longer does.
+    Regressed,
+    /// Didn't work in the baseline but now does.
+    Fixed,
+    /// Status is the same, but the last frame rendered differs.
+    FrameChanged,
+    /// Not in the baseline.
+    New,
+}
+
+impl Change {
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Regressed => "Regressed",
+            Self::Fixed => "Fixed",
+            Self::FrameChanged => "Frame Changed",
+            Self::New => "New",
+        }
+    }
+}
+
+/// A ROM result along with how it changed since the baseline, if any.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct Entry {
+    #[serde(flatten)]
+    pub report: RomReport,
+    pub change: Option<Change>,
+}
+
+/// A full compatibility sweep report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct Report {
+    pub version: String,
+    pub date: String,
+    pub frames: u32,
+    pub roms: Vec<Entry>,
+}
+
+impl Report {
+    /// Number of ROMs with each status label, in order of first appearance.
+    fn summary(&self) -> Vec<(&'static str, usize)> {
+        let mut summary = Vec::<(&'static str, usize)>::new();
+        for entry in &self.roms {
+            let label = entry.report.status.label();
+            match summary.iter_mut().find(|(l, _)| *l == label) {
+                Some((_, count)) => *count += 1,
+                None => summary.push((label, 1)),
+            }
+        }
+        summary
+    }
+
+    fn regressions(&self) -> usize {
+        self.roms
+            .iter()
+            .filter(|entry| entry.change == Some(Change::Regressed))
+            .count()
+    }
+
+    /// Render the report as a self-contained HTML page.
+    fn to_html(&self) -> String {
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>TetaNES Compatibility Report</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; background: #1b1b1b; color: #ddd; }}
+table {{ border-collapse: collapse; margin-bottom: 2em; }}
+th, td {{ border: 1px solid #444; padding: 4px 8px; text-align: left; }}
+th {{ background: #2b2b2b; }}
+td.num {{ text-align: right; font-family: monospace; }}
+tr.ok td.status {{ color: #6c6; }}
+tr.warn td.status {{ color: #dc6; }}
+tr.fail td.status {{ color: #e66; }}
+td.change {{ font-weight: bold; }}
+</style>
+</head>
+<body>
+<h1>TetaNES Compatibility Report</h1>
+<p>Version {} &middot; {} &middot; {} frames per ROM</p>
+<table>
+<tr><th>Status</th><th>ROMs</th></tr>
+"#,
+            escape(&self.version),
+            escape(&self.date),
+            self.frames,
+        );
+        for (label, count) in self.summary() {
+            let _ = writeln!(
+                html,
+                r#"<tr><td>{label}</td><td class="num">{count}</td></tr>"#
+            );
+        }
+        let _ = writeln!(
+            html,
+            r#"<tr><th>Total</th><th class="num">{}</th></tr>
+</table>
+<table>
+<tr><th>ROM</th><th>Status</th><th>Change</th><th>Mapper</th><th>CRC32</th><th>Frames</th><th>Unique Frames</th><th>FPS</th></tr>"#,
+            self.roms.len()
+        );
+        for Entry { report, change } in &self.roms {
+            let class = match report.status {
+                Status::Ok => "ok",
+                Status::BlackScreen | Status::UnsupportedMapper(_) => "warn",
+                Status::Crashed(_) | Status::LoadFailed(_) => "fail",
+            };
+            let _ = writeln!(
+                html,
+                r#"<tr class="{class}"><td>{}</td><td class="status">{}</td><td class="change">{}</td><td>{}</td><td class="num">{}</td><td class="num">{}</td><td class="num">{}</td><td class="num">{:.1}</td></tr>"#,
+                escape(&report.name),
+                escape(&report.status.to_string()),
+                change.map_or("", Change::label),
+                escape(report.mapper.as_deref().unwrap_or("")),
+                report
+                    .crc32
+                    .map_or_else(String::new, |crc32| format!("{crc32:08X}")),
+                report.frames,
+                report.distinct_frames,
+                report.fps(),
+            );
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+}
+
+/// Escape text for inclusion in HTML.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Recursively find `.nes` files in `dir`, sorted by path.
+fn find_roms(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut roms = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in dir
+            .read_dir()
+            .with_context(|| format!("failed to read directory {dir:?}"))?
+        {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case(OsStr::new("nes")))
+            {
+                roms.push(path);
+            }
+        }
+    }
+    roms.sort();
+    Ok(roms)
+}
+
+/// Run a single ROM, recording panics as crashes.
+fn check_rom(cfg: &control_deck::Config, name: &str, path: &Path, frames: u32) -> RomReport {
+    let rom = match std::fs::read(path) {
+        Ok(rom) => rom,
+        Err(err) => return RomReport::new(name, Status::LoadFailed(err.to_string())),
+    };
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        compat::check_rom(cfg.clone(), name, &mut rom.as_slice(), frames)
+    }))
+    .unwrap_or_else(|err| {
+        let reason = err
+            .downcast_ref::<&str>()
+            .map(ToString::to_string)
+            .or_else(|| err.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        RomReport::new(name, Status::Crashed(format!("panicked: {reason}")))
+    })
+}
+
+impl CompatOpts {
+    /// Run the sweep with the deck settings from `cfg` and write the reports.
+    pub fn run(self, cfg: Config) -> anyhow::Result<()> {
+        let baseline = self
+            .baseline
+            .as_ref()
+            .map(|path| {
+                let data = std::fs::read(path)
+                    .with_context(|| format!("failed to read baseline report {path:?}"))?;
+                let report = serde_json::from_slice::<Report>(&data)
+                    .with_context(|| format!("invalid baseline report {path:?}"))?;
+                if report.frames != self.frames {
+                    tracing::warn!(
+                        "baseline report ran {} frames per ROM instead of {}, results may differ",
+                        report.frames,
+                        self.frames
+                    );
+                }
+                anyhow::Ok(
+                    report
+                        .roms
+                        .into_iter()
+                        .map(|entry| (entry.report.name.clone(), entry.report))
+                        .collect::<HashMap<_, _>>(),
+                )
+            })
+            .transpose()?;
+
+        let paths = find_roms(&self.dir)?;
+        anyhow::ensure!(!paths.is_empty(), "no `.nes` files found in {:?}", self.dir);
+
+        let mut roms = Vec::with_capacity(paths.len());
+        for (index, path) in paths.iter().enumerate() {
+            let name = path
+                .strip_prefix(&self.dir)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .into_owned();
+            println!("[{}/{}] {name}", index + 1, paths.len());
+            let report = check_rom(&cfg.deck, &name, path, self.frames);
+            println!("    {} ({:.1} fps)", report.status, report.fps());
+            let change = baseline.as_ref().and_then(|baseline| {
+                let Some(previous) = baseline.get(&name) else {
+                    return Some(Change::New);
+                };
+                match (previous.status.is_ok(), report.status.is_ok()) {
+                    (true, false) => Some(Change::Regressed),
+                    (false, true) => Some(Change::Fixed),
+                    _ if previous.crc32 == report.crc32
+                        && previous.frames == report.frames
+                        && previous.frame_hash != report.frame_hash =>
+                    {
+                        Some(Change::FrameChanged)
+                    }
+                    _ => None,
+                }
+            });
+            roms.push(Entry { report, change });
+        }
+
+        let report = Report {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            date: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            frames: self.frames,
+            roms,
+        };
+        let json_path = self.output.with_extension("json");
+        let data = serde_json::to_vec_pretty(&report).context("failed to serialize report")?;
+        std::fs::write(&json_path, data)
+            .with_context(|| format!("failed to write {json_path:?}"))?;
+        let html_path = self.output.with_extension("html");
+        std::fs::write(&html_path, report.to_html())
+            .with_context(|| format!("failed to write {html_path:?}"))?;
+
+        println!();
+        for (label, count) in report.summary() {
+            println!("{label}: {count}");
+        }
+        if baseline.is_some() {
+            println!("Regressions: {}", report.regressions());
+        }
+        println!("Wrote {json_path:?} and {html_path:?}");
+
+        Ok(())
+    }
+}
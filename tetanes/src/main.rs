@@ -2,42 +2,94 @@
 //!
 //! USAGE:
 //!     tetanes [FLAGS] [OPTIONS] [path]
+//!     tetanes compat [OPTIONS] <dir>
+//!     tetanes rom-info <path>
+//!     tetanes tui [OPTIONS] <path>
+//!     tetanes verify-movie [OPTIONS] <rom> <movie>
 //!
 //! FLAGS:
 //!     -f, --fullscreen    Start fullscreen.
+//!         --headless      Run headless as fast as possible and print the average FPS and
+//!                         checksums.
 //!     -h, --help          Prints help information
+//!         --portable      Store data in a `userdata` directory next to the executable
 //!     -V, --version       Prints version information
 //!
 //! OPTIONS:
 //!     -s, --scale <scale>    Window scale [default: 3.0]
+//!         --frames <n>       Number of frames to run with `--headless` [default: 600]
+//!         --set <key=value>  Override a config setting by its JSON path, e.g. `deck.filter=Ntsc`
 //!
 //! ARGS:
 //!     <path>    The NES ROM to load, a directory containing `.nes` ROM files, or a recording
 //!               playback `.playback` file. [default: current directory]
+//!
+//! SUBCOMMANDS:
+//!     compat    Run every ROM in a directory headless and write an HTML and JSON compatibility
+//!               report.
+//!     rom-info  Print a ROM's parsed header details, hashes and database matches.
+//!     tui       Inspect a ROM running headless in the terminal. Requires the `tui` feature.
+//!     verify-movie
+//!               Play back an FCEUX `.fm2` TAS movie headless, reporting the end state hash,
+//!               runtime and desync frame compared to a previous sync log.
 
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use tetanes::{logging, nes::Nes};
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod benchmark;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod compat;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod opts;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod rom_info;
+#[cfg(all(not(target_arch = "wasm32"), feature = "tui"))]
+pub mod tui;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod verify_movie;
 
 fn main() -> anyhow::Result<()> {
+    // Portable mode has to be enabled before logging to a file
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut opts = {
+        use clap::Parser;
+        let opts = opts::Opts::parse();
+        tetanes::nes::config::Config::init_portable(opts.portable);
+        opts
+    };
     let _log = logging::init();
     #[cfg(feature = "profiling")]
     puffin::set_scopes_on(true);
 
+    // Browser storage has to be loaded asynchronously before the configuration can be read
     #[cfg(target_arch = "wasm32")]
-    let config = tetanes::nes::config::Config::load(None);
+    tetanes::thread::spawn(async {
+        if let Err(err) = tetanes_core::fs::load_storage().await {
+            tracing::error!("failed to load browser storage: {err:?}");
+        }
+        let config = tetanes::nes::config::Config::load(None);
+        if let Err(err) = Nes::run(config) {
+            tracing::error!("{err:?}");
+        }
+    });
     #[cfg(not(target_arch = "wasm32"))]
-    let config = {
-        use clap::Parser;
-        let opts = opts::Opts::parse();
+    {
         tracing::debug!("CLI Options: {opts:?}");
-        opts.load()?
-    };
-
-    Nes::run(config)?;
+        if let Some(dir) = tetanes_core::control_deck::Config::portable_dir() {
+            tracing::info!("Portable mode, storing data in {dir:?}");
+        }
+        if let Some(command) = opts.command.take() {
+            return command.run(opts.load()?);
+        }
+        if opts.headless {
+            let path = opts.path.clone().unwrap_or_default();
+            let frames = opts.frames.unwrap_or(benchmark::DEFAULT_FRAMES);
+            return benchmark::run(&path, frames, opts.load()?);
+        }
+        Nes::run(opts.load()?)?;
+    }
 
     Ok(())
 }
@@ -2,14 +2,16 @@
 
 use crate::{
     nes::{
+        action::Action,
         event::{RendererEvent, SendNesEvent, UiEvent},
-        input::{Gamepads, InputBindings},
+        input::{Gamepads, HeldAction, InputBindings},
         renderer::{FrameRecycle, Resources},
+        shutdown::Shutdown,
     },
     platform::{EventLoopExt, Initialize},
     thread,
 };
-use config::Config;
+use config::{Config, ConfigWatcher};
 use crossbeam::channel::{self, Receiver};
 use egui::{ahash::HashMap, ViewportBuilder};
 use egui_wgpu::winit::Painter;
@@ -28,12 +30,18 @@ use winit::{
 pub mod action;
 pub mod audio;
 pub mod config;
+pub mod debug_session;
 pub mod emulation;
 pub mod event;
 pub mod input;
+pub mod library;
+pub mod plugin;
 pub mod renderer;
 pub mod rom;
+pub mod rom_db;
+pub mod shutdown;
 pub mod version;
+pub mod view;
 
 /// Represents all the NES Emulation state.
 #[derive(Debug)]
@@ -72,19 +80,30 @@ impl State {
 #[derive(Debug)]
 pub(crate) struct Running {
     pub(crate) cfg: Config,
+    /// Last configuration saved to disk, to only flush changes when auto-saving.
+    pub(crate) saved_cfg: Config,
+    /// Watches the configuration file to apply settings edited while running.
+    pub(crate) config_watcher: ConfigWatcher,
     // Only used by wasm currently
     #[allow(unused)]
     pub(crate) tx: EventLoopProxy<NesEvent>,
     pub(crate) emulation: Emulation,
     pub(crate) renderer: Renderer,
     pub(crate) input_bindings: InputBindings,
+    /// Actions held down that repeat while held.
+    pub(crate) held_actions: HashMap<Action, HeldAction>,
     pub(crate) gamepads: Gamepads,
     pub(crate) modifiers: Modifiers,
     pub(crate) paused: bool,
+    pub(crate) fast_forwarding: bool,
     pub(crate) replay_recording: bool,
     pub(crate) audio_recording: bool,
+    pub(crate) map_dumping: bool,
+    pub(crate) sprite_ripping: bool,
     pub(crate) rewinding: bool,
     pub(crate) repaint_times: HashMap<WindowId, Instant>,
+    /// Set once exiting, dropped last so the shutdown watchdog covers dropping everything else.
+    pub(crate) shutdown: Option<Shutdown>,
 }
 
 impl Nes {
@@ -94,8 +113,31 @@ impl Nes {
     ///
     /// If event loop fails to build or run, then an error is returned.
     pub fn run(cfg: Config) -> anyhow::Result<()> {
-        // Set up window, events and NES state
         let event_loop = EventLoopBuilder::<NesEvent>::with_user_event().build()?;
+        Self::run_event_loop(cfg, event_loop)
+    }
+
+    /// Runs the NES application as an Android activity by starting the event loop.
+    ///
+    /// # Errors
+    ///
+    /// If event loop fails to build or run, then an error is returned.
+    #[cfg(target_os = "android")]
+    pub fn run_android(
+        cfg: Config,
+        app: winit::platform::android::activity::AndroidApp,
+    ) -> anyhow::Result<()> {
+        use winit::platform::android::EventLoopBuilderExtAndroid;
+
+        crate::sys::platform::set_android_app(app.clone());
+        let event_loop = EventLoopBuilder::<NesEvent>::with_user_event()
+            .with_android_app(app)
+            .build()?;
+        Self::run_event_loop(cfg, event_loop)
+    }
+
+    fn run_event_loop(cfg: Config, event_loop: EventLoop<NesEvent>) -> anyhow::Result<()> {
+        // Set up window, events and NES state
         let mut nes = Nes::new(cfg, &event_loop);
         event_loop
             .run_platform(move |event, window_target| nes.event_loop(event, window_target))?;
@@ -189,18 +231,25 @@ impl Nes {
                 let gamepads = Gamepads::new();
                 cfg.input.update_gamepad_assignments(&gamepads);
                 let mut running = Running {
+                    saved_cfg: cfg.clone(),
                     cfg,
+                    config_watcher: ConfigWatcher::new(),
                     tx,
                     emulation,
                     renderer,
                     input_bindings,
+                    held_actions: HashMap::default(),
                     gamepads,
                     modifiers: Modifiers::default(),
                     paused: false,
+                    fast_forwarding: false,
                     replay_recording: false,
                     audio_recording: false,
+                    map_dumping: false,
+                    sprite_ripping: false,
                     rewinding: false,
                     repaint_times: HashMap::default(),
+                    shutdown: None,
                 };
                 running.initialize()?;
                 self.state = State::Running(running);
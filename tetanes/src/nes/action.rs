@@ -2,7 +2,7 @@
 //! allows for event handling and test abstractions such as being able to map a custom keybind to a
 //! given state change.
 
-use crate::nes::renderer::gui::Menu;
+use crate::nes::{input::RepeatPolicy, renderer::gui::Menu};
 use serde::{Deserialize, Serialize};
 use tetanes_core::{
     action::Action as DeckAction,
@@ -24,14 +24,16 @@ pub enum Action {
 }
 
 impl Action {
-    pub const BINDABLE: [Self; 109] = [
+    pub const BINDABLE: [Self; 121] = [
         Self::Ui(Ui::Quit),
         Self::Ui(Ui::TogglePause),
+        Self::Ui(Ui::FrameAdvance),
         Self::Ui(Ui::LoadRom),
         Self::Ui(Ui::UnloadRom),
         Self::Ui(Ui::LoadReplay),
         Self::Menu(Menu::About),
         Self::Menu(Menu::Keybinds),
+        Self::Menu(Menu::Menubar),
         Self::Menu(Menu::PerfStats),
         Self::Menu(Menu::Preferences),
         Self::Feature(Feature::ToggleReplayRecording),
@@ -39,6 +41,8 @@ impl Action {
         Self::Feature(Feature::VisualRewind),
         Self::Feature(Feature::InstantRewind),
         Self::Feature(Feature::TakeScreenshot),
+        Self::Feature(Feature::ToggleMapDump),
+        Self::Feature(Feature::ToggleSpriteRip),
         Self::Setting(Setting::ToggleFullscreen),
         Self::Setting(Setting::ToggleAudio),
         Self::Setting(Setting::ToggleCycleAccurate),
@@ -47,6 +51,7 @@ impl Action {
         Self::Setting(Setting::ToggleMenubar),
         Self::Setting(Setting::ToggleMessages),
         Self::Setting(Setting::ToggleFps),
+        Self::Setting(Setting::ToggleAudioScope),
         Self::Setting(Setting::FastForward),
         Self::Setting(Setting::IncrementScale),
         Self::Setting(Setting::DecrementScale),
@@ -97,9 +102,13 @@ impl Action {
         Self::Deck(DeckAction::ToggleZapperConnected),
         // Self::Deck(DeckAction::ZapperAim), // Binding doesn't make sense
         Self::Deck(DeckAction::ZapperTrigger),
+        Self::Deck(DeckAction::InsertCoin(1)),
+        Self::Deck(DeckAction::InsertCoin(2)),
+        Self::Deck(DeckAction::VsServiceButton),
         Self::Deck(DeckAction::FourPlayer(FourPlayer::Disabled)),
         Self::Deck(DeckAction::FourPlayer(FourPlayer::FourScore)),
         Self::Deck(DeckAction::FourPlayer(FourPlayer::Satellite)),
+        Self::Deck(DeckAction::FourPlayer(FourPlayer::FamicomAdapter)),
         // Only allow bindings up to 8 slots
         Self::Deck(DeckAction::SetSaveSlot(1)),
         Self::Deck(DeckAction::SetSaveSlot(2)),
@@ -138,6 +147,9 @@ impl Action {
         Self::Deck(DeckAction::SetNesRegion(NesRegion::Dendy)),
         Self::Deck(DeckAction::SetVideoFilter(VideoFilter::Pixellate)),
         Self::Deck(DeckAction::SetVideoFilter(VideoFilter::Ntsc)),
+        Self::Deck(DeckAction::SetVideoFilter(VideoFilter::Crt)),
+        Self::Deck(DeckAction::SetVideoFilter(VideoFilter::Smooth2x)),
+        Self::Deck(DeckAction::SetVideoFilter(VideoFilter::Edge2x)),
         Self::Debug(Debug::Toggle(Debugger::Cpu)),
         Self::Debug(Debug::Toggle(Debugger::Ppu)),
         Self::Debug(Debug::Toggle(Debugger::Apu)),
@@ -151,6 +163,54 @@ impl Action {
     pub const fn is_joypad(&self) -> bool {
         matches!(self, Action::Deck(DeckAction::Joypad(_)))
     }
+
+    /// Whether the [`RepeatPolicy`] of this action can be configured. Actions that are held, like
+    /// joypad buttons, or that should only ever happen once per press, like saving state or
+    /// taking a screenshot, always use their default policy.
+    pub const fn is_repeatable(&self) -> bool {
+        !matches!(
+            self,
+            Action::Ui(Ui::Quit | Ui::LoadRom | Ui::LoadReplay | Ui::UnloadRom)
+                | Action::Feature(
+                    Feature::ToggleReplayRecording
+                        | Feature::ToggleAudioRecording
+                        | Feature::VisualRewind
+                        | Feature::TakeScreenshot
+                        | Feature::ToggleMapDump
+                        | Feature::ToggleSpriteRip
+                )
+                | Action::Setting(Setting::FastForward)
+                | Action::Deck(
+                    DeckAction::Reset(_)
+                        | DeckAction::Joypad(_)
+                        | DeckAction::ZapperAim(_)
+                        | DeckAction::ZapperAimOffscreen
+                        | DeckAction::ZapperTrigger
+                        | DeckAction::InsertCoin(_)
+                        | DeckAction::VsServiceButton
+                        | DeckAction::SaveState
+                        | DeckAction::LoadState
+                )
+        )
+    }
+
+    /// Returns the [`RepeatPolicy`] used when one isn't configured.
+    pub const fn default_repeat(&self) -> RepeatPolicy {
+        match self {
+            // Rewinding starts once held past the repeat delay, instead of instantly rewinding
+            // on release
+            Action::Feature(Feature::VisualRewind)
+            | Action::Setting(
+                Setting::IncrementScale
+                | Setting::DecrementScale
+                | Setting::IncrementSpeed
+                | Setting::DecrementSpeed,
+            )
+            | Action::Ui(Ui::FrameAdvance)
+            | Action::Debug(Debug::Step(_)) => RepeatPolicy::DEFAULT_DELAY_RATE,
+            _ => RepeatPolicy::None,
+        }
+    }
 }
 
 impl std::fmt::Display for Action {
@@ -165,6 +225,7 @@ impl AsRef<str> for Action {
             Action::Ui(ui) => match ui {
                 Ui::Quit => "Quit",
                 Ui::TogglePause => "Toggle Pause",
+                Ui::FrameAdvance => "Frame Advance",
                 Ui::LoadRom => "Load ROM",
                 Ui::UnloadRom => "Unload ROM",
                 Ui::LoadReplay => "Load Replay",
@@ -172,6 +233,7 @@ impl AsRef<str> for Action {
             Action::Menu(menu) => match menu {
                 Menu::About => "Toggle About Window",
                 Menu::Keybinds => "Toggle Keybinds Window",
+                Menu::Menubar => "Focus Menu Bar",
                 Menu::PerfStats => "Toggle Performance Stats Window",
                 Menu::Preferences => "Toggle Preferences Window",
             },
@@ -181,6 +243,8 @@ impl AsRef<str> for Action {
                 Feature::VisualRewind => "Visual Rewind",
                 Feature::InstantRewind => "Instant Rewind",
                 Feature::TakeScreenshot => "Take Screenshot",
+                Feature::ToggleMapDump => "Toggle Map Dump",
+                Feature::ToggleSpriteRip => "Toggle Sprite Rip",
             },
             Action::Setting(setting) => match setting {
                 Setting::ToggleFullscreen => "Toggle Fullscreen",
@@ -190,7 +254,8 @@ impl AsRef<str> for Action {
                 Setting::ToggleOverscan => "Toggle Overscan",
                 Setting::ToggleMenubar => "Toggle Menubar",
                 Setting::ToggleMessages => "Toggle Messages",
-                Setting::ToggleFps => "Toggle FPS",
+                Setting::ToggleAudioScope => "Toggle Audio Scope",
+                Setting::ToggleFps => "Toggle Performance Overlay",
                 Setting::FastForward => "Fast Forward",
                 Setting::IncrementScale => "Increment Scale",
                 Setting::DecrementScale => "Decrement Scale",
@@ -218,9 +283,16 @@ impl AsRef<str> for Action {
                 DeckAction::ZapperAim(_) => "Zapper Aim",
                 DeckAction::ZapperAimOffscreen => "Zapper Aim Offscreen (Hold)",
                 DeckAction::ZapperTrigger => "Zapper Trigger",
+                DeckAction::InsertCoin(1) => "Insert Coin 1",
+                DeckAction::InsertCoin(2) => "Insert Coin 2",
+                DeckAction::InsertCoin(_) => "Insert Coin N",
+                DeckAction::VsServiceButton => "Vs. Service Button",
                 DeckAction::FourPlayer(FourPlayer::Disabled) => "Disable Four Player Mode",
                 DeckAction::FourPlayer(FourPlayer::FourScore) => "Enable Four Player (FourScore)",
                 DeckAction::FourPlayer(FourPlayer::Satellite) => "Enable Four Player (Satellite)",
+                DeckAction::FourPlayer(FourPlayer::FamicomAdapter) => {
+                    "Enable Four Player (Famicom Adapter)"
+                }
                 DeckAction::SetSaveSlot(1) => "Set Save Slot 1",
                 DeckAction::SetSaveSlot(2) => "Set Save Slot 2",
                 DeckAction::SetSaveSlot(3) => "Set Save Slot 3",
@@ -260,6 +332,9 @@ impl AsRef<str> for Action {
                 DeckAction::SetVideoFilter(filter) => match filter {
                     VideoFilter::Pixellate => "Set Filter to Pixellate",
                     VideoFilter::Ntsc => "Set Filter to NTSC",
+                    VideoFilter::Crt => "Set Filter to CRT",
+                    VideoFilter::Smooth2x => "Set Filter to Smooth 2x",
+                    VideoFilter::Edge2x => "Set Filter to Edge 2x",
                 },
             },
             Action::Debug(debug) => match debug {
@@ -326,6 +401,8 @@ impl From<Debug> for Action {
 pub enum Ui {
     Quit,
     TogglePause,
+    /// Pause, or advance a single frame if already paused.
+    FrameAdvance,
     LoadRom,
     LoadReplay,
     UnloadRom,
@@ -338,6 +415,8 @@ pub enum Feature {
     VisualRewind,
     InstantRewind,
     TakeScreenshot,
+    ToggleMapDump,
+    ToggleSpriteRip,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -350,6 +429,7 @@ pub enum Setting {
     ToggleMenubar,
     ToggleMessages,
     ToggleFps,
+    ToggleAudioScope,
     FastForward,
     IncrementScale,
     DecrementScale,
@@ -6,13 +6,23 @@ use ringbuf::{
     traits::{Consumer, Observer, Split},
     CachingCons, CachingProd, HeapRb,
 };
-use std::{fs::File, io::BufWriter, iter, path::PathBuf, sync::Arc};
-use tetanes_core::time::Duration;
+use std::{
+    fs::File,
+    io::BufWriter,
+    iter,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tetanes_core::{
+    apu::{Apu, Channel},
+    time::Duration,
+};
 use tracing::{debug, error, info, trace, warn};
 
 type SampleRb = Arc<HeapRb<f32>>;
 type SampleProducer = CachingProd<SampleRb>;
 type SampleConsumer = CachingCons<SampleRb>;
+type WavWriter = hound::WavWriter<BufWriter<File>>;
 
 /// Represents the state of the audio stream.
 #[derive(Debug)]
@@ -44,6 +54,10 @@ pub struct Audio {
     pub latency: Duration,
     pub buffer_size: usize,
     pub host: cpal::Host,
+    /// Output volume scale applied to samples.
+    volume: f32,
+    /// Ratio to time-stretch samples by without changing their pitch.
+    time_stretch: f32,
     output: Option<Output>,
 }
 
@@ -54,6 +68,8 @@ impl std::fmt::Debug for Audio {
             .field("sample_rate", &self.sample_rate)
             .field("latency", &self.latency)
             .field("buffer_size", &self.buffer_size)
+            .field("volume", &self.volume)
+            .field("time_stretch", &self.time_stretch)
             .field("output", &self.output)
             .finish_non_exhaustive()
     }
@@ -84,6 +100,8 @@ impl Audio {
             latency,
             buffer_size,
             host,
+            volume: 1.0,
+            time_stretch: 1.0,
             output,
         }
     }
@@ -116,10 +134,21 @@ impl Audio {
             .as_mut()
             .and_then(|output| output.mixer.as_mut())
         {
-            mixer.process(samples);
+            mixer.process(samples, self.volume, self.time_stretch);
         }
     }
 
+    /// Set the output volume scale, e.g. to lower volume while fast-forwarding.
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+    }
+
+    /// Set the ratio to shorten processed samples by without changing their pitch. A ratio of
+    /// `1.0` disables time-stretching.
+    pub fn set_time_stretch(&mut self, ratio: f32) {
+        self.time_stretch = ratio.max(1.0);
+    }
+
     /// Returns the number of audio channels.
     #[must_use]
     pub fn channels(&self) -> u16 {
@@ -141,6 +170,21 @@ impl Audio {
             })
     }
 
+    /// Returns a ratio to adjust the emulation sample rate by to keep the queued audio near the
+    /// configured latency, based on how full the output buffer is. Produces fewer samples when the
+    /// buffer is filling up and more when it's draining, within `max_delta` of `1.0`.
+    #[must_use]
+    pub fn rate_control_ratio(&self, max_delta: f32) -> f32 {
+        self.output
+            .as_ref()
+            .and_then(|output| output.mixer.as_ref())
+            .filter(|mixer| mixer.sample_latency > 0)
+            .map_or(1.0, |mixer| {
+                let fill = mixer.producer.occupied_len() as f32 / mixer.sample_latency as f32;
+                1.0 + max_delta * (1.0 - fill).clamp(-1.0, 1.0)
+            })
+    }
+
     /// Pause or resume the audio output stream. If `paused` is false and the stream is not started
     /// yet, it will be started.
     pub fn pause(&mut self, paused: bool) {
@@ -189,19 +233,45 @@ impl Audio {
             .map_or(false, |mixer| mixer.recording.is_some())
     }
 
-    /// Start recording audio to a file.
-    pub fn start_recording(&mut self) -> anyhow::Result<()> {
+    /// Start recording audio to a file, optionally with a separate file per APU channel.
+    pub fn start_recording(&mut self, stems: bool) -> anyhow::Result<()> {
         if let Some(mixer) = &mut self
             .output
             .as_mut()
             .and_then(|output| output.mixer.as_mut())
         {
-            mixer.start_recording()
+            mixer.start_recording(stems)
         } else {
             Ok(())
         }
     }
 
+    /// Whether the current recording is writing per-channel stems.
+    pub fn is_recording_stems(&self) -> bool {
+        self.output
+            .as_ref()
+            .and_then(|output| output.mixer.as_ref())
+            .and_then(|mixer| mixer.recording.as_ref())
+            .map_or(false, |recording| !recording.stems.is_empty())
+    }
+
+    /// Processes samples for the current recording, along with per-channel output levels if
+    /// recording stems. These are captured separately from the samples sent to the speakers so
+    /// that recordings aren't warped by dynamic rate control.
+    pub fn process_recording(
+        &mut self,
+        samples: &[f32],
+        channel_samples: &[[f32; Apu::MAX_CHANNEL_COUNT]],
+    ) {
+        if let Some(mixer) = &mut self
+            .output
+            .as_mut()
+            .and_then(|output| output.mixer.as_mut())
+        {
+            mixer.process_recording(samples, channel_samples);
+        }
+    }
+
     /// Stop recording audio to a file.
     pub fn stop_recording(&mut self) -> anyhow::Result<Option<PathBuf>> {
         self.output
@@ -416,7 +486,8 @@ pub(crate) struct Mixer {
     sample_latency: usize,
     producer: SampleProducer,
     processed_samples: Vec<f32>,
-    recording: Option<(PathBuf, hound::WavWriter<BufWriter<File>>)>,
+    time_stretch: TimeStretch,
+    recording: Option<Recording>,
 }
 
 impl std::fmt::Debug for Mixer {
@@ -473,6 +544,7 @@ impl Mixer {
             sample_latency,
             producer,
             processed_samples,
+            time_stretch: TimeStretch::new(sample_rate),
             recording: None,
         })
     }
@@ -483,6 +555,7 @@ impl Mixer {
         if paused && !self.paused {
             let _ = self.stop_recording();
             self.processed_samples.clear();
+            self.time_stretch.clear();
             // FIXME: Currently cpal doesn't let the underyling audio device empty samples before
             // pausing which leads to the remaining audio playing again upon resume. The only work
             // around is to leave the stream playing
@@ -497,7 +570,7 @@ impl Mixer {
         self.paused = paused;
     }
 
-    fn start_recording(&mut self) -> anyhow::Result<()> {
+    fn start_recording(&mut self, stems: bool) -> anyhow::Result<()> {
         let _ = self.stop_recording();
         if let Some(dir) = Config::default_audio_dir() {
             let path = dir
@@ -517,28 +590,15 @@ impl Mixer {
                     })?;
                 }
             }
-            let spec = hound::WavSpec {
-                channels: self.channels,
-                sample_rate: self.sample_rate,
-                bits_per_sample: 32,
-                sample_format: hound::SampleFormat::Float,
-            };
-            let writer = hound::WavWriter::create(&path, spec)
-                .context("failed to create audio recording")?;
-            self.recording = Some((path, writer));
+            self.recording = Some(Recording::create(path, self.sample_rate, stems)?);
         }
         Ok(())
     }
 
     fn stop_recording(&mut self) -> anyhow::Result<Option<PathBuf>> {
-        if let Some((path, mut recording)) = self.recording.take() {
-            match recording.flush() {
-                Ok(_) => Ok(Some(path)),
-                Err(err) => Err(anyhow!("failed to flush audio recording: {err:?}")),
-            }
-        } else {
-            Ok(None)
-        }
+        self.recording
+            .take()
+            .map_or(Ok(None), |recording| recording.finalize().map(Some))
     }
 
     fn make_stream<T>(
@@ -575,23 +635,22 @@ impl Mixer {
         )?)
     }
 
-    fn process(&mut self, samples: &[f32]) {
+    fn process(&mut self, samples: &[f32], volume: f32, time_stretch: f32) {
         #[cfg(feature = "profiling")]
         puffin::profile_function!();
 
         if self.paused {
             return;
         }
-        for sample in samples {
+        let output = if time_stretch > 1.0 {
+            self.time_stretch.process(samples, time_stretch)
+        } else {
+            self.time_stretch.clear();
+            samples
+        };
+        for sample in output {
             for _ in 0..self.channels {
-                self.processed_samples.push(*sample);
-            }
-            if let Some((_, recording)) = &mut self.recording {
-                // TODO: push slice to recording thread
-                if let Err(err) = recording.write_sample(*sample) {
-                    error!("failed to write audio sample: {err:?}");
-                    let _ = self.stop_recording();
-                }
+                self.processed_samples.push(*sample * volume);
             }
         }
         let processed_len = self.processed_samples.len();
@@ -604,4 +663,221 @@ impl Mixer {
             self.producer.occupied_len()
         );
     }
+
+    fn process_recording(
+        &mut self,
+        samples: &[f32],
+        channel_samples: &[[f32; Apu::MAX_CHANNEL_COUNT]],
+    ) {
+        if self.paused {
+            return;
+        }
+        if let Some(recording) = &mut self.recording {
+            // TODO: push slice to recording thread
+            if let Err(err) = recording.write(samples) {
+                error!("failed to write audio samples: {err:?}");
+                let _ = self.stop_recording();
+                return;
+            }
+            if let Err(err) = recording.write_stems(channel_samples) {
+                error!("failed to write audio stem samples: {err:?}");
+                let _ = self.stop_recording();
+            }
+        }
+    }
+}
+
+/// Overlap-add time stretcher that shortens audio by a ratio without changing its pitch by
+/// skipping ahead between short grains of audio and crossfading them together.
+#[derive(Debug)]
+#[must_use]
+struct TimeStretch {
+    grain_len: usize,
+    overlap_len: usize,
+    input: Vec<f32>,
+    position: f32,
+    tail: Vec<f32>,
+    output: Vec<f32>,
+}
+
+impl TimeStretch {
+    /// Duration of each grain of audio copied to the output.
+    const GRAIN_SECS: f32 = 0.04;
+    /// Portion of each grain crossfaded with the end of the previous grain.
+    const OVERLAP: f32 = 0.25;
+
+    fn new(sample_rate: u32) -> Self {
+        let grain_len = ((Self::GRAIN_SECS * sample_rate as f32) as usize).max(4);
+        let overlap_len = (grain_len as f32 * Self::OVERLAP) as usize;
+        Self {
+            grain_len,
+            overlap_len,
+            input: Vec::with_capacity(2 * grain_len),
+            position: 0.0,
+            tail: Vec::with_capacity(overlap_len),
+            output: Vec::with_capacity(grain_len),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.input.clear();
+        self.position = 0.0;
+        self.tail.clear();
+    }
+
+    /// Stretch `samples` by `ratio`, returning roughly `samples.len() / ratio` samples. Input is
+    /// buffered until there's enough for a full grain.
+    fn process(&mut self, samples: &[f32], ratio: f32) -> &[f32] {
+        self.output.clear();
+        self.input.extend_from_slice(samples);
+
+        let hop = self.grain_len - self.overlap_len;
+        while self.position as usize + self.grain_len <= self.input.len() {
+            let start = self.position as usize;
+            let grain = &self.input[start..start + self.grain_len];
+            if self.tail.is_empty() {
+                self.output.extend_from_slice(&grain[..self.overlap_len]);
+            } else {
+                let fade_len = (self.overlap_len + 1) as f32;
+                for (i, (sample, prev)) in grain.iter().zip(&self.tail).enumerate() {
+                    let fade = (i + 1) as f32 / fade_len;
+                    self.output.push(prev * (1.0 - fade) + sample * fade);
+                }
+            }
+            self.output.extend_from_slice(&grain[self.overlap_len..hop]);
+            self.tail.clear();
+            self.tail.extend_from_slice(&grain[hop..]);
+            self.position += hop as f32 * ratio;
+        }
+
+        let consumed = (self.position as usize).min(self.input.len());
+        self.input.drain(..consumed);
+        self.position -= consumed as f32;
+
+        &self.output
+    }
+}
+
+/// Per-channel stem recording.
+#[must_use]
+struct Stem {
+    channel: Channel,
+    writer: WavWriter,
+    // DC blocking filter state, since channel outputs are unipolar
+    prev_input: f32,
+    prev_output: f32,
+}
+
+impl Stem {
+    const DC_BLOCK_POLE: f32 = 0.995;
+
+    fn write(&mut self, level: f32) -> hound::Result<()> {
+        let input = Apu::channel_mix_output(self.channel, level);
+        let output = input - self.prev_input + Self::DC_BLOCK_POLE * self.prev_output;
+        self.prev_input = input;
+        self.prev_output = output;
+        self.writer.write_sample(output)
+    }
+}
+
+/// Audio recording of the mixed output, and optionally of each APU channel.
+#[must_use]
+struct Recording {
+    path: PathBuf,
+    writer: WavWriter,
+    stems: Vec<Stem>,
+}
+
+impl Recording {
+    const CHANNELS: [Channel; Apu::MAX_CHANNEL_COUNT] = [
+        Channel::Pulse1,
+        Channel::Pulse2,
+        Channel::Triangle,
+        Channel::Noise,
+        Channel::Dmc,
+        Channel::Mapper,
+    ];
+
+    fn create(path: PathBuf, sample_rate: u32, stems: bool) -> anyhow::Result<Self> {
+        // NES audio is mono, so only a single channel is recorded regardless of the output
+        // device configuration
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let create_writer = |path: &Path| {
+            hound::WavWriter::create(path, spec)
+                .with_context(|| format!("failed to create audio recording: {}", path.display()))
+        };
+        let writer = create_writer(&path)?;
+        let stems = if stems {
+            Self::CHANNELS
+                .into_iter()
+                .map(|channel| {
+                    let stem_path = Self::stem_path(&path, channel);
+                    Ok(Stem {
+                        channel,
+                        writer: create_writer(&stem_path)?,
+                        prev_input: 0.0,
+                        prev_output: 0.0,
+                    })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?
+        } else {
+            Vec::new()
+        };
+        Ok(Self {
+            path,
+            writer,
+            stems,
+        })
+    }
+
+    fn stem_path(path: &Path, channel: Channel) -> PathBuf {
+        let name = match channel {
+            Channel::Pulse1 => "pulse1",
+            Channel::Pulse2 => "pulse2",
+            Channel::Triangle => "triangle",
+            Channel::Noise => "noise",
+            Channel::Dmc => "dmc",
+            Channel::Mapper => "expansion",
+        };
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        path.with_file_name(format!("{stem}_{name}"))
+            .with_extension("wav")
+    }
+
+    fn write(&mut self, samples: &[f32]) -> hound::Result<()> {
+        samples
+            .iter()
+            .try_for_each(|sample| self.writer.write_sample(*sample))
+    }
+
+    fn write_stems(
+        &mut self,
+        channel_samples: &[[f32; Apu::MAX_CHANNEL_COUNT]],
+    ) -> hound::Result<()> {
+        for levels in channel_samples {
+            for stem in &mut self.stems {
+                stem.write(levels[stem.channel as usize])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Finalize the WAV headers for the recording and all stems, returning the path of the
+    /// mixed recording.
+    fn finalize(self) -> anyhow::Result<PathBuf> {
+        self.writer
+            .finalize()
+            .context("failed to finalize audio recording")?;
+        for stem in self.stems {
+            stem.writer
+                .finalize()
+                .context("failed to finalize audio stem recording")?;
+        }
+        Ok(self.path)
+    }
 }
@@ -1,11 +1,25 @@
-use crate::nes::input::{ActionBindings, Gamepads, Input};
+use crate::nes::{
+    action::Action,
+    emulation::{lan::TrustedPeer, livesplit::LiveSplitConfig},
+    input::{ActionBindings, Gamepads, Input, RepeatPolicy},
+    rom_db::RomDbConfig,
+};
 use anyhow::Context;
 use egui::ahash::HashSet;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
 use tetanes_core::{
-    common::NesRegion, control_deck::Config as DeckConfig, fs, input::Player, ppu::Ppu,
-    time::Duration,
+    common::NesRegion,
+    control_deck::Config as DeckConfig,
+    fs,
+    input::{JoypadBtn, Player},
+    playlist::Playlist,
+    ppu::Ppu,
+    time::{Duration, Instant},
 };
 use tracing::{error, info};
 use uuid::Uuid;
@@ -17,6 +31,12 @@ pub struct AudioConfig {
     pub enabled: bool,
     pub buffer_size: usize,
     pub latency: Duration,
+    pub record_stems: bool,
+    /// How audio is played while emulation speed is above 100%.
+    pub fast_forward: FastForwardAudio,
+    /// Whether to slightly adjust the emulation sample rate to keep queued audio near the
+    /// configured latency.
+    pub dynamic_rate_control: bool,
 }
 
 impl Default for AudioConfig {
@@ -34,6 +54,46 @@ impl Default for AudioConfig {
             } else {
                 Duration::from_millis(50)
             },
+            record_stems: false,
+            fast_forward: FastForwardAudio::default(),
+            dynamic_rate_control: true,
+        }
+    }
+}
+
+/// How audio is played while fast-forwarding.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub enum FastForwardAudio {
+    /// Play audio at full volume, sped up with a higher pitch.
+    #[default]
+    Normal,
+    /// Silence audio.
+    Mute,
+    /// Play audio sped up at 25% volume.
+    Quiet,
+    /// Time-stretch audio to keep the original pitch.
+    PitchLocked,
+}
+
+impl FastForwardAudio {
+    /// Volume to play audio at while fast-forwarding.
+    pub const fn volume(&self) -> f32 {
+        match self {
+            Self::Mute => 0.0,
+            Self::Quiet => 0.25,
+            Self::Normal | Self::PitchLocked => 1.0,
+        }
+    }
+}
+
+impl AsRef<str> for FastForwardAudio {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Normal => "Normal",
+            Self::Mute => "Mute",
+            Self::Quiet => "Quiet",
+            Self::PitchLocked => "Pitch Locked",
         }
     }
 }
@@ -45,13 +105,42 @@ pub struct EmulationConfig {
     pub auto_load: bool,
     pub auto_save: bool,
     pub auto_save_interval: Duration,
+    /// Whether to save a session state on exit and resume it the next time the same ROM is
+    /// loaded.
+    pub session: SessionMode,
+    /// Resume the session state of the first ROM loaded without asking. Set by `--resume` and
+    /// not saved.
+    #[serde(skip)]
+    pub resume: bool,
+    /// Apply a patch file next to a loaded ROM with the same name, e.g. `Game.ips` for
+    /// `Game.nes`.
+    pub auto_patch: bool,
+    /// Patch to apply to the first ROM loaded. Set by `--patch` and not saved.
+    #[serde(skip)]
+    pub patch: Option<PathBuf>,
+    pub live_reload: LiveReloadConfig,
     pub rewind: bool,
     pub rewind_seconds: u32,
     pub rewind_interval: u32,
     pub run_ahead: usize,
     pub save_slot: u8,
     pub speed: f32,
+    pub fast_forward: FastForwardConfig,
     pub threaded: bool,
+    pub screenshot: ScreenshotConfig,
+    pub lan_transfer: bool,
+    /// Devices allowed to send save states over the local network.
+    pub lan_trusted_peers: Vec<TrustedPeer>,
+    pub livesplit: LiveSplitConfig,
+    pub rom_db: RomDbConfig,
+    pub playlist: Playlist,
+    /// Load plugins from the plugins directory on startup.
+    pub plugins: bool,
+    /// What to do while the window is unfocused or hidden.
+    pub background: BackgroundMode,
+    /// Maximum number of frames in a row to emulate without rendering when falling behind, or
+    /// `0` to render every frame.
+    pub max_frame_skip: u32,
 }
 
 impl Default for EmulationConfig {
@@ -60,6 +149,11 @@ impl Default for EmulationConfig {
             auto_load: true,
             auto_save: true,
             auto_save_interval: Duration::from_secs(5),
+            session: SessionMode::default(),
+            resume: false,
+            auto_patch: true,
+            patch: None,
+            live_reload: LiveReloadConfig::default(),
             // WASM framerates suffer with garbage collection pauses when rewind is enabled.
             // FIXME: Perhaps re-using Vec allocations could help resolve it.
             rewind: cfg!(not(target_arch = "wasm32")),
@@ -74,32 +168,311 @@ impl Default for EmulationConfig {
             },
             save_slot: 1,
             speed: 1.0,
+            fast_forward: FastForwardConfig::default(),
             threaded: true,
+            screenshot: ScreenshotConfig::default(),
+            lan_transfer: false,
+            lan_trusted_peers: Vec::new(),
+            livesplit: LiveSplitConfig::default(),
+            rom_db: RomDbConfig::default(),
+            playlist: Playlist::default(),
+            plugins: cfg!(not(target_arch = "wasm32")),
+            background: BackgroundMode::default(),
+            max_frame_skip: 0,
+        }
+    }
+}
+
+/// How the Fast Forward shortcut speeds up emulation.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[must_use]
+#[serde(default)] // Ensures new fields don't break existing configurations
+pub struct FastForwardConfig {
+    /// Emulation speed while fast-forwarding.
+    pub speed: f32,
+    /// Run as fast as possible instead of at `speed`, with audio silenced.
+    pub uncapped: bool,
+    /// Toggle fast-forward on each press instead of only while held.
+    pub toggle: bool,
+    /// Number of frames to skip rendering between each rendered frame while fast-forwarding.
+    pub frame_skip: u32,
+}
+
+impl FastForwardConfig {
+    pub const MIN_SPEED: f32 = 1.25;
+    pub const MAX_SPEED: f32 = 8.0;
+}
+
+impl Default for FastForwardConfig {
+    fn default() -> Self {
+        Self {
+            speed: 2.0,
+            uncapped: false,
+            toggle: false,
+            frame_skip: 0,
+        }
+    }
+}
+
+/// Whether to save a session state on exit and resume it the next time the same ROM is loaded.
+/// Separate from the numbered save slots.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub enum SessionMode {
+    /// Don't save or resume sessions.
+    #[default]
+    Off,
+    /// Save sessions and ask whether to resume them.
+    Ask,
+    /// Save sessions and always resume them.
+    Always,
+}
+
+impl AsRef<str> for SessionMode {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Off => "Off",
+            Self::Ask => "Ask",
+            Self::Always => "Always",
+        }
+    }
+}
+
+/// Reloading the loaded ROM when its file changes, e.g. for homebrew development.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+#[serde(default)] // Ensures new fields don't break existing configurations
+pub struct LiveReloadConfig {
+    /// Watch the loaded ROM file and reload it when it changes.
+    pub enabled: bool,
+    /// What to keep from before the ROM was reloaded.
+    pub preserve: LiveReloadPreserve,
+}
+
+/// What to keep when reloading a changed ROM.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub enum LiveReloadPreserve {
+    /// Start the reloaded ROM from power on.
+    #[default]
+    Nothing,
+    /// Keep the contents of Work RAM and cartridge PRG-RAM.
+    Ram,
+    /// Load the state saved in the current save slot.
+    SaveState,
+}
+
+impl AsRef<str> for LiveReloadPreserve {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Nothing => "Nothing",
+            Self::Ram => "RAM",
+            Self::SaveState => "Save State",
+        }
+    }
+}
+
+/// What to do while the window is unfocused or hidden.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub enum BackgroundMode {
+    /// Pause emulation and audio.
+    #[default]
+    Pause,
+    /// Keep emulating with audio silenced.
+    Mute,
+    /// Keep emulating and playing audio.
+    Run,
+}
+
+impl AsRef<str> for BackgroundMode {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Pause => "Pause",
+            Self::Mute => "Mute",
+            Self::Run => "Run",
+        }
+    }
+}
+
+/// What to capture when taking a screenshot.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub enum ScreenshotFormat {
+    /// Unfiltered 256x240 frame of NES palette indices and emphasis bits, saved as 16-bit
+    /// grayscale with each 9-bit pixel value shifted into the top bits.
+    Raw,
+    /// Frame after video filtering and post-processing.
+    #[default]
+    Filtered,
+    /// Filtered frame scaled up by an integer factor.
+    Scaled,
+}
+
+impl AsRef<str> for ScreenshotFormat {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Raw => "Raw",
+            Self::Filtered => "Filtered",
+            Self::Scaled => "Scaled",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[must_use]
+#[serde(default)] // Ensures new fields don't break existing configurations
+pub struct ScreenshotConfig {
+    pub format: ScreenshotFormat,
+    /// Integer scale used with [`ScreenshotFormat::Scaled`].
+    pub scale: u32,
+    pub copy_to_clipboard: bool,
+    /// Output directory. Defaults to [`Config::default_picture_dir`].
+    pub dir: Option<PathBuf>,
+    /// Whether to save screenshots requested by scripts or achievements into a per-game
+    /// subdirectory.
+    pub capture_milestones: bool,
+}
+
+impl Default for ScreenshotConfig {
+    fn default() -> Self {
+        Self {
+            format: ScreenshotFormat::default(),
+            scale: 3,
+            copy_to_clipboard: false,
+            dir: None,
+            capture_milestones: true,
+        }
+    }
+}
+
+/// Corner of the NES frame to anchor an overlay to.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub enum OverlayPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+impl AsRef<str> for OverlayPosition {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::TopLeft => "Top Left",
+            Self::TopRight => "Top Right",
+            Self::BottomLeft => "Bottom Left",
+            Self::BottomRight => "Bottom Right",
         }
     }
 }
 
+/// When to show the on-screen touch controller.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub enum TouchControllerMode {
+    /// Show once the screen has been touched.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl AsRef<str> for TouchControllerMode {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Auto => "Auto",
+            Self::Always => "Always",
+            Self::Never => "Never",
+        }
+    }
+}
+
+/// On-screen D-Pad, A/B and Start/Select buttons for player one on touch screens.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[must_use]
+#[serde(default)]
+pub struct TouchControllerConfig {
+    pub mode: TouchControllerMode,
+    /// Opacity of the buttons, from `0.0` to `1.0`.
+    pub opacity: f32,
+    /// Size of the buttons relative to the NES frame.
+    pub size: f32,
+    /// Put the D-Pad on the right and A/B on the left.
+    pub left_handed: bool,
+}
+
+impl Default for TouchControllerConfig {
+    fn default() -> Self {
+        Self {
+            mode: TouchControllerMode::default(),
+            opacity: 0.5,
+            size: 1.0,
+            left_handed: false,
+        }
+    }
+}
+
+/// Kind of file picked with a file dialog. Each kind remembers the directory it was last picked
+/// from separately.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[must_use]
+pub enum FileKind {
+    Rom,
+    Replay,
+    GameDatabase,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[must_use]
 #[serde(default)] // Ensures new fields don't break existing configurations
 pub struct RendererConfig {
     pub fullscreen: bool,
-    pub hide_overscan: bool,
+    /// Overscan cropped from NTSC frames.
+    pub overscan_ntsc: Overscan,
+    /// Overscan cropped from PAL and Dendy frames.
+    pub overscan_pal: Overscan,
     pub scale: f32,
     pub recent_roms: HashSet<PathBuf>,
     pub roms_path: Option<PathBuf>,
+    /// Directory each kind of file dialog was last picked from.
+    pub recent_dirs: BTreeMap<FileKind, PathBuf>,
+    /// Folders pinned to the File menu to quickly load ROMs from.
+    pub pinned_dirs: Vec<PathBuf>,
+    /// Directories scanned for the ROM library.
+    pub library_dirs: Vec<PathBuf>,
+    /// No-Intro style DAT file used to identify ROMs in the library.
+    pub game_database: Option<PathBuf>,
+    /// Show the performance overlay.
     pub show_perf_stats: bool,
+    pub perf_stats_position: OverlayPosition,
     pub show_messages: bool,
     pub show_menubar: bool,
     pub embed_viewports: bool,
     pub dark_theme: bool,
+    /// High-contrast variant of the theme with pure background and text colors and bolder outlines.
+    pub high_contrast: bool,
+    /// Scale of UI text and widgets, independent of the window scale.
+    pub ui_scale: f32,
+    pub show_audio_scope: bool,
+    pub audio_scope_position: OverlayPosition,
+    pub audio_scope_scale: f32,
+    pub window_geometry: Option<WindowGeometry>,
+    /// User shader preset applied to the NES frame.
+    pub shader_preset: Option<PathBuf>,
+    /// Shader parameter values overriding the preset defaults.
+    pub shader_parameters: BTreeMap<String, f32>,
+    pub orientation: Orientation,
+    pub touch_controller: TouchControllerConfig,
 }
 
 impl Default for RendererConfig {
     fn default() -> Self {
         Self {
             fullscreen: false,
-            hide_overscan: true,
+            overscan_ntsc: Overscan::default_for(NesRegion::Ntsc),
+            overscan_pal: Overscan::default_for(NesRegion::Pal),
             scale: if cfg!(target_arch = "wasm32") {
                 2.0
             } else {
@@ -107,15 +480,229 @@ impl Default for RendererConfig {
             },
             recent_roms: HashSet::default(),
             roms_path: None,
+            recent_dirs: BTreeMap::new(),
+            pinned_dirs: Vec::new(),
+            library_dirs: Vec::new(),
+            game_database: None,
             show_perf_stats: false,
+            perf_stats_position: OverlayPosition::TopRight,
             show_messages: true,
             show_menubar: true,
             embed_viewports: false,
             dark_theme: true,
+            high_contrast: false,
+            ui_scale: 1.0,
+            show_audio_scope: false,
+            audio_scope_position: OverlayPosition::default(),
+            audio_scope_scale: 1.0,
+            window_geometry: None,
+            shader_preset: None,
+            shader_parameters: BTreeMap::new(),
+            orientation: Orientation::default(),
+            touch_controller: TouchControllerConfig::default(),
         }
     }
 }
 
+/// Pixels cropped from each edge of the NES frame, hiding the overscan area that traditional CRT
+/// displays would cut off.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+#[serde(default)]
+pub struct Overscan {
+    pub top: u32,
+    pub bottom: u32,
+    pub left: u32,
+    pub right: u32,
+}
+
+impl Overscan {
+    /// Maximum pixels cropped from a single edge.
+    pub const MAX: u32 = 64;
+    pub const NONE: Self = Self {
+        top: 0,
+        bottom: 0,
+        left: 0,
+        right: 0,
+    };
+
+    /// Default cropping for `region`. NTSC TVs commonly cut off the top and bottom 8 scanlines,
+    /// while the PAL PPU blanks the top scanline and 2 pixels on each side.
+    pub const fn default_for(region: NesRegion) -> Self {
+        match region {
+            NesRegion::Pal | NesRegion::Dendy => Self {
+                top: 1,
+                bottom: 0,
+                left: 2,
+                right: 2,
+            },
+            NesRegion::Auto | NesRegion::Ntsc => Self {
+                top: 8,
+                bottom: 8,
+                left: 0,
+                right: 0,
+            },
+        }
+    }
+
+    #[must_use]
+    pub const fn is_none(&self) -> bool {
+        self.top == 0 && self.bottom == 0 && self.left == 0 && self.right == 0
+    }
+
+    /// Size of the NES frame after cropping.
+    #[must_use]
+    pub fn frame_size(&self) -> egui::Vec2 {
+        egui::Vec2::new(
+            Ppu::WIDTH.saturating_sub(self.left + self.right).max(1) as f32,
+            Ppu::HEIGHT.saturating_sub(self.top + self.bottom).max(1) as f32,
+        )
+    }
+
+    /// Normalized texture coordinates of the cropped NES frame.
+    #[must_use]
+    pub fn uv(&self) -> egui::Rect {
+        let (width, height) = (Ppu::WIDTH as f32, Ppu::HEIGHT as f32);
+        let size = self.frame_size();
+        let min = egui::Pos2::new(self.left as f32 / width, self.top as f32 / height);
+        egui::Rect::from_min_size(min, egui::Vec2::new(size.x / width, size.y / height))
+    }
+}
+
+/// Clockwise rotation of the NES frame.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub enum Rotation {
+    #[default]
+    R0,
+    R90,
+    R180,
+    R270,
+}
+
+impl Rotation {
+    pub const fn as_slice() -> &'static [Self] {
+        &[Self::R0, Self::R90, Self::R180, Self::R270]
+    }
+}
+
+impl AsRef<str> for Rotation {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::R0 => "0°",
+            Self::R90 => "90°",
+            Self::R180 => "180°",
+            Self::R270 => "270°",
+        }
+    }
+}
+
+/// Rotation and mirroring of the NES frame, e.g. for cabinets with a vertically mounted monitor.
+/// Mirroring is applied before rotating.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+#[serde(default)]
+pub struct Orientation {
+    pub rotation: Rotation,
+    pub mirror_horizontal: bool,
+    pub mirror_vertical: bool,
+    /// Remap the D-Pad so directions match the displayed frame instead of the game.
+    pub remap_dpad: bool,
+}
+
+impl Orientation {
+    /// Whether the frame is displayed on its side, swapping its width and height.
+    #[must_use]
+    pub const fn is_sideways(&self) -> bool {
+        matches!(self.rotation, Rotation::R90 | Rotation::R270)
+    }
+
+    /// Displayed size of a frame of `size`.
+    #[must_use]
+    pub fn size(&self, size: egui::Vec2) -> egui::Vec2 {
+        if self.is_sideways() {
+            egui::Vec2::new(size.y, size.x)
+        } else {
+            size
+        }
+    }
+
+    /// Maps a normalized position in the displayed frame to the normalized position in the NES
+    /// frame shown there.
+    #[must_use]
+    pub fn to_frame(&self, pos: egui::Pos2) -> egui::Pos2 {
+        let (x, y) = match self.rotation {
+            Rotation::R0 => (pos.x, pos.y),
+            Rotation::R90 => (pos.y, 1.0 - pos.x),
+            Rotation::R180 => (1.0 - pos.x, 1.0 - pos.y),
+            Rotation::R270 => (1.0 - pos.y, pos.x),
+        };
+        egui::Pos2::new(
+            if self.mirror_horizontal { 1.0 - x } else { x },
+            if self.mirror_vertical { 1.0 - y } else { y },
+        )
+    }
+
+    /// Mesh drawing the texture region `uv` rotated and mirrored to fill `rect`.
+    pub fn mesh(
+        &self,
+        texture_id: egui::TextureId,
+        rect: egui::Rect,
+        uv: egui::Rect,
+    ) -> egui::Mesh {
+        let mut mesh = egui::Mesh::with_texture(texture_id);
+        mesh.add_rect_with_uv(rect, uv, egui::Color32::WHITE);
+        // Vertices are added in left-top, right-top, left-bottom, right-bottom order
+        let corners = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)];
+        for (vertex, (x, y)) in mesh.vertices.iter_mut().zip(corners) {
+            let pos = self.to_frame(egui::Pos2::new(x, y));
+            vertex.uv = uv.min + pos.to_vec2() * uv.size();
+        }
+        mesh
+    }
+
+    /// Remaps a D-Pad direction pressed for the displayed frame to the direction in the NES frame,
+    /// if enabled.
+    pub fn remap(&self, button: JoypadBtn) -> JoypadBtn {
+        if !self.remap_dpad {
+            return button;
+        }
+        let (x, y) = match button {
+            JoypadBtn::Left => (-1.0, 0.0),
+            JoypadBtn::Right => (1.0, 0.0),
+            JoypadBtn::Up => (0.0, -1.0),
+            JoypadBtn::Down => (0.0, 1.0),
+            _ => return button,
+        };
+        // Directions are offsets from the center of the frame
+        let center = self.to_frame(egui::Pos2::new(0.5, 0.5));
+        let dir = self.to_frame(egui::Pos2::new(0.5 + x, 0.5 + y)) - center;
+        if dir.x.abs() > dir.y.abs() {
+            if dir.x < 0.0 {
+                JoypadBtn::Left
+            } else {
+                JoypadBtn::Right
+            }
+        } else if dir.y < 0.0 {
+            JoypadBtn::Up
+        } else {
+            JoypadBtn::Down
+        }
+    }
+}
+
+/// Position and size of the main window, restored between sessions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[must_use]
+pub struct WindowGeometry {
+    /// Outer position in physical pixels.
+    pub position: [i32; 2],
+    /// Inner size in logical pixels.
+    pub size: [f32; 2],
+    /// Name of the monitor the window was on.
+    pub monitor: Option<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[must_use]
 #[serde(default)] // Ensures new fields don't break existing configurations
@@ -139,6 +726,29 @@ impl Default for InputConfig {
 }
 
 impl InputConfig {
+    /// Returns the repeat policy for a shortcut `action`.
+    pub fn repeat_policy(&self, action: Action) -> RepeatPolicy {
+        self.shortcuts
+            .iter()
+            .find(|bind| bind.action == action)
+            .map_or_else(|| action.default_repeat(), ActionBindings::repeat_policy)
+    }
+
+    /// Set the repeat policy for a shortcut `action`, if it's repeatable.
+    pub fn set_repeat_policy(&mut self, action: Action, policy: RepeatPolicy) {
+        if !action.is_repeatable() {
+            return;
+        }
+        let repeat = (policy != action.default_repeat()).then_some(policy);
+        match self.shortcuts.iter_mut().find(|bind| bind.action == action) {
+            Some(bind) => bind.repeat = repeat,
+            None => self.shortcuts.push(ActionBindings {
+                repeat,
+                ..ActionBindings::empty(action)
+            }),
+        }
+    }
+
     pub fn clear_binding(&mut self, input: Input) {
         if let Some(binding) = self
             .shortcuts
@@ -237,24 +847,61 @@ impl Config {
     pub const SAVE_DIR: &'static str = "save";
     pub const WINDOW_TITLE: &'static str = "TetaNES";
     pub const FILENAME: &'static str = "config.json";
+    /// Slowest emulation speed, below the usual 25% steps, for practicing tricky sections.
+    pub const SLOW_MOTION_SPEED: f32 = 0.1;
+    /// File next to the executable that enables portable mode when present.
+    pub const PORTABLE_FILENAME: &'static str = "portable.txt";
+    /// Directory next to the executable that stores everything in portable mode.
+    pub const PORTABLE_DIR: &'static str = "userdata";
+
+    /// Enable portable mode if `force` is set or a [`Config::PORTABLE_FILENAME`] file exists next
+    /// to the executable, storing configuration, saves, screenshots and recordings in
+    /// [`Config::PORTABLE_DIR`] next to it instead of the platform directories. Returns the
+    /// portable directory, if enabled.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn init_portable(force: bool) -> Option<PathBuf> {
+        let exe_dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+        if !force && !exe_dir.join(Self::PORTABLE_FILENAME).exists() {
+            return None;
+        }
+        let dir = exe_dir.join(Self::PORTABLE_DIR);
+        DeckConfig::set_portable_dir(Some(dir.clone()));
+        Some(dir)
+    }
 
     #[must_use]
     pub fn default_config_dir() -> Option<PathBuf> {
-        dirs::config_local_dir().map(|dir| dir.join(DeckConfig::BASE_DIR))
+        if let Some(dir) = DeckConfig::portable_dir() {
+            return Some(dir.join("config"));
+        }
+        // Browser storage has no standard directories, so use fixed paths within it
+        #[cfg(target_arch = "wasm32")]
+        return Some(PathBuf::from("/config").join(DeckConfig::BASE_DIR));
+        #[cfg(not(target_arch = "wasm32"))]
+        return dirs::config_local_dir().map(|dir| dir.join(DeckConfig::BASE_DIR));
     }
 
     #[must_use]
     pub fn default_data_dir() -> Option<PathBuf> {
-        dirs::data_local_dir().map(|dir| dir.join(DeckConfig::BASE_DIR))
+        DeckConfig::default_data_dir()
     }
 
     #[must_use]
     pub fn default_picture_dir() -> Option<PathBuf> {
-        dirs::picture_dir().map(|dir| dir.join(DeckConfig::BASE_DIR))
+        if let Some(dir) = DeckConfig::portable_dir() {
+            return Some(dir.join("pictures"));
+        }
+        #[cfg(target_arch = "wasm32")]
+        return Some(PathBuf::from("/pictures").join(DeckConfig::BASE_DIR));
+        #[cfg(not(target_arch = "wasm32"))]
+        return dirs::picture_dir().map(|dir| dir.join(DeckConfig::BASE_DIR));
     }
 
     #[must_use]
     pub fn default_audio_dir() -> Option<PathBuf> {
+        if let Some(dir) = DeckConfig::portable_dir() {
+            return Some(dir.join("audio"));
+        }
         dirs::audio_dir().map(|dir| dir.join(DeckConfig::BASE_DIR))
     }
 
@@ -273,6 +920,45 @@ impl Config {
         })
     }
 
+    /// Returns the path of the session state saved on exit, separate from the numbered slots.
+    #[must_use]
+    pub fn session_path(name: &str) -> Option<PathBuf> {
+        Self::default_data_dir().map(|dir| {
+            dir.join(Self::SAVE_DIR)
+                .join(name)
+                .join("session")
+                .with_extension("sav")
+        })
+    }
+
+    /// Returns the directory to open a file dialog for `kind` in. Defaults to the directory last
+    /// picked from if it still exists.
+    #[must_use]
+    pub fn dialog_dir(&self, kind: FileKind) -> Option<PathBuf> {
+        self.renderer
+            .recent_dirs
+            .get(&kind)
+            .filter(|dir| dir.is_dir())
+            .cloned()
+            .or_else(|| match kind {
+                FileKind::Rom => self.renderer.roms_path.clone(),
+                FileKind::Replay | FileKind::GameDatabase => Self::default_data_dir(),
+            })
+    }
+
+    /// Remember the directory of `path`, picked from a file dialog for `kind`.
+    pub fn set_dialog_dir(&mut self, kind: FileKind, path: &Path) {
+        let dir = if path.is_dir() {
+            Some(path)
+        } else {
+            path.parent()
+        };
+        // Browsers don't expose the directory files are picked from
+        if let Some(dir) = dir.filter(|dir| !dir.as_os_str().is_empty()) {
+            self.renderer.recent_dirs.insert(kind, dir.to_path_buf());
+        }
+    }
+
     pub fn reset(&mut self) {
         *self = Self::default();
     }
@@ -288,30 +974,64 @@ impl Config {
     }
 
     pub fn load(path: Option<PathBuf>) -> Self {
-        path.or_else(Config::config_path)
+        let mut cfg = path
+            .or_else(Config::config_path)
             .and_then(|path| {
-                path.exists().then(|| {
+                fs::exists(&path).then(|| {
                     info!("Loading saved configuration");
-                    fs::load_raw(&path)
-                        .context("failed to load config")
-                        .and_then(|data| Ok(serde_json::from_slice::<Self>(&data)?))
-                        .with_context(|| format!("failed to parse {path:?}"))
-                        .unwrap_or_else(|err| {
-                            error!(
-                                "Invalid config: {path:?}, reverting to defaults. Error: {err:?}",
-                            );
-                            Self::default()
-                        })
+                    Self::read(&path).unwrap_or_else(|err| {
+                        error!("Invalid config: {path:?}, reverting to defaults. Error: {err:?}",);
+                        Self::default()
+                    })
                 })
             })
             .unwrap_or_else(|| {
                 info!("Loading default configuration");
                 Self::default()
-            })
+            });
+        // Portable installs can move, e.g. when a USB drive is mounted at a different path
+        if DeckConfig::portable_dir().is_some() {
+            cfg.deck.data_dir =
+                DeckConfig::default_data_dir().map(|dir| dir.to_string_lossy().into_owned());
+        }
+        cfg
+    }
+
+    fn read(path: &Path) -> anyhow::Result<Self> {
+        fs::load_raw(path)
+            .context("failed to load config")
+            .and_then(|data| Ok(serde_json::from_slice::<Self>(&data)?))
+            .with_context(|| format!("failed to parse {path:?}"))
+    }
+
+    /// Override the setting at the `.` separated `key` path of its JSON field names, e.g.
+    /// `deck.filter` or `deck.mixer.channel_volumes.2`. `value` is parsed as JSON, falling back to
+    /// a plain string so enum variants like `Ntsc` don't need quoting.
+    pub fn set(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+        let mut cfg = serde_json::to_value(&*self).context("failed to serialize config")?;
+        let mut setting = &mut cfg;
+        for field in key.split('.') {
+            setting = match setting {
+                serde_json::Value::Object(fields) => fields.get_mut(field),
+                serde_json::Value::Array(values) => field
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| values.get_mut(index)),
+                _ => None,
+            }
+            .with_context(|| format!("unknown setting {key:?}"))?;
+        }
+        *setting = serde_json::from_str(value)
+            .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+        *self = serde_json::from_value(cfg)
+            .with_context(|| format!("invalid value {value:?} for setting {key:?}"))?;
+        Ok(())
     }
 
     pub fn increment_speed(&mut self) -> f32 {
-        if self.emulation.speed <= 1.75 {
+        if self.emulation.speed < 0.25 {
+            self.emulation.speed = 0.25;
+        } else if self.emulation.speed <= 1.75 {
             self.emulation.speed += 0.25;
         }
         self.emulation.speed
@@ -320,6 +1040,8 @@ impl Config {
     pub fn decrement_speed(&mut self) -> f32 {
         if self.emulation.speed >= 0.50 {
             self.emulation.speed -= 0.25;
+        } else {
+            self.emulation.speed = Self::SLOW_MOTION_SPEED;
         }
         self.emulation.speed
     }
@@ -338,29 +1060,109 @@ impl Config {
         self.renderer.scale
     }
 
+    /// Window size for the NES frame of `region`, before applying the aspect ratio.
     #[must_use]
-    pub fn window_size(&self) -> egui::Vec2 {
-        let scale = self.renderer.scale;
-        let texture_size = self.texture_size();
-        egui::Vec2::new(scale * texture_size.x, scale * texture_size.y)
+    pub fn window_size(&self, region: NesRegion) -> egui::Vec2 {
+        self.renderer.scale * self.overscan(region).frame_size()
     }
 
+    /// Size of the NES frame texture, which is always uncropped. Overscan is hidden when the
+    /// texture is displayed.
     #[must_use]
     pub const fn texture_size(&self) -> egui::Vec2 {
-        let width = Ppu::WIDTH;
-        let height = if self.renderer.hide_overscan {
-            Ppu::HEIGHT - 16
+        egui::Vec2::new(Ppu::WIDTH as f32, Ppu::HEIGHT as f32)
+    }
+
+    /// Overscan cropped from frames of `region`.
+    pub const fn overscan(&self, region: NesRegion) -> Overscan {
+        match region {
+            NesRegion::Pal | NesRegion::Dendy => self.renderer.overscan_pal,
+            NesRegion::Auto | NesRegion::Ntsc => self.renderer.overscan_ntsc,
+        }
+    }
+
+    /// Mutable overscan cropped from frames of `region`.
+    pub fn overscan_mut(&mut self, region: NesRegion) -> &mut Overscan {
+        match region {
+            NesRegion::Pal | NesRegion::Dendy => &mut self.renderer.overscan_pal,
+            NesRegion::Auto | NesRegion::Ntsc => &mut self.renderer.overscan_ntsc,
+        }
+    }
+}
+
+/// Polls the configuration file for changes made outside of `TetaNES`, e.g. in a text editor.
+#[derive(Debug)]
+#[must_use]
+pub struct ConfigWatcher {
+    path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    last_polled: Instant,
+    /// Configuration as last read from disk, to only apply settings that were edited.
+    cfg: Config,
+}
+
+impl ConfigWatcher {
+    const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new() -> Self {
+        // Browser storage can't be edited externally
+        let path = if cfg!(target_arch = "wasm32") {
+            None
         } else {
-            Ppu::HEIGHT
+            Config::config_path()
         };
-        egui::Vec2::new(width as f32, height as f32)
+        let modified = path.as_deref().and_then(Self::modified);
+        let cfg = path
+            .as_deref()
+            .filter(|path| fs::exists(path))
+            .and_then(|path| Config::read(path).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            modified,
+            last_polled: Instant::now(),
+            cfg,
+        }
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    /// Returns the previous and new configuration if the file was modified since last polled.
+    pub fn poll(&mut self) -> Option<(Config, Config)> {
+        if self.last_polled.elapsed() < Self::POLL_INTERVAL {
+            return None;
+        }
+        self.last_polled = Instant::now();
+
+        let path = self.path.as_deref()?;
+        let modified = Self::modified(path);
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        match Config::read(path) {
+            Ok(cfg) => Some((std::mem::replace(&mut self.cfg, cfg.clone()), cfg)),
+            Err(err) => {
+                error!("failed to reload configuration: {err:?}");
+                None
+            }
+        }
+    }
+}
+
+impl Default for ConfigWatcher {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum FrameRate {
     X50,
-    X59,
     #[default]
     X60,
 }
@@ -369,8 +1171,13 @@ impl FrameRate {
     pub const MIN: Self = Self::X50;
     pub const MAX: Self = Self::X60;
 
+    /// Exact duration of a frame, e.g. NTSC runs at ~60.0988 Hz instead of 60 Hz.
     pub fn duration(&self) -> Duration {
-        Duration::from_secs_f32(f32::from(self).recip())
+        let region = match self {
+            Self::X50 => NesRegion::Pal,
+            Self::X60 => NesRegion::Ntsc,
+        };
+        Duration::from_secs_f32(Ppu::region_frame_rate(region).recip())
     }
 }
 
@@ -378,7 +1185,6 @@ impl From<FrameRate> for u32 {
     fn from(frame_rate: FrameRate) -> Self {
         match frame_rate {
             FrameRate::X50 => 50,
-            FrameRate::X59 => 59,
             FrameRate::X60 => 60,
         }
     }
@@ -406,8 +1212,7 @@ impl From<NesRegion> for FrameRate {
     fn from(region: NesRegion) -> Self {
         match region {
             NesRegion::Auto | NesRegion::Ntsc => Self::X60,
-            NesRegion::Pal => Self::X50,
-            NesRegion::Dendy => Self::X59,
+            NesRegion::Pal | NesRegion::Dendy => Self::X50,
         }
     }
 }
@@ -422,7 +1227,6 @@ impl AsRef<str> for FrameRate {
     fn as_ref(&self) -> &str {
         match self {
             Self::X50 => "50 Hz",
-            Self::X59 => "59 Hz",
             Self::X60 => "60 Hz",
         }
     }
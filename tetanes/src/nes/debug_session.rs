@@ -0,0 +1,70 @@
+//! Debugging sessions saved per ROM, so reopening a game restores the debugging windows and RAM
+//! watches that were open the last time it was played.
+//!
+//! Sessions are keyed by the ROM CRC32 rather than the filename so they follow a ROM that's been
+//! renamed or moved.
+//!
+//! Breakpoints and labels are out of scope since the debugger doesn't support them yet. They're
+//! tracked on the roadmap.
+
+use crate::nes::config::Config;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tetanes_core::{fs, mem::MemorySpace};
+use tracing::error;
+
+const DIR: &str = "debug";
+
+/// Debugging windows and watches for a ROM.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+#[serde(default)] // Ensures new fields don't break existing sessions
+pub struct DebugSession {
+    /// Watched CPU RAM addresses.
+    pub ram_watches: Vec<u16>,
+    pub ram_search_open: bool,
+    pub mapper_inspector_open: bool,
+    pub input_diagnostics_open: bool,
+    /// Memory space and address of the first row shown in the hex editor, if open.
+    pub hex_editor: Option<(MemorySpace, usize)>,
+}
+
+impl DebugSession {
+    /// Path to the debugging session of a ROM matching `crc32`.
+    #[must_use]
+    pub fn path(crc32: u32) -> Option<PathBuf> {
+        Config::default_data_dir().map(|dir| {
+            dir.join(DIR)
+                .join(format!("{crc32:08X}"))
+                .with_extension("json")
+        })
+    }
+
+    /// Load the debugging session of a ROM matching `crc32`, if one was saved.
+    #[must_use]
+    pub fn load(crc32: u32) -> Option<Self> {
+        Self::path(crc32)
+            .filter(|path| fs::exists(path))
+            .and_then(|path| {
+                fs::load_raw(&path)
+                    .context("failed to load debug session")
+                    .and_then(|data| Ok(serde_json::from_slice::<Self>(&data)?))
+                    .map_err(|err| error!("invalid debug session {path:?}: {err:?}"))
+                    .ok()
+            })
+    }
+
+    /// Save the debugging session of a ROM matching `crc32`. Empty sessions are only saved to
+    /// replace an existing one.
+    pub fn save(&self, crc32: u32) -> anyhow::Result<()> {
+        let Some(path) = Self::path(crc32) else {
+            return Ok(());
+        };
+        if *self == Self::default() && !fs::exists(&path) {
+            return Ok(());
+        }
+        let data = serde_json::to_vec(self).context("failed to serialize debug session")?;
+        fs::save_raw(path, &data).context("failed to save debug session")
+    }
+}
@@ -2,123 +2,152 @@ use crate::{
     nes::{
         action::DebugStep,
         audio::{Audio, State as AudioState},
-        config::{Config, FrameRate},
-        emulation::{replay::Record, rewind::Rewind},
+        config::{
+            BackgroundMode, Config, FastForwardAudio, FastForwardConfig, FrameRate,
+            LiveReloadConfig, LiveReloadPreserve, ScreenshotConfig, ScreenshotFormat, SessionMode,
+        },
+        emulation::{
+            lan::{LanTransfer, ReceivedState, TrustedPeer},
+            livesplit::LiveSplit,
+            pipeline::VideoPipeline,
+            ram_watch::RamWatch,
+            replay::Record,
+            rewind::Rewind,
+            save_writer::SaveWriter,
+            stats::{self, Stats},
+        },
         event::{ConfigEvent, EmulationEvent, NesEvent, RendererEvent, SendNesEvent, UiEvent},
+        library,
+        plugin::{PluginEvent, Plugins},
         renderer::{gui::MessageType, FrameRecycle},
+        rom::{
+            patch,
+            stream::{self, ReadProgress, RomReader},
+            watch::RomWatcher,
+        },
     },
-    thread,
+    platform, thread,
 };
-use anyhow::{anyhow, bail};
+use anyhow::{anyhow, bail, Context};
 use chrono::Local;
 use crossbeam::channel;
 use egui::ViewportId;
 use replay::Replay;
 use std::{
-    collections::VecDeque,
     io::{self, Read},
+    ops::Range,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     thread::JoinHandle,
 };
 use tetanes_core::{
     apu::Apu,
+    autosplit::AutosplitRun,
     common::{NesRegion, Regional, Reset, ResetKind},
     control_deck::{self, ControlDeck, LoadedRom},
     cpu::Cpu,
+    fs,
+    genie::CheatMismatch,
+    input::Player,
+    mem::MemorySpace,
+    playlist::{Outcome, Playlist, PlaylistRun},
     ppu::Ppu,
     time::{Duration, Instant},
-    video::Frame,
+    video::{
+        ripper::{SpriteRipper, SpriteSheet},
+        stitch::MapStitcher,
+        Frame,
+    },
+};
+use thingbuf::{
+    mpsc::{
+        blocking::{SendRef, Sender as BufSender},
+        errors::{SendTimeoutError, TrySendError},
+    },
+    Recycle,
 };
-use thingbuf::mpsc::{blocking::Sender as BufSender, errors::TrySendError};
 use tracing::{debug, error};
 use winit::{event::ElementState, event_loop::EventLoopProxy};
 
+pub mod lan;
+pub mod livesplit;
+pub mod pipeline;
+pub mod ram_watch;
 pub mod replay;
 pub mod rewind;
+pub mod save_writer;
+pub mod stats;
 
-#[derive(Default, Debug, Copy, Clone, PartialEq)]
+/// A range of a memory space shown in the hex editor.
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[must_use]
-pub struct FrameStats {
-    pub fps: f32,
-    pub fps_min: f32,
-    pub frame_time: f32,
-    pub frame_time_max: f32,
-    pub frame_count: usize,
+pub struct MemoryPage {
+    pub space: MemorySpace,
+    /// Address of the first byte in `data`.
+    pub start: usize,
+    /// Size of the whole memory space in bytes.
+    pub len: usize,
+    pub data: Vec<u8>,
 }
 
-impl FrameStats {
-    pub fn new() -> Self {
-        Self::default()
-    }
-}
-
-#[derive(Debug)]
-#[must_use]
-pub struct FrameTimeDiag {
-    frame_count: usize,
-    history: VecDeque<f32>,
-    sum: f32,
-    avg: f32,
-    last_update: Instant,
+fn shutdown(tx: &EventLoopProxy<NesEvent>, err: impl std::fmt::Display) {
+    error!("{err}");
+    tx.nes_event(UiEvent::Terminate);
+    std::process::exit(1);
 }
 
-impl FrameTimeDiag {
-    const MAX_HISTORY: usize = 120;
-    const UPDATE_INTERVAL: Duration = Duration::from_millis(300);
-
-    fn new() -> Self {
-        Self {
-            frame_count: 0,
-            history: VecDeque::with_capacity(Self::MAX_HISTORY),
-            sum: 0.0,
-            avg: 1.0 / 60.0,
-            last_update: Instant::now(),
-        }
-    }
-
-    fn push(&mut self, frame_time: f32) {
-        self.frame_count += 1;
-
-        // Ignore the first few frames to allow the average to stabilize
-        if frame_time.is_finite() && self.frame_count >= 10 {
-            if self.history.len() >= Self::MAX_HISTORY {
-                if let Some(oldest) = self.history.pop_front() {
-                    self.sum -= oldest;
+/// Get a slot to send a frame in. If `vsync` is set, blocks until a slot is available, waking up
+/// periodically in case emulation is shutting down and frames stopped being received, otherwise
+/// drops the frame if the receiver is behind.
+fn send_ref<'a, T, R: Recycle<T>>(
+    frame_tx: &'a BufSender<T, R>,
+    vsync: bool,
+    stop: &AtomicBool,
+    tx: &EventLoopProxy<NesEvent>,
+) -> Option<SendRef<'a, T>> {
+    if vsync {
+        loop {
+            match frame_tx.send_ref_timeout(State::FRAME_SEND_TIMEOUT) {
+                Ok(frame) => return Some(frame),
+                Err(SendTimeoutError::Timeout(_)) => {
+                    if stop.load(Ordering::Acquire) {
+                        return None;
+                    }
+                }
+                Err(_) => {
+                    shutdown(tx, "failed to get frame");
+                    return None;
                 }
             }
-            self.sum += frame_time;
-            self.history.push_back(frame_time);
         }
-    }
-
-    fn avg(&mut self) -> f32 {
-        if !self.history.is_empty() {
-            let now = Instant::now();
-            if now > self.last_update + Self::UPDATE_INTERVAL {
-                self.last_update = now;
-                self.avg = self.sum / self.history.len() as f32;
+    } else {
+        match frame_tx.try_send_ref() {
+            Ok(frame) => Some(frame),
+            Err(TrySendError::Full(_)) => {
+                debug!("dropped frame");
+                stats::frame_dropped();
+                None
+            }
+            Err(_) => {
+                shutdown(tx, "failed to get frame");
+                None
             }
         }
-        self.avg
-    }
-
-    fn history(&self) -> impl Iterator<Item = &f32> {
-        self.history.iter()
-    }
-
-    fn reset(&mut self) {
-        self.frame_count = 0;
-        self.history.clear();
-        self.sum = 0.0;
-        self.avg = 1.0 / 60.0;
-        self.last_update = Instant::now();
     }
 }
 
-fn shutdown(tx: &EventLoopProxy<NesEvent>, err: impl std::fmt::Display) {
-    error!("{err}");
-    tx.nes_event(UiEvent::Terminate);
-    std::process::exit(1);
+/// Encode `image` as a PNG and save it through [`fs`], so it's also persisted in browser storage
+/// on the web.
+fn save_image(image: impl Into<image::DynamicImage>, path: &Path) -> anyhow::Result<()> {
+    let mut png = io::Cursor::new(Vec::new());
+    image
+        .into()
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .context("failed to encode image")?;
+    fs::save_raw(path, png.get_ref()).with_context(|| format!("failed to save image {path:?}"))
 }
 
 #[derive(Debug)]
@@ -126,6 +155,7 @@ fn shutdown(tx: &EventLoopProxy<NesEvent>, err: impl std::fmt::Display) {
 enum Threads {
     Single(Single),
     Multi(Multi),
+    Stopped,
 }
 
 #[derive(Debug)]
@@ -139,6 +169,7 @@ struct Single {
 struct Multi {
     tx: channel::Sender<NesEvent>,
     handle: JoinHandle<()>,
+    stop: Arc<AtomicBool>,
 }
 
 impl Multi {
@@ -148,11 +179,16 @@ impl Multi {
         config: Config,
     ) -> anyhow::Result<Self> {
         let (tx, rx) = channel::bounded(1024);
+        let stop = Arc::new(AtomicBool::new(false));
         Ok(Self {
             tx,
             handle: std::thread::Builder::new()
                 .name("emulation".into())
-                .spawn(move || Self::main(proxy_tx, rx, frame_tx, config))?,
+                .spawn({
+                    let stop = Arc::clone(&stop);
+                    move || Self::main(proxy_tx, rx, frame_tx, config, stop)
+                })?,
+            stop,
         })
     }
 
@@ -161,10 +197,14 @@ impl Multi {
         rx: channel::Receiver<NesEvent>,
         frame_tx: BufSender<Frame, FrameRecycle>,
         config: Config,
+        stop: Arc<AtomicBool>,
     ) {
         debug!("emulation thread started");
-        let mut state = State::new(tx, frame_tx, config); // Has to be created on the thread, since
-        loop {
+        // Has to be created on the thread, since
+        let mut state = State::new(tx, frame_tx, config, Arc::clone(&stop));
+        state.start_video_pipeline();
+        state.start_save_writer();
+        while !stop.load(Ordering::Acquire) {
             #[cfg(feature = "profiling")]
             puffin::profile_scope!("emulation loop");
 
@@ -174,6 +214,13 @@ impl Multi {
 
             state.clock_frame();
         }
+
+        // Handle anything sent before stopping, e.g. a final save state
+        while let Ok(event) = rx.try_recv() {
+            state.on_event(&event);
+        }
+        state.shutdown();
+        debug!("emulation thread stopped");
     }
 }
 
@@ -196,7 +243,7 @@ impl Emulation {
             Threads::Multi(Multi::spawn(tx, frame_tx, cfg)?)
         } else {
             Threads::Single(Single {
-                state: State::new(tx, frame_tx, cfg),
+                state: State::new(tx, frame_tx, cfg, Arc::default()),
             })
         };
 
@@ -207,13 +254,14 @@ impl Emulation {
     pub fn on_event(&mut self, event: &NesEvent) {
         match &mut self.threads {
             Threads::Single(Single { state }) => state.on_event(event),
-            Threads::Multi(Multi { tx, handle }) => {
+            Threads::Multi(Multi { tx, handle, .. }) => {
                 handle.thread().unpark();
                 if let Err(err) = tx.try_send(event.clone()) {
                     error!("failed to send emulation event: {event:?}. {err:?}");
                     std::process::exit(1);
                 }
             }
+            Threads::Stopped => (),
         }
     }
 
@@ -222,6 +270,23 @@ impl Emulation {
             Threads::Single(Single { state }) => state.clock_frame(),
             // Multi-threaded emulation handles it's own clock timing and redraw requests
             Threads::Multi(Multi { handle, .. }) => handle.thread().unpark(),
+            Threads::Stopped => (),
+        }
+    }
+
+    /// Stop emulation, flushing save RAM, save states and audio output. Blocks until the
+    /// emulation thread has finished, if threaded.
+    pub fn shutdown(&mut self) {
+        match std::mem::replace(&mut self.threads, Threads::Stopped) {
+            Threads::Single(Single { mut state }) => state.shutdown(),
+            Threads::Multi(Multi { handle, stop, .. }) => {
+                stop.store(true, Ordering::Release);
+                handle.thread().unpark();
+                if handle.join().is_err() {
+                    error!("emulation thread panicked while stopping");
+                }
+            }
+            Threads::Stopped => (),
         }
     }
 }
@@ -233,26 +298,77 @@ pub struct State {
     control_deck: ControlDeck,
     audio: Audio,
     frame_tx: BufSender<Frame, FrameRecycle>,
+    /// Filters frames on a worker thread while the next frame is emulated, if threaded.
+    video_pipeline: Option<VideoPipeline>,
+    /// Writes auto-saves on a worker thread, if threaded.
+    save_writer: Option<SaveWriter>,
     frame_latency: usize,
     target_frame_duration: Duration,
     last_clock_time: Instant,
     clock_time_accumulator: f32,
-    last_frame_time: Instant,
-    frame_time_diag: FrameTimeDiag,
+    stats: Stats,
+    /// Whether the window is unfocused or hidden.
+    in_background: bool,
+    background: BackgroundMode,
     unfocused_paused: bool,
+    /// Set to clock a single frame while paused.
+    frame_advance: bool,
+    /// Frames emulated in a row without rendering while catching up.
+    frames_skipped: u32,
+    max_frame_skip: u32,
     paused: bool,
     rewinding: bool,
     rewind: Rewind,
     record: Record,
     replay: Replay,
+    map_stitcher: Option<MapStitcher>,
+    sprite_ripper: Option<SpriteRipper>,
     save_slot: u8,
     auto_save: bool,
     auto_save_interval: Duration,
     last_auto_save: Instant,
     auto_load: bool,
+    session: SessionMode,
+    /// Set to resume the session of the next ROM loaded without asking.
+    resume_session: bool,
+    auto_patch: bool,
+    /// Patch to apply to the next ROM loaded from a path.
+    patch: Option<PathBuf>,
+    live_reload: LiveReloadConfig,
+    /// Path of the ROM being read, to watch once loaded.
+    pending_rom_path: Option<PathBuf>,
+    /// Watches the file the loaded ROM was read from.
+    rom_watcher: Option<RomWatcher>,
     speed: f32,
+    fast_forward: FastForwardConfig,
+    fast_forwarding: bool,
+    /// Frames emulated since the last rendered frame while fast-forwarding.
+    fast_forward_frames: u32,
+    fast_forward_audio: FastForwardAudio,
+    dynamic_rate_control: bool,
     run_ahead: usize,
     show_frame_stats: bool,
+    show_perf_stats: bool,
+    show_input_diagnostics: bool,
+    show_mapper_inspector: bool,
+    hex_editor_view: Option<(MemorySpace, Range<usize>)>,
+    show_ram_watch: bool,
+    ram_watch: RamWatch,
+    show_audio_scope: bool,
+    record_stems: bool,
+    screenshot: ScreenshotConfig,
+    lan: Option<LanTransfer>,
+    /// Devices allowed to send save states.
+    lan_trusted_peers: Vec<TrustedPeer>,
+    /// Save state received from a peer, waiting for its ROM to be loaded or to be accepted.
+    pending_lan_state: Option<ReceivedState>,
+    livesplit: Option<LiveSplit>,
+    autosplit: Option<AutosplitRun>,
+    playlist: Option<PlaylistRun>,
+    rom_reader: Option<RomReader>,
+    plugins: Plugins,
+    /// Set when emulation is shutting down.
+    stop: Arc<AtomicBool>,
 }
 
 impl Drop for State {
@@ -262,10 +378,16 @@ impl Drop for State {
 }
 
 impl State {
+    /// Maximum adjustment to the emulation sample rate made by dynamic rate control.
+    const MAX_RATE_DELTA: f32 = 0.005;
+    /// How long to block waiting on vsync before checking if emulation is shutting down.
+    const FRAME_SEND_TIMEOUT: Duration = Duration::from_millis(100);
+
     fn new(
         tx: EventLoopProxy<NesEvent>,
         frame_tx: BufSender<Frame, FrameRecycle>,
         cfg: Config,
+        stop: Arc<AtomicBool>,
     ) -> Self {
         let mut control_deck = ControlDeck::with_config(cfg.deck.clone());
         let audio = Audio::new(
@@ -277,6 +399,7 @@ impl State {
         if Apu::DEFAULT_SAMPLE_RATE != audio.sample_rate {
             control_deck.set_sample_rate(audio.sample_rate);
         }
+        control_deck.set_capture_channel_samples(cfg.renderer.show_audio_scope);
         let rewind = Rewind::new(
             cfg.emulation.rewind,
             cfg.emulation.rewind_seconds,
@@ -288,28 +411,76 @@ impl State {
             control_deck,
             audio,
             frame_tx,
+            video_pipeline: None,
+            save_writer: None,
             frame_latency: 1,
             target_frame_duration,
             last_clock_time: Instant::now(),
             clock_time_accumulator: 0.0,
-            last_frame_time: Instant::now(),
-            frame_time_diag: FrameTimeDiag::new(),
+            stats: Stats::new(),
+            in_background: false,
+            background: cfg.emulation.background,
             unfocused_paused: false,
+            frame_advance: false,
+            frames_skipped: 0,
+            max_frame_skip: cfg.emulation.max_frame_skip,
             paused: true,
             rewinding: false,
             rewind,
             record: Record::new(),
             replay: Replay::new(),
+            map_stitcher: None,
+            sprite_ripper: None,
             save_slot: cfg.emulation.save_slot,
             auto_save: cfg.emulation.auto_save,
             auto_save_interval: cfg.emulation.auto_save_interval,
             last_auto_save: Instant::now(),
             auto_load: cfg.emulation.auto_load,
+            session: cfg.emulation.session,
+            resume_session: cfg.emulation.resume,
+            auto_patch: cfg.emulation.auto_patch,
+            patch: cfg.emulation.patch.clone(),
+            live_reload: cfg.emulation.live_reload,
+            pending_rom_path: None,
+            rom_watcher: None,
             speed: cfg.emulation.speed,
+            fast_forward: cfg.emulation.fast_forward,
+            fast_forwarding: false,
+            fast_forward_frames: 0,
+            fast_forward_audio: cfg.audio.fast_forward,
+            dynamic_rate_control: cfg.audio.dynamic_rate_control,
             run_ahead: cfg.emulation.run_ahead,
             show_frame_stats: false,
+            show_perf_stats: cfg.renderer.show_perf_stats,
+            show_input_diagnostics: false,
+            show_mapper_inspector: false,
+            hex_editor_view: None,
+            show_ram_watch: false,
+            ram_watch: RamWatch::default(),
+            show_audio_scope: cfg.renderer.show_audio_scope,
+            record_stems: cfg.audio.record_stems,
+            screenshot: cfg.emulation.screenshot.clone(),
+            lan: None,
+            lan_trusted_peers: cfg.emulation.lan_trusted_peers.clone(),
+            pending_lan_state: None,
+            livesplit: None,
+            autosplit: None,
+            playlist: None,
+            rom_reader: None,
+            plugins: Plugins::default(),
+            stop,
         };
-        state.update_region(cfg.deck.region);
+        state.update_region(state.control_deck.region());
+        state.update_speed();
+        state.set_lan_transfer(cfg.emulation.lan_transfer);
+        state.set_livesplit(&cfg.emulation.livesplit);
+        if cfg.emulation.plugins {
+            state.plugins = Plugins::load(&state.control_deck);
+            state
+                .control_deck
+                .set_scanline_hook(state.plugins.scanline_hook());
+            state.flush_plugins();
+        }
         state
     }
 
@@ -317,6 +488,24 @@ impl State {
         self.tx.nes_event(UiEvent::Message((ty, msg.to_string())));
     }
 
+    fn plugin_event(&mut self, event: PluginEvent) {
+        if self.plugins.is_empty() {
+            return;
+        }
+        self.plugins.on_event(&self.control_deck, &event);
+        self.flush_plugins();
+    }
+
+    /// Forward plugin messages and UI changes to the renderer.
+    fn flush_plugins(&mut self) {
+        for msg in self.plugins.take_messages() {
+            self.add_message(MessageType::Info, msg);
+        }
+        if let Some(ui) = self.plugins.take_ui() {
+            self.tx.nes_event(RendererEvent::Plugins(ui));
+        }
+    }
+
     fn write_deck<T>(
         &mut self,
         writer: impl FnOnce(&mut ControlDeck) -> control_deck::Result<T>,
@@ -358,6 +547,16 @@ impl State {
                     self.audio_record(*recording);
                 }
             }
+            EmulationEvent::AutoSave => {
+                if self.control_deck.is_running() {
+                    if self.auto_save {
+                        self.auto_save();
+                    } else {
+                        self.save_sram();
+                    }
+                }
+            }
+            EmulationEvent::CodeDataLog(logging) => self.code_data_log(*logging),
             EmulationEvent::DebugStep(step) => {
                 if self.control_deck.is_running() {
                     match step {
@@ -380,6 +579,11 @@ impl State {
                                 self.send_frame();
                             }
                         }
+                        DebugStep::Frame if self.paused => {
+                            // Clock through the normal frame path so input replays, rewind
+                            // and frame callbacks stay in sync
+                            self.frame_advance = true;
+                        }
                         DebugStep::Frame => {
                             if self.write_deck(|deck| deck.clock_frame()).is_some() {
                                 self.send_frame();
@@ -391,6 +595,40 @@ impl State {
             EmulationEvent::EmulatePpuWarmup(enabled) => {
                 self.control_deck.set_emulate_ppu_warmup(*enabled);
             }
+            EmulationEvent::FastForward(fast_forwarding) => {
+                self.fast_forwarding = *fast_forwarding;
+                self.fast_forward_frames = 0;
+                self.update_speed();
+            }
+            EmulationEvent::FamilyKey((key, state)) => {
+                if self.control_deck.is_running() {
+                    let pressed = *state == ElementState::Pressed;
+                    self.control_deck
+                        .expansion_mut()
+                        .keyboard
+                        .set_key(*key, pressed);
+                    self.record
+                        .push(self.control_deck.frame_number(), event.clone());
+                }
+            }
+            EmulationEvent::HexEditor(view) => {
+                self.hex_editor_view.clone_from(view);
+                self.send_memory_page();
+            }
+            EmulationEvent::NsfTrack(track) => {
+                if self.control_deck.is_running() {
+                    self.control_deck.set_nsf_track(*track);
+                    self.record
+                        .push(self.control_deck.frame_number(), event.clone());
+                }
+            }
+            EmulationEvent::InsertCoin(slot) => {
+                if self.control_deck.is_running() {
+                    self.control_deck.insert_coin(*slot);
+                    self.record
+                        .push(self.control_deck.frame_number(), event.clone());
+                }
+            }
             EmulationEvent::InstantRewind => {
                 if self.control_deck.is_running() {
                     self.instant_rewind();
@@ -405,6 +643,13 @@ impl State {
                         .push(self.control_deck.frame_number(), event.clone());
                 }
             }
+            EmulationEvent::LanStateReceived(state) => self.on_lan_state_received(state.clone()),
+            EmulationEvent::LoadLanState => {
+                if self.control_deck.is_running() {
+                    self.load_lan_state();
+                }
+            }
+            EmulationEvent::DiscardLanState => self.discard_lan_state(),
             EmulationEvent::LoadReplay((name, replay)) => {
                 if self.control_deck.is_running() {
                     self.load_replay(name, &mut io::Cursor::new(replay));
@@ -416,22 +661,104 @@ impl State {
                 }
             }
             EmulationEvent::LoadRom((name, rom)) => {
+                // Finished reading, or a ROM was loaded from elsewhere while reading
+                if let Some(reader) = self.rom_reader.take() {
+                    reader.cancel();
+                }
+                self.stop_playlist();
+                // ROMs loaded from elsewhere while reading aren't watched
+                let path = self
+                    .pending_rom_path
+                    .take()
+                    .filter(|path| name.starts_with(fs::filename(path)));
                 self.load_rom(name, &mut io::Cursor::new(rom));
+                if self.control_deck.is_running() {
+                    self.rom_watcher = path.map(RomWatcher::new);
+                }
+            }
+            EmulationEvent::LoadRomPath(path) => {
+                self.stop_playlist();
+                self.load_rom_path(path);
             }
-            EmulationEvent::LoadRomPath(path) => self.load_rom_path(path),
             EmulationEvent::LoadState(slot) => self.load_state(*slot),
+            EmulationEvent::MapDump(dumping) => {
+                if self.control_deck.is_running() {
+                    self.map_dump(*dumping);
+                }
+            }
+            EmulationEvent::MouseButtons((left, right)) => {
+                if self.control_deck.is_running() {
+                    self.control_deck.set_mouse_buttons(*left, *right);
+                    self.record
+                        .push(self.control_deck.frame_number(), event.clone());
+                }
+            }
+            EmulationEvent::MouseMotion((dx, dy)) => {
+                if self.control_deck.is_running() {
+                    self.control_deck.move_mouse(*dx, *dy);
+                    self.record
+                        .push(self.control_deck.frame_number(), event.clone());
+                }
+            }
             EmulationEvent::Pause(paused) => {
                 if self.control_deck.is_running() {
                     self.pause(*paused);
                 }
             }
+            EmulationEvent::PlaylistSkip => self.advance_playlist(Outcome::Skipped),
+            EmulationEvent::PlaylistStart(playlist) => self.start_playlist(playlist),
+            EmulationEvent::PlaylistStop => self.stop_playlist(),
+            EmulationEvent::PluginMenuItem((index, id)) => {
+                self.plugins.on_menu_item(&self.control_deck, *index, *id);
+                self.flush_plugins();
+            }
+            EmulationEvent::RamCheat((addr, val)) => {
+                match val {
+                    Some(val) => self.control_deck.add_ram_cheat(*addr, *val),
+                    None => self.control_deck.remove_ram_cheat(*addr),
+                }
+                self.send_ram_watch();
+            }
+            EmulationEvent::RamSearchFilter(filter) => {
+                self.ram_watch.filter(&self.control_deck, *filter);
+                self.send_ram_watch();
+            }
+            EmulationEvent::RamSearchReset => {
+                self.ram_watch.reset(&self.control_deck);
+                self.send_ram_watch();
+            }
+            EmulationEvent::RamWatches(watches) => {
+                self.ram_watch.set_watches(watches.clone());
+                self.send_ram_watch();
+            }
+            EmulationEvent::PokeMemory((space, addr, val)) => {
+                if self.control_deck.poke_memory(*space, *addr, *val) {
+                    self.send_memory_page();
+                } else {
+                    self.add_message(
+                        MessageType::Warn,
+                        format!("{space} ${addr:04X} is not writable"),
+                    );
+                }
+            }
+            EmulationEvent::PowerPad((button, state)) => {
+                if self.control_deck.is_running() {
+                    let pressed = *state == ElementState::Pressed;
+                    self.control_deck
+                        .expansion_mut()
+                        .power_pad
+                        .set_button(*button, pressed);
+                    self.record
+                        .push(self.control_deck.frame_number(), event.clone());
+                }
+            }
             EmulationEvent::ReplayRecord(recording) => {
                 if self.control_deck.is_running() {
                     self.replay_record(*recording);
                 }
             }
             EmulationEvent::Reset(kind) => {
-                self.frame_time_diag.reset();
+                self.stats.reset();
                 if self.control_deck.is_running() {
                     self.control_deck.reset(*kind);
                     self.pause(false);
@@ -439,6 +766,27 @@ impl State {
                         ResetKind::Soft => self.add_message(MessageType::Info, "Reset"),
                         ResetKind::Hard => self.add_message(MessageType::Info, "Power Cycled"),
                     }
+                    self.plugin_event(PluginEvent::Reset);
+                }
+            }
+            EmulationEvent::ResumeSession => {
+                if self.control_deck.is_running() {
+                    self.resume_session();
+                }
+            }
+            EmulationEvent::RewindSeek(index) => {
+                if self.control_deck.is_running() {
+                    self.rewind_seek(*index);
+                }
+            }
+            EmulationEvent::RewindTimeline => {
+                if self.control_deck.is_running() {
+                    if self.rewind.enabled {
+                        self.tx
+                            .nes_event(RendererEvent::RewindTimeline(self.rewind.timeline()));
+                    } else {
+                        self.rewind_disabled();
+                    }
                 }
             }
             EmulationEvent::Rewinding(rewind) => {
@@ -454,30 +802,97 @@ impl State {
                 }
             }
             EmulationEvent::SaveState(slot) => self.save_state(*slot, false),
+            EmulationEvent::ScanBarcode(barcode) => {
+                if self.control_deck.is_running() {
+                    match self
+                        .control_deck
+                        .expansion_mut()
+                        .barcode_battler
+                        .scan(barcode)
+                    {
+                        Ok(()) => {
+                            self.add_message(MessageType::Info, format!("Scanned {barcode}"));
+                            self.record
+                                .push(self.control_deck.frame_number(), event.clone());
+                        }
+                        Err(err) => self.add_message(MessageType::Warn, err),
+                    }
+                }
+            }
+            EmulationEvent::SearchMemory((space, pattern, from)) => {
+                let found = self.search_memory(*space, pattern, *from);
+                self.tx.nes_event(RendererEvent::MemorySearch(found));
+            }
+            EmulationEvent::SendState(peer) => {
+                if self.control_deck.is_running() {
+                    self.send_state(peer.clone());
+                }
+            }
             EmulationEvent::ShowFrameStats(show) => {
-                self.frame_time_diag.reset();
+                self.stats.reset();
                 self.show_frame_stats = *show;
             }
-            EmulationEvent::Screenshot => {
+            EmulationEvent::ShowInputDiagnostics(show) => self.show_input_diagnostics = *show,
+            EmulationEvent::ShowRamWatch(show) => {
+                self.show_ram_watch = *show;
+                self.send_ram_watch();
+            }
+            EmulationEvent::ShowMapperInspector(show) => {
+                self.show_mapper_inspector = *show;
+                self.control_deck.set_log_mapper_writes(*show);
+            }
+            EmulationEvent::Screenshot(cfg) => {
                 if self.control_deck.is_running() {
-                    match self.save_screenshot() {
+                    match self.save_screenshot(cfg, None) {
                         Ok(filename) => {
+                            let copied = if cfg.copy_to_clipboard {
+                                " and copied"
+                            } else {
+                                ""
+                            };
                             self.add_message(
                                 MessageType::Info,
-                                format!("Screenshot Saved: {}", filename.display()),
+                                format!("Screenshot Saved{copied}: {}", filename.display()),
                             );
                         }
                         Err(err) => self.on_error(err),
                     }
                 }
             }
-            EmulationEvent::UnfocusedPause(paused) => {
-                self.unfocused_paused = *paused;
+            EmulationEvent::SpriteRip(ripping) => {
                 if self.control_deck.is_running() {
-                    self.audio.pause(self.unfocused_paused);
+                    self.sprite_rip(*ripping);
+                }
+            }
+            EmulationEvent::UnfocusedPause(in_background) => {
+                self.in_background = *in_background;
+                self.update_background();
+            }
+            EmulationEvent::UnloadRom => {
+                self.stop_playlist();
+                self.unload_rom();
+            }
+            EmulationEvent::VausAim(x) => {
+                if self.control_deck.is_running() {
+                    self.control_deck.aim_vaus(*x);
+                    self.record
+                        .push(self.control_deck.frame_number(), event.clone());
+                }
+            }
+            EmulationEvent::VausButton(pressed) => {
+                if self.control_deck.is_running() {
+                    self.control_deck.set_vaus_button(*pressed);
+                    self.record
+                        .push(self.control_deck.frame_number(), event.clone());
+                }
+            }
+            EmulationEvent::VsServiceButton(pressed) => {
+                if self.control_deck.is_running() {
+                    self.control_deck.set_vs_service_button(*pressed);
+                    self.record
+                        .push(self.control_deck.frame_number(), event.clone());
                 }
             }
-            EmulationEvent::UnloadRom => self.unload_rom(),
             EmulationEvent::ZapperAim((x, y)) => {
                 self.control_deck.aim_zapper(*x, *y);
                 self.record
@@ -508,6 +923,13 @@ impl State {
                     self.on_error(err);
                 }
             }
+            ConfigEvent::AccurateMixing(enabled) => {
+                self.control_deck.set_accurate_mixing(*enabled);
+            }
+            ConfigEvent::ApuChannelVolume((channel, volume)) => {
+                self.control_deck.set_apu_channel_volume(*channel, *volume);
+            }
+            ConfigEvent::AudioFilters(filters) => self.control_deck.set_audio_filters(*filters),
             ConfigEvent::AudioEnabled(enabled) => match self.audio.set_enabled(*enabled) {
                 Ok(state) => match state {
                     AudioState::Started => self.add_message(MessageType::Info, "Audio Enabled"),
@@ -523,7 +945,20 @@ impl State {
                     self.on_error(err);
                 }
             }
+            ConfigEvent::AudioRecordStems(enabled) => self.record_stems = *enabled,
+            ConfigEvent::AudioScope(enabled) => {
+                self.show_audio_scope = *enabled;
+                self.update_channel_capture();
+            }
+            ConfigEvent::PerfStats(enabled) => {
+                self.stats.reset();
+                self.show_perf_stats = *enabled;
+            }
+            ConfigEvent::ApplyRecordedCheats(enabled) => {
+                self.control_deck.set_apply_recorded_cheats(*enabled);
+            }
             ConfigEvent::AutoLoad(enabled) => self.auto_load = *enabled,
+            ConfigEvent::AutoPatch(enabled) => self.auto_patch = *enabled,
             ConfigEvent::AutoSave(enabled) => self.auto_save = *enabled,
             ConfigEvent::AutoSaveInterval(interval) => self.auto_save_interval = *interval,
             ConfigEvent::ConcurrentDpad(enabled) => {
@@ -532,6 +967,25 @@ impl State {
             ConfigEvent::CycleAccurate(enabled) => {
                 self.control_deck.set_cycle_accurate(*enabled);
             }
+            ConfigEvent::DynamicRateControl(enabled) => {
+                self.dynamic_rate_control = *enabled;
+                self.update_rate_control();
+            }
+            ConfigEvent::ExpansionDevice(device) => {
+                self.control_deck.set_expansion_device(*device);
+            }
+            ConfigEvent::BackgroundMode(mode) => {
+                self.background = *mode;
+                self.update_background();
+            }
+            ConfigEvent::FastForward(fast_forward) => {
+                self.fast_forward = *fast_forward;
+                self.update_speed();
+            }
+            ConfigEvent::FastForwardAudio(fast_forward) => {
+                self.fast_forward_audio = *fast_forward;
+                self.update_speed();
+            }
             ConfigEvent::FourPlayer(four_player) => {
                 self.control_deck.set_four_player(*four_player);
             }
@@ -544,83 +998,262 @@ impl State {
             ConfigEvent::GenieCodeRemoved(code) => {
                 self.control_deck.remove_genie_code(code);
             }
+            ConfigEvent::HeaderOverrides(overrides) => {
+                if let Some(rom) = self.control_deck.loaded_rom() {
+                    let name = rom.name.clone();
+                    self.control_deck.set_header_overrides(name, *overrides);
+                    self.reload_rom();
+                }
+            }
             ConfigEvent::RamState(ram_state) => {
                 self.control_deck.set_ram_state(*ram_state);
             }
             ConfigEvent::Region(region) => {
                 self.control_deck.set_region(*region);
-                self.update_region(*region);
+                // Auto-detected regions depend on the loaded ROM
+                self.update_region(self.control_deck.region());
             }
             ConfigEvent::RewindEnabled(enabled) => self.rewind.set_enabled(*enabled),
             ConfigEvent::RewindSeconds(seconds) => self.rewind.set_seconds(*seconds),
             ConfigEvent::RewindInterval(interval) => self.rewind.set_interval(*interval),
+            ConfigEvent::RomDipSwitches(value) => {
+                if let Some(rom) = self.control_deck.loaded_rom() {
+                    let name = rom.name.clone();
+                    self.control_deck.set_rom_dip_switches(name, *value);
+                }
+            }
+            ConfigEvent::RomExpansionDevice(device) => {
+                if let Some(rom) = self.control_deck.loaded_rom() {
+                    let name = rom.name.clone();
+                    self.control_deck.set_rom_expansion_device(name, *device);
+                }
+            }
+            ConfigEvent::RomMapperRevision(rev) => {
+                if let Some(rom) = self.control_deck.loaded_rom() {
+                    let name = rom.name.clone();
+                    self.control_deck.set_rom_mapper_revision(name, *rev);
+                }
+            }
+            ConfigEvent::RomRamState(ram_state) => {
+                if let Some(rom) = self.control_deck.loaded_rom() {
+                    let name = rom.name.clone();
+                    self.control_deck.set_rom_ram_state(name, *ram_state);
+                }
+            }
+            ConfigEvent::RomRegion(region) => {
+                if let Some(rom) = self.control_deck.loaded_rom() {
+                    let name = rom.name.clone();
+                    self.control_deck.set_rom_region(name, *region);
+                    self.update_region(self.control_deck.region());
+                }
+            }
+            ConfigEvent::MaxFrameSkip(max_frame_skip) => self.max_frame_skip = *max_frame_skip,
             ConfigEvent::RunAhead(run_ahead) => self.run_ahead = *run_ahead,
             ConfigEvent::SaveSlot(slot) => self.save_slot = *slot,
+            ConfigEvent::Screenshot(screenshot) => self.screenshot = screenshot.clone(),
+            ConfigEvent::SessionMode(mode) => self.session = *mode,
             ConfigEvent::MapperRevisions(revs) => {
                 self.control_deck.set_mapper_revisions(*revs);
             }
+            ConfigEvent::MasterVolume(volume) => self.control_deck.set_master_volume(*volume),
+            ConfigEvent::Mouse(port) => self.control_deck.connect_mouse(*port),
+            ConfigEvent::MouseSensitivity(sensitivity) => {
+                self.control_deck.set_mouse_sensitivity(*sensitivity);
+            }
+            ConfigEvent::NtscConfig(ntsc) => self.control_deck.set_ntsc_config(*ntsc),
+            ConfigEvent::PostProcess(stages) => {
+                self.control_deck.set_post_process(stages.clone());
+            }
             ConfigEvent::Speed(speed) => {
                 self.speed = *speed;
-                self.control_deck.set_frame_speed(*speed);
+                self.update_speed();
+            }
+            ConfigEvent::SramAutosaveInterval(interval) => {
+                self.control_deck.set_sram_autosave_interval(*interval);
+            }
+            ConfigEvent::VausConnected(connected) => {
+                self.control_deck.connect_vaus(*connected);
             }
             ConfigEvent::VideoFilter(filter) => self.control_deck.set_filter(*filter),
             ConfigEvent::ZapperConnected(connected) => {
                 self.control_deck.connect_zapper(*connected);
             }
-            ConfigEvent::HideOverscan(_) | ConfigEvent::InputBindings | ConfigEvent::Scale(_) => (),
+            ConfigEvent::LanTransfer(enabled) => self.set_lan_transfer(*enabled),
+            ConfigEvent::LanTrustedPeers(trusted) => self.set_lan_trusted_peers(trusted.clone()),
+            ConfigEvent::LiveReload(live_reload) => {
+                if live_reload.enabled && !self.live_reload.enabled {
+                    // Only reload changes made from now on
+                    self.rom_watcher = self
+                        .rom_watcher
+                        .take()
+                        .map(|watcher| RomWatcher::new(watcher.path().to_path_buf()));
+                }
+                self.live_reload = *live_reload;
+            }
+            ConfigEvent::LiveSplit(cfg) => self.set_livesplit(cfg),
+            ConfigEvent::InputBindings | ConfigEvent::Scale(_) => (),
+        }
+    }
+
+    /// Record how long the last frame took to clock and mix audio, and send updated stats if
+    /// the performance stats window or overlay is shown.
+    fn update_frame_stats(&mut self, emulation_time: Duration, audio_time: Duration) {
+        if !self.show_frame_stats && !self.show_perf_stats {
+            return;
+        }
+        self.stats.push(emulation_time, audio_time);
+        let stats = self
+            .stats
+            .frame_stats(self.audio.queued_time(), self.audio.latency);
+        self.tx.nes_event(RendererEvent::FrameStats(stats));
+    }
+
+    /// Per-channel samples are only captured while needed by the audio scope or stem recording,
+    /// and nominal rate samples only while recording.
+    fn update_channel_capture(&mut self) {
+        let capture = self.show_audio_scope || self.audio.is_recording_stems();
+        self.control_deck.set_capture_channel_samples(capture);
+        self.control_deck
+            .set_capture_record_samples(self.audio.is_recording());
+    }
+
+    fn send_audio_scope(&mut self) {
+        if !self.show_audio_scope {
+            return;
+        }
+        let channel_samples = self.control_deck.channel_samples();
+        if !channel_samples.is_empty() {
+            self.tx
+                .nes_event(RendererEvent::AudioScope(channel_samples.to_vec()));
+        }
+    }
+
+    fn send_joypad_state(&mut self) {
+        if !self.show_input_diagnostics {
+            return;
+        }
+        let joypads = [Player::One, Player::Two, Player::Three, Player::Four]
+            .map(|player| self.control_deck.joypad(player).buttons);
+        self.tx.nes_event(RendererEvent::JoypadState((
+            self.control_deck.frame_number(),
+            joypads,
+        )));
+    }
+
+    fn send_nsf_state(&mut self) {
+        if let (Some(track), Some(elapsed)) = (
+            self.control_deck.nsf_track(),
+            self.control_deck.nsf_elapsed(),
+        ) {
+            self.tx.nes_event(RendererEvent::NsfState((track, elapsed)));
+        }
+    }
+
+    fn send_mapper_state(&mut self) {
+        if !self.show_mapper_inspector {
+            return;
         }
+        self.tx.nes_event(RendererEvent::MapperState((
+            self.control_deck.mapper_registers(),
+            self.control_deck.take_mapper_writes(),
+        )));
     }
 
-    fn update_frame_stats(&mut self) {
-        if !self.show_frame_stats {
+    fn send_ram_watch(&mut self) {
+        if !self.show_ram_watch {
             return;
         }
+        self.tx.nes_event(RendererEvent::RamWatch(
+            self.ram_watch.state(&self.control_deck),
+        ));
+    }
 
-        self.frame_time_diag
-            .push(self.last_frame_time.elapsed().as_secs_f32());
-        self.last_frame_time = Instant::now();
-        let frame_time = self.frame_time_diag.avg();
-        let frame_time_max = self
-            .frame_time_diag
-            .history()
-            .fold(-f32::INFINITY, |a, b| a.max(*b));
-        let mut fps = 1.0 / frame_time;
-        let mut fps_min = 1.0 / frame_time_max;
-        if !fps.is_finite() {
-            fps = 0.0;
-        }
-        if !fps_min.is_finite() {
-            fps_min = 0.0;
-        }
-        self.tx.nes_event(RendererEvent::FrameStats(FrameStats {
-            fps,
-            fps_min,
-            frame_time: frame_time * 1000.0,
-            frame_time_max: frame_time_max * 1000.0,
-            frame_count: self.frame_time_diag.frame_count,
+    fn send_memory_page(&mut self) {
+        let Some((space, range)) = &self.hex_editor_view else {
+            return;
+        };
+        let len = self.control_deck.memory_len(*space);
+        let start = range.start.min(len);
+        let data = (start..range.end.min(len))
+            .filter_map(|addr| self.control_deck.peek_memory(*space, addr))
+            .collect();
+        self.tx.nes_event(RendererEvent::MemoryPage(MemoryPage {
+            space: *space,
+            start,
+            len,
+            data,
         }));
     }
 
+    /// Find the first occurrence of `pattern` in `space` at or after `from`, wrapping around to
+    /// the start.
+    fn search_memory(&self, space: MemorySpace, pattern: &[u8], from: usize) -> Option<usize> {
+        if pattern.is_empty() {
+            return None;
+        }
+        let memory = (0..self.control_deck.memory_len(space))
+            .filter_map(|addr| self.control_deck.peek_memory(space, addr))
+            .collect::<Vec<_>>();
+        let len = memory.len();
+        (0..len)
+            .map(|offset| (from + offset) % len)
+            .find(|&addr| memory[addr..].starts_with(pattern))
+    }
+
+    /// Filter frames on a worker thread while the next frame is emulated, falling back to
+    /// filtering them inline if the worker fails to start.
+    fn start_video_pipeline(&mut self) {
+        match VideoPipeline::spawn(
+            self.tx.clone(),
+            self.frame_tx.clone(),
+            self.control_deck.video(),
+        ) {
+            Ok(pipeline) => self.video_pipeline = Some(pipeline),
+            Err(err) => error!("failed to start video pipeline: {err:?}"),
+        }
+    }
+
+    /// Write auto-saves on a worker thread, falling back to writing them inline if the worker fails
+    /// to start.
+    fn start_save_writer(&mut self) {
+        match SaveWriter::spawn(self.tx.clone()) {
+            Ok(save_writer) => self.save_writer = Some(save_writer),
+            Err(err) => error!("failed to start save writer: {err:?}"),
+        }
+    }
+
+    /// Whether frame rate is paced by blocking on vsync. If audio is enabled, frame rate is
+    /// controlled by the amount of audio queued instead. IMPORTANT: Wasm can't block.
+    fn vsync_paced(&self) -> bool {
+        // Hidden windows aren't redrawn, so they can't pace frames
+        !self.audio.enabled()
+            && !cfg!(target_arch = "wasm32")
+            && !self.in_background
+            && !self.uncapped()
+    }
+
     fn send_frame(&mut self) {
+        let vsync = self.vsync_paced();
+        if let Some(pipeline) = &mut self.video_pipeline {
+            pipeline.update_settings(self.control_deck.video());
+            let ppu = self.control_deck.ppu();
+            pipeline.send(ppu.frame_buffer(), ppu.frame_number(), vsync, &self.stop);
+            return;
+        }
         // Indicate we want to redraw to ensure there's a frame slot made available if
         // the pool is already full
         self.tx.nes_event(RendererEvent::RequestRedraw {
             viewport_id: ViewportId::ROOT,
             when: Instant::now(),
         });
-        // IMPORTANT: Wasm can't block
-        if self.audio.enabled() || cfg!(target_arch = "wasm32") {
-            match self.frame_tx.try_send_ref() {
-                Ok(mut frame) => self.control_deck.frame_buffer_into(&mut frame),
-                Err(TrySendError::Full(_)) => debug!("dropped frame"),
-                Err(_) => shutdown(&self.tx, "failed to get frame"),
-            }
-        } else if let Ok(mut frame) = self.frame_tx.send_ref() {
+        if let Some(mut frame) = send_ref(&self.frame_tx, vsync, &self.stop, &self.tx) {
+            frame.resize(self.control_deck.filter().frame_len(), 0xFF);
             self.control_deck.frame_buffer_into(&mut frame);
         }
     }
 
     fn pause(&mut self, paused: bool) {
+        let was_paused = self.paused;
         if !self.control_deck.cpu_corrupted() {
             self.paused = paused;
             if self.paused {
@@ -633,15 +1266,18 @@ impl State {
             self.audio.pause(self.paused);
             if !self.paused {
                 self.last_auto_save = Instant::now();
-                // To avoid having a large dip in frame stats when unpausing
-                self.last_frame_time = Instant::now();
+                self.stats.resume();
             }
         } else {
             self.paused = true;
         }
+        if self.paused != was_paused {
+            self.plugin_event(PluginEvent::Paused(self.paused));
+        }
     }
 
     fn save_state(&mut self, slot: u8, auto: bool) {
+        self.flush_saves();
         if let Some(rom) = self.control_deck.loaded_rom() {
             if let Some(data_dir) = Config::save_path(&rom.name, slot) {
                 match self.control_deck.save_state(data_dir) {
@@ -656,18 +1292,104 @@ impl State {
         }
     }
 
+    /// Write battery-backed RAM that changed since it was last saved to disk, in the background
+    /// if the save writer is running.
+    fn save_sram(&mut self) {
+        if self.save_writer.is_none() {
+            if let Err(err) = self.control_deck.save_sram() {
+                self.on_error(err);
+            }
+            return;
+        }
+        match self.control_deck.take_sram_changes() {
+            Ok(changes) => {
+                if let Some(save_writer) = &self.save_writer {
+                    for (path, data) in changes {
+                        save_writer.write(path, data);
+                    }
+                }
+            }
+            Err(err) => self.on_error(err),
+        }
+    }
+
+    /// Flush battery-backed RAM and the session save state to disk in the background, then signal
+    /// the UI to flush any configuration changes and show the auto-save indicator.
+    fn auto_save(&mut self) {
+        self.save_sram();
+        if self.save_writer.is_none() {
+            self.save_state(self.save_slot, true);
+        } else if let Some(path) = self
+            .control_deck
+            .loaded_rom()
+            .and_then(|rom| Config::save_path(&rom.name, self.save_slot))
+        {
+            match self.control_deck.save_state_bytes() {
+                Ok(data) => {
+                    if let Some(save_writer) = &self.save_writer {
+                        save_writer.write(path, data);
+                    }
+                }
+                Err(err) => self.on_error(err),
+            }
+        }
+        self.tx.nes_event(RendererEvent::AutoSaved);
+    }
+
+    /// Wait for saves queued on the save writer to be written, so they aren't read before they're
+    /// written or written over newer saves.
+    fn flush_saves(&self) {
+        if let Some(save_writer) = &self.save_writer {
+            save_writer.flush();
+        }
+    }
+
     fn load_state(&mut self, slot: u8) {
+        self.flush_saves();
         if let Some(rom) = self.control_deck.loaded_rom() {
             if let Some(path) = Config::save_path(&rom.name, slot) {
                 match self.control_deck.load_state(path) {
-                    Ok(_) => self.add_message(MessageType::Info, format!("State {slot} Loaded")),
+                    Ok(mismatch) => {
+                        self.add_message(MessageType::Info, format!("State {slot} Loaded"));
+                        if let Some(mismatch) = mismatch {
+                            self.on_cheat_mismatch(mismatch);
+                        }
+                    }
+                    Err(err) => self.on_error(err),
+                }
+            }
+        }
+    }
+
+    /// Load the session state saved when the loaded ROM was last unloaded.
+    fn resume_session(&mut self) {
+        self.flush_saves();
+        if let Some(rom) = self.control_deck.loaded_rom() {
+            if let Some(path) = Config::session_path(&rom.name) {
+                match self.control_deck.load_state(path) {
+                    Ok(mismatch) => {
+                        self.add_message(MessageType::Info, "Session Resumed");
+                        if let Some(mismatch) = mismatch {
+                            self.on_cheat_mismatch(mismatch);
+                        }
+                    }
                     Err(err) => self.on_error(err),
                 }
             }
         }
     }
 
+    /// Flush save RAM, save states and audio output before exiting.
+    fn shutdown(&mut self) {
+        self.unload_rom();
+        let _ = self.audio.stop();
+        // Finish filtering any frames already sent and writing any saves
+        self.video_pipeline = None;
+        self.save_writer = None;
+    }
+
     fn unload_rom(&mut self) {
+        self.flush_saves();
         if let Some(rom) = self.control_deck.loaded_rom() {
             if self.auto_save {
                 if let Some(path) = Config::save_path(&rom.name, self.save_slot) {
@@ -676,45 +1398,204 @@ impl State {
                     }
                 }
             }
+            if self.session != SessionMode::Off {
+                if let Some(path) = Config::session_path(&rom.name) {
+                    if let Err(err) = self.control_deck.save_state(path) {
+                        self.on_error(err);
+                    }
+                }
+            }
             self.replay_record(false);
+            self.map_dump(false);
+            self.sprite_rip(false);
             self.rewind.clear();
             let _ = self.audio.stop();
+            let crc32 = rom.crc32;
+            if platform::supports(platform::Feature::Filesystem) {
+                if let Err(err) = self.save_thumbnail(crc32) {
+                    error!("failed to save library thumbnail: {err:?}");
+                }
+            }
             if let Err(err) = self.control_deck.unload_rom() {
                 self.on_error(err);
             }
             self.tx.nes_event(RendererEvent::RomUnloaded);
-            self.frame_time_diag.reset();
+            self.plugin_event(PluginEvent::RomUnloaded);
+            self.autosplit = None;
+            self.ram_watch.clear();
+            self.stats.reset();
+            self.rom_watcher = None;
         }
     }
 
     fn on_load_rom(&mut self, rom: LoadedRom) {
+        self.flush_saves();
         if self.auto_load {
             if let Some(path) = Config::save_path(&rom.name, self.save_slot) {
-                if let Err(err) = self.control_deck.load_state(path) {
-                    error!("failed to load state: {err:?}");
+                match self.control_deck.load_state(path) {
+                    Ok(Some(mismatch)) => self.on_cheat_mismatch(mismatch),
+                    Ok(None) => (),
+                    Err(err) => error!("failed to load state: {err:?}"),
                 }
             }
         }
+        // Only the first ROM loaded is resumed by `--resume`
+        let resume = std::mem::take(&mut self.resume_session);
+        let has_session = Config::session_path(&rom.name).is_some_and(fs::exists);
+        let prompt_session = if !has_session {
+            if resume {
+                self.add_message(MessageType::Warn, "No session saved to resume");
+            }
+            false
+        } else if resume || self.session == SessionMode::Always {
+            self.resume_session();
+            false
+        } else {
+            self.session == SessionMode::Ask
+        };
+        self.apply_pending_lan_state();
+        self.update_region(self.control_deck.region());
+        self.load_autosplitter();
+        if rom.header_repaired {
+            self.add_message(
+                MessageType::Info,
+                format!(
+                    "Repaired the ROM header of {}. It can be adjusted from the `ROM Header` window.",
+                    rom.name
+                ),
+            );
+        }
+        if let Some(sound_info) = &rom.sound_info {
+            debug!("detected sound info: {sound_info:?}");
+        }
+        self.plugin_event(PluginEvent::RomLoaded(rom.name.clone()));
+        let name = rom.name.clone();
         self.tx.nes_event(RendererEvent::RomLoaded(rom));
+        if prompt_session {
+            self.tx.nes_event(RendererEvent::ResumeSessionPrompt(name));
+        }
         if let Err(err) = self.audio.start() {
             self.on_error(err);
         }
         self.pause(false);
-        self.frame_time_diag.reset();
+        self.stats.reset();
         self.last_auto_save = Instant::now();
-        // To avoid having a large dip in frame stats after loading
-        self.last_frame_time = Instant::now();
     }
 
     fn load_rom_path(&mut self, path: impl AsRef<std::path::Path>) {
         let path = path.as_ref();
-        self.unload_rom();
-        match self.control_deck.load_rom_path(path) {
-            Ok(rom) => self.on_load_rom(rom),
+        // Read in the background so slow storage doesn't stall emulation, loading the ROM once
+        // it's fully read
+        if let Some(reader) = self.rom_reader.take() {
+            reader.cancel();
+        }
+        let patch = self.patch.take();
+        self.pending_rom_path = Some(path.to_path_buf());
+        match RomReader::spawn(path.to_path_buf(), patch, self.auto_patch, self.tx.clone()) {
+            Ok(reader) => self.rom_reader = Some(reader),
             Err(err) => self.on_error(err),
         }
     }
 
+    fn start_playlist(&mut self, playlist: &Playlist) {
+        if playlist.entries.is_empty() {
+            self.add_message(MessageType::Warn, "Playlist is empty.");
+            return;
+        }
+        self.stop_playlist();
+        self.playlist = Some(PlaylistRun::new(playlist.clone()));
+        self.load_playlist_entry();
+    }
+
+    /// Load the current playlist entry, recording a failure and moving on for each ROM that fails
+    /// to load.
+    fn load_playlist_entry(&mut self) {
+        while let Some(path) = self
+            .playlist
+            .as_ref()
+            .and_then(PlaylistRun::current)
+            .map(|entry| PathBuf::from(&entry.path))
+        {
+            self.unload_rom();
+            let res = self.read_rom(&path).and_then(|(name, rom)| {
+                Ok(self.control_deck.load_rom(name, &mut rom.as_slice())?)
+            });
+            match res {
+                Ok(rom) => {
+                    self.on_load_rom(rom);
+                    self.send_playlist();
+                    return;
+                }
+                Err(err) => {
+                    error!("failed to load playlist rom {path:?}: {err:?}");
+                    if let Some(run) = &mut self.playlist {
+                        run.advance(Outcome::Failed(err.to_string()));
+                    }
+                }
+            }
+        }
+        self.finish_playlist();
+    }
+
+    /// Record `outcome` for the current playlist entry and load the next one.
+    fn advance_playlist(&mut self, outcome: Outcome) {
+        let Some(run) = &mut self.playlist else {
+            return;
+        };
+        let message = run
+            .current()
+            .map(|entry| format!("{}: {outcome}", entry.name()));
+        run.advance(outcome);
+        if let Some(message) = message {
+            self.add_message(MessageType::Info, message);
+        }
+        self.load_playlist_entry();
+    }
+
+    /// Check whether the current playlist entry ended after clocking a frame.
+    fn update_playlist(&mut self) {
+        let outcome = self
+            .playlist
+            .as_mut()
+            .and_then(|run| run.clock_frame(&self.control_deck, self.target_frame_duration));
+        if let Some(outcome) = outcome {
+            self.advance_playlist(outcome);
+        }
+    }
+
+    fn stop_playlist(&mut self) {
+        if let Some(mut run) = self.playlist.take() {
+            run.stop();
+            self.add_message(MessageType::Info, "Playlist stopped.");
+            self.tx.nes_event(RendererEvent::PlaylistRun(run));
+        }
+    }
+
+    fn finish_playlist(&mut self) {
+        if let Some(run) = self.playlist.take() {
+            self.unload_rom();
+            let completed = run
+                .results()
+                .iter()
+                .filter(|result| result.outcome == Outcome::Completed)
+                .count();
+            self.add_message(
+                MessageType::Info,
+                format!(
+                    "Playlist finished. Completed {completed} of {} games.",
+                    run.results().len()
+                ),
+            );
+            self.tx.nes_event(RendererEvent::PlaylistRun(run));
+        }
+    }
+
+    fn send_playlist(&self) {
+        if let Some(run) = &self.playlist {
+            self.tx.nes_event(RendererEvent::PlaylistRun(run.clone()));
+        }
+    }
+
     fn load_rom(&mut self, name: &str, rom: &mut impl Read) {
         self.unload_rom();
         match self.control_deck.load_rom(name, rom) {
@@ -723,15 +1604,118 @@ impl State {
         }
     }
 
+    /// Read the ROM at `path`, applying a patch file next to it with the same name if
+    /// auto-patching is enabled. Returns the name to load the ROM as.
+    fn read_rom(&self, path: &Path) -> anyhow::Result<(String, Vec<u8>)> {
+        let mut name = fs::filename(path).to_string();
+        let mut rom = stream::read(path, &mut ReadProgress::new(&name), |_| ())?;
+        if let Some(patch_path) = self.auto_patch.then(|| patch::find_sibling(path)).flatten() {
+            name = patch::apply(&name, &mut rom, &patch_path)?;
+        }
+        Ok((name, rom))
+    }
+
+    /// Reload the current ROM, e.g. to apply changed header overrides.
+    fn reload_rom(&mut self) {
+        let Some(name) = self.control_deck.loaded_rom().map(|rom| rom.name.clone()) else {
+            return;
+        };
+        let data = self.control_deck.rom_data().to_vec();
+        self.load_changed_rom(&name, data);
+    }
+
+    /// Load a changed version of the loaded ROM, continuing to watch its file. Save states may not
+    /// match the changed ROM, so they aren't auto-saved or auto-loaded.
+    fn load_changed_rom(&mut self, name: &str, data: Vec<u8>) {
+        let rom_watcher = self.rom_watcher.take();
+        let auto_save = std::mem::replace(&mut self.auto_save, false);
+        let auto_load = std::mem::replace(&mut self.auto_load, false);
+        let session = std::mem::replace(&mut self.session, SessionMode::Off);
+        self.load_rom(name, &mut io::Cursor::new(data));
+        self.auto_save = auto_save;
+        self.auto_load = auto_load;
+        self.session = session;
+        if self.control_deck.is_running() {
+            self.rom_watcher = rom_watcher;
+        }
+    }
+
+    /// Reload the ROM if its file changed, e.g. after rebuilding a homebrew ROM, keeping RAM or
+    /// loading the current save slot as configured.
+    fn live_reload(&mut self) {
+        if !self.live_reload.enabled {
+            return;
+        }
+        let Some(watcher) = &mut self.rom_watcher else {
+            return;
+        };
+        if !watcher.poll() {
+            return;
+        }
+        let path = watcher.path().to_path_buf();
+        let (name, data) = match self.read_rom(&path) {
+            Ok(rom) => rom,
+            Err(err) => return self.on_error(err),
+        };
+
+        let ram = (self.live_reload.preserve == LiveReloadPreserve::Ram).then(|| {
+            let prg_ram = (0..self.control_deck.memory_len(MemorySpace::PrgRam))
+                .filter_map(|addr| self.control_deck.peek_memory(MemorySpace::PrgRam, addr))
+                .collect::<Vec<_>>();
+            (self.control_deck.wram().to_vec(), prg_ram)
+        });
+        self.load_changed_rom(&name, data);
+        if !self.control_deck.is_running() {
+            return;
+        }
+        match self.live_reload.preserve {
+            LiveReloadPreserve::Nothing => (),
+            LiveReloadPreserve::Ram => {
+                if let Some((wram, prg_ram)) = ram {
+                    for (addr, val) in wram.into_iter().enumerate() {
+                        self.control_deck.poke_memory(MemorySpace::Cpu, addr, val);
+                    }
+                    // PRG-RAM may have changed size if the header changed
+                    for (addr, val) in prg_ram.into_iter().enumerate() {
+                        self.control_deck
+                            .poke_memory(MemorySpace::PrgRam, addr, val);
+                    }
+                }
+            }
+            LiveReloadPreserve::SaveState => self.load_state(self.save_slot),
+        }
+        self.add_message(MessageType::Info, format!("Reloaded {name}"));
+    }
+
     fn on_load_replay(&mut self, start: Cpu, name: impl AsRef<str>) {
         self.add_message(
             MessageType::Info,
             format!("Loaded Replay Recording {:?}", name.as_ref()),
         );
-        self.control_deck.load_cpu(start);
+        if let Some(mismatch) = self.control_deck.load_recorded_cpu(start) {
+            self.on_cheat_mismatch(mismatch);
+        }
         self.pause(false);
     }
 
+    /// Warn that the Game Genie codes recorded in a save state or replay differ from those active,
+    /// syncing the configured codes if the recorded codes were applied.
+    fn on_cheat_mismatch(&mut self, mismatch: CheatMismatch) {
+        if self.control_deck.apply_recorded_cheats() {
+            self.add_message(
+                MessageType::Warn,
+                format!("Applied recorded Game Genie codes. {mismatch}"),
+            );
+            let genie_codes = self.control_deck.genie_codes().cloned().collect();
+            self.tx.nes_event(RendererEvent::GenieCodes(genie_codes));
+        } else {
+            self.add_message(
+                MessageType::Warn,
+                format!("Game Genie codes differ from recording. {mismatch}"),
+            );
+        }
+    }
+
     fn load_replay_path(&mut self, path: impl AsRef<Path>) {
         let path = path.as_ref();
         match self.replay.load_path(path) {
@@ -748,12 +1732,97 @@ impl State {
     }
 
     fn update_region(&mut self, region: NesRegion) {
+        self.tx.nes_event(RendererEvent::RegionChanged(region));
         self.target_frame_duration = FrameRate::from(region).duration();
         self.frame_latency = (self.audio.latency.as_secs_f32()
             / self.target_frame_duration.as_secs_f32())
         .ceil() as usize;
     }
 
+    /// Update APU sample timing and audio output for the current speed. When fast-forwarding
+    /// with [`FastForwardAudio::PitchLocked`], the APU keeps generating samples at the original
+    /// pitch and the mixer time-stretches them to match the emulation speed instead.
+    fn update_speed(&mut self) {
+        let speed = self.current_speed();
+        let fast_forwarding = speed > 1.0;
+        if fast_forwarding && self.fast_forward_audio == FastForwardAudio::PitchLocked {
+            self.control_deck.set_frame_speed(1.0);
+            self.audio.set_time_stretch(speed);
+        } else {
+            self.control_deck.set_frame_speed(speed);
+            self.audio.set_time_stretch(1.0);
+        }
+        self.audio.set_volume(
+            if self.in_background && self.background == BackgroundMode::Mute {
+                0.0
+            } else if fast_forwarding {
+                self.fast_forward_audio.volume()
+            } else {
+                1.0
+            },
+        );
+    }
+
+    /// Emulation speed, including fast-forwarding.
+    const fn current_speed(&self) -> f32 {
+        if self.fast_forwarding {
+            self.fast_forward.speed
+        } else {
+            self.speed
+        }
+    }
+
+    /// Whether to emulate as fast as possible, without pacing frames by queued audio or vsync.
+    const fn uncapped(&self) -> bool {
+        self.fast_forwarding && self.fast_forward.uncapped
+    }
+
+    /// Pause or mute emulation while the window is in the background, depending on the
+    /// configured [`BackgroundMode`].
+    fn update_background(&mut self) {
+        self.unfocused_paused = self.in_background && self.background == BackgroundMode::Pause;
+        if self.control_deck.is_running() {
+            self.audio.pause(self.unfocused_paused);
+        }
+        self.update_speed();
+    }
+
+    /// Whether to emulate the next frame without rendering it, either to only render every Nth
+    /// frame while fast-forwarding, or to catch up after queued audio ran low because rendering
+    /// couldn't keep up. Without audio, frames are paced by vsync or elapsed time instead and
+    /// never fall behind.
+    fn skip_frame(&mut self) -> bool {
+        if self.fast_forwarding && self.fast_forward.frame_skip > 0 {
+            // Only render every Nth frame while fast-forwarding
+            self.fast_forward_frames =
+                (self.fast_forward_frames + 1) % (self.fast_forward.frame_skip + 1);
+            return self.fast_forward_frames != 0;
+        }
+        let behind = self.audio.latency.saturating_sub(self.audio.queued_time());
+        // Allow a frame of slack so normal jitter doesn't cause skipping
+        let skip = self.audio.enabled()
+            && self.frames_skipped < self.max_frame_skip
+            && behind > 2 * self.target_frame_duration;
+        if skip {
+            self.frames_skipped += 1;
+            self.stats.frame_skipped();
+        } else {
+            self.frames_skipped = 0;
+        }
+        skip
+    }
+
+    /// Nudge the emulation sample rate based on how much audio is queued, so the audio device
+    /// neither underruns nor accumulates latency as its clock drifts from the emulation clock.
+    fn update_rate_control(&mut self) {
+        let ratio = if self.dynamic_rate_control && self.audio.enabled() {
+            self.audio.rate_control_ratio(Self::MAX_RATE_DELTA)
+        } else {
+            1.0
+        };
+        self.control_deck.set_sample_rate_adjustment(ratio);
+    }
+
     fn audio_record(&mut self, recording: bool) {
         if self.control_deck.is_running() {
             if !recording && self.audio.is_recording() {
@@ -761,17 +1830,18 @@ impl State {
                     Ok(Some(filename)) => {
                         self.add_message(
                             MessageType::Info,
-                            format!("Saved Replay Recording {filename:?}"),
+                            format!("Saved Audio Recording {filename:?}"),
                         );
                     }
                     Err(err) => self.on_error(err),
                     _ => (),
                 }
             } else if recording {
-                if let Err(err) = self.audio.start_recording() {
+                if let Err(err) = self.audio.start_recording(self.record_stems) {
                     self.on_error(err);
                 }
             }
+            self.update_channel_capture();
         }
     }
 
@@ -794,33 +1864,272 @@ impl State {
         }
     }
 
-    fn save_screenshot(&mut self) -> anyhow::Result<PathBuf> {
+    fn map_dump(&mut self, dumping: bool) {
+        if dumping {
+            self.map_stitcher = Some(MapStitcher::default());
+            self.add_message(MessageType::Info, "Dumping Map...");
+        } else if let Some(stitcher) = self.map_stitcher.take() {
+            match Self::save_map(&stitcher) {
+                Ok(filename) => {
+                    self.add_message(
+                        MessageType::Info,
+                        format!("Map Saved: {}", filename.display()),
+                    );
+                }
+                Err(err) => self.on_error(err),
+            }
+        }
+    }
+
+    fn save_map(stitcher: &MapStitcher) -> anyhow::Result<PathBuf> {
+        if stitcher.is_empty() {
+            bail!("no frames were captured for the map");
+        }
         match Config::default_picture_dir() {
             Some(picture_dir) => {
                 let filename = picture_dir
-                    .join(
-                        Local::now()
-                            .format("screenshot_%Y-%m-%d_at_%H_%M_%S")
-                            .to_string(),
-                    )
+                    .join(Local::now().format("map_%Y-%m-%d_at_%H_%M_%S").to_string())
                     .with_extension("png");
-                let image = image::ImageBuffer::<image::Rgba<u8>, &[u8]>::from_raw(
+                let (width, height) = stitcher.dimensions();
+                let image = image::RgbaImage::from_raw(width, height, stitcher.to_rgba())
+                    .ok_or_else(|| anyhow!("failed to create image buffer"))?;
+
+                save_image(image, &filename)?;
+                Ok(filename)
+            }
+            None => bail!("failed to find default picture directory"),
+        }
+    }
+
+    fn code_data_log(&mut self, logging: bool) {
+        if let Err(err) = self.control_deck.set_cdl_enabled(logging) {
+            self.on_error(err);
+        } else if logging {
+            self.add_message(MessageType::Info, "Code/Data Logging...");
+        } else if let Some(path) = self.control_deck.cdl_path() {
+            self.add_message(
+                MessageType::Info,
+                format!("Code/Data Log Saved: {}", path.display()),
+            );
+        }
+    }
+
+    fn sprite_rip(&mut self, ripping: bool) {
+        if ripping {
+            self.sprite_ripper = Some(SpriteRipper::new(true));
+            self.add_message(MessageType::Info, "Ripping Sprites...");
+        } else if let Some(ripper) = self.sprite_ripper.take() {
+            match Self::save_sprites(&ripper) {
+                Ok(filename) => {
+                    self.add_message(
+                        MessageType::Info,
+                        format!("Sprites Saved: {}", filename.display()),
+                    );
+                }
+                Err(err) => self.on_error(err),
+            }
+        }
+    }
+
+    fn save_sprites(ripper: &SpriteRipper) -> anyhow::Result<PathBuf> {
+        const SHEET_COLUMNS: u32 = 16;
+
+        if ripper.sprites().is_empty() {
+            bail!("no sprites were captured");
+        }
+        match Config::default_picture_dir() {
+            Some(picture_dir) => {
+                let timestamp = Local::now().format("%Y-%m-%d_at_%H_%M_%S");
+                let save_sheet = |sheet: SpriteSheet, filename: PathBuf| -> anyhow::Result<_> {
+                    let image = image::RgbaImage::from_raw(sheet.width, sheet.height, sheet.pixels)
+                        .ok_or_else(|| anyhow!("failed to create image buffer"))?;
+                    save_image(image, &filename)?;
+                    Ok(filename)
+                };
+
+                if !ripper.groups().is_empty() {
+                    save_sheet(
+                        ripper.group_sheet(),
+                        picture_dir
+                            .join(format!("sprite_groups_{timestamp}"))
+                            .with_extension("png"),
+                    )?;
+                }
+                save_sheet(
+                    ripper.sprite_sheet(SHEET_COLUMNS),
+                    picture_dir
+                        .join(format!("sprites_{timestamp}"))
+                        .with_extension("png"),
+                )
+            }
+            None => bail!("failed to find default picture directory"),
+        }
+    }
+
+    fn save_milestone_screenshots(&mut self) {
+        let labels = self.control_deck.take_screenshot_requests();
+        if labels.is_empty() || !self.screenshot.capture_milestones {
+            return;
+        }
+        let cfg = ScreenshotConfig {
+            copy_to_clipboard: false,
+            ..self.screenshot.clone()
+        };
+        for label in labels {
+            match self.save_screenshot(&cfg, Some(&label)) {
+                Ok(filename) => self.add_message(
+                    MessageType::Info,
+                    format!("Milestone Screenshot Saved: {}", filename.display()),
+                ),
+                Err(err) => self.on_error(err),
+            }
+        }
+    }
+
+    /// Save a screenshot of the current frame. Labeled screenshots are saved into a subdirectory
+    /// named after the loaded ROM.
+    /// Save a thumbnail of the current frame for the ROM library.
+    fn save_thumbnail(&self, crc32: u32) -> anyhow::Result<()> {
+        let Some(path) = library::thumbnail_path(crc32) else {
+            bail!("failed to find thumbnail directory");
+        };
+        let (width, height) = self.control_deck.frame_size();
+        let image =
+            image::RgbaImage::from_raw(width, height, self.control_deck.frame_buffer().to_vec())
+                .ok_or_else(|| anyhow!("failed to create image buffer"))?;
+        save_image(image::imageops::thumbnail(&image, 128, 120), &path)
+    }
+
+    fn save_screenshot(
+        &mut self,
+        cfg: &ScreenshotConfig,
+        label: Option<&str>,
+    ) -> anyhow::Result<PathBuf> {
+        let Some(mut picture_dir) = cfg.dir.clone().or_else(Config::default_picture_dir) else {
+            bail!("failed to find default picture directory");
+        };
+        let sanitize = |name: &str| {
+            name.chars()
+                .map(|c| {
+                    if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '(' | ')') {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect::<String>()
+        };
+        let prefix = match label {
+            Some(label) => {
+                if let Some(rom) = self.control_deck.loaded_rom() {
+                    picture_dir.push(sanitize(&rom.name));
+                }
+                sanitize(label)
+            }
+            None => String::from("screenshot"),
+        };
+        let filename = picture_dir
+            .join(
+                Local::now()
+                    .format(&format!("{prefix}_%Y-%m-%d_at_%H_%M_%S"))
+                    .to_string(),
+            )
+            .with_extension("png");
+
+        let rgba = match cfg.format {
+            ScreenshotFormat::Raw => {
+                let raw = self.control_deck.frame_buffer_raw();
+                // Pixels are a 6-bit palette index with 3 emphasis bits above it. Shift them into
+                // the top bits so they span the 16-bit range instead of looking black, while
+                // keeping the original value recoverable with `>> 7`.
+                let image = image::ImageBuffer::<image::Luma<u16>, _>::from_raw(
                     Ppu::WIDTH,
                     Ppu::HEIGHT,
-                    self.control_deck.frame_buffer(),
+                    raw.iter().map(|&pixel| pixel << 7).collect(),
                 )
                 .ok_or_else(|| anyhow!("failed to create image buffer"))?;
-
-                // TODO: provide wasm download
-                Ok(image.save(&filename).map(|_| filename)?)
+                save_image(image, &filename)?;
+                // Palette indices aren't viewable as-is, so copy the decoded colors instead
+                let rgba = raw
+                    .iter()
+                    .flat_map(|&pixel| {
+                        let index = (pixel as usize) * 3;
+                        [
+                            Ppu::NTSC_PALETTE[index],
+                            Ppu::NTSC_PALETTE[index + 1],
+                            Ppu::NTSC_PALETTE[index + 2],
+                            0xFF,
+                        ]
+                    })
+                    .collect::<Vec<_>>();
+                image::RgbaImage::from_raw(Ppu::WIDTH, Ppu::HEIGHT, rgba)
             }
-            None => bail!("failed to find default picture directory"),
+            ScreenshotFormat::Filtered => {
+                let (width, height) = self.control_deck.frame_size();
+                let image = image::RgbaImage::from_raw(
+                    width,
+                    height,
+                    self.control_deck.frame_buffer().to_vec(),
+                );
+                if let Some(image) = &image {
+                    save_image(image.clone(), &filename)?;
+                }
+                image
+            }
+            ScreenshotFormat::Scaled => {
+                let scale = cfg.scale.max(1);
+                let (width, height) = self.control_deck.frame_size();
+                let image = image::RgbaImage::from_raw(
+                    width,
+                    height,
+                    self.control_deck.frame_buffer().to_vec(),
+                )
+                .map(|image| {
+                    image::imageops::resize(
+                        &image,
+                        Ppu::WIDTH * scale,
+                        Ppu::HEIGHT * scale,
+                        image::imageops::FilterType::Nearest,
+                    )
+                });
+                if let Some(image) = &image {
+                    save_image(image.clone(), &filename)?;
+                }
+                image
+            }
+        }
+        .ok_or_else(|| anyhow!("failed to create image buffer"))?;
+
+        if cfg.copy_to_clipboard {
+            Self::copy_image_to_clipboard(&rgba)?;
         }
+
+        // TODO: provide wasm download
+        Ok(filename)
+    }
+
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    fn copy_image_to_clipboard(image: &image::RgbaImage) -> anyhow::Result<()> {
+        let mut clipboard = arboard::Clipboard::new().context("failed to open clipboard")?;
+        clipboard
+            .set_image(arboard::ImageData {
+                width: image.width() as usize,
+                height: image.height() as usize,
+                bytes: image.as_raw().into(),
+            })
+            .context("failed to copy screenshot to clipboard")
+    }
+
+    #[cfg(any(target_arch = "wasm32", target_os = "android"))]
+    fn copy_image_to_clipboard(_image: &image::RgbaImage) -> anyhow::Result<()> {
+        bail!("copying screenshots to the clipboard is not supported on this platform")
     }
 
     #[cfg(target_arch = "wasm32")]
     fn should_park(&self) -> bool {
-        if self.audio.enabled() {
+        if self.uncapped() {
+            false
+        } else if self.audio.enabled() {
             self.audio.queued_time() >= self.audio.latency
         } else {
             self.clock_time_accumulator < self.target_frame_duration.as_secs_f32()
@@ -829,13 +2138,24 @@ impl State {
 
     #[cfg(not(target_arch = "wasm32"))]
     fn should_park(&self) -> bool {
-        self.audio.enabled() && self.audio.queued_time() >= self.audio.latency
+        if self.uncapped() {
+            false
+        } else if self.audio.enabled() {
+            self.audio.queued_time() >= self.audio.latency
+        } else {
+            // Fall back to time-based clocking when frames aren't paced by vsync
+            !self.vsync_paced()
+                && self.clock_time_accumulator < self.target_frame_duration.as_secs_f32()
+        }
     }
 
     fn clock_frame(&mut self) {
         #[cfg(feature = "profiling")]
         puffin::profile_function!();
 
+        // Reloaded even while paused so changes show up right away
+        self.live_reload();
+
         let last_clock_duration = self.last_clock_time.elapsed();
         self.last_clock_time = Instant::now();
         let frame_duration_secs = last_clock_duration.as_secs_f32();
@@ -845,8 +2165,17 @@ impl State {
         }
 
         let park_epsilon = Duration::from_millis(1);
+        let frame_advance = std::mem::take(&mut self.frame_advance)
+            && !self.unfocused_paused
+            && self.control_deck.is_running();
+        if frame_advance {
+            // Avoid counting the time spent paused as a slow frame
+            self.stats.resume();
+        }
         // Park if we're paused, occluded, or not running
-        if self.paused || self.unfocused_paused || !self.control_deck.is_running() {
+        if !frame_advance
+            && (self.paused || self.unfocused_paused || !self.control_deck.is_running())
+        {
             // But if we're only running + paused and not occluded, send a frame
             if self.paused && !self.unfocused_paused && self.control_deck.is_running() {
                 self.send_frame();
@@ -854,8 +2183,15 @@ impl State {
             thread::park_timeout(self.target_frame_duration - park_epsilon);
             return;
         }
-        if !self.rewinding && self.should_park() {
-            thread::park_timeout(self.audio.queued_time().saturating_sub(self.audio.latency));
+        if !frame_advance && !self.rewinding && self.should_park() {
+            let timeout = if self.audio.enabled() {
+                self.audio.queued_time().saturating_sub(self.audio.latency)
+            } else {
+                let remaining =
+                    self.target_frame_duration.as_secs_f32() - self.clock_time_accumulator;
+                Duration::from_secs_f32(remaining.max(0.0))
+            };
+            thread::park_timeout(timeout);
             return;
         }
 
@@ -863,16 +2199,18 @@ impl State {
         // not rewinding, otherwise fall back to time-based clocking
         // let mut clocked_frames = 0; // Prevent infinite loop when queued audio falls behind
         let mut run_ahead = self.run_ahead;
-        if self.speed > 1.0 {
+        if self.current_speed() > 1.0 || self.uncapped() {
             run_ahead = 0;
         }
 
         if self.rewinding {
             match self.rewind.pop() {
                 Some(cpu) => {
+                    let start = Instant::now();
                     self.control_deck.load_cpu(cpu);
+                    let emulation_time = start.elapsed();
                     self.send_frame();
-                    self.update_frame_stats();
+                    self.update_frame_stats(emulation_time, Duration::ZERO);
                     thread::park_timeout(self.target_frame_duration - park_epsilon);
                 }
                 None => self.rewinding = false,
@@ -881,52 +2219,102 @@ impl State {
             if let Some(event) = self.replay.next(self.control_deck.frame_number()) {
                 self.on_emulation_event(&event);
             }
-            let res = self.control_deck.clock_frame_ahead(
-                run_ahead,
-                |_cycles, frame_buffer, audio_samples| {
-                    self.audio.process(audio_samples);
-                    let send_frame = |frame: &mut Frame| {
-                        frame.clear();
-                        frame.extend_from_slice(frame_buffer);
-                    };
-                    self.clock_time_accumulator -= frame_duration_secs;
-
-                    // Indicate we want to redraw to ensure there's a frame slot made available if
-                    // the pool is already full
-                    self.tx.nes_event(RendererEvent::RequestRedraw {
-                        viewport_id: ViewportId::ROOT,
-                        when: Instant::now(),
-                    });
-                    // IMPORTANT: Wasm can't block
-                    if self.audio.enabled() || cfg!(target_arch = "wasm32") {
-                        // If audio is enabled or wasm, frame rate is controlled by park_timeout
-                        // above
-                        match self.frame_tx.try_send_ref() {
-                            Ok(mut frame) => send_frame(&mut frame),
-                            Err(TrySendError::Full(_)) => debug!("dropped frame"),
-                            Err(_) => shutdown(&self.tx, "failed to get frame"),
+            let vsync = self.vsync_paced();
+            let render = !self.skip_frame();
+            // Audio can't keep up when uncapped, so let the queue drain instead
+            let play_audio = !self.uncapped();
+            let start = Instant::now();
+            let mut emulation_time = Duration::ZERO;
+            let mut audio_time = Duration::ZERO;
+            let res = if let Some(pipeline) = &mut self.video_pipeline {
+                pipeline.update_settings(self.control_deck.video());
+                self.control_deck.clock_frame_ahead_raw(
+                    run_ahead,
+                    |_cycles, frame_buffer, frame_number, audio_samples| {
+                        emulation_time = start.elapsed();
+                        if play_audio {
+                            self.audio.process(audio_samples);
                         }
-                    } else {
-                        // Otherwise we'll block on vsync
-                        match self.frame_tx.send_ref() {
-                            Ok(mut frame) => send_frame(&mut frame),
-                            Err(_) => shutdown(&self.tx, "failed to get frame"),
+                        audio_time = start.elapsed() - emulation_time;
+                        self.clock_time_accumulator -= frame_duration_secs;
+                        if render {
+                            pipeline.send(frame_buffer, frame_number, vsync, &self.stop);
                         }
-                    }
-                },
-            );
+                    },
+                )
+            } else {
+                self.control_deck.clock_frame_ahead(
+                    run_ahead,
+                    |_cycles, frame_buffer, audio_samples| {
+                        emulation_time = start.elapsed();
+                        if play_audio {
+                            self.audio.process(audio_samples);
+                        }
+                        audio_time = start.elapsed() - emulation_time;
+                        self.clock_time_accumulator -= frame_duration_secs;
+                        if !render {
+                            return;
+                        }
+
+                        // Indicate we want to redraw to ensure there's a frame slot made
+                        // available if the pool is already full
+                        self.tx.nes_event(RendererEvent::RequestRedraw {
+                            viewport_id: ViewportId::ROOT,
+                            when: Instant::now(),
+                        });
+                        // If audio is enabled or wasm, frame rate is controlled by park_timeout
+                        // above. Otherwise we'll block on vsync
+                        if let Some(mut frame) =
+                            send_ref(&self.frame_tx, vsync, &self.stop, &self.tx)
+                        {
+                            frame.clear();
+                            frame.extend_from_slice(frame_buffer);
+                        }
+                    },
+                )
+            };
             match res {
                 Ok(()) => {
-                    self.update_frame_stats();
+                    self.update_rate_control();
+                    self.update_frame_stats(emulation_time, audio_time);
+                    self.send_audio_scope();
+                    self.send_joypad_state();
+                    self.send_nsf_state();
+                    self.send_mapper_state();
+                    self.send_memory_page();
+                    self.send_ram_watch();
+                    if self.audio.is_recording() {
+                        self.audio.process_recording(
+                            self.control_deck.record_samples(),
+                            self.control_deck.channel_samples(),
+                        );
+                    }
+                    if let Some(stitcher) = &mut self.map_stitcher {
+                        let _ = stitcher.capture(self.control_deck.ppu());
+                    }
+                    if let Some(ripper) = &mut self.sprite_ripper {
+                        ripper.capture(self.control_deck.ppu());
+                    }
+                    self.plugin_event(PluginEvent::Frame(self.control_deck.frame_number()));
+                    self.update_autosplit();
+                    self.save_milestone_screenshots();
+                    self.update_playlist();
                     if let Err(err) = self.rewind.push(self.control_deck.cpu()) {
                         self.rewind.set_enabled(false);
                         self.on_error(err);
                     }
-                    if self.last_auto_save.elapsed() > self.auto_save_interval {
+                    if self.auto_save
+                        && !self.auto_save_interval.is_zero()
+                        && self.last_auto_save.elapsed() > self.auto_save_interval
+                    {
                         self.last_auto_save = Instant::now();
-                        self.save_state(self.save_slot, true);
+                        self.auto_save();
                     }
                 }
+                Err(err) if self.playlist.is_some() => {
+                    error!("playlist rom failed: {err:?}");
+                    self.advance_playlist(Outcome::Failed(err.to_string()));
+                }
                 Err(err) => {
                     self.pause(true);
                     self.on_error(err);
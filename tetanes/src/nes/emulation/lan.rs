@@ -0,0 +1,670 @@
+//! Transfer save states between `TetaNES` instances on the local network, discovered using
+//! mDNS.
+//!
+//! Each instance has an Ed25519 identity key. States are only accepted from devices the user has
+//! paired with, signed over a per-connection challenge, and are only loaded after the user
+//! confirms.
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::nes::event::{EmulationEvent, UiEvent};
+use crate::nes::{
+    emulation::State,
+    event::{NesEvent, RendererEvent, SendNesEvent},
+    renderer::gui::MessageType,
+};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use tetanes_core::control_deck::LoadedRom;
+use winit::event_loop::EventLoopProxy;
+
+/// Another `TetaNES` instance discovered on the local network.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub struct Peer {
+    /// Unique mDNS service name of the instance.
+    pub id: String,
+    /// Display name of the device.
+    pub name: String,
+    pub addr: SocketAddr,
+}
+
+/// A device allowed to send save states to this instance.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub struct TrustedPeer {
+    /// Display name of the device when it was paired.
+    pub name: String,
+    /// Ed25519 public key of the device.
+    pub key: [u8; 32],
+}
+
+impl TrustedPeer {
+    /// Short form of the public key for users to compare between devices when pairing.
+    #[must_use]
+    pub fn fingerprint(&self) -> String {
+        fingerprint(&self.key)
+    }
+}
+
+/// Short form of a public key for users to compare between devices when pairing.
+#[must_use]
+pub fn fingerprint(key: &[u8; 32]) -> String {
+    key[..8]
+        .chunks(2)
+        .map(|pair| format!("{:02X}{:02X}", pair[0], pair[1]))
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Save state received from a [`Peer`].
+#[derive(Clone, PartialEq)]
+#[must_use]
+pub struct ReceivedState {
+    /// Display name of the device that sent the state.
+    pub from: String,
+    pub rom_name: String,
+    /// CRC32 of the ROM the state was saved from.
+    pub crc32: u32,
+    pub data: Vec<u8>,
+}
+
+impl std::fmt::Debug for ReceivedState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReceivedState")
+            .field("from", &self.from)
+            .field("rom_name", &self.rom_name)
+            .field("crc32", &format_args!("{:08X}", self.crc32))
+            .field("data", &format_args!("{} bytes", self.data.len()))
+            .finish()
+    }
+}
+
+/// Advertises this instance on the local network, tracks other instances and accepts save
+/// states sent from them.
+#[derive(Debug)]
+#[must_use]
+pub struct LanTransfer {
+    /// Display name of this device sent to peers.
+    #[cfg(not(target_arch = "wasm32"))]
+    name: String,
+    #[cfg(not(target_arch = "wasm32"))]
+    key: std::sync::Arc<ed25519_dalek::SigningKey>,
+    /// Public keys of devices allowed to send save states.
+    #[cfg(not(target_arch = "wasm32"))]
+    trusted: std::sync::Arc<parking_lot::RwLock<Vec<[u8; 32]>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    daemon: mdns_sd::ServiceDaemon,
+    #[cfg(not(target_arch = "wasm32"))]
+    shutdown: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    #[cfg(not(target_arch = "wasm32"))]
+    port: u16,
+}
+
+impl LanTransfer {
+    #[cfg(not(target_arch = "wasm32"))]
+    const SERVICE_TYPE: &'static str = "_tetanes._tcp.local.";
+    #[cfg(not(target_arch = "wasm32"))]
+    const MAGIC: [u8; 8] = *b"TNSTATE\x02";
+    /// Largest save state accepted from a peer.
+    #[cfg(not(target_arch = "wasm32"))]
+    const MAX_STATE_SIZE: usize = 16 * 1024 * 1024;
+    /// Most connections from peers handled at once.
+    #[cfg(not(target_arch = "wasm32"))]
+    const MAX_CONNECTIONS: usize = 4;
+    #[cfg(not(target_arch = "wasm32"))]
+    const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+    #[cfg(not(target_arch = "wasm32"))]
+    const IDENTITY_FILE: &'static str = "lan_identity.key";
+    /// Sent by the receiver once the sender is known to be paired.
+    #[cfg(not(target_arch = "wasm32"))]
+    const ACCEPTED: u8 = 0;
+    /// Sent by the receiver when the sender isn't paired yet.
+    #[cfg(not(target_arch = "wasm32"))]
+    const PAIRING_REQUIRED: u8 = 1;
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn start(_tx: EventLoopProxy<NesEvent>, _trusted: &[TrustedPeer]) -> anyhow::Result<Self> {
+        anyhow::bail!("LAN state transfer is not supported on this platform")
+    }
+
+    /// Start advertising this instance and listening for save states from `trusted` peers.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn start(tx: EventLoopProxy<NesEvent>, trusted: &[TrustedPeer]) -> anyhow::Result<Self> {
+        use anyhow::Context;
+        use mdns_sd::{ServiceDaemon, ServiceInfo};
+        use parking_lot::RwLock;
+        use std::{
+            net::{Ipv4Addr, TcpListener},
+            sync::{atomic::AtomicBool, Arc},
+        };
+
+        let key = Arc::new(Self::load_identity()?);
+        let trusted = Arc::new(RwLock::new(
+            trusted.iter().map(|peer| peer.key).collect::<Vec<_>>(),
+        ));
+
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0))
+            .context("failed to bind LAN transfer listener")?;
+        let port = listener.local_addr()?.port();
+
+        let name = sysinfo::System::host_name().unwrap_or_else(|| "TetaNES".to_string());
+        let instance = format!("{name}-{}", std::process::id());
+        let daemon = ServiceDaemon::new().context("failed to start mDNS daemon")?;
+        let service = ServiceInfo::new(
+            Self::SERVICE_TYPE,
+            &instance,
+            &format!("{instance}.local."),
+            "",
+            port,
+            [("name", name.as_str())].as_slice(),
+        )?
+        .enable_addr_auto();
+        let id = service.get_fullname().to_string();
+        daemon
+            .register(service)
+            .context("failed to register mDNS service")?;
+
+        let shutdown = Arc::new(AtomicBool::new(false));
+        std::thread::Builder::new()
+            .name("lan_listener".into())
+            .spawn({
+                let tx = tx.clone();
+                let trusted = Arc::clone(&trusted);
+                let shutdown = Arc::clone(&shutdown);
+                move || Self::listen(&tx, &listener, &trusted, &shutdown)
+            })?;
+
+        let browser = daemon
+            .browse(Self::SERVICE_TYPE)
+            .context("failed to browse mDNS services")?;
+        std::thread::Builder::new()
+            .name("lan_browser".into())
+            .spawn(move || Self::browse(&tx, &browser, &id))?;
+
+        tracing::info!("LAN state transfer listening on port {port}");
+
+        Ok(Self {
+            name,
+            key,
+            trusted,
+            daemon,
+            shutdown,
+            port,
+        })
+    }
+
+    /// Load the identity key of this device, generating one the first time.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_identity() -> anyhow::Result<ed25519_dalek::SigningKey> {
+        use crate::nes::config::Config;
+        use anyhow::Context;
+        use ed25519_dalek::SigningKey;
+        use tetanes_core::fs;
+
+        let path = Config::default_config_dir()
+            .context("failed to find config directory")?
+            .join(Self::IDENTITY_FILE);
+        if fs::exists(&path) {
+            let seed = fs::load_raw(&path).context("failed to load LAN identity key")?;
+            let seed = <[u8; 32]>::try_from(seed.as_slice())
+                .map_err(|_| anyhow::anyhow!("invalid LAN identity key: {path:?}"))?;
+            return Ok(SigningKey::from_bytes(&seed));
+        }
+
+        let mut seed = [0; 32];
+        getrandom::getrandom(&mut seed).context("failed to generate LAN identity key")?;
+        fs::save_raw(&path, &seed).context("failed to save LAN identity key")?;
+        Ok(SigningKey::from_bytes(&seed))
+    }
+
+    /// Replace the devices allowed to send save states.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_trusted(&self, trusted: &[TrustedPeer]) {
+        *self.trusted.write() = trusted.iter().map(|peer| peer.key).collect();
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub const fn set_trusted(&self, _trusted: &[TrustedPeer]) {}
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn send(
+        &self,
+        _tx: EventLoopProxy<NesEvent>,
+        _peer: Peer,
+        _rom: &LoadedRom,
+        _data: Vec<u8>,
+    ) {
+    }
+
+    /// Send the save state `data` for `rom` to `peer` in the background, reporting the result as a
+    /// message.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn send(&self, tx: EventLoopProxy<NesEvent>, peer: Peer, rom: &LoadedRom, data: Vec<u8>) {
+        let state = ReceivedState {
+            from: self.name.clone(),
+            rom_name: rom.name.clone(),
+            crc32: rom.crc32,
+            data,
+        };
+        let key = std::sync::Arc::clone(&self.key);
+        let res = std::thread::Builder::new()
+            .name("lan_sender".into())
+            .spawn(move || {
+                let message = match Self::write_state(&peer, &key, &state) {
+                    Ok(true) => (
+                        MessageType::Info,
+                        format!("Sent save state to {}.", peer.name),
+                    ),
+                    Ok(false) => (
+                        MessageType::Warn,
+                        format!(
+                            "{} isn't paired with this device yet. Accept the pairing request there if it shows the fingerprint {}, then send the state again.",
+                            peer.name,
+                            fingerprint(key.verifying_key().as_bytes()),
+                        ),
+                    ),
+                    Err(err) => {
+                        tracing::error!("failed to send save state to {peer:?}: {err:?}");
+                        (
+                            MessageType::Error,
+                            format!("Failed to send save state to {}.", peer.name),
+                        )
+                    }
+                };
+                tx.nes_event(UiEvent::Message(message));
+            });
+        if let Err(err) = res {
+            tracing::error!("failed to spawn LAN sender thread: {err:?}");
+        }
+    }
+
+    /// Bytes signed by the sender, binding the state to the receiver's challenge and the sender's
+    /// identity.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn transcript(nonce: &[u8; 32], key: &[u8; 32], state: &ReceivedState) -> Vec<u8> {
+        let mut transcript = Vec::with_capacity(
+            nonce.len()
+                + key.len()
+                + state.from.len()
+                + state.rom_name.len()
+                + state.data.len()
+                + 24,
+        );
+        transcript.extend_from_slice(&Self::MAGIC);
+        transcript.extend_from_slice(nonce);
+        transcript.extend_from_slice(key);
+        transcript.extend_from_slice(&state.crc32.to_le_bytes());
+        for field in [
+            state.from.as_bytes(),
+            state.rom_name.as_bytes(),
+            &state.data,
+        ] {
+            transcript.extend_from_slice(&(field.len() as u64).to_le_bytes());
+            transcript.extend_from_slice(field);
+        }
+        transcript
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write_string(stream: &mut impl std::io::Write, value: &str) -> anyhow::Result<()> {
+        stream.write_all(&u16::try_from(value.len())?.to_le_bytes())?;
+        stream.write_all(value.as_bytes())?;
+        Ok(())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read_string(stream: &mut impl std::io::Read) -> anyhow::Result<String> {
+        let mut len = [0; 2];
+        stream.read_exact(&mut len)?;
+        let mut bytes = vec![0; usize::from(u16::from_le_bytes(len))];
+        stream.read_exact(&mut bytes)?;
+        Ok(String::from_utf8(bytes)?)
+    }
+
+    /// Send `state` to `peer`, returning `false` if `peer` hasn't paired with this device.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write_state(
+        peer: &Peer,
+        key: &ed25519_dalek::SigningKey,
+        state: &ReceivedState,
+    ) -> anyhow::Result<bool> {
+        use ed25519_dalek::Signer;
+        use std::{
+            io::{Read, Write},
+            net::TcpStream,
+        };
+
+        let mut stream = TcpStream::connect_timeout(&peer.addr, Self::TIMEOUT)?;
+        stream.set_read_timeout(Some(Self::TIMEOUT))?;
+        stream.set_write_timeout(Some(Self::TIMEOUT))?;
+
+        let mut magic = [0; 8];
+        stream.read_exact(&mut magic)?;
+        anyhow::ensure!(magic == Self::MAGIC, "invalid LAN transfer header");
+        let mut nonce = [0; 32];
+        stream.read_exact(&mut nonce)?;
+
+        let public_key = key.verifying_key().to_bytes();
+        stream.write_all(&public_key)?;
+        Self::write_string(&mut stream, &state.from)?;
+        stream.flush()?;
+
+        let mut status = [0; 1];
+        stream.read_exact(&mut status)?;
+        match status[0] {
+            Self::ACCEPTED => (),
+            Self::PAIRING_REQUIRED => return Ok(false),
+            status => anyhow::bail!("invalid LAN transfer status: {status}"),
+        }
+
+        let signature = key.sign(&Self::transcript(&nonce, &public_key, state));
+        stream.write_all(&state.crc32.to_le_bytes())?;
+        Self::write_string(&mut stream, &state.rom_name)?;
+        stream.write_all(&u32::try_from(state.data.len())?.to_le_bytes())?;
+        stream.write_all(&state.data)?;
+        stream.write_all(&signature.to_bytes())?;
+        stream.flush()?;
+        Ok(true)
+    }
+
+    /// Receive a save state from a peer, challenging it to prove it holds a `trusted` key before
+    /// accepting any state data. Unknown peers are asked to pair instead.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read_state(
+        tx: &EventLoopProxy<NesEvent>,
+        mut stream: std::net::TcpStream,
+        trusted: &parking_lot::RwLock<Vec<[u8; 32]>>,
+    ) -> anyhow::Result<()> {
+        use ed25519_dalek::{Signature, VerifyingKey};
+        use std::io::{Read, Write};
+
+        stream.set_read_timeout(Some(Self::TIMEOUT))?;
+        stream.set_write_timeout(Some(Self::TIMEOUT))?;
+
+        let mut nonce = [0; 32];
+        getrandom::getrandom(&mut nonce)?;
+        stream.write_all(&Self::MAGIC)?;
+        stream.write_all(&nonce)?;
+        stream.flush()?;
+
+        let mut key = [0; 32];
+        stream.read_exact(&mut key)?;
+        let from = Self::read_string(&mut stream)?;
+        // Reject malformed keys before bothering the user with them
+        let verifying_key = VerifyingKey::from_bytes(&key)?;
+
+        if !trusted.read().contains(&key) {
+            stream.write_all(&[Self::PAIRING_REQUIRED])?;
+            stream.flush()?;
+            tx.nes_event(RendererEvent::LanPairRequest(TrustedPeer {
+                name: from,
+                key,
+            }));
+            return Ok(());
+        }
+        stream.write_all(&[Self::ACCEPTED])?;
+        stream.flush()?;
+
+        let mut crc32 = [0; 4];
+        stream.read_exact(&mut crc32)?;
+        let rom_name = Self::read_string(&mut stream)?;
+        let mut len = [0; 4];
+        stream.read_exact(&mut len)?;
+        let len = u32::from_le_bytes(len) as usize;
+        anyhow::ensure!(
+            len <= Self::MAX_STATE_SIZE,
+            "save state too large: {len} bytes"
+        );
+        let mut data = vec![0; len];
+        stream.read_exact(&mut data)?;
+        let mut signature = [0; Signature::BYTE_SIZE];
+        stream.read_exact(&mut signature)?;
+
+        let state = ReceivedState {
+            from,
+            rom_name,
+            crc32: u32::from_le_bytes(crc32),
+            data,
+        };
+        verifying_key.verify_strict(
+            &Self::transcript(&nonce, &key, &state),
+            &Signature::from_bytes(&signature),
+        )?;
+        tx.nes_event(EmulationEvent::LanStateReceived(state));
+        Ok(())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn listen(
+        tx: &EventLoopProxy<NesEvent>,
+        listener: &std::net::TcpListener,
+        trusted: &std::sync::Arc<parking_lot::RwLock<Vec<[u8; 32]>>>,
+        shutdown: &std::sync::atomic::AtomicBool,
+    ) {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        let connections = Arc::new(AtomicUsize::new(0));
+        for stream in listener.incoming() {
+            if shutdown.load(Ordering::Relaxed) {
+                break;
+            }
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    tracing::warn!("failed to accept LAN connection: {err:?}");
+                    continue;
+                }
+            };
+            // Handle each peer on its own thread so a slow peer can't hold up others
+            if connections.fetch_add(1, Ordering::AcqRel) >= Self::MAX_CONNECTIONS {
+                connections.fetch_sub(1, Ordering::AcqRel);
+                tracing::warn!(
+                    "too many LAN connections, dropping {:?}",
+                    stream.peer_addr()
+                );
+                continue;
+            }
+            let res = std::thread::Builder::new()
+                .name("lan_receiver".into())
+                .spawn({
+                    let tx = tx.clone();
+                    let trusted = Arc::clone(trusted);
+                    let connections = Arc::clone(&connections);
+                    move || {
+                        if let Err(err) = Self::read_state(&tx, stream, &trusted) {
+                            tracing::warn!("failed to receive save state: {err:?}");
+                        }
+                        connections.fetch_sub(1, Ordering::AcqRel);
+                    }
+                });
+            if let Err(err) = res {
+                connections.fetch_sub(1, Ordering::AcqRel);
+                tracing::error!("failed to spawn LAN receiver thread: {err:?}");
+            }
+        }
+        tracing::debug!("LAN transfer listener stopped");
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn browse(
+        tx: &EventLoopProxy<NesEvent>,
+        browser: &mdns_sd::Receiver<mdns_sd::ServiceEvent>,
+        id: &str,
+    ) {
+        use mdns_sd::ServiceEvent;
+        use std::{collections::BTreeMap, net::IpAddr};
+
+        let mut peers = BTreeMap::new();
+        while let Ok(event) = browser.recv() {
+            match event {
+                ServiceEvent::ServiceResolved(info) if info.get_fullname() != id => {
+                    let addrs = info
+                        .get_addresses()
+                        .iter()
+                        .map(|&addr| IpAddr::from(addr))
+                        .collect::<Vec<_>>();
+                    let Some(&addr) = addrs.iter().find(|addr| addr.is_ipv4()).or(addrs.first())
+                    else {
+                        continue;
+                    };
+                    let id = info.get_fullname().to_string();
+                    let name = info
+                        .get_property_val_str("name")
+                        .unwrap_or_else(|| info.get_hostname())
+                        .to_string();
+                    peers.insert(
+                        id.clone(),
+                        Peer {
+                            id,
+                            name,
+                            addr: SocketAddr::new(addr, info.get_port()),
+                        },
+                    );
+                }
+                ServiceEvent::ServiceRemoved(_, id) => {
+                    peers.remove(&id);
+                }
+                ServiceEvent::SearchStopped(_) => break,
+                _ => continue,
+            }
+            tx.nes_event(RendererEvent::LanPeers(peers.values().cloned().collect()));
+        }
+        tracing::debug!("LAN transfer browser stopped");
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Drop for LanTransfer {
+    fn drop(&mut self) {
+        use std::{net::Ipv4Addr, sync::atomic::Ordering};
+
+        self.shutdown.store(true, Ordering::Relaxed);
+        // Wake the listener so it can observe the shutdown
+        let _ = std::net::TcpStream::connect((Ipv4Addr::LOCALHOST, self.port));
+        if let Err(err) = self.daemon.shutdown() {
+            tracing::error!("failed to shutdown mDNS daemon: {err:?}");
+        }
+    }
+}
+
+impl State {
+    pub fn set_lan_transfer(&mut self, enabled: bool) {
+        if !enabled {
+            if self.lan.take().is_some() {
+                self.tx.nes_event(RendererEvent::LanPeers(Vec::new()));
+            }
+            return;
+        }
+        if self.lan.is_some() {
+            return;
+        }
+        match LanTransfer::start(self.tx.clone(), &self.lan_trusted_peers) {
+            Ok(lan) => self.lan = Some(lan),
+            Err(err) => {
+                tracing::error!("failed to start LAN state transfer: {err:?}");
+                self.add_message(MessageType::Error, "Failed to start LAN state transfer.");
+            }
+        }
+    }
+
+    pub fn send_state(&mut self, peer: Peer) {
+        let Some(lan) = &self.lan else {
+            return self.add_message(
+                MessageType::Warn,
+                "LAN state transfer disabled. You can enable it in the Preferences menu.",
+            );
+        };
+        let Some(rom) = self.control_deck.loaded_rom() else {
+            return;
+        };
+        match self.control_deck.save_state_bytes() {
+            Ok(data) => lan.send(self.tx.clone(), peer, rom, data),
+            Err(err) => self.on_error(err),
+        }
+    }
+
+    pub fn set_lan_trusted_peers(&mut self, trusted: Vec<TrustedPeer>) {
+        if let Some(lan) = &self.lan {
+            lan.set_trusted(&trusted);
+        }
+        self.lan_trusted_peers = trusted;
+    }
+
+    /// Hold on to a received save state and ask whether to load it once its ROM is loaded.
+    pub fn on_lan_state_received(&mut self, state: ReceivedState) {
+        let matches_rom = self
+            .control_deck
+            .loaded_rom()
+            .is_some_and(|rom| rom.crc32 == state.crc32);
+        if !matches_rom {
+            self.add_message(
+                MessageType::Info,
+                format!(
+                    "Received save state for {} from {}. Load the ROM to continue playing.",
+                    state.rom_name, state.from
+                ),
+            );
+        }
+        self.pending_lan_state = Some(state);
+        if matches_rom {
+            self.prompt_lan_state();
+        }
+    }
+
+    /// Ask whether to load a previously received save state if it matches the loaded ROM.
+    pub fn apply_pending_lan_state(&mut self) {
+        let Some(crc32) = self.control_deck.loaded_rom().map(|rom| rom.crc32) else {
+            return;
+        };
+        if self
+            .pending_lan_state
+            .as_ref()
+            .is_some_and(|state| state.crc32 == crc32)
+        {
+            self.prompt_lan_state();
+        }
+    }
+
+    fn prompt_lan_state(&mut self) {
+        if let Some(state) = &self.pending_lan_state {
+            self.tx.nes_event(RendererEvent::LanStatePrompt((
+                state.from.clone(),
+                state.rom_name.clone(),
+            )));
+        }
+    }
+
+    /// Load the pending save state the user accepted, if it still matches the loaded ROM.
+    pub fn load_lan_state(&mut self) {
+        let Some(crc32) = self.control_deck.loaded_rom().map(|rom| rom.crc32) else {
+            return;
+        };
+        let Some(state) = self.pending_lan_state.take() else {
+            return;
+        };
+        if state.crc32 != crc32 {
+            self.pending_lan_state = Some(state);
+            return;
+        }
+        match self.control_deck.load_state_bytes(&state.data) {
+            Ok(mismatch) => {
+                self.rewind.clear();
+                self.add_message(
+                    MessageType::Info,
+                    format!("Loaded save state from {}.", state.from),
+                );
+                if let Some(mismatch) = mismatch {
+                    self.on_cheat_mismatch(mismatch);
+                }
+            }
+            Err(err) => self.on_error(err),
+        }
+    }
+
+    pub fn discard_lan_state(&mut self) {
+        self.pending_lan_state = None;
+    }
+}
@@ -0,0 +1,264 @@
+//! Speedrun timing using the `LiveSplit` Server component, driven by per-game autosplitters loaded
+//! from TOML files in the `autosplitters` configuration directory.
+//!
+//! An example autosplitter for a game that sets `$0770` to `$01` when a game starts:
+//!
+//! ```toml
+//! name = "Any%"
+//! rom_name = "Example Game"
+//! start = [{ addr = 0x0770, comparison = "Equal", value = 0x01 }]
+//! reset = [{ addr = 0x0770, comparison = "Equal", value = 0x00 }]
+//!
+//! [[splits]]
+//! name = "World 1"
+//! conditions = [{ addr = 0x075F, comparison = "Equal", value = 0x01 }]
+//! ```
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::nes::event::{SendNesEvent, UiEvent};
+use crate::nes::{config::Config, emulation::State, event::NesEvent, renderer::gui::MessageType};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tetanes_core::{
+    autosplit::{AutosplitRun, Autosplitter, SplitEvent},
+    control_deck::LoadedRom,
+};
+use winit::event_loop::EventLoopProxy;
+
+/// Default address of the `LiveSplit` Server component.
+pub const DEFAULT_ADDR: &str = "localhost:16834";
+
+/// `LiveSplit` Server configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+#[serde(default)] // Ensures new fields don't break existing configurations
+pub struct LiveSplitConfig {
+    pub enabled: bool,
+    /// Host and port of the `LiveSplit` Server component.
+    pub addr: String,
+}
+
+impl Default for LiveSplitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            addr: DEFAULT_ADDR.to_string(),
+        }
+    }
+}
+
+/// Connection to a `LiveSplit` Server. Commands are sent from a background thread so that an
+/// unreachable server doesn't stall emulation.
+#[derive(Debug)]
+#[must_use]
+pub struct LiveSplit {
+    addr: String,
+    #[cfg(not(target_arch = "wasm32"))]
+    tx: crossbeam::channel::Sender<SplitEvent>,
+}
+
+impl LiveSplit {
+    #[cfg(not(target_arch = "wasm32"))]
+    const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn start(_addr: String, _tx: EventLoopProxy<NesEvent>) -> anyhow::Result<Self> {
+        anyhow::bail!("LiveSplit is not supported on this platform")
+    }
+
+    /// Start a thread that sends timer commands to the server at `addr`, connecting as needed.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn start(addr: String, tx: EventLoopProxy<NesEvent>) -> anyhow::Result<Self> {
+        let (event_tx, event_rx) = crossbeam::channel::unbounded();
+        std::thread::Builder::new()
+            .name("livesplit".into())
+            .spawn({
+                let addr = addr.clone();
+                move || Self::run(&addr, &tx, &event_rx)
+            })?;
+        Ok(Self { addr, tx: event_tx })
+    }
+
+    /// Address of the server.
+    #[must_use]
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn send(&self, _event: SplitEvent) {}
+
+    /// Queue a timer command to send to the server.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn send(&self, event: SplitEvent) {
+        let _ = self.tx.send(event);
+    }
+
+    /// Send commands until the [`LiveSplit`] is dropped, reporting the first failure after each
+    /// successful connection.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn run(
+        addr: &str,
+        tx: &EventLoopProxy<NesEvent>,
+        rx: &crossbeam::channel::Receiver<SplitEvent>,
+    ) {
+        use std::net::TcpStream;
+
+        let mut stream = None::<TcpStream>;
+        let mut reported = false;
+        while let Ok(event) = rx.recv() {
+            let command = match event {
+                SplitEvent::Start => "starttimer",
+                SplitEvent::Split(_) => "split",
+                SplitEvent::Reset => "reset",
+            };
+            let connected = stream.is_some();
+            let mut res = Self::send_command(&mut stream, addr, command);
+            if res.is_err() && connected {
+                // Retry with a new connection in case the server was restarted
+                res = Self::send_command(&mut stream, addr, command);
+            }
+            match res {
+                Ok(()) => reported = false,
+                Err(err) => {
+                    tracing::error!("failed to send `{command}` to LiveSplit at {addr}: {err:?}");
+                    if !reported {
+                        reported = true;
+                        tx.nes_event(UiEvent::Message((
+                            MessageType::Error,
+                            format!("Failed to connect to LiveSplit Server at {addr}."),
+                        )));
+                    }
+                }
+            }
+        }
+        tracing::debug!("LiveSplit sender stopped");
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn connect(addr: &str) -> anyhow::Result<std::net::TcpStream> {
+        use anyhow::Context;
+        use std::net::{TcpStream, ToSocketAddrs};
+
+        let addr = addr
+            .to_socket_addrs()?
+            .next()
+            .with_context(|| format!("failed to resolve {addr}"))?;
+        let stream = TcpStream::connect_timeout(&addr, Self::TIMEOUT)?;
+        stream.set_write_timeout(Some(Self::TIMEOUT))?;
+        stream.set_nodelay(true)?;
+        Ok(stream)
+    }
+
+    /// Write `command` to `stream`, connecting first if needed. The connection is closed on
+    /// failure.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn send_command(
+        stream: &mut Option<std::net::TcpStream>,
+        addr: &str,
+        command: &str,
+    ) -> anyhow::Result<()> {
+        use std::io::Write;
+
+        let mut conn = match stream.take() {
+            Some(conn) => conn,
+            None => Self::connect(addr)?,
+        };
+        conn.write_all(format!("{command}\r\n").as_bytes())?;
+        conn.flush()?;
+        *stream = Some(conn);
+        Ok(())
+    }
+}
+
+/// Directory autosplitter definitions are loaded from.
+#[must_use]
+pub fn autosplitter_dir() -> Option<PathBuf> {
+    Config::default_config_dir().map(|dir| dir.join("autosplitters"))
+}
+
+/// Find the first autosplitter in [`autosplitter_dir`] that applies to `rom`, ordered by filename.
+#[cfg(target_arch = "wasm32")]
+pub fn find_autosplitter(_rom: &LoadedRom) -> Option<Autosplitter> {
+    None
+}
+
+/// Find the first autosplitter in [`autosplitter_dir`] that applies to `rom`, ordered by filename.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn find_autosplitter(rom: &LoadedRom) -> Option<Autosplitter> {
+    let dir = autosplitter_dir()?;
+    let mut paths = std::fs::read_dir(&dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths.into_iter().find_map(|path| {
+        let autosplitter = std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| Ok(toml::from_str::<Autosplitter>(&data)?));
+        match autosplitter {
+            Ok(autosplitter) => autosplitter.matches(rom).then_some(autosplitter),
+            Err(err) => {
+                tracing::error!("invalid autosplitter {path:?}: {err:?}");
+                None
+            }
+        }
+    })
+}
+
+impl State {
+    pub fn set_livesplit(&mut self, cfg: &LiveSplitConfig) {
+        if !cfg.enabled {
+            self.livesplit = None;
+            self.autosplit = None;
+            return;
+        }
+        if self
+            .livesplit
+            .as_ref()
+            .is_some_and(|livesplit| livesplit.addr() == cfg.addr)
+        {
+            return;
+        }
+        match LiveSplit::start(cfg.addr.clone(), self.tx.clone()) {
+            Ok(livesplit) => {
+                self.livesplit = Some(livesplit);
+                if self.autosplit.is_none() {
+                    self.load_autosplitter();
+                }
+            }
+            Err(err) => {
+                tracing::error!("failed to start LiveSplit: {err:?}");
+                self.add_message(MessageType::Error, "Failed to start LiveSplit.");
+            }
+        }
+    }
+
+    /// Load the autosplitter for the current ROM, if `LiveSplit` is enabled.
+    pub fn load_autosplitter(&mut self) {
+        self.autosplit = None;
+        if self.livesplit.is_none() {
+            return;
+        }
+        let Some(autosplitter) = self.control_deck.loaded_rom().and_then(find_autosplitter) else {
+            return;
+        };
+        self.add_message(
+            MessageType::Info,
+            format!("Loaded autosplitter: {}", autosplitter.name),
+        );
+        self.autosplit = Some(AutosplitRun::new(autosplitter));
+    }
+
+    /// Check autosplitter conditions for the last clocked frame.
+    pub fn update_autosplit(&mut self) {
+        let (Some(livesplit), Some(run)) = (&self.livesplit, &mut self.autosplit) else {
+            return;
+        };
+        if let Some(event) = run.update(&self.control_deck) {
+            tracing::debug!("autosplit: {event:?}");
+            livesplit.send(event);
+        }
+    }
+}
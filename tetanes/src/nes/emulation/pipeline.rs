@@ -0,0 +1,210 @@
+//! Pipelined video output for multi-threaded emulation.
+//!
+//! Applying the video filter, post-processing and upscaling can take a large part of the frame
+//! time, so when emulation runs on its own thread, unfiltered frames are handed off to a worker
+//! thread that filters them and sends them to the renderer while the next frame is emulated.
+//!
+//! Audio is still mixed on the emulation thread. The APU resamples audio as it's clocked, and the
+//! amount of audio queued for playback paces emulation, so it has to be queued as frames are
+//! clocked.
+
+use crate::nes::{
+    emulation::send_ref,
+    event::{NesEvent, RendererEvent, SendNesEvent},
+    renderer::FrameRecycle,
+};
+use egui::ViewportId;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+use tetanes_core::{
+    ppu::Ppu,
+    time::Instant,
+    video::{post_process::PostProcessStage, Frame, NtscConfig, Video, VideoFilter},
+};
+use thingbuf::{
+    mpsc::blocking::{self, Receiver as BufReceiver, Sender as BufSender},
+    Recycle,
+};
+use tracing::{debug, error};
+use winit::event_loop::EventLoopProxy;
+
+/// Settings applied by a [`Video`] decoder.
+#[derive(Debug, Clone, PartialEq)]
+#[must_use]
+struct VideoSettings {
+    filter: VideoFilter,
+    ntsc: NtscConfig,
+    post_process: Vec<PostProcessStage>,
+}
+
+impl VideoSettings {
+    fn new(video: &Video) -> Self {
+        Self {
+            filter: video.filter,
+            ntsc: *video.ntsc_config(),
+            post_process: video.post_process.stages.clone(),
+        }
+    }
+
+    fn apply(self, video: &mut Video) {
+        video.filter = self.filter;
+        video.set_ntsc_config(self.ntsc);
+        video.set_post_process(self.post_process);
+    }
+}
+
+/// An unfiltered frame waiting to be filtered by the worker.
+#[derive(Debug)]
+#[must_use]
+struct RawFrame {
+    buffer: Vec<u16>,
+    frame_number: u32,
+    /// Settings changed since the previous frame was sent.
+    settings: Option<VideoSettings>,
+    /// Whether frame rate is paced by vsync, so the worker waits for the renderer instead of
+    /// dropping the frame if the renderer is behind.
+    vsync: bool,
+}
+
+#[derive(Debug)]
+#[must_use]
+struct RawFrameRecycle;
+
+impl Recycle<RawFrame> for RawFrameRecycle {
+    fn new_element(&self) -> RawFrame {
+        RawFrame {
+            buffer: Vec::with_capacity(Ppu::SIZE),
+            frame_number: 0,
+            settings: None,
+            vsync: false,
+        }
+    }
+
+    fn recycle(&self, frame: &mut RawFrame) {
+        frame.settings = None;
+    }
+}
+
+/// Filters frames on a worker thread and sends them to the renderer.
+#[derive(Debug)]
+#[must_use]
+pub struct VideoPipeline {
+    tx: EventLoopProxy<NesEvent>,
+    raw_tx: Option<BufSender<RawFrame, RawFrameRecycle>>,
+    handle: Option<JoinHandle<()>>,
+    /// Settings last sent to the worker.
+    settings: VideoSettings,
+    /// Settings changed since the last frame was sent.
+    pending_settings: Option<VideoSettings>,
+    /// Set when the pipeline is dropped, so the worker stops waiting on the renderer.
+    stop: Arc<AtomicBool>,
+}
+
+impl Drop for VideoPipeline {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        // Closing the channel stops the worker once it's filtered any frames already sent
+        self.raw_tx = None;
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                error!("video pipeline panicked");
+            }
+        }
+    }
+}
+
+impl VideoPipeline {
+    /// Number of unfiltered frames buffered for the worker. Along with the frame being emulated,
+    /// frames are triple-buffered: one being emulated, one being filtered and one waiting to be
+    /// filtered.
+    const BUFFERS: usize = 2;
+
+    /// Spawn a worker thread that filters frames using a copy of `video` and sends them to
+    /// `frame_tx`.
+    pub fn spawn(
+        tx: EventLoopProxy<NesEvent>,
+        frame_tx: BufSender<Frame, FrameRecycle>,
+        video: &Video,
+    ) -> anyhow::Result<Self> {
+        let (raw_tx, raw_rx) = blocking::with_recycle(Self::BUFFERS, RawFrameRecycle);
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = std::thread::Builder::new()
+            .name("video_pipeline".into())
+            .spawn({
+                let tx = tx.clone();
+                let video = video.clone();
+                let stop = Arc::clone(&stop);
+                move || Self::main(&tx, &raw_rx, &frame_tx, video, &stop)
+            })?;
+        Ok(Self {
+            tx,
+            raw_tx: Some(raw_tx),
+            handle: Some(handle),
+            settings: VideoSettings::new(video),
+            pending_settings: None,
+            stop,
+        })
+    }
+
+    fn main(
+        tx: &EventLoopProxy<NesEvent>,
+        raw_rx: &BufReceiver<RawFrame, RawFrameRecycle>,
+        frame_tx: &BufSender<Frame, FrameRecycle>,
+        mut video: Video,
+        stop: &AtomicBool,
+    ) {
+        debug!("video pipeline started");
+        while let Some(mut raw) = raw_rx.recv_ref() {
+            #[cfg(feature = "profiling")]
+            puffin::profile_scope!("video pipeline");
+
+            if let Some(settings) = raw.settings.take() {
+                settings.apply(&mut video);
+            }
+            let filtered = video.apply_filter(&raw.buffer, raw.frame_number);
+
+            // Indicate we want to redraw to ensure there's a frame slot made available if
+            // the pool is already full
+            tx.nes_event(RendererEvent::RequestRedraw {
+                viewport_id: ViewportId::ROOT,
+                when: Instant::now(),
+            });
+            if let Some(mut frame) = send_ref(frame_tx, raw.vsync, stop, tx) {
+                frame.clear();
+                frame.extend_from_slice(filtered);
+            }
+        }
+        debug!("video pipeline stopped");
+    }
+
+    /// Update the settings used to filter frames sent after this, if they changed.
+    pub fn update_settings(&mut self, video: &Video) {
+        let settings = VideoSettings::new(video);
+        if settings != self.settings {
+            self.settings = settings.clone();
+            self.pending_settings = Some(settings);
+        }
+    }
+
+    /// Send an unfiltered frame to be filtered and sent to the renderer. If `vsync` is set, waits
+    /// for the worker to be ready, waking up periodically in case emulation is shutting down,
+    /// otherwise drops the frame if the worker is behind.
+    pub fn send(&mut self, buffer: &[u16], frame_number: u32, vsync: bool, stop: &AtomicBool) {
+        let Some(raw_tx) = &self.raw_tx else {
+            return;
+        };
+        let Some(mut raw) = send_ref(raw_tx, vsync, stop, &self.tx) else {
+            return;
+        };
+        raw.buffer.clear();
+        raw.buffer.extend_from_slice(buffer);
+        raw.frame_number = frame_number;
+        raw.settings = self.pending_settings.take();
+        raw.vsync = vsync;
+    }
+}
@@ -0,0 +1,93 @@
+//! RAM search and watches, updated from a snapshot of CPU RAM taken after each frame.
+
+use tetanes_core::{
+    control_deck::ControlDeck,
+    mem::MemorySpace,
+    ram_search::{RamSearch, SearchFilter},
+};
+
+/// RAM search results and watched values sent to the renderer after each frame.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct RamWatchState {
+    /// Frame number the values were read after.
+    pub frame: u32,
+    /// Whether a search has been started.
+    pub searching: bool,
+    /// Number of candidate addresses remaining.
+    pub count: usize,
+    /// Candidate addresses with their previous and current values, up to
+    /// [`RamWatch::MAX_CANDIDATES`].
+    pub candidates: Vec<(u16, u8, u8)>,
+    /// Watched addresses with their current values.
+    pub watches: Vec<(u16, u8)>,
+    /// Active RAM cheats, sorted by address.
+    pub cheats: Vec<(u16, u8)>,
+}
+
+/// A RAM search and list of watched addresses.
+#[derive(Default, Debug)]
+#[must_use]
+pub struct RamWatch {
+    search: Option<RamSearch>,
+    watches: Vec<u16>,
+}
+
+impl RamWatch {
+    /// Maximum number of candidates sent to the renderer, so large result sets don't have to be
+    /// copied every frame.
+    pub const MAX_CANDIDATES: usize = 500;
+
+    /// Start a new search with every address as a candidate.
+    pub fn reset(&mut self, deck: &ControlDeck) {
+        self.search = Some(RamSearch::new(deck.ram_snapshot()));
+    }
+
+    /// Narrow down candidates, starting a new search if one isn't in progress.
+    pub fn filter(&mut self, deck: &ControlDeck, filter: SearchFilter) {
+        self.search
+            .get_or_insert_with(|| RamSearch::new(deck.ram_snapshot()))
+            .filter(deck.ram_snapshot(), filter);
+    }
+
+    pub fn set_watches(&mut self, watches: Vec<u16>) {
+        self.watches = watches;
+    }
+
+    /// Stop searching, e.g. when the ROM is unloaded. Watches are kept.
+    pub fn clear(&mut self) {
+        self.search = None;
+    }
+
+    pub fn state(&self, deck: &ControlDeck) -> RamWatchState {
+        let mut state = RamWatchState {
+            frame: deck.frame_number(),
+            searching: self.search.is_some(),
+            ..Default::default()
+        };
+        if let Some(search) = &self.search {
+            let snapshot = deck.ram_snapshot();
+            state.count = search.candidates().len();
+            state.candidates = search
+                .candidates()
+                .iter()
+                .take(Self::MAX_CANDIDATES)
+                .filter_map(|&addr| {
+                    let previous = search.previous().value(addr)?;
+                    Some((addr, previous, snapshot.value(addr)?))
+                })
+                .collect();
+        }
+        state.watches = self
+            .watches
+            .iter()
+            .filter_map(|&addr| {
+                let value = deck.peek_memory(MemorySpace::Cpu, addr.into())?;
+                Some((addr, value))
+            })
+            .collect();
+        state.cheats = deck.ram_cheats().collect();
+        state.cheats.sort_unstable();
+        state
+    }
+}
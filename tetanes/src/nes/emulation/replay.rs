@@ -44,7 +44,18 @@ impl Record {
         if self.start.is_some()
             && matches!(
                 event,
-                EmulationEvent::Joypad(..) | EmulationEvent::ZapperTrigger
+                EmulationEvent::Joypad(..)
+                    | EmulationEvent::ZapperTrigger
+                    | EmulationEvent::FamilyKey(..)
+                    | EmulationEvent::PowerPad(..)
+                    | EmulationEvent::ScanBarcode(..)
+                    | EmulationEvent::VausAim(..)
+                    | EmulationEvent::VausButton(..)
+                    | EmulationEvent::MouseButtons(..)
+                    | EmulationEvent::MouseMotion(..)
+                    | EmulationEvent::InsertCoin(..)
+                    | EmulationEvent::VsServiceButton(..)
+                    | EmulationEvent::NsfTrack(..)
             )
         {
             self.events.push(ReplayEvent { frame, event });
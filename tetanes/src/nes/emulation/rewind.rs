@@ -1,16 +1,63 @@
 use crate::nes::{emulation::State, renderer::gui::MessageType};
 use tetanes_core::{
     cpu::Cpu,
-    fs::{Error, Result},
-    ppu::frame::Buffer,
+    fs::Result,
+    ppu::Ppu,
+    snapshot::SnapshotRing,
+    time::{Duration, Instant},
 };
 use tracing::error;
 
-#[derive(Default, Debug, Clone)]
+#[derive(Debug, Clone)]
 #[must_use]
 pub struct Frame {
-    pub buffer: Buffer,
-    pub state: Vec<u8>,
+    pub frame_number: u32,
+    pub timestamp: Instant,
+}
+
+impl Frame {
+    pub const THUMBNAIL_SCALE: usize = 4;
+    pub const THUMBNAIL_WIDTH: usize = Ppu::WIDTH as usize / Self::THUMBNAIL_SCALE;
+    pub const THUMBNAIL_HEIGHT: usize = Ppu::HEIGHT as usize / Self::THUMBNAIL_SCALE;
+
+    /// Returns a downscaled thumbnail of `buffer` as RGBA pixels.
+    ///
+    /// Thumbnails are generated from decoded snapshots on demand rather than stored with each
+    /// frame, since they'd take up nearly as much memory as the delta-encoded snapshots.
+    #[must_use]
+    pub fn thumbnail_rgba(buffer: &[u16]) -> Vec<u8> {
+        buffer
+            .chunks_exact(Ppu::WIDTH as usize)
+            .step_by(Self::THUMBNAIL_SCALE)
+            .flat_map(|row| row.iter().step_by(Self::THUMBNAIL_SCALE))
+            .flat_map(|&pixel| {
+                let index = (pixel as usize) * 3;
+                [
+                    Ppu::NTSC_PALETTE[index],
+                    Ppu::NTSC_PALETTE[index + 1],
+                    Ppu::NTSC_PALETTE[index + 2],
+                    0xFF,
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Summary of a rewind frame, used to display the rewind timeline.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct TimelineFrame {
+    pub frame_number: u32,
+    pub age: Duration,
+}
+
+/// Snapshot of the rewind buffer sent to the renderer for scrubbing.
+#[derive(Default, Debug, Clone)]
+#[must_use]
+pub struct Timeline {
+    pub frames: Vec<TimelineFrame>,
+    /// RGBA thumbnails for a subset of `frames`, keyed by frame index.
+    pub thumbnails: Vec<(usize, Vec<u8>)>,
 }
 
 #[derive(Default, Debug)]
@@ -18,15 +65,18 @@ pub struct Frame {
 pub struct Rewind {
     pub enabled: bool,
     pub interval_counter: usize,
-    pub index: usize,
-    pub count: usize,
     pub interval: usize,
     pub seconds: usize,
-    pub frames: Vec<Option<Frame>>,
+    /// Index of the frame last seeked to. Frames after the cursor are discarded on the next
+    /// push.
+    pub cursor: Option<usize>,
+    frames: SnapshotRing<Frame>,
 }
 
 impl Rewind {
     const TARGET_FPS: usize = 60;
+    /// Number of thumbnails sent to the renderer with a [`Timeline`].
+    const TIMELINE_THUMBNAILS: usize = 8;
 
     pub fn new(enabled: bool, seconds: u32, interval: u32) -> Self {
         let interval = interval as usize;
@@ -34,11 +84,10 @@ impl Rewind {
         Self {
             enabled,
             interval_counter: 0,
-            index: 0,
-            count: 0,
             interval,
             seconds,
-            frames: vec![None; Self::frame_size(seconds, interval)],
+            cursor: None,
+            frames: SnapshotRing::new(Self::frame_size(seconds, interval)),
         }
     }
 
@@ -55,14 +104,45 @@ impl Rewind {
 
     pub fn set_seconds(&mut self, seconds: u32) {
         self.seconds = seconds as usize;
-        self.frames
-            .resize(Self::frame_size(self.seconds, self.interval), None);
+        self.set_capacity(Self::frame_size(self.seconds, self.interval));
     }
 
     pub fn set_interval(&mut self, interval: u32) {
         self.interval = interval as usize;
-        self.frames
-            .resize(Self::frame_size(self.seconds, self.interval), None);
+        self.set_capacity(Self::frame_size(self.seconds, self.interval));
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        let evicted = self.frames.len().saturating_sub(capacity);
+        self.cursor = self.cursor.and_then(|cursor| cursor.checked_sub(evicted));
+        self.frames.set_capacity(capacity);
+    }
+
+    /// Returns the frames currently in the rewind buffer, oldest first.
+    pub fn frames(&self) -> impl ExactSizeIterator<Item = &Frame> {
+        self.frames.iter()
+    }
+
+    /// Returns a [`Timeline`] of the current rewind buffer.
+    pub fn timeline(&self) -> Timeline {
+        let now = Instant::now();
+        let frames = self
+            .frames
+            .iter()
+            .map(|frame| TimelineFrame {
+                frame_number: frame.frame_number,
+                age: now.saturating_duration_since(frame.timestamp),
+            })
+            .collect::<Vec<_>>();
+        let step = (frames.len() / Self::TIMELINE_THUMBNAILS).max(1);
+        let thumbnails = (0..frames.len())
+            .step_by(step)
+            .filter_map(|index| {
+                let cpu = self.frames.get(index)?.ok()?;
+                Some((index, Frame::thumbnail_rgba(&cpu.bus.ppu.frame.buffer)))
+            })
+            .collect();
+        Timeline { frames, thumbnails }
     }
 
     pub fn push(&mut self, cpu: &Cpu) -> Result<()> {
@@ -73,18 +153,18 @@ impl Rewind {
         if self.interval_counter >= self.interval {
             self.interval_counter = 0;
 
-            let state = bincode::serialize(&cpu)
-                .map_err(|err| Error::SerializationFailed(err.to_string()))?;
-            self.frames[self.index] = Some(Frame {
-                buffer: cpu.bus.ppu.frame.buffer.clone(),
-                state,
-            });
-
-            self.count += 1;
-            self.index += 1;
-            if self.index >= self.frames.len() {
-                self.index = 0;
+            // Resuming after seeking discards any frames ahead of the cursor
+            if let Some(cursor) = self.cursor.take() {
+                self.frames.truncate(cursor + 1);
             }
+
+            self.frames.push(
+                cpu,
+                Frame {
+                    frame_number: cpu.bus.ppu.frame_number(),
+                    timestamp: Instant::now(),
+                },
+            )?;
         }
         Ok(())
     }
@@ -93,32 +173,35 @@ impl Rewind {
         if !self.enabled {
             return None;
         }
-        if self.count > 0 {
-            self.count -= 1;
-            self.index -= 1;
-            if self.index == 0 {
-                self.index = self.frames.len() - 1;
-            }
+        if let Some(cursor) = self.cursor.take() {
+            self.frames.truncate(cursor + 1);
+        }
+        Self::restore(self.frames.pop()?)
+    }
 
-            let frame = self.frames[self.index].take()?;
-            bincode::deserialize::<Cpu>(&frame.state)
-                .map(|mut cpu| {
-                    cpu.bus.input.clear();
-                    cpu.bus.ppu.frame.buffer = frame.buffer;
-                    cpu
-                })
-                .map_err(|err| error!("Failed to deserialize CPU state: {err:?}"))
-                .ok()
-        } else {
-            None
+    /// Decode the frame at `index` without removing it, moving the cursor so that newer frames
+    /// are discarded once emulation resumes.
+    pub fn seek(&mut self, index: usize) -> Option<Cpu> {
+        if !self.enabled || index >= self.frames.len() {
+            return None;
         }
+        self.cursor = Some(index);
+        Self::restore(self.frames.get(index)?)
     }
 
     pub fn clear(&mut self) {
         self.interval_counter = 0;
-        self.index = 0;
-        self.count = 0;
-        self.frames.fill(None);
+        self.cursor = None;
+        self.frames.clear();
+    }
+
+    fn restore(cpu: Result<Cpu>) -> Option<Cpu> {
+        cpu.map(|mut cpu| {
+            cpu.bus.input.clear();
+            cpu
+        })
+        .map_err(|err| error!("Failed to deserialize CPU state: {err:?}"))
+        .ok()
     }
 }
 
@@ -144,4 +227,14 @@ impl State {
             }
         }
     }
+
+    pub fn rewind_seek(&mut self, index: usize) {
+        if !self.rewind.enabled {
+            return self.rewind_disabled();
+        }
+        if let Some(cpu) = self.rewind.seek(index) {
+            self.control_deck.load_cpu(cpu);
+            self.send_frame();
+        }
+    }
 }
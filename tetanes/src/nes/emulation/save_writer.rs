@@ -0,0 +1,95 @@
+//! Writes auto-saves on a worker thread.
+//!
+//! Auto-saves are written atomically and synced to disk, which can stall for a noticeable amount
+//! of time on slow drives. Save data is serialized on the emulation thread and handed off to a
+//! worker so emulation keeps running while it's written.
+
+use crate::nes::{
+    event::{NesEvent, SendNesEvent, UiEvent},
+    renderer::gui::MessageType,
+};
+use crossbeam::channel::{self, Receiver, Sender};
+use std::{path::PathBuf, thread::JoinHandle};
+use tetanes_core::fs;
+use tracing::{debug, error};
+use winit::event_loop::EventLoopProxy;
+
+#[derive(Debug)]
+#[must_use]
+enum Job {
+    Write { path: PathBuf, data: Vec<u8> },
+    Flush(Sender<()>),
+}
+
+/// Writes save data on a worker thread.
+#[derive(Debug)]
+#[must_use]
+pub struct SaveWriter {
+    job_tx: Option<Sender<Job>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for SaveWriter {
+    fn drop(&mut self) {
+        // Closing the channel stops the worker once it's written any saves already sent
+        self.job_tx = None;
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                error!("save writer panicked");
+            }
+        }
+    }
+}
+
+impl SaveWriter {
+    /// Spawn a worker thread that writes saves, reporting failures to the UI through `tx`.
+    pub fn spawn(tx: EventLoopProxy<NesEvent>) -> anyhow::Result<Self> {
+        let (job_tx, job_rx) = channel::unbounded();
+        let handle = std::thread::Builder::new()
+            .name("save_writer".into())
+            .spawn(move || Self::main(&tx, &job_rx))?;
+        Ok(Self {
+            job_tx: Some(job_tx),
+            handle: Some(handle),
+        })
+    }
+
+    fn main(tx: &EventLoopProxy<NesEvent>, job_rx: &Receiver<Job>) {
+        debug!("save writer started");
+        while let Ok(job) = job_rx.recv() {
+            match job {
+                Job::Write { path, data } => {
+                    if let Err(err) = fs::save_raw(&path, &data) {
+                        error!("failed to write {path:?}: {err:?}");
+                        tx.nes_event(UiEvent::Message((MessageType::Error, err.to_string())));
+                    }
+                }
+                Job::Flush(done_tx) => {
+                    let _ = done_tx.send(());
+                }
+            }
+        }
+        debug!("save writer stopped");
+    }
+
+    /// Queue `data` to be written to `path`.
+    pub fn write(&self, path: PathBuf, data: Vec<u8>) {
+        if let Some(job_tx) = &self.job_tx {
+            if job_tx.send(Job::Write { path, data }).is_err() {
+                error!("save writer stopped unexpectedly");
+            }
+        }
+    }
+
+    /// Wait for all queued saves to be written, e.g. before reading or writing the same files
+    /// directly.
+    pub fn flush(&self) {
+        let Some(job_tx) = &self.job_tx else {
+            return;
+        };
+        let (done_tx, done_rx) = channel::bounded(1);
+        if job_tx.send(Job::Flush(done_tx)).is_ok() {
+            let _ = done_rx.recv();
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! Frame time statistics shown in the performance stats window and overlay.
+//!
+//! Emulation records how long each frame took to clock and to mix audio, and sends a
+//! [`FrameStats`] summary to the renderer every frame, which adds how long it took to render.
+
+use std::{
+    collections::VecDeque,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use tetanes_core::time::{Duration, Instant};
+
+/// Frames dropped because the renderer fell behind. Frames can be dropped on the emulation or
+/// video pipeline thread.
+static DROPPED_FRAMES: AtomicUsize = AtomicUsize::new(0);
+
+/// Count a frame dropped because the renderer fell behind.
+pub fn frame_dropped() {
+    DROPPED_FRAMES.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Summary of recent frame times. Times are in milliseconds.
+#[derive(Default, Debug, Clone, PartialEq)]
+#[must_use]
+pub struct FrameStats {
+    pub fps: f32,
+    pub fps_min: f32,
+    pub frame_time: f32,
+    pub frame_time_max: f32,
+    pub frame_count: usize,
+    /// Average time spent clocking the emulator each frame.
+    pub emulation_time: f32,
+    /// Average time spent mixing and queueing audio each frame.
+    pub audio_time: f32,
+    /// Audio queued for playback.
+    pub audio_queued: f32,
+    /// Target amount of audio queued for playback.
+    pub audio_latency: f32,
+    /// Frames dropped since stats were reset.
+    pub dropped_frames: usize,
+    /// Frames emulated without rendering to catch up since stats were reset.
+    pub skipped_frames: usize,
+    /// Recent frame times, oldest first.
+    pub frame_times: Vec<f32>,
+}
+
+impl FrameStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Rolling window of recent per-frame times, in seconds.
+#[derive(Default, Debug, Clone)]
+#[must_use]
+pub struct History {
+    samples: VecDeque<f32>,
+    sum: f32,
+}
+
+impl History {
+    /// Number of frames kept.
+    pub const LEN: usize = 120;
+
+    pub fn new() -> Self {
+        Self {
+            samples: VecDeque::with_capacity(Self::LEN),
+            sum: 0.0,
+        }
+    }
+
+    pub fn push(&mut self, time: f32) {
+        if !time.is_finite() {
+            return;
+        }
+        if self.samples.len() >= Self::LEN {
+            if let Some(oldest) = self.samples.pop_front() {
+                self.sum -= oldest;
+            }
+        }
+        self.sum += time;
+        self.samples.push_back(time);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn avg(&self) -> f32 {
+        if self.samples.is_empty() {
+            0.0
+        } else {
+            self.sum / self.samples.len() as f32
+        }
+    }
+
+    pub fn max(&self) -> f32 {
+        self.samples.iter().copied().fold(0.0, f32::max)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = f32> + '_ {
+        self.samples.iter().copied()
+    }
+}
+
+/// Collects frame time statistics on the emulation thread.
+#[derive(Debug)]
+#[must_use]
+pub struct Stats {
+    frame_count: usize,
+    last_frame: Instant,
+    frame_time: History,
+    emulation_time: History,
+    audio_time: History,
+    /// Averages, only updated periodically so they're readable.
+    frame_time_avg: f32,
+    emulation_time_avg: f32,
+    audio_time_avg: f32,
+    last_update: Instant,
+    /// Dropped frame count when stats were last reset.
+    dropped_frames_start: usize,
+    skipped_frames: usize,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Stats {
+    const UPDATE_INTERVAL: Duration = Duration::from_millis(300);
+    /// Frames ignored after a reset to allow the average to stabilize.
+    const WARMUP_FRAMES: usize = 10;
+
+    pub fn new() -> Self {
+        Self {
+            frame_count: 0,
+            last_frame: Instant::now(),
+            frame_time: History::new(),
+            emulation_time: History::new(),
+            audio_time: History::new(),
+            frame_time_avg: 1.0 / 60.0,
+            emulation_time_avg: 0.0,
+            audio_time_avg: 0.0,
+            last_update: Instant::now(),
+            dropped_frames_start: DROPPED_FRAMES.load(Ordering::Relaxed),
+            skipped_frames: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Restart timing the current frame, e.g. to avoid a large dip in frame stats after
+    /// unpausing.
+    pub fn resume(&mut self) {
+        self.last_frame = Instant::now();
+    }
+
+    /// Record a frame that took `emulation_time` to clock and `audio_time` to mix.
+    pub fn push(&mut self, emulation_time: Duration, audio_time: Duration) {
+        let frame_time = self.last_frame.elapsed();
+        self.last_frame = Instant::now();
+        self.frame_count += 1;
+
+        if self.frame_count >= Self::WARMUP_FRAMES {
+            self.frame_time.push(frame_time.as_secs_f32());
+            self.emulation_time.push(emulation_time.as_secs_f32());
+            self.audio_time.push(audio_time.as_secs_f32());
+        }
+    }
+
+    /// Count a frame emulated without rendering to catch up.
+    pub fn frame_skipped(&mut self) {
+        self.skipped_frames += 1;
+    }
+
+    /// Summarize recorded frames, given the amount of audio queued for playback.
+    pub fn frame_stats(&mut self, audio_queued: Duration, audio_latency: Duration) -> FrameStats {
+        let now = Instant::now();
+        if !self.frame_time.is_empty() && now > self.last_update + Self::UPDATE_INTERVAL {
+            self.last_update = now;
+            self.frame_time_avg = self.frame_time.avg();
+            self.emulation_time_avg = self.emulation_time.avg();
+            self.audio_time_avg = self.audio_time.avg();
+        }
+
+        let frame_time_max = self.frame_time.max();
+        let mut fps = 1.0 / self.frame_time_avg;
+        let mut fps_min = 1.0 / frame_time_max;
+        if !fps.is_finite() {
+            fps = 0.0;
+        }
+        if !fps_min.is_finite() {
+            fps_min = 0.0;
+        }
+        FrameStats {
+            fps,
+            fps_min,
+            frame_time: self.frame_time_avg * 1000.0,
+            frame_time_max: frame_time_max * 1000.0,
+            frame_count: self.frame_count,
+            emulation_time: self.emulation_time_avg * 1000.0,
+            audio_time: self.audio_time_avg * 1000.0,
+            audio_queued: audio_queued.as_secs_f32() * 1000.0,
+            audio_latency: audio_latency.as_secs_f32() * 1000.0,
+            dropped_frames: DROPPED_FRAMES
+                .load(Ordering::Relaxed)
+                .saturating_sub(self.dropped_frames_start),
+            skipped_frames: self.skipped_frames,
+            frame_times: self.frame_time.iter().map(|time| time * 1000.0).collect(),
+        }
+    }
+}
@@ -1,35 +1,57 @@
 use crate::{
     nes::{
         action::{Action, Debug, DebugStep, Feature, Setting, Ui},
-        config::Config,
-        emulation::FrameStats,
-        input::{AxisDirection, Gamepads, Input, InputBindings},
-        renderer::gui::{Menu, MessageType},
-        rom::RomData,
+        config::{
+            BackgroundMode, Config, FastForwardAudio, FastForwardConfig, FileKind,
+            LiveReloadConfig, ScreenshotConfig, SessionMode,
+        },
+        emulation::{
+            lan::{Peer, ReceivedState, TrustedPeer},
+            livesplit::LiveSplitConfig,
+            ram_watch::RamWatchState,
+            rewind::Timeline,
+            stats::FrameStats,
+            MemoryPage,
+        },
+        input::{
+            family_key, power_pad_button, AxisDirection, Gamepads, HeldAction, Input, InputBindings,
+        },
+        library::Library,
+        plugin::PluginUi,
+        renderer::gui::{InputLog, Menu, MessageType},
+        rom::{stream::ReadProgress, RomData},
+        shutdown::Shutdown,
         Nes, Running, State,
     },
-    platform::{self, open_file_dialog},
+    platform::{self, open_file_dialog, open_folder_dialog},
 };
 use anyhow::anyhow;
 use egui::ViewportId;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::{ops::Range, path::PathBuf};
 use tetanes_core::{
     action::Action as DeckAction,
-    apu::Channel,
+    apu::{filter::FilterConfig, Apu, Channel},
+    cart::HeaderOverrides,
     common::{NesRegion, ResetKind},
     control_deck::{LoadedRom, MapperRevisionsConfig},
     genie::GenieCode,
-    input::{FourPlayer, JoypadBtn, Player},
-    mem::RamState,
+    input::{
+        expansion::{ExpansionDevice, FamilyKey},
+        FourPlayer, JoypadBtn, JoypadBtnState, Player,
+    },
+    mapper::{MapperRegister, MapperRevision, MapperWrite},
+    mem::{MemorySpace, RamState},
+    playlist::{Playlist, PlaylistEntry, PlaylistRun},
+    ram_search::SearchFilter,
     time::{Duration, Instant},
-    video::VideoFilter,
+    video::{post_process::PostProcessStage, NtscConfig, VideoFilter},
 };
 use tracing::{error, trace};
 use winit::{
     event::{ElementState, Event, WindowEvent},
     event_loop::{ControlFlow, DeviceEvents, EventLoopProxy, EventLoopWindowTarget},
-    keyboard::PhysicalKey,
+    keyboard::{KeyCode, ModifiersState, PhysicalKey},
     window::WindowId,
 };
 
@@ -53,8 +75,14 @@ impl SendNesEvent for EventLoopProxy<NesEvent> {
 pub enum UiEvent {
     Error(String),
     Message((MessageType, String)),
+    LibraryAddDirDialog,
+    LibraryDatabaseDialog,
+    LibraryScan,
     LoadRomDialog,
+    LoadRomDialogIn(PathBuf),
     LoadReplayDialog,
+    PinDirDialog,
+    PlaylistAddRomDialog,
     FileDialogCancelled,
     Terminate,
 }
@@ -77,30 +105,62 @@ impl AsRef<[u8]> for ReplayData {
 #[derive(Debug, Clone, PartialEq)]
 #[must_use]
 pub enum ConfigEvent {
+    AccurateMixing(bool),
     ApuChannelEnabled((Channel, bool)),
+    ApuChannelVolume((Channel, f32)),
+    ApplyRecordedCheats(bool),
     AudioBuffer(usize),
     AudioEnabled(bool),
+    AudioFilters(FilterConfig),
     AudioLatency(Duration),
+    AudioRecordStems(bool),
+    AudioScope(bool),
     AutoLoad(bool),
+    AutoPatch(bool),
     AutoSave(bool),
     AutoSaveInterval(Duration),
+    BackgroundMode(BackgroundMode),
     ConcurrentDpad(bool),
     CycleAccurate(bool),
+    DynamicRateControl(bool),
+    ExpansionDevice(ExpansionDevice),
+    FastForward(FastForwardConfig),
+    FastForwardAudio(FastForwardAudio),
     FourPlayer(FourPlayer),
     GenieCodeAdded(GenieCode),
     GenieCodeRemoved(String),
-    HideOverscan(bool),
+    HeaderOverrides(Option<HeaderOverrides>),
     InputBindings,
+    LanTransfer(bool),
+    LanTrustedPeers(Vec<TrustedPeer>),
+    LiveReload(LiveReloadConfig),
+    LiveSplit(LiveSplitConfig),
     MapperRevisions(MapperRevisionsConfig),
+    MasterVolume(f32),
+    MaxFrameSkip(u32),
+    Mouse(Option<Player>),
+    MouseSensitivity(f32),
+    NtscConfig(NtscConfig),
+    PerfStats(bool),
+    PostProcess(Vec<PostProcessStage>),
     RamState(RamState),
     Region(NesRegion),
     RewindEnabled(bool),
     RewindSeconds(u32),
     RewindInterval(u32),
+    RomDipSwitches(Option<u8>),
+    RomExpansionDevice(Option<ExpansionDevice>),
+    RomMapperRevision(Option<MapperRevision>),
+    RomRamState(Option<RamState>),
+    RomRegion(Option<NesRegion>),
     RunAhead(usize),
     SaveSlot(u8),
     Scale(f32),
+    Screenshot(ScreenshotConfig),
+    SessionMode(SessionMode),
     Speed(f32),
+    SramAutosaveInterval(Option<Duration>),
+    VausConnected(bool),
     VideoFilter(VideoFilter),
     ZapperConnected(bool),
 }
@@ -109,26 +169,82 @@ pub enum ConfigEvent {
 #[must_use]
 pub enum EmulationEvent {
     AudioRecord(bool),
+    /// Flush battery-backed RAM and the auto-save state to disk, e.g. before a mobile app is
+    /// suspended in the background.
+    AutoSave,
+    CodeDataLog(bool),
     DebugStep(DebugStep),
+    /// Drop the save state received from a peer after the user declined it.
+    DiscardLanState,
     EmulatePpuWarmup(bool),
+    /// Start or stop fast-forwarding using the [`FastForwardConfig`] settings.
+    FastForward(bool),
+    FamilyKey((FamilyKey, ElementState)),
+    /// Show a range of a memory space in the hex editor, or `None` to close it.
+    HexEditor(Option<(MemorySpace, Range<usize>)>),
+    /// Insert a coin into a Vs. System coin slot.
+    InsertCoin(u8),
     InstantRewind,
     Joypad((Player, JoypadBtn, ElementState)),
     #[serde(skip)]
+    LanStateReceived(ReceivedState),
+    /// Load the save state received from a peer after the user accepted it.
+    LoadLanState,
+    #[serde(skip)]
     LoadReplay((String, ReplayData)),
     LoadReplayPath(PathBuf),
     #[serde(skip)]
     LoadRom((String, RomData)),
     LoadRomPath(PathBuf),
     LoadState(u8),
+    MapDump(bool),
+    /// Set the Super NES Mouse left and right button states.
+    MouseButtons((bool, bool)),
+    /// Move the Super NES Mouse by host mouse motion.
+    MouseMotion((f32, f32)),
+    /// Restart the NSF player on a 0-based track.
+    NsfTrack(u8),
+    /// Set whether the window is unfocused or hidden, pausing or muting emulation depending on
+    /// the configured [`BackgroundMode`].
     UnfocusedPause(bool),
     Pause(bool),
+    PlaylistSkip,
+    PlaylistStart(Playlist),
+    PlaylistStop,
+    PluginMenuItem((usize, u32)),
+    PokeMemory((MemorySpace, usize, u8)),
+    /// Press or release a Family Trainer Mat button.
+    PowerPad((usize, ElementState)),
+    /// Freeze the value at a RAM address, or `None` to unfreeze it.
+    RamCheat((u16, Option<u8>)),
+    RamSearchFilter(SearchFilter),
+    RamSearchReset,
+    RamWatches(Vec<u16>),
     ReplayRecord(bool),
     Reset(ResetKind),
+    /// Load the session state saved when the loaded ROM was last exited.
+    ResumeSession,
+    RewindSeek(usize),
+    RewindTimeline,
     Rewinding(bool),
     SaveState(u8),
+    /// Scan a barcode with the Barcode Battler.
+    ScanBarcode(String),
+    /// Find a byte pattern in a memory space, starting at an address and wrapping around.
+    SearchMemory((MemorySpace, Vec<u8>, usize)),
+    SendState(Peer),
     ShowFrameStats(bool),
-    Screenshot,
+    ShowInputDiagnostics(bool),
+    ShowMapperInspector(bool),
+    ShowRamWatch(bool),
+    Screenshot(ScreenshotConfig),
+    SpriteRip(bool),
     UnloadRom,
+    /// Turn the Arkanoid Vaus paddle, from `0.0` all the way left to `1.0` all the way right.
+    VausAim(f32),
+    VausButton(bool),
+    /// Press or release the Vs. System service button.
+    VsServiceButton(bool),
     ZapperAim((u32, u32)),
     ZapperTrigger,
 }
@@ -136,16 +252,39 @@ pub enum EmulationEvent {
 #[derive(Debug, Clone)]
 #[must_use]
 pub enum RendererEvent {
+    AudioScope(Vec<[f32; Apu::MAX_CHANNEL_COUNT]>),
+    AutoSaved,
     FrameStats(FrameStats),
+    GenieCodes(Vec<GenieCode>),
+    JoypadState((u32, [JoypadBtnState; 4])),
+    /// An unpaired device tried to send a save state.
+    LanPairRequest(TrustedPeer),
+    LanPeers(Vec<Peer>),
+    /// Ask whether to load a save state received from a device, and the ROM it's for.
+    LanStatePrompt((String, String)),
+    LibraryScanned(Library),
+    MapperState((Vec<MapperRegister>, Vec<MapperWrite>)),
+    MemoryPage(MemoryPage),
+    MemorySearch(Option<usize>),
+    /// Current 0-based NSF track and time elapsed since it started.
+    NsfState((u8, Duration)),
+    PlaylistRun(PlaylistRun),
+    Plugins(Vec<PluginUi>),
+    RamWatch(RamWatchState),
     ShowMenubar(bool),
     ScaleChanged,
+    RegionChanged(NesRegion),
     ResourcesReady,
     RequestRedraw {
         viewport_id: ViewportId,
         when: Instant,
     },
     RomLoaded(LoadedRom),
+    RomReadProgress(Option<ReadProgress>),
     RomUnloaded,
+    /// Ask whether to resume the session saved for the named ROM.
+    ResumeSessionPrompt(String),
+    RewindTimeline(Timeline),
     Menu(Menu),
 }
 
@@ -200,6 +339,7 @@ impl Nes {
                 let state = if let State::Running(state) = &mut self.state {
                     if platform::supports(platform::Feature::Suspend) {
                         state.renderer.recreate_window(event_loop);
+                        state.nes_event(EmulationEvent::UnfocusedPause(false));
                     }
                     state
                 } else {
@@ -299,6 +439,10 @@ impl Running {
         match event {
             Event::Suspended => {
                 if platform::supports(platform::Feature::Suspend) {
+                    // Mobile apps can be killed at any time while in the background
+                    self.nes_event(EmulationEvent::UnfocusedPause(true));
+                    self.nes_event(EmulationEvent::AutoSave);
+                    self.save_config_changes();
                     if let Err(err) = self.renderer.drop_window() {
                         error!("failed to suspend window: {err:?}");
                         event_loop.exit();
@@ -327,13 +471,15 @@ impl Running {
                         }
                     }
                 }
+                self.repeat_held_actions();
+                self.reload_config_changes();
 
                 self.emulation.clock_frame();
             }
             Event::WindowEvent {
                 window_id, event, ..
             } => {
-                let res = self.renderer.on_window_event(window_id, &event);
+                let res = self.renderer.on_window_event(window_id, &event, &self.cfg);
                 if res.repaint {
                     self.repaint_times.insert(window_id, Instant::now());
                 }
@@ -351,14 +497,24 @@ impl Running {
                                 self.renderer.on_error(err);
                             }
                         }
-                        WindowEvent::Resized(_) => {
+                        WindowEvent::Resized(_) | WindowEvent::Moved(_) => {
                             if Some(window_id) == self.renderer.root_window_id() {
                                 self.cfg.renderer.fullscreen = self.renderer.fullscreen();
+                                if let Some(geometry) = self.renderer.window_geometry() {
+                                    self.cfg.renderer.window_geometry = Some(geometry);
+                                }
                             }
                         }
                         WindowEvent::Focused(focused) => {
                             if focused {
                                 self.repaint_times.insert(window_id, Instant::now());
+                            } else {
+                                // Releases aren't received while unfocused, so stop repeating
+                                self.held_actions.clear();
+                                if self.fast_forwarding && !self.cfg.emulation.fast_forward.toggle {
+                                    self.fast_forwarding = false;
+                                    self.nes_event(EmulationEvent::FastForward(false));
+                                }
                             }
                         }
                         WindowEvent::Occluded(occluded) => {
@@ -368,6 +524,12 @@ impl Running {
                             }
                         }
                         WindowEvent::KeyboardInput { event, .. } => {
+                            self.renderer.log_input(|| {
+                                InputLog::Raw(format!(
+                                    "Key {:?} ({:?}) {:?}",
+                                    event.physical_key, event.logical_key, event.state
+                                ))
+                            });
                             if let PhysicalKey::Code(key) = event.physical_key {
                                 self.on_input(
                                     window_id,
@@ -381,6 +543,8 @@ impl Running {
                             self.modifiers = modifiers;
                         }
                         WindowEvent::MouseInput { button, state, .. } => {
+                            self.renderer
+                                .log_input(|| InputLog::Raw(format!("Mouse {button:?} {state:?}")));
                             self.on_input(window_id, Input::Mouse(button), state, false);
                         }
                         WindowEvent::DroppedFile(path) => {
@@ -403,6 +567,10 @@ impl Running {
                     NesEvent::Config(ConfigEvent::InputBindings) => {
                         self.input_bindings = InputBindings::from_input_config(&self.cfg.input);
                     }
+                    NesEvent::Renderer(RendererEvent::GenieCodes(genie_codes)) => {
+                        self.cfg.deck.genie_codes = genie_codes;
+                    }
+                    NesEvent::Renderer(RendererEvent::AutoSaved) => self.save_config_changes(),
                     NesEvent::Renderer(RendererEvent::RequestRedraw { viewport_id, when }) => {
                         if let Some(window_id) = self.renderer.window_id_for_viewport(viewport_id) {
                             self.repaint_times.insert(
@@ -424,54 +592,103 @@ impl Running {
                 }
             }
             Event::LoopExiting => {
+                let shutdown = Shutdown::start(Shutdown::TIMEOUT);
+
                 #[cfg(feature = "profiling")]
                 puffin::set_scopes_on(false);
 
+                // Emulation has to stop before the renderer is destroyed, since it may be blocked
+                // waiting on vsync
+                shutdown.step("stopping emulation");
+                self.emulation.shutdown();
+
+                shutdown.step("saving debug session");
+                self.renderer.save_debug_session();
+
+                shutdown.step("saving configuration");
+                // Only flush changes so settings edited in the file that weren't applied live
+                // aren't overwritten
+                self.save_config_changes();
+
+                shutdown.step("destroying renderer");
                 self.renderer.destroy();
 
-                if let Err(err) = self.cfg.save() {
-                    error!("failed to save configuration: {err:?}");
-                }
+                // Keep the watchdog running until the remaining resources are dropped
+                shutdown.step("releasing resources");
+                self.shutdown = Some(shutdown);
             }
             _ => (),
         }
     }
 
+    /// Flush configuration changes to disk, if any were made since it was last saved.
+    pub fn save_config_changes(&mut self) {
+        if self.cfg != self.saved_cfg {
+            match self.cfg.save() {
+                Ok(()) => self.saved_cfg = self.cfg.clone(),
+                Err(err) => error!("failed to save configuration: {err:?}"),
+            }
+        }
+    }
+
     pub fn on_ui_event(&mut self, event: UiEvent) {
         match event {
             UiEvent::Message((ty, msg)) => self.renderer.add_message(ty, msg),
             UiEvent::Error(err) => self.renderer.on_error(anyhow!(err)),
-            UiEvent::LoadRomDialog => {
+            UiEvent::LoadRomDialog => self.load_rom_dialog(self.cfg.dialog_dir(FileKind::Rom)),
+            UiEvent::LoadRomDialogIn(dir) => self.load_rom_dialog(Some(dir)),
+            UiEvent::LibraryAddDirDialog => {
+                match open_folder_dialog("Add ROM Directory", self.cfg.dialog_dir(FileKind::Rom)) {
+                    Ok(maybe_path) => {
+                        if let Some(path) = maybe_path {
+                            self.cfg.set_dialog_dir(FileKind::Rom, &path);
+                            if !self.cfg.renderer.library_dirs.contains(&path) {
+                                self.cfg.renderer.library_dirs.push(path);
+                            }
+                            self.renderer.scan_library(&self.cfg);
+                        }
+                    }
+                    Err(err) => {
+                        error!("failed to open directory dialog: {err:?}");
+                        self.nes_event(UiEvent::Error(
+                            "failed to open directory dialog".to_string(),
+                        ));
+                    }
+                }
+            }
+            UiEvent::LibraryDatabaseDialog => {
                 match open_file_dialog(
-                    "Load ROM",
-                    "NES ROMs",
-                    &["nes"],
-                    self.cfg
-                        .renderer
-                        .roms_path
-                        .as_ref()
-                        .map(|p| p.to_path_buf()),
+                    "Load Game Database",
+                    "No-Intro DAT",
+                    &["dat", "xml"],
+                    self.cfg.dialog_dir(FileKind::GameDatabase),
                 ) {
                     Ok(maybe_path) => {
                         if let Some(path) = maybe_path {
-                            self.nes_event(EmulationEvent::LoadRomPath(path));
+                            self.cfg.set_dialog_dir(FileKind::GameDatabase, &path);
+                            self.cfg.renderer.game_database = Some(path);
+                            self.renderer.scan_library(&self.cfg);
                         }
                     }
                     Err(err) => {
-                        error!("failed top open rom dialog: {err:?}");
-                        self.nes_event(UiEvent::Error("failed to open rom dialog".to_string()));
+                        error!("failed to open database dialog: {err:?}");
+                        self.nes_event(UiEvent::Error(
+                            "failed to open database dialog".to_string(),
+                        ));
                     }
                 }
             }
+            UiEvent::LibraryScan => self.renderer.scan_library(&self.cfg),
             UiEvent::LoadReplayDialog => {
                 match open_file_dialog(
                     "Load Replay",
                     "Replay Recording",
                     &["replay"],
-                    Config::default_data_dir(),
+                    self.cfg.dialog_dir(FileKind::Replay),
                 ) {
                     Ok(maybe_path) => {
                         if let Some(path) = maybe_path {
+                            self.cfg.set_dialog_dir(FileKind::Replay, &path);
                             self.nes_event(EmulationEvent::LoadReplayPath(path));
                         }
                     }
@@ -481,6 +698,46 @@ impl Running {
                     }
                 }
             }
+            UiEvent::PinDirDialog => {
+                match open_folder_dialog("Pin Folder", self.cfg.dialog_dir(FileKind::Rom)) {
+                    Ok(maybe_path) => {
+                        if let Some(path) = maybe_path {
+                            if !self.cfg.renderer.pinned_dirs.contains(&path) {
+                                self.cfg.renderer.pinned_dirs.push(path);
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        error!("failed to open directory dialog: {err:?}");
+                        self.nes_event(UiEvent::Error(
+                            "failed to open directory dialog".to_string(),
+                        ));
+                    }
+                }
+            }
+            UiEvent::PlaylistAddRomDialog => {
+                match open_file_dialog(
+                    "Add ROM to Playlist",
+                    "NES ROMs",
+                    &["nes", "nsf", "nsfe"],
+                    self.cfg.dialog_dir(FileKind::Rom),
+                ) {
+                    Ok(maybe_path) => {
+                        if let Some(path) = maybe_path {
+                            self.cfg.set_dialog_dir(FileKind::Rom, &path);
+                            self.cfg
+                                .emulation
+                                .playlist
+                                .entries
+                                .push(PlaylistEntry::new(path.to_string_lossy()));
+                        }
+                    }
+                    Err(err) => {
+                        error!("failed to open rom dialog: {err:?}");
+                        self.nes_event(UiEvent::Error("failed to open rom dialog".to_string()));
+                    }
+                }
+            }
             UiEvent::FileDialogCancelled => {
                 if self.renderer.rom_loaded() {
                     self.paused = false;
@@ -491,6 +748,22 @@ impl Running {
         }
     }
 
+    /// Open a file dialog in `dir` to pick a ROM to load.
+    fn load_rom_dialog(&mut self, dir: Option<PathBuf>) {
+        match open_file_dialog("Load ROM", "NES ROMs", &["nes", "nsf", "nsfe"], dir) {
+            Ok(maybe_path) => {
+                if let Some(path) = maybe_path {
+                    self.cfg.set_dialog_dir(FileKind::Rom, &path);
+                    self.nes_event(EmulationEvent::LoadRomPath(path));
+                }
+            }
+            Err(err) => {
+                error!("failed top open rom dialog: {err:?}");
+                self.nes_event(UiEvent::Error("failed to open rom dialog".to_string()));
+            }
+        }
+    }
+
     /// Trigger a custom event.
     pub fn nes_event(&mut self, event: impl Into<NesEvent>) {
         let event = event.into();
@@ -500,6 +773,8 @@ impl Running {
         self.renderer.on_event(&event);
         match event {
             NesEvent::Ui(event) => self.on_ui_event(event),
+            // Keep in sync with pausing from the menu, so frame advance knows when to step
+            NesEvent::Emulation(EmulationEvent::Pause(paused)) => self.paused = paused,
             NesEvent::Emulation(EmulationEvent::LoadRomPath(path)) => {
                 if let Ok(path) = path.canonicalize() {
                     self.cfg.renderer.recent_roms.insert(path);
@@ -519,6 +794,18 @@ impl Running {
         }
 
         if let Some(uuid) = self.gamepads.gamepad_uuid(event.id) {
+            self.renderer.log_input(|| {
+                let name = self
+                    .gamepads
+                    .gamepad_name(event.id)
+                    .unwrap_or_else(|| format!("Gamepad {}", event.id));
+                let assigned = if self.cfg.input.gamepad_assignment(&uuid).is_some() {
+                    ""
+                } else {
+                    " (unassigned)"
+                };
+                InputLog::Raw(format!("{name}{assigned}: {:?}", event.event))
+            });
             match event.event {
                 EventType::ButtonPressed(button, _) => {
                     if let Some(player) = self.cfg.input.gamepad_assignment(&uuid) {
@@ -581,19 +868,27 @@ impl Running {
                                 MessageType::Info,
                                 format!("Assigned gamepad `{name}` to player {player:?}."),
                             );
+                            if matches!(player, Player::Three | Player::Four)
+                                && self.cfg.deck.four_player == FourPlayer::Disabled
+                            {
+                                self.renderer.add_message(
+                                    MessageType::Warn,
+                                    "Enable a Four Player mode to use players 3 and 4.",
+                                );
+                            }
                             self.cfg.input.assign_gamepad(player, uuid);
                         }
                     }
                 }
                 EventType::Disconnected => {
+                    let name = self.gamepads.gamepad_name(event.id);
                     self.gamepads.disconnect(event.id);
                     if let Some(player) = self.cfg.input.unassign_gamepad_name(&uuid) {
-                        if let Some(name) = self.gamepads.gamepad_name_by_uuid(&uuid) {
-                            self.renderer.add_message(
-                                MessageType::Info,
-                                format!("Unassigned gamepad `{name}` from player {player:?}."),
-                            );
-                        }
+                        let name = name.unwrap_or_else(|| format!("Gamepad {}", event.id));
+                        self.renderer.add_message(
+                            MessageType::Info,
+                            format!("Unassigned gamepad `{name}` from player {player:?}."),
+                        );
                     }
                 }
                 _ => (),
@@ -609,263 +904,530 @@ impl Running {
         state: ElementState,
         repeat: bool,
     ) {
+        self.renderer.log_input(|| InputLog::Resolved {
+            input,
+            action: self.input_bindings.get(&input).copied(),
+            state,
+            repeat,
+        });
+        // Platform key repeats are ignored in favor of each action's `RepeatPolicy`
+        if repeat {
+            return;
+        }
+        if let Input::Key(key, modifiers) = input {
+            if self.on_expansion_key(window_id, key, modifiers, state) {
+                return;
+            }
+        }
         if let Some(action) = self.input_bindings.get(&input).copied() {
-            trace!("action: {action:?}, state: {state:?}, repeat: {repeat:?}");
-            let released = state == ElementState::Released;
-            let root_window = Some(window_id) == self.renderer.root_window_id();
-            match action {
-                Action::Ui(ui_state) if released => match ui_state {
-                    Ui::Quit => self.tx.nes_event(UiEvent::Terminate),
-                    Ui::TogglePause => {
-                        if root_window && self.renderer.rom_loaded() {
-                            self.paused = !self.paused;
-                            self.nes_event(EmulationEvent::Pause(self.paused));
-                        }
+            if let Some((delay, interval)) = self.input_bindings.repeat_policy(action).timing() {
+                if state == ElementState::Pressed {
+                    // Already held, e.g. an axis moving further in the same direction
+                    if self.held_actions.contains_key(&action) {
+                        return;
                     }
-                    Ui::LoadRom => {
-                        if self.renderer.rom_loaded() {
+                    self.held_actions.insert(
+                        action,
+                        HeldAction {
+                            window_id,
+                            interval,
+                            next_repeat: Instant::now() + delay,
+                        },
+                    );
+                    self.wake_for_held_actions();
+                } else {
+                    self.held_actions.remove(&action);
+                }
+            }
+            self.on_action(window_id, action, state, false);
+        }
+    }
+
+    /// Pass keys through to a connected Family BASIC Keyboard or Family Trainer Mat instead of
+    /// their bindings, returning whether the key was handled. Keys pressed while holding Control
+    /// or Super are left to their bindings so shortcuts keep working.
+    fn on_expansion_key(
+        &mut self,
+        window_id: WindowId,
+        key: KeyCode,
+        modifiers: ModifiersState,
+        state: ElementState,
+    ) -> bool {
+        if Some(window_id) != self.renderer.root_window_id()
+            || !self.renderer.rom_loaded()
+            || (state == ElementState::Pressed
+                && modifiers.intersects(ModifiersState::CONTROL | ModifiersState::SUPER))
+        {
+            return false;
+        }
+        let event = match self.renderer.expansion_device(&self.cfg) {
+            ExpansionDevice::FamilyKeyboard => {
+                family_key(key).map(|key| EmulationEvent::FamilyKey((key, state)))
+            }
+            ExpansionDevice::PowerPad => {
+                power_pad_button(key).map(|button| EmulationEvent::PowerPad((button, state)))
+            }
+            _ => None,
+        };
+        match event {
+            Some(event) => {
+                self.nes_event(event);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Trigger held actions again once their repeat delay or interval has elapsed.
+    fn repeat_held_actions(&mut self) {
+        if self.held_actions.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        let repeating = self
+            .held_actions
+            .iter_mut()
+            .filter(|(_, held)| held.next_repeat <= now)
+            .map(|(action, held)| {
+                held.next_repeat = now + held.interval;
+                (*action, held.window_id)
+            })
+            .collect::<Vec<_>>();
+        for (action, window_id) in repeating {
+            self.on_action(window_id, action, ElementState::Pressed, true);
+        }
+        self.wake_for_held_actions();
+    }
+
+    /// Schedule the event loop to wake up in time for the next held action repeat.
+    fn wake_for_held_actions(&mut self) {
+        for held in self.held_actions.values() {
+            self.repaint_times
+                .entry(held.window_id)
+                .and_modify(|when| *when = (*when).min(held.next_repeat))
+                .or_insert(held.next_repeat);
+        }
+    }
+
+    /// Apply settings edited in the configuration file since it was last read that are safe to
+    /// change while running. Other settings take effect on restart.
+    fn reload_config_changes(&mut self) {
+        /// Returns the edited setting if it differs from the current one.
+        fn edited<T: PartialEq + Clone>(prev: &T, new: &T, current: &mut T) -> Option<T> {
+            (new != prev && new != current).then(|| {
+                current.clone_from(new);
+                new.clone()
+            })
+        }
+
+        let Some((prev, cfg)) = self.config_watcher.poll() else {
+            return;
+        };
+        // Edits are now on disk, so only flush later changes
+        self.saved_cfg = cfg.clone();
+
+        let mut events = Vec::new();
+        if let Some(filter) = edited(
+            &prev.deck.filter,
+            &cfg.deck.filter,
+            &mut self.cfg.deck.filter,
+        ) {
+            events.push(ConfigEvent::VideoFilter(filter));
+        }
+        if let Some(ntsc) = edited(&prev.deck.ntsc, &cfg.deck.ntsc, &mut self.cfg.deck.ntsc) {
+            events.push(ConfigEvent::NtscConfig(ntsc));
+        }
+        if let Some(stages) = edited(
+            &prev.deck.post_process,
+            &cfg.deck.post_process,
+            &mut self.cfg.deck.post_process,
+        ) {
+            events.push(ConfigEvent::PostProcess(stages));
+        }
+        if let Some(filters) = edited(
+            &prev.deck.audio_filters,
+            &cfg.deck.audio_filters,
+            &mut self.cfg.deck.audio_filters,
+        ) {
+            events.push(ConfigEvent::AudioFilters(filters));
+        }
+        if let Some(volume) = edited(
+            &prev.deck.mixer.master_volume,
+            &cfg.deck.mixer.master_volume,
+            &mut self.cfg.deck.mixer.master_volume,
+        ) {
+            events.push(ConfigEvent::MasterVolume(volume));
+        }
+        for (index, volume) in self.cfg.deck.mixer.channel_volumes.iter_mut().enumerate() {
+            let (Ok(channel), Some(volume)) = (
+                Channel::try_from(index),
+                edited(
+                    &prev.deck.mixer.channel_volumes[index],
+                    &cfg.deck.mixer.channel_volumes[index],
+                    volume,
+                ),
+            ) else {
+                continue;
+            };
+            events.push(ConfigEvent::ApuChannelVolume((channel, volume)));
+        }
+        if let Some(speed) = edited(
+            &prev.emulation.speed,
+            &cfg.emulation.speed,
+            &mut self.cfg.emulation.speed,
+        ) {
+            events.push(ConfigEvent::Speed(speed));
+        }
+        if let Some(fast_forward) = edited(
+            &prev.emulation.fast_forward,
+            &cfg.emulation.fast_forward,
+            &mut self.cfg.emulation.fast_forward,
+        ) {
+            events.push(ConfigEvent::FastForward(fast_forward));
+        }
+
+        if !events.is_empty() {
+            self.renderer.add_message(
+                MessageType::Info,
+                format!("Reloaded {} setting(s) from configuration", events.len()),
+            );
+        }
+        for event in events {
+            self.nes_event(event);
+        }
+    }
+
+    /// Handle an action being pressed, released or repeated while held.
+    fn on_action(
+        &mut self,
+        window_id: WindowId,
+        action: Action,
+        state: ElementState,
+        repeat: bool,
+    ) {
+        trace!("action: {action:?}, state: {state:?}, repeat: {repeat:?}");
+        let released = state == ElementState::Released;
+        // Actions that repeat trigger when pressed and on each repeat, others when released
+        let triggered = if self.input_bindings.repeat_policy(action).repeats() {
+            !released
+        } else {
+            released
+        };
+        let root_window = Some(window_id) == self.renderer.root_window_id();
+        match action {
+            Action::Ui(ui_state) if triggered => match ui_state {
+                Ui::Quit => self.tx.nes_event(UiEvent::Terminate),
+                Ui::TogglePause => {
+                    if root_window && self.renderer.rom_loaded() {
+                        self.paused = !self.paused;
+                        self.nes_event(EmulationEvent::Pause(self.paused));
+                    }
+                }
+                Ui::FrameAdvance => {
+                    if root_window && self.renderer.rom_loaded() {
+                        if self.paused {
+                            self.nes_event(EmulationEvent::DebugStep(DebugStep::Frame));
+                        } else {
                             self.paused = true;
                             self.nes_event(EmulationEvent::Pause(self.paused));
                         }
+                    }
+                }
+                Ui::LoadRom => {
+                    if self.renderer.rom_loaded() {
+                        self.paused = true;
+                        self.nes_event(EmulationEvent::Pause(self.paused));
+                    }
+                    // NOTE: Due to some platforms file dialogs blocking the event loop,
+                    // loading requires a round-trip in order for the above pause to
+                    // get processed.
+                    self.tx.nes_event(UiEvent::LoadRomDialog);
+                }
+                Ui::UnloadRom => {
+                    if self.renderer.rom_loaded() {
+                        self.nes_event(EmulationEvent::UnloadRom);
+                    }
+                }
+                Ui::LoadReplay => {
+                    if self.renderer.rom_loaded() {
+                        self.paused = true;
+                        self.nes_event(EmulationEvent::Pause(self.paused));
                         // NOTE: Due to some platforms file dialogs blocking the event loop,
                         // loading requires a round-trip in order for the above pause to
                         // get processed.
-                        self.tx.nes_event(UiEvent::LoadRomDialog);
+                        self.tx.nes_event(UiEvent::LoadReplayDialog);
                     }
-                    Ui::UnloadRom => {
+                }
+            },
+            Action::Menu(menu) if triggered => self.nes_event(RendererEvent::Menu(menu)),
+            Action::Feature(feature) if root_window => match feature {
+                Feature::ToggleReplayRecording if triggered => {
+                    if platform::supports(platform::Feature::Filesystem) {
                         if self.renderer.rom_loaded() {
-                            self.nes_event(EmulationEvent::UnloadRom);
+                            self.replay_recording = !self.replay_recording;
+                            self.nes_event(EmulationEvent::ReplayRecord(self.replay_recording));
                         }
+                    } else {
+                        self.renderer.add_message(
+                            MessageType::Warn,
+                            "Replay recordings are not supported yet on this platform.",
+                        );
                     }
-                    Ui::LoadReplay => {
+                }
+                Feature::ToggleAudioRecording if triggered => {
+                    if platform::supports(platform::Feature::Filesystem) {
                         if self.renderer.rom_loaded() {
-                            self.paused = true;
-                            self.nes_event(EmulationEvent::Pause(self.paused));
-                            // NOTE: Due to some platforms file dialogs blocking the event loop,
-                            // loading requires a round-trip in order for the above pause to
-                            // get processed.
-                            self.tx.nes_event(UiEvent::LoadReplayDialog);
+                            self.audio_recording = !self.audio_recording;
+                            self.nes_event(EmulationEvent::AudioRecord(self.audio_recording));
                         }
+                    } else {
+                        self.renderer.add_message(
+                            MessageType::Warn,
+                            "Audio recordings are not supported yet on this platform.",
+                        );
                     }
-                },
-                Action::Menu(menu) if released => self.nes_event(RendererEvent::Menu(menu)),
-                Action::Feature(feature) if root_window => match feature {
-                    Feature::ToggleReplayRecording if released => {
-                        if platform::supports(platform::Feature::Filesystem) {
-                            if self.renderer.rom_loaded() {
-                                self.replay_recording = !self.replay_recording;
-                                self.nes_event(EmulationEvent::ReplayRecord(self.replay_recording));
-                            }
-                        } else {
-                            self.renderer.add_message(
-                                MessageType::Warn,
-                                "Replay recordings are not supported yet on this platform.",
-                            );
+                }
+                Feature::ToggleMapDump if triggered => {
+                    if platform::supports(platform::Feature::Filesystem) {
+                        if self.renderer.rom_loaded() {
+                            self.map_dumping = !self.map_dumping;
+                            self.nes_event(EmulationEvent::MapDump(self.map_dumping));
                         }
+                    } else {
+                        self.renderer.add_message(
+                            MessageType::Warn,
+                            "Map dumping is not supported yet on this platform.",
+                        );
                     }
-                    Feature::ToggleAudioRecording if released => {
-                        if platform::supports(platform::Feature::Filesystem) {
-                            if self.renderer.rom_loaded() {
-                                self.audio_recording = !self.audio_recording;
-                                self.nes_event(EmulationEvent::AudioRecord(self.audio_recording));
-                            }
-                        } else {
-                            self.renderer.add_message(
-                                MessageType::Warn,
-                                "Audio recordings are not supported yet on this platform.",
-                            );
+                }
+                Feature::ToggleSpriteRip if triggered => {
+                    if platform::supports(platform::Feature::Filesystem) {
+                        if self.renderer.rom_loaded() {
+                            self.sprite_ripping = !self.sprite_ripping;
+                            self.nes_event(EmulationEvent::SpriteRip(self.sprite_ripping));
                         }
+                    } else {
+                        self.renderer.add_message(
+                            MessageType::Warn,
+                            "Sprite ripping is not supported yet on this platform.",
+                        );
                     }
-                    Feature::TakeScreenshot if released => {
-                        if platform::supports(platform::Feature::Filesystem) {
-                            if self.renderer.rom_loaded() {
-                                self.nes_event(EmulationEvent::Screenshot);
-                            }
-                        } else {
-                            self.renderer.add_message(
-                                MessageType::Warn,
-                                "Screenshots are not supported yet on this platform.",
-                            );
+                }
+                Feature::TakeScreenshot if triggered => {
+                    if platform::supports(platform::Feature::Filesystem) {
+                        if self.renderer.rom_loaded() {
+                            self.nes_event(EmulationEvent::Screenshot(
+                                self.cfg.emulation.screenshot.clone(),
+                            ));
                         }
+                    } else {
+                        self.renderer.add_message(
+                            MessageType::Warn,
+                            "Screenshots are not supported yet on this platform.",
+                        );
                     }
-                    Feature::VisualRewind => {
-                        if !self.rewinding {
-                            if repeat {
-                                self.rewinding = true;
-                                self.nes_event(EmulationEvent::Rewinding(self.rewinding));
-                            } else if released {
-                                self.nes_event(EmulationEvent::InstantRewind);
-                            }
-                        } else if released {
-                            self.rewinding = false;
+                }
+                Feature::VisualRewind => {
+                    if !self.rewinding {
+                        if repeat {
+                            self.rewinding = true;
                             self.nes_event(EmulationEvent::Rewinding(self.rewinding));
+                        } else if released {
+                            self.nes_event(EmulationEvent::InstantRewind);
                         }
+                    } else if released {
+                        self.rewinding = false;
+                        self.nes_event(EmulationEvent::Rewinding(self.rewinding));
                     }
-                    _ => (),
-                },
-                Action::Setting(setting) => match setting {
-                    Setting::ToggleFullscreen if released && root_window => {
-                        self.cfg.renderer.fullscreen = !self.cfg.renderer.fullscreen;
-                        self.renderer.set_fullscreen(self.cfg.renderer.fullscreen);
-                    }
-                    Setting::ToggleAudio if released => {
-                        self.cfg.audio.enabled = !self.cfg.audio.enabled;
-                        self.nes_event(ConfigEvent::AudioEnabled(self.cfg.audio.enabled));
-                    }
-                    Setting::ToggleMenubar if released => {
-                        self.cfg.renderer.show_menubar = !self.cfg.renderer.show_menubar;
-                        self.nes_event(RendererEvent::ShowMenubar(self.cfg.renderer.show_menubar));
-                    }
-                    Setting::IncrementScale if released => {
-                        let scale = self.cfg.renderer.scale;
-                        let new_scale = self.cfg.increment_scale();
-                        if scale != new_scale {
-                            self.nes_event(RendererEvent::ScaleChanged);
-                        }
-                    }
-                    Setting::DecrementScale if released => {
-                        let scale = self.cfg.renderer.scale;
-                        let new_scale = self.cfg.decrement_scale();
-                        if scale != new_scale {
-                            self.nes_event(RendererEvent::ScaleChanged);
-                        }
-                    }
-                    Setting::IncrementSpeed if released => {
-                        let speed = self.cfg.emulation.speed;
-                        let new_speed = self.cfg.increment_speed();
-                        if speed != new_speed {
-                            self.nes_event(ConfigEvent::Speed(self.cfg.emulation.speed));
-                            self.renderer.add_message(
-                                MessageType::Info,
-                                format!("Increased Emulation Speed to {new_speed}"),
-                            );
-                        }
+                }
+                _ => (),
+            },
+            Action::Setting(setting) => match setting {
+                Setting::ToggleFullscreen if triggered && root_window => {
+                    self.cfg.renderer.fullscreen = !self.cfg.renderer.fullscreen;
+                    self.renderer.set_fullscreen(self.cfg.renderer.fullscreen);
+                }
+                Setting::ToggleAudio if triggered => {
+                    self.cfg.audio.enabled = !self.cfg.audio.enabled;
+                    self.nes_event(ConfigEvent::AudioEnabled(self.cfg.audio.enabled));
+                }
+                Setting::ToggleMenubar if triggered => {
+                    self.cfg.renderer.show_menubar = !self.cfg.renderer.show_menubar;
+                    self.nes_event(RendererEvent::ShowMenubar(self.cfg.renderer.show_menubar));
+                }
+                Setting::ToggleAudioScope if triggered => {
+                    self.cfg.renderer.show_audio_scope = !self.cfg.renderer.show_audio_scope;
+                    self.nes_event(ConfigEvent::AudioScope(self.cfg.renderer.show_audio_scope));
+                }
+                Setting::ToggleFps if triggered => {
+                    self.cfg.renderer.show_perf_stats = !self.cfg.renderer.show_perf_stats;
+                    self.nes_event(ConfigEvent::PerfStats(self.cfg.renderer.show_perf_stats));
+                }
+                Setting::IncrementScale if triggered => {
+                    let scale = self.cfg.renderer.scale;
+                    let new_scale = self.cfg.increment_scale();
+                    if scale != new_scale {
+                        self.nes_event(RendererEvent::ScaleChanged);
                     }
-                    Setting::DecrementSpeed if released => {
-                        let speed = self.cfg.emulation.speed;
-                        let new_speed = self.cfg.decrement_speed();
-                        if speed != new_speed {
-                            self.nes_event(ConfigEvent::Speed(self.cfg.emulation.speed));
-                            self.renderer.add_message(
-                                MessageType::Info,
-                                format!("Decreased Emulation Speed to {new_speed}"),
-                            );
-                        }
+                }
+                Setting::DecrementScale if triggered => {
+                    let scale = self.cfg.renderer.scale;
+                    let new_scale = self.cfg.decrement_scale();
+                    if scale != new_scale {
+                        self.nes_event(RendererEvent::ScaleChanged);
                     }
-                    Setting::FastForward
-                        if !repeat && root_window && self.renderer.rom_loaded() =>
-                    {
-                        let new_speed = if released { 1.0 } else { 2.0 };
-                        let speed = self.cfg.emulation.speed;
-                        if speed != new_speed {
-                            self.cfg.emulation.speed = new_speed;
-                            self.nes_event(ConfigEvent::Speed(self.cfg.emulation.speed));
-                            if new_speed == 2.0 {
-                                self.renderer
-                                    .add_message(MessageType::Info, "Fast forwarding");
-                            }
-                        }
+                }
+                Setting::IncrementSpeed if triggered => {
+                    let speed = self.cfg.emulation.speed;
+                    let new_speed = self.cfg.increment_speed();
+                    if speed != new_speed {
+                        self.nes_event(ConfigEvent::Speed(self.cfg.emulation.speed));
+                        self.renderer.add_message(
+                            MessageType::Info,
+                            format!("Increased Emulation Speed to {new_speed}"),
+                        );
                     }
-                    _ => (),
-                },
-                Action::Deck(action) => match action {
-                    DeckAction::Reset(kind) if released => {
-                        self.nes_event(EmulationEvent::Reset(kind));
-                    }
-                    DeckAction::Joypad((player, button)) if !repeat && root_window => {
-                        self.nes_event(EmulationEvent::Joypad((player, button, state)));
-                    }
-                    // Handled by `gui` module
-                    DeckAction::ZapperAim(_)
-                    | DeckAction::ZapperAimOffscreen
-                    | DeckAction::ZapperTrigger => (),
-                    DeckAction::SetSaveSlot(slot) if released => {
-                        if platform::supports(platform::Feature::Filesystem) {
-                            if self.cfg.emulation.save_slot != slot {
-                                self.cfg.emulation.save_slot = slot;
-                                self.renderer.add_message(
-                                    MessageType::Info,
-                                    format!("Changed Save Slot to {slot}"),
-                                );
-                            }
-                        } else {
-                            self.renderer.add_message(
-                                MessageType::Warn,
-                                "Save states are not supported yet on this platform.",
-                            );
-                        }
+                }
+                Setting::DecrementSpeed if triggered => {
+                    let speed = self.cfg.emulation.speed;
+                    let new_speed = self.cfg.decrement_speed();
+                    if speed != new_speed {
+                        self.nes_event(ConfigEvent::Speed(self.cfg.emulation.speed));
+                        self.renderer.add_message(
+                            MessageType::Info,
+                            format!("Decreased Emulation Speed to {new_speed}"),
+                        );
                     }
-                    DeckAction::SaveState if released && root_window => {
-                        if platform::supports(platform::Feature::Filesystem) {
-                            self.nes_event(EmulationEvent::SaveState(self.cfg.emulation.save_slot));
-                        } else {
-                            self.renderer.add_message(
-                                MessageType::Warn,
-                                "Save states are not supported yet on this platform.",
-                            );
+                }
+                Setting::FastForward if root_window && self.renderer.rom_loaded() => {
+                    let fast_forwarding = if !self.cfg.emulation.fast_forward.toggle {
+                        !released
+                    } else if !released && !repeat {
+                        !self.fast_forwarding
+                    } else {
+                        self.fast_forwarding
+                    };
+                    if fast_forwarding != self.fast_forwarding {
+                        self.fast_forwarding = fast_forwarding;
+                        self.nes_event(EmulationEvent::FastForward(fast_forwarding));
+                        if fast_forwarding {
+                            self.renderer
+                                .add_message(MessageType::Info, "Fast forwarding");
                         }
                     }
-                    DeckAction::LoadState if released && root_window => {
-                        if platform::supports(platform::Feature::Filesystem) {
-                            self.nes_event(EmulationEvent::LoadState(self.cfg.emulation.save_slot));
-                        } else {
+                }
+                _ => (),
+            },
+            Action::Deck(action) => match action {
+                DeckAction::Reset(kind) if triggered => {
+                    self.nes_event(EmulationEvent::Reset(kind));
+                }
+                DeckAction::Joypad((player, button)) if root_window => {
+                    let button = self.cfg.renderer.orientation.remap(button);
+                    self.nes_event(EmulationEvent::Joypad((player, button, state)));
+                }
+                DeckAction::InsertCoin(slot) if triggered && root_window => {
+                    self.nes_event(EmulationEvent::InsertCoin(slot));
+                }
+                DeckAction::VsServiceButton if root_window => {
+                    self.nes_event(EmulationEvent::VsServiceButton(!released));
+                }
+                // Handled by `gui` module
+                DeckAction::ZapperAim(_)
+                | DeckAction::ZapperAimOffscreen
+                | DeckAction::ZapperTrigger => (),
+                DeckAction::SetSaveSlot(slot) if triggered => {
+                    if platform::supports(platform::Feature::Filesystem) {
+                        if self.cfg.emulation.save_slot != slot {
+                            self.cfg.emulation.save_slot = slot;
                             self.renderer.add_message(
-                                MessageType::Warn,
-                                "Save states are not supported yet on this platform.",
+                                MessageType::Info,
+                                format!("Changed Save Slot to {slot}"),
                             );
                         }
-                    }
-                    DeckAction::ToggleApuChannel(channel) if released => {
-                        self.cfg.deck.channels_enabled[channel as usize] =
-                            !self.cfg.deck.channels_enabled[channel as usize];
-                        self.nes_event(ConfigEvent::ApuChannelEnabled((
-                            channel,
-                            self.cfg.deck.channels_enabled[channel as usize],
-                        )));
-                    }
-                    DeckAction::MapperRevision(rev) if released => {
-                        self.cfg.deck.mapper_revisions.set(rev);
-                        self.nes_event(ConfigEvent::MapperRevisions(
-                            self.cfg.deck.mapper_revisions,
-                        ));
+                    } else {
                         self.renderer.add_message(
-                            MessageType::Info,
-                            format!("Changed Mapper Revision to {rev}"),
+                            MessageType::Warn,
+                            "Save states are not supported yet on this platform.",
                         );
                     }
-                    DeckAction::SetNesRegion(region) if released => {
-                        self.cfg.deck.region = region;
-                        self.nes_event(ConfigEvent::Region(self.cfg.deck.region));
+                }
+                DeckAction::SaveState if triggered && root_window => {
+                    if platform::supports(platform::Feature::Filesystem) {
+                        self.nes_event(EmulationEvent::SaveState(self.cfg.emulation.save_slot));
+                    } else {
                         self.renderer.add_message(
-                            MessageType::Info,
-                            format!("Changed NES Region to {region:?}"),
+                            MessageType::Warn,
+                            "Save states are not supported yet on this platform.",
                         );
                     }
-                    DeckAction::SetVideoFilter(filter) if released => {
-                        let filter = if self.cfg.deck.filter == filter {
-                            VideoFilter::Pixellate
-                        } else {
-                            filter
-                        };
-                        self.cfg.deck.filter = filter;
-                        self.nes_event(ConfigEvent::VideoFilter(filter));
-                    }
-                    _ => (),
-                },
-                Action::Debug(action) => match action {
-                    Debug::Toggle(kind) if released => {
+                }
+                DeckAction::LoadState if triggered && root_window => {
+                    if platform::supports(platform::Feature::Filesystem) {
+                        self.nes_event(EmulationEvent::LoadState(self.cfg.emulation.save_slot));
+                    } else {
                         self.renderer.add_message(
                             MessageType::Warn,
-                            format!("{kind:?} is not implemented yet"),
+                            "Save states are not supported yet on this platform.",
                         );
                     }
-                    Debug::Step(step) if (released | repeat) && root_window => {
-                        self.nes_event(EmulationEvent::DebugStep(step));
-                    }
-                    _ => (),
-                },
+                }
+                DeckAction::ToggleApuChannel(channel) if triggered => {
+                    self.cfg.deck.channels_enabled[channel as usize] =
+                        !self.cfg.deck.channels_enabled[channel as usize];
+                    self.nes_event(ConfigEvent::ApuChannelEnabled((
+                        channel,
+                        self.cfg.deck.channels_enabled[channel as usize],
+                    )));
+                }
+                DeckAction::MapperRevision(rev) if triggered => {
+                    self.cfg.deck.mapper_revisions.set(rev);
+                    self.nes_event(ConfigEvent::MapperRevisions(self.cfg.deck.mapper_revisions));
+                    self.renderer.add_message(
+                        MessageType::Info,
+                        format!("Changed Mapper Revision to {rev}"),
+                    );
+                }
+                DeckAction::SetNesRegion(region) if triggered => {
+                    self.cfg.deck.region = region;
+                    self.nes_event(ConfigEvent::Region(self.cfg.deck.region));
+                    self.renderer.add_message(
+                        MessageType::Info,
+                        format!("Changed NES Region to {region:?}"),
+                    );
+                }
+                DeckAction::FourPlayer(four_player) if triggered => {
+                    self.cfg.deck.four_player = four_player;
+                    self.nes_event(ConfigEvent::FourPlayer(four_player));
+                    self.renderer.add_message(
+                        MessageType::Info,
+                        format!("Changed Four Player to {four_player}"),
+                    );
+                }
+                DeckAction::SetVideoFilter(filter) if triggered => {
+                    let filter = if self.cfg.deck.filter == filter {
+                        VideoFilter::Pixellate
+                    } else {
+                        filter
+                    };
+                    self.cfg.deck.filter = filter;
+                    self.nes_event(ConfigEvent::VideoFilter(filter));
+                }
                 _ => (),
-            }
+            },
+            Action::Debug(action) => match action {
+                Debug::Toggle(kind) if triggered => {
+                    self.renderer.add_message(
+                        MessageType::Warn,
+                        format!("{kind:?} is not implemented yet"),
+                    );
+                }
+                Debug::Step(step) if triggered && root_window => {
+                    self.nes_event(EmulationEvent::DebugStep(step));
+                }
+                _ => (),
+            },
+            _ => (),
         }
     }
 }
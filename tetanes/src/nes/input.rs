@@ -14,7 +14,8 @@ use tetanes_core::{
     action::Action as DeckAction,
     apu::Channel,
     common::ResetKind,
-    input::{JoypadBtn, Player},
+    input::{expansion::FamilyKey, JoypadBtn, Player},
+    time::{Duration, Instant},
     video::VideoFilter,
 };
 use tracing::warn;
@@ -22,6 +23,7 @@ use uuid::Uuid;
 use winit::{
     event::{ElementState, MouseButton},
     keyboard::{KeyCode, ModifiersState},
+    window::WindowId,
 };
 
 macro_rules! action_binding {
@@ -29,6 +31,7 @@ macro_rules! action_binding {
         ActionBindings {
             action: $action.into(),
             bindings: $bindings,
+            repeat: None,
         }
     };
     ($action:expr => $modifiers:expr, $key:expr) => {
@@ -99,11 +102,71 @@ pub enum AxisDirection {
     Positive, // Right or Down
 }
 
+/// How an action triggers while its binding is held down.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub enum RepeatPolicy {
+    /// Trigger once when released.
+    #[default]
+    None,
+    /// Trigger when pressed, then every `interval_ms` once held for `delay_ms`.
+    DelayRate { delay_ms: u32, interval_ms: u32 },
+    /// Trigger when pressed, then every frame while held.
+    Continuous,
+}
+
+impl RepeatPolicy {
+    pub const DEFAULT_DELAY_RATE: Self = Self::DelayRate {
+        delay_ms: 400,
+        interval_ms: 50,
+    };
+    /// Interval between [`RepeatPolicy::Continuous`] repeats, roughly once per frame.
+    const CONTINUOUS_INTERVAL: Duration = Duration::from_micros(16_667);
+
+    pub const fn repeats(&self) -> bool {
+        !matches!(self, Self::None)
+    }
+
+    /// Returns the delay before the first repeat and the interval between each repeat after
+    /// that, or `None` if the action doesn't repeat.
+    #[must_use]
+    pub fn timing(&self) -> Option<(Duration, Duration)> {
+        match self {
+            Self::None => None,
+            Self::DelayRate {
+                delay_ms,
+                interval_ms,
+            } => Some((
+                Duration::from_millis((*delay_ms).into()),
+                Duration::from_millis((*interval_ms).max(1).into()),
+            )),
+            Self::Continuous => Some((Self::CONTINUOUS_INTERVAL, Self::CONTINUOUS_INTERVAL)),
+        }
+    }
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::DelayRate { .. } => "Delay + Rate",
+            Self::Continuous => "Continuous",
+        }
+    }
+}
+
+impl AsRef<str> for RepeatPolicy {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 #[must_use]
 pub struct ActionBindings {
     pub action: Action,
     pub bindings: [Option<Input>; 2],
+    /// Repeat policy overriding the action default. Ignored for actions that can't repeat.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat: Option<RepeatPolicy>,
 }
 
 impl ActionBindings {
@@ -111,6 +174,15 @@ impl ActionBindings {
         Self {
             action,
             bindings: Default::default(),
+            repeat: None,
+        }
+    }
+
+    /// Returns the repeat policy for the bound action.
+    pub fn repeat_policy(&self) -> RepeatPolicy {
+        match self.repeat {
+            Some(repeat) if self.action.is_repeatable() => repeat,
+            _ => self.action.default_repeat(),
         }
     }
 
@@ -129,6 +201,8 @@ impl ActionBindings {
             { Debug::Toggle(Debugger::Apu) => :SHIFT, KeyA },
             { Debug::Toggle(Debugger::Cpu) => :SHIFT, KeyD },
             { Debug::Toggle(Debugger::Ppu) => :SHIFT, KeyP },
+            { DeckAction::InsertCoin(1) => Digit3 },
+            { DeckAction::InsertCoin(2) => Digit4 },
             { DeckAction::LoadState => :CONTROL, KeyL },
             { DeckAction::Reset(ResetKind::Hard) => :CONTROL, KeyH },
             { DeckAction::Reset(ResetKind::Soft) => :CONTROL, KeyR },
@@ -148,13 +222,17 @@ impl ActionBindings {
             { DeckAction::ToggleApuChannel(Channel::Pulse1) => :SHIFT, Digit1 },
             { DeckAction::ToggleApuChannel(Channel::Pulse2) => :SHIFT, Digit2 },
             { DeckAction::ToggleApuChannel(Channel::Triangle) => :SHIFT, Digit3 },
+            { DeckAction::VsServiceButton => Digit7 },
             { Feature::InstantRewind => KeyR },
             { Feature::TakeScreenshot => F10 },
             { Feature::ToggleAudioRecording => :SHIFT, KeyR },
+            { Feature::ToggleMapDump => :SHIFT, KeyM },
+            { Feature::ToggleSpriteRip => :SHIFT, KeyS },
             { Feature::ToggleReplayRecording => :SHIFT, KeyV },
             { Feature::VisualRewind => KeyR },
             { Menu::About => F1 },
             { Menu::Keybinds => :CONTROL, KeyK; F3 },
+            { Menu::Menubar => F6 },
             { Menu::Preferences => :CONTROL, KeyP; F2 },
             { Menu::PerfStats => :CONTROL, KeyF },
             { Setting::DecrementScale => :SHIFT, Minus },
@@ -168,6 +246,7 @@ impl ActionBindings {
             { Ui::LoadRom => :CONTROL, KeyO; F3 },
             { Ui::Quit => :CONTROL, KeyQ },
             { Ui::TogglePause => Escape },
+            { Ui::FrameAdvance => Backslash },
         ));
         bindings.extend(mouse_map!(
             { DeckAction::ZapperTrigger => MouseButton::Left },
@@ -222,7 +301,6 @@ impl ActionBindings {
                 { (Player::Two, JoypadBtn::Select) => Digit9 },
                 { (Player::Two, JoypadBtn::Start) => Digit8 },
             ),
-            #[cfg(debug_assertions)]
             Player::Three => shortcut_map!(
                 { (Player::Three, JoypadBtn::A) => KeyV },
                 { (Player::Three, JoypadBtn::B) => KeyB },
@@ -233,7 +311,16 @@ impl ActionBindings {
                 { (Player::Three, JoypadBtn::Select) => Digit6 },
                 { (Player::Three, JoypadBtn::Start) => Digit5 },
             ),
-            _ => Vec::new(),
+            Player::Four => shortcut_map!(
+                { (Player::Four, JoypadBtn::A) => NumpadDecimal },
+                { (Player::Four, JoypadBtn::B) => Numpad0 },
+                { (Player::Four, JoypadBtn::Up) => Numpad8 },
+                { (Player::Four, JoypadBtn::Down) => Numpad5 },
+                { (Player::Four, JoypadBtn::Left) => Numpad4 },
+                { (Player::Four, JoypadBtn::Right) => Numpad6 },
+                { (Player::Four, JoypadBtn::Select) => NumpadSubtract },
+                { (Player::Four, JoypadBtn::Start) => NumpadAdd },
+            ),
         };
 
         for binding in additional_bindings {
@@ -251,38 +338,160 @@ impl ActionBindings {
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct InputBindings(HashMap<Input, Action>);
+pub struct InputBindings {
+    actions: HashMap<Input, Action>,
+    repeat: HashMap<Action, RepeatPolicy>,
+}
 
 impl InputBindings {
     pub fn from_input_config(config: &InputConfig) -> Self {
-        let mut map = HashMap::with_capacity(256);
+        let mut actions = HashMap::with_capacity(256);
+        let mut repeat = HashMap::new();
         for bind in config
             .shortcuts
             .iter()
             .chain(config.joypad_bindings.iter().flatten())
         {
             for input in bind.bindings.into_iter().flatten() {
-                map.insert(input, bind.action);
+                actions.insert(input, bind.action);
             }
+            repeat.insert(bind.action, bind.repeat_policy());
         }
-        map.shrink_to_fit();
-        Self(map)
+        actions.shrink_to_fit();
+        Self { actions, repeat }
+    }
+
+    /// Returns the repeat policy for `action`.
+    pub fn repeat_policy(&self, action: Action) -> RepeatPolicy {
+        self.repeat
+            .get(&action)
+            .copied()
+            .unwrap_or_else(|| action.default_repeat())
     }
 }
 
 impl Deref for InputBindings {
     type Target = HashMap<Input, Action>;
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.actions
     }
 }
 
 impl DerefMut for InputBindings {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.actions
     }
 }
 
+/// Returns the Family BASIC Keyboard key for a host keyboard key, for passing keys through to
+/// the emulated keyboard while it's connected.
+pub const fn family_key(key: KeyCode) -> Option<FamilyKey> {
+    Some(match key {
+        KeyCode::BracketRight => FamilyKey::RightBracket,
+        KeyCode::BracketLeft => FamilyKey::LeftBracket,
+        KeyCode::Enter | KeyCode::NumpadEnter => FamilyKey::Return,
+        KeyCode::F8 => FamilyKey::F8,
+        KeyCode::End | KeyCode::Pause => FamilyKey::Stop,
+        KeyCode::IntlYen | KeyCode::Backslash => FamilyKey::Yen,
+        KeyCode::ShiftRight => FamilyKey::RightShift,
+        KeyCode::AltRight | KeyCode::KanaMode => FamilyKey::Kana,
+        KeyCode::Semicolon => FamilyKey::Semicolon,
+        KeyCode::Quote => FamilyKey::Colon,
+        KeyCode::Backquote => FamilyKey::At,
+        KeyCode::F7 => FamilyKey::F7,
+        KeyCode::Equal => FamilyKey::Caret,
+        KeyCode::Minus => FamilyKey::Minus,
+        KeyCode::Slash => FamilyKey::Slash,
+        KeyCode::IntlRo => FamilyKey::Underscore,
+        KeyCode::KeyK => FamilyKey::K,
+        KeyCode::KeyL => FamilyKey::L,
+        KeyCode::KeyO => FamilyKey::O,
+        KeyCode::F6 => FamilyKey::F6,
+        KeyCode::Digit0 => FamilyKey::Num0,
+        KeyCode::KeyP => FamilyKey::P,
+        KeyCode::Comma => FamilyKey::Comma,
+        KeyCode::Period => FamilyKey::Period,
+        KeyCode::KeyJ => FamilyKey::J,
+        KeyCode::KeyU => FamilyKey::U,
+        KeyCode::KeyI => FamilyKey::I,
+        KeyCode::F5 => FamilyKey::F5,
+        KeyCode::Digit8 => FamilyKey::Num8,
+        KeyCode::Digit9 => FamilyKey::Num9,
+        KeyCode::KeyN => FamilyKey::N,
+        KeyCode::KeyM => FamilyKey::M,
+        KeyCode::KeyH => FamilyKey::H,
+        KeyCode::KeyG => FamilyKey::G,
+        KeyCode::KeyY => FamilyKey::Y,
+        KeyCode::F4 => FamilyKey::F4,
+        KeyCode::Digit6 => FamilyKey::Num6,
+        KeyCode::Digit7 => FamilyKey::Num7,
+        KeyCode::KeyV => FamilyKey::V,
+        KeyCode::KeyB => FamilyKey::B,
+        KeyCode::KeyD => FamilyKey::D,
+        KeyCode::KeyR => FamilyKey::R,
+        KeyCode::KeyT => FamilyKey::T,
+        KeyCode::F3 => FamilyKey::F3,
+        KeyCode::Digit4 => FamilyKey::Num4,
+        KeyCode::Digit5 => FamilyKey::Num5,
+        KeyCode::KeyC => FamilyKey::C,
+        KeyCode::KeyF => FamilyKey::F,
+        KeyCode::KeyA => FamilyKey::A,
+        KeyCode::KeyS => FamilyKey::S,
+        KeyCode::KeyW => FamilyKey::W,
+        KeyCode::F2 => FamilyKey::F2,
+        KeyCode::Digit3 => FamilyKey::Num3,
+        KeyCode::KeyE => FamilyKey::E,
+        KeyCode::KeyZ => FamilyKey::Z,
+        KeyCode::KeyX => FamilyKey::X,
+        KeyCode::Tab => FamilyKey::Ctrl,
+        KeyCode::KeyQ => FamilyKey::Q,
+        KeyCode::Escape => FamilyKey::Escape,
+        KeyCode::F1 => FamilyKey::F1,
+        KeyCode::Digit2 => FamilyKey::Num2,
+        KeyCode::Digit1 => FamilyKey::Num1,
+        KeyCode::AltLeft => FamilyKey::Grph,
+        KeyCode::ShiftLeft => FamilyKey::LeftShift,
+        KeyCode::ArrowLeft => FamilyKey::Left,
+        KeyCode::ArrowRight => FamilyKey::Right,
+        KeyCode::ArrowUp => FamilyKey::Up,
+        KeyCode::Home => FamilyKey::ClrHome,
+        KeyCode::Insert => FamilyKey::Ins,
+        KeyCode::Delete | KeyCode::Backspace => FamilyKey::Del,
+        KeyCode::Space => FamilyKey::Space,
+        KeyCode::ArrowDown => FamilyKey::Down,
+        _ => return None,
+    })
+}
+
+/// Returns the Family Trainer Mat button for a host keyboard key, laid out as a grid of three
+/// rows of four keys.
+pub const fn power_pad_button(key: KeyCode) -> Option<usize> {
+    Some(match key {
+        KeyCode::KeyQ => 0,
+        KeyCode::KeyW => 1,
+        KeyCode::KeyE => 2,
+        KeyCode::KeyR => 3,
+        KeyCode::KeyA => 4,
+        KeyCode::KeyS => 5,
+        KeyCode::KeyD => 6,
+        KeyCode::KeyF => 7,
+        KeyCode::KeyZ => 8,
+        KeyCode::KeyX => 9,
+        KeyCode::KeyC => 10,
+        KeyCode::KeyV => 11,
+        _ => return None,
+    })
+}
+
+/// An action whose binding is held down, triggering again according to its [`RepeatPolicy`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct HeldAction {
+    pub window_id: WindowId,
+    pub interval: Duration,
+    pub next_repeat: Instant,
+}
+
 /// Represents gamepad input state.
 #[derive(Default, Debug)]
 pub struct Gamepads {
@@ -400,12 +609,20 @@ impl Gamepads {
         })
     }
 
+    pub fn gamepad_name(&self, id: gilrs::GamepadId) -> Option<String> {
+        self.gamepad(id).map(|g| g.name().to_string())
+    }
+
     pub fn gamepad_name_by_uuid(&self, uuid: &Uuid) -> Option<String> {
         self.gamepad_by_uuid(uuid).map(|g| g.name().to_string())
     }
 
+    /// Returns the UUID assigned to a gamepad when it connected. Gamepads that don't report a
+    /// UUID or vendor and product IDs, which is common on the web, are given a random UUID, so it
+    /// has to be looked up rather than recreated. Still returns the UUID of a gamepad that just
+    /// disconnected until [`Gamepads::disconnect`] is called.
     pub fn gamepad_uuid(&self, id: gilrs::GamepadId) -> Option<Uuid> {
-        self.connected_gamepad(id).map(|g| Self::create_uuid(&g))
+        self.connected.get(&id).copied()
     }
 
     pub fn is_connected(&self, uuid: &Uuid) -> bool {
@@ -421,7 +638,7 @@ impl Gamepads {
     }
 
     pub fn next_event(&mut self) -> Option<gilrs::Event> {
-        self.events.pop_back()
+        self.events.pop_front()
     }
 
     pub fn connect(&mut self, gamepad_id: gilrs::GamepadId) {
@@ -433,11 +650,10 @@ impl Gamepads {
     }
 
     pub fn disconnect(&mut self, gamepad_id: gilrs::GamepadId) {
-        if let Some(gamepad) = self.gamepad(gamepad_id) {
-            let uuid = Self::create_uuid(&gamepad);
-            tracing::debug!("gamepad disconnected: {} ({uuid})", gamepad.name());
+        if let Some(uuid) = self.connected.remove(&gamepad_id) {
+            let name = self.gamepad_name(gamepad_id).unwrap_or_default();
+            tracing::debug!("gamepad disconnected: {name} ({uuid})");
         }
-        self.connected.remove(&gamepad_id);
     }
 
     pub fn create_uuid(gamepad: &gilrs::Gamepad<'_>) -> Uuid {
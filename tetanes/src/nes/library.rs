@@ -0,0 +1,390 @@
+//! ROM library that scans directories for ROMs, identifies them using a No-Intro style game
+//! database, and remembers when each was last played.
+//!
+//! The library is cached in the data directory along with a thumbnail of the last frame played
+//! for each ROM, so ROMs are only re-hashed when they change.
+
+use crate::nes::{config::Config, event::NesEvent};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+use tetanes_core::{
+    cart::Cart,
+    common::{NesRegion, Regional},
+    control_deck::LoadedRom,
+    fs,
+    mem::RamState,
+};
+use tracing::error;
+use winit::event_loop::EventLoopProxy;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::nes::{
+    event::{RendererEvent, SendNesEvent, UiEvent},
+    renderer::gui::MessageType,
+};
+
+const FILENAME: &str = "library.json";
+const THUMBNAIL_DIR: &str = "thumbnails";
+
+/// Path to the thumbnail of the last frame played for a ROM matching `crc32`.
+#[must_use]
+pub fn thumbnail_path(crc32: u32) -> Option<PathBuf> {
+    Config::default_data_dir().map(|dir| {
+        dir.join(THUMBNAIL_DIR)
+            .join(format!("{crc32:08X}"))
+            .with_extension("png")
+    })
+}
+
+/// A ROM in the library.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub struct LibraryEntry {
+    pub path: PathBuf,
+    /// Title from the game database, or the filename if not found.
+    pub title: String,
+    /// CRC32 of the PRG-ROM and CHR-ROM, excluding the header.
+    pub crc32: u32,
+    pub mapper_num: u16,
+    pub region: NesRegion,
+    /// File size and modification time when the ROM was hashed, used to skip unchanged ROMs.
+    pub size: u64,
+    pub modified: Option<u64>,
+    /// Unix timestamp of when the ROM was last played, if ever.
+    pub last_played: Option<i64>,
+}
+
+impl LibraryEntry {
+    /// Identify the ROM at `path`, looking up its title in `database`.
+    fn from_path(path: PathBuf, database: &GameDatabase) -> anyhow::Result<Self> {
+        let data = std::fs::read(&path).with_context(|| format!("failed to read rom {path:?}"))?;
+        let cart = Cart::from_rom(
+            fs::filename(&path),
+            &mut data.as_slice(),
+            RamState::AllZeros,
+        )?;
+        let crc32 = cart.crc32();
+        let title = database.title(crc32);
+        let region = title
+            .and_then(title_region)
+            .unwrap_or_else(|| cart.region());
+        let (size, modified) = file_stamp(&path);
+        Ok(Self {
+            title: title.map_or_else(|| file_title(&path), ToString::to_string),
+            crc32,
+            mapper_num: cart.header().mapper_num,
+            region,
+            size,
+            modified,
+            last_played: None,
+            path,
+        })
+    }
+
+    /// Whether the ROM file hasn't changed since it was hashed.
+    fn is_current(&self) -> bool {
+        file_stamp(&self.path) == (self.size, self.modified)
+    }
+}
+
+/// ROMs found in the configured library directories, along with any ROMs played from elsewhere.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+pub struct Library {
+    /// Sorted by title.
+    pub entries: Vec<LibraryEntry>,
+}
+
+impl Library {
+    #[must_use]
+    pub fn path() -> Option<PathBuf> {
+        Config::default_data_dir().map(|dir| dir.join(FILENAME))
+    }
+
+    /// Load the cached library, or an empty library if there isn't one.
+    pub fn load() -> Self {
+        Self::path()
+            .filter(|path| fs::exists(path))
+            .and_then(|path| {
+                fs::load_raw(&path)
+                    .context("failed to load library")
+                    .and_then(|data| Ok(serde_json::from_slice::<Self>(&data)?))
+                    .map_err(|err| error!("invalid library {path:?}: {err:?}"))
+                    .ok()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        if let Some(path) = Self::path() {
+            let data = serde_json::to_vec(self).context("failed to serialize library")?;
+            fs::save_raw(path, &data).context("failed to save library")?;
+        }
+        Ok(())
+    }
+
+    /// Played entries, most recent first.
+    pub fn recently_played(&self) -> impl Iterator<Item = &LibraryEntry> {
+        let mut played = self
+            .entries
+            .iter()
+            .filter(|entry| entry.last_played.is_some())
+            .collect::<Vec<_>>();
+        played.sort_by_key(|entry| std::cmp::Reverse(entry.last_played));
+        played.into_iter()
+    }
+
+    /// Record that the ROM at `path` was just played, adding it to the library if needed.
+    pub fn played(&mut self, path: &Path, rom: &LoadedRom) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let now = chrono::Local::now().timestamp();
+        match self.entries.iter_mut().find(|entry| entry.path == path) {
+            Some(entry) => entry.last_played = Some(now),
+            None => {
+                let (size, modified) = file_stamp(&path);
+                self.entries.push(LibraryEntry {
+                    title: file_title(&path),
+                    crc32: rom.crc32,
+                    mapper_num: rom.header.mapper_num,
+                    region: rom.region,
+                    size,
+                    modified,
+                    last_played: Some(now),
+                    path,
+                });
+                self.sort();
+            }
+        }
+        if let Err(err) = self.save() {
+            error!("{err:?}");
+        }
+    }
+
+    /// Scan `dirs` for ROMs, identifying new or changed ROMs using the game database at
+    /// `database`. Played ROMs outside of `dirs` are kept as long as they still exist.
+    pub fn scan(&self, dirs: &[PathBuf], database: Option<&Path>) -> anyhow::Result<Self> {
+        let database = database
+            .map(GameDatabase::load)
+            .transpose()?
+            .unwrap_or_default();
+        let mut previous = self
+            .entries
+            .iter()
+            .map(|entry| (entry.path.clone(), entry))
+            .collect::<HashMap<_, _>>();
+
+        let mut entries = Vec::new();
+        for dir in dirs {
+            for path in find_roms(dir)? {
+                let existing = previous.remove(&path);
+                match existing.filter(|entry| entry.is_current()) {
+                    Some(entry) => entries.push(LibraryEntry {
+                        title: database
+                            .title(entry.crc32)
+                            .map_or_else(|| entry.title.clone(), ToString::to_string),
+                        ..entry.clone()
+                    }),
+                    None => match LibraryEntry::from_path(path, &database) {
+                        Ok(entry) => entries.push(LibraryEntry {
+                            last_played: existing.and_then(|entry| entry.last_played),
+                            ..entry
+                        }),
+                        Err(err) => error!("skipping rom: {err:?}"),
+                    },
+                }
+            }
+        }
+        entries.extend(
+            previous
+                .into_values()
+                .filter(|entry| entry.last_played.is_some() && entry.path.exists())
+                .cloned(),
+        );
+
+        let mut library = Self { entries };
+        library.sort();
+        Ok(library)
+    }
+
+    /// Scan library directories in the background, saving the updated library and sending it to
+    /// the renderer once finished.
+    pub fn spawn_scan(&self, cfg: &Config, tx: EventLoopProxy<NesEvent>) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let library = self.clone();
+            let dirs = cfg.renderer.library_dirs.clone();
+            let database = cfg.renderer.game_database.clone();
+            let res = std::thread::Builder::new()
+                .name("library_scan".into())
+                .spawn(move || match library.scan(&dirs, database.as_deref()) {
+                    Ok(library) => {
+                        tracing::info!("found {} roms in library", library.entries.len());
+                        if let Err(err) = library.save() {
+                            error!("{err:?}");
+                        }
+                        tx.nes_event(RendererEvent::LibraryScanned(library));
+                    }
+                    Err(err) => {
+                        error!("failed to scan library: {err:?}");
+                        tx.nes_event(UiEvent::Message((
+                            MessageType::Error,
+                            "Failed to scan ROM library.".to_string(),
+                        )));
+                        tx.nes_event(RendererEvent::LibraryScanned(library));
+                    }
+                });
+            if let Err(err) = res {
+                error!("failed to spawn library scan thread: {err:?}");
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = (cfg, tx);
+    }
+
+    fn sort(&mut self) {
+        self.entries.sort_by(|a, b| {
+            a.title
+                .to_lowercase()
+                .cmp(&b.title.to_lowercase())
+                .then_with(|| a.path.cmp(&b.path))
+        });
+    }
+}
+
+/// Game titles from a No-Intro style Logiqx XML DAT file, keyed by the CRC32 of each ROM without
+/// its header, which matches [`LoadedRom::crc32`].
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct GameDatabase {
+    titles: HashMap<u32, String>,
+}
+
+impl GameDatabase {
+    /// Load a DAT file from `path`.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let xml = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read game database {path:?}"))?;
+        let database = Self::parse(&xml);
+        anyhow::ensure!(
+            !database.titles.is_empty(),
+            "no games found in game database {path:?}"
+        );
+        Ok(database)
+    }
+
+    /// Parse the `<game>` entries of a DAT file, ignoring anything else.
+    pub fn parse(xml: &str) -> Self {
+        let mut titles = HashMap::new();
+        let mut games = xml;
+        while let Some(start) = games.find("<game ") {
+            games = &games[start..];
+            let end = games.find("</game>").unwrap_or(games.len());
+            let game = &games[..end];
+            if let Some(name) = attribute(game, "name") {
+                let name = unescape(name);
+                for (start, _) in game.match_indices("<rom ") {
+                    if let Some(crc32) = attribute(&game[start..], "crc")
+                        .and_then(|crc| u32::from_str_radix(crc, 16).ok())
+                    {
+                        titles.insert(crc32, name.clone());
+                    }
+                }
+            }
+            games = &games[end..];
+        }
+        Self { titles }
+    }
+
+    /// Title of the game matching `crc32`, if any.
+    #[must_use]
+    pub fn title(&self, crc32: u32) -> Option<&str> {
+        self.titles.get(&crc32).map(String::as_str)
+    }
+}
+
+/// Region from the tags in a No-Intro title, e.g. `Super Mario Bros. (World)` or
+/// `Tetris (Europe)`.
+#[must_use]
+pub fn title_region(title: &str) -> Option<NesRegion> {
+    title
+        .split(['(', ')'])
+        .skip(1)
+        .step_by(2)
+        .flat_map(|tags| tags.split(',').map(str::trim))
+        .find_map(|tag| match tag {
+            "USA" | "Japan" | "Korea" | "Canada" | "Brazil" | "Asia" | "World" => {
+                Some(NesRegion::Ntsc)
+            }
+            "Europe" | "Australia" | "Germany" | "France" | "Spain" | "Italy" | "Sweden"
+            | "Netherlands" | "UK" => Some(NesRegion::Pal),
+            "Russia" => Some(NesRegion::Dendy),
+            _ => None,
+        })
+}
+
+/// Value of attribute `name` in the tag starting `tag`.
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+    let key = format!(" {name}=\"");
+    let start = tag.find(&key)? + key.len();
+    let len = tag[start..].find('"')?;
+    Some(&tag[start..start + len])
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+fn file_title(path: &Path) -> String {
+    path.file_stem().map_or_else(
+        || fs::filename(path).to_string(),
+        |stem| stem.to_string_lossy().into(),
+    )
+}
+
+/// File size and modification time in seconds since the Unix epoch.
+fn file_stamp(path: &Path) -> (u64, Option<u64>) {
+    std::fs::metadata(path).map_or((0, None), |metadata| {
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|modified| modified.as_secs());
+        (metadata.len(), modified)
+    })
+}
+
+/// Recursively find `.nes` files in `dir`.
+fn find_roms(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let dir = dir
+        .canonicalize()
+        .with_context(|| format!("failed to find library directory {dir:?}"))?;
+    let mut roms = Vec::new();
+    let mut dirs = vec![dir];
+    while let Some(dir) = dirs.pop() {
+        for entry in dir
+            .read_dir()
+            .with_context(|| format!("failed to read directory {dir:?}"))?
+        {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("nes"))
+            {
+                roms.push(path);
+            }
+        }
+    }
+    Ok(roms)
+}
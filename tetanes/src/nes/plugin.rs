@@ -0,0 +1,426 @@
+//! Frontend plugins loaded from dynamic libraries with a stable C ABI.
+//!
+//! Plugins ship niche features, like speedrun timers or LiveSplit integration, without changes
+//! to `TetaNES` itself. Each plugin is a dynamic library (`.so`, `.dylib` or `.dll`) in the
+//! `plugins` directory of the configuration directory exporting:
+//!
+//! ```c
+//! bool tetanes_plugin_init(const TetanesHostApi *api, TetanesHost *host, TetanesPlugin *plugin);
+//! ```
+//!
+//! The plugin fills in `plugin` with its name and callbacks and returns `true` to be loaded. From
+//! then on, it receives [`PluginEvent`]s on the emulation thread and can read memory, register
+//! menu items, show an overlay or display messages through `api`. Plugins can also modify the
+//! palette used to render each scanline for raster color effects. The matching header is
+//! `include/tetanes_plugin.h`.
+//!
+//! `TETANES_PLUGIN_API_VERSION` is only incremented for breaking changes. New host functions are
+//! appended to `TetanesHostApi` and new callbacks to `TetanesPlugin`, so plugins built against an
+//! older version keep working. Plugins reporting a different version are unloaded without calling
+//! any of their callbacks, including `destroy`.
+
+#![allow(unsafe_code)]
+
+use crate::nes::config::Config;
+use std::{
+    ffi::{c_char, c_void, CStr, CString},
+    path::PathBuf,
+};
+use tetanes_core::{
+    control_deck::ControlDeck,
+    mem::{Access, Mem},
+    ppu::hook::ScanlineHook,
+};
+use tracing::{error, info, warn};
+
+/// Version of the plugin ABI.
+pub const API_VERSION: u32 = 1;
+/// Directory plugins are loaded from, within the configuration directory.
+pub const PLUGIN_DIR: &str = "plugins";
+
+/// A ROM was loaded. `rom_name` is set.
+pub const EVENT_ROM_LOADED: u32 = 0;
+/// The ROM was unloaded.
+pub const EVENT_ROM_UNLOADED: u32 = 1;
+/// A frame was emulated. `frame` is set.
+pub const EVENT_FRAME: u32 = 2;
+/// The console was reset or power cycled.
+pub const EVENT_RESET: u32 = 3;
+/// Emulation was paused.
+pub const EVENT_PAUSED: u32 = 4;
+/// Emulation was resumed.
+pub const EVENT_RESUMED: u32 = 5;
+
+/// Event sent to plugins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub enum PluginEvent {
+    RomLoaded(String),
+    RomUnloaded,
+    Frame(u32),
+    Reset,
+    Paused(bool),
+}
+
+/// Menu items and overlay registered by a plugin, shown by the UI.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub struct PluginUi {
+    pub name: String,
+    /// Menu item ids and labels, in order of registration.
+    pub menu_items: Vec<(u32, String)>,
+    /// Text drawn over the NES frame.
+    pub overlay: Option<String>,
+}
+
+/// C representation of a [`PluginEvent`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct RawEvent {
+    pub kind: u32,
+    pub frame: u32,
+    pub rom_name: *const c_char,
+}
+
+/// Host functions available to plugins, passed to `tetanes_plugin_init`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct HostApi {
+    pub api_version: u32,
+    /// Read a byte from the CPU address space without side effects.
+    pub peek: extern "C" fn(host: *mut Host<'_>, addr: u16) -> u8,
+    /// Current frame number.
+    pub frame_number: extern "C" fn(host: *mut Host<'_>) -> u32,
+    /// Add a menu item, or rename it if `id` is already registered.
+    pub add_menu_item: extern "C" fn(host: *mut Host<'_>, id: u32, label: *const c_char),
+    /// Set the overlay text drawn over the NES frame. Null or empty clears it.
+    pub set_overlay: extern "C" fn(host: *mut Host<'_>, text: *const c_char),
+    /// Show a message notification.
+    pub show_message: extern "C" fn(host: *mut Host<'_>, text: *const c_char),
+}
+
+/// Plugin description and callbacks, filled in by `tetanes_plugin_init`. Callbacks are optional.
+#[repr(C)]
+#[derive(Debug)]
+pub struct RawPlugin {
+    /// [`API_VERSION`] the plugin was built against.
+    pub api_version: u32,
+    /// Plugin name, valid until `destroy` is called.
+    pub name: *const c_char,
+    /// Plugin state passed to every callback.
+    pub user_data: *mut c_void,
+    pub on_event:
+        Option<extern "C" fn(user_data: *mut c_void, host: *mut Host<'_>, event: *const RawEvent)>,
+    pub on_menu_item: Option<extern "C" fn(user_data: *mut c_void, host: *mut Host<'_>, id: u32)>,
+    pub destroy: Option<extern "C" fn(user_data: *mut c_void)>,
+    /// Called at the start of each visible scanline with the 32 byte palette used to render it,
+    /// which can be modified. Runs mid-frame, so no host functions are available.
+    pub on_scanline: Option<extern "C" fn(user_data: *mut c_void, scanline: u32, palette: *mut u8)>,
+}
+
+type InitFn = unsafe extern "C" fn(*const HostApi, *mut Host<'_>, *mut RawPlugin) -> bool;
+
+/// Host state passed to plugin callbacks. Only valid for the duration of a callback.
+#[derive(Debug)]
+pub struct Host<'a> {
+    deck: &'a ControlDeck,
+    ui: &'a mut PluginUi,
+    messages: &'a mut Vec<String>,
+    ui_changed: bool,
+}
+
+/// Convert a plugin string, returning `None` for null pointers.
+fn from_c_str(text: *const c_char) -> Option<String> {
+    // SAFETY: Plugins are required to pass null or a valid, nul-terminated string.
+    (!text.is_null()).then(|| {
+        unsafe { CStr::from_ptr(text) }
+            .to_string_lossy()
+            .into_owned()
+    })
+}
+
+extern "C" fn host_peek(host: *mut Host<'_>, addr: u16) -> u8 {
+    // SAFETY: `host` is only passed to plugins during callbacks, while it's valid.
+    unsafe { host.as_ref() }.map_or(0, |host| host.deck.bus().peek(addr, Access::Dummy))
+}
+
+extern "C" fn host_frame_number(host: *mut Host<'_>) -> u32 {
+    // SAFETY: `host` is only passed to plugins during callbacks, while it's valid.
+    unsafe { host.as_ref() }.map_or(0, |host| host.deck.frame_number())
+}
+
+extern "C" fn host_add_menu_item(host: *mut Host<'_>, id: u32, label: *const c_char) {
+    // SAFETY: `host` is only passed to plugins during callbacks, while it's valid.
+    let (Some(host), Some(label)) = (unsafe { host.as_mut() }, from_c_str(label)) else {
+        return;
+    };
+    match host.ui.menu_items.iter_mut().find(|(item, _)| *item == id) {
+        Some((_, existing)) => *existing = label,
+        None => host.ui.menu_items.push((id, label)),
+    }
+    host.ui_changed = true;
+}
+
+extern "C" fn host_set_overlay(host: *mut Host<'_>, text: *const c_char) {
+    // SAFETY: `host` is only passed to plugins during callbacks, while it's valid.
+    let Some(host) = (unsafe { host.as_mut() }) else {
+        return;
+    };
+    let overlay = from_c_str(text).filter(|text| !text.is_empty());
+    if host.ui.overlay != overlay {
+        host.ui.overlay = overlay;
+        host.ui_changed = true;
+    }
+}
+
+extern "C" fn host_show_message(host: *mut Host<'_>, text: *const c_char) {
+    // SAFETY: `host` is only passed to plugins during callbacks, while it's valid.
+    if let (Some(host), Some(text)) = (unsafe { host.as_mut() }, from_c_str(text)) {
+        host.messages.push(text);
+    }
+}
+
+static HOST_API: HostApi = HostApi {
+    api_version: API_VERSION,
+    peek: host_peek,
+    frame_number: host_frame_number,
+    add_menu_item: host_add_menu_item,
+    set_overlay: host_set_overlay,
+    show_message: host_show_message,
+};
+
+/// Plugin `on_scanline` callback, run by the PPU.
+#[derive(Debug, Copy, Clone)]
+struct ScanlineCallback {
+    on_scanline: extern "C" fn(user_data: *mut c_void, scanline: u32, palette: *mut u8),
+    user_data: *mut c_void,
+}
+
+// SAFETY: The scanline hook is only run while clocking the `ControlDeck` on the emulation thread,
+// the same thread every other plugin callback runs on, and is dropped along with the
+// `ControlDeck` before plugins are destroyed.
+unsafe impl Send for ScanlineCallback {}
+// SAFETY: See above.
+unsafe impl Sync for ScanlineCallback {}
+
+#[must_use]
+struct Plugin {
+    raw: RawPlugin,
+    ui: PluginUi,
+    // Dropped after `raw` is destroyed, since its callbacks live in the library
+    #[cfg(not(target_arch = "wasm32"))]
+    _library: libloading::Library,
+}
+
+impl Drop for Plugin {
+    fn drop(&mut self) {
+        if let Some(destroy) = self.raw.destroy {
+            destroy(self.raw.user_data);
+        }
+    }
+}
+
+/// Loaded plugins.
+#[derive(Default)]
+#[must_use]
+pub struct Plugins {
+    plugins: Vec<Plugin>,
+    messages: Vec<String>,
+    ui_changed: bool,
+}
+
+impl std::fmt::Debug for Plugins {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Plugins")
+            .field(
+                "plugins",
+                &self
+                    .plugins
+                    .iter()
+                    .map(|plugin| &plugin.ui.name)
+                    .collect::<Vec<_>>(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl Plugins {
+    /// Directory plugins are loaded from.
+    #[must_use]
+    pub fn dir() -> Option<PathBuf> {
+        Config::default_config_dir().map(|dir| dir.join(PLUGIN_DIR))
+    }
+
+    /// Load every plugin in [`Plugins::dir`], skipping any that fail to load.
+    pub fn load(deck: &ControlDeck) -> Self {
+        let mut plugins = Self::default();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let Some(dir) = Self::dir() else {
+                return plugins;
+            };
+            let Ok(entries) = dir.read_dir() else {
+                return plugins;
+            };
+            let mut paths = entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION)
+                })
+                .collect::<Vec<_>>();
+            paths.sort();
+            for path in paths {
+                match plugins.load_plugin(deck, &path) {
+                    Ok(()) => info!("loaded plugin {path:?}"),
+                    Err(err) => error!("failed to load plugin {path:?}: {err:?}"),
+                }
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = deck;
+        plugins
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_plugin(&mut self, deck: &ControlDeck, path: &std::path::Path) -> anyhow::Result<()> {
+        // SAFETY: Loading a library runs its initialization routines. Plugins are trusted native
+        // code the user installed.
+        let library = unsafe { libloading::Library::new(path) }?;
+        // SAFETY: The symbol type matches the documented plugin ABI.
+        let init = *unsafe { library.get::<InitFn>(b"tetanes_plugin_init\0") }?;
+        let mut raw = RawPlugin {
+            api_version: 0,
+            name: std::ptr::null(),
+            user_data: std::ptr::null_mut(),
+            on_event: None,
+            on_menu_item: None,
+            destroy: None,
+            on_scanline: None,
+        };
+        let mut ui = PluginUi::default();
+        let mut host = Host {
+            deck,
+            ui: &mut ui,
+            messages: &mut self.messages,
+            ui_changed: false,
+        };
+        // SAFETY: All pointers are valid for the duration of the call.
+        let loaded = unsafe { init(&HOST_API, &mut host, &mut raw) };
+        anyhow::ensure!(loaded, "plugin initialization failed");
+        // Only `api_version` is laid out the same across versions, so none of the callbacks,
+        // including `destroy`, are safe to call for other versions
+        anyhow::ensure!(
+            raw.api_version == API_VERSION,
+            "plugin api version {} is not supported, expected {API_VERSION}",
+            raw.api_version
+        );
+        let mut plugin = Plugin {
+            raw,
+            ui,
+            _library: library,
+        };
+        plugin.ui.name = from_c_str(plugin.raw.name).unwrap_or_else(|| {
+            path.file_stem()
+                .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned())
+        });
+        self.plugins.push(plugin);
+        self.ui_changed = true;
+        Ok(())
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Send `event` to every plugin.
+    pub fn on_event(&mut self, deck: &ControlDeck, event: &PluginEvent) {
+        let rom_name = match event {
+            PluginEvent::RomLoaded(name) => CString::new(name.as_str()).ok(),
+            _ => None,
+        };
+        let (kind, frame) = match event {
+            PluginEvent::RomLoaded(_) => (EVENT_ROM_LOADED, 0),
+            PluginEvent::RomUnloaded => (EVENT_ROM_UNLOADED, 0),
+            PluginEvent::Frame(frame) => (EVENT_FRAME, *frame),
+            PluginEvent::Reset => (EVENT_RESET, 0),
+            PluginEvent::Paused(true) => (EVENT_PAUSED, 0),
+            PluginEvent::Paused(false) => (EVENT_RESUMED, 0),
+        };
+        let raw_event = RawEvent {
+            kind,
+            frame,
+            rom_name: rom_name
+                .as_ref()
+                .map_or(std::ptr::null(), |name| name.as_ptr()),
+        };
+        for plugin in &mut self.plugins {
+            if let Some(on_event) = plugin.raw.on_event {
+                let mut host = Host {
+                    deck,
+                    ui: &mut plugin.ui,
+                    messages: &mut self.messages,
+                    ui_changed: false,
+                };
+                on_event(plugin.raw.user_data, &mut host, &raw_event);
+                self.ui_changed |= host.ui_changed;
+            }
+        }
+    }
+
+    /// Notify the plugin at `index` that its menu item `id` was clicked.
+    pub fn on_menu_item(&mut self, deck: &ControlDeck, index: usize, id: u32) {
+        let Some(plugin) = self.plugins.get_mut(index) else {
+            warn!("invalid plugin index: {index}");
+            return;
+        };
+        if let Some(on_menu_item) = plugin.raw.on_menu_item {
+            let mut host = Host {
+                deck,
+                ui: &mut plugin.ui,
+                messages: &mut self.messages,
+                ui_changed: false,
+            };
+            on_menu_item(plugin.raw.user_data, &mut host, id);
+            self.ui_changed |= host.ui_changed;
+        }
+    }
+
+    /// Hook running the `on_scanline` callback of every plugin that has one, if any.
+    pub fn scanline_hook(&self) -> Option<ScanlineHook> {
+        let callbacks = self
+            .plugins
+            .iter()
+            .filter_map(|plugin| {
+                plugin.raw.on_scanline.map(|on_scanline| ScanlineCallback {
+                    on_scanline,
+                    user_data: plugin.raw.user_data,
+                })
+            })
+            .collect::<Vec<_>>();
+        (!callbacks.is_empty()).then(|| {
+            ScanlineHook::new(move |scanline, palette| {
+                for callback in &callbacks {
+                    (callback.on_scanline)(callback.user_data, scanline, palette.as_mut_ptr());
+                }
+            })
+        })
+    }
+
+    /// Messages plugins asked to show since the last call.
+    pub fn take_messages(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.messages)
+    }
+
+    /// Menu items and overlays of every plugin, if any changed since the last call.
+    pub fn take_ui(&mut self) -> Option<Vec<PluginUi>> {
+        std::mem::take(&mut self.ui_changed).then(|| {
+            self.plugins
+                .iter()
+                .map(|plugin| plugin.ui.clone())
+                .collect()
+        })
+    }
+}
@@ -1,10 +1,12 @@
 use crate::{
     nes::{
-        config::Config,
-        event::{EmulationEvent, NesEvent, RendererEvent, SendNesEvent, UiEvent},
+        config::{BackgroundMode, Config, WindowGeometry},
+        debug_session::DebugSession,
+        event::{ConfigEvent, EmulationEvent, NesEvent, RendererEvent, SendNesEvent, UiEvent},
         input::Gamepads,
         renderer::{
-            gui::{Gui, Menu, MessageType},
+            gui::{Gui, InputLog, Menu, MessageType},
+            shader::{Preset, ShaderChain},
             texture::Texture,
         },
     },
@@ -12,30 +14,36 @@ use crate::{
     thread,
 };
 use egui::{
-    ahash::HashMap, DeferredViewportUiCallback, ImmediateViewport, SystemTheme, Vec2,
-    ViewportBuilder, ViewportClass, ViewportCommand, ViewportId, ViewportIdMap, ViewportIdPair,
-    ViewportIdSet, ViewportInfo, ViewportOutput,
+    ahash::HashMap, load::SizedTexture, DeferredViewportUiCallback, ImmediateViewport, SystemTheme,
+    Vec2, ViewportBuilder, ViewportClass, ViewportCommand, ViewportId, ViewportIdMap,
+    ViewportIdPair, ViewportIdSet, ViewportInfo, ViewportOutput,
 };
 use egui_wgpu::{winit::Painter, RenderState};
 use egui_winit::EventResponse;
 use parking_lot::Mutex;
-use std::{cell::RefCell, collections::hash_map::Entry, rc::Rc, sync::Arc};
-use tetanes_core::{ppu::Ppu, time::Instant, video::Frame};
+use std::{cell::RefCell, collections::hash_map::Entry, path::Path, rc::Rc, sync::Arc};
+use tetanes_core::{
+    input::expansion::ExpansionDevice,
+    ppu::Ppu,
+    time::Instant,
+    video::{Frame, VideoFilter},
+};
 use thingbuf::{
     mpsc::{blocking::Receiver as BufReceiver, errors::TryRecvError},
     Recycle,
 };
 use tracing::{debug, error, trace, warn};
 use winit::{
+    dpi::PhysicalPosition,
     event::WindowEvent,
     event_loop::{ControlFlow, EventLoopProxy, EventLoopWindowTarget},
     window::{Theme, Window, WindowId},
 };
 
 pub mod gui;
+pub mod shader;
 pub mod texture;
-
-pub const OVERSCAN_TRIM: usize = (4 * Ppu::WIDTH * 8) as usize;
+pub mod touch;
 
 #[derive(Debug)]
 #[must_use]
@@ -104,7 +112,11 @@ pub struct Renderer {
     ctx: egui::Context,
     render_state: Option<RenderState>,
     texture: Texture,
+    /// User shader preset rendering `texture` for display, if any.
+    shader: Option<ShaderChain>,
     first_frame: bool,
+    /// Saved window size to restore on the first resize, instead of the configured scale.
+    restore_window_size: Option<Vec2>,
 }
 
 impl std::fmt::Debug for Renderer {
@@ -117,7 +129,9 @@ impl std::fmt::Debug for Renderer {
             .field("gui", &self.gui)
             .field("ctx", &self.ctx)
             .field("texture", &self.texture)
+            .field("shader", &self.shader)
             .field("first_frame", &self.first_frame)
+            .field("restore_window_size", &self.restore_window_size)
             .finish_non_exhaustive()
     }
 }
@@ -220,9 +234,18 @@ impl Renderer {
             &mut render_state.renderer.write(),
             texture_size.x.min(max_texture_side as f32) as u32,
             texture_size.y.min(max_texture_side as f32) as u32,
+            cfg.deck.filter.scale(),
             cfg.deck.region.aspect_ratio(),
             Some("nes frame"),
         );
+        let restore_window_size = cfg
+            .renderer
+            .window_geometry
+            .as_ref()
+            .filter(|geometry| {
+                !cfg.renderer.fullscreen && Self::restored_position(event_loop, geometry).is_some()
+            })
+            .map(|geometry| Vec2::from(geometry.size));
         let gui = Gui::new(
             Arc::clone(&window),
             tx.clone(),
@@ -262,10 +285,21 @@ impl Renderer {
             ctx,
             render_state: Some(render_state),
             texture,
+            shader: None,
             first_frame: true,
+            restore_window_size,
         })
     }
 
+    /// Save the debugging windows and watches open for the loaded ROM.
+    pub fn save_debug_session(&self) {
+        if let Some(rom) = &self.gui.loaded_rom {
+            if let Err(err) = self.gui.debug_session().save(rom.crc32) {
+                error!("failed to save debug session: {err:?}");
+            }
+        }
+    }
+
     pub fn destroy(&mut self) {
         let State {
             viewports,
@@ -301,6 +335,14 @@ impl Renderer {
         self.window_id_for_viewport(ViewportId::ROOT)
     }
 
+    /// Returns the texture the NES frame is rendered to, to draw it in other egui panels.
+    pub fn frame_texture(&self) -> SizedTexture {
+        self.shader.as_ref().map_or_else(
+            || self.texture.sized_texture(),
+            |shader| shader.sized_texture(&self.texture),
+        )
+    }
+
     pub fn all_viewports_occluded(&self) -> bool {
         self.state
             .borrow()
@@ -332,6 +374,59 @@ impl Renderer {
             .flatten()
     }
 
+    /// Returns the current geometry of the root window, if it's in a restorable state.
+    pub fn window_geometry(&self) -> Option<WindowGeometry> {
+        let window = self
+            .root_viewport(|viewport| viewport.window.clone())
+            .flatten()?;
+        if self.fullscreen() || window.is_maximized() || window.is_minimized() == Some(true) {
+            return None;
+        }
+        let position = window.outer_position().ok()?;
+        let size = window.inner_size().to_logical::<f32>(window.scale_factor());
+        Some(WindowGeometry {
+            position: [position.x, position.y],
+            size: [size.width, size.height],
+            monitor: window.current_monitor().and_then(|monitor| monitor.name()),
+        })
+    }
+
+    /// Returns the position to restore a window with the saved `geometry` to, ensuring it stays
+    /// visible on one of the available monitors.
+    fn restored_position(
+        event_loop: &EventLoopWindowTarget<NesEvent>,
+        geometry: &WindowGeometry,
+    ) -> Option<PhysicalPosition<i32>> {
+        // Offset into the window to ensure the title bar can still be grabbed
+        const VISIBLE_MARGIN: i32 = 32;
+
+        let monitors = event_loop.available_monitors().collect::<Vec<_>>();
+        let [x, y] = geometry.position;
+        let on_screen = monitors.iter().any(|monitor| {
+            let position = monitor.position();
+            let size = monitor.size();
+            (position.x..position.x + size.width as i32).contains(&(x + VISIBLE_MARGIN))
+                && (position.y..position.y + size.height as i32).contains(&(y + VISIBLE_MARGIN))
+        });
+        if on_screen {
+            return Some(PhysicalPosition::new(x, y));
+        }
+
+        // The saved position is no longer visible, e.g. the monitor layout or resolution
+        // changed, so center on the saved monitor if it's still connected
+        let monitor = monitors
+            .into_iter()
+            .find(|monitor| geometry.monitor.is_some() && monitor.name() == geometry.monitor)?;
+        let position = monitor.position();
+        let size = monitor.size();
+        let scale_factor = monitor.scale_factor() as f32;
+        let [width, height] = geometry.size.map(|len| (len * scale_factor) as i32);
+        Some(PhysicalPosition::new(
+            position.x + (size.width as i32 - width).max(0) / 2,
+            position.y + (size.height as i32 - height).max(0) / 2,
+        ))
+    }
+
     pub fn fullscreen(&self) -> bool {
         self.root_viewport(|viewport| viewport.info.fullscreen)
             .flatten()
@@ -353,14 +448,27 @@ impl Renderer {
                 EmulationEvent::AudioRecord(recording) => {
                     self.gui.audio_recording = *recording;
                 }
+                EmulationEvent::MapDump(dumping) => {
+                    self.gui.map_dumping = *dumping;
+                }
+                EmulationEvent::SpriteRip(ripping) => {
+                    self.gui.sprite_ripping = *ripping;
+                }
                 EmulationEvent::Pause(paused) => {
                     self.gui.paused = *paused;
                 }
+                EmulationEvent::LoadRomPath(path) => {
+                    self.gui.pending_library_path = Some(path.clone());
+                }
                 _ => (),
             },
             NesEvent::Renderer(event) => match event {
+                RendererEvent::AudioScope(samples) => {
+                    self.gui.audio_scope.clone_from(samples);
+                }
+                RendererEvent::NsfState(state) => self.gui.nsf_state = Some(*state),
                 RendererEvent::FrameStats(stats) => {
-                    self.gui.frame_stats = *stats;
+                    self.gui.frame_stats.clone_from(stats);
                 }
                 RendererEvent::ShowMenubar(show) => {
                     if !show {
@@ -375,25 +483,81 @@ impl Renderer {
                 }
                 RendererEvent::RomUnloaded => {
                     self.gui.paused = false;
+                    self.gui.audio_scope.clear();
+                    self.gui.nsf_state = None;
+                    self.gui.rewind_timeline_open = false;
+                    self.gui.resume_session_prompt = None;
+                    self.save_debug_session();
+                    if let Some(rom) = &self.gui.loaded_rom {
+                        // Thumbnail was updated when the ROM was unloaded
+                        self.gui.library_thumbnails.remove(&rom.crc32);
+                    }
                     self.gui.loaded_rom = None;
                     self.gui.title = Config::WINDOW_TITLE.to_string();
                 }
                 RendererEvent::RomLoaded(rom) => {
                     self.gui.paused = false;
+                    self.gui.nsf_state = None;
                     self.gui.title = format!("{} :: {}", Config::WINDOW_TITLE, rom.name);
                     self.gui.loaded_rom = Some(rom.clone());
-                    if self.gui.loaded_rom.as_ref().map(|rom| rom.region) != Some(rom.region) {
-                        self.gui.resize_window = true;
-                        self.gui.resize_texture = true;
+                    // Keep the current windows open for ROMs without a saved session
+                    if let Some(session) = DebugSession::load(rom.crc32) {
+                        self.gui.restore_debug_session(session);
+                    }
+                    if let Some(path) = self.gui.pending_library_path.take() {
+                        self.gui.library.played(&path, rom);
                     }
                     if self.state.borrow_mut().focused != Some(ViewportId::ROOT) {
                         self.ctx
                             .send_viewport_cmd_to(ViewportId::ROOT, ViewportCommand::Focus);
                     }
                 }
+                RendererEvent::RegionChanged(region) => {
+                    if self.gui.loaded_region != *region {
+                        self.gui.loaded_region = *region;
+                        self.gui.resize_window = true;
+                        self.gui.resize_texture = true;
+                    }
+                }
+                RendererEvent::AutoSaved => self.gui.auto_saved = Some(Instant::now()),
+                RendererEvent::ResumeSessionPrompt(name) => {
+                    self.gui.resume_session_prompt = Some(name.clone());
+                }
+                RendererEvent::JoypadState((frame_number, joypads)) => {
+                    self.gui
+                        .input_diagnostics
+                        .set_joypads(*frame_number, *joypads);
+                }
+                RendererEvent::LanPeers(peers) => self.gui.lan_peers.clone_from(peers),
+                RendererEvent::LanPairRequest(peer) => {
+                    self.gui.lan_pair_request = Some(peer.clone());
+                }
+                RendererEvent::LanStatePrompt(prompt) => {
+                    self.gui.lan_state_prompt = Some(prompt.clone());
+                }
+                RendererEvent::RomReadProgress(progress) => {
+                    self.gui.set_rom_read_progress(progress.clone());
+                }
+                RendererEvent::MapperState((registers, writes)) => {
+                    self.gui.mapper_inspector.update(registers, writes);
+                }
+                RendererEvent::RamWatch(state) => self.gui.ram_search.update(state.clone()),
+                RendererEvent::MemoryPage(page) => self.gui.set_memory_page(page.clone()),
+                RendererEvent::MemorySearch(found) => self.gui.set_memory_search_result(*found),
+                RendererEvent::LibraryScanned(library) => {
+                    self.gui.library = library.clone();
+                    self.gui.library_scanning = false;
+                    self.gui.library_thumbnails.clear();
+                }
+                RendererEvent::PlaylistRun(run) => self.gui.playlist_run = Some(run.clone()),
+                RendererEvent::Plugins(plugins) => self.gui.plugins.clone_from(plugins),
+                RendererEvent::RewindTimeline(timeline) => {
+                    self.gui.set_rewind_timeline(&self.ctx, timeline);
+                }
                 RendererEvent::Menu(menu) => match menu {
                     Menu::About => self.gui.about_open = !self.gui.about_open,
                     Menu::Keybinds => self.gui.keybinds_open = !self.gui.keybinds_open,
+                    Menu::Menubar => self.gui.focus_menubar = true,
                     Menu::PerfStats => {
                         self.gui.perf_stats_open = !self.gui.perf_stats_open;
                         self.tx
@@ -401,7 +565,9 @@ impl Renderer {
                     }
                     Menu::Preferences => self.gui.preferences_open = !self.gui.preferences_open,
                 },
-                RendererEvent::ResourcesReady | RendererEvent::RequestRedraw { .. } => (),
+                RendererEvent::ResourcesReady
+                | RendererEvent::RequestRedraw { .. }
+                | RendererEvent::GenieCodes(_) => (),
             },
             _ => (),
         }
@@ -434,8 +600,18 @@ impl Renderer {
         self.gui.loaded_rom.is_some()
     }
 
+    /// Returns the device connected to the expansion port for the loaded ROM.
+    pub fn expansion_device(&self, cfg: &Config) -> ExpansionDevice {
+        self.gui.expansion_device(cfg)
+    }
+
     /// Handle window event.
-    pub fn on_window_event(&mut self, window_id: WindowId, event: &WindowEvent) -> EventResponse {
+    pub fn on_window_event(
+        &mut self,
+        window_id: WindowId,
+        event: &WindowEvent,
+        cfg: &Config,
+    ) -> EventResponse {
         let viewport_id = self.viewport_id_for_window(window_id);
         let mut state = self.state.borrow_mut();
         match event {
@@ -447,7 +623,9 @@ impl Renderer {
                 {
                     if viewport.ids.this == ViewportId::ROOT && self.rom_loaded() {
                         self.tx.nes_event(EmulationEvent::UnfocusedPause(!focused));
-                        self.gui.paused = !*focused;
+                        if cfg.emulation.background == BackgroundMode::Pause {
+                            self.gui.paused = !*focused;
+                        }
                     }
                 }
             }
@@ -460,7 +638,9 @@ impl Renderer {
                     viewport.occluded = *occluded;
                     if viewport.ids.this == ViewportId::ROOT && self.rom_loaded() {
                         self.tx.nes_event(EmulationEvent::UnfocusedPause(*occluded));
-                        self.gui.paused = *occluded;
+                        if cfg.emulation.background == BackgroundMode::Pause {
+                            self.gui.paused = *occluded;
+                        }
                     }
                 }
             }
@@ -539,6 +719,19 @@ impl Renderer {
         EventResponse::default()
     }
 
+    /// Record an input event if the input diagnostics window is open.
+    pub fn log_input(&mut self, entry: impl FnOnce() -> InputLog) {
+        if self.gui.input_diagnostics_open {
+            self.gui.input_diagnostics.push(entry());
+        }
+    }
+
+    /// Rescan the configured library directories in the background.
+    pub fn scan_library(&mut self, cfg: &Config) {
+        self.gui.library_scanning = true;
+        self.gui.library.spawn_scan(cfg, self.tx.clone());
+    }
+
     pub fn add_message<S>(&mut self, ty: MessageType, text: S)
     where
         S: Into<String>,
@@ -557,7 +750,17 @@ impl Renderer {
         ctx: &egui::Context,
         cfg: &Config,
     ) -> anyhow::Result<(Window, ViewportBuilder)> {
-        let window_size = cfg.window_size();
+        let restored_position = cfg
+            .renderer
+            .window_geometry
+            .as_ref()
+            .filter(|_| !cfg.renderer.fullscreen)
+            .and_then(|geometry| {
+                Self::restored_position(event_loop, geometry)
+                    .map(|position| (position, Vec2::from(geometry.size)))
+            });
+        let window_size =
+            restored_position.map_or_else(|| cfg.window_size(cfg.deck.region), |(_, size)| size);
         let viewport_builder = ViewportBuilder::default()
             .with_app_id(Config::WINDOW_TITLE)
             .with_title(Config::WINDOW_TITLE)
@@ -581,6 +784,9 @@ impl Renderer {
             .build(event_loop)?;
 
         egui_winit::apply_viewport_builder_to_window(ctx, &window, &viewport_builder);
+        if let Some((position, _)) = restored_position {
+            window.set_outer_position(position);
+        }
 
         debug!("created new window: {:?}", window.id());
 
@@ -753,12 +959,73 @@ impl Renderer {
                 &mut render_state.renderer.write(),
                 texture_size.x.min(max_texture_side as f32) as u32,
                 texture_size.y.min(max_texture_side as f32) as u32,
+                cfg.deck.filter.scale(),
                 self.gui.aspect_ratio(cfg),
             );
-            self.gui.texture = self.texture.sized_texture();
+            // Shader passes read from the old texture, so they're recreated by `update_shader`
+            if let Some(shader) = self.shader.take() {
+                shader.destroy(&mut render_state.renderer.write());
+            }
+            self.gui.texture = self.frame_texture();
         }
     }
 
+    /// Load the configured shader preset, or the built-in CRT preset for [`VideoFilter::Crt`], if
+    /// it changed or a reload was requested, falling back to displaying the NES frame directly if
+    /// it fails to load.
+    fn update_shader(&mut self, cfg: &mut Config) {
+        let wanted = cfg.renderer.shader_preset.as_deref().or_else(|| {
+            (cfg.deck.filter == VideoFilter::Crt).then(|| Path::new(shader::CRT_PRESET))
+        });
+        let loaded = self.shader.as_ref().map(|shader| shader.preset().path());
+        if !self.gui.reload_shader && wanted == loaded {
+            return;
+        }
+        self.gui.reload_shader = false;
+        let Some(render_state) = &self.render_state else {
+            return;
+        };
+
+        if let Some(shader) = self.shader.take() {
+            shader.destroy(&mut render_state.renderer.write());
+        }
+        self.gui.shader_parameters.clear();
+        if let Some(path) = wanted {
+            let preset = if path == Path::new(shader::CRT_PRESET) {
+                Preset::crt()
+            } else {
+                Preset::load(path)
+            };
+            let shader = preset.and_then(|preset| {
+                ShaderChain::new(
+                    &render_state.device,
+                    &mut render_state.renderer.write(),
+                    preset,
+                    &self.texture,
+                )
+            });
+            match shader {
+                Ok(shader) => {
+                    self.gui.shader_parameters = shader.preset().parameters();
+                    self.shader = Some(shader);
+                }
+                Err(err) => {
+                    error!("failed to load shader preset: {err:?}");
+                    self.gui.add_message(
+                        MessageType::Error,
+                        format!("Failed to load shader preset: {err:#}"),
+                    );
+                    if cfg.renderer.shader_preset.take().is_none() {
+                        // The built-in CRT preset failed, so fall back to the filter it builds on
+                        cfg.deck.filter = VideoFilter::Ntsc;
+                        self.tx.nes_event(ConfigEvent::VideoFilter(cfg.deck.filter));
+                    }
+                }
+            }
+        }
+        self.gui.texture = self.frame_texture();
+    }
+
     fn handle_viewport_output(
         ctx: &egui::Context,
         viewports: &mut ViewportIdMap<Viewport>,
@@ -916,16 +1183,20 @@ impl Renderer {
         let Some(viewport_id) = self.viewport_id_for_window(window_id) else {
             return Ok(());
         };
+        let render_start = Instant::now();
 
         #[cfg(feature = "profiling")]
         puffin::GlobalProfiler::lock().new_frame();
 
         if self.gui.resize_window {
             if !self.fullscreen() {
-                let aspect_ratio = self.gui.aspect_ratio(cfg);
-                let mut window_size = cfg.window_size();
-                window_size.x *= aspect_ratio;
-                window_size.y += self.gui.menu_height;
+                let window_size = self.restore_window_size.take().unwrap_or_else(|| {
+                    let mut window_size = cfg.window_size(self.gui.region(cfg));
+                    window_size.x *= self.gui.aspect_ratio(cfg);
+                    window_size = cfg.renderer.orientation.size(window_size);
+                    window_size.y += self.gui.menu_height;
+                    window_size
+                });
                 self.ctx.send_viewport_cmd_to(
                     ViewportId::ROOT,
                     ViewportCommand::InnerSize(window_size),
@@ -933,10 +1204,12 @@ impl Renderer {
             }
             self.gui.resize_window = false;
         }
-        if self.gui.resize_texture {
+        // Upscaling filters change the texture size
+        if self.gui.resize_texture || self.texture.scale != cfg.deck.filter.scale() {
             self.resize_texture(cfg);
             self.gui.resize_texture = false;
         }
+        self.update_shader(cfg);
 
         let (viewport_ui_cb, raw_input) = {
             let State {
@@ -1001,16 +1274,11 @@ impl Renderer {
         // Copy NES frame buffer before drawing UI because a UI interaction might cause a texture
         // resize tied to a configuration change.
         if let Some(render_state) = &self.render_state {
+            let mut new_frame = false;
             match self.frame_rx.try_recv() {
                 Ok(frame_buffer) => {
-                    self.texture.update(
-                        &render_state.queue,
-                        if cfg.renderer.hide_overscan && self.gui.loaded_region.is_ntsc() {
-                            &frame_buffer[OVERSCAN_TRIM..frame_buffer.len() - OVERSCAN_TRIM]
-                        } else {
-                            &frame_buffer
-                        },
-                    );
+                    new_frame = true;
+                    self.texture.update(&render_state.queue, &frame_buffer);
                 }
                 Err(err) => match err {
                     TryRecvError::Empty if self.rom_loaded() && !self.gui.paused => {
@@ -1031,6 +1299,14 @@ impl Renderer {
                     when: Instant::now(),
                 });
             }
+            if let Some(shader) = &mut self.shader {
+                shader.render(
+                    &render_state.device,
+                    &render_state.queue,
+                    &cfg.renderer.shader_parameters,
+                    new_frame,
+                );
+            }
         }
 
         let output = self.ctx.run(raw_input, |ctx| {
@@ -1094,6 +1370,12 @@ impl Renderer {
             painter.borrow_mut().gc_viewports(&active_viewports_ids);
         }
 
+        if viewport_id == ViewportId::ROOT {
+            self.gui
+                .render_time
+                .push(render_start.elapsed().as_secs_f32());
+        }
+
         Ok(())
     }
 }
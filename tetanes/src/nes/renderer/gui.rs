@@ -1,11 +1,30 @@
 use crate::{
     nes::{
         action::{Action, Debug, DebugStep, Debugger, Feature, Setting, Ui as UiAction},
-        config::Config,
-        emulation::FrameStats,
+        config::{
+            BackgroundMode, Config, FastForwardAudio, FastForwardConfig, FrameRate,
+            LiveReloadPreserve, Orientation, OverlayPosition, Overscan, Rotation, ScreenshotFormat,
+            SessionMode, TouchControllerMode,
+        },
+        debug_session::DebugSession,
+        emulation::{
+            lan::{Peer, TrustedPeer},
+            livesplit,
+            ram_watch::RamWatchState,
+            rewind::{Frame as RewindFrame, Timeline},
+            stats::{FrameStats, History},
+            MemoryPage,
+        },
         event::{ConfigEvent, EmulationEvent, NesEvent, SendNesEvent, UiEvent},
-        input::{ActionBindings, Gamepads, Input},
-        rom::{RomAsset, HOMEBREW_ROMS},
+        input::{ActionBindings, Gamepads, Input, RepeatPolicy},
+        library::{self, Library, LibraryEntry},
+        plugin::{PluginUi, Plugins},
+        renderer::{
+            shader::{self, Parameter as ShaderParameter},
+            touch::TouchController,
+        },
+        rom::{stream::ReadProgress, RomAsset, HOMEBREW_ROMS},
+        rom_db,
         version::Version,
     },
     platform,
@@ -15,32 +34,38 @@ use egui::{
     load::SizedTexture,
     menu,
     style::{HandleShape, Selection, WidgetVisuals},
-    Align, Align2, Area, Button, CentralPanel, Checkbox, Color32, Context, CursorIcon, Direction,
-    DragValue, FontData, FontDefinitions, FontFamily, Frame, Grid, Id, Image, Key,
-    KeyboardShortcut, Layout, Modifiers, Order, PointerButton, Pos2, Rect, Response, RichText,
-    Rounding, ScrollArea, Sense, Slider, Stroke, TopBottomPanel, Ui, Vec2, ViewportClass,
-    ViewportCommand, ViewportId, Visuals, Widget, WidgetText,
+    Align, Align2, Area, Button, CentralPanel, Checkbox, Color32, ColorImage, Context, CursorIcon,
+    Direction, DragValue, FontData, FontDefinitions, FontFamily, Frame, Grid, Id, Image, Key,
+    KeyboardShortcut, Label, Layout, Modifiers, Order, PointerButton, Pos2, ProgressBar, Rect,
+    Response, RichText, Rounding, ScrollArea, Sense, Shape, SidePanel, Slider, Stroke, TextEdit,
+    TextureHandle, TextureOptions, TopBottomPanel, Ui, Vec2, ViewportClass, ViewportCommand,
+    ViewportId, Visuals, Widget, WidgetText,
 };
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap, VecDeque},
     mem,
-    ops::{Deref, DerefMut},
+    ops::{Deref, DerefMut, Range},
+    path::PathBuf,
     sync::Arc,
 };
 use sysinfo::{Pid, ProcessRefreshKind, RefreshKind, System};
 use tetanes_core::{
     action::Action as DeckAction,
-    apu::Channel,
+    apu::{filter::FilterConfig, mixer::Mixer, Apu, Channel},
+    cart::HeaderOverrides,
     common::{NesRegion, ResetKind},
-    control_deck::LoadedRom,
+    control_deck::{Config as DeckConfig, LoadedRom},
     fs,
     genie::GenieCode,
-    input::{FourPlayer, Player},
-    mem::RamState,
-    ppu::Ppu,
+    input::{expansion::ExpansionDevice, FourPlayer, JoypadBtnState, Player, VausPaddle},
+    mapper::{registry, MapperRegister, MapperRevision, MapperWrite, Mmc3Revision},
+    mem::{MemorySpace, RamState},
+    playlist::{Comparison, PlaylistRun},
+    ppu::{Mirroring, Ppu},
+    ram_search::SearchFilter,
     time::{Duration, Instant},
-    video::VideoFilter,
+    video::{NtscConfig, VideoFilter},
 };
 use tracing::info;
 use uuid::Uuid;
@@ -68,6 +93,8 @@ where
 pub enum Menu {
     About,
     Keybinds,
+    /// Move keyboard focus to the menu bar, to navigate menus with the arrow and enter keys.
+    Menubar,
     PerfStats,
     Preferences,
 }
@@ -129,6 +156,239 @@ impl PendingGenieEntry {
     }
 }
 
+/// Fields of the Game Genie converter window.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct GenieConverter {
+    code: String,
+    addr: String,
+    data: String,
+    compare: String,
+    error: Option<String>,
+}
+
+impl GenieConverter {
+    fn decode(&mut self) {
+        match GenieCode::new(self.code.trim().to_string()) {
+            Ok(code) => {
+                self.code = code.code().to_string();
+                self.addr = format!("{:04X}", code.addr());
+                self.data = format!("{:02X}", code.data());
+                self.compare = code
+                    .compare()
+                    .map(|compare| format!("{compare:02X}"))
+                    .unwrap_or_default();
+                self.error = None;
+            }
+            Err(err) => self.error = Some(err.to_string()),
+        }
+    }
+
+    fn encode(&mut self) {
+        match self.raw_code() {
+            Ok(code) => {
+                self.code = code.code().to_string();
+                self.error = None;
+            }
+            Err(err) => self.error = Some(err),
+        }
+    }
+
+    fn raw_code(&self) -> Result<GenieCode, String> {
+        fn parse_hex(field: &str, value: &str) -> Result<u16, String> {
+            let value = value.trim().trim_start_matches('$');
+            u16::from_str_radix(value, 16).map_err(|_| format!("invalid {field}: `{value}`"))
+        }
+
+        let addr = parse_hex("address", &self.addr)?;
+        let data = u8::try_from(parse_hex("value", &self.data)?)
+            .map_err(|_| format!("value must be a byte. found `{}`", self.data))?;
+        let compare = if self.compare.trim().is_empty() {
+            None
+        } else {
+            Some(
+                u8::try_from(parse_hex("compare", &self.compare)?)
+                    .map_err(|_| format!("compare must be a byte. found `{}`", self.compare))?,
+            )
+        };
+        GenieCode::encode(addr, data, compare).map_err(|err| err.to_string())
+    }
+
+    /// Raw patch formatted as `ADDR:VALUE` or `ADDR:VALUE:COMPARE`.
+    fn raw_text(&self) -> Option<String> {
+        self.raw_code().ok().map(|code| {
+            let mut text = format!("{:04X}:{:02X}", code.addr(), code.data());
+            if let Some(compare) = code.compare() {
+                text.push_str(&format!(":{compare:02X}"));
+            }
+            text
+        })
+    }
+}
+
+/// An input event captured by the input diagnostics window.
+#[derive(Debug, Clone)]
+pub enum InputLog {
+    /// Raw keyboard, mouse, or gamepad event before bindings are resolved.
+    Raw(String),
+    /// Resolved [`Input`] and the [`Action`] it's bound to, if any.
+    Resolved {
+        input: Input,
+        action: Option<Action>,
+        state: ElementState,
+        repeat: bool,
+    },
+}
+
+/// State of the input diagnostics window.
+#[derive(Debug)]
+pub struct InputDiagnostics {
+    log: VecDeque<(Instant, InputLog)>,
+    frozen: bool,
+    frame_number: u32,
+    joypads: [JoypadBtnState; 4],
+}
+
+impl Default for InputDiagnostics {
+    fn default() -> Self {
+        Self {
+            log: VecDeque::with_capacity(Self::MAX_LOG_LEN),
+            frozen: false,
+            frame_number: 0,
+            joypads: [JoypadBtnState::empty(); 4],
+        }
+    }
+}
+
+impl InputDiagnostics {
+    const MAX_LOG_LEN: usize = 200;
+
+    pub fn push(&mut self, entry: InputLog) {
+        if self.frozen {
+            return;
+        }
+        if self.log.len() >= Self::MAX_LOG_LEN {
+            self.log.pop_front();
+        }
+        self.log.push_back((Instant::now(), entry));
+    }
+
+    pub fn set_joypads(&mut self, frame_number: u32, joypads: [JoypadBtnState; 4]) {
+        if !self.frozen {
+            self.frame_number = frame_number;
+            self.joypads = joypads;
+        }
+    }
+}
+
+/// State of the mapper inspector window.
+#[derive(Debug)]
+pub struct MapperInspector {
+    registers: Vec<MapperRegister>,
+    writes: VecDeque<MapperWrite>,
+    frozen: bool,
+}
+
+impl Default for MapperInspector {
+    fn default() -> Self {
+        Self {
+            registers: Vec::new(),
+            writes: VecDeque::with_capacity(Self::MAX_LOG_LEN),
+            frozen: false,
+        }
+    }
+}
+
+impl MapperInspector {
+    const MAX_LOG_LEN: usize = 500;
+
+    pub fn update(&mut self, registers: &[MapperRegister], writes: &[MapperWrite]) {
+        if self.frozen {
+            return;
+        }
+        self.registers = registers.to_vec();
+        for write in writes {
+            if self.writes.len() >= Self::MAX_LOG_LEN {
+                self.writes.pop_front();
+            }
+            self.writes.push_back(*write);
+        }
+    }
+}
+
+/// State of the hex editor window.
+#[derive(Default, Debug)]
+pub struct HexEditor {
+    space: MemorySpace,
+    page: Option<MemoryPage>,
+    /// Address of the first row shown.
+    offset: usize,
+    selected: Option<usize>,
+    edit: String,
+    focus_edit: bool,
+    search: String,
+    search_status: Option<&'static str>,
+    /// Memory spaces that can be edited. All are write-protected by default.
+    writable: Vec<MemorySpace>,
+}
+
+impl HexEditor {
+    const ROW_LEN: usize = 16;
+    const ROW_COUNT: usize = 16;
+    const PAGE_LEN: usize = Self::ROW_LEN * Self::ROW_COUNT;
+
+    fn view(&self) -> (MemorySpace, Range<usize>) {
+        (self.space, self.offset..self.offset + Self::PAGE_LEN)
+    }
+
+    fn len(&self) -> usize {
+        self.page
+            .as_ref()
+            .filter(|page| page.space == self.space)
+            .map_or(0, |page| page.len)
+    }
+
+    /// Scroll so that `addr` is shown, keeping the view aligned to rows.
+    fn scroll_to(&mut self, addr: usize) {
+        if addr < self.offset || addr >= self.offset + Self::PAGE_LEN {
+            self.offset = addr - addr % Self::ROW_LEN;
+        }
+    }
+
+    /// Parse a search pattern of hex bytes, e.g. `A9 00 8D`, or quoted text, e.g. `"HELLO"`.
+    fn parse_pattern(pattern: &str) -> Option<Vec<u8>> {
+        let pattern = pattern.trim();
+        if let Some(text) = pattern.strip_prefix('"') {
+            let text = text.strip_suffix('"').unwrap_or(text);
+            return (!text.is_empty()).then(|| text.as_bytes().to_vec());
+        }
+        let digits = pattern
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<Vec<_>>();
+        if digits.is_empty() || digits.len() % 2 != 0 {
+            return None;
+        }
+        digits
+            .chunks(2)
+            .map(|byte| u8::from_str_radix(&byte.iter().collect::<String>(), 16).ok())
+            .collect()
+    }
+}
+
+/// State of the RAM search window.
+#[derive(Default, Debug)]
+pub struct RamSearchView {
+    state: RamWatchState,
+    filter: SearchFilter,
+    watches: Vec<u16>,
+}
+
+impl RamSearchView {
+    pub fn update(&mut self, state: RamWatchState) {
+        self.state = state;
+    }
+}
+
 type Keybind = (Action, [Option<Input>; 2]);
 
 #[derive(Debug)]
@@ -141,12 +401,55 @@ pub struct Gui {
     pub texture: SizedTexture,
     pub paused: bool,
     pub menu_height: f32,
+    /// Focus the first menu on the next frame.
+    pub focus_menubar: bool,
+    pub touch_controller: TouchController,
     pub nes_frame: Rect,
     pub pending_genie_entry: PendingGenieEntry,
+    pub pending_barcode: String,
+    /// Arkanoid Vaus paddle position and button last sent, to only send changes.
+    pub vaus_input: Option<(f32, bool)>,
+    /// Super NES Mouse buttons last sent, to only send changes.
+    pub mouse_buttons: Option<(bool, bool)>,
+    pub genie_converter_open: bool,
+    pub genie_converter: GenieConverter,
+    pub header_editor_open: bool,
+    pub header_overrides: HeaderOverrides,
+    pub header_overrides_rom: String,
+    pub input_diagnostics_open: bool,
+    pub input_diagnostics: InputDiagnostics,
+    pub mapper_inspector_open: bool,
+    pub mapper_inspector: MapperInspector,
+    pub hex_editor_open: bool,
+    pub hex_editor: HexEditor,
+    pub ram_search_open: bool,
+    pub ram_search: RamSearchView,
+    pub code_data_logging: bool,
+    /// Progress reading a ROM file and when reading started.
+    pub rom_read_progress: Option<(Instant, ReadProgress)>,
+    pub auto_saved: Option<Instant>,
+    /// Name of the ROM to ask whether to resume the saved session of.
+    pub resume_session_prompt: Option<String>,
+    /// Unpaired device that tried to send a save state.
+    pub lan_pair_request: Option<TrustedPeer>,
+    /// Device and ROM name of a received save state to ask whether to load.
+    pub lan_state_prompt: Option<(String, String)>,
     pub about_open: bool,
     pub keybinds_open: bool,
     pub keybinds_tab: KeybindsTab,
     pub perf_stats_open: bool,
+    pub playlist_open: bool,
+    pub playlist_run: Option<PlaylistRun>,
+    pub library_open: bool,
+    pub library: Library,
+    pub library_scanning: bool,
+    pub library_filter: String,
+    pub library_thumbnails: HashMap<u32, Option<TextureHandle>>,
+    pub pending_library_path: Option<PathBuf>,
+    pub rewind_timeline_open: bool,
+    pub rewind_timeline: Timeline,
+    pub rewind_timeline_index: usize,
+    pub rewind_thumbnails: Vec<(usize, TextureHandle)>,
     pub preferences_open: bool,
     pub preferences_tab: PreferencesTab,
     pub update_window_open: bool,
@@ -160,13 +463,24 @@ pub struct Gui {
     pub loaded_region: NesRegion,
     pub resize_window: bool,
     pub resize_texture: bool,
+    pub reload_shader: bool,
+    pub shader_parameters: Vec<ShaderParameter>,
     pub replay_recording: bool,
     pub audio_recording: bool,
+    pub map_dumping: bool,
+    pub sprite_ripping: bool,
     pub shortcut_keybinds: BTreeMap<String, Keybind>,
     pub joypad_keybinds: [BTreeMap<String, Keybind>; 4],
     pub frame_stats: FrameStats,
+    /// How long recent frames took to render, in seconds.
+    pub render_time: History,
+    pub audio_scope: Vec<[f32; Apu::MAX_CHANNEL_COUNT]>,
+    /// Current 0-based NSF track and time elapsed since it started, if an NSF is playing.
+    pub nsf_state: Option<(u8, Duration)>,
     pub messages: Vec<(MessageType, String, Instant)>,
     pub loaded_rom: Option<LoadedRom>,
+    pub lan_peers: Vec<Peer>,
+    pub plugins: Vec<PluginUi>,
     pub about_homebrew_rom_open: Option<RomAsset>,
     pub start: Instant,
     pub sys: Option<System>,
@@ -185,6 +499,9 @@ macro_rules! hex_color {
 impl Gui {
     const MSG_TIMEOUT: Duration = Duration::from_secs(3);
     const MAX_MESSAGES: usize = 5;
+    const AUTO_SAVE_INDICATOR_TIMEOUT: Duration = Duration::from_secs(1);
+    /// Delay before showing progress reading a ROM, so ROMs read quickly don't flash a window.
+    const ROM_READ_WINDOW_DELAY: Duration = Duration::from_millis(250);
     const MENU_WIDTH: f32 = 250.0;
     const NO_ROM_LOADED: &'static str = "No ROM is loaded.";
 
@@ -224,12 +541,48 @@ impl Gui {
             texture,
             paused: false,
             menu_height: 0.0,
+            focus_menubar: false,
+            touch_controller: TouchController::default(),
             nes_frame: Rect::ZERO,
             pending_genie_entry: PendingGenieEntry::empty(),
+            pending_barcode: String::new(),
+            vaus_input: None,
+            mouse_buttons: None,
+            genie_converter_open: false,
+            genie_converter: GenieConverter::default(),
+            header_editor_open: false,
+            header_overrides: HeaderOverrides::default(),
+            header_overrides_rom: String::new(),
+            input_diagnostics_open: false,
+            input_diagnostics: InputDiagnostics::default(),
+            mapper_inspector_open: false,
+            mapper_inspector: MapperInspector::default(),
+            hex_editor_open: false,
+            hex_editor: HexEditor::default(),
+            ram_search_open: false,
+            ram_search: RamSearchView::default(),
+            code_data_logging: false,
+            rom_read_progress: None,
+            auto_saved: None,
+            resume_session_prompt: None,
+            lan_pair_request: None,
+            lan_state_prompt: None,
             about_open: false,
             keybinds_open: false,
             keybinds_tab: KeybindsTab::Shortcuts,
             perf_stats_open: false,
+            playlist_open: false,
+            playlist_run: None,
+            library_open: false,
+            library: Library::load(),
+            library_scanning: false,
+            library_filter: String::new(),
+            library_thumbnails: HashMap::new(),
+            pending_library_path: None,
+            rewind_timeline_open: false,
+            rewind_timeline: Timeline::default(),
+            rewind_timeline_index: 0,
+            rewind_thumbnails: Vec::new(),
             preferences_open: false,
             preferences_tab: PreferencesTab::Emulation,
             update_window_open: false,
@@ -243,13 +596,22 @@ impl Gui {
             loaded_region: cfg.deck.region,
             resize_window: false,
             resize_texture: false,
+            reload_shader: false,
+            shader_parameters: Vec::new(),
             replay_recording: false,
             audio_recording: false,
+            map_dumping: false,
+            sprite_ripping: false,
             shortcut_keybinds: Self::shortcut_keybinds(&cfg.input.shortcuts),
             joypad_keybinds: Self::joypad_keybinds(&cfg.input.joypad_bindings),
             frame_stats: FrameStats::new(),
+            render_time: History::new(),
+            audio_scope: Vec::new(),
+            nsf_state: None,
             messages: Vec::new(),
             loaded_rom: None,
+            lan_peers: Vec::new(),
+            plugins: Vec::new(),
             about_homebrew_rom_open: None,
             start: Instant::now(),
             sys,
@@ -292,11 +654,82 @@ impl Gui {
             .push((ty, text, Instant::now() + Self::MSG_TIMEOUT));
     }
 
-    pub fn aspect_ratio(&self, cfg: &Config) -> f32 {
-        if cfg.deck.region.is_auto() {
-            self.loaded_region.aspect_ratio()
+    /// Region of the displayed frame, which is the loaded ROM's region when auto-detected.
+    pub fn region(&self, cfg: &Config) -> NesRegion {
+        let has_override = self
+            .loaded_rom
+            .as_ref()
+            .is_some_and(|rom| cfg.deck.rom_regions.contains_key(&rom.name));
+        if cfg.deck.region.is_auto() || has_override {
+            self.loaded_region
         } else {
-            cfg.deck.region.aspect_ratio()
+            cfg.deck.region
+        }
+    }
+
+    /// Returns the device connected to the expansion port: the override for the loaded ROM, if
+    /// any, otherwise the device specified by its header or configured.
+    pub fn expansion_device(&self, cfg: &Config) -> ExpansionDevice {
+        self.loaded_rom
+            .as_ref()
+            .and_then(|rom| {
+                cfg.deck
+                    .rom_expansion_devices
+                    .get(&rom.name)
+                    .copied()
+                    .or_else(|| ExpansionDevice::from_header(rom.header.expansion_device))
+            })
+            .unwrap_or(cfg.deck.expansion_device)
+    }
+
+    /// Returns whether an NES or Famicom Arkanoid Vaus paddle is connected.
+    pub fn vaus_connected(&self, cfg: &Config) -> bool {
+        cfg.deck.vaus
+            || self.expansion_device(cfg) == ExpansionDevice::Vaus
+            || self
+                .loaded_rom
+                .as_ref()
+                .is_some_and(|rom| rom.header.expansion_device == VausPaddle::NES_DEVICE)
+    }
+
+    pub fn aspect_ratio(&self, cfg: &Config) -> f32 {
+        self.region(cfg).aspect_ratio()
+    }
+
+    /// Reserve empty panels along the edges of the window covered by system UI, e.g. the status
+    /// bar and display cutouts on mobile devices, so the menu and NES frame aren't hidden.
+    fn safe_area_panels(ctx: &Context) {
+        let insets = platform::safe_area_insets() / ctx.pixels_per_point();
+        let frame = Frame::none().fill(Color32::BLACK);
+        if insets.top > 0.0 {
+            TopBottomPanel::top("safe_area_top")
+                .frame(frame)
+                .show_separator_line(false)
+                .exact_height(insets.top)
+                .show(ctx, |_| ());
+        }
+        if insets.bottom > 0.0 {
+            TopBottomPanel::bottom("safe_area_bottom")
+                .frame(frame)
+                .show_separator_line(false)
+                .exact_height(insets.bottom)
+                .show(ctx, |_| ());
+        }
+        if insets.left > 0.0 {
+            SidePanel::left("safe_area_left")
+                .frame(frame)
+                .show_separator_line(false)
+                .resizable(false)
+                .exact_width(insets.left)
+                .show(ctx, |_| ());
+        }
+        if insets.right > 0.0 {
+            SidePanel::right("safe_area_right")
+                .frame(frame)
+                .show_separator_line(false)
+                .resizable(false)
+                .exact_width(insets.right)
+                .show(ctx, |_| ());
         }
     }
 
@@ -309,6 +742,7 @@ impl Gui {
             self.initialize(ctx, cfg);
         }
 
+        Self::safe_area_panels(ctx);
         if cfg.renderer.show_menubar {
             TopBottomPanel::top("menu_bar").show(ctx, |ui| self.menu_bar(ui, cfg));
         }
@@ -319,6 +753,19 @@ impl Gui {
         self.show_keybinds_viewport(ctx, gamepads, cfg);
 
         self.show_performance_window(ctx, cfg);
+        self.show_rewind_timeline_window(ctx);
+        self.show_playlist_window(ctx, cfg);
+        self.show_library_window(ctx, cfg);
+        self.show_genie_converter_window(ctx, cfg);
+        self.show_header_editor_window(ctx, cfg);
+        self.show_input_diagnostics_window(ctx);
+        self.show_mapper_inspector_window(ctx);
+        self.show_hex_editor_window(ctx);
+        self.show_ram_search_window(ctx);
+        self.show_rom_read_window(ctx);
+        self.show_resume_session_window(ctx);
+        self.show_lan_pair_window(ctx, cfg);
+        self.show_lan_state_window(ctx);
         self.show_preferences_viewport(ctx, cfg);
         self.show_about_window(ctx);
         self.show_about_homebrew_window(ctx);
@@ -332,12 +779,7 @@ impl Gui {
     }
 
     fn initialize(&mut self, ctx: &Context, cfg: &Config) {
-        let theme = if cfg.renderer.dark_theme {
-            Self::dark_theme()
-        } else {
-            Self::light_theme()
-        };
-        ctx.set_visuals(theme);
+        Self::set_style(ctx, cfg);
 
         const FONT: (&str, &[u8]) = (
             "pixeloid-sans",
@@ -650,1329 +1092,3178 @@ impl Gui {
         self.perf_stats_open = perf_stats_open;
     }
 
-    fn show_preferences_viewport(&mut self, ctx: &Context, cfg: &mut Config) {
-        if !self.preferences_open {
-            return;
+    fn show_rewind_timeline_window(&mut self, ctx: &Context) {
+        let mut rewind_timeline_open = self.rewind_timeline_open;
+        egui::Window::new("Rewind Timeline")
+            .open(&mut rewind_timeline_open)
+            .resizable(false)
+            .show(ctx, |ui| self.rewind_timeline(ui));
+        if self.rewind_timeline_open && !rewind_timeline_open {
+            self.close_rewind_timeline();
         }
-
-        let title = "Preferences";
-        // TODO: Make this deferred? Requires `tx` and `cfg` to be Send + Sync
-        ctx.show_viewport_immediate(
-            egui::ViewportId::from_hash_of("preferences"),
-            egui::ViewportBuilder::default().with_title(title),
-            |ctx, class| {
-                if class == ViewportClass::Embedded {
-                    let mut preferences_open = self.preferences_open;
-                    let mut default_rect = ctx.available_rect();
-                    let border = 1.0;
-                    default_rect.min.y +=
-                        self.menu_height + ctx.style().spacing.item_spacing.y + border;
-                    default_rect.max.y -= self.menu_height;
-                    egui::Window::new(title)
-                        .open(&mut preferences_open)
-                        .default_rect(default_rect)
-                        .show(ctx, |ui| self.preferences(ui, cfg));
-                    self.preferences_open = preferences_open;
-                } else {
-                    CentralPanel::default().show(ctx, |ui| self.preferences(ui, cfg));
-                    if ctx.input(|i| i.viewport().close_requested()) {
-                        self.preferences_open = false;
-                    }
-                }
-            },
-        );
     }
 
-    fn show_keybinds_viewport(&mut self, ctx: &Context, gamepads: &mut Gamepads, cfg: &mut Config) {
-        if !self.keybinds_open {
-            self.pending_keybind = None;
-            self.gamepad_unassign = None;
-            return;
-        }
+    fn show_playlist_window(&mut self, ctx: &Context, cfg: &mut Config) {
+        let mut playlist_open = self.playlist_open;
+        egui::Window::new("Playlist")
+            .open(&mut playlist_open)
+            .default_width(600.0)
+            .show(ctx, |ui| self.playlist(ui, cfg));
+        self.playlist_open = playlist_open;
+    }
 
-        let title = "Keybinds";
-        // TODO: Make this deferred? Requires `tx` and `cfg` to be Send + Sync
-        ctx.show_viewport_immediate(
-            egui::ViewportId::from_hash_of("keybinds"),
-            egui::ViewportBuilder::default().with_title(title),
-            |ctx, class| {
-                if class == ViewportClass::Embedded {
-                    let mut keybinds_open = self.keybinds_open;
-                    let mut default_rect = ctx.available_rect();
-                    let border = 1.0;
-                    default_rect.min.y +=
-                        self.menu_height + ctx.style().spacing.item_spacing.y + border;
-                    default_rect.max.y -= self.menu_height;
-                    egui::Window::new("Keybinds")
-                        .open(&mut keybinds_open)
-                        .default_rect(default_rect)
-                        .show(ctx, |ui| self.keybinds(ui, gamepads, cfg));
-                    self.keybinds_open = keybinds_open;
-                } else {
-                    CentralPanel::default().show(ctx, |ui| self.keybinds(ui, gamepads, cfg));
-                    if ctx.input(|i| i.viewport().close_requested()) {
-                        self.keybinds_open = false;
-                    }
-                }
-            },
-        );
+    fn show_library_window(&mut self, ctx: &Context, cfg: &mut Config) {
+        let mut library_open = self.library_open;
+        egui::Window::new("Library")
+            .open(&mut library_open)
+            .default_width(600.0)
+            .show(ctx, |ui| self.library(ui, cfg));
+        self.library_open = library_open;
     }
 
-    fn show_about_window(&mut self, ctx: &Context) {
-        let mut about_open = self.about_open;
-        egui::Window::new("About TetaNES")
-            .open(&mut about_open)
-            .show(ctx, |ui| self.about(ui));
-        self.about_open = about_open;
+    fn show_genie_converter_window(&mut self, ctx: &Context, cfg: &mut Config) {
+        let mut genie_converter_open = self.genie_converter_open;
+        egui::Window::new("Game Genie Converter")
+            .open(&mut genie_converter_open)
+            .resizable(false)
+            .show(ctx, |ui| self.genie_converter(ui, cfg));
+        self.genie_converter_open = genie_converter_open;
     }
 
-    fn show_about_homebrew_window(&mut self, ctx: &Context) {
-        let Some(rom) = self.about_homebrew_rom_open else {
-            return;
-        };
+    fn show_header_editor_window(&mut self, ctx: &Context, cfg: &mut Config) {
+        let mut header_editor_open = self.header_editor_open;
+        egui::Window::new("ROM Header")
+            .open(&mut header_editor_open)
+            .resizable(false)
+            .show(ctx, |ui| self.header_editor(ui, cfg));
+        self.header_editor_open = header_editor_open;
+    }
 
-        let mut about_homebrew_open = true;
-        egui::Window::new(format!("About {}", rom.name))
-            .open(&mut about_homebrew_open)
-            .show(ctx, |ui| {
-                ScrollArea::vertical().show(ui, |ui| {
-                    ui.strong("Author(s):");
-                    ui.label(rom.authors);
-                    ui.add_space(12.0);
+    fn show_input_diagnostics_window(&mut self, ctx: &Context) {
+        let mut input_diagnostics_open = self.input_diagnostics_open;
+        egui::Window::new("Input Diagnostics")
+            .open(&mut input_diagnostics_open)
+            .default_width(450.0)
+            .show(ctx, |ui| self.input_diagnostics(ui));
+        if self.input_diagnostics_open != input_diagnostics_open {
+            self.set_input_diagnostics_open(input_diagnostics_open);
+        }
+    }
 
-                    ui.strong("Description:");
-                    ui.label(rom.description);
-                    ui.add_space(12.0);
+    pub fn set_input_diagnostics_open(&mut self, open: bool) {
+        self.input_diagnostics_open = open;
+        self.tx
+            .nes_event(EmulationEvent::ShowInputDiagnostics(open));
+        if !open {
+            self.input_diagnostics = InputDiagnostics::default();
+        }
+    }
 
-                    ui.strong("Source:");
-                    ui.hyperlink(rom.source);
-                });
-            });
-        if !about_homebrew_open {
-            self.about_homebrew_rom_open = None;
+    fn show_mapper_inspector_window(&mut self, ctx: &Context) {
+        let mut mapper_inspector_open = self.mapper_inspector_open;
+        egui::Window::new("Mapper Inspector")
+            .open(&mut mapper_inspector_open)
+            .default_width(400.0)
+            .show(ctx, |ui| self.mapper_inspector(ui));
+        if self.mapper_inspector_open != mapper_inspector_open {
+            self.set_mapper_inspector_open(mapper_inspector_open);
         }
     }
 
-    fn show_update_window(&mut self, ctx: &Context) {
-        let mut update_window_open = self.update_window_open;
-        let mut close_window = false;
-        egui::Window::new("Update Available")
-            .open(&mut update_window_open)
-            .resizable(false)
-            .show(ctx, |ui| {
-                ui.label(format!(
-                    "An update is available for TetaNES! (v{})",
-                    self.version.latest(),
-                ));
-                ui.hyperlink("https://github.com/lukexor/tetanes/releases");
+    pub fn set_mapper_inspector_open(&mut self, open: bool) {
+        self.mapper_inspector_open = open;
+        self.tx.nes_event(EmulationEvent::ShowMapperInspector(open));
+        if !open {
+            self.mapper_inspector = MapperInspector::default();
+        }
+    }
 
-                ui.add_space(15.0);
-                ui.separator();
-                ui.add_space(15.0);
+    fn show_hex_editor_window(&mut self, ctx: &Context) {
+        let mut hex_editor_open = self.hex_editor_open;
+        egui::Window::new("Hex Editor")
+            .open(&mut hex_editor_open)
+            .default_width(600.0)
+            .show(ctx, |ui| self.hex_editor(ui));
+        if self.hex_editor_open != hex_editor_open {
+            self.set_hex_editor_open(hex_editor_open);
+        }
+    }
 
-                ui.label("Would you like to install it and restart?");
-                ui.add_space(15.0);
+    pub fn set_hex_editor_open(&mut self, open: bool) {
+        self.hex_editor_open = open;
+        self.tx.nes_event(EmulationEvent::HexEditor(
+            open.then(|| self.hex_editor.view()),
+        ));
+        if !open {
+            self.hex_editor.page = None;
+        }
+    }
 
-                ui.horizontal(|ui| {
-                    let res = ui.button("Continue").on_hover_text(format!(
-                        "Install the latest version (v{}) restart TetaNES.",
-                        self.version.current()
-                    ));
-                    if res.clicked() {
-                        if let Err(err) = self.version.install_update_and_restart() {
-                            self.add_message(
-                                MessageType::Error,
-                                format!("Failed to install update: {err}"),
-                            );
-                            close_window = true;
-                        }
-                    }
-                    let res = ui.button("Cancel").on_hover_text(format!(
-                        "Keep the current version of TetaNES (v{}).",
-                        self.version.current()
-                    ));
-                    if res.clicked() {
-                        close_window = true;
-                    }
-                });
-            });
-        if close_window {
-            update_window_open = false;
+    fn show_ram_search_window(&mut self, ctx: &Context) {
+        let mut ram_search_open = self.ram_search_open;
+        egui::Window::new("RAM Search")
+            .open(&mut ram_search_open)
+            .default_width(360.0)
+            .show(ctx, |ui| self.ram_search(ui));
+        if self.ram_search_open != ram_search_open {
+            self.set_ram_search_open(ram_search_open);
         }
-        self.update_window_open = update_window_open;
     }
 
-    fn menu_bar(&mut self, ui: &mut Ui, cfg: &mut Config) {
-        #[cfg(feature = "profiling")]
-        puffin::profile_function!();
-
-        ui.set_enabled(self.pending_keybind.is_none());
-
-        let inner_res = menu::bar(ui, |ui| {
-            ui.horizontal_wrapped(|ui| {
-                Self::toggle_dark_mode_button(ui, cfg);
-
-                ui.separator();
+    pub fn set_ram_search_open(&mut self, open: bool) {
+        self.ram_search_open = open;
+        self.tx.nes_event(EmulationEvent::ShowRamWatch(open));
+    }
 
-                ui.menu_button("📁 File", |ui| self.file_menu(ui, cfg));
-                ui.menu_button("🔧 Controls", |ui| self.controls_menu(ui, cfg));
-                ui.menu_button("⚙ Config", |ui| self.config_menu(ui, cfg));
-                // icon: screen
-                ui.menu_button("🖵 Window", |ui| self.window_menu(ui, cfg));
-                ui.menu_button("🕷 Debug", |ui| self.debug_menu(ui));
-                ui.menu_button("❓ Help", |ui| self.help_menu(ui));
-            });
-        });
-        let spacing = ui.style().spacing.item_spacing;
-        let border = 1.0;
-        let height = inner_res.response.rect.height() + spacing.y + border;
-        if height != self.menu_height {
-            self.menu_height = height;
-            self.resize_window = true;
+    /// Debugging windows and watches currently open, to restore the next time the ROM is loaded.
+    pub fn debug_session(&self) -> DebugSession {
+        DebugSession {
+            ram_watches: self.ram_search.watches.clone(),
+            ram_search_open: self.ram_search_open,
+            mapper_inspector_open: self.mapper_inspector_open,
+            input_diagnostics_open: self.input_diagnostics_open,
+            hex_editor: self
+                .hex_editor_open
+                .then_some((self.hex_editor.space, self.hex_editor.offset)),
         }
     }
 
-    pub fn toggle_dark_mode_button(ui: &mut Ui, cfg: &mut Config) {
-        if ui.ctx().style().visuals.dark_mode {
-            let button = Button::new("☀").frame(false);
-            let res = ui.add(button).on_hover_text("Switch to light mode");
-            if res.clicked() {
-                ui.ctx().set_visuals(Self::light_theme());
-                cfg.renderer.dark_theme = false;
-            }
-        } else {
-            let button = Button::new("🌙").frame(false);
-            let res = ui.add(button).on_hover_text("Switch to dark mode");
-            if res.clicked() {
-                ui.ctx().set_visuals(Self::dark_theme());
-                cfg.renderer.dark_theme = true;
+    pub fn restore_debug_session(&mut self, session: DebugSession) {
+        if self.ram_search.watches != session.ram_watches {
+            self.ram_search.watches = session.ram_watches;
+            self.tx
+                .nes_event(EmulationEvent::RamWatches(self.ram_search.watches.clone()));
+        }
+        if self.ram_search_open != session.ram_search_open {
+            self.set_ram_search_open(session.ram_search_open);
+        }
+        if self.mapper_inspector_open != session.mapper_inspector_open {
+            self.set_mapper_inspector_open(session.mapper_inspector_open);
+        }
+        if self.input_diagnostics_open != session.input_diagnostics_open {
+            self.set_input_diagnostics_open(session.input_diagnostics_open);
+        }
+        match session.hex_editor {
+            Some((space, offset)) => {
+                let editor = &mut self.hex_editor;
+                editor.space = space;
+                editor.offset = offset - offset % HexEditor::ROW_LEN;
+                editor.selected = None;
+                editor.page = None;
+                self.set_hex_editor_open(true);
             }
+            None if self.hex_editor_open => self.set_hex_editor_open(false),
+            None => (),
         }
     }
 
-    fn file_menu(&mut self, ui: &mut Ui, cfg: &mut Config) {
-        #[cfg(feature = "profiling")]
-        puffin::profile_function!();
-
-        ui.allocate_space(Vec2::new(Self::MENU_WIDTH, 0.0));
+    fn update_hex_editor_view(&mut self) {
+        self.tx
+            .nes_event(EmulationEvent::HexEditor(Some(self.hex_editor.view())));
+    }
 
-        let button =
-            Button::new("📂 Load ROM...").shortcut_text(self.fmt_shortcut(UiAction::LoadRom));
-        if ui.add(button).clicked() {
-            if self.loaded_rom.is_some() {
-                self.paused = true;
-                self.tx.nes_event(EmulationEvent::Pause(true));
-            }
-            // NOTE: Due to some platforms file dialogs blocking the event loop,
-            // loading requires a round-trip in order for the above pause to
-            // get processed.
-            self.tx.nes_event(UiEvent::LoadRomDialog);
-            ui.close_menu();
+    pub fn set_memory_page(&mut self, page: MemoryPage) {
+        let editor = &mut self.hex_editor;
+        if page.space != editor.space {
+            return;
         }
+        // Memory spaces can shrink when a different ROM is loaded
+        let clamped = editor.offset >= page.len && page.len > 0;
+        if clamped {
+            editor.offset = 0;
+            editor.selected = None;
+        }
+        editor.page = Some(page);
+        if clamped {
+            self.update_hex_editor_view();
+        }
+    }
 
-        ui.menu_button("🍺 Homebrew ROM...", |ui| self.homebrew_rom_menu(ui));
-
-        ui.add_enabled_ui(self.loaded_rom.is_some(), |ui| {
-            let button = Button::new("⏹ Unload ROM...")
-                .shortcut_text(self.fmt_shortcut(UiAction::UnloadRom));
-            let res = ui.add(button).on_disabled_hover_text(Self::NO_ROM_LOADED);
-            if res.clicked() {
-                self.tx.nes_event(EmulationEvent::UnloadRom);
-                ui.close_menu();
-            }
-
-            let button =
-                Button::new("🎞 Load Replay").shortcut_text(self.fmt_shortcut(UiAction::LoadReplay));
-            let res = ui
-                .add(button)
-                .on_hover_text("Load a replay file for the currently loaded ROM.")
-                .on_disabled_hover_text(Self::NO_ROM_LOADED);
-            if res.clicked() {
-                self.paused = true;
-                self.tx.nes_event(EmulationEvent::Pause(true));
-                // NOTE: Due to some platforms file dialogs blocking the event loop,
-                // loading requires a round-trip in order for the above pause to
-                // get processed.
-                self.tx.nes_event(UiEvent::LoadReplayDialog);
-                ui.close_menu();
+    pub fn set_memory_search_result(&mut self, found: Option<usize>) {
+        let editor = &mut self.hex_editor;
+        match found {
+            Some(addr) => {
+                editor.selected = Some(addr);
+                editor.edit.clear();
+                editor.search_status = None;
+                editor.scroll_to(addr);
+                self.update_hex_editor_view();
             }
-        });
+            None => editor.search_status = Some("Not found"),
+        }
+    }
 
-        // TODO: support saves and recent games on wasm? Requires storing the data
-        if platform::supports(platform::Feature::Filesystem) {
-            ui.menu_button("🗄 Recently Played...", |ui| {
-                use tetanes_core::fs;
+    pub fn set_rom_read_progress(&mut self, progress: Option<ReadProgress>) {
+        let started = self
+            .rom_read_progress
+            .as_ref()
+            .map_or_else(Instant::now, |(started, _)| *started);
+        self.rom_read_progress = progress.map(|progress| (started, progress));
+    }
 
-                if cfg.renderer.recent_roms.is_empty() {
-                    ui.label("No recent ROMs");
-                } else {
-                    ui.allocate_space(Vec2::new(Self::MENU_WIDTH, 0.0));
+    fn show_rom_read_window(&mut self, ctx: &Context) {
+        let Some((started, progress)) = &self.rom_read_progress else {
+            return;
+        };
+        // Avoid flashing the window for ROMs that are read quickly
+        let elapsed = started.elapsed();
+        if elapsed < Self::ROM_READ_WINDOW_DELAY {
+            ctx.request_repaint_after(Self::ROM_READ_WINDOW_DELAY - elapsed);
+            return;
+        }
 
-                    ScrollArea::vertical().show(ui, |ui| {
-                        // TODO: add timestamp, save slots, and screenshot
-                        for rom in &cfg.renderer.recent_roms {
-                            if ui.button(fs::filename(rom)).clicked() {
-                                self.tx
-                                    .nes_event(EmulationEvent::LoadRomPath(rom.to_path_buf()));
-                                ui.close_menu();
-                            }
-                        }
-                    });
+        let mut cancelled = false;
+        egui::Window::new("Loading ROM")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(&progress.name);
+                let bar = match progress.fraction() {
+                    Some(fraction) => ProgressBar::new(fraction).show_percentage(),
+                    None => ProgressBar::new(0.0).animate(true),
+                };
+                ui.add(bar.desired_width(300.0));
+                if progress.retries > 0 {
+                    ui.label(format!(
+                        "Retried {} failed reads from slow or unreliable storage...",
+                        progress.retries
+                    ));
+                }
+                if ui.button("Cancel").clicked() {
+                    progress.cancel();
+                    cancelled = true;
                 }
             });
+        if cancelled {
+            self.rom_read_progress = None;
+        } else {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+    }
 
-            ui.separator();
+    fn show_resume_session_window(&mut self, ctx: &Context) {
+        let Some(name) = &self.resume_session_prompt else {
+            return;
+        };
 
-            ui.add_enabled_ui(self.loaded_rom.is_some(), |ui| {
-                let button = Button::new("💾 Save State")
-                    .shortcut_text(self.fmt_shortcut(DeckAction::SaveState));
-                let res = ui
-                    .add(button)
-                    .on_hover_text("Save the current state to the selected save slot.")
-                    .on_disabled_hover_text(Self::NO_ROM_LOADED);
-                if res.clicked() {
-                    self.tx
-                        .nes_event(EmulationEvent::SaveState(cfg.emulation.save_slot));
-                };
+        let mut close_window = false;
+        egui::Window::new("Resume Session")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!("Resume {name} where you left off?"));
+                ui.add_space(15.0);
 
-                let button = Button::new("⎗ Load State")
-                    .shortcut_text(self.fmt_shortcut(DeckAction::LoadState));
-                let res = ui
-                    .add(button)
-                    .on_hover_text("Load a previous state from the selected save slot.")
-                    .on_disabled_hover_text(Self::NO_ROM_LOADED);
-                if res.clicked() {
-                    self.tx
-                        .nes_event(EmulationEvent::LoadState(cfg.emulation.save_slot));
-                }
+                ui.horizontal(|ui| {
+                    let res = ui
+                        .button("⟲ Resume")
+                        .on_hover_text("Load the session saved when the ROM was last exited.");
+                    if res.clicked() {
+                        self.tx.nes_event(EmulationEvent::ResumeSession);
+                        close_window = true;
+                    }
+                    let res = ui
+                        .button("Start Over")
+                        .on_hover_text("Keep playing from the start.");
+                    if res.clicked() {
+                        close_window = true;
+                    }
+                });
             });
+        if close_window {
+            self.resume_session_prompt = None;
+        }
+    }
 
-            // icon: # in a square
-            ui.menu_button("󾠬 Save Slot...", |ui| {
-                self.save_slot_radio(ui, cfg, ShowShortcut::Yes);
-            });
+    fn show_lan_pair_window(&mut self, ctx: &Context, cfg: &mut Config) {
+        let Some(peer) = &self.lan_pair_request else {
+            return;
+        };
 
-            ui.separator();
+        let mut close_window = false;
+        egui::Window::new("Pair Device")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} wants to send save states to this device.",
+                    peer.name
+                ));
+                ui.label(format!("Fingerprint: {}", peer.fingerprint()));
+                ui.label("Only pair if the sending device shows the same fingerprint.");
+                ui.add_space(15.0);
 
-            let button = Button::new("⎆ Quit").shortcut_text(self.fmt_shortcut(UiAction::Quit));
-            if ui.add(button).clicked() {
-                self.tx.nes_event(UiEvent::Terminate);
-                ui.close_menu();
-            };
+                ui.horizontal(|ui| {
+                    let res = ui
+                        .button("Pair")
+                        .on_hover_text("Accept save states from this device from now on.");
+                    if res.clicked() {
+                        let trusted = &mut cfg.emulation.lan_trusted_peers;
+                        trusted.retain(|trusted| trusted.key != peer.key);
+                        trusted.push(peer.clone());
+                        self.tx
+                            .nes_event(ConfigEvent::LanTrustedPeers(trusted.clone()));
+                        close_window = true;
+                    }
+                    if ui.button("Ignore").clicked() {
+                        close_window = true;
+                    }
+                });
+            });
+        if close_window {
+            self.lan_pair_request = None;
         }
     }
 
-    fn homebrew_rom_menu(&mut self, ui: &mut Ui) {
-        ScrollArea::vertical().show(ui, |ui| {
-            for rom in HOMEBREW_ROMS {
+    fn show_lan_state_window(&mut self, ctx: &Context) {
+        let Some((from, rom_name)) = &self.lan_state_prompt else {
+            return;
+        };
+
+        let mut close_window = false;
+        egui::Window::new("Received Save State")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Load the save state for {rom_name} sent from {from}?"
+                ));
+                ui.add_space(15.0);
+
                 ui.horizontal(|ui| {
-                    if ui.button(rom.name).clicked() {
-                        self.tx
-                            .nes_event(EmulationEvent::LoadRom((rom.name.to_string(), rom.data())));
-                        ui.close_menu();
-                    }
-                    let res = ui.button("ℹ").on_hover_ui(|ui| {
-                        ui.set_max_width(400.0);
-                        Self::about_homebrew(ui, rom);
-                    });
+                    let res = ui.button("Load").on_hover_text(
+                        "Replace the current game progress with the received state.",
+                    );
                     if res.clicked() {
-                        self.about_homebrew_rom_open = Some(rom);
-                        ui.close_menu();
+                        self.tx.nes_event(EmulationEvent::LoadLanState);
+                        close_window = true;
+                    }
+                    if ui.button("Discard").clicked() {
+                        self.tx.nes_event(EmulationEvent::DiscardLanState);
+                        close_window = true;
                     }
                 });
-            }
-        });
+            });
+        if close_window {
+            self.lan_state_prompt = None;
+        }
     }
 
-    fn controls_menu(&mut self, ui: &mut Ui, cfg: &mut Config) {
+    pub fn set_rewind_timeline(&mut self, ctx: &Context, timeline: &Timeline) {
+        self.rewind_thumbnails = timeline
+            .thumbnails
+            .iter()
+            .map(|(index, rgba)| {
+                let image = ColorImage::from_rgba_unmultiplied(
+                    [RewindFrame::THUMBNAIL_WIDTH, RewindFrame::THUMBNAIL_HEIGHT],
+                    rgba,
+                );
+                let texture = ctx.load_texture(
+                    format!("rewind_thumbnail_{index}"),
+                    image,
+                    TextureOptions::NEAREST,
+                );
+                (*index, texture)
+            })
+            .collect();
+        self.rewind_timeline = timeline.clone();
+        self.rewind_timeline_index = timeline.frames.len().saturating_sub(1);
+    }
+
+    fn open_rewind_timeline(&mut self) {
+        self.rewind_timeline_open = true;
+        self.paused = true;
+        self.tx.nes_event(EmulationEvent::Pause(true));
+        self.tx.nes_event(EmulationEvent::RewindTimeline);
+    }
+
+    fn close_rewind_timeline(&mut self) {
+        self.rewind_timeline_open = false;
+        self.rewind_timeline = Timeline::default();
+        self.rewind_thumbnails.clear();
+        self.paused = false;
+        self.tx.nes_event(EmulationEvent::Pause(false));
+    }
+
+    fn library(&mut self, ui: &mut Ui, cfg: &mut Config) {
         #[cfg(feature = "profiling")]
         puffin::profile_function!();
 
-        ui.allocate_space(Vec2::new(Self::MENU_WIDTH, 0.0));
+        egui::CollapsingHeader::new("Directories")
+            .default_open(cfg.renderer.library_dirs.is_empty())
+            .show(ui, |ui| self.library_dirs(ui, cfg));
 
-        ui.add_enabled_ui(self.loaded_rom.is_some(), |ui| {
-            let button = Button::new(if self.paused {
-                "▶ Resume"
-            } else {
-                "⏸ Pause"
-            })
-            .shortcut_text(self.fmt_shortcut(UiAction::TogglePause));
-            let res = ui.add(button).on_disabled_hover_text(Self::NO_ROM_LOADED);
-            if res.clicked() {
-                self.paused = !self.paused;
-                self.tx.nes_event(EmulationEvent::Pause(self.paused));
-                ui.close_menu();
-            };
-        });
+        ui.separator();
 
-        let button = Button::new(if cfg.audio.enabled {
-            "🔇 Mute"
-        } else {
-            "🔊 Unmute"
-        })
-        .shortcut_text(self.fmt_shortcut(Setting::ToggleAudio));
+        ui.add(
+            TextEdit::singleline(&mut self.library_filter)
+                .hint_text("Filter by title...")
+                .desired_width(f32::INFINITY),
+        );
+        let filter = self.library_filter.to_lowercase();
+        let matches = |entry: &&LibraryEntry| entry.title.to_lowercase().contains(&filter);
+
+        let recent = self
+            .library
+            .recently_played()
+            .filter(matches)
+            .take(5)
+            .cloned()
+            .collect::<Vec<_>>();
+        if !recent.is_empty() {
+            ui.strong("Recently Played");
+            for entry in &recent {
+                self.library_recent_entry(ui, cfg, entry);
+            }
+            ui.separator();
+        }
 
-        if ui.add(button).clicked() {
-            cfg.audio.enabled = !cfg.audio.enabled;
-            self.tx
-                .nes_event(ConfigEvent::AudioEnabled(cfg.audio.enabled));
-        };
+        ui.strong("All ROMs");
+        if self.library.entries.is_empty() {
+            ui.label("No ROMs found. Add a directory to scan for ROMs.");
+            return;
+        }
+        let mut play = None;
+        ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+            let grid = Grid::new("library_entries")
+                .num_columns(4)
+                .spacing([20.0, 6.0])
+                .striped(true);
+            grid.show(ui, |ui| {
+                for entry in self.library.entries.iter().filter(matches) {
+                    ui.label(&entry.title)
+                        .on_hover_text(entry.path.to_string_lossy());
+                    ui.label(entry.region.as_ref());
+                    ui.label(format!("Mapper {}", entry.mapper_num));
+                    if ui.button("▶ Play").clicked() {
+                        play = Some(entry.path.clone());
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+        if let Some(path) = play {
+            self.tx.nes_event(EmulationEvent::LoadRomPath(path));
+        }
+    }
 
-        ui.separator();
+    fn library_dirs(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let mut remove = None;
+        for (index, dir) in cfg.renderer.library_dirs.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button("✖").on_hover_text("Remove directory.").clicked() {
+                    remove = Some(index);
+                }
+                ui.label(dir.to_string_lossy());
+            });
+        }
+        if let Some(index) = remove {
+            cfg.renderer.library_dirs.remove(index);
+            self.tx.nes_event(UiEvent::LibraryScan);
+        }
 
-        ui.add_enabled_ui(self.loaded_rom.is_some(), |ui| {
-            if platform::supports(platform::Feature::Filesystem) {
-                ui.add_enabled_ui(cfg.emulation.rewind, |ui| {
-                    let button = Button::new("⟲ Instant Rewind")
-                        .shortcut_text(self.fmt_shortcut(Feature::InstantRewind));
-                    let disabled_hover_text = if self.loaded_rom.is_none() {
-                        Self::NO_ROM_LOADED
-                    } else {
-                        "Rewind can be enabled under the `Config` menu."
-                    };
-                    let res = ui
-                        .add(button)
-                        .on_hover_text("Instantly rewind state to a previous point.")
-                        .on_disabled_hover_text(disabled_hover_text);
-                    if res.clicked() {
-                        self.tx.nes_event(EmulationEvent::InstantRewind);
-                        ui.close_menu();
-                    };
-                });
+        ui.horizontal(|ui| {
+            ui.label("Game Database:");
+            match &cfg.renderer.game_database {
+                Some(path) => {
+                    ui.label(fs::filename(path))
+                        .on_hover_text(path.to_string_lossy());
+                }
+                None => {
+                    ui.label("None");
+                }
             }
-
-            let button = Button::new("🔃 Reset")
-                .shortcut_text(self.fmt_shortcut(DeckAction::Reset(ResetKind::Soft)));
-            let res = ui
-                .add(button)
-                .on_hover_text("Emulate a soft reset of the NES.")
-                .on_disabled_hover_text(Self::NO_ROM_LOADED);
+            let res = ui.button("Browse...").on_hover_text(
+                "Select a No-Intro style DAT file to identify ROM titles and regions.",
+            );
             if res.clicked() {
-                self.tx.nes_event(EmulationEvent::Reset(ResetKind::Soft));
-                ui.close_menu();
-            };
+                self.tx.nes_event(UiEvent::LibraryDatabaseDialog);
+            }
+            if cfg.renderer.game_database.is_some() && ui.button("✖").clicked() {
+                cfg.renderer.game_database = None;
+                self.tx.nes_event(UiEvent::LibraryScan);
+            }
+        });
 
-            let button = Button::new("🔌 Power Cycle")
-                .shortcut_text(self.fmt_shortcut(DeckAction::Reset(ResetKind::Hard)));
-            let res = ui
-                .add(button)
-                .on_hover_text("Emulate a power cycle of the NES.")
-                .on_disabled_hover_text(Self::NO_ROM_LOADED);
+        ui.horizontal(|ui| {
+            if ui.button("➕ Add Directory...").clicked() {
+                self.tx.nes_event(UiEvent::LibraryAddDirDialog);
+            }
+            let res = ui.add_enabled(!self.library_scanning, Button::new("🔄 Rescan"));
             if res.clicked() {
-                self.tx.nes_event(EmulationEvent::Reset(ResetKind::Hard));
-                ui.close_menu();
-            };
+                self.tx.nes_event(UiEvent::LibraryScan);
+            }
+            if self.library_scanning {
+                ui.spinner();
+            }
         });
+    }
 
-        if platform::supports(platform::Feature::Filesystem) {
-            ui.separator();
+    fn library_recent_entry(&mut self, ui: &mut Ui, cfg: &Config, entry: &LibraryEntry) {
+        ui.horizontal(|ui| {
+            if let Some(texture) = self.library_thumbnail(ui.ctx(), entry.crc32) {
+                ui.add(
+                    Image::from_texture(SizedTexture::from_handle(&texture))
+                        .max_size(Vec2::new(64.0, 60.0)),
+                );
+            }
+            ui.vertical(|ui| {
+                ui.label(RichText::new(&entry.title).strong())
+                    .on_hover_text(entry.path.to_string_lossy());
+                if let Some(last_played) = entry.last_played.and_then(|timestamp| {
+                    chrono::TimeZone::timestamp_opt(&chrono::Local, timestamp, 0).single()
+                }) {
+                    ui.label(format!(
+                        "Last played {}",
+                        last_played.format("%Y-%m-%d %H:%M")
+                    ));
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("▶ Play").clicked() {
+                        self.tx
+                            .nes_event(EmulationEvent::LoadRomPath(entry.path.clone()));
+                    }
+                    let slot = cfg.emulation.save_slot;
+                    let has_save = Config::save_path(fs::filename(&entry.path), slot)
+                        .is_some_and(|path| path.exists());
+                    let res = ui
+                        .add_enabled(has_save, Button::new("⟲ Resume"))
+                        .on_hover_text(format!("Load the state saved in slot {slot}."))
+                        .on_disabled_hover_text(format!("No state is saved in slot {slot}."));
+                    if res.clicked() {
+                        self.tx
+                            .nes_event(EmulationEvent::LoadRomPath(entry.path.clone()));
+                        if !cfg.emulation.auto_load {
+                            self.tx.nes_event(EmulationEvent::LoadState(slot));
+                        }
+                    }
+                });
+            });
+        });
+    }
 
-            ui.add_enabled_ui(self.loaded_rom.is_some(), |ui| {
-                let button = Button::new("🖼 Screenshot")
-                    .shortcut_text(self.fmt_shortcut(Feature::TakeScreenshot));
-                let res = ui.add(button).on_disabled_hover_text(Self::NO_ROM_LOADED);
-                if res.clicked() {
-                    self.tx.nes_event(EmulationEvent::Screenshot);
-                    ui.close_menu();
-                };
+    /// Load the cached thumbnail for a library entry, if one has been saved.
+    fn library_thumbnail(&mut self, ctx: &Context, crc32: u32) -> Option<TextureHandle> {
+        self.library_thumbnails
+            .entry(crc32)
+            .or_insert_with(|| {
+                let path = library::thumbnail_path(crc32)?;
+                let image = image::load_from_memory(&fs::load_raw(path).ok()?)
+                    .ok()?
+                    .to_rgba8();
+                let size = [image.width() as usize, image.height() as usize];
+                Some(ctx.load_texture(
+                    format!("library_thumbnail_{crc32:08X}"),
+                    ColorImage::from_rgba_unmultiplied(size, image.as_raw()),
+                    TextureOptions::LINEAR,
+                ))
+            })
+            .clone()
+    }
 
-                let button_txt = if self.replay_recording {
-                    "⏹ Stop Replay Recording"
-                } else {
-                    "🎞 Record Replay"
-                };
-                let button = Button::new(button_txt)
-                    .shortcut_text(self.fmt_shortcut(Feature::ToggleReplayRecording));
+    fn playlist(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        let running = self.playlist_run.as_ref().filter(|run| !run.is_finished());
+        if let Some(run) = running {
+            if let Some(entry) = run.current() {
+                ui.label(format!(
+                    "Playing {} of {}: {}",
+                    run.current_index() + 1,
+                    run.playlist().entries.len(),
+                    entry.name()
+                ));
+                if let Some(limit) = entry.time_limit {
+                    ui.label(format!("Time Limit: {}", Self::fmt_playlist_time(limit)));
+                }
+                if let Some(condition) = entry.completion {
+                    ui.label(format!("Complete When: {condition}"));
+                }
+            }
+            ui.horizontal(|ui| {
                 let res = ui
-                    .add(button)
-                    .on_hover_text("Record or stop recording a game replay file.")
-                    .on_disabled_hover_text(Self::NO_ROM_LOADED);
+                    .button("⏭ Skip")
+                    .on_hover_text("End the current game and load the next one.");
                 if res.clicked() {
-                    self.tx
-                        .nes_event(EmulationEvent::ReplayRecord(!self.replay_recording));
-                    ui.close_menu();
-                };
-
-                let button_txt = if self.audio_recording {
-                    "⏹ Stop Audio Recording"
-                } else {
-                    "🎤 Record Audio"
-                };
-                let button = Button::new(button_txt)
-                    .shortcut_text(self.fmt_shortcut(Feature::ToggleAudioRecording));
+                    self.tx.nes_event(EmulationEvent::PlaylistSkip);
+                }
                 let res = ui
-                    .add(button)
-                    .on_hover_text("Record or stop recording a audio file.")
-                    .on_disabled_hover_text(Self::NO_ROM_LOADED);
+                    .button("⏹ Stop")
+                    .on_hover_text("End the playlist, leaving the current game running.");
                 if res.clicked() {
-                    self.tx
-                        .nes_event(EmulationEvent::AudioRecord(!self.audio_recording));
-                    ui.close_menu();
-                };
+                    self.tx.nes_event(EmulationEvent::PlaylistStop);
+                }
             });
+        } else {
+            self.playlist_editor(ui, cfg);
+        }
+
+        if let Some(run) = self
+            .playlist_run
+            .as_ref()
+            .filter(|run| !run.results().is_empty())
+        {
+            ui.separator();
+            ui.strong(format!("Results: {}", run.playlist().name));
+            Self::playlist_results(ui, run);
         }
     }
 
-    fn config_menu(&mut self, ui: &mut Ui, cfg: &mut Config) {
-        #[cfg(feature = "profiling")]
-        puffin::profile_function!();
+    fn playlist_editor(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let playlist = &mut cfg.emulation.playlist;
+        ui.horizontal(|ui| {
+            ui.label("Name:");
+            ui.text_edit_singleline(&mut playlist.name);
+        });
 
-        ui.allocate_space(Vec2::new(Self::MENU_WIDTH, 0.0));
+        ui.separator();
 
-        self.cycle_acurate_checkbox(ui, cfg, ShowShortcut::Yes);
-        self.zapper_checkbox(ui, cfg, ShowShortcut::Yes);
-        self.rewind_checkbox(ui, cfg, ShowShortcut::Yes);
-        self.overscan_checkbox(ui, cfg, ShowShortcut::Yes);
+        if playlist.entries.is_empty() {
+            ui.label("Add ROMs to play them back to back, e.g. for relay races or marathons.");
+        }
 
-        ui.separator();
+        let mut swap = None;
+        let mut remove = None;
+        let len = playlist.entries.len();
+        ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+            let grid = Grid::new("playlist_entries")
+                .num_columns(4)
+                .spacing([20.0, 6.0])
+                .striped(true);
+            grid.show(ui, |ui| {
+                for (index, entry) in playlist.entries.iter_mut().enumerate() {
+                    ui.label(entry.name()).on_hover_text(&entry.path);
 
-        ui.menu_button("🕒 Emulation Speed...", |ui| {
-            let speed = cfg.emulation.speed;
-            let button =
-                Button::new("Increment").shortcut_text(self.fmt_shortcut(Setting::IncrementSpeed));
-            if ui.add(button).clicked() {
-                let new_speed = cfg.increment_speed();
-                if speed != new_speed {
-                    self.tx.nes_event(ConfigEvent::Speed(new_speed));
-                }
-            }
+                    ui.horizontal(|ui| {
+                        let mut limited = entry.time_limit.is_some();
+                        ui.checkbox(&mut limited, "Time Limit")
+                            .on_hover_text("End the game after this much emulated time.");
+                        let mut minutes = entry
+                            .time_limit
+                            .map_or(10.0, |limit| limit.as_secs_f32() / 60.0);
+                        let drag = DragValue::new(&mut minutes)
+                            .speed(0.1)
+                            .clamp_range(0.1..=600.0)
+                            .max_decimals(1)
+                            .suffix(" min");
+                        ui.add_enabled(limited, drag);
+                        entry.time_limit =
+                            limited.then(|| Duration::from_secs_f32(minutes * 60.0));
+                    });
 
-            let button =
-                Button::new("Decrement").shortcut_text(self.fmt_shortcut(Setting::DecrementSpeed));
-            if ui.add(button).clicked() {
-                let new_speed = cfg.decrement_speed();
-                if speed != new_speed {
-                    self.tx.nes_event(ConfigEvent::Speed(new_speed));
+                    ui.horizontal(|ui| {
+                        let mut triggered = entry.completion.is_some();
+                        ui.checkbox(&mut triggered, "Complete When").on_hover_text(
+                            "End the game once a byte of CPU memory matches, e.g. a \"game cleared\" flag.",
+                        );
+                        let mut condition = entry.completion.unwrap_or_default();
+                        ui.add_enabled_ui(triggered, |ui| {
+                            ui.add(
+                                DragValue::new(&mut condition.addr)
+                                    .hexadecimal(4, false, true)
+                                    .prefix("$"),
+                            );
+                            egui::ComboBox::from_id_source(("playlist_comparison", index))
+                                .width(40.0)
+                                .selected_text(condition.comparison.as_ref())
+                                .show_ui(ui, |ui| {
+                                    for comparison in Comparison::as_slice() {
+                                        ui.selectable_value(
+                                            &mut condition.comparison,
+                                            *comparison,
+                                            comparison.as_ref(),
+                                        );
+                                    }
+                                });
+                            ui.add(
+                                DragValue::new(&mut condition.value)
+                                    .hexadecimal(2, false, true)
+                                    .prefix("$"),
+                            );
+                        });
+                        entry.completion = triggered.then_some(condition);
+                    });
+
+                    ui.horizontal(|ui| {
+                        // icon: up triangle
+                        if ui.add_enabled(index > 0, Button::new("⏶")).clicked() {
+                            swap = Some((index, index - 1));
+                        }
+                        // icon: down triangle
+                        if ui.add_enabled(index + 1 < len, Button::new("⏷")).clicked() {
+                            swap = Some((index, index + 1));
+                        }
+                        if ui.button("🗑").on_hover_text("Remove from playlist.").clicked() {
+                            remove = Some(index);
+                        }
+                    });
+                    ui.end_row();
                 }
-            }
-            self.speed_slider(ui, cfg);
+            });
         });
-        ui.menu_button("🏃 Run Ahead...", |ui| self.run_ahead_slider(ui, cfg));
+        if let Some((a, b)) = swap {
+            playlist.entries.swap(a, b);
+        }
+        if let Some(index) = remove {
+            playlist.entries.remove(index);
+        }
 
         ui.separator();
 
-        ui.menu_button("🌉 Video Filter...", |ui| {
-            self.video_filter_radio(ui, cfg)
-        });
-        ui.menu_button("🌎 Nes Region...", |ui| self.nes_region_radio(ui, cfg));
-        ui.menu_button("🎮 Four Player...", |ui| self.four_player_radio(ui, cfg));
-        ui.menu_button("📓 Game Genie Codes...", |ui| {
-            self.genie_codes_entry(ui, cfg)
+        ui.horizontal(|ui| {
+            if ui.button("➕ Add ROM...").clicked() {
+                self.tx.nes_event(UiEvent::PlaylistAddRomDialog);
+            }
+            ui.add_enabled_ui(!playlist.entries.is_empty(), |ui| {
+                let res = ui
+                    .button("▶ Start")
+                    .on_hover_text("Play each game in order, advancing when it ends.");
+                if res.clicked() {
+                    self.tx
+                        .nes_event(EmulationEvent::PlaylistStart(playlist.clone()));
+                }
+                if ui.button("🗑 Clear").clicked() {
+                    playlist.entries.clear();
+                }
+            });
         });
+    }
 
-        ui.separator();
+    fn playlist_results(ui: &mut Ui, run: &PlaylistRun) {
+        let grid = Grid::new("playlist_results")
+            .num_columns(4)
+            .spacing([20.0, 6.0])
+            .striped(true);
+        grid.show(ui, |ui| {
+            ui.strong("Game");
+            ui.strong("Result");
+            ui.strong("Time");
+            ui.strong("Frames");
+            ui.end_row();
 
-        let mut preferences_open = self.preferences_open;
-        // icon: gear
-        let toggle = ToggleValue::new(&mut preferences_open, "⛭ Preferences")
-            .shortcut_text(self.fmt_shortcut(Menu::Preferences));
-        if ui.add(toggle).clicked() {
-            self.preferences_open = preferences_open;
-            ui.close_menu();
-        }
+            for result in run.results() {
+                ui.label(&result.name);
+                ui.label(result.outcome.to_string());
+                ui.label(Self::fmt_playlist_time(result.elapsed));
+                ui.label(result.frames.to_string());
+                ui.end_row();
+            }
+        });
+    }
 
-        let mut keybinds_open = self.keybinds_open;
-        // icon: keyboard
-        let toggle = ToggleValue::new(&mut keybinds_open, "🖮 Keybinds")
-            .shortcut_text(self.fmt_shortcut(Menu::Keybinds));
-        if ui.add(toggle).clicked() {
-            self.keybinds_open = keybinds_open;
-            ui.close_menu();
-        };
+    fn fmt_playlist_time(time: Duration) -> String {
+        let secs = time.as_secs_f32();
+        format!("{}:{:04.1}", (secs / 60.0) as u32, secs % 60.0)
     }
 
-    fn window_menu(&mut self, ui: &mut Ui, cfg: &mut Config) {
+    fn rewind_timeline(&mut self, ui: &mut Ui) {
         #[cfg(feature = "profiling")]
         puffin::profile_function!();
 
-        ui.allocate_space(Vec2::new(Self::MENU_WIDTH, 0.0));
+        let frames = &self.rewind_timeline.frames;
+        let Some(last) = frames.len().checked_sub(1) else {
+            ui.label("No rewind frames have been recorded yet.");
+            return;
+        };
 
-        ui.menu_button("📏 Window Scale...", |ui| {
-            let scale = cfg.renderer.scale;
-            let button =
-                Button::new("Increment").shortcut_text(self.fmt_shortcut(Setting::IncrementScale));
-            if ui.add(button).clicked() {
-                let new_scale = cfg.increment_scale();
-                if scale != new_scale {
-                    self.resize_window = true;
-                    self.resize_texture = true;
-                    self.tx.nes_event(ConfigEvent::Scale(cfg.renderer.scale));
-                }
-            }
+        let mut index = self.rewind_timeline_index.min(last);
+        ui.spacing_mut().slider_width = 400.0;
+        let slider = Slider::new(&mut index, 0..=last)
+            .show_value(false)
+            .text(format!("-{:.1}s", frames[index].age.as_secs_f32()));
+        let res = ui
+            .add(slider)
+            .on_hover_text("Drag to scrub through the rewind buffer.");
+        if res.changed() {
+            self.rewind_timeline_index = index;
+            self.tx.nes_event(EmulationEvent::RewindSeek(index));
+        }
+        ui.label(format!("Frame: {}", frames[index].frame_number));
 
-            let button =
-                Button::new("Decrement").shortcut_text(self.fmt_shortcut(Setting::DecrementScale));
-            if ui.add(button).clicked() {
-                let new_scale = cfg.decrement_scale();
-                if scale != new_scale {
-                    self.resize_window = true;
-                    self.resize_texture = true;
-                    self.tx.nes_event(ConfigEvent::Scale(cfg.renderer.scale));
-                }
-            }
+        ui.separator();
 
-            self.window_scale_radio(ui, cfg);
+        ScrollArea::horizontal().show(ui, |ui| {
+            ui.horizontal(|ui| {
+                for (thumbnail_index, texture) in &self.rewind_thumbnails {
+                    let image = Image::from_texture(SizedTexture::from_handle(texture))
+                        .sense(Sense::click());
+                    let res = ui.add(image).on_hover_text(format!(
+                        "-{:.1}s",
+                        frames[*thumbnail_index].age.as_secs_f32()
+                    ));
+                    if res.clicked() {
+                        self.rewind_timeline_index = *thumbnail_index;
+                        self.tx
+                            .nes_event(EmulationEvent::RewindSeek(*thumbnail_index));
+                    }
+                }
+            });
         });
 
         ui.separator();
 
-        self.fullscreen_checkbox(ui, cfg, ShowShortcut::Yes);
+        // icon: play
+        if ui
+            .button("▶ Resume")
+            .on_hover_text("Resume emulation from the selected point, discarding newer frames.")
+            .clicked()
+        {
+            self.close_rewind_timeline();
+        }
+    }
 
-        if platform::supports(platform::Feature::Viewports) {
-            ui.add_enabled_ui(!cfg.renderer.fullscreen, |ui| {
-                let mut embed_viewports = ui.ctx().embed_viewports();
-                // icon: maximize
-                let res = ui
-                    .checkbox(&mut embed_viewports, "🗖 Embed viewports")
-                    .on_disabled_hover_text(
-                        "Non-embedded viewports are not supported while in fullscreen.",
-                    );
-                if res.clicked() {
-                    cfg.renderer.embed_viewports = embed_viewports;
-                    ui.ctx().set_embed_viewports(embed_viewports);
+    fn show_preferences_viewport(&mut self, ctx: &Context, cfg: &mut Config) {
+        if !self.preferences_open {
+            return;
+        }
+
+        let title = "Preferences";
+        // TODO: Make this deferred? Requires `tx` and `cfg` to be Send + Sync
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("preferences"),
+            egui::ViewportBuilder::default().with_title(title),
+            |ctx, class| {
+                if class == ViewportClass::Embedded {
+                    let mut preferences_open = self.preferences_open;
+                    let mut default_rect = ctx.available_rect();
+                    let border = 1.0;
+                    default_rect.min.y +=
+                        self.menu_height + ctx.style().spacing.item_spacing.y + border;
+                    default_rect.max.y -= self.menu_height;
+                    egui::Window::new(title)
+                        .open(&mut preferences_open)
+                        .default_rect(default_rect)
+                        .show(ctx, |ui| self.preferences(ui, cfg));
+                    self.preferences_open = preferences_open;
+                } else {
+                    CentralPanel::default().show(ctx, |ui| self.preferences(ui, cfg));
+                    if ctx.input(|i| i.viewport().close_requested()) {
+                        self.preferences_open = false;
+                    }
+                }
+            },
+        );
+    }
+
+    fn show_keybinds_viewport(&mut self, ctx: &Context, gamepads: &mut Gamepads, cfg: &mut Config) {
+        if !self.keybinds_open {
+            self.pending_keybind = None;
+            self.gamepad_unassign = None;
+            return;
+        }
+
+        let title = "Keybinds";
+        // TODO: Make this deferred? Requires `tx` and `cfg` to be Send + Sync
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("keybinds"),
+            egui::ViewportBuilder::default().with_title(title),
+            |ctx, class| {
+                if class == ViewportClass::Embedded {
+                    let mut keybinds_open = self.keybinds_open;
+                    let mut default_rect = ctx.available_rect();
+                    let border = 1.0;
+                    default_rect.min.y +=
+                        self.menu_height + ctx.style().spacing.item_spacing.y + border;
+                    default_rect.max.y -= self.menu_height;
+                    egui::Window::new("Keybinds")
+                        .open(&mut keybinds_open)
+                        .default_rect(default_rect)
+                        .show(ctx, |ui| self.keybinds(ui, gamepads, cfg));
+                    self.keybinds_open = keybinds_open;
+                } else {
+                    CentralPanel::default().show(ctx, |ui| self.keybinds(ui, gamepads, cfg));
+                    if ctx.input(|i| i.viewport().close_requested()) {
+                        self.keybinds_open = false;
+                    }
                 }
+            },
+        );
+    }
+
+    fn show_about_window(&mut self, ctx: &Context) {
+        let mut about_open = self.about_open;
+        egui::Window::new("About TetaNES")
+            .open(&mut about_open)
+            .show(ctx, |ui| self.about(ui));
+        self.about_open = about_open;
+    }
+
+    fn show_about_homebrew_window(&mut self, ctx: &Context) {
+        let Some(rom) = self.about_homebrew_rom_open else {
+            return;
+        };
+
+        let mut about_homebrew_open = true;
+        egui::Window::new(format!("About {}", rom.name))
+            .open(&mut about_homebrew_open)
+            .show(ctx, |ui| {
+                ScrollArea::vertical().show(ui, |ui| {
+                    ui.strong("Author(s):");
+                    ui.label(rom.authors);
+                    ui.add_space(12.0);
+
+                    ui.strong("Description:");
+                    ui.label(rom.description);
+                    ui.add_space(12.0);
+
+                    ui.strong("Source:");
+                    ui.hyperlink(rom.source);
+                });
             });
+        if !about_homebrew_open {
+            self.about_homebrew_rom_open = None;
         }
+    }
 
-        ui.separator();
+    fn show_update_window(&mut self, ctx: &Context) {
+        let mut update_window_open = self.update_window_open;
+        let mut close_window = false;
+        egui::Window::new("Update Available")
+            .open(&mut update_window_open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "An update is available for TetaNES! (v{})",
+                    self.version.latest(),
+                ));
+                ui.hyperlink("https://github.com/lukexor/tetanes/releases");
 
-        self.menubar_checkbox(ui, cfg, ShowShortcut::Yes);
-        self.messages_checkbox(ui, cfg, ShowShortcut::Yes);
+                ui.add_space(15.0);
+                ui.separator();
+                ui.add_space(15.0);
+
+                ui.label("Would you like to install it and restart?");
+                ui.add_space(15.0);
+
+                ui.horizontal(|ui| {
+                    let res = ui.button("Continue").on_hover_text(format!(
+                        "Install the latest version (v{}) restart TetaNES.",
+                        self.version.current()
+                    ));
+                    if res.clicked() {
+                        if let Err(err) = self.version.install_update_and_restart() {
+                            self.add_message(
+                                MessageType::Error,
+                                format!("Failed to install update: {err}"),
+                            );
+                            close_window = true;
+                        }
+                    }
+                    let res = ui.button("Cancel").on_hover_text(format!(
+                        "Keep the current version of TetaNES (v{}).",
+                        self.version.current()
+                    ));
+                    if res.clicked() {
+                        close_window = true;
+                    }
+                });
+            });
+        if close_window {
+            update_window_open = false;
+        }
+        self.update_window_open = update_window_open;
     }
 
-    fn debug_menu(&mut self, ui: &mut Ui) {
+    fn menu_bar(&mut self, ui: &mut Ui, cfg: &mut Config) {
         #[cfg(feature = "profiling")]
         puffin::profile_function!();
 
-        ui.allocate_space(Vec2::new(Self::MENU_WIDTH, 0.0));
+        ui.set_enabled(self.pending_keybind.is_none());
 
-        #[cfg(feature = "profiling")]
-        {
-            let mut profile = puffin::are_scopes_on();
-            ui.checkbox(&mut profile, "Enable Profiling")
-                .on_hover_text("Toggle the Puffin profiling window");
-            puffin::set_scopes_on(profile);
-        }
+        let inner_res = menu::bar(ui, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                Self::toggle_dark_mode_button(ui, cfg);
 
-        let mut perf_stats_open = self.perf_stats_open;
-        let toggle = ToggleValue::new(&mut perf_stats_open, "🛠 Performance Stats")
-            .shortcut_text(self.fmt_shortcut(Menu::PerfStats));
-        let res = ui
-            .add(toggle)
-            .on_hover_text("Enable a performance statistics overlay");
-        if res.clicked() {
-            self.perf_stats_open = perf_stats_open;
-            self.tx
-                .nes_event(EmulationEvent::ShowFrameStats(self.perf_stats_open));
-            ui.close_menu();
+                ui.separator();
+
+                let res = ui.menu_button("📁 File", |ui| self.file_menu(ui, cfg));
+                if std::mem::take(&mut self.focus_menubar) {
+                    res.response.request_focus();
+                }
+                ui.menu_button("🔧 Controls", |ui| self.controls_menu(ui, cfg));
+                ui.menu_button("⚙ Config", |ui| self.config_menu(ui, cfg));
+                // icon: screen
+                ui.menu_button("🖵 Window", |ui| self.window_menu(ui, cfg));
+                if !self.plugins.is_empty() {
+                    ui.menu_button("🧩 Plugins", |ui| self.plugins_menu(ui));
+                }
+                ui.menu_button("🕷 Debug", |ui| self.debug_menu(ui));
+                ui.menu_button("❓ Help", |ui| self.help_menu(ui));
+            });
+        });
+        let spacing = ui.style().spacing.item_spacing;
+        let border = 1.0;
+        let height = inner_res.response.rect.height() + spacing.y + border;
+        if height != self.menu_height {
+            self.menu_height = height;
+            self.resize_window = true;
         }
+    }
 
-        #[cfg(debug_assertions)]
-        {
-            let res = ui.checkbox(&mut self.debug_on_hover, "Debug on Hover");
+    pub fn toggle_dark_mode_button(ui: &mut Ui, cfg: &mut Config) {
+        if ui.ctx().style().visuals.dark_mode {
+            let button = Button::new("☀").frame(false);
+            let res = ui.add(button).on_hover_text("Switch to light mode");
             if res.clicked() {
-                ui.ctx().set_debug_on_hover(self.debug_on_hover);
+                cfg.renderer.dark_theme = false;
+                Self::set_style(ui.ctx(), cfg);
+            }
+        } else {
+            let button = Button::new("🌙").frame(false);
+            let res = ui.add(button).on_hover_text("Switch to dark mode");
+            if res.clicked() {
+                cfg.renderer.dark_theme = true;
+                Self::set_style(ui.ctx(), cfg);
             }
         }
+    }
 
-        ui.separator();
+    fn file_menu(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
 
-        ui.add_enabled_ui(false, |ui| {
-            let debugger_shortcut = self.fmt_shortcut(Debug::Toggle(Debugger::Cpu));
-            let toggle = ToggleValue::new(&mut self.debugger_open, "🚧 Debugger")
-                .shortcut_text(debugger_shortcut);
-            let res = ui
-                .add(toggle)
-                .on_hover_text("Toggle the Debugger.")
-                .on_disabled_hover_text("Not yet implemented.");
-            if res.clicked() {
-                ui.close_menu();
-            }
+        ui.allocate_space(Vec2::new(Self::MENU_WIDTH, 0.0));
 
-            let ppu_viewer_shortcut = self.fmt_shortcut(Debug::Toggle(Debugger::Ppu));
-            let toggle = ToggleValue::new(&mut self.ppu_viewer_open, "🌇 PPU Viewer")
-                .shortcut_text(ppu_viewer_shortcut);
-            let res = ui
-                .add(toggle)
-                .on_hover_text("Toggle the PPU Viewer.")
-                .on_disabled_hover_text("Not yet implemented.");
-            if res.clicked() {
-                ui.close_menu();
+        let button =
+            Button::new("📂 Load ROM...").shortcut_text(self.fmt_shortcut(UiAction::LoadRom));
+        if ui.add(button).clicked() {
+            if self.loaded_rom.is_some() {
+                self.paused = true;
+                self.tx.nes_event(EmulationEvent::Pause(true));
             }
+            // NOTE: Due to some platforms file dialogs blocking the event loop,
+            // loading requires a round-trip in order for the above pause to
+            // get processed.
+            self.tx.nes_event(UiEvent::LoadRomDialog);
+            ui.close_menu();
+        }
 
-            let apu_mixer_shortcut = self.fmt_shortcut(Debug::Toggle(Debugger::Apu));
-            let toggle = ToggleValue::new(&mut self.apu_mixer_open, "🎼 APU Mixer")
-                .shortcut_text(apu_mixer_shortcut);
-            let res = ui
-                .add(toggle)
-                .on_hover_text("Toggle the APU Mixer.")
-                .on_disabled_hover_text("Not yet implemented.");
-            if res.clicked() {
-                ui.close_menu();
-            }
-        });
+        if platform::supports(platform::Feature::Filesystem) {
+            // icon: pushpin
+            ui.menu_button("📌 Pinned Folders...", |ui| {
+                self.pinned_dirs_menu(ui, cfg)
+            });
+        }
 
-        ui.separator();
+        ui.menu_button("🍺 Homebrew ROM...", |ui| self.homebrew_rom_menu(ui));
 
         ui.add_enabled_ui(self.loaded_rom.is_some(), |ui| {
-            let button = Button::new("Step Into")
-                .shortcut_text(self.fmt_shortcut(Debug::Step(DebugStep::Into)));
-            let res = ui
-                .add(button)
-                .on_hover_text("Step a single CPU instruction.")
-                .on_disabled_hover_text(Self::NO_ROM_LOADED);
+            let button = Button::new("⏹ Unload ROM...")
+                .shortcut_text(self.fmt_shortcut(UiAction::UnloadRom));
+            let res = ui.add(button).on_disabled_hover_text(Self::NO_ROM_LOADED);
             if res.clicked() {
-                self.tx
-                    .nes_event(EmulationEvent::DebugStep(DebugStep::Into));
+                self.tx.nes_event(EmulationEvent::UnloadRom);
+                ui.close_menu();
             }
 
-            let button = Button::new("Step Out")
-                .shortcut_text(self.fmt_shortcut(Debug::Step(DebugStep::Out)));
+            let button =
+                Button::new("🎞 Load Replay").shortcut_text(self.fmt_shortcut(UiAction::LoadReplay));
             let res = ui
                 .add(button)
-                .on_hover_text("Step out of the current CPU function.")
+                .on_hover_text("Load a replay file for the currently loaded ROM.")
                 .on_disabled_hover_text(Self::NO_ROM_LOADED);
             if res.clicked() {
-                self.tx.nes_event(EmulationEvent::DebugStep(DebugStep::Out));
+                self.paused = true;
+                self.tx.nes_event(EmulationEvent::Pause(true));
+                // NOTE: Due to some platforms file dialogs blocking the event loop,
+                // loading requires a round-trip in order for the above pause to
+                // get processed.
+                self.tx.nes_event(UiEvent::LoadReplayDialog);
+                ui.close_menu();
             }
+        });
 
-            let button = Button::new("Step Over")
-                .shortcut_text(self.fmt_shortcut(Debug::Step(DebugStep::Over)));
-            let res = ui
-                .add(button)
-                .on_hover_text("Step over the next CPU instruction.")
-                .on_disabled_hover_text(Self::NO_ROM_LOADED);
-            if res.clicked() {
-                self.tx
-                    .nes_event(EmulationEvent::DebugStep(DebugStep::Over));
-            }
+        // TODO: support saves and recent games on wasm? Requires storing the data
+        if platform::supports(platform::Feature::Filesystem) {
+            ui.menu_button("🗄 Recently Played...", |ui| {
+                use tetanes_core::fs;
 
-            let button = Button::new("Step Scanline")
-                .shortcut_text(self.fmt_shortcut(Debug::Step(DebugStep::Scanline)));
+                if cfg.renderer.recent_roms.is_empty() {
+                    ui.label("No recent ROMs");
+                } else {
+                    ui.allocate_space(Vec2::new(Self::MENU_WIDTH, 0.0));
+
+                    ScrollArea::vertical().show(ui, |ui| {
+                        // TODO: add timestamp, save slots, and screenshot
+                        for rom in &cfg.renderer.recent_roms {
+                            if ui.button(fs::filename(rom)).clicked() {
+                                self.tx
+                                    .nes_event(EmulationEvent::LoadRomPath(rom.to_path_buf()));
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                }
+            });
+
+            let mut library_open = self.library_open;
+            // icon: books
+            let toggle = ToggleValue::new(&mut library_open, "📚 Library");
             let res = ui
-                .add(button)
-                .on_hover_text("Step an entire PPU scanline.")
-                .on_disabled_hover_text(Self::NO_ROM_LOADED);
+                .add(toggle)
+                .on_hover_text("Browse ROMs in your library directories.");
             if res.clicked() {
-                self.tx
-                    .nes_event(EmulationEvent::DebugStep(DebugStep::Scanline));
+                self.library_open = library_open;
+                ui.close_menu();
             }
 
-            let button = Button::new("Step Frame")
-                .shortcut_text(self.fmt_shortcut(Debug::Step(DebugStep::Frame)));
+            let mut playlist_open = self.playlist_open;
+            // icon: list
+            let toggle = ToggleValue::new(&mut playlist_open, "📋 Playlist");
             let res = ui
-                .add(button)
-                .on_hover_text("Step an entire PPU Frame.")
-                .on_disabled_hover_text(Self::NO_ROM_LOADED);
+                .add(toggle)
+                .on_hover_text("Queue several ROMs to play back to back.");
             if res.clicked() {
-                self.tx
-                    .nes_event(EmulationEvent::DebugStep(DebugStep::Frame));
-            }
-        });
-    }
-
-    fn help_menu(&mut self, ui: &mut Ui) {
-        ui.allocate_space(Vec2::new(Self::MENU_WIDTH, 0.0));
-
-        if self.version.requires_updates() && ui.button("🌐 Check for Updates...").clicked() {
-            match self.version.update_available() {
-                Ok(update_available) => self.update_window_open = update_available,
-                Err(err) => self.add_message(MessageType::Error, err.to_string()),
-            }
-            if !self.update_window_open {
-                self.add_message(
-                    MessageType::Info,
-                    format!("TetaNES v{} is up to date!", self.version.current()),
-                );
+                self.playlist_open = playlist_open;
+                ui.close_menu();
             }
-            ui.close_menu();
-        }
-        ui.toggle_value(&mut self.about_open, "ℹ About");
-    }
 
-    fn nes_frame(&mut self, ui: &mut Ui, gamepads: &Gamepads, cfg: &mut Config) {
-        #[cfg(feature = "profiling")]
-        puffin::profile_function!();
+            ui.separator();
 
-        ui.set_enabled(self.pending_keybind.is_none());
+            ui.add_enabled_ui(self.loaded_rom.is_some(), |ui| {
+                let button = Button::new("💾 Save State")
+                    .shortcut_text(self.fmt_shortcut(DeckAction::SaveState));
+                let res = ui
+                    .add(button)
+                    .on_hover_text("Save the current state to the selected save slot.")
+                    .on_disabled_hover_text(Self::NO_ROM_LOADED);
+                if res.clicked() {
+                    self.tx
+                        .nes_event(EmulationEvent::SaveState(cfg.emulation.save_slot));
+                };
 
-        let inner_res = CentralPanel::default()
-            .frame(Frame::none())
-            .show_inside(ui, |ui| {
-                if self.loaded_rom.is_some() {
-                    let layout = Layout {
-                        main_dir: Direction::TopDown,
-                        main_align: Align::Center,
-                        cross_align: Align::Center,
-                        ..Default::default()
-                    };
-                    ui.with_layout(layout, |ui| {
-                        let image = Image::from_texture(self.texture)
-                            .maintain_aspect_ratio(true)
-                            .shrink_to_fit()
-                            .sense(Sense::click());
-                        let hover_cursor = if cfg.deck.zapper {
-                            CursorIcon::Crosshair
-                        } else {
-                            CursorIcon::Default
-                        };
-                        let res = ui.add(image).on_hover_cursor(hover_cursor);
-                        self.nes_frame = res.rect;
+                let button = Button::new("⎗ Load State")
+                    .shortcut_text(self.fmt_shortcut(DeckAction::LoadState));
+                let res = ui
+                    .add(button)
+                    .on_hover_text("Load a previous state from the selected save slot.")
+                    .on_disabled_hover_text(Self::NO_ROM_LOADED);
+                if res.clicked() {
+                    self.tx
+                        .nes_event(EmulationEvent::LoadState(cfg.emulation.save_slot));
+                }
 
-                        if cfg.deck.zapper {
-                            if self
-                                .action_input(DeckAction::ZapperAimOffscreen)
-                                .map_or(false, |input| input_down(ui, gamepads, cfg, input))
-                            {
-                                let pos = (Ppu::WIDTH + 10, Ppu::HEIGHT + 10);
-                                self.tx.nes_event(EmulationEvent::ZapperAim(pos));
-                            } else if let Some(Pos2 { x, y }) = res
-                                .hover_pos()
-                                .and_then(|Pos2 { x, y }| cursor_to_zapper(x, y, res.rect))
-                            {
-                                let pos = (x.round() as u32, y.round() as u32);
-                                self.tx.nes_event(EmulationEvent::ZapperAim(pos));
+                if platform::supports(platform::Feature::LanTransfer) {
+                    ui.add_enabled_ui(cfg.emulation.lan_transfer, |ui| {
+                        // icon: satellite antenna
+                        ui.menu_button("📡 Send State To...", |ui| {
+                            if self.lan_peers.is_empty() {
+                                ui.label("No devices found on the local network.");
                             }
-                            if res.clicked() {
-                                self.tx.nes_event(EmulationEvent::ZapperTrigger);
+                            for peer in &self.lan_peers {
+                                let res =
+                                    ui.button(&peer.name).on_hover_text(peer.addr.to_string());
+                                if res.clicked() {
+                                    self.tx.nes_event(EmulationEvent::SendState(peer.clone()));
+                                    ui.close_menu();
+                                }
                             }
-                        }
-                    });
-                } else {
-                    ui.vertical_centered(|ui| {
-                        ui.horizontal_centered(|ui| {
-                            let image = Image::new(include_image!("../../../assets/tetanes.png"))
-                                .shrink_to_fit()
-                                .tint(Color32::GRAY);
-                            ui.add(image);
-                        });
+                        })
+                        .response
+                        .on_disabled_hover_text(
+                            "LAN state transfer can be enabled in the Preferences menu.",
+                        );
                     });
                 }
             });
 
-        // Start at the left-top of the NES frame.
-        let mut messages_pos = inner_res.response.rect.left_top();
+            // icon: # in a square
+            ui.menu_button("󾠬 Save Slot...", |ui| {
+                self.save_slot_radio(ui, cfg, ShowShortcut::Yes);
+            });
 
-        let mut recording_labels = Vec::new();
-        if self.replay_recording {
-            recording_labels.push("Replay");
+            ui.separator();
+
+            let button = Button::new("⎆ Quit").shortcut_text(self.fmt_shortcut(UiAction::Quit));
+            if ui.add(button).clicked() {
+                self.tx.nes_event(UiEvent::Terminate);
+                ui.close_menu();
+            };
         }
-        if self.audio_recording {
-            recording_labels.push("Audio");
+    }
+
+    fn pinned_dirs_menu(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        ui.allocate_space(Vec2::new(Self::MENU_WIDTH, 0.0));
+
+        if cfg.renderer.pinned_dirs.is_empty() {
+            ui.label("No pinned folders");
         }
-        if !recording_labels.is_empty() {
-            let inner_res = Area::new(Id::new("status"))
-                .order(Order::Foreground)
-                .fixed_pos(messages_pos)
-                .show(ui.ctx(), |ui| {
-                    Frame::popup(ui.style()).show(ui, |ui| {
-                        ui.with_layout(
-                            Layout::top_down_justified(Align::LEFT).with_main_wrap(true),
-                            |ui| ui.label(format!("Recording {}", recording_labels.join(" & "))),
-                        );
-                    });
-                });
-            // Update to the left-bottom of this area, if rendered
-            messages_pos = inner_res.response.rect.left_bottom();
+        let mut unpin = None;
+        for (index, dir) in cfg.renderer.pinned_dirs.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button("✖").on_hover_text("Unpin folder.").clicked() {
+                    unpin = Some(index);
+                }
+                let name = dir.file_name().unwrap_or(dir.as_os_str()).to_string_lossy();
+                let res = ui
+                    .button(format!("📁 {name}"))
+                    .on_hover_text(dir.to_string_lossy());
+                if res.clicked() {
+                    if self.loaded_rom.is_some() {
+                        self.paused = true;
+                        self.tx.nes_event(EmulationEvent::Pause(true));
+                    }
+                    // NOTE: Due to some platforms file dialogs blocking the event loop,
+                    // loading requires a round-trip in order for the above pause to
+                    // get processed.
+                    self.tx.nes_event(UiEvent::LoadRomDialogIn(dir.clone()));
+                    ui.close_menu();
+                }
+            });
         }
-
-        if cfg.renderer.show_messages && (!self.messages.is_empty() || self.error.is_some()) {
-            Area::new(Id::new("messages"))
-                .order(Order::Foreground)
-                .fixed_pos(messages_pos)
-                .show(ui.ctx(), |ui| {
-                    Frame::popup(ui.style()).show(ui, |ui| {
-                        ui.with_layout(
-                            Layout::top_down_justified(Align::LEFT).with_main_wrap(true),
-                            |ui| {
-                                self.message_bar(ui);
-                                self.error_bar(ui);
-                            },
-                        );
-                    });
-                });
+        if let Some(index) = unpin {
+            cfg.renderer.pinned_dirs.remove(index);
         }
 
-        let mut frame = Frame::none();
-        if self.paused {
-            frame = Frame::dark_canvas(ui.style()).multiply_with_opacity(0.7);
-        }
-
-        frame.show(ui, |ui| {
-            ui.with_layout(Layout::centered_and_justified(Direction::TopDown), |ui| {
-                if self.paused {
-                    ui.heading(RichText::new("⏸").size(40.0));
-                }
-            });
-        });
-    }
+        ui.separator();
 
-    fn message_bar(&mut self, ui: &mut Ui) {
-        let now = Instant::now();
-        self.messages.retain(|(_, _, expires)| now < *expires);
-        self.messages.dedup_by(|a, b| a.1.eq(&b.1));
-        for (ty, message, _) in self.messages.iter().take(Self::MAX_MESSAGES) {
-            let visuals = &ui.style().visuals;
-            let (icon, color) = match ty {
-                MessageType::Info => ("ℹ", visuals.widgets.noninteractive.fg_stroke.color),
-                MessageType::Warn => ("⚠", visuals.warn_fg_color),
-                MessageType::Error => ("❗", visuals.error_fg_color),
-            };
-            ui.colored_label(color, format!("{icon} {message}"));
+        let res = ui
+            .button("➕ Pin Folder...")
+            .on_hover_text("Pin a folder to quickly load ROMs from.");
+        if res.clicked() {
+            self.tx.nes_event(UiEvent::PinDirDialog);
+            ui.close_menu();
         }
     }
 
-    fn error_bar(&mut self, ui: &mut Ui) {
-        if let Some(error) = self.error.clone() {
-            ui.horizontal(|ui| {
-                ui.label(RichText::new(error).color(Color32::RED));
-                if ui.button("").clicked() {
-                    self.error = None;
-                }
-            });
-        }
+    fn homebrew_rom_menu(&mut self, ui: &mut Ui) {
+        ScrollArea::vertical().show(ui, |ui| {
+            for rom in HOMEBREW_ROMS {
+                ui.horizontal(|ui| {
+                    if ui.button(rom.name).clicked() {
+                        self.tx
+                            .nes_event(EmulationEvent::LoadRom((rom.name.to_string(), rom.data())));
+                        ui.close_menu();
+                    }
+                    let res = ui.button("ℹ").on_hover_ui(|ui| {
+                        ui.set_max_width(400.0);
+                        Self::about_homebrew(ui, rom);
+                    });
+                    if res.clicked() {
+                        self.about_homebrew_rom_open = Some(rom);
+                        ui.close_menu();
+                    }
+                });
+            }
+        });
     }
 
-    fn performance_stats(&mut self, ui: &mut Ui, cfg: &Config) {
+    fn controls_menu(&mut self, ui: &mut Ui, cfg: &mut Config) {
         #[cfg(feature = "profiling")]
         puffin::profile_function!();
 
-        ui.allocate_space(Vec2::new(200.0, 0.0));
-        ui.set_enabled(self.pending_keybind.is_none());
+        ui.allocate_space(Vec2::new(Self::MENU_WIDTH, 0.0));
 
-        let grid = Grid::new("perf_stats").num_columns(2).spacing([40.0, 6.0]);
-        grid.show(ui, |ui| {
-            ui.ctx().request_repaint_after(Duration::from_secs(1));
+        ui.add_enabled_ui(self.loaded_rom.is_some(), |ui| {
+            let button = Button::new(if self.paused {
+                "▶ Resume"
+            } else {
+                "⏸ Pause"
+            })
+            .shortcut_text(self.fmt_shortcut(UiAction::TogglePause));
+            let res = ui.add(button).on_disabled_hover_text(Self::NO_ROM_LOADED);
+            if res.clicked() {
+                self.paused = !self.paused;
+                self.tx.nes_event(EmulationEvent::Pause(self.paused));
+                ui.close_menu();
+            };
 
-            if let Some(sys) = &mut self.sys {
-                // NOTE: refreshing sysinfo is cpu-intensive if done too frequently and skews the
-                // results
-                let sys_update_interval = Duration::from_secs(1);
-                assert!(sys_update_interval > sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
-                if self.sys_updated.elapsed() >= sys_update_interval {
-                    sys.refresh_specifics(
-                        RefreshKind::new().with_processes(
-                            ProcessRefreshKind::new()
-                                .with_cpu()
-                                .with_memory()
-                                .with_disk_usage(),
-                        ),
-                    );
-                    self.sys_updated = Instant::now();
+            let button = Button::new("⏭ Frame Advance")
+                .shortcut_text(self.fmt_shortcut(UiAction::FrameAdvance));
+            let res = ui
+                .add(button)
+                .on_hover_text("Pause, or advance a single frame if already paused.")
+                .on_disabled_hover_text(Self::NO_ROM_LOADED);
+            if res.clicked() {
+                if self.paused {
+                    self.tx
+                        .nes_event(EmulationEvent::DebugStep(DebugStep::Frame));
+                } else {
+                    self.paused = true;
+                    self.tx.nes_event(EmulationEvent::Pause(self.paused));
                 }
             }
+        });
 
-            let good_color = if ui.style().visuals.dark_mode {
-                hex_color!("#b8cc52")
-            } else {
-                hex_color!("#86b300")
-            };
-            let warn_color = ui.style().visuals.warn_fg_color;
-            let bad_color = ui.style().visuals.error_fg_color;
-            let fps_color = |fps| match fps {
-                fps if fps < 30.0 => bad_color,
-                fps if fps < 60.0 => warn_color,
-                _ => good_color,
-            };
-            let frame_time_color = |time| match time {
-                time if time <= 1000.0 * 1.0 / 60.0 => good_color,
-                time if time <= 1000.0 * 1.0 / 30.0 => warn_color,
-                _ => bad_color,
-            };
-            let cpu_color = |cpu| match cpu {
-                cpu if cpu <= 25.0 => good_color,
-                cpu if cpu <= 50.0 => warn_color,
-                _ => bad_color,
-            };
+        let button = Button::new(if cfg.audio.enabled {
+            "🔇 Mute"
+        } else {
+            "🔊 Unmute"
+        })
+        .shortcut_text(self.fmt_shortcut(Setting::ToggleAudio));
 
-            let fps = self.frame_stats.fps;
-            ui.strong("FPS:");
-            if fps.is_finite() {
-                ui.colored_label(fps_color(fps), format!("{fps:.2}"));
-            } else {
-                ui.label("N/A");
-            }
-            ui.end_row();
+        if ui.add(button).clicked() {
+            cfg.audio.enabled = !cfg.audio.enabled;
+            self.tx
+                .nes_event(ConfigEvent::AudioEnabled(cfg.audio.enabled));
+        };
 
-            let fps_min = self.frame_stats.fps_min;
-            ui.strong("FPS (min):");
-            if fps_min.is_finite() {
-                ui.colored_label(fps_color(fps_min), format!("{fps_min:.2}"));
-            } else {
-                ui.label("N/A");
-            }
-            ui.end_row();
+        ui.separator();
 
-            let frame_time = self.frame_stats.frame_time;
-            ui.strong("Frame Time:");
-            if frame_time.is_finite() {
-                ui.colored_label(frame_time_color(frame_time), format!("{frame_time:.2} ms"));
-            } else {
-                ui.label("N/A");
-            }
-            ui.end_row();
+        ui.add_enabled_ui(self.loaded_rom.is_some(), |ui| {
+            if platform::supports(platform::Feature::Filesystem) {
+                ui.add_enabled_ui(cfg.emulation.rewind, |ui| {
+                    let button = Button::new("⟲ Instant Rewind")
+                        .shortcut_text(self.fmt_shortcut(Feature::InstantRewind));
+                    let disabled_hover_text = if self.loaded_rom.is_none() {
+                        Self::NO_ROM_LOADED
+                    } else {
+                        "Rewind can be enabled under the `Config` menu."
+                    };
+                    let res = ui
+                        .add(button)
+                        .on_hover_text("Instantly rewind state to a previous point.")
+                        .on_disabled_hover_text(disabled_hover_text);
+                    if res.clicked() {
+                        self.tx.nes_event(EmulationEvent::InstantRewind);
+                        ui.close_menu();
+                    };
 
-            let frame_time_max = self.frame_stats.frame_time_max;
-            ui.strong("Frame Time (max):");
-            if frame_time_max.is_finite() {
-                ui.colored_label(
-                    frame_time_color(frame_time_max),
-                    format!("{frame_time_max:.2} ms"),
-                );
-            } else {
-                ui.label("N/A");
+                    let res = ui
+                        .button("⏪ Rewind Timeline...")
+                        .on_hover_text("Scrub through the rewind buffer and resume from any point.")
+                        .on_disabled_hover_text(disabled_hover_text);
+                    if res.clicked() {
+                        self.open_rewind_timeline();
+                        ui.close_menu();
+                    };
+                });
             }
-            ui.end_row();
-
-            ui.strong("Frame Count:");
-            ui.label(format!("{}", self.frame_stats.frame_count));
-            ui.end_row();
 
-            if let Some(ref sys) = self.sys {
-                ui.label("");
-                ui.end_row();
+            let button = Button::new("🔃 Reset")
+                .shortcut_text(self.fmt_shortcut(DeckAction::Reset(ResetKind::Soft)));
+            let res = ui
+                .add(button)
+                .on_hover_text("Emulate a soft reset of the NES.")
+                .on_disabled_hover_text(Self::NO_ROM_LOADED);
+            if res.clicked() {
+                self.tx.nes_event(EmulationEvent::Reset(ResetKind::Soft));
+                ui.close_menu();
+            };
 
-                match sys.process(Pid::from_u32(std::process::id())) {
-                    Some(proc) => {
-                        ui.strong("CPU:");
-                        let cpu_usage = proc.cpu_usage();
-                        ui.colored_label(cpu_color(cpu_usage), format!("{cpu_usage:.2}%"));
-                        ui.end_row();
+            let button = Button::new("🔌 Power Cycle")
+                .shortcut_text(self.fmt_shortcut(DeckAction::Reset(ResetKind::Hard)));
+            let res = ui
+                .add(button)
+                .on_hover_text("Emulate a power cycle of the NES.")
+                .on_disabled_hover_text(Self::NO_ROM_LOADED);
+            if res.clicked() {
+                self.tx.nes_event(EmulationEvent::Reset(ResetKind::Hard));
+                ui.close_menu();
+            };
 
-                        ui.strong("Memory:");
-                        ui.label(format!("{} MB", bytes_to_mb(proc.memory()),));
-                        ui.end_row();
+            if self
+                .loaded_rom
+                .as_ref()
+                .is_some_and(|rom| rom.header.is_vs_system())
+            {
+                for slot in 1..=2 {
+                    let button = Button::new(format!("💰 Insert Coin {slot}"))
+                        .shortcut_text(self.fmt_shortcut(DeckAction::InsertCoin(slot)));
+                    let res = ui
+                        .add(button)
+                        .on_hover_text("Insert a coin into the Vs. System coin slot.");
+                    if res.clicked() {
+                        self.tx.nes_event(EmulationEvent::InsertCoin(slot));
+                        ui.close_menu();
+                    };
+                }
+            }
 
-                        let du = proc.disk_usage();
-                        ui.strong("Disk read new/total:");
-                        ui.label(format!(
-                            "{:.2}/{:.2} MB",
-                            bytes_to_mb(du.read_bytes),
-                            bytes_to_mb(du.total_read_bytes)
-                        ));
-                        ui.end_row();
-
-                        ui.strong("Disk written new/total:");
-                        ui.label(format!(
-                            "{:.2}/{:.2} MB",
-                            bytes_to_mb(du.written_bytes),
-                            bytes_to_mb(du.total_written_bytes),
-                        ));
-                        ui.end_row();
-                    }
-                    None => todo!(),
-                }
+            let mut header_editor_open = self.header_editor_open;
+            // icon: hammer and wrench
+            let toggle = ToggleValue::new(&mut header_editor_open, "🛠 ROM Header");
+            let res = ui
+                .add(toggle)
+                .on_hover_text("Override the iNES header of the loaded ROM.")
+                .on_disabled_hover_text(Self::NO_ROM_LOADED);
+            if res.clicked() {
+                self.header_editor_open = header_editor_open;
+                ui.close_menu();
             }
+        });
 
-            ui.label("");
-            ui.end_row();
+        if platform::supports(platform::Feature::Filesystem) {
+            ui.separator();
 
-            ui.strong("Run Time:");
-            ui.label(format!("{} s", self.start.elapsed().as_secs()));
-            ui.end_row();
+            ui.add_enabled_ui(self.loaded_rom.is_some(), |ui| {
+                let button = Button::new("🖼 Screenshot")
+                    .shortcut_text(self.fmt_shortcut(Feature::TakeScreenshot));
+                let res = ui.add(button).on_disabled_hover_text(Self::NO_ROM_LOADED);
+                if res.clicked() {
+                    self.tx
+                        .nes_event(EmulationEvent::Screenshot(cfg.emulation.screenshot.clone()));
+                    ui.close_menu();
+                };
 
-            let (cursor_pos, zapper_pos) = match ui.input(|i| i.pointer.latest_pos()) {
-                Some(Pos2 { x, y }) => {
-                    let zapper_pos = match cursor_to_zapper(x, y, self.nes_frame) {
-                        Some(Pos2 { x, y }) => format!("({x:.0}, {y:.0})"),
-                        None => "(-, -)".to_string(),
-                    };
-                    (format!("({x:.0}, {y:.0})"), zapper_pos)
-                }
-                None => ("(-, -)".to_string(), "(-, -)".to_string()),
-            };
+                let button_txt = if self.replay_recording {
+                    "⏹ Stop Replay Recording"
+                } else {
+                    "🎞 Record Replay"
+                };
+                let button = Button::new(button_txt)
+                    .shortcut_text(self.fmt_shortcut(Feature::ToggleReplayRecording));
+                let res = ui
+                    .add(button)
+                    .on_hover_text("Record or stop recording a game replay file.")
+                    .on_disabled_hover_text(Self::NO_ROM_LOADED);
+                if res.clicked() {
+                    self.tx
+                        .nes_event(EmulationEvent::ReplayRecord(!self.replay_recording));
+                    ui.close_menu();
+                };
 
-            ui.strong("Cursor Pos:");
-            ui.label(cursor_pos);
-            ui.end_row();
+                let button_txt = if self.audio_recording {
+                    "⏹ Stop Audio Recording"
+                } else {
+                    "🎤 Record Audio"
+                };
+                let button = Button::new(button_txt)
+                    .shortcut_text(self.fmt_shortcut(Feature::ToggleAudioRecording));
+                let res = ui
+                    .add(button)
+                    .on_hover_text("Record or stop recording a audio file.")
+                    .on_disabled_hover_text(Self::NO_ROM_LOADED);
+                if res.clicked() {
+                    self.tx
+                        .nes_event(EmulationEvent::AudioRecord(!self.audio_recording));
+                    ui.close_menu();
+                };
 
-            if cfg.deck.zapper {
-                ui.strong("Zapper Pos:");
-                ui.label(zapper_pos);
-                ui.end_row();
-            }
-        });
+                let button_txt = if self.map_dumping {
+                    "⏹ Stop Map Dump"
+                } else {
+                    "🗺 Dump Map"
+                };
+                let button = Button::new(button_txt)
+                    .shortcut_text(self.fmt_shortcut(Feature::ToggleMapDump));
+                let res = ui
+                    .add(button)
+                    .on_hover_text(
+                        "Stitch frames into a full map image while scrolling through a level.",
+                    )
+                    .on_disabled_hover_text(Self::NO_ROM_LOADED);
+                if res.clicked() {
+                    self.tx
+                        .nes_event(EmulationEvent::MapDump(!self.map_dumping));
+                    ui.close_menu();
+                };
+
+                let button_txt = if self.sprite_ripping {
+                    "⏹ Stop Sprite Rip"
+                } else {
+                    "👾 Rip Sprites"
+                };
+                let button = Button::new(button_txt)
+                    .shortcut_text(self.fmt_shortcut(Feature::ToggleSpriteRip));
+                let res = ui
+                    .add(button)
+                    .on_hover_text(
+                        "Export all unique sprites seen during gameplay as sprite sheets.",
+                    )
+                    .on_disabled_hover_text(Self::NO_ROM_LOADED);
+                if res.clicked() {
+                    self.tx
+                        .nes_event(EmulationEvent::SpriteRip(!self.sprite_ripping));
+                    ui.close_menu();
+                };
+            });
+        }
     }
 
-    fn preferences(&mut self, ui: &mut Ui, cfg: &mut Config) {
+    fn config_menu(&mut self, ui: &mut Ui, cfg: &mut Config) {
         #[cfg(feature = "profiling")]
         puffin::profile_function!();
 
-        ui.set_enabled(self.pending_keybind.is_none());
+        ui.allocate_space(Vec2::new(Self::MENU_WIDTH, 0.0));
 
-        ScrollArea::vertical().show(ui, |ui| {
-            ui.horizontal(|ui| {
-                ui.selectable_value(
-                    &mut self.preferences_tab,
-                    PreferencesTab::Emulation,
-                    "Emulation",
-                );
-                ui.selectable_value(&mut self.preferences_tab, PreferencesTab::Audio, "Audio");
-                ui.selectable_value(&mut self.preferences_tab, PreferencesTab::Video, "Video");
-                ui.selectable_value(&mut self.preferences_tab, PreferencesTab::Input, "Input");
-            });
+        self.cycle_acurate_checkbox(ui, cfg, ShowShortcut::Yes);
+        self.zapper_checkbox(ui, cfg, ShowShortcut::Yes);
+        self.vaus_checkbox(ui, cfg, ShowShortcut::Yes);
+        self.rewind_checkbox(ui, cfg, ShowShortcut::Yes);
+        self.overscan_checkbox(ui, cfg, ShowShortcut::Yes);
 
-            ui.separator();
+        ui.separator();
 
-            match self.preferences_tab {
-                PreferencesTab::Emulation => self.emulation_preferences(ui, cfg),
-                PreferencesTab::Audio => self.audio_preferences(ui, cfg),
-                PreferencesTab::Video => self.video_preferences(ui, cfg),
-                PreferencesTab::Input => self.input_preferences(ui, cfg),
+        ui.menu_button("🕒 Emulation Speed...", |ui| {
+            let speed = cfg.emulation.speed;
+            let button =
+                Button::new("Increment").shortcut_text(self.fmt_shortcut(Setting::IncrementSpeed));
+            if ui.add(button).clicked() {
+                let new_speed = cfg.increment_speed();
+                if speed != new_speed {
+                    self.tx.nes_event(ConfigEvent::Speed(new_speed));
+                }
             }
 
-            ui.separator();
-
-            ui.horizontal(|ui| {
-                if ui.button("Restore Defaults").clicked() {
-                    cfg.reset();
-                    self.shortcut_keybinds = Self::shortcut_keybinds(&cfg.input.shortcuts);
-                    self.joypad_keybinds = Self::joypad_keybinds(&cfg.input.joypad_bindings);
-                    self.tx.nes_event(ConfigEvent::InputBindings);
-                }
-                if platform::supports(platform::Feature::Filesystem) {
-                    if let Some(data_dir) = Config::default_data_dir() {
-                        if ui.button("Clear Save States").clicked() {
-                            match fs::clear_dir(data_dir) {
-                                Ok(_) => {
-                                    self.add_message(MessageType::Info, "Save States cleared.")
-                                }
-                                Err(_) => self.add_message(
-                                    MessageType::Error,
-                                    "Failed to clear Save States.",
-                                ),
-                            }
-                        }
-                        if ui.button("Clear Recent ROMs").clicked() {
-                            cfg.renderer.recent_roms.clear();
-                        }
-                    }
+            let button =
+                Button::new("Decrement").shortcut_text(self.fmt_shortcut(Setting::DecrementSpeed));
+            if ui.add(button).clicked() {
+                let new_speed = cfg.decrement_speed();
+                if speed != new_speed {
+                    self.tx.nes_event(ConfigEvent::Speed(new_speed));
                 }
+            }
+            self.speed_slider(ui, cfg);
+        });
+        ui.menu_button("🏃 Run Ahead...", |ui| self.run_ahead_slider(ui, cfg));
+
+        ui.separator();
+
+        ui.menu_button("🌉 Video Filter...", |ui| {
+            self.video_filter_radio(ui, cfg)
+        });
+        ui.menu_button("🌎 Nes Region...", |ui| self.nes_region_radio(ui, cfg));
+        if self
+            .loaded_rom
+            .as_ref()
+            .is_some_and(|rom| rom.header.mapper_num == 4)
+        {
+            ui.menu_button("🔩 MMC3 Revision...", |ui| {
+                self.mmc3_revision_radio(ui, cfg)
+            });
+        }
+        if let Some(dip_switches) = self.loaded_rom.as_ref().and_then(|rom| rom.dip_switches) {
+            ui.menu_button("🔀 DIP Switches...", |ui| {
+                self.dip_switches_checkboxes(ui, cfg, dip_switches.count)
             });
+        }
+        ui.menu_button("🎮 Four Player...", |ui| self.four_player_radio(ui, cfg));
+        ui.menu_button("🖱 Mouse...", |ui| self.mouse_radio(ui, cfg));
+        ui.menu_button("🔌 Expansion Port...", |ui| {
+            self.expansion_device_radio(ui, cfg)
+        });
+        if self.expansion_device(cfg) == ExpansionDevice::BarcodeBattler {
+            ui.menu_button("📇 Scan Barcode...", |ui| self.barcode_entry(ui));
+        }
+        ui.menu_button("📓 Game Genie Codes...", |ui| {
+            self.genie_codes_entry(ui, cfg)
         });
+        let mut genie_converter_open = self.genie_converter_open;
+        let toggle = ToggleValue::new(&mut genie_converter_open, "🔁 Game Genie Converter");
+        let res = ui
+            .add(toggle)
+            .on_hover_text("Convert between Game Genie codes and raw address/value patches");
+        if res.clicked() {
+            self.genie_converter_open = genie_converter_open;
+            ui.close_menu();
+        }
+
+        ui.separator();
+
+        let mut preferences_open = self.preferences_open;
+        // icon: gear
+        let toggle = ToggleValue::new(&mut preferences_open, "⛭ Preferences")
+            .shortcut_text(self.fmt_shortcut(Menu::Preferences));
+        if ui.add(toggle).clicked() {
+            self.preferences_open = preferences_open;
+            ui.close_menu();
+        }
+
+        let mut keybinds_open = self.keybinds_open;
+        // icon: keyboard
+        let toggle = ToggleValue::new(&mut keybinds_open, "🖮 Keybinds")
+            .shortcut_text(self.fmt_shortcut(Menu::Keybinds));
+        if ui.add(toggle).clicked() {
+            self.keybinds_open = keybinds_open;
+            ui.close_menu();
+        };
     }
 
-    fn emulation_preferences(&mut self, ui: &mut Ui, cfg: &mut Config) {
+    fn window_menu(&mut self, ui: &mut Ui, cfg: &mut Config) {
         #[cfg(feature = "profiling")]
         puffin::profile_function!();
 
-        let grid = Grid::new("emulation_checkboxes")
-            .num_columns(2)
-            .spacing([80.0, 6.0]);
-        grid.show(ui, |ui| {
-            self.cycle_acurate_checkbox(ui, cfg, ShowShortcut::No);
-            let res = ui.checkbox(&mut cfg.emulation.auto_load, "Auto-Load")
-                .on_hover_text("Automatically load game state from the current save slot on load.");
-            if res.changed() {
-                self.tx.nes_event(ConfigEvent::AutoLoad(
-                    cfg.emulation.auto_load,
-                ));
-            }
-            ui.end_row();
+        ui.allocate_space(Vec2::new(Self::MENU_WIDTH, 0.0));
 
-            ui.vertical(|ui| {
-                self.rewind_checkbox(ui, cfg, ShowShortcut::No);
+        ui.menu_button("📏 Window Scale...", |ui| {
+            let scale = cfg.renderer.scale;
+            let button =
+                Button::new("Increment").shortcut_text(self.fmt_shortcut(Setting::IncrementScale));
+            if ui.add(button).clicked() {
+                let new_scale = cfg.increment_scale();
+                if scale != new_scale {
+                    self.resize_window = true;
+                    self.resize_texture = true;
+                    self.tx.nes_event(ConfigEvent::Scale(cfg.renderer.scale));
+                }
+            }
 
-                ui.add_enabled_ui(cfg.emulation.rewind, |ui| {
-                    ui.indent("rewind_settings", |ui| {
-                        ui.label("Seconds:")
-                            .on_hover_text("The maximum number of seconds to rewind.");
-                        let drag = DragValue::new(&mut cfg.emulation.rewind_seconds)
-                            .clamp_range(1..=360)
-                            .suffix(" seconds");
-                        let res = ui.add(drag);
-                        if res.changed() {
-                            self.tx.nes_event(ConfigEvent::RewindSeconds(cfg.emulation.rewind_seconds));
-                        }
-
-                        ui.label("Interval:")
-                            .on_hover_text("The frame interval to save rewind states.");
-                        let drag = DragValue::new(&mut cfg.emulation.rewind_interval)
-                            .clamp_range(1..=60)
-                            .suffix(" frames");
-                        let res = ui.add(drag);
-                        if res.changed() {
-                            self.tx.nes_event(ConfigEvent::RewindInterval(cfg.emulation.rewind_interval));
-                        }
-                    });
-                });
-            });
-
-            ui.vertical(|ui| {
-                let res = ui.checkbox(&mut cfg.emulation.auto_save, "Auto-Save")
-                    .on_hover_text(concat!(
-                        "Automatically save game state to the current save slot ",
-                        "on exit or unloading and an optional interval. ",
-                        "Setting to 0 will disable saving on an interval.",
-                    ));
-                if res.changed() {
-                    self.tx.nes_event(ConfigEvent::AutoSave(
-                        cfg.emulation.auto_save,
-                    ));
+            let button =
+                Button::new("Decrement").shortcut_text(self.fmt_shortcut(Setting::DecrementScale));
+            if ui.add(button).clicked() {
+                let new_scale = cfg.decrement_scale();
+                if scale != new_scale {
+                    self.resize_window = true;
+                    self.resize_texture = true;
+                    self.tx.nes_event(ConfigEvent::Scale(cfg.renderer.scale));
                 }
+            }
 
-                ui.add_enabled_ui(cfg.emulation.auto_save, |ui| {
-                    ui.indent("auto_save_settings", |ui| {
-                        let mut auto_save_interval = cfg.emulation.auto_save_interval.as_secs();
-                        ui.label("Interval:")
-                            .on_hover_text(concat!(
-                                "Set the interval to auto-save game state. ",
-                                "A value of `0` will still save on exit or unload while Auto-Save is enabled."
-                            ));
-                        let drag = DragValue::new(&mut auto_save_interval)
-                            .clamp_range(0..=60)
-                            .suffix(" seconds");
-                        let res = ui.add(drag);
-                        if res.changed() {
-                            cfg.emulation.auto_save_interval =
-                                Duration::from_secs(auto_save_interval);
-                            self.tx.nes_event(ConfigEvent::AutoSaveInterval(
-                                cfg.emulation.auto_save_interval,
-                            ));
-                        }
-                    });
-                });
-            });
-            ui.end_row();
+            self.window_scale_radio(ui, cfg);
 
-            let res = ui.checkbox(&mut cfg.deck.emulate_ppu_warmup, "Emulate PPU Warmup")
-                .on_hover_text(concat!(
-                    "Set whether to emulate PPU warmup where writes to certain registers are ignored. ",
-                    "Can result in some games not working correctly"
-                ));
+            ui.separator();
+
+            let res = ui
+                .button("⛶ Fit Screen")
+                .on_hover_text("Scale the window to fit the current monitor.");
             if res.clicked() {
-                self.tx.nes_event(EmulationEvent::EmulatePpuWarmup(cfg.deck.emulate_ppu_warmup));
+                if let Some(new_scale) = self.fit_screen_scale(ui.ctx(), cfg) {
+                    if scale != new_scale {
+                        cfg.renderer.scale = new_scale;
+                        self.resize_window = true;
+                        self.resize_texture = true;
+                        self.tx.nes_event(ConfigEvent::Scale(cfg.renderer.scale));
+                    }
+                }
+                ui.close_menu();
             }
-            ui.end_row();
         });
 
         ui.separator();
 
-        Grid::new("emulation_preferences")
-            .num_columns(2)
-            .spacing([40.0, 6.0])
-            .show(ui, |ui| {
-                ui.strong("Emulation Speed:");
-                self.speed_slider(ui, cfg);
-                ui.end_row();
+        self.fullscreen_checkbox(ui, cfg, ShowShortcut::Yes);
 
-                ui.strong("Run Ahead:")
-                    .on_hover_cursor(CursorIcon::Help)
-                    .on_hover_text(
-                        "Simulate a number of frames in the future to reduce input lag.",
+        if platform::supports(platform::Feature::Viewports) {
+            ui.add_enabled_ui(!cfg.renderer.fullscreen, |ui| {
+                let mut embed_viewports = ui.ctx().embed_viewports();
+                // icon: maximize
+                let res = ui
+                    .checkbox(&mut embed_viewports, "🗖 Embed viewports")
+                    .on_disabled_hover_text(
+                        "Non-embedded viewports are not supported while in fullscreen.",
                     );
-                self.run_ahead_slider(ui, cfg);
-                ui.end_row();
+                if res.clicked() {
+                    cfg.renderer.embed_viewports = embed_viewports;
+                    ui.ctx().set_embed_viewports(embed_viewports);
+                }
+            });
+        }
 
-                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
-                    ui.strong("Save Slot:")
-                        .on_hover_cursor(CursorIcon::Help)
-                        .on_hover_text(
-                            "Select which slot to use when saving or loading game state.",
-                        );
-                });
-                Grid::new("save_slots")
-                    .num_columns(2)
-                    .spacing([20.0, 6.0])
-                    .show(ui, |ui| self.save_slot_radio(ui, cfg, ShowShortcut::No));
-                ui.end_row();
+        ui.separator();
 
-                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
-                    ui.strong("Four Player:")
-                    .on_hover_cursor(CursorIcon::Help)
-                    .on_hover_text("Some game titles support up to 4 players (requires connected controllers).");
-                });
-                ui.vertical(|ui| self.four_player_radio(ui, cfg));
-                ui.end_row();
+        self.menubar_checkbox(ui, cfg, ShowShortcut::Yes);
+        self.messages_checkbox(ui, cfg, ShowShortcut::Yes);
+        self.audio_scope_checkbox(ui, cfg, ShowShortcut::Yes);
+        self.perf_stats_checkbox(ui, cfg, ShowShortcut::Yes);
+    }
 
-                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
-                    ui.strong("NES Region:")
-                        .on_hover_cursor(CursorIcon::Help)
-                        .on_hover_text("Which regional NES hardware to emulate.");
-                });
-                ui.vertical(|ui| self.nes_region_radio(ui, cfg));
-                ui.end_row();
+    fn plugins_menu(&mut self, ui: &mut Ui) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
 
-                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
-                    ui.strong("RAM State:")
-                        .on_hover_cursor(CursorIcon::Help)
-                        .on_hover_text("What values are read from NES RAM on load.");
-                });
-                ui.vertical(|ui| self.ram_state_radio(ui, cfg));
-                ui.end_row();
+        ui.allocate_space(Vec2::new(Self::MENU_WIDTH, 0.0));
+
+        for (index, plugin) in self.plugins.iter().enumerate() {
+            if plugin.menu_items.is_empty() {
+                ui.add_enabled(false, Button::new(&plugin.name));
+                continue;
+            }
+            ui.menu_button(&plugin.name, |ui| {
+                for (id, label) in &plugin.menu_items {
+                    if ui.button(label).clicked() {
+                        self.tx
+                            .nes_event(EmulationEvent::PluginMenuItem((index, *id)));
+                        ui.close_menu();
+                    }
+                }
             });
+        }
     }
 
-    fn audio_preferences(&mut self, ui: &mut Ui, cfg: &mut Config) {
+    fn debug_menu(&mut self, ui: &mut Ui) {
         #[cfg(feature = "profiling")]
         puffin::profile_function!();
 
-        let res = ui.checkbox(&mut cfg.audio.enabled, "Enable Audio");
+        ui.allocate_space(Vec2::new(Self::MENU_WIDTH, 0.0));
+
+        #[cfg(feature = "profiling")]
+        {
+            let mut profile = puffin::are_scopes_on();
+            ui.checkbox(&mut profile, "Enable Profiling")
+                .on_hover_text("Toggle the Puffin profiling window");
+            puffin::set_scopes_on(profile);
+        }
+
+        let mut perf_stats_open = self.perf_stats_open;
+        let toggle = ToggleValue::new(&mut perf_stats_open, "🛠 Performance Stats")
+            .shortcut_text(self.fmt_shortcut(Menu::PerfStats));
+        let res = ui
+            .add(toggle)
+            .on_hover_text("Show frame rate, frame time and resource usage statistics");
         if res.clicked() {
+            self.perf_stats_open = perf_stats_open;
             self.tx
-                .nes_event(ConfigEvent::AudioEnabled(cfg.audio.enabled));
+                .nes_event(EmulationEvent::ShowFrameStats(self.perf_stats_open));
+            ui.close_menu();
         }
 
-        ui.add_enabled_ui(cfg.audio.enabled, |ui| {
-            ui.indent("apu_channels", |ui| {
-                let channels = &mut cfg.deck.channels_enabled;
-                Grid::new("apu_channels")
-                    .spacing([60.0, 6.0])
-                    .num_columns(2)
-                    .show(ui, |ui| {
-                        if ui.checkbox(&mut channels[0], "Enable Pulse1").clicked() {
-                            let enabled = (Channel::Pulse1, channels[0]);
-                            self.tx.nes_event(ConfigEvent::ApuChannelEnabled(enabled));
-                        }
-                        if ui.checkbox(&mut channels[3], "Enable Noise").clicked() {
-                            let enabled = (Channel::Noise, channels[3]);
-                            self.tx.nes_event(ConfigEvent::ApuChannelEnabled(enabled));
-                        }
-                        ui.end_row();
+        let mut input_diagnostics_open = self.input_diagnostics_open;
+        let toggle = ToggleValue::new(&mut input_diagnostics_open, "🎮 Input Diagnostics");
+        let res = ui
+            .add(toggle)
+            .on_hover_text("Show raw input events, matched bindings, and joypad state");
+        if res.clicked() {
+            self.set_input_diagnostics_open(input_diagnostics_open);
+            ui.close_menu();
+        }
 
-                        if ui.checkbox(&mut channels[1], "Enable Pulse2").clicked() {
-                            let enabled = (Channel::Pulse2, channels[1]);
-                            self.tx.nes_event(ConfigEvent::ApuChannelEnabled(enabled));
-                        }
-                        if ui.checkbox(&mut channels[4], "Enable DMC").clicked() {
-                            let enabled = (Channel::Dmc, channels[4]);
-                            self.tx.nes_event(ConfigEvent::ApuChannelEnabled(enabled));
-                        }
-                        ui.end_row();
+        let mut mapper_inspector_open = self.mapper_inspector_open;
+        // icon: magnifying glass
+        let toggle = ToggleValue::new(&mut mapper_inspector_open, "🔍 Mapper Inspector");
+        let res = ui
+            .add(toggle)
+            .on_hover_text("Show decoded mapper registers and a history of register writes");
+        if res.clicked() {
+            self.set_mapper_inspector_open(mapper_inspector_open);
+            ui.close_menu();
+        }
 
-                        if ui.checkbox(&mut channels[2], "Enable Triangle").clicked() {
-                            let enabled = (Channel::Triangle, channels[2]);
-                            self.tx.nes_event(ConfigEvent::ApuChannelEnabled(enabled));
-                        }
-                        if ui.checkbox(&mut channels[5], "Enable Mapper").clicked() {
-                            let enabled = (Channel::Mapper, channels[5]);
-                            self.tx.nes_event(ConfigEvent::ApuChannelEnabled(enabled));
-                        }
-                        ui.end_row();
-                    });
+        let mut hex_editor_open = self.hex_editor_open;
+        // icon: input numbers
+        let toggle = ToggleValue::new(&mut hex_editor_open, "🔢 Hex Editor");
+        let res = ui
+            .add(toggle)
+            .on_hover_text("View and edit CPU, PPU, and cartridge memory as it changes");
+        if res.clicked() {
+            self.set_hex_editor_open(hex_editor_open);
+            ui.close_menu();
+        }
 
-                ui.separator();
+        let mut ram_search_open = self.ram_search_open;
+        // icon: magnifying glass
+        let toggle = ToggleValue::new(&mut ram_search_open, "🔎 RAM Search");
+        let res = ui.add(toggle).on_hover_text(
+            "Find and watch the RAM addresses of game values and freeze them as cheats",
+        );
+        if res.clicked() {
+            self.set_ram_search_open(ram_search_open);
+            ui.close_menu();
+        }
 
-                Grid::new("audio_settings")
-                    .spacing([40.0, 6.0])
-                    .num_columns(2)
-                    .show(ui, |ui| {
-                        ui.strong("Buffer Size:")
-                            .on_hover_cursor(CursorIcon::Help)
-                            .on_hover_text(
-                                "The audio sample buffer size allocated to the sound driver. Increased audio buffer size can help reduce audio underruns.",
-                            );
-                        let drag = DragValue::new(&mut cfg.audio.buffer_size)
-                            .speed(10)
-                            .clamp_range(0..=8192)
-                            .suffix(" samples");
-                        let res = ui.add(drag);
-                        if res.changed() {
-                            self.tx.nes_event(ConfigEvent::AudioBuffer(cfg.audio.buffer_size));
-                        }
-                        ui.end_row();
+        let mut code_data_logging = self.code_data_logging;
+        // icon: memo
+        let toggle = ToggleValue::new(&mut code_data_logging, "📝 Code/Data Logger");
+        let res = ui.add(toggle).on_hover_text(
+            "Log which bytes of PRG-ROM are executed as code or read as data to a .cdl file",
+        );
+        if res.clicked() {
+            self.code_data_logging = code_data_logging;
+            self.tx
+                .nes_event(EmulationEvent::CodeDataLog(self.code_data_logging));
+            ui.close_menu();
+        }
 
-                        ui.strong("Latency:")
+        #[cfg(debug_assertions)]
+        {
+            let res = ui.checkbox(&mut self.debug_on_hover, "Debug on Hover");
+            if res.clicked() {
+                ui.ctx().set_debug_on_hover(self.debug_on_hover);
+            }
+        }
+
+        ui.separator();
+
+        ui.add_enabled_ui(false, |ui| {
+            let debugger_shortcut = self.fmt_shortcut(Debug::Toggle(Debugger::Cpu));
+            let toggle = ToggleValue::new(&mut self.debugger_open, "🚧 Debugger")
+                .shortcut_text(debugger_shortcut);
+            let res = ui
+                .add(toggle)
+                .on_hover_text("Toggle the Debugger.")
+                .on_disabled_hover_text("Not yet implemented.");
+            if res.clicked() {
+                ui.close_menu();
+            }
+
+            let ppu_viewer_shortcut = self.fmt_shortcut(Debug::Toggle(Debugger::Ppu));
+            let toggle = ToggleValue::new(&mut self.ppu_viewer_open, "🌇 PPU Viewer")
+                .shortcut_text(ppu_viewer_shortcut);
+            let res = ui
+                .add(toggle)
+                .on_hover_text("Toggle the PPU Viewer.")
+                .on_disabled_hover_text("Not yet implemented.");
+            if res.clicked() {
+                ui.close_menu();
+            }
+
+            let apu_mixer_shortcut = self.fmt_shortcut(Debug::Toggle(Debugger::Apu));
+            let toggle = ToggleValue::new(&mut self.apu_mixer_open, "🎼 APU Mixer")
+                .shortcut_text(apu_mixer_shortcut);
+            let res = ui
+                .add(toggle)
+                .on_hover_text("Toggle the APU Mixer.")
+                .on_disabled_hover_text("Not yet implemented.");
+            if res.clicked() {
+                ui.close_menu();
+            }
+        });
+
+        ui.separator();
+
+        ui.add_enabled_ui(self.loaded_rom.is_some(), |ui| {
+            let button = Button::new("Step Into")
+                .shortcut_text(self.fmt_shortcut(Debug::Step(DebugStep::Into)));
+            let res = ui
+                .add(button)
+                .on_hover_text("Step a single CPU instruction.")
+                .on_disabled_hover_text(Self::NO_ROM_LOADED);
+            if res.clicked() {
+                self.tx
+                    .nes_event(EmulationEvent::DebugStep(DebugStep::Into));
+            }
+
+            let button = Button::new("Step Out")
+                .shortcut_text(self.fmt_shortcut(Debug::Step(DebugStep::Out)));
+            let res = ui
+                .add(button)
+                .on_hover_text("Step out of the current CPU function.")
+                .on_disabled_hover_text(Self::NO_ROM_LOADED);
+            if res.clicked() {
+                self.tx.nes_event(EmulationEvent::DebugStep(DebugStep::Out));
+            }
+
+            let button = Button::new("Step Over")
+                .shortcut_text(self.fmt_shortcut(Debug::Step(DebugStep::Over)));
+            let res = ui
+                .add(button)
+                .on_hover_text("Step over the next CPU instruction.")
+                .on_disabled_hover_text(Self::NO_ROM_LOADED);
+            if res.clicked() {
+                self.tx
+                    .nes_event(EmulationEvent::DebugStep(DebugStep::Over));
+            }
+
+            let button = Button::new("Step Scanline")
+                .shortcut_text(self.fmt_shortcut(Debug::Step(DebugStep::Scanline)));
+            let res = ui
+                .add(button)
+                .on_hover_text("Step an entire PPU scanline.")
+                .on_disabled_hover_text(Self::NO_ROM_LOADED);
+            if res.clicked() {
+                self.tx
+                    .nes_event(EmulationEvent::DebugStep(DebugStep::Scanline));
+            }
+
+            let button = Button::new("Step Frame")
+                .shortcut_text(self.fmt_shortcut(Debug::Step(DebugStep::Frame)));
+            let res = ui
+                .add(button)
+                .on_hover_text("Step an entire PPU Frame.")
+                .on_disabled_hover_text(Self::NO_ROM_LOADED);
+            if res.clicked() {
+                self.tx
+                    .nes_event(EmulationEvent::DebugStep(DebugStep::Frame));
+            }
+        });
+    }
+
+    fn help_menu(&mut self, ui: &mut Ui) {
+        ui.allocate_space(Vec2::new(Self::MENU_WIDTH, 0.0));
+
+        if self.version.requires_updates() && ui.button("🌐 Check for Updates...").clicked() {
+            match self.version.update_available() {
+                Ok(update_available) => self.update_window_open = update_available,
+                Err(err) => self.add_message(MessageType::Error, err.to_string()),
+            }
+            if !self.update_window_open {
+                self.add_message(
+                    MessageType::Info,
+                    format!("TetaNES v{} is up to date!", self.version.current()),
+                );
+            }
+            ui.close_menu();
+        }
+        ui.toggle_value(&mut self.about_open, "ℹ About");
+    }
+
+    fn nes_frame(&mut self, ui: &mut Ui, gamepads: &Gamepads, cfg: &mut Config) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        ui.set_enabled(self.pending_keybind.is_none());
+
+        let inner_res = CentralPanel::default()
+            .frame(Frame::none())
+            .show_inside(ui, |ui| {
+                if self.loaded_rom.is_some() {
+                    let layout = Layout {
+                        main_dir: Direction::TopDown,
+                        main_align: Align::Center,
+                        cross_align: Align::Center,
+                        ..Default::default()
+                    };
+                    let overscan = cfg.overscan(self.region(cfg));
+                    let orientation = cfg.renderer.orientation;
+                    let frame_size = overscan.frame_size();
+                    let size = orientation.size(Vec2::new(
+                        frame_size.x * self.aspect_ratio(cfg),
+                        frame_size.y,
+                    ));
+                    ui.with_layout(layout, |ui| {
+                        let available = ui.available_size();
+                        let scale = (available.x / size.x).min(available.y / size.y);
+                        let (rect, res) = ui.allocate_exact_size(size * scale, Sense::click());
+                        if ui.is_rect_visible(rect) {
+                            let mesh = orientation.mesh(self.texture.id, rect, overscan.uv());
+                            ui.painter().add(Shape::mesh(mesh));
+                        }
+                        let vaus = self.vaus_connected(cfg);
+                        let hover_cursor = if cfg.deck.mouse.is_some() {
+                            CursorIcon::None
+                        } else if cfg.deck.zapper {
+                            CursorIcon::Crosshair
+                        } else if vaus {
+                            CursorIcon::ResizeHorizontal
+                        } else {
+                            CursorIcon::Default
+                        };
+                        let res = res.on_hover_cursor(hover_cursor);
+                        self.nes_frame = res.rect;
+
+                        if cfg.deck.zapper {
+                            if self
+                                .action_input(DeckAction::ZapperAimOffscreen)
+                                .map_or(false, |input| input_down(ui, gamepads, cfg, input))
+                            {
+                                let pos = (Ppu::WIDTH + 10, Ppu::HEIGHT + 10);
+                                self.tx.nes_event(EmulationEvent::ZapperAim(pos));
+                            } else if let Some(Pos2 { x, y }) =
+                                res.hover_pos().and_then(|Pos2 { x, y }| {
+                                    cursor_to_zapper(x, y, res.rect, overscan, orientation)
+                                })
+                            {
+                                let pos = (x.round() as u32, y.round() as u32);
+                                self.tx.nes_event(EmulationEvent::ZapperAim(pos));
+                            }
+                            if res.clicked() {
+                                self.tx.nes_event(EmulationEvent::ZapperTrigger);
+                            }
+                        }
+
+                        if vaus {
+                            self.vaus_input(ui, &res, overscan, orientation);
+                        } else {
+                            self.vaus_input = None;
+                        }
+                        if cfg.deck.mouse.is_some() {
+                            self.mouse_input(ui, &res);
+                        } else {
+                            self.mouse_buttons = None;
+                        }
+                    });
+                } else {
+                    ui.vertical_centered(|ui| {
+                        ui.horizontal_centered(|ui| {
+                            let image = Image::new(include_image!("../../../assets/tetanes.png"))
+                                .shrink_to_fit()
+                                .tint(Color32::GRAY);
+                            ui.add(image);
+                        });
+                    });
+                }
+            });
+
+        // Start at the left-top of the NES frame.
+        let mut messages_pos = inner_res.response.rect.left_top();
+
+        let mut recording_labels = Vec::new();
+        if self.replay_recording {
+            recording_labels.push("Replay");
+        }
+        if self.audio_recording {
+            recording_labels.push("Audio");
+        }
+        if self.map_dumping {
+            recording_labels.push("Map");
+        }
+        if self.sprite_ripping {
+            recording_labels.push("Sprites");
+        }
+        if !recording_labels.is_empty() {
+            let inner_res = Area::new(Id::new("status"))
+                .order(Order::Foreground)
+                .fixed_pos(messages_pos)
+                .show(ui.ctx(), |ui| {
+                    Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.with_layout(
+                            Layout::top_down_justified(Align::LEFT).with_main_wrap(true),
+                            |ui| ui.label(format!("Recording {}", recording_labels.join(" & "))),
+                        );
+                    });
+                });
+            // Update to the left-bottom of this area, if rendered
+            messages_pos = inner_res.response.rect.left_bottom();
+        }
+
+        if cfg.renderer.show_messages && (!self.messages.is_empty() || self.error.is_some()) {
+            Area::new(Id::new("messages"))
+                .order(Order::Foreground)
+                .fixed_pos(messages_pos)
+                .show(ui.ctx(), |ui| {
+                    Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.with_layout(
+                            Layout::top_down_justified(Align::LEFT).with_main_wrap(true),
+                            |ui| {
+                                self.message_bar(ui);
+                                self.error_bar(ui);
+                            },
+                        );
+                    });
+                });
+        }
+
+        if self
+            .loaded_rom
+            .as_ref()
+            .is_some_and(|rom| rom.header.is_nsf())
+        {
+            self.nsf_player_overlay(ui);
+        }
+
+        if cfg.renderer.show_audio_scope && self.loaded_rom.is_some() {
+            self.audio_scope_overlay(ui, cfg);
+        }
+
+        if cfg.renderer.show_perf_stats && self.loaded_rom.is_some() {
+            self.perf_stats_overlay(ui, cfg);
+        }
+
+        if self.loaded_rom.is_some() {
+            self.plugin_overlay(ui);
+            self.touch_controller
+                .show(ui.ctx(), inner_res.response.rect, cfg, &self.tx);
+        }
+
+        if let Some(auto_saved) = self.auto_saved {
+            let elapsed = auto_saved.elapsed();
+            if elapsed < Self::AUTO_SAVE_INDICATOR_TIMEOUT && self.loaded_rom.is_some() {
+                Area::new(Id::new("auto_save_indicator"))
+                    .order(Order::Foreground)
+                    .interactable(false)
+                    .pivot(Align2::RIGHT_BOTTOM)
+                    .fixed_pos(self.nes_frame.right_bottom() - Vec2::splat(4.0))
+                    .show(ui.ctx(), |ui| {
+                        ui.label(RichText::new("💾").size(16.0));
+                    });
+                ui.ctx()
+                    .request_repaint_after(Self::AUTO_SAVE_INDICATOR_TIMEOUT - elapsed);
+            } else {
+                self.auto_saved = None;
+            }
+        }
+
+        let mut frame = Frame::none();
+        if self.paused {
+            frame = Frame::dark_canvas(ui.style()).multiply_with_opacity(0.7);
+        }
+
+        frame.show(ui, |ui| {
+            ui.with_layout(Layout::centered_and_justified(Direction::TopDown), |ui| {
+                if self.paused {
+                    ui.heading(RichText::new("⏸").size(40.0));
+                }
+            });
+        });
+    }
+
+    /// Move the Super NES Mouse with the mouse over the NES frame, pressing its buttons while the
+    /// primary or secondary mouse buttons are held.
+    fn mouse_input(&mut self, ui: &Ui, res: &Response) {
+        if !res.hovered() {
+            return;
+        }
+        let (delta, left, right) = ui.input(|i| {
+            (
+                i.pointer.delta(),
+                i.pointer.primary_down(),
+                i.pointer.secondary_down(),
+            )
+        });
+        if delta != Vec2::ZERO {
+            self.tx
+                .nes_event(EmulationEvent::MouseMotion((delta.x, delta.y)));
+        }
+        if self.mouse_buttons != Some((left, right)) {
+            self.tx
+                .nes_event(EmulationEvent::MouseButtons((left, right)));
+            self.mouse_buttons = Some((left, right));
+        }
+    }
+
+    /// Turn the Arkanoid Vaus paddle with the mouse over the NES frame, pressing its button while
+    /// the primary mouse button is held.
+    fn vaus_input(
+        &mut self,
+        ui: &Ui,
+        res: &Response,
+        overscan: Overscan,
+        orientation: Orientation,
+    ) {
+        let (mut x, _) = self.vaus_input.unwrap_or((0.5, false));
+        if let Some(pos) = res
+            .hover_pos()
+            .and_then(|Pos2 { x, y }| cursor_to_zapper(x, y, res.rect, overscan, orientation))
+        {
+            x = pos.x / Ppu::WIDTH as f32;
+        }
+        let pressed = res.hovered() && ui.input(|i| i.pointer.primary_down());
+        if self.vaus_input.map_or(true, |(prev_x, _)| prev_x != x) {
+            self.tx.nes_event(EmulationEvent::VausAim(x));
+        }
+        if self
+            .vaus_input
+            .map_or(true, |(_, prev_pressed)| prev_pressed != pressed)
+        {
+            self.tx.nes_event(EmulationEvent::VausButton(pressed));
+        }
+        self.vaus_input = Some((x, pressed));
+    }
+
+    fn message_bar(&mut self, ui: &mut Ui) {
+        let now = Instant::now();
+        self.messages.retain(|(_, _, expires)| now < *expires);
+        self.messages.dedup_by(|a, b| a.1.eq(&b.1));
+        for (ty, message, _) in self.messages.iter().take(Self::MAX_MESSAGES) {
+            let visuals = &ui.style().visuals;
+            let (icon, color) = match ty {
+                MessageType::Info => ("ℹ", visuals.widgets.noninteractive.fg_stroke.color),
+                MessageType::Warn => ("⚠", visuals.warn_fg_color),
+                MessageType::Error => ("❗", visuals.error_fg_color),
+            };
+            ui.colored_label(color, format!("{icon} {message}"));
+        }
+    }
+
+    fn error_bar(&mut self, ui: &mut Ui) {
+        if let Some(error) = self.error.clone() {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(error).color(Color32::RED));
+                if ui.button("").clicked() {
+                    self.error = None;
+                }
+            });
+        }
+    }
+
+    fn plugin_overlay(&self, ui: &Ui) {
+        let mut overlays = self
+            .plugins
+            .iter()
+            .filter_map(|plugin| plugin.overlay.as_deref())
+            .peekable();
+        if overlays.peek().is_none() {
+            return;
+        }
+        Area::new(Id::new("plugin_overlay"))
+            .order(Order::Foreground)
+            .interactable(false)
+            .pivot(Align2::LEFT_BOTTOM)
+            .fixed_pos(self.nes_frame.left_bottom() + Vec2::new(4.0, -4.0))
+            .show(ui.ctx(), |ui| {
+                Frame::popup(ui.style())
+                    .multiply_with_opacity(0.8)
+                    .show(ui, |ui| {
+                        for overlay in overlays {
+                            ui.label(overlay);
+                        }
+                    });
+            });
+    }
+
+    /// Music player for NSF files, which don't draw anything to the NES frame.
+    fn nsf_player_overlay(&mut self, ui: &Ui) {
+        let Some(sound_info) = self
+            .loaded_rom
+            .as_ref()
+            .and_then(|rom| rom.sound_info.as_ref())
+        else {
+            return;
+        };
+        let (track, elapsed) = self.nsf_state.unwrap_or_default();
+        let track_count = sound_info.track_count.unwrap_or(1).max(1);
+        let mut selected_track = None;
+
+        Area::new(Id::new("nsf_player"))
+            .order(Order::Foreground)
+            .pivot(Align2::CENTER_CENTER)
+            .fixed_pos(self.nes_frame.center())
+            .show(ui.ctx(), |ui| {
+                Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_max_width(self.nes_frame.width() - 16.0);
+                    ui.heading(sound_info.title.as_deref().unwrap_or("Unknown Title"));
+                    for info in [&sound_info.artist, &sound_info.copyright]
+                        .into_iter()
+                        .flatten()
+                    {
+                        ui.label(info);
+                    }
+                    let expansion_audio = sound_info.expansion_audio;
+                    if !expansion_audio.is_empty() {
+                        ui.label(format!("Expansion Audio: {expansion_audio}"));
+                        let unsupported = expansion_audio.unsupported();
+                        if !unsupported.is_empty() {
+                            ui.colored_label(
+                                ui.style().visuals.warn_fg_color,
+                                format!("⚠ {unsupported} audio is not supported"),
+                            );
+                        }
+                    }
+
+                    ui.separator();
+
+                    let track_name = |index: u8| {
+                        sound_info
+                            .track_name(usize::from(index))
+                            .map_or_else(|| format!("Track {}", index + 1), str::to_string)
+                    };
+                    ui.strong(format!(
+                        "{}/{track_count}: {}",
+                        track + 1,
+                        track_name(track)
+                    ));
+                    let length = sound_info.track_length(usize::from(track));
+                    ui.label(match length {
+                        Some(length) => {
+                            format!("{} / {}", fmt_track_time(elapsed), fmt_track_time(length))
+                        }
+                        None => fmt_track_time(elapsed),
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("⏮").on_hover_text("Previous track").clicked() {
+                            selected_track = Some(track.checked_sub(1).unwrap_or(track_count - 1));
+                        }
+                        if ui.button("🔄").on_hover_text("Restart track").clicked() {
+                            selected_track = Some(track);
+                        }
+                        if ui.button("⏭").on_hover_text("Next track").clicked() {
+                            selected_track = Some((track + 1) % track_count);
+                        }
+                    });
+
+                    ui.separator();
+
+                    ScrollArea::vertical()
+                        .max_height(self.nes_frame.height() / 2.0)
+                        .show(ui, |ui| {
+                            for index in 0..track_count {
+                                let mut label = format!("{:>3}. {}", index + 1, track_name(index));
+                                if let Some(length) = sound_info.track_length(usize::from(index)) {
+                                    label += &format!(" ({})", fmt_track_time(length));
+                                }
+                                if ui.selectable_label(index == track, label).clicked() {
+                                    selected_track = Some(index);
+                                }
+                            }
+                        });
+                });
+            });
+
+        if let Some(track) = selected_track {
+            self.tx.nes_event(EmulationEvent::NsfTrack(track));
+        }
+    }
+
+    /// Pivot and position to anchor an overlay to a corner of the NES frame.
+    fn overlay_anchor(&self, position: OverlayPosition) -> (Align2, Pos2) {
+        let padding = Vec2::splat(4.0);
+        match position {
+            OverlayPosition::TopLeft => (Align2::LEFT_TOP, self.nes_frame.left_top() + padding),
+            OverlayPosition::TopRight => (
+                Align2::RIGHT_TOP,
+                self.nes_frame.right_top() + Vec2::new(-padding.x, padding.y),
+            ),
+            OverlayPosition::BottomLeft => (
+                Align2::LEFT_BOTTOM,
+                self.nes_frame.left_bottom() + Vec2::new(padding.x, -padding.y),
+            ),
+            OverlayPosition::BottomRight => (
+                Align2::RIGHT_BOTTOM,
+                self.nes_frame.right_bottom() - padding,
+            ),
+        }
+    }
+
+    fn audio_scope_overlay(&self, ui: &Ui, cfg: &Config) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        const SCOPE_SIZE: Vec2 = Vec2::new(96.0, 20.0);
+        const CHANNELS: [(&str, Color32); Apu::MAX_CHANNEL_COUNT] = [
+            ("P1", Color32::from_rgb(0xE0, 0x60, 0x60)),
+            ("P2", Color32::from_rgb(0xE0, 0xA0, 0x40)),
+            ("TRI", Color32::from_rgb(0x60, 0xC0, 0x60)),
+            ("NOI", Color32::from_rgb(0x60, 0xA0, 0xE0)),
+            ("DMC", Color32::from_rgb(0xB0, 0x70, 0xE0)),
+            ("EXP", Color32::from_rgb(0xC0, 0xC0, 0xC0)),
+        ];
+
+        let (pivot, pos) = self.overlay_anchor(cfg.renderer.audio_scope_position);
+        let scope_size = SCOPE_SIZE * cfg.renderer.audio_scope_scale;
+        // Expansion audio is only shown when the mapper is producing output
+        let has_mapper_output = self
+            .audio_scope
+            .iter()
+            .any(|samples| samples[Channel::Mapper as usize] != 0.0);
+
+        Area::new(Id::new("audio_scope"))
+            .order(Order::Foreground)
+            .interactable(false)
+            .pivot(pivot)
+            .fixed_pos(pos)
+            .show(ui.ctx(), |ui| {
+                Frame::popup(ui.style())
+                    .multiply_with_opacity(0.8)
+                    .show(ui, |ui| {
+                        if let Some(sound_info) = self
+                            .loaded_rom
+                            .as_ref()
+                            .and_then(|rom| rom.sound_info.as_ref())
+                        {
+                            let mut label = sound_info
+                                .driver
+                                .map(|driver| driver.to_string())
+                                .into_iter()
+                                .chain(sound_info.title.clone())
+                                .collect::<Vec<_>>()
+                                .join(" - ");
+                            if let Some(track_count) = sound_info.track_count {
+                                label += &format!(" ({track_count} tracks)");
+                            }
+                            ui.label(RichText::new(label.trim()).small());
+                        }
+                        for (channel, (label, color)) in CHANNELS.into_iter().enumerate() {
+                            if channel == Channel::Mapper as usize && !has_mapper_output {
+                                continue;
+                            }
+                            ui.horizontal(|ui| {
+                                ui.add_sized(
+                                    Vec2::new(24.0, scope_size.y),
+                                    Label::new(RichText::new(label).small().color(color)),
+                                );
+                                let (res, painter) =
+                                    ui.allocate_painter(scope_size, Sense::hover());
+                                let rect = res.rect;
+                                painter.rect_filled(rect, 2.0, Color32::from_black_alpha(96));
+
+                                let sample_count = self.audio_scope.len();
+                                if sample_count == 0 {
+                                    return;
+                                }
+                                let point_count = (rect.width() as usize).max(2);
+                                let points = (0..point_count)
+                                    .map(|i| {
+                                        let idx = i * sample_count / point_count;
+                                        let level = self.audio_scope[idx][channel].clamp(0.0, 1.0);
+                                        let x = rect.left()
+                                            + rect.width() * i as f32 / (point_count - 1) as f32;
+                                        let y = rect.bottom() - 1.0 - level * (rect.height() - 2.0);
+                                        Pos2::new(x, y)
+                                    })
+                                    .collect::<Vec<_>>();
+                                painter.add(Shape::line(points, Stroke::new(1.0, color)));
+                            });
+                        }
+                    });
+            });
+    }
+
+    fn perf_stats_overlay(&self, ui: &Ui, cfg: &Config) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        const GRAPH_SIZE: Vec2 = Vec2::new(160.0, 40.0);
+
+        let stats = &self.frame_stats;
+        let target_frame_time =
+            FrameRate::from(self.loaded_region).duration().as_secs_f32() * 1000.0;
+        // Frame times up to double the target fit in the graph
+        let graph_max = 2.0 * target_frame_time;
+        let good_color = if ui.style().visuals.dark_mode {
+            hex_color!("#b8cc52")
+        } else {
+            hex_color!("#86b300")
+        };
+        let frame_time_color = |time: f32| {
+            if time <= 1.1 * target_frame_time {
+                good_color
+            } else if time <= graph_max {
+                ui.style().visuals.warn_fg_color
+            } else {
+                ui.style().visuals.error_fg_color
+            }
+        };
+
+        let (pivot, pos) = self.overlay_anchor(cfg.renderer.perf_stats_position);
+        Area::new(Id::new("perf_stats_overlay"))
+            .order(Order::Foreground)
+            .interactable(false)
+            .pivot(pivot)
+            .fixed_pos(pos)
+            .show(ui.ctx(), |ui| {
+                Frame::popup(ui.style())
+                    .multiply_with_opacity(0.8)
+                    .show(ui, |ui| {
+                        ui.label(
+                            RichText::new(format!(
+                                "{:.1} FPS  {:.2} ms",
+                                stats.fps, stats.frame_time
+                            ))
+                            .small()
+                            .color(frame_time_color(stats.frame_time)),
+                        );
+
+                        let (res, painter) = ui.allocate_painter(GRAPH_SIZE, Sense::hover());
+                        let rect = res.rect;
+                        painter.rect_filled(rect, 2.0, Color32::from_black_alpha(96));
+                        let y = |time: f32| {
+                            rect.bottom() - (time / graph_max).clamp(0.0, 1.0) * rect.height()
+                        };
+                        painter.hline(
+                            rect.x_range(),
+                            y(target_frame_time),
+                            Stroke::new(1.0, Color32::from_gray(96)),
+                        );
+                        // Newest frames are on the right, scrolling left
+                        let dx = rect.width() / (History::LEN - 1) as f32;
+                        let first = History::LEN.saturating_sub(stats.frame_times.len());
+                        for (i, times) in stats.frame_times.windows(2).enumerate() {
+                            let x = rect.left() + (first + i) as f32 * dx;
+                            painter.line_segment(
+                                [Pos2::new(x, y(times[0])), Pos2::new(x + dx, y(times[1]))],
+                                Stroke::new(1.0, frame_time_color(times[1])),
+                            );
+                        }
+
+                        Grid::new("perf_stats_overlay_times")
+                            .num_columns(2)
+                            .spacing([12.0, 0.0])
+                            .show(ui, |ui| {
+                                let times = [
+                                    ("Emulation", stats.emulation_time),
+                                    ("Audio", stats.audio_time),
+                                    ("Render", self.render_time.avg() * 1000.0),
+                                ];
+                                for (label, time) in times {
+                                    ui.label(RichText::new(label).small());
+                                    ui.label(RichText::new(format!("{time:.2} ms")).small());
+                                    ui.end_row();
+                                }
+                                ui.label(RichText::new("Dropped").small());
+                                ui.label(RichText::new(stats.dropped_frames.to_string()).small());
+                                ui.end_row();
+                                ui.label(RichText::new("Skipped").small());
+                                ui.label(RichText::new(stats.skipped_frames.to_string()).small());
+                                ui.end_row();
+                            });
+
+                        let fill = if stats.audio_latency > 0.0 {
+                            stats.audio_queued / stats.audio_latency
+                        } else {
+                            0.0
+                        };
+                        ui.add(
+                            ProgressBar::new(fill.clamp(0.0, 1.0))
+                                .desired_width(GRAPH_SIZE.x)
+                                .text(
+                                    RichText::new(format!(
+                                        "Audio {:.0} / {:.0} ms",
+                                        stats.audio_queued, stats.audio_latency
+                                    ))
+                                    .small(),
+                                ),
+                        );
+                    });
+            });
+    }
+
+    fn performance_stats(&mut self, ui: &mut Ui, cfg: &Config) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        ui.allocate_space(Vec2::new(200.0, 0.0));
+        ui.set_enabled(self.pending_keybind.is_none());
+
+        let grid = Grid::new("perf_stats").num_columns(2).spacing([40.0, 6.0]);
+        grid.show(ui, |ui| {
+            ui.ctx().request_repaint_after(Duration::from_secs(1));
+
+            if let Some(sys) = &mut self.sys {
+                // NOTE: refreshing sysinfo is cpu-intensive if done too frequently and skews the
+                // results
+                let sys_update_interval = Duration::from_secs(1);
+                assert!(sys_update_interval > sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+                if self.sys_updated.elapsed() >= sys_update_interval {
+                    sys.refresh_specifics(
+                        RefreshKind::new().with_processes(
+                            ProcessRefreshKind::new()
+                                .with_cpu()
+                                .with_memory()
+                                .with_disk_usage(),
+                        ),
+                    );
+                    self.sys_updated = Instant::now();
+                }
+            }
+
+            let good_color = if ui.style().visuals.dark_mode {
+                hex_color!("#b8cc52")
+            } else {
+                hex_color!("#86b300")
+            };
+            let warn_color = ui.style().visuals.warn_fg_color;
+            let bad_color = ui.style().visuals.error_fg_color;
+            let fps_color = |fps| match fps {
+                fps if fps < 30.0 => bad_color,
+                fps if fps < 60.0 => warn_color,
+                _ => good_color,
+            };
+            let frame_time_color = |time| match time {
+                time if time <= 1000.0 * 1.0 / 60.0 => good_color,
+                time if time <= 1000.0 * 1.0 / 30.0 => warn_color,
+                _ => bad_color,
+            };
+            let cpu_color = |cpu| match cpu {
+                cpu if cpu <= 25.0 => good_color,
+                cpu if cpu <= 50.0 => warn_color,
+                _ => bad_color,
+            };
+
+            let fps = self.frame_stats.fps;
+            ui.strong("FPS:");
+            if fps.is_finite() {
+                ui.colored_label(fps_color(fps), format!("{fps:.2}"));
+            } else {
+                ui.label("N/A");
+            }
+            ui.end_row();
+
+            let fps_min = self.frame_stats.fps_min;
+            ui.strong("FPS (min):");
+            if fps_min.is_finite() {
+                ui.colored_label(fps_color(fps_min), format!("{fps_min:.2}"));
+            } else {
+                ui.label("N/A");
+            }
+            ui.end_row();
+
+            let frame_time = self.frame_stats.frame_time;
+            ui.strong("Frame Time:");
+            if frame_time.is_finite() {
+                ui.colored_label(frame_time_color(frame_time), format!("{frame_time:.2} ms"));
+            } else {
+                ui.label("N/A");
+            }
+            ui.end_row();
+
+            let frame_time_max = self.frame_stats.frame_time_max;
+            ui.strong("Frame Time (max):");
+            if frame_time_max.is_finite() {
+                ui.colored_label(
+                    frame_time_color(frame_time_max),
+                    format!("{frame_time_max:.2} ms"),
+                );
+            } else {
+                ui.label("N/A");
+            }
+            ui.end_row();
+
+            ui.strong("Frame Count:");
+            ui.label(format!("{}", self.frame_stats.frame_count));
+            ui.end_row();
+
+            ui.strong("Emulation Time:");
+            ui.label(format!("{:.2} ms", self.frame_stats.emulation_time));
+            ui.end_row();
+
+            ui.strong("Audio Time:");
+            ui.label(format!("{:.2} ms", self.frame_stats.audio_time));
+            ui.end_row();
+
+            ui.strong("Render Time:");
+            ui.label(format!("{:.2} ms", self.render_time.avg() * 1000.0));
+            ui.end_row();
+
+            ui.strong("Audio Buffer:");
+            ui.label(format!(
+                "{:.0} / {:.0} ms",
+                self.frame_stats.audio_queued, self.frame_stats.audio_latency
+            ));
+            ui.end_row();
+
+            ui.strong("Dropped Frames:");
+            ui.label(format!("{}", self.frame_stats.dropped_frames));
+            ui.end_row();
+
+            ui.strong("Skipped Frames:");
+            ui.label(format!("{}", self.frame_stats.skipped_frames));
+            ui.end_row();
+
+            if let Some(ref sys) = self.sys {
+                ui.label("");
+                ui.end_row();
+
+                match sys.process(Pid::from_u32(std::process::id())) {
+                    Some(proc) => {
+                        ui.strong("CPU:");
+                        let cpu_usage = proc.cpu_usage();
+                        ui.colored_label(cpu_color(cpu_usage), format!("{cpu_usage:.2}%"));
+                        ui.end_row();
+
+                        ui.strong("Memory:");
+                        ui.label(format!("{} MB", bytes_to_mb(proc.memory()),));
+                        ui.end_row();
+
+                        let du = proc.disk_usage();
+                        ui.strong("Disk read new/total:");
+                        ui.label(format!(
+                            "{:.2}/{:.2} MB",
+                            bytes_to_mb(du.read_bytes),
+                            bytes_to_mb(du.total_read_bytes)
+                        ));
+                        ui.end_row();
+
+                        ui.strong("Disk written new/total:");
+                        ui.label(format!(
+                            "{:.2}/{:.2} MB",
+                            bytes_to_mb(du.written_bytes),
+                            bytes_to_mb(du.total_written_bytes),
+                        ));
+                        ui.end_row();
+                    }
+                    None => todo!(),
+                }
+            }
+
+            ui.label("");
+            ui.end_row();
+
+            ui.strong("Run Time:");
+            ui.label(format!("{} s", self.start.elapsed().as_secs()));
+            ui.end_row();
+
+            let (cursor_pos, zapper_pos) = match ui.input(|i| i.pointer.latest_pos()) {
+                Some(Pos2 { x, y }) => {
+                    let zapper_pos = match cursor_to_zapper(x, y, self.nes_frame) {
+                        Some(Pos2 { x, y }) => format!("({x:.0}, {y:.0})"),
+                        None => "(-, -)".to_string(),
+                    };
+                    (format!("({x:.0}, {y:.0})"), zapper_pos)
+                }
+                None => ("(-, -)".to_string(), "(-, -)".to_string()),
+            };
+
+            ui.strong("Cursor Pos:");
+            ui.label(cursor_pos);
+            ui.end_row();
+
+            if cfg.deck.zapper {
+                ui.strong("Zapper Pos:");
+                ui.label(zapper_pos);
+                ui.end_row();
+            }
+        });
+    }
+
+    fn preferences(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        ui.set_enabled(self.pending_keybind.is_none());
+
+        ScrollArea::vertical().show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.selectable_value(
+                    &mut self.preferences_tab,
+                    PreferencesTab::Emulation,
+                    "Emulation",
+                );
+                ui.selectable_value(&mut self.preferences_tab, PreferencesTab::Audio, "Audio");
+                ui.selectable_value(&mut self.preferences_tab, PreferencesTab::Video, "Video");
+                ui.selectable_value(&mut self.preferences_tab, PreferencesTab::Input, "Input");
+            });
+
+            ui.separator();
+
+            match self.preferences_tab {
+                PreferencesTab::Emulation => self.emulation_preferences(ui, cfg),
+                PreferencesTab::Audio => self.audio_preferences(ui, cfg),
+                PreferencesTab::Video => self.video_preferences(ui, cfg),
+                PreferencesTab::Input => self.input_preferences(ui, cfg),
+            }
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                if ui.button("Restore Defaults").clicked() {
+                    cfg.reset();
+                    self.shortcut_keybinds = Self::shortcut_keybinds(&cfg.input.shortcuts);
+                    self.joypad_keybinds = Self::joypad_keybinds(&cfg.input.joypad_bindings);
+                    self.tx.nes_event(ConfigEvent::InputBindings);
+                }
+                if platform::supports(platform::Feature::Filesystem) {
+                    if let Some(data_dir) = Config::default_data_dir() {
+                        if ui.button("Clear Save States").clicked() {
+                            match fs::clear_dir(data_dir) {
+                                Ok(_) => {
+                                    self.add_message(MessageType::Info, "Save States cleared.")
+                                }
+                                Err(_) => self.add_message(
+                                    MessageType::Error,
+                                    "Failed to clear Save States.",
+                                ),
+                            }
+                        }
+                        if ui.button("Clear Recent ROMs").clicked() {
+                            cfg.renderer.recent_roms.clear();
+                        }
+                    }
+                }
+            });
+        });
+    }
+
+    fn emulation_preferences(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        let grid = Grid::new("emulation_checkboxes")
+            .num_columns(2)
+            .spacing([80.0, 6.0]);
+        grid.show(ui, |ui| {
+            self.cycle_acurate_checkbox(ui, cfg, ShowShortcut::No);
+            let res = ui.checkbox(&mut cfg.emulation.auto_load, "Auto-Load")
+                .on_hover_text("Automatically load game state from the current save slot on load.");
+            if res.changed() {
+                self.tx.nes_event(ConfigEvent::AutoLoad(
+                    cfg.emulation.auto_load,
+                ));
+            }
+            ui.end_row();
+
+            ui.vertical(|ui| {
+                self.rewind_checkbox(ui, cfg, ShowShortcut::No);
+
+                ui.add_enabled_ui(cfg.emulation.rewind, |ui| {
+                    ui.indent("rewind_settings", |ui| {
+                        ui.label("Seconds:")
+                            .on_hover_text("The maximum number of seconds to rewind.");
+                        let drag = DragValue::new(&mut cfg.emulation.rewind_seconds)
+                            .clamp_range(1..=360)
+                            .suffix(" seconds");
+                        let res = ui.add(drag);
+                        if res.changed() {
+                            self.tx.nes_event(ConfigEvent::RewindSeconds(cfg.emulation.rewind_seconds));
+                        }
+
+                        ui.label("Interval:")
+                            .on_hover_text("The frame interval to save rewind states.");
+                        let drag = DragValue::new(&mut cfg.emulation.rewind_interval)
+                            .clamp_range(1..=60)
+                            .suffix(" frames");
+                        let res = ui.add(drag);
+                        if res.changed() {
+                            self.tx.nes_event(ConfigEvent::RewindInterval(cfg.emulation.rewind_interval));
+                        }
+                    });
+                });
+            });
+
+            ui.vertical(|ui| {
+                let res = ui.checkbox(&mut cfg.emulation.auto_save, "Auto-Save")
+                    .on_hover_text(concat!(
+                        "Automatically save game state to the current save slot ",
+                        "on exit or unloading and an optional interval. ",
+                        "Setting to 0 will disable saving on an interval.",
+                    ));
+                if res.changed() {
+                    self.tx.nes_event(ConfigEvent::AutoSave(
+                        cfg.emulation.auto_save,
+                    ));
+                }
+
+                ui.add_enabled_ui(cfg.emulation.auto_save, |ui| {
+                    ui.indent("auto_save_settings", |ui| {
+                        let mut auto_save_interval = cfg.emulation.auto_save_interval.as_secs();
+                        ui.label("Interval:")
+                            .on_hover_text(concat!(
+                                "Set the interval to auto-save game state, battery-backed RAM and ",
+                                "configuration changes. ",
+                                "A value of `0` will still save on exit or unload while Auto-Save is enabled."
+                            ));
+                        let drag = DragValue::new(&mut auto_save_interval)
+                            .clamp_range(0..=60)
+                            .suffix(" seconds");
+                        let res = ui.add(drag);
+                        if res.changed() {
+                            cfg.emulation.auto_save_interval =
+                                Duration::from_secs(auto_save_interval);
+                            self.tx.nes_event(ConfigEvent::AutoSaveInterval(
+                                cfg.emulation.auto_save_interval,
+                            ));
+                        }
+                    });
+                });
+
+                ui.horizontal(|ui| {
+                    let mut sram_autosave_interval = cfg
+                        .deck
+                        .sram_autosave_interval
+                        .map_or(0, |interval| interval.as_secs());
+                    ui.label("Battery Save Interval:")
+                        .on_hover_text(concat!(
+                            "Set the interval in game time to save battery-backed RAM if it changed, ",
+                            "independent of Auto-Save. ",
+                            "A value of `0` will still save on exit or unload."
+                        ));
+                    let drag = DragValue::new(&mut sram_autosave_interval)
+                        .clamp_range(0..=300)
+                        .suffix(" seconds");
+                    let res = ui.add(drag);
+                    if res.changed() {
+                        cfg.deck.sram_autosave_interval = (sram_autosave_interval > 0)
+                            .then_some(Duration::from_secs(sram_autosave_interval));
+                        self.tx.nes_event(ConfigEvent::SramAutosaveInterval(
+                            cfg.deck.sram_autosave_interval,
+                        ));
+                    }
+                });
+            });
+            ui.end_row();
+
+            let res = ui.checkbox(&mut cfg.deck.emulate_ppu_warmup, "Emulate PPU Warmup")
+                .on_hover_text(concat!(
+                    "Set whether to emulate PPU warmup where writes to certain registers are ignored. ",
+                    "Can result in some games not working correctly. Takes effect on the next reset."
+                ));
+            if res.clicked() {
+                self.tx.nes_event(EmulationEvent::EmulatePpuWarmup(cfg.deck.emulate_ppu_warmup));
+            }
+
+            if platform::supports(platform::Feature::LanTransfer) {
+                ui.horizontal(|ui| {
+                    let res = ui.checkbox(&mut cfg.emulation.lan_transfer, "LAN State Transfer")
+                        .on_hover_text(concat!(
+                            "Discover other TetaNES instances on the local network to send and ",
+                            "receive save states. Save states are only accepted from paired devices."
+                        ));
+                    if res.clicked() {
+                        self.tx.nes_event(ConfigEvent::LanTransfer(cfg.emulation.lan_transfer));
+                    }
+                    let paired = cfg.emulation.lan_trusted_peers.len();
+                    if paired > 0 {
+                        let res = ui.button(format!("Forget {paired} Paired"))
+                            .on_hover_text("Stop accepting save states from previously paired devices.");
+                        if res.clicked() {
+                            cfg.emulation.lan_trusted_peers.clear();
+                            self.tx.nes_event(ConfigEvent::LanTrustedPeers(Vec::new()));
+                        }
+                    }
+                });
+            }
+            ui.end_row();
+
+            if platform::supports(platform::Feature::Filesystem) {
+                let dir = Plugins::dir().map_or_else(String::new, |dir| dir.display().to_string());
+                ui.checkbox(&mut cfg.emulation.plugins, "Load Plugins")
+                    .on_hover_text(format!(
+                        "Load plugins from {dir} on startup. Takes effect after restarting."
+                    ));
+                let res = ui.checkbox(&mut cfg.emulation.auto_patch, "Auto-Patch")
+                    .on_hover_text(concat!(
+                        "Apply an `.ips`, `.bps` or `.ups` patch next to a loaded ROM with the ",
+                        "same name, leaving the ROM file unchanged."
+                    ));
+                if res.changed() {
+                    self.tx.nes_event(ConfigEvent::AutoPatch(cfg.emulation.auto_patch));
+                }
+                ui.end_row();
+            }
+        });
+
+        ui.separator();
+
+        Grid::new("emulation_preferences")
+            .num_columns(2)
+            .spacing([40.0, 6.0])
+            .show(ui, |ui| {
+                ui.strong("Emulation Speed:");
+                self.speed_slider(ui, cfg);
+                ui.end_row();
+
+                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                    ui.strong("Fast Forward:")
+                        .on_hover_cursor(CursorIcon::Help)
+                        .on_hover_text(format!(
+                            "How {} speeds up emulation.",
+                            self.fmt_shortcut(Setting::FastForward)
+                        ));
+                });
+                ui.vertical(|ui| self.fast_forward_settings(ui, cfg));
+                ui.end_row();
+
+                ui.strong("Run Ahead:")
+                    .on_hover_cursor(CursorIcon::Help)
+                    .on_hover_text(
+                        "Simulate a number of frames in the future to reduce input lag.",
+                    );
+                self.run_ahead_slider(ui, cfg);
+                ui.end_row();
+
+                ui.strong("Max Frame Skip:")
+                    .on_hover_cursor(CursorIcon::Help)
+                    .on_hover_text(
+                        "Emulate up to a number of frames in a row without rendering them \
+                        when falling behind, to keep audio from stuttering.",
+                    );
+                let slider = Slider::new(&mut cfg.emulation.max_frame_skip, 0..=9);
+                if ui.add(slider).changed() {
+                    self.tx
+                        .nes_event(ConfigEvent::MaxFrameSkip(cfg.emulation.max_frame_skip));
+                }
+                ui.end_row();
+
+                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                    ui.strong("In Background:")
+                        .on_hover_cursor(CursorIcon::Help)
+                        .on_hover_text(
+                            "What to do while the window is unfocused or hidden.",
+                        );
+                });
+                ui.vertical(|ui| self.background_mode_radio(ui, cfg));
+                ui.end_row();
+
+                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                    ui.strong("Resume Session:")
+                        .on_hover_cursor(CursorIcon::Help)
+                        .on_hover_text(concat!(
+                            "Save a session state on exit or unloading, separate from the save ",
+                            "slots, and resume it the next time the same ROM is loaded.",
+                        ));
+                });
+                ui.vertical(|ui| self.session_mode_radio(ui, cfg));
+                ui.end_row();
+
+                if platform::supports(platform::Feature::Filesystem) {
+                    ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                        ui.strong("Live Reload:")
+                            .on_hover_cursor(CursorIcon::Help)
+                            .on_hover_text(concat!(
+                                "Reload the ROM when its file changes, e.g. to see changes to a ",
+                                "homebrew ROM as soon as it's rebuilt.",
+                            ));
+                    });
+                    ui.vertical(|ui| self.live_reload_settings(ui, cfg));
+                    ui.end_row();
+                }
+
+                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                    ui.strong("Save Slot:")
+                        .on_hover_cursor(CursorIcon::Help)
+                        .on_hover_text(
+                            "Select which slot to use when saving or loading game state.",
+                        );
+                });
+                Grid::new("save_slots")
+                    .num_columns(2)
+                    .spacing([20.0, 6.0])
+                    .show(ui, |ui| self.save_slot_radio(ui, cfg, ShowShortcut::No));
+                ui.end_row();
+
+                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                    ui.strong("Four Player:")
+                    .on_hover_cursor(CursorIcon::Help)
+                    .on_hover_text("Some game titles support up to 4 players (requires connected controllers).");
+                });
+                ui.vertical(|ui| self.four_player_radio(ui, cfg));
+                ui.end_row();
+
+                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                    ui.strong("Mouse:")
+                        .on_hover_cursor(CursorIcon::Help)
+                        .on_hover_text(
+                            "Connect a Super NES Mouse for games and homebrew that support it.",
+                        );
+                });
+                ui.vertical(|ui| self.mouse_radio(ui, cfg));
+                ui.end_row();
+
+                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                    ui.strong("Expansion Port:")
+                        .on_hover_cursor(CursorIcon::Help)
+                        .on_hover_text(
+                            "Device connected to the Famicom expansion port for games that \
+                            support it.",
+                        );
+                });
+                ui.vertical(|ui| self.expansion_device_radio(ui, cfg));
+                ui.end_row();
+
+                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                    ui.strong("NES Region:")
+                        .on_hover_cursor(CursorIcon::Help)
+                        .on_hover_text("Which regional NES hardware to emulate.");
+                });
+                ui.vertical(|ui| self.nes_region_radio(ui, cfg));
+                ui.end_row();
+
+                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                    ui.strong("MMC3 Revision:")
+                        .on_hover_cursor(CursorIcon::Help)
+                        .on_hover_text(
+                            "Which MMC3 chip revision to emulate. Some games rely on the IRQ \
+                            behavior of a specific revision, which can't be detected from the ROM.",
+                        );
+                });
+                ui.vertical(|ui| self.mmc3_revision_radio(ui, cfg));
+                ui.end_row();
+
+                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                    ui.strong("RAM State:")
+                        .on_hover_cursor(CursorIcon::Help)
+                        .on_hover_text("What values are read from NES RAM on load.");
+                });
+                ui.vertical(|ui| self.ram_state_radio(ui, cfg));
+                ui.end_row();
+
+                if platform::supports(platform::Feature::Filesystem) {
+                    ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                        ui.strong("Screenshots:")
+                            .on_hover_cursor(CursorIcon::Help)
+                            .on_hover_text("What to capture when taking a screenshot.");
+                    });
+                    ui.vertical(|ui| self.screenshot_settings(ui, cfg));
+                    ui.end_row();
+                }
+
+                if platform::supports(platform::Feature::RomDbUpdates) {
+                    ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                        ui.strong("ROM Database:")
+                            .on_hover_cursor(CursorIcon::Help)
+                            .on_hover_text(
+                                "Database used to detect game regions and compatibility patches.",
+                            );
+                    });
+                    ui.vertical(|ui| self.rom_db_settings(ui, cfg));
+                    ui.end_row();
+                }
+
+                if platform::supports(platform::Feature::LiveSplit) {
+                    ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                        ui.strong("LiveSplit:")
+                            .on_hover_cursor(CursorIcon::Help)
+                            .on_hover_text(
+                                "Control a LiveSplit timer using autosplitters for each game.",
+                            );
+                    });
+                    ui.vertical(|ui| self.livesplit_settings(ui, cfg));
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn audio_preferences(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        let res = ui.checkbox(&mut cfg.audio.enabled, "Enable Audio");
+        if res.clicked() {
+            self.tx
+                .nes_event(ConfigEvent::AudioEnabled(cfg.audio.enabled));
+        }
+
+        ui.add_enabled_ui(cfg.audio.enabled, |ui| {
+            ui.indent("apu_channels", |ui| {
+                let channels = &mut cfg.deck.channels_enabled;
+                Grid::new("apu_channels")
+                    .spacing([60.0, 6.0])
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        if ui.checkbox(&mut channels[0], "Enable Pulse1").clicked() {
+                            let enabled = (Channel::Pulse1, channels[0]);
+                            self.tx.nes_event(ConfigEvent::ApuChannelEnabled(enabled));
+                        }
+                        if ui.checkbox(&mut channels[3], "Enable Noise").clicked() {
+                            let enabled = (Channel::Noise, channels[3]);
+                            self.tx.nes_event(ConfigEvent::ApuChannelEnabled(enabled));
+                        }
+                        ui.end_row();
+
+                        if ui.checkbox(&mut channels[1], "Enable Pulse2").clicked() {
+                            let enabled = (Channel::Pulse2, channels[1]);
+                            self.tx.nes_event(ConfigEvent::ApuChannelEnabled(enabled));
+                        }
+                        if ui.checkbox(&mut channels[4], "Enable DMC").clicked() {
+                            let enabled = (Channel::Dmc, channels[4]);
+                            self.tx.nes_event(ConfigEvent::ApuChannelEnabled(enabled));
+                        }
+                        ui.end_row();
+
+                        if ui.checkbox(&mut channels[2], "Enable Triangle").clicked() {
+                            let enabled = (Channel::Triangle, channels[2]);
+                            self.tx.nes_event(ConfigEvent::ApuChannelEnabled(enabled));
+                        }
+                        if ui.checkbox(&mut channels[5], "Enable Mapper").clicked() {
+                            let enabled = (Channel::Mapper, channels[5]);
+                            self.tx.nes_event(ConfigEvent::ApuChannelEnabled(enabled));
+                        }
+                        ui.end_row();
+                    });
+
+                ui.separator();
+
+                self.mixer_sliders(ui, cfg);
+
+                ui.separator();
+
+                let res = ui
+                    .checkbox(&mut cfg.audio.record_stems, "Record Channel Stems")
+                    .on_hover_text(
+                        "Save a separate WAV file for each APU channel when recording audio.",
+                    );
+                if res.clicked() {
+                    self.tx
+                        .nes_event(ConfigEvent::AudioRecordStems(cfg.audio.record_stems));
+                }
+
+                let res = ui
+                    .checkbox(&mut cfg.audio.dynamic_rate_control, "Dynamic Rate Control")
+                    .on_hover_text(
+                        "Slightly adjust the audio sample rate to avoid underruns and growing latency.",
+                    );
+                if res.clicked() {
+                    self.tx.nes_event(ConfigEvent::DynamicRateControl(
+                        cfg.audio.dynamic_rate_control,
+                    ));
+                }
+
+                ui.separator();
+
+                Grid::new("audio_settings")
+                    .spacing([40.0, 6.0])
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.strong("Buffer Size:")
+                            .on_hover_cursor(CursorIcon::Help)
+                            .on_hover_text(
+                                "The audio sample buffer size allocated to the sound driver. Increased audio buffer size can help reduce audio underruns.",
+                            );
+                        let drag = DragValue::new(&mut cfg.audio.buffer_size)
+                            .speed(10)
+                            .clamp_range(0..=8192)
+                            .suffix(" samples");
+                        let res = ui.add(drag);
+                        if res.changed() {
+                            self.tx.nes_event(ConfigEvent::AudioBuffer(cfg.audio.buffer_size));
+                        }
+                        ui.end_row();
+
+                        ui.strong("Latency:")
                             .on_hover_cursor(CursorIcon::Help)
                             .on_hover_text(
                                 "The amount of queued audio before sending to the sound driver. Increased audio latency can help reduce audio underruns.",
@@ -1986,581 +4277,2534 @@ impl Gui {
                             cfg.audio.latency = Duration::from_millis(latency);
                             self.tx.nes_event(ConfigEvent::AudioLatency(cfg.audio.latency));
                         }
-                        ui.end_row();
+                        ui.end_row();
+
+                        ui.strong("Fast Forward:")
+                            .on_hover_cursor(CursorIcon::Help)
+                            .on_hover_text(
+                                "How audio is played while emulation speed is above 100%.",
+                            );
+                        ui.vertical(|ui| self.fast_forward_audio_radio(ui, cfg));
+                        ui.end_row();
+
+                        ui.strong("Filters:")
+                            .on_hover_cursor(CursorIcon::Help)
+                            .on_hover_text(
+                                "Filters applied to audio output, emulating the analog output of the console.",
+                            );
+                        ui.vertical(|ui| self.audio_filters_settings(ui, cfg));
+                        ui.end_row();
+                    });
+            });
+        });
+    }
+
+    fn mixer_sliders(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        fn volume_slider(volume: &mut f32) -> Slider<'_> {
+            Slider::new(volume, 0.0..=Mixer::MAX_VOLUME)
+                .step_by(0.05)
+                .custom_formatter(|volume, _| format!("{:.0}%", volume * 100.0))
+        }
+
+        Grid::new("mixer")
+            .spacing([40.0, 6.0])
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.strong("Master Volume:");
+                let res = ui.add(volume_slider(&mut cfg.deck.mixer.master_volume));
+                if res.changed() {
+                    self.tx
+                        .nes_event(ConfigEvent::MasterVolume(cfg.deck.mixer.master_volume));
+                }
+                ui.end_row();
+
+                for (channel, label) in [
+                    (Channel::Pulse1, "Pulse1"),
+                    (Channel::Pulse2, "Pulse2"),
+                    (Channel::Triangle, "Triangle"),
+                    (Channel::Noise, "Noise"),
+                    (Channel::Dmc, "DMC"),
+                    (Channel::Mapper, "Mapper"),
+                ] {
+                    ui.label(format!("{label} Volume:"));
+                    let volume = &mut cfg.deck.mixer.channel_volumes[channel as usize];
+                    if ui.add(volume_slider(volume)).changed() {
+                        self.tx
+                            .nes_event(ConfigEvent::ApuChannelVolume((channel, *volume)));
+                    }
+                    ui.end_row();
+                }
+            });
+
+        let res = ui
+            .checkbox(&mut cfg.deck.mixer.accurate, "Accurate Mixing")
+            .on_hover_text(
+                "Mix channels with the nonlinear DAC formulas, where loud channels slightly quiet the others, instead of lookup tables.",
+            );
+        if res.clicked() {
+            self.tx
+                .nes_event(ConfigEvent::AccurateMixing(cfg.deck.mixer.accurate));
+        }
+    }
+
+    fn audio_filters_settings(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let prev_filters = cfg.deck.audio_filters;
+        ui.horizontal(|ui| {
+            for (preset, label, description) in [
+                (
+                    FilterConfig::NES,
+                    "NES",
+                    "90 Hz and 440 Hz high-pass and 14 kHz low-pass, like the NES.",
+                ),
+                (
+                    FilterConfig::FAMICOM,
+                    "Famicom",
+                    "37 Hz high-pass and 14 kHz low-pass, like the Famicom.",
+                ),
+                (
+                    FilterConfig::BYPASS,
+                    "Bypass",
+                    "No filters, for clean output.",
+                ),
+            ] {
+                ui.selectable_value(&mut cfg.deck.audio_filters, preset, label)
+                    .on_hover_text(description);
+            }
+        });
+
+        let filters = &mut cfg.deck.audio_filters;
+        let [high_pass1, high_pass2] = &mut filters.high_pass;
+        for (label, cutoff, default_cutoff) in [
+            ("High-Pass", high_pass1, 90.0),
+            ("High-Pass", high_pass2, 440.0),
+            ("Low-Pass", &mut filters.low_pass, 14_000.0),
+        ] {
+            ui.horizontal(|ui| {
+                let mut enabled = cutoff.is_some();
+                if ui.checkbox(&mut enabled, label).changed() {
+                    *cutoff = enabled.then_some(default_cutoff);
+                }
+                let mut value = cutoff.unwrap_or(default_cutoff);
+                let drag = DragValue::new(&mut value)
+                    .speed(1.0)
+                    .clamp_range(10.0..=20_000.0)
+                    .suffix(" Hz");
+                if ui.add_enabled(enabled, drag).changed() {
+                    *cutoff = Some(value);
+                }
+            });
+        }
+
+        if cfg.deck.audio_filters != prev_filters {
+            self.tx
+                .nes_event(ConfigEvent::AudioFilters(cfg.deck.audio_filters));
+        }
+    }
+
+    fn fast_forward_audio_radio(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let fast_forward = cfg.audio.fast_forward;
+        for (value, description) in [
+            (
+                FastForwardAudio::Normal,
+                "Play audio at full volume with a higher pitch.",
+            ),
+            (FastForwardAudio::Mute, "Silence audio."),
+            (FastForwardAudio::Quiet, "Play audio at 25% volume."),
+            (
+                FastForwardAudio::PitchLocked,
+                "Time-stretch audio to keep the original pitch.",
+            ),
+        ] {
+            ui.radio_value(&mut cfg.audio.fast_forward, value, value.as_ref())
+                .on_hover_text(description);
+        }
+        if fast_forward != cfg.audio.fast_forward {
+            self.tx
+                .nes_event(ConfigEvent::FastForwardAudio(cfg.audio.fast_forward));
+        }
+    }
+
+    fn video_preferences(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        Grid::new("video_checkboxes")
+            .spacing([80.0, 6.0])
+            .num_columns(2)
+            .show(ui, |ui| {
+                self.menubar_checkbox(ui, cfg, ShowShortcut::No);
+                self.fullscreen_checkbox(ui, cfg, ShowShortcut::No);
+                ui.end_row();
+
+                self.messages_checkbox(ui, cfg, ShowShortcut::No);
+                self.audio_scope_checkbox(ui, cfg, ShowShortcut::No);
+                ui.end_row();
+
+                self.overscan_checkbox(ui, cfg, ShowShortcut::No);
+                self.perf_stats_checkbox(ui, cfg, ShowShortcut::No);
+                ui.end_row();
+
+                let res = ui
+                    .checkbox(&mut cfg.renderer.high_contrast, "High Contrast")
+                    .on_hover_text("Pure background and text colors with bolder outlines.");
+                if res.changed() {
+                    Self::set_style(ui.ctx(), cfg);
+                }
+                ui.end_row();
+            });
+
+        ui.separator();
+
+        Grid::new("video_preferences")
+            .num_columns(2)
+            .spacing([40.0, 6.0])
+            .show(ui, |ui| {
+                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                    ui.strong("Window Scale:");
+                });
+                Grid::new("save_slots")
+                    .num_columns(2)
+                    .spacing([20.0, 6.0])
+                    .show(ui, |ui| self.window_scale_radio(ui, cfg));
+                ui.end_row();
+
+                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                    ui.strong("Overscan:");
+                });
+                ui.vertical(|ui| Self::overscan_settings(ui, cfg))
+                    .response
+                    .on_hover_text("Pixels cropped from each edge of the frame.");
+                ui.end_row();
+
+                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                    ui.strong("Orientation:");
+                });
+                ui.vertical(|ui| self.orientation_settings(ui, cfg));
+                ui.end_row();
+
+                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                    ui.strong("Video Filter:");
+                });
+                ui.vertical(|ui| self.video_filter_radio(ui, cfg));
+                ui.end_row();
+
+                if matches!(cfg.deck.filter, VideoFilter::Ntsc | VideoFilter::Crt) {
+                    ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                        ui.strong("NTSC Settings:");
+                    });
+                    ui.vertical(|ui| self.ntsc_settings(ui, cfg));
+                    ui.end_row();
+                }
+
+                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                    ui.strong("Post Processing:");
+                });
+                ui.vertical(|ui| self.post_process_list(ui, cfg));
+                ui.end_row();
+
+                if platform::supports(platform::Feature::Filesystem) {
+                    ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                        ui.strong("Shader Preset:");
+                    });
+                    ui.vertical(|ui| self.shader_preset_settings(ui, cfg));
+                    ui.end_row();
+                }
+
+                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                    ui.strong("Audio Scope Position:");
+                });
+                Grid::new("audio_scope_position")
+                    .num_columns(2)
+                    .spacing([20.0, 6.0])
+                    .show(ui, |ui| {
+                        Self::overlay_position_radio(ui, &mut cfg.renderer.audio_scope_position);
+                    });
+                ui.end_row();
+
+                ui.strong("UI Scale:");
+                let res = ui
+                    .add(
+                        Slider::new(&mut cfg.renderer.ui_scale, 0.75..=3.0)
+                            .step_by(0.25)
+                            .suffix("x"),
+                    )
+                    .on_hover_text("Scale of menu and window text, independent of Window Scale.");
+                // Wait until dragging stops so the slider doesn't move out from under the cursor
+                if res.drag_stopped() || (res.changed() && !res.dragged()) {
+                    Self::set_style(ui.ctx(), cfg);
+                }
+                ui.end_row();
+
+                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                    ui.strong("Touch Controller:");
+                });
+                ui.vertical(|ui| Self::touch_controller_settings(ui, cfg));
+                ui.end_row();
+
+                ui.strong("Audio Scope Size:");
+                ui.add(
+                    Slider::new(&mut cfg.renderer.audio_scope_scale, 0.5..=3.0)
+                        .step_by(0.25)
+                        .suffix("x"),
+                );
+                ui.end_row();
+
+                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+                    ui.strong("Performance Overlay Position:");
+                });
+                Grid::new("perf_stats_position")
+                    .num_columns(2)
+                    .spacing([20.0, 6.0])
+                    .show(ui, |ui| {
+                        Self::overlay_position_radio(ui, &mut cfg.renderer.perf_stats_position);
+                    });
+                ui.end_row();
+            });
+    }
+
+    fn shader_preset_settings(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        ui.horizontal(|ui| {
+            let selected = cfg
+                .renderer
+                .shader_preset
+                .as_deref()
+                .map_or_else(|| "None".to_string(), shader::preset_name);
+            let mut preset = cfg.renderer.shader_preset.clone();
+            egui::ComboBox::from_id_source("shader_preset")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut preset, None, "None");
+                    for path in shader::list_presets() {
+                        let name = shader::preset_name(&path);
+                        ui.selectable_value(&mut preset, Some(path), name);
+                    }
+                });
+            if preset != cfg.renderer.shader_preset {
+                cfg.renderer.shader_preset = preset;
+                cfg.renderer.shader_parameters.clear();
+            }
+
+            let res = ui
+                .add_enabled(
+                    cfg.renderer.shader_preset.is_some(),
+                    Button::new("⟲ Reload"),
+                )
+                .on_hover_text("Reload the preset and its shaders after editing them.");
+            if res.clicked() {
+                self.reload_shader = true;
+            }
+        });
+        if let Some(dir) = shader::presets_dir() {
+            ui.label(format!(
+                "WGSL presets (.{}) are loaded from {}",
+                shader::PRESET_EXTENSION,
+                dir.display()
+            ));
+        }
+
+        if !self.shader_parameters.is_empty() {
+            Grid::new("shader_parameters")
+                .num_columns(2)
+                .spacing([20.0, 6.0])
+                .show(ui, |ui| {
+                    for param in &self.shader_parameters {
+                        ui.label(&param.label);
+                        let mut value = cfg
+                            .renderer
+                            .shader_parameters
+                            .get(&param.name)
+                            .copied()
+                            .unwrap_or(param.default);
+                        let slider = Slider::new(&mut value, param.min..=param.max)
+                            .step_by(f64::from(param.step));
+                        if ui.add(slider).changed() {
+                            cfg.renderer
+                                .shader_parameters
+                                .insert(param.name.clone(), value);
+                        }
+                        ui.end_row();
+                    }
+                });
+            if ui.button("Reset Parameters").clicked() {
+                cfg.renderer.shader_parameters.clear();
+            }
+        }
+    }
+
+    fn input_preferences(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        Grid::new("input_checkboxes")
+            .num_columns(2)
+            .spacing([80.0, 6.0])
+            .show(ui, |ui| {
+                self.zapper_checkbox(ui, cfg, ShowShortcut::No);
+                ui.end_row();
+
+                self.vaus_checkbox(ui, cfg, ShowShortcut::No);
+                ui.end_row();
+
+                let res = ui.checkbox(&mut cfg.deck.concurrent_dpad, "Enable Concurrent D-Pad");
+                if res.clicked() {
+                    self.tx
+                        .nes_event(ConfigEvent::ConcurrentDpad(cfg.deck.concurrent_dpad));
+                }
+            });
+    }
+
+    fn keybinds(&mut self, ui: &mut Ui, gamepads: &mut Gamepads, cfg: &mut Config) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        self.show_set_keybind_viewport(ui.ctx(), gamepads, cfg);
+        self.show_gamepad_unassign_viewport(ui.ctx(), gamepads, cfg);
+        ui.set_enabled(self.pending_keybind.is_none() && self.gamepad_unassign.is_none());
+
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.keybinds_tab, KeybindsTab::Shortcuts, "Shortcuts");
+            ui.selectable_value(
+                &mut self.keybinds_tab,
+                KeybindsTab::Joypad(Player::One),
+                "Player1",
+            );
+            ui.selectable_value(
+                &mut self.keybinds_tab,
+                KeybindsTab::Joypad(Player::Two),
+                "Player2",
+            );
+            ui.selectable_value(
+                &mut self.keybinds_tab,
+                KeybindsTab::Joypad(Player::Three),
+                "Player3",
+            );
+            ui.selectable_value(
+                &mut self.keybinds_tab,
+                KeybindsTab::Joypad(Player::Four),
+                "Player4",
+            );
+        });
+
+        ui.separator();
+
+        match self.keybinds_tab {
+            KeybindsTab::Shortcuts => self.keybind_list(ui, gamepads, cfg, None),
+            KeybindsTab::Joypad(player) => self.keybind_list(ui, gamepads, cfg, Some(player)),
+        }
+    }
+
+    fn keybind_list(
+        &mut self,
+        ui: &mut Ui,
+        gamepads: &mut Gamepads,
+        cfg: &mut Config,
+        player: Option<Player>,
+    ) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        if let Some(player) = player {
+            self.player_gamepad_combo(ui, player, gamepads, cfg);
+
+            ui.separator();
+        }
+
+        let keybinds = match player {
+            None => &mut self.shortcut_keybinds,
+            Some(player) => &mut self.joypad_keybinds[player as usize],
+        };
+
+        // Joypad buttons are held, so only shortcuts have a repeat policy
+        let shortcuts = player.is_none();
+        ScrollArea::both().show(ui, |ui| {
+            ui.set_width(ui.available_width()); // Pushes scrollbar to the right of the window
+
+            let grid = Grid::new("keybind_list")
+                .num_columns(if shortcuts { 4 } else { 3 })
+                .spacing([40.0, 6.0]);
+            grid.show(ui, |ui| {
+                ui.heading("Action");
+                ui.heading("Binding #1");
+                ui.heading("Binding #2");
+                if shortcuts {
+                    ui.heading("Repeat");
+                }
+                ui.end_row();
+
+                for (action, input) in keybinds.values_mut() {
+                    ui.strong(action.to_string());
+                    for (slot, input) in input.iter_mut().enumerate() {
+                        let button = Button::new(input.map(format_input).unwrap_or_default())
+                            .min_size(Vec2::new(100.0, 0.0));
+                        let res = ui
+                            .add(button)
+                            .on_hover_text("Click to set. Right-click to unset.");
+                        if res.clicked() {
+                            self.pending_keybind = Some(PendingKeybind {
+                                action: *action,
+                                player,
+                                binding: slot,
+                                input: None,
+                                conflict: None,
+                            });
+                        } else if res.secondary_clicked() {
+                            if let Some(input) = input.take() {
+                                cfg.input.clear_binding(input);
+                                self.tx.nes_event(ConfigEvent::InputBindings);
+                            }
+                        }
+                    }
+                    if shortcuts && Self::repeat_policy_edit(ui, cfg, *action) {
+                        self.tx.nes_event(ConfigEvent::InputBindings);
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    /// Edit the repeat policy of a shortcut `action`, returning whether it changed.
+    fn repeat_policy_edit(ui: &mut Ui, cfg: &mut Config, action: Action) -> bool {
+        if !action.is_repeatable() {
+            ui.weak("Never").on_hover_text(
+                "Held actions and actions that should only happen once per press never repeat.",
+            );
+            return false;
+        }
+
+        let mut policy = cfg.input.repeat_policy(action);
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source(("repeat_policy", action))
+                .selected_text(policy.as_str())
+                .show_ui(ui, |ui| {
+                    for option in [
+                        RepeatPolicy::None,
+                        RepeatPolicy::DEFAULT_DELAY_RATE,
+                        RepeatPolicy::Continuous,
+                    ] {
+                        let checked = mem::discriminant(&option) == mem::discriminant(&policy);
+                        if ui.selectable_label(checked, option.as_str()).clicked() && !checked {
+                            policy = option;
+                            changed = true;
+                        }
+                    }
+                })
+                .response
+                .on_hover_text(
+                    "None triggers once when released. Delay + Rate and Continuous trigger when \
+                    pressed and repeat while held.",
+                );
+            if let RepeatPolicy::DelayRate {
+                delay_ms,
+                interval_ms,
+            } = &mut policy
+            {
+                changed |= ui
+                    .add(DragValue::new(delay_ms).clamp_range(0..=2000).suffix(" ms"))
+                    .on_hover_text("Delay before repeating.")
+                    .changed();
+                changed |= ui
+                    .add(
+                        DragValue::new(interval_ms)
+                            .clamp_range(1..=1000)
+                            .suffix(" ms"),
+                    )
+                    .on_hover_text("Interval between repeats.")
+                    .changed();
+            }
+        });
+        if changed {
+            cfg.input.set_repeat_policy(action, policy);
+        }
+        changed
+    }
+
+    fn player_gamepad_combo(
+        &mut self,
+        ui: &mut Ui,
+        player: Player,
+        gamepads: &Gamepads,
+        cfg: &mut Config,
+    ) {
+        ui.horizontal(|ui| {
+            ui.strong("Assigned Gamepad:");
+
+            let unassigned = "Unassigned".to_string();
+            match gamepads.list() {
+                Some(mut list) => {
+                    if list.peek().is_some() {
+                        let mut assigned_gamepad = cfg.input.gamepad_assigned_to(player);
+                        let previous_gamepad = assigned_gamepad;
+                        let gamepad_name = assigned_gamepad
+                            .and_then(|uuid| gamepads.gamepad_name_by_uuid(&uuid))
+                            .unwrap_or_else(|| unassigned.clone());
+                        let combo = egui::ComboBox::from_id_source("assigned_gamepad")
+                            .selected_text(gamepad_name.clone());
+                        combo.show_ui(ui, |ui| {
+                            ui.selectable_value(&mut assigned_gamepad, None, unassigned);
+                            for (id, gamepad) in list {
+                                let uuid = gamepads
+                                    .gamepad_uuid(id)
+                                    .unwrap_or_else(|| Gamepads::create_uuid(&gamepad));
+                                ui.selectable_value(
+                                    &mut assigned_gamepad,
+                                    Some(uuid),
+                                    gamepad.name(),
+                                );
+                            }
+                        });
+                        if previous_gamepad != assigned_gamepad {
+                            match &assigned_gamepad {
+                                Some(uuid) => {
+                                    match assigned_gamepad
+                                        .as_ref()
+                                        .and_then(|name| cfg.input.gamepad_assignment(name))
+                                    {
+                                        Some(existing_player) => {
+                                            self.gamepad_unassign =
+                                                Some((existing_player, player, *uuid));
+                                        }
+                                        None => self.assign_gamepad(player, *uuid, gamepads, cfg),
+                                    }
+                                }
+                                None => {
+                                    self.unassign_gamepad(player, gamepads, cfg);
+                                }
+                            }
+                        }
+                    } else {
+                        ui.set_enabled(false);
+                        let combo = egui::ComboBox::from_id_source("assigned_gamepad")
+                            .selected_text("No Gamepads Connected");
+                        combo
+                            .show_ui(ui, |_| {})
+                            .response
+                            .on_disabled_hover_text(
+                                "Some platforms, like web browsers, only detect a gamepad once a button is pressed on it.",
+                            );
+                    }
+                }
+                None => {
+                    ui.set_enabled(false);
+                    let combo = egui::ComboBox::from_id_source("assigned_gamepad")
+                        .selected_text("Gamepads not supported");
+                    combo.show_ui(ui, |_| {});
+                }
+            }
+        });
+    }
+
+    fn about(&mut self, ui: &mut Ui) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        ui.set_enabled(self.pending_keybind.is_none());
+
+        ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
+            let image = Image::new(include_image!("../../../assets/tetanes_icon.png"))
+                .max_height(50.0)
+                .shrink_to_fit();
+            ui.add(image);
+
+            ui.vertical(|ui| {
+                let grid = Grid::new("version").num_columns(2).spacing([40.0, 6.0]);
+                grid.show(ui, |ui| {
+                    ui.strong("Version:");
+                    ui.label(self.version.current());
+                    ui.end_row();
+
+                    ui.strong("GitHub:");
+                    ui.hyperlink("https://github.com/lukexor/tetanes");
+                    ui.end_row();
+                });
+
+                if platform::supports(platform::Feature::Filesystem) {
+                    ui.separator();
+                    ui.horizontal_wrapped(|ui| {
+                        let grid = Grid::new("directories").num_columns(2).spacing([40.0, 6.0]);
+                        grid.show(ui, |ui| {
+                            if let Some(portable_dir) = DeckConfig::portable_dir() {
+                                ui.strong("Portable Mode:");
+                                ui.label(format!("{}", portable_dir.display()));
+                                ui.end_row();
+                            }
+
+                            if let Some(config_dir) = Config::default_config_dir() {
+                                ui.strong("Preferences:");
+                                ui.label(format!("{}", config_dir.display()));
+                                ui.end_row();
+                            }
+
+                            if let Some(data_dir) = Config::default_data_dir() {
+                                ui.strong("Save States/RAM, Replays: ");
+                                ui.label(format!("{}", data_dir.display()));
+                                ui.end_row();
+                            }
+
+                            if let Some(picture_dir) = Config::default_picture_dir() {
+                                ui.strong("Screenshots: ");
+                                ui.label(format!("{}", picture_dir.display()));
+                                ui.end_row();
+                            }
+
+                            if let Some(audio_dir) = Config::default_audio_dir() {
+                                ui.strong("Audio Recordings: ");
+                                ui.label(format!("{}", audio_dir.display()));
+                                ui.end_row();
+                            }
+                        });
                     });
+                }
+            });
+        });
+    }
+
+    fn about_homebrew(ui: &mut Ui, rom: RomAsset) {
+        ScrollArea::vertical().show(ui, |ui| {
+            ui.strong("Author(s):");
+            ui.label(rom.authors);
+            ui.add_space(12.0);
+
+            ui.strong("Description:");
+            ui.label(rom.description);
+            ui.add_space(12.0);
+
+            ui.strong("Source:");
+            ui.hyperlink(rom.source);
+        });
+    }
+
+    fn save_slot_radio(&mut self, ui: &mut Ui, cfg: &mut Config, shortcut: ShowShortcut) {
+        ui.vertical(|ui| {
+            for slot in 1..=4 {
+                let shortcut_txt = shortcut
+                    .then(|| self.fmt_shortcut(DeckAction::SetSaveSlot(slot)))
+                    .unwrap_or_default();
+                let radio = RadioValue::new(&mut cfg.emulation.save_slot, slot, slot.to_string())
+                    .shortcut_text(shortcut_txt);
+                ui.add(radio);
+            }
+        });
+        ui.vertical(|ui| {
+            for slot in 5..=8 {
+                let shortcut_txt = shortcut
+                    .then(|| self.fmt_shortcut(DeckAction::SetSaveSlot(slot)))
+                    .unwrap_or_default();
+                let radio = RadioValue::new(&mut cfg.emulation.save_slot, slot, slot.to_string())
+                    .shortcut_text(shortcut_txt);
+                ui.add(radio);
+            }
+        });
+    }
+
+    fn speed_slider(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let slider = Slider::new(&mut cfg.emulation.speed, Config::SLOW_MOTION_SPEED..=2.0)
+            .step_by(0.05)
+            .suffix("x");
+        let res = ui
+            .add(slider)
+            .on_hover_text("Adjust the speed of the NES emulation.");
+        if res.changed() {
+            self.tx.nes_event(ConfigEvent::Speed(cfg.emulation.speed));
+        }
+    }
+
+    fn fast_forward_settings(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let fast_forward = cfg.emulation.fast_forward;
+        let ff = &mut cfg.emulation.fast_forward;
+        ui.add_enabled(
+            !ff.uncapped,
+            Slider::new(
+                &mut ff.speed,
+                FastForwardConfig::MIN_SPEED..=FastForwardConfig::MAX_SPEED,
+            )
+            .step_by(0.25)
+            .suffix("x"),
+        )
+        .on_hover_text("Emulation speed while fast-forwarding.");
+        ui.checkbox(&mut ff.uncapped, "Uncapped")
+            .on_hover_text("Run as fast as possible, with audio silenced.");
+        ui.checkbox(&mut ff.toggle, "Toggle").on_hover_text(
+            "Press once to start fast-forwarding and again to stop, instead of holding.",
+        );
+        ui.add(Slider::new(&mut ff.frame_skip, 0..=9).text("Frame Skip"))
+            .on_hover_text("Frames to skip rendering between each rendered frame.");
+        if fast_forward != cfg.emulation.fast_forward {
+            self.tx
+                .nes_event(ConfigEvent::FastForward(cfg.emulation.fast_forward));
+        }
+    }
+
+    fn live_reload_settings(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let live_reload = cfg.emulation.live_reload;
+        let lr = &mut cfg.emulation.live_reload;
+        ui.checkbox(&mut lr.enabled, "Enabled")
+            .on_hover_text("Watch the loaded ROM file and reload it when it changes.");
+        ui.add_enabled_ui(lr.enabled, |ui| {
+            ui.indent("live_reload_settings", |ui| {
+                ui.label("Preserve:");
+                for (value, hover) in [
+                    (
+                        LiveReloadPreserve::Nothing,
+                        "Start the reloaded ROM from power on.",
+                    ),
+                    (
+                        LiveReloadPreserve::Ram,
+                        "Keep the contents of Work RAM and cartridge PRG-RAM.",
+                    ),
+                    (
+                        LiveReloadPreserve::SaveState,
+                        "Load the state saved in the current save slot.",
+                    ),
+                ] {
+                    ui.radio_value(&mut lr.preserve, value, value.as_ref())
+                        .on_hover_text(hover);
+                }
+            });
+        });
+        if live_reload != cfg.emulation.live_reload {
+            self.tx
+                .nes_event(ConfigEvent::LiveReload(cfg.emulation.live_reload));
+        }
+    }
+
+    fn run_ahead_slider(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let slider = Slider::new(&mut cfg.emulation.run_ahead, 0..=4);
+        let res = ui
+            .add(slider)
+            .on_hover_text("Simulate a number of frames in the future to reduce input lag.");
+        if res.changed() {
+            self.tx
+                .nes_event(ConfigEvent::RunAhead(cfg.emulation.run_ahead));
+        }
+    }
+
+    fn cycle_acurate_checkbox(&mut self, ui: &mut Ui, cfg: &mut Config, shortcut: ShowShortcut) {
+        let shortcut_txt = shortcut
+            .then(|| self.fmt_shortcut(Setting::ToggleCycleAccurate))
+            .unwrap_or_default();
+        let icon = shortcut.then(|| "📐 ").unwrap_or_default();
+        let checkbox = Checkbox::new(
+            &mut cfg.deck.cycle_accurate,
+            format!("{icon}Cycle Accurate"),
+        )
+        .shortcut_text(shortcut_txt);
+        let res = ui
+            .add(checkbox)
+            .on_hover_text("Enables more accurate NES emulation at a slight cost in performance.");
+        if res.clicked() {
+            self.tx
+                .nes_event(ConfigEvent::CycleAccurate(cfg.deck.cycle_accurate));
+        }
+    }
+
+    fn rewind_checkbox(&mut self, ui: &mut Ui, cfg: &mut Config, shortcut: ShowShortcut) {
+        let shortcut_txt = shortcut
+            .then(|| self.fmt_shortcut(Setting::ToggleRewinding))
+            .unwrap_or_default();
+        let icon = shortcut.then(|| "🔄 ").unwrap_or_default();
+        let checkbox = Checkbox::new(&mut cfg.emulation.rewind, format!("{icon}Enable Rewinding"))
+            .shortcut_text(shortcut_txt);
+        let res = ui
+            .add(checkbox)
+            .on_hover_text("Enable instant and visual rewinding. Increases memory usage.");
+        if res.clicked() {
+            self.tx
+                .nes_event(ConfigEvent::RewindEnabled(cfg.emulation.rewind));
+        }
+    }
+
+    fn zapper_checkbox(&mut self, ui: &mut Ui, cfg: &mut Config, shortcut: ShowShortcut) {
+        let shortcut_txt = shortcut
+            .then(|| self.fmt_shortcut(DeckAction::ToggleZapperConnected))
+            .unwrap_or_default();
+        let icon = shortcut.then(|| "🔫 ").unwrap_or_default();
+        let checkbox = Checkbox::new(&mut cfg.deck.zapper, format!("{icon}Enable Zapper Gun"))
+            .shortcut_text(shortcut_txt);
+        let res = ui
+            .add(checkbox)
+            .on_hover_text("Enable the Zapper Light Gun for games that support it.");
+        if res.clicked() {
+            self.tx
+                .nes_event(ConfigEvent::ZapperConnected(cfg.deck.zapper));
+        }
+    }
+
+    fn vaus_checkbox(&mut self, ui: &mut Ui, cfg: &mut Config, shortcut: ShowShortcut) {
+        let icon = shortcut.then(|| "🕹 ").unwrap_or_default();
+        let res = ui
+            .checkbox(&mut cfg.deck.vaus, format!("{icon}Enable Arkanoid Vaus"))
+            .on_hover_text(
+                "Enable the NES Arkanoid Vaus paddle for games that support it. Turn it with the \
+                mouse and click to fire.",
+            );
+        if res.clicked() {
+            self.tx.nes_event(ConfigEvent::VausConnected(cfg.deck.vaus));
+        }
+    }
+
+    fn overscan_checkbox(&mut self, ui: &mut Ui, cfg: &mut Config, shortcut: ShowShortcut) {
+        let shortcut_txt = shortcut
+            .then(|| self.fmt_shortcut(Setting::ToggleOverscan))
+            .unwrap_or_default();
+        let icon = shortcut.then(|| "📺 ").unwrap_or_default();
+        let region = self.region(cfg);
+        let overscan = cfg.overscan_mut(region);
+        let mut hide_overscan = !overscan.is_none();
+        let checkbox = Checkbox::new(&mut hide_overscan, format!("{icon}Hide Overscan"))
+            .shortcut_text(shortcut_txt);
+        let res = ui.add(checkbox)
+            .on_hover_text("Traditional CRT displays would crop the edges of the image. Disable this to show the overscan.");
+        if res.clicked() {
+            *overscan = if hide_overscan {
+                Overscan::default_for(region)
+            } else {
+                Overscan::NONE
+            };
+        }
+    }
+
+    fn overscan_settings(ui: &mut Ui, cfg: &mut Config) {
+        Grid::new("overscan_settings")
+            .num_columns(6)
+            .spacing([10.0, 6.0])
+            .show(ui, |ui| {
+                for (label, region) in [("NTSC:", NesRegion::Ntsc), ("PAL/Dendy:", NesRegion::Pal)]
+                {
+                    ui.label(label);
+                    let overscan = cfg.overscan_mut(region);
+                    for (edge, value) in [
+                        ("Top", &mut overscan.top),
+                        ("Bottom", &mut overscan.bottom),
+                        ("Left", &mut overscan.left),
+                        ("Right", &mut overscan.right),
+                    ] {
+                        ui.add(
+                            DragValue::new(value)
+                                .clamp_range(0..=Overscan::MAX)
+                                .prefix(format!("{edge}: "))
+                                .suffix(" px"),
+                        );
+                    }
+                    let default = Overscan::default_for(region);
+                    if ui
+                        .add_enabled(*overscan != default, Button::new("Reset"))
+                        .on_hover_text("Restore the default cropping for this region.")
+                        .clicked()
+                    {
+                        *overscan = default;
+                    }
+                    ui.end_row();
+                }
             });
+    }
+
+    fn orientation_settings(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let orientation = &mut cfg.renderer.orientation;
+        let sideways = orientation.is_sideways();
+        ui.horizontal(|ui| {
+            for rotation in Rotation::as_slice() {
+                ui.radio_value(&mut orientation.rotation, *rotation, rotation.as_ref());
+            }
+        })
+        .response
+        .on_hover_text("Rotate the frame clockwise, e.g. for a vertically mounted monitor.");
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut orientation.mirror_horizontal, "Mirror Horizontally");
+            ui.checkbox(&mut orientation.mirror_vertical, "Mirror Vertically");
         });
+        ui.checkbox(&mut orientation.remap_dpad, "Remap D-Pad")
+            .on_hover_text("Remap D-Pad directions to match the rotated and mirrored frame.");
+        if sideways != orientation.is_sideways() {
+            self.resize_window = true;
+        }
     }
 
-    fn video_preferences(&mut self, ui: &mut Ui, cfg: &mut Config) {
-        #[cfg(feature = "profiling")]
-        puffin::profile_function!();
+    fn video_filter_radio(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let filter = cfg.deck.filter;
+        ui.radio_value(&mut cfg.deck.filter, VideoFilter::Pixellate, "Pixellate")
+            .on_hover_text("Basic pixel-perfect rendering");
+        ui.radio_value(&mut cfg.deck.filter, VideoFilter::Ntsc, "Ntsc")
+            .on_hover_text(
+                "Emulate traditional NTSC rendering where chroma spills over into luma.",
+            );
+        ui.radio_value(&mut cfg.deck.filter, VideoFilter::Crt, "CRT")
+            .on_hover_text("NTSC rendering with scanlines and the curvature of a CRT screen.");
+        ui.radio_value(&mut cfg.deck.filter, VideoFilter::Smooth2x, "Smooth 2x")
+            .on_hover_text("Upscale 2x, smoothing diagonal edges between similar colors.");
+        ui.radio_value(&mut cfg.deck.filter, VideoFilter::Edge2x, "Edge 2x")
+            .on_hover_text("Upscale 2x with edge-directed interpolation for sharper curves.");
+        if filter != cfg.deck.filter {
+            self.tx.nes_event(ConfigEvent::VideoFilter(cfg.deck.filter));
+        }
+    }
 
-        Grid::new("video_checkboxes")
-            .spacing([80.0, 6.0])
+    fn ntsc_settings(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let ntsc = &mut cfg.deck.ntsc;
+        let mut changed = false;
+        Grid::new("ntsc_settings")
             .num_columns(2)
+            .spacing([20.0, 6.0])
             .show(ui, |ui| {
-                self.menubar_checkbox(ui, cfg, ShowShortcut::No);
-                self.fullscreen_checkbox(ui, cfg, ShowShortcut::No);
-                ui.end_row();
+                let sliders = [
+                    (
+                        "Sharpness:",
+                        &mut ntsc.sharpness,
+                        NtscConfig::SHARPNESS_RANGE,
+                        "",
+                        "Sharpen or soften edges between pixels.",
+                    ),
+                    (
+                        "Fringing:",
+                        &mut ntsc.fringing,
+                        NtscConfig::FRINGING_RANGE,
+                        "",
+                        "Amount of color bleeding from the previous pixel.",
+                    ),
+                    (
+                        "Artifacts:",
+                        &mut ntsc.artifacts,
+                        NtscConfig::ARTIFACTS_RANGE,
+                        "",
+                        "Intensity of luma artifacts from the previous pixel, such as dot crawl.",
+                    ),
+                    (
+                        "Hue:",
+                        &mut ntsc.hue,
+                        NtscConfig::HUE_RANGE,
+                        "°",
+                        "Rotate the hue of all colors.",
+                    ),
+                    (
+                        "Saturation:",
+                        &mut ntsc.saturation,
+                        NtscConfig::SATURATION_RANGE,
+                        "x",
+                        "Color saturation.",
+                    ),
+                    (
+                        "Gamma:",
+                        &mut ntsc.gamma,
+                        NtscConfig::GAMMA_RANGE,
+                        "",
+                        "Assumed display gamma. Lower values brighten the image.",
+                    ),
+                ];
+                for (label, value, range, suffix, hover_text) in sliders {
+                    ui.label(label).on_hover_text(hover_text);
+                    let res = ui.add(Slider::new(value, range).suffix(suffix));
+                    changed |= res.changed();
+                    ui.end_row();
+                }
+            });
+        if ui.button("Reset").clicked() {
+            *ntsc = NtscConfig::default();
+            changed = true;
+        }
+        if changed {
+            self.tx.nes_event(ConfigEvent::NtscConfig(cfg.deck.ntsc));
+        }
+    }
 
-                self.messages_checkbox(ui, cfg, ShowShortcut::No);
-                ui.end_row();
+    fn post_process_list(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        ui.label(format!("1. {}", cfg.deck.filter.as_ref()))
+            .on_hover_text("The selected video filter is always applied first.");
 
-                self.overscan_checkbox(ui, cfg, ShowShortcut::No);
-                ui.end_row();
+        let mut changed = false;
+        let mut moved = None;
+        for (idx, stage) in cfg.deck.post_process.iter_mut().enumerate() {
+            let (_, payload) = ui.dnd_drop_zone::<usize, _>(Frame::none(), |ui| {
+                ui.horizontal(|ui| {
+                    ui.dnd_drag_source(Id::new(("post_process", idx)), idx, |ui| {
+                        ui.label("☰");
+                    })
+                    .response
+                    .on_hover_cursor(CursorIcon::Grab)
+                    .on_hover_text("Drag to reorder.");
+                    let label = format!("{}. {}", idx + 2, stage.effect.as_ref());
+                    if ui.checkbox(&mut stage.enabled, label).clicked() {
+                        changed = true;
+                    }
+                });
             });
+            if let Some(from) = payload {
+                moved = Some((*from, idx));
+            }
+        }
 
-        ui.separator();
+        if let Some((from, to)) = moved {
+            if from != to {
+                let stage = cfg.deck.post_process.remove(from);
+                cfg.deck.post_process.insert(to, stage);
+                changed = true;
+            }
+        }
+        if changed {
+            self.tx
+                .nes_event(ConfigEvent::PostProcess(cfg.deck.post_process.clone()));
+        }
+    }
 
-        Grid::new("video_preferences")
-            .num_columns(2)
-            .spacing([40.0, 6.0])
-            .show(ui, |ui| {
-                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
-                    ui.strong("Window Scale:");
-                });
-                Grid::new("save_slots")
-                    .num_columns(2)
-                    .spacing([20.0, 6.0])
-                    .show(ui, |ui| self.window_scale_radio(ui, cfg));
-                ui.end_row();
+    fn background_mode_radio(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let background = cfg.emulation.background;
+        for (value, hover) in [
+            (BackgroundMode::Pause, "Pause emulation and audio."),
+            (BackgroundMode::Mute, "Keep emulating with audio silenced."),
+            (BackgroundMode::Run, "Keep emulating and playing audio."),
+        ] {
+            ui.radio_value(&mut cfg.emulation.background, value, value.as_ref())
+                .on_hover_text(hover);
+        }
+        if background != cfg.emulation.background {
+            self.tx
+                .nes_event(ConfigEvent::BackgroundMode(cfg.emulation.background));
+        }
+    }
+
+    fn session_mode_radio(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let session = cfg.emulation.session;
+        for (value, hover) in [
+            (SessionMode::Off, "Don't save or resume sessions."),
+            (
+                SessionMode::Ask,
+                "Save sessions and ask whether to resume them.",
+            ),
+            (SessionMode::Always, "Save sessions and always resume them."),
+        ] {
+            ui.radio_value(&mut cfg.emulation.session, value, value.as_ref())
+                .on_hover_text(hover);
+        }
+        if session != cfg.emulation.session {
+            self.tx
+                .nes_event(ConfigEvent::SessionMode(cfg.emulation.session));
+        }
+    }
+
+    fn four_player_radio(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let four_player = cfg.deck.four_player;
+        ui.radio_value(&mut cfg.deck.four_player, FourPlayer::Disabled, "Disabled");
+        ui.radio_value(
+            &mut cfg.deck.four_player,
+            FourPlayer::FourScore,
+            "Four Score",
+        )
+        .on_hover_text("Enable NES Four Score for games that support 4 players.");
+        ui.radio_value(
+            &mut cfg.deck.four_player,
+            FourPlayer::Satellite,
+            "Satellite",
+        )
+        .on_hover_text(
+            "Enable Famicom expansion port controllers for games that support 4 players.",
+        );
+        ui.radio_value(
+            &mut cfg.deck.four_player,
+            FourPlayer::FamicomAdapter,
+            "Famicom Adapter",
+        )
+        .on_hover_text("Enable Hori 4 Players Adapter for Famicom games that support 4 players.");
+        if four_player != cfg.deck.four_player {
+            self.tx
+                .nes_event(ConfigEvent::FourPlayer(cfg.deck.four_player));
+        }
+    }
+
+    fn mouse_radio(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let port = cfg.deck.mouse;
+        ui.radio_value(&mut cfg.deck.mouse, None, "Disconnected");
+        ui.radio_value(&mut cfg.deck.mouse, Some(Player::One), "Port 1")
+            .on_hover_text("Connect the mouse in place of controller 1.");
+        ui.radio_value(&mut cfg.deck.mouse, Some(Player::Two), "Port 2")
+            .on_hover_text("Connect the mouse in place of controller 2.");
+        if port != cfg.deck.mouse {
+            self.tx.nes_event(ConfigEvent::Mouse(cfg.deck.mouse));
+        }
+
+        let slider = Slider::new(&mut cfg.deck.mouse_sensitivity, 0.25..=4.0)
+            .step_by(0.25)
+            .suffix("x")
+            .text("Sensitivity");
+        let res = ui
+            .add_enabled(cfg.deck.mouse.is_some(), slider)
+            .on_hover_text("Scale mouse motion before the speed selected by the game.");
+        if res.changed() {
+            self.tx
+                .nes_event(ConfigEvent::MouseSensitivity(cfg.deck.mouse_sensitivity));
+        }
+    }
+
+    fn expansion_device_radio(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let rom_name = self.loaded_rom.as_ref().map(|rom| rom.name.clone());
+        let rom_device = rom_name
+            .as_ref()
+            .and_then(|name| cfg.deck.rom_expansion_devices.get(name).copied());
+        let header_device = self
+            .loaded_rom
+            .as_ref()
+            .and_then(|rom| ExpansionDevice::from_header(rom.header.expansion_device));
+        let prev_device = self.expansion_device(cfg);
+        let mut device = prev_device;
+
+        ui.radio_value(&mut device, ExpansionDevice::None, "None");
+        ui.radio_value(
+            &mut device,
+            ExpansionDevice::FamilyKeyboard,
+            "Family BASIC Keyboard",
+        )
+        .on_hover_text(
+            "Type on the Family BASIC Keyboard with your keyboard. Hold Control to use shortcuts.",
+        );
+        ui.radio_value(&mut device, ExpansionDevice::Vaus, "Arkanoid Vaus")
+            .on_hover_text(
+                "Turn the Famicom Arkanoid Vaus paddle with the mouse and click to fire.",
+            );
+        ui.radio_value(&mut device, ExpansionDevice::PowerPad, "Family Trainer")
+            .on_hover_text(
+                "Step on the Family Trainer Mat with the `Q`-`R`, `A`-`F` and `Z`-`V` keys.",
+            );
+        ui.radio_value(
+            &mut device,
+            ExpansionDevice::BarcodeBattler,
+            "Barcode Battler",
+        )
+        .on_hover_text("Scan barcodes with the Barcode Battler from the Controls menu.");
+        if let Some(header_device) = header_device {
+            ui.label(format!("ROM header default: {header_device}"));
+        }
+
+        let mut event = None;
+        if let Some(name) = rom_name {
+            let mut per_rom = rom_device.is_some();
+            ui.checkbox(&mut per_rom, "Use for this ROM only")
+                .on_hover_text(format!("Only connect this device when loading {name}."));
+            // Devices specified by the ROM header can only be changed for that ROM
+            if per_rom || (header_device.is_some() && device != prev_device) {
+                if rom_device != Some(device) {
+                    cfg.deck.rom_expansion_devices.insert(name, device);
+                    event = Some(ConfigEvent::RomExpansionDevice(Some(device)));
+                }
+            } else if rom_device.is_some() {
+                cfg.deck.rom_expansion_devices.remove(&name);
+                event = Some(ConfigEvent::RomExpansionDevice(None));
+            }
+        }
+        if event.is_none() && rom_device.is_none() && device != prev_device {
+            cfg.deck.expansion_device = device;
+            event = Some(ConfigEvent::ExpansionDevice(device));
+        }
+        if let Some(event) = event {
+            self.tx.nes_event(event);
+        }
+    }
+
+    fn barcode_entry(&mut self, ui: &mut Ui) {
+        ui.strong("Barcode:")
+            .on_hover_cursor(CursorIcon::Help)
+            .on_hover_text("An 8 or 13 digit barcode to scan with the Barcode Battler.");
+        ui.horizontal(|ui| {
+            let entry_res = ui.add(
+                TextEdit::singleline(&mut self.pending_barcode)
+                    .char_limit(13)
+                    .hint_text("4902425023455"),
+            );
+            let has_entry = !self.pending_barcode.is_empty();
+            let scan_res = ui.add_enabled(has_entry, Button::new("Scan"));
+            if (has_entry && entry_res.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)))
+                || scan_res.clicked()
+            {
+                self.tx
+                    .nes_event(EmulationEvent::ScanBarcode(self.pending_barcode.clone()));
+            }
+        });
+    }
+
+    fn nes_region_radio(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let rom_name = self.loaded_rom.as_ref().map(|rom| rom.name.clone());
+        let rom_region = rom_name
+            .as_ref()
+            .and_then(|name| cfg.deck.rom_regions.get(name).copied());
+        let prev_region = rom_region.unwrap_or(cfg.deck.region);
+        let mut region = prev_region;
+
+        ui.radio_value(&mut region, NesRegion::Auto, "Auto")
+            .on_hover_text(
+                "Auto-detect region from the ROM header, game database or region tags in the \
+                file name, e.g. `(E)`.",
+            );
+        ui.radio_value(&mut region, NesRegion::Ntsc, "NTSC")
+            .on_hover_text("Emulate NTSC timing and aspect-ratio.");
+        ui.radio_value(&mut region, NesRegion::Pal, "PAL")
+            .on_hover_text("Emulate PAL timing and aspect-ratio.");
+        ui.radio_value(&mut region, NesRegion::Dendy, "Dendy")
+            .on_hover_text("Emulate Dendy timing and aspect-ratio.");
+
+        let mut event = None;
+        if let Some(name) = rom_name {
+            let mut per_rom = rom_region.is_some();
+            ui.checkbox(&mut per_rom, "Use for this ROM only")
+                .on_hover_text(format!("Only emulate this region when loading {name}."));
+            if per_rom {
+                if rom_region != Some(region) {
+                    cfg.deck.rom_regions.insert(name, region);
+                    event = Some(ConfigEvent::RomRegion(Some(region)));
+                }
+            } else if rom_region.is_some() {
+                cfg.deck.rom_regions.remove(&name);
+                event = Some(ConfigEvent::RomRegion(None));
+            }
+        }
+        if event.is_none() && rom_region.is_none() && region != prev_region {
+            cfg.deck.region = region;
+            event = Some(ConfigEvent::Region(region));
+        }
+        if let Some(event) = event {
+            self.resize_window = true;
+            self.resize_texture = true;
+            self.tx.nes_event(event);
+        }
+    }
+
+    fn mmc3_revision_radio(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let rom_name = self.loaded_rom.as_ref().map(|rom| rom.name.clone());
+        let rom_revision = rom_name.as_ref().and_then(|name| {
+            match cfg.deck.rom_mapper_revisions.get(name).copied() {
+                Some(MapperRevision::Mmc3(rev)) => Some(rev),
+                _ => None,
+            }
+        });
+        let header_revision = self.loaded_rom.as_ref().and_then(|rom| {
+            match registry::lookup(rom.header.mapper_num, rom.header.submapper_num)
+                .and_then(|board| board.revision)
+            {
+                Some(MapperRevision::Mmc3(rev)) => Some(rev),
+                _ => None,
+            }
+        });
+        let prev_revision = rom_revision
+            .or(header_revision)
+            .unwrap_or(cfg.deck.mapper_revisions.mmc3);
+        let mut revision = prev_revision;
+
+        ui.radio_value(&mut revision, Mmc3Revision::A, "MMC3A")
+            .on_hover_text(
+                "MMC3A and some non-Sharp MMC3B chips, which only trigger an IRQ when the counter \
+                is decremented to 0 or reloaded after a write to $C001.",
+            );
+        ui.radio_value(&mut revision, Mmc3Revision::BC, "MMC3B/C")
+            .on_hover_text(
+                "Sharp MMC3B and MMC3C chips, which trigger an IRQ every time the counter is 0. \
+                Used by most games.",
+            );
+        ui.radio_value(&mut revision, Mmc3Revision::Acc, "MC-ACC")
+            .on_hover_text("Acclaim's MMC3 clone, which clocks the counter on a falling edge.");
+        if let Some(header_revision) = header_revision {
+            ui.label(format!(
+                "ROM header default: {}",
+                MapperRevision::Mmc3(header_revision)
+            ));
+        }
+
+        let mut event = None;
+        if let Some(name) = rom_name {
+            let mut per_rom = rom_revision.is_some();
+            ui.checkbox(&mut per_rom, "Use for this ROM only")
+                .on_hover_text(format!("Only emulate this revision when loading {name}."));
+            // Revisions specified by the ROM header can only be changed for that ROM
+            if per_rom || (header_revision.is_some() && revision != prev_revision) {
+                if rom_revision != Some(revision) {
+                    let revision = MapperRevision::Mmc3(revision);
+                    cfg.deck.rom_mapper_revisions.insert(name, revision);
+                    event = Some(ConfigEvent::RomMapperRevision(Some(revision)));
+                }
+            } else if rom_revision.is_some() {
+                cfg.deck.rom_mapper_revisions.remove(&name);
+                event = Some(ConfigEvent::RomMapperRevision(None));
+            }
+        }
+        if event.is_none() && rom_revision.is_none() && revision != prev_revision {
+            cfg.deck.mapper_revisions.mmc3 = revision;
+            event = Some(ConfigEvent::MapperRevisions(cfg.deck.mapper_revisions));
+        }
+        if let Some(event) = event {
+            self.tx.nes_event(event);
+        }
+    }
+
+    fn dip_switches_checkboxes(&mut self, ui: &mut Ui, cfg: &mut Config, count: u8) {
+        let Some(name) = self.loaded_rom.as_ref().map(|rom| rom.name.clone()) else {
+            return;
+        };
+        let prev_value = cfg
+            .deck
+            .rom_dip_switches
+            .get(&name)
+            .copied()
+            .unwrap_or_default();
+        let mut value = prev_value;
+        for n in 0..count {
+            let mut on = value & (1 << n) != 0;
+            if ui.checkbox(&mut on, format!("Switch {}", n + 1)).changed() {
+                value ^= 1 << n;
+            }
+        }
+        ui.label("Multicarts usually read their switches on reset to select a menu.");
+        if value != prev_value {
+            if value == 0 {
+                cfg.deck.rom_dip_switches.remove(&name);
+                self.tx.nes_event(ConfigEvent::RomDipSwitches(None));
+            } else {
+                cfg.deck.rom_dip_switches.insert(name, value);
+                self.tx.nes_event(ConfigEvent::RomDipSwitches(Some(value)));
+            }
+        }
+
+        let res = ui
+            .button("🔃 Reset")
+            .on_hover_text("Emulate a soft reset of the NES to apply the new switch settings.");
+        if res.clicked() {
+            self.tx.nes_event(EmulationEvent::Reset(ResetKind::Soft));
+            ui.close_menu();
+        }
+    }
+
+    fn ram_state_radio(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let rom_name = self.loaded_rom.as_ref().map(|rom| rom.name.clone());
+        let rom_ram_state = rom_name
+            .as_ref()
+            .and_then(|name| cfg.deck.rom_ram_states.get(name).copied());
+        let prev_ram_state = rom_ram_state.unwrap_or(cfg.deck.ram_state);
+        let mut ram_state = prev_ram_state;
+
+        ui.radio_value(&mut ram_state, RamState::AllZeros, "All 0x00")
+            .on_hover_text("Clear startup RAM to all zeroes for predictable emulation.");
+        ui.radio_value(&mut ram_state, RamState::AllOnes, "All 0xFF")
+            .on_hover_text("Clear startup RAM to all ones for predictable emulation.");
+        ui.radio_value(
+            &mut ram_state,
+            RamState::Alternating,
+            "Alternating 0x00/0xFF",
+        )
+        .on_hover_text(
+            "Fill startup RAM with alternating pages of 0x00 and 0xFF, similar to real hardware.",
+        );
+        ui.radio_value(&mut ram_state, RamState::Random, "Random")
+            .on_hover_text("Randomize startup RAM, which some games use as a basic RNG seed.");
+        ui.horizontal(|ui| {
+            let seeded = matches!(ram_state, RamState::Seeded(_));
+            let res = ui.radio(seeded, "Seeded").on_hover_text(
+                "Fill startup RAM with a reproducible pattern generated from a seed.",
+            );
+            if res.clicked() && !seeded {
+                ram_state = RamState::Seeded(0);
+            }
+            if let RamState::Seeded(seed) = &mut ram_state {
+                ui.add(DragValue::new(seed));
+            }
+        });
 
-                ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
-                    ui.strong("Video Filter:");
-                });
-                ui.vertical(|ui| self.video_filter_radio(ui, cfg));
-            });
+        let Some(name) = rom_name else {
+            if ram_state != prev_ram_state {
+                cfg.deck.ram_state = ram_state;
+                self.tx.nes_event(ConfigEvent::RamState(ram_state));
+            }
+            return;
+        };
+
+        let mut per_rom = rom_ram_state.is_some();
+        ui.checkbox(&mut per_rom, "Use for this ROM only")
+            .on_hover_text(format!("Only apply this RAM state when loading {name}."));
+        if per_rom {
+            if rom_ram_state != Some(ram_state) {
+                cfg.deck.rom_ram_states.insert(name, ram_state);
+                self.tx.nes_event(ConfigEvent::RomRamState(Some(ram_state)));
+            }
+        } else if rom_ram_state.is_some() {
+            cfg.deck.rom_ram_states.remove(&name);
+            self.tx.nes_event(ConfigEvent::RomRamState(None));
+        } else if ram_state != prev_ram_state {
+            cfg.deck.ram_state = ram_state;
+            self.tx.nes_event(ConfigEvent::RamState(ram_state));
+        }
     }
 
-    fn input_preferences(&mut self, ui: &mut Ui, cfg: &mut Config) {
-        #[cfg(feature = "profiling")]
-        puffin::profile_function!();
+    fn genie_codes_entry(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        ui.strong("Add Genie Code:")
+            .on_hover_cursor(CursorIcon::Help)
+            .on_hover_text(
+                "A Game Genie Code is a 6 or 8 letter string that temporarily modifies game memory during operation. e.g. `AATOZE` will start Super Mario Bros. with 9 lives."
+            );
+        ui.horizontal(|ui| {
+            let entry_res = ui.text_edit_singleline(&mut self.pending_genie_entry.code);
+            let has_entry = !self.pending_genie_entry.code.is_empty();
+            let submit_res = ui.add_enabled(has_entry, Button::new("➕"));
+            if entry_res.changed() {
+                self.pending_genie_entry.error = None;
+            }
+            if (has_entry && entry_res.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)))
+                || submit_res.clicked()
+            {
+                match GenieCode::parse(&self.pending_genie_entry.code) {
+                    Ok(hex) => {
+                        let code =
+                            GenieCode::from_raw(mem::take(&mut self.pending_genie_entry.code), hex);
+                        if !cfg.deck.genie_codes.contains(&code) {
+                            cfg.deck.genie_codes.push(code.clone());
+                            self.tx.nes_event(ConfigEvent::GenieCodeAdded(code));
+                        }
+                    }
+                    Err(err) => self.pending_genie_entry.error = Some(err.to_string()),
+                }
+            }
+        });
+        if let Some(error) = &self.pending_genie_entry.error {
+            ui.allocate_space(Vec2::new(Self::MENU_WIDTH, 0.0));
+            ui.colored_label(Color32::RED, error);
+        }
 
-        Grid::new("input_checkboxes")
-            .num_columns(2)
-            .spacing([80.0, 6.0])
-            .show(ui, |ui| {
-                self.zapper_checkbox(ui, cfg, ShowShortcut::No);
-                ui.end_row();
+        let res = ui
+            .checkbox(&mut cfg.deck.apply_recorded_cheats, "Apply Recorded Codes")
+            .on_hover_text(
+                "Apply the Game Genie codes recorded in save states and replays when loaded instead of keeping the current codes.",
+            );
+        if res.clicked() {
+            self.tx.nes_event(ConfigEvent::ApplyRecordedCheats(
+                cfg.deck.apply_recorded_cheats,
+            ));
+        }
 
-                let res = ui.checkbox(&mut cfg.deck.concurrent_dpad, "Enable Concurrent D-Pad");
-                if res.clicked() {
-                    self.tx
-                        .nes_event(ConfigEvent::ConcurrentDpad(cfg.deck.concurrent_dpad));
-                }
+        if !cfg.deck.genie_codes.is_empty() {
+            ui.separator();
+            ui.strong("Current Genie Codes:");
+            ScrollArea::vertical().show(ui, |ui| {
+                cfg.deck.genie_codes.retain(|genie| {
+                    ui.horizontal(|ui| {
+                        ui.label(genie.code());
+                        // icon: waste basket
+                        if ui.button("🗑").clicked() {
+                            self.tx
+                                .nes_event(ConfigEvent::GenieCodeRemoved(genie.code().to_string()));
+                            false
+                        } else {
+                            true
+                        }
+                    })
+                    .inner
+                });
             });
+        }
     }
 
-    fn keybinds(&mut self, ui: &mut Ui, gamepads: &mut Gamepads, cfg: &mut Config) {
-        #[cfg(feature = "profiling")]
-        puffin::profile_function!();
-
-        self.show_set_keybind_viewport(ui.ctx(), gamepads, cfg);
-        self.show_gamepad_unassign_viewport(ui.ctx(), gamepads, cfg);
-        ui.set_enabled(self.pending_keybind.is_none() && self.gamepad_unassign.is_none());
+    fn header_editor(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let Some(rom) = &self.loaded_rom else {
+            ui.label(Self::NO_ROM_LOADED);
+            return;
+        };
+        let (name, header, header_repaired) = (rom.name.clone(), rom.header, rom.header_repaired);
+        if self.header_overrides_rom != name {
+            self.header_overrides = cfg
+                .deck
+                .header_overrides
+                .get(&name)
+                .copied()
+                .unwrap_or_default();
+            self.header_overrides_rom.clone_from(&name);
+        }
+        if header_repaired {
+            ui.label("The header of this ROM was repaired automatically when loaded.");
+            ui.add_space(6.0);
+        }
 
-        ui.horizontal(|ui| {
-            ui.selectable_value(&mut self.keybinds_tab, KeybindsTab::Shortcuts, "Shortcuts");
-            ui.selectable_value(
-                &mut self.keybinds_tab,
-                KeybindsTab::Joypad(Player::One),
-                "Player1",
+        let overrides = &mut self.header_overrides;
+        let grid = Grid::new("header_editor")
+            .num_columns(3)
+            .spacing([20.0, 6.0]);
+        grid.show(ui, |ui| {
+            Self::header_override_row(
+                ui,
+                "Mapper:",
+                "iNES mapper number.",
+                &mut overrides.mapper_num,
+                header.mapper_num,
+                |ui, mapper_num| {
+                    ui.add(DragValue::new(mapper_num).clamp_range(0..=4095));
+                },
             );
-            ui.selectable_value(
-                &mut self.keybinds_tab,
-                KeybindsTab::Joypad(Player::Two),
-                "Player2",
+            Self::header_override_row(
+                ui,
+                "Submapper:",
+                "NES 2.0 submapper number.",
+                &mut overrides.submapper_num,
+                header.submapper_num,
+                |ui, submapper_num| {
+                    ui.add(DragValue::new(submapper_num).clamp_range(0..=15));
+                },
             );
-            ui.selectable_value(
-                &mut self.keybinds_tab,
-                KeybindsTab::Joypad(Player::Three),
-                "Player3",
+            let mirroring = if header.flags & 0x08 == 0x08 {
+                Mirroring::FourScreen
+            } else if header.flags & 0x01 == 0x01 {
+                Mirroring::Vertical
+            } else {
+                Mirroring::Horizontal
+            };
+            Self::header_override_row(
+                ui,
+                "Mirroring:",
+                "Hardwired nametable mirroring.",
+                &mut overrides.mirroring,
+                mirroring,
+                |ui, mirroring| {
+                    egui::ComboBox::from_id_source("header_mirroring")
+                        .selected_text(format!("{mirroring:?}"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(mirroring, Mirroring::Horizontal, "Horizontal");
+                            ui.selectable_value(mirroring, Mirroring::Vertical, "Vertical");
+                            ui.selectable_value(mirroring, Mirroring::FourScreen, "Four Screen");
+                        });
+                },
             );
-            ui.selectable_value(
-                &mut self.keybinds_tab,
-                KeybindsTab::Joypad(Player::Four),
-                "Player4",
+            Self::header_override_row(
+                ui,
+                "PRG-ROM Banks:",
+                "Number of 16KB PRG-ROM banks.",
+                &mut overrides.prg_rom_banks,
+                header.prg_rom_banks,
+                |ui, prg_rom_banks| {
+                    ui.add(DragValue::new(prg_rom_banks).clamp_range(1..=4095));
+                },
+            );
+            Self::header_override_row(
+                ui,
+                "CHR-ROM Banks:",
+                "Number of 8KB CHR-ROM banks. 0 uses CHR-RAM.",
+                &mut overrides.chr_rom_banks,
+                header.chr_rom_banks,
+                |ui, chr_rom_banks| {
+                    ui.add(DragValue::new(chr_rom_banks).clamp_range(0..=4095));
+                },
+            );
+            Self::header_override_row(
+                ui,
+                "Battery:",
+                "Whether PRG-RAM is battery-backed and saved.",
+                &mut overrides.battery_backed,
+                header.flags & 0x02 == 0x02,
+                |ui, battery_backed| {
+                    ui.checkbox(battery_backed, "");
+                },
             );
         });
 
-        ui.separator();
-
-        match self.keybinds_tab {
-            KeybindsTab::Shortcuts => self.keybind_list(ui, gamepads, cfg, None),
-            KeybindsTab::Joypad(player) => self.keybind_list(ui, gamepads, cfg, Some(player)),
-        }
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            let res = ui
+                .button("Apply & Reload")
+                .on_hover_text("Reload the ROM with the overridden header.");
+            if res.clicked() {
+                let overrides = self.header_overrides;
+                if overrides.is_empty() {
+                    cfg.deck.header_overrides.remove(&name);
+                    self.tx.nes_event(ConfigEvent::HeaderOverrides(None));
+                } else {
+                    cfg.deck.header_overrides.insert(name.clone(), overrides);
+                    self.tx
+                        .nes_event(ConfigEvent::HeaderOverrides(Some(overrides)));
+                }
+            }
+            let res = ui
+                .button("Clear")
+                .on_hover_text("Remove all overrides and reload the ROM.");
+            if res.clicked() {
+                self.header_overrides = HeaderOverrides::default();
+                cfg.deck.header_overrides.remove(&name);
+                self.tx.nes_event(ConfigEvent::HeaderOverrides(None));
+            }
+        });
     }
 
-    fn keybind_list(
-        &mut self,
+    /// Show a header field row with a checkbox to override `value`, editable with `widget`.
+    /// `current` is shown while not overridden.
+    fn header_override_row<T: Copy>(
         ui: &mut Ui,
-        gamepads: &mut Gamepads,
-        cfg: &mut Config,
-        player: Option<Player>,
+        label: &str,
+        hover_text: &str,
+        value: &mut Option<T>,
+        current: T,
+        widget: impl FnOnce(&mut Ui, &mut T),
     ) {
-        #[cfg(feature = "profiling")]
-        puffin::profile_function!();
+        ui.strong(label)
+            .on_hover_cursor(CursorIcon::Help)
+            .on_hover_text(hover_text);
+        let mut overridden = value.is_some();
+        if ui.checkbox(&mut overridden, "Override").changed() {
+            *value = overridden.then_some(current);
+        }
+        let mut edited = value.unwrap_or(current);
+        ui.add_enabled_ui(overridden, |ui| widget(ui, &mut edited));
+        if overridden {
+            *value = Some(edited);
+        }
+        ui.end_row();
+    }
 
-        if let Some(player) = player {
-            self.player_gamepad_combo(ui, player, gamepads, cfg);
+    fn genie_converter(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let grid = Grid::new("genie_converter")
+            .num_columns(3)
+            .spacing([20.0, 6.0]);
+        grid.show(ui, |ui| {
+            let converter = &mut self.genie_converter;
+
+            ui.strong("Code:")
+                .on_hover_cursor(CursorIcon::Help)
+                .on_hover_text("A 6 or 8 letter Game Genie code. e.g. `AATOZE`.");
+            let res = ui.add(TextEdit::singleline(&mut converter.code).char_limit(8));
+            if res.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                converter.decode();
+            }
+            // icon: clipboard
+            let res = ui.add_enabled(!converter.code.is_empty(), Button::new("📋"));
+            if res.on_hover_text("Copy code").clicked() {
+                ui.output_mut(|o| o.copied_text = converter.code.clone());
+            }
+            ui.end_row();
 
-            ui.separator();
+            ui.strong("Address:")
+                .on_hover_cursor(CursorIcon::Help)
+                .on_hover_text("Hex CPU address in the range $8000-$FFFF.");
+            ui.add(TextEdit::singleline(&mut converter.addr).char_limit(5));
+            let raw_text = converter.raw_text();
+            // icon: clipboard
+            let res = ui.add_enabled(raw_text.is_some(), Button::new("📋"));
+            if res.on_hover_text("Copy as ADDR:VALUE:COMPARE").clicked() {
+                ui.output_mut(|o| o.copied_text = raw_text.unwrap_or_default());
+            }
+            ui.end_row();
+
+            ui.strong("Value:")
+                .on_hover_cursor(CursorIcon::Help)
+                .on_hover_text("Hex byte returned when reading the address.");
+            ui.add(TextEdit::singleline(&mut converter.data).char_limit(3));
+            ui.end_row();
+
+            ui.strong("Compare:")
+                .on_hover_cursor(CursorIcon::Help)
+                .on_hover_text(
+                    "Optional hex byte. The value is only replaced if the original matches.",
+                );
+            ui.add(TextEdit::singleline(&mut converter.compare).char_limit(3));
+            ui.end_row();
+        });
+
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            let converter = &mut self.genie_converter;
+            if ui
+                .button("⬇ Decode")
+                .on_hover_text("Code to raw patch")
+                .clicked()
+            {
+                converter.decode();
+            }
+            if ui
+                .button("⬆ Encode")
+                .on_hover_text("Raw patch to code")
+                .clicked()
+            {
+                converter.encode();
+            }
+            let res = ui
+                .add_enabled(!converter.code.is_empty(), Button::new("➕ Apply as Cheat"))
+                .on_hover_text("Add the code to the current Game Genie codes");
+            if res.clicked() {
+                match GenieCode::new(converter.code.trim().to_string()) {
+                    Ok(code) => {
+                        converter.error = None;
+                        if !cfg.deck.genie_codes.contains(&code) {
+                            cfg.deck.genie_codes.push(code.clone());
+                            self.tx.nes_event(ConfigEvent::GenieCodeAdded(code));
+                        }
+                    }
+                    Err(err) => converter.error = Some(err.to_string()),
+                }
+            }
+        });
+        if let Some(error) = &self.genie_converter.error {
+            ui.colored_label(Color32::RED, error);
         }
+    }
 
-        let keybinds = match player {
-            None => &mut self.shortcut_keybinds,
-            Some(player) => &mut self.joypad_keybinds[player as usize],
-        };
+    fn input_diagnostics(&mut self, ui: &mut Ui) {
+        let diagnostics = &mut self.input_diagnostics;
 
-        ScrollArea::both().show(ui, |ui| {
-            ui.set_width(ui.available_width()); // Pushes scrollbar to the right of the window
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut diagnostics.frozen, "Freeze")
+                .on_hover_text("Stop capturing new input events.");
+            if ui.button("Clear").clicked() {
+                diagnostics.log.clear();
+            }
+        });
+        ui.separator();
 
-            let grid = Grid::new("keybind_list")
-                .num_columns(3)
-                .spacing([40.0, 6.0]);
-            grid.show(ui, |ui| {
-                ui.heading("Action");
-                ui.heading("Binding #1");
-                ui.heading("Binding #2");
+        ui.strong(format!("Joypad State (Frame {})", diagnostics.frame_number));
+        let grid = Grid::new("input_diagnostics_joypads")
+            .num_columns(2)
+            .spacing([20.0, 4.0]);
+        grid.show(ui, |ui| {
+            let players = [Player::One, Player::Two, Player::Three, Player::Four];
+            for (player, buttons) in players.into_iter().zip(diagnostics.joypads) {
+                ui.label(format!("{player:?}"));
+                ui.horizontal(|ui| {
+                    for (name, button) in JoypadBtnState::all().iter_names() {
+                        if buttons.contains(button) {
+                            ui.strong(name);
+                        } else {
+                            ui.weak(name);
+                        }
+                    }
+                });
                 ui.end_row();
+            }
+        });
+        ui.separator();
 
-                for (action, input) in keybinds.values_mut() {
-                    ui.strong(action.to_string());
-                    for (slot, input) in input.iter_mut().enumerate() {
-                        let button = Button::new(input.map(format_input).unwrap_or_default())
-                            .min_size(Vec2::new(100.0, 0.0));
-                        let res = ui
-                            .add(button)
-                            .on_hover_text("Click to set. Right-click to unset.");
-                        if res.clicked() {
-                            self.pending_keybind = Some(PendingKeybind {
-                                action: *action,
-                                player,
-                                binding: slot,
-                                input: None,
-                                conflict: None,
-                            });
-                        } else if res.secondary_clicked() {
-                            if let Some(input) = input.take() {
-                                cfg.input.clear_binding(input);
-                                self.tx.nes_event(ConfigEvent::InputBindings);
+        ui.strong("Events")
+            .on_hover_cursor(CursorIcon::Help)
+            .on_hover_text(
+                "Raw keyboard, mouse, and gamepad events followed by the resolved input and \
+                the action it's bound to.",
+            );
+        let now = Instant::now();
+        ScrollArea::vertical()
+            .max_height(300.0)
+            .stick_to_bottom(true)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                for (time, entry) in &diagnostics.log {
+                    let age = now.duration_since(*time).as_secs_f32();
+                    ui.horizontal(|ui| {
+                        ui.monospace(format!("{age:>7.2}s"));
+                        match entry {
+                            InputLog::Raw(raw) => {
+                                ui.weak(raw);
+                            }
+                            InputLog::Resolved {
+                                input,
+                                action,
+                                state,
+                                repeat,
+                            } => {
+                                let repeat = if *repeat { " (repeat)" } else { "" };
+                                ui.label(format!("{} {state:?}{repeat} ➡", format_input(*input)));
+                                match action {
+                                    Some(action) => ui.strong(action.as_ref()),
+                                    None => ui.colored_label(Color32::YELLOW, "Unbound"),
+                                };
                             }
                         }
+                    });
+                }
+            });
+    }
+
+    fn mapper_inspector(&mut self, ui: &mut Ui) {
+        let inspector = &mut self.mapper_inspector;
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut inspector.frozen, "Freeze")
+                .on_hover_text("Stop updating registers and capturing new writes.");
+            if ui.button("Clear").clicked() {
+                inspector.writes.clear();
+            }
+        });
+        ui.separator();
+
+        ui.strong("Registers");
+        ScrollArea::vertical()
+            .id_source("mapper_inspector_registers")
+            .max_height(250.0)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                let grid = Grid::new("mapper_inspector_registers_grid")
+                    .num_columns(2)
+                    .spacing([20.0, 4.0])
+                    .striped(true);
+                grid.show(ui, |ui| {
+                    for register in &inspector.registers {
+                        ui.label(&register.name);
+                        ui.monospace(&register.value);
+                        ui.end_row();
                     }
-                    ui.end_row();
+                });
+            });
+        ui.separator();
+
+        ui.strong("Write History")
+            .on_hover_cursor(CursorIcon::Help)
+            .on_hover_text(
+                "CPU writes to the mapper address space, excluding PRG-RAM, stamped with the \
+                frame, scanline, and PPU cycle they occurred on.",
+            );
+        ui.monospace(format!(
+            "{:>7} {:>8} {:>5}  {:<5}  {}",
+            "Frame", "Scanline", "Cycle", "Addr", "Val"
+        ));
+        ScrollArea::vertical()
+            .id_source("mapper_inspector_writes")
+            .max_height(300.0)
+            .stick_to_bottom(true)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                for write in &inspector.writes {
+                    ui.monospace(format!(
+                        "{:>7} {:>8} {:>5}  ${:04X}  ${:02X}",
+                        write.frame, write.scanline, write.cycle, write.addr, write.val
+                    ));
                 }
             });
-        });
     }
 
-    fn player_gamepad_combo(
-        &mut self,
-        ui: &mut Ui,
-        player: Player,
-        gamepads: &Gamepads,
-        cfg: &mut Config,
-    ) {
+    fn hex_editor(&mut self, ui: &mut Ui) {
+        let editor = &mut self.hex_editor;
+        let mut view_changed = false;
+        let mut search = None;
+        let mut poke = None;
+
         ui.horizontal(|ui| {
-            ui.strong("Assigned Gamepad:");
+            let space = editor.space;
+            egui::ComboBox::from_id_source("hex_editor_space")
+                .selected_text(space.to_string())
+                .show_ui(ui, |ui| {
+                    for &space in MemorySpace::as_slice() {
+                        ui.selectable_value(&mut editor.space, space, space.to_string());
+                    }
+                });
+            if editor.space != space {
+                editor.offset = 0;
+                editor.selected = None;
+                editor.page = None;
+                view_changed = true;
+            }
 
-            let unassigned = "Unassigned".to_string();
-            match gamepads.list() {
-                Some(mut list) => {
-                    if list.peek().is_some() {
-                        let mut assigned_gamepad = cfg.input.gamepad_assigned_to(player);
-                        let previous_gamepad = assigned_gamepad;
-                        let gamepad_name = assigned_gamepad
-                            .and_then(|uuid| gamepads.gamepad_name_by_uuid(&uuid))
-                            .unwrap_or_else(|| unassigned.clone());
-                        let combo = egui::ComboBox::from_id_source("assigned_gamepad")
-                            .selected_text(gamepad_name.clone());
-                        combo.show_ui(ui, |ui| {
-                            ui.selectable_value(&mut assigned_gamepad, None, unassigned);
-                            for (_, gamepad) in list {
-                                ui.selectable_value(
-                                    &mut assigned_gamepad,
-                                    Some(Gamepads::create_uuid(&gamepad)),
-                                    gamepad.name(),
-                                );
+            let last_row = editor.len().saturating_sub(1) & !(HexEditor::ROW_LEN - 1);
+            ui.label("Address:");
+            let res = ui.add(
+                DragValue::new(&mut editor.offset)
+                    .hexadecimal(4, false, true)
+                    .prefix("$")
+                    .clamp_range(0..=last_row),
+            );
+            if res.changed() {
+                editor.offset &= !(HexEditor::ROW_LEN - 1);
+                view_changed = true;
+            }
+            if ui.button("⏶").on_hover_text("Previous page").clicked() {
+                editor.offset = editor.offset.saturating_sub(HexEditor::PAGE_LEN);
+                view_changed = true;
+            }
+            if ui.button("⏷").on_hover_text("Next page").clicked() {
+                editor.offset = (editor.offset + HexEditor::PAGE_LEN).min(last_row);
+                view_changed = true;
+            }
+
+            let mut write_protect = !editor.writable.contains(&editor.space);
+            let res = ui
+                .checkbox(&mut write_protect, "Write Protect")
+                .on_hover_text(
+                    "Prevent edits to this memory. Edits change memory directly, without \
+                    triggering mapper or I/O register writes.",
+                );
+            if res.changed() {
+                if write_protect {
+                    editor.writable.retain(|&space| space != editor.space);
+                } else {
+                    editor.writable.push(editor.space);
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Search:");
+            let res = ui.add(
+                TextEdit::singleline(&mut editor.search)
+                    .hint_text("A9 00 8D or \"TEXT\"")
+                    .desired_width(200.0),
+            );
+            if res.changed() {
+                editor.search_status = None;
+            }
+            let enter = res.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+            if ui.button("Find Next").clicked() || enter {
+                match HexEditor::parse_pattern(&editor.search) {
+                    Some(pattern) => {
+                        let from = editor.selected.map_or(editor.offset, |addr| addr + 1);
+                        search = Some((editor.space, pattern, from));
+                    }
+                    None => editor.search_status = Some("Invalid search pattern"),
+                }
+            }
+            if let Some(status) = editor.search_status {
+                ui.label(status);
+            }
+        });
+        ui.separator();
+
+        let writable = editor.writable.contains(&editor.space);
+        let addr_width = format!("{:X}", editor.len().saturating_sub(1)).len().max(4);
+        let cell_width = ui.fonts(|fonts| {
+            fonts
+                .layout_no_wrap("00".into(), egui::FontId::monospace(14.0), Color32::WHITE)
+                .size()
+                .x
+        });
+        let res = ui.vertical(|ui| {
+            ui.spacing_mut().item_spacing.x = 6.0;
+            ui.horizontal(|ui| {
+                ui.monospace(format!("{:addr_width$} ", ""));
+                for col in 0..HexEditor::ROW_LEN {
+                    ui.add_sized(
+                        [cell_width, 0.0],
+                        Label::new(RichText::new(format!("{col:02X}")).monospace().weak()),
+                    );
+                }
+            });
+
+            let Some(page) = editor
+                .page
+                .as_ref()
+                .filter(|page| page.space == editor.space)
+            else {
+                ui.label("Loading...");
+                return;
+            };
+            for (row, bytes) in page.data.chunks(HexEditor::ROW_LEN).enumerate() {
+                let row_addr = page.start + row * HexEditor::ROW_LEN;
+                ui.horizontal(|ui| {
+                    ui.monospace(format!("${row_addr:0addr_width$X}"));
+                    for (col, &val) in bytes.iter().enumerate() {
+                        let addr = row_addr + col;
+                        if writable && editor.selected == Some(addr) {
+                            let res = ui.add(
+                                TextEdit::singleline(&mut editor.edit)
+                                    .id_source("hex_editor_edit")
+                                    .font(egui::TextStyle::Monospace)
+                                    .char_limit(2)
+                                    .hint_text(format!("{val:02X}"))
+                                    .desired_width(cell_width)
+                                    .margin(Vec2::ZERO),
+                            );
+                            if editor.focus_edit {
+                                res.request_focus();
+                                editor.focus_edit = false;
                             }
-                        });
-                        if previous_gamepad != assigned_gamepad {
-                            match &assigned_gamepad {
-                                Some(uuid) => {
-                                    match assigned_gamepad
-                                        .as_ref()
-                                        .and_then(|name| cfg.input.gamepad_assignment(name))
-                                    {
-                                        Some(existing_player) => {
-                                            self.gamepad_unassign =
-                                                Some((existing_player, player, *uuid));
-                                        }
-                                        None => self.assign_gamepad(player, *uuid, gamepads, cfg),
-                                    }
-                                }
-                                None => {
-                                    self.unassign_gamepad(player, gamepads, cfg);
+                            if res.changed() && editor.edit.len() == 2 {
+                                if let Ok(val) = u8::from_str_radix(&editor.edit, 16) {
+                                    poke = Some((editor.space, addr, val));
+                                    editor.selected =
+                                        Some(addr + 1).filter(|&addr| addr < page.len);
+                                    editor.focus_edit = true;
                                 }
+                                editor.edit.clear();
+                            }
+                            if ui.input(|i| i.key_pressed(Key::Escape)) {
+                                editor.selected = None;
+                                editor.edit.clear();
+                            }
+                        } else {
+                            let text = RichText::new(format!("{val:02X}")).monospace();
+                            let text = if val == 0x00 { text.weak() } else { text };
+                            let res = ui
+                                .add_sized(
+                                    [cell_width, 0.0],
+                                    egui::SelectableLabel::new(editor.selected == Some(addr), text),
+                                )
+                                .on_hover_text(format!(
+                                    "${addr:0addr_width$X}: {val} ({val:#010b})"
+                                ));
+                            if res.clicked() {
+                                editor.selected = Some(addr);
+                                editor.edit.clear();
+                                editor.focus_edit = true;
                             }
                         }
-                    } else {
-                        ui.set_enabled(false);
-                        let combo = egui::ComboBox::from_id_source("assigned_gamepad")
-                            .selected_text("No Gamepads Connected");
-                        combo.show_ui(ui, |_| {});
                     }
-                }
-                None => {
-                    ui.set_enabled(false);
-                    let combo = egui::ComboBox::from_id_source("assigned_gamepad")
-                        .selected_text("Gamepads not supported");
-                    combo.show_ui(ui, |_| {});
-                }
+                    let ascii = bytes
+                        .iter()
+                        .map(|&val| {
+                            if val.is_ascii_graphic() || val == b' ' {
+                                char::from(val)
+                            } else {
+                                '.'
+                            }
+                        })
+                        .collect::<String>();
+                    ui.add_space(8.0);
+                    ui.monospace(ascii);
+                });
             }
         });
-    }
 
-    fn about(&mut self, ui: &mut Ui) {
-        #[cfg(feature = "profiling")]
-        puffin::profile_function!();
+        // Scroll a row at a time with the mouse wheel
+        if res.response.hovered() {
+            let scroll = ui.input(|i| i.raw_scroll_delta.y);
+            if scroll != 0.0 {
+                let last_row = editor.len().saturating_sub(1) & !(HexEditor::ROW_LEN - 1);
+                editor.offset = if scroll > 0.0 {
+                    editor.offset.saturating_sub(HexEditor::ROW_LEN)
+                } else {
+                    (editor.offset + HexEditor::ROW_LEN).min(last_row)
+                };
+                view_changed = true;
+            }
+        }
 
-        ui.set_enabled(self.pending_keybind.is_none());
+        if let Some(addr) = editor.selected {
+            if poke.is_some() {
+                editor.scroll_to(addr);
+                view_changed = true;
+            }
+        }
+        if let Some(poke) = poke {
+            self.tx.nes_event(EmulationEvent::PokeMemory(poke));
+        }
+        if let Some(search) = search {
+            self.tx.nes_event(EmulationEvent::SearchMemory(search));
+        }
+        if view_changed {
+            self.update_hex_editor_view();
+        }
+    }
 
-        ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
-            let image = Image::new(include_image!("../../../assets/tetanes_icon.png"))
-                .max_height(50.0)
-                .shrink_to_fit();
-            ui.add(image);
+    fn ram_search(&mut self, ui: &mut Ui) {
+        let view = &mut self.ram_search;
+        let mut events = Vec::new();
 
-            ui.vertical(|ui| {
-                let grid = Grid::new("version").num_columns(2).spacing([40.0, 6.0]);
-                grid.show(ui, |ui| {
-                    ui.strong("Version:");
-                    ui.label(self.version.current());
-                    ui.end_row();
+        ui.horizontal(|ui| {
+            // icon: arrows in a circle
+            let res = ui
+                .button("🔄 New Search")
+                .on_hover_text("Start a new search with every RAM address as a candidate.");
+            if res.clicked() {
+                events.push(EmulationEvent::RamSearchReset);
+            }
+            if view.state.searching {
+                ui.label(format!("{} candidates", view.state.count));
+            }
+        });
 
-                    ui.strong("GitHub:");
-                    ui.hyperlink("https://github.com/lukexor/tetanes");
-                    ui.end_row();
+        ui.horizontal(|ui| {
+            let (mut comparison, value, delta) = match view.filter {
+                SearchFilter::Previous(comparison) => (comparison, 0x00, 1),
+                SearchFilter::Value(comparison, value) => (comparison, value, 1),
+                SearchFilter::ChangedBy(delta) => (Comparison::default(), 0x00, delta),
+            };
+            let kinds = [
+                ("Previous", SearchFilter::Previous(comparison)),
+                ("Value", SearchFilter::Value(comparison, value)),
+                ("Changed By", SearchFilter::ChangedBy(delta)),
+            ];
+            let selected = kinds
+                .iter()
+                .find(|(_, filter)| mem::discriminant(filter) == mem::discriminant(&view.filter))
+                .map_or("", |(label, _)| label);
+            egui::ComboBox::from_id_source("ram_search_filter")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    for (label, filter) in kinds {
+                        let checked = mem::discriminant(&filter) == mem::discriminant(&view.filter);
+                        if ui.selectable_label(checked, label).clicked() && !checked {
+                            view.filter = filter;
+                        }
+                    }
                 });
 
-                if platform::supports(platform::Feature::Filesystem) {
-                    ui.separator();
-                    ui.horizontal_wrapped(|ui| {
-                        let grid = Grid::new("directories").num_columns(2).spacing([40.0, 6.0]);
-                        grid.show(ui, |ui| {
-                            if let Some(config_dir) = Config::default_config_dir() {
-                                ui.strong("Preferences:");
-                                ui.label(format!("{}", config_dir.display()));
-                                ui.end_row();
-                            }
+            if let SearchFilter::Previous(_) | SearchFilter::Value(..) = view.filter {
+                egui::ComboBox::from_id_source("ram_search_comparison")
+                    .width(40.0)
+                    .selected_text(comparison.as_ref())
+                    .show_ui(ui, |ui| {
+                        for value in Comparison::as_slice() {
+                            ui.selectable_value(&mut comparison, *value, value.as_ref());
+                        }
+                    });
+            }
+            match &mut view.filter {
+                SearchFilter::Previous(filter_comparison) => *filter_comparison = comparison,
+                SearchFilter::Value(filter_comparison, value) => {
+                    *filter_comparison = comparison;
+                    ui.add(
+                        DragValue::new(value)
+                            .hexadecimal(2, false, true)
+                            .prefix("$"),
+                    );
+                }
+                SearchFilter::ChangedBy(delta) => {
+                    ui.add(DragValue::new(delta).clamp_range(-255..=255));
+                }
+            }
 
-                            if let Some(data_dir) = Config::default_data_dir() {
-                                ui.strong("Save States/RAM, Replays: ");
-                                ui.label(format!("{}", data_dir.display()));
-                                ui.end_row();
-                            }
+            // icon: magnifying glass
+            let res = ui.button("🔍 Search").on_hover_text(
+                "Keep only the candidates matching the filter, compared against their values \
+                at the previous search.",
+            );
+            if res.clicked() {
+                events.push(EmulationEvent::RamSearchFilter(view.filter));
+            }
+        });
+        ui.separator();
 
-                            if let Some(picture_dir) = Config::default_picture_dir() {
-                                ui.strong("Screenshots: ");
-                                ui.label(format!("{}", picture_dir.display()));
-                                ui.end_row();
+        ui.strong("Candidates");
+        if view.state.count > view.state.candidates.len() {
+            ui.label(format!(
+                "Showing the first {} candidates.",
+                view.state.candidates.len()
+            ));
+        }
+        ScrollArea::vertical()
+            .id_source("ram_search_candidates")
+            .max_height(250.0)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                let grid = Grid::new("ram_search_candidates_grid")
+                    .num_columns(4)
+                    .spacing([20.0, 4.0])
+                    .striped(true);
+                grid.show(ui, |ui| {
+                    ui.strong("Address");
+                    ui.strong("Previous");
+                    ui.strong("Current");
+                    ui.end_row();
+                    for &(addr, previous, current) in &view.state.candidates {
+                        ui.monospace(format!("${addr:04X}"));
+                        ui.monospace(format!("${previous:02X} ({previous})"));
+                        ui.monospace(format!("${current:02X} ({current})"));
+                        ui.horizontal(|ui| {
+                            let watched = view.watches.contains(&addr);
+                            let res = ui
+                                .add_enabled(!watched, Button::new("Watch"))
+                                .on_hover_text("Add to the watch list.");
+                            if res.clicked() {
+                                view.watches.push(addr);
+                                events.push(EmulationEvent::RamWatches(view.watches.clone()));
                             }
-
-                            if let Some(audio_dir) = Config::default_audio_dir() {
-                                ui.strong("Audio Recordings: ");
-                                ui.label(format!("{}", audio_dir.display()));
-                                ui.end_row();
+                            let res = ui
+                                .button("Freeze")
+                                .on_hover_text("Freeze at the current value as a cheat.");
+                            if res.clicked() {
+                                events.push(EmulationEvent::RamCheat((addr, Some(current))));
                             }
                         });
-                    });
-                }
+                        ui.end_row();
+                    }
+                });
             });
-        });
-    }
-
-    fn about_homebrew(ui: &mut Ui, rom: RomAsset) {
-        ScrollArea::vertical().show(ui, |ui| {
-            ui.strong("Author(s):");
-            ui.label(rom.authors);
-            ui.add_space(12.0);
-
-            ui.strong("Description:");
-            ui.label(rom.description);
-            ui.add_space(12.0);
-
-            ui.strong("Source:");
-            ui.hyperlink(rom.source);
-        });
-    }
+        ui.separator();
 
-    fn save_slot_radio(&mut self, ui: &mut Ui, cfg: &mut Config, shortcut: ShowShortcut) {
-        ui.vertical(|ui| {
-            for slot in 1..=4 {
-                let shortcut_txt = shortcut
-                    .then(|| self.fmt_shortcut(DeckAction::SetSaveSlot(slot)))
-                    .unwrap_or_default();
-                let radio = RadioValue::new(&mut cfg.emulation.save_slot, slot, slot.to_string())
-                    .shortcut_text(shortcut_txt);
-                ui.add(radio);
-            }
-        });
-        ui.vertical(|ui| {
-            for slot in 5..=8 {
-                let shortcut_txt = shortcut
-                    .then(|| self.fmt_shortcut(DeckAction::SetSaveSlot(slot)))
-                    .unwrap_or_default();
-                let radio = RadioValue::new(&mut cfg.emulation.save_slot, slot, slot.to_string())
-                    .shortcut_text(shortcut_txt);
-                ui.add(radio);
+        ui.strong("Watches");
+        let grid = Grid::new("ram_search_watches_grid")
+            .num_columns(3)
+            .spacing([20.0, 4.0])
+            .striped(true);
+        grid.show(ui, |ui| {
+            for &(addr, value) in &view.state.watches {
+                ui.monospace(format!("${addr:04X}"));
+                ui.monospace(format!("${value:02X} ({value})"));
+                ui.horizontal(|ui| {
+                    let res = ui
+                        .button("Freeze")
+                        .on_hover_text("Freeze at the current value as a cheat.");
+                    if res.clicked() {
+                        events.push(EmulationEvent::RamCheat((addr, Some(value))));
+                    }
+                    // icon: waste basket
+                    if ui.button("🗑").on_hover_text("Stop watching.").clicked() {
+                        view.watches.retain(|&watch| watch != addr);
+                        events.push(EmulationEvent::RamWatches(view.watches.clone()));
+                    }
+                });
+                ui.end_row();
             }
         });
-    }
+        ui.separator();
 
-    fn speed_slider(&mut self, ui: &mut Ui, cfg: &mut Config) {
-        let slider = Slider::new(&mut cfg.emulation.speed, 0.25..=2.0)
-            .step_by(0.25)
-            .suffix("x");
-        let res = ui
-            .add(slider)
-            .on_hover_text("Adjust the speed of the NES emulation.");
-        if res.changed() {
-            self.tx.nes_event(ConfigEvent::Speed(cfg.emulation.speed));
-        }
-    }
+        ui.strong("Cheats")
+            .on_hover_cursor(CursorIcon::Help)
+            .on_hover_text(
+                "Frozen RAM values. Reads return the frozen value even after the game writes a \
+                new one. Cleared when the ROM is unloaded.",
+            );
+        let grid = Grid::new("ram_search_cheats_grid")
+            .num_columns(3)
+            .spacing([20.0, 4.0])
+            .striped(true);
+        grid.show(ui, |ui| {
+            for &(addr, value) in &view.state.cheats {
+                ui.monospace(format!("${addr:04X}"));
+                let mut value = value;
+                let res = ui.add(
+                    DragValue::new(&mut value)
+                        .hexadecimal(2, false, true)
+                        .prefix("$"),
+                );
+                if res.changed() {
+                    events.push(EmulationEvent::RamCheat((addr, Some(value))));
+                }
+                // icon: waste basket
+                if ui.button("🗑").on_hover_text("Unfreeze.").clicked() {
+                    events.push(EmulationEvent::RamCheat((addr, None)));
+                }
+                ui.end_row();
+            }
+        });
 
-    fn run_ahead_slider(&mut self, ui: &mut Ui, cfg: &mut Config) {
-        let slider = Slider::new(&mut cfg.emulation.run_ahead, 0..=4);
-        let res = ui
-            .add(slider)
-            .on_hover_text("Simulate a number of frames in the future to reduce input lag.");
-        if res.changed() {
-            self.tx
-                .nes_event(ConfigEvent::RunAhead(cfg.emulation.run_ahead));
+        for event in events {
+            self.tx.nes_event(event);
         }
     }
 
-    fn cycle_acurate_checkbox(&mut self, ui: &mut Ui, cfg: &mut Config, shortcut: ShowShortcut) {
+    fn menubar_checkbox(&mut self, ui: &mut Ui, cfg: &mut Config, shortcut: ShowShortcut) {
         let shortcut_txt = shortcut
-            .then(|| self.fmt_shortcut(Setting::ToggleCycleAccurate))
+            .then(|| self.fmt_shortcut(Setting::ToggleMenubar))
             .unwrap_or_default();
-        let icon = shortcut.then(|| "📐 ").unwrap_or_default();
+        let icon = shortcut.then(|| "☰ ").unwrap_or_default();
         let checkbox = Checkbox::new(
-            &mut cfg.deck.cycle_accurate,
-            format!("{icon}Cycle Accurate"),
+            &mut cfg.renderer.show_menubar,
+            format!("{icon}Show Menu Bar"),
         )
         .shortcut_text(shortcut_txt);
-        let res = ui
-            .add(checkbox)
-            .on_hover_text("Enables more accurate NES emulation at a slight cost in performance.");
-        if res.clicked() {
-            self.tx
-                .nes_event(ConfigEvent::CycleAccurate(cfg.deck.cycle_accurate));
+        let res = ui.add(checkbox).on_hover_text("Show the menu bar.");
+        if res.clicked() && !cfg.renderer.show_menubar {
+            self.menu_height = 0.0;
+            self.resize_window = true;
         }
     }
 
-    fn rewind_checkbox(&mut self, ui: &mut Ui, cfg: &mut Config, shortcut: ShowShortcut) {
+    fn messages_checkbox(&mut self, ui: &mut Ui, cfg: &mut Config, shortcut: ShowShortcut) {
         let shortcut_txt = shortcut
-            .then(|| self.fmt_shortcut(Setting::ToggleRewinding))
+            .then(|| self.fmt_shortcut(Setting::ToggleMessages))
             .unwrap_or_default();
-        let icon = shortcut.then(|| "🔄 ").unwrap_or_default();
-        let checkbox = Checkbox::new(&mut cfg.emulation.rewind, format!("{icon}Enable Rewinding"))
-            .shortcut_text(shortcut_txt);
-        let res = ui
-            .add(checkbox)
-            .on_hover_text("Enable instant and visual rewinding. Increases memory usage.");
-        if res.clicked() {
-            self.tx
-                .nes_event(ConfigEvent::RewindEnabled(cfg.emulation.rewind));
-        }
+        // icon: document with text
+        let icon = shortcut.then(|| "🖹 ").unwrap_or_default();
+        let checkbox = Checkbox::new(
+            &mut cfg.renderer.show_messages,
+            format!("{icon}Show Messages"),
+        )
+        .shortcut_text(shortcut_txt);
+        ui.add(checkbox)
+            .on_hover_text("Show shortcut and emulator messages.");
     }
 
-    fn zapper_checkbox(&mut self, ui: &mut Ui, cfg: &mut Config, shortcut: ShowShortcut) {
+    fn audio_scope_checkbox(&mut self, ui: &mut Ui, cfg: &mut Config, shortcut: ShowShortcut) {
         let shortcut_txt = shortcut
-            .then(|| self.fmt_shortcut(DeckAction::ToggleZapperConnected))
+            .then(|| self.fmt_shortcut(Setting::ToggleAudioScope))
             .unwrap_or_default();
-        let icon = shortcut.then(|| "🔫 ").unwrap_or_default();
-        let checkbox = Checkbox::new(&mut cfg.deck.zapper, format!("{icon}Enable Zapper Gun"))
-            .shortcut_text(shortcut_txt);
+        // icon: sine wave
+        let icon = shortcut.then(|| "∿ ").unwrap_or_default();
+        let checkbox = Checkbox::new(
+            &mut cfg.renderer.show_audio_scope,
+            format!("{icon}Show Audio Scope"),
+        )
+        .shortcut_text(shortcut_txt);
         let res = ui
             .add(checkbox)
-            .on_hover_text("Enable the Zapper Light Gun for games that support it.");
+            .on_hover_text("Show an oscilloscope overlay for each APU channel.");
         if res.clicked() {
             self.tx
-                .nes_event(ConfigEvent::ZapperConnected(cfg.deck.zapper));
+                .nes_event(ConfigEvent::AudioScope(cfg.renderer.show_audio_scope));
         }
     }
 
-    fn overscan_checkbox(&mut self, ui: &mut Ui, cfg: &mut Config, shortcut: ShowShortcut) {
+    fn perf_stats_checkbox(&mut self, ui: &mut Ui, cfg: &mut Config, shortcut: ShowShortcut) {
         let shortcut_txt = shortcut
-            .then(|| self.fmt_shortcut(Setting::ToggleOverscan))
+            .then(|| self.fmt_shortcut(Setting::ToggleFps))
             .unwrap_or_default();
-        let icon = shortcut.then(|| "📺 ").unwrap_or_default();
+        // icon: stopwatch
+        let icon = shortcut.then(|| "⏱ ").unwrap_or_default();
         let checkbox = Checkbox::new(
-            &mut cfg.renderer.hide_overscan,
-            format!("{icon}Hide Overscan"),
+            &mut cfg.renderer.show_perf_stats,
+            format!("{icon}Show Performance Overlay"),
         )
         .shortcut_text(shortcut_txt);
-        let res = ui.add(checkbox)
-            .on_hover_text("Traditional CRT displays would crop the top and bottom edges of the image. Disable this to show the overscan.");
+        let res = ui.add(checkbox).on_hover_text(
+            "Show frame times, where frame time is spent, audio buffer fill and dropped frames.",
+        );
         if res.clicked() {
-            self.resize_texture = self.loaded_region.is_ntsc();
             self.tx
-                .nes_event(ConfigEvent::HideOverscan(cfg.renderer.hide_overscan));
-        }
-    }
-
-    fn video_filter_radio(&mut self, ui: &mut Ui, cfg: &mut Config) {
-        let filter = cfg.deck.filter;
-        ui.radio_value(&mut cfg.deck.filter, VideoFilter::Pixellate, "Pixellate")
-            .on_hover_text("Basic pixel-perfect rendering");
-        ui.radio_value(&mut cfg.deck.filter, VideoFilter::Ntsc, "Ntsc")
-            .on_hover_text(
-                "Emulate traditional NTSC rendering where chroma spills over into luma.",
-            );
-        if filter != cfg.deck.filter {
-            self.tx.nes_event(ConfigEvent::VideoFilter(cfg.deck.filter));
+                .nes_event(ConfigEvent::PerfStats(cfg.renderer.show_perf_stats));
         }
     }
 
-    fn four_player_radio(&mut self, ui: &mut Ui, cfg: &mut Config) {
-        let four_player = cfg.deck.four_player;
-        ui.radio_value(&mut cfg.deck.four_player, FourPlayer::Disabled, "Disabled");
-        ui.radio_value(
-            &mut cfg.deck.four_player,
-            FourPlayer::FourScore,
-            "Four Score",
-        )
-        .on_hover_text("Enable NES Four Score for games that support 4 players.");
+    fn screenshot_settings(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let screenshot = &mut cfg.emulation.screenshot;
+        let prev_screenshot = screenshot.clone();
+        ui.radio_value(&mut screenshot.format, ScreenshotFormat::Raw, "Raw")
+            .on_hover_text("Save the unfiltered 256x240 frame of NES palette indices.");
         ui.radio_value(
-            &mut cfg.deck.four_player,
-            FourPlayer::Satellite,
-            "Satellite",
+            &mut screenshot.format,
+            ScreenshotFormat::Filtered,
+            "Filtered",
         )
-        .on_hover_text("Enable NES Satellite for games that support 4 players.");
-        if four_player != cfg.deck.four_player {
-            self.tx
-                .nes_event(ConfigEvent::FourPlayer(cfg.deck.four_player));
-        }
-    }
+        .on_hover_text("Save the frame after video filtering and post-processing.");
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut screenshot.format, ScreenshotFormat::Scaled, "Scaled")
+                .on_hover_text("Save the filtered frame scaled up by an integer factor.");
+            ui.add_enabled(
+                screenshot.format == ScreenshotFormat::Scaled,
+                DragValue::new(&mut screenshot.scale)
+                    .clamp_range(1..=8)
+                    .suffix("x"),
+            );
+        });
+        ui.checkbox(&mut screenshot.copy_to_clipboard, "Copy to Clipboard");
 
-    fn nes_region_radio(&mut self, ui: &mut Ui, cfg: &mut Config) {
-        let region = cfg.deck.region;
-        ui.radio_value(&mut cfg.deck.region, NesRegion::Auto, "Auto")
-            .on_hover_text("Auto-detect region based on loaded ROM.");
-        ui.radio_value(&mut cfg.deck.region, NesRegion::Ntsc, "NTSC")
-            .on_hover_text("Emulate NTSC timing and aspect-ratio.");
-        ui.radio_value(&mut cfg.deck.region, NesRegion::Pal, "PAL")
-            .on_hover_text("Emulate PAL timing and aspect-ratio.");
-        ui.radio_value(&mut cfg.deck.region, NesRegion::Dendy, "Dendy")
-            .on_hover_text("Emulate Dendy timing and aspect-ratio.");
-        if region != cfg.deck.region {
-            self.resize_window = true;
-            self.resize_texture = true;
-            self.tx.nes_event(ConfigEvent::Region(cfg.deck.region));
+        let mut dir = screenshot
+            .dir
+            .as_ref()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default();
+        let default_dir = Config::default_picture_dir()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default();
+        let res = ui
+            .add(TextEdit::singleline(&mut dir).hint_text(default_dir))
+            .on_hover_text("Directory to save screenshots to.");
+        if res.changed() {
+            screenshot.dir = (!dir.is_empty()).then(|| PathBuf::from(dir));
         }
-    }
 
-    fn ram_state_radio(&mut self, ui: &mut Ui, cfg: &mut Config) {
-        let ram_state = cfg.deck.ram_state;
-        ui.radio_value(&mut cfg.deck.ram_state, RamState::AllZeros, "All 0x00")
-            .on_hover_text("Clear startup RAM to all zeroes for predictable emulation.");
-        ui.radio_value(&mut cfg.deck.ram_state, RamState::AllOnes, "All 0xFF")
-            .on_hover_text("Clear startup RAM to all ones for predictable emulation.");
-        ui.radio_value(&mut cfg.deck.ram_state, RamState::Random, "Random")
-            .on_hover_text("Randomize startup RAM, which some games use as a basic RNG seed.");
-        if ram_state != cfg.deck.ram_state {
-            self.tx.nes_event(ConfigEvent::RamState(cfg.deck.ram_state));
+        ui.checkbox(&mut screenshot.capture_milestones, "Capture Milestones")
+            .on_hover_text(
+                "Save screenshots requested by scripts or achievements into a folder per game.",
+            );
+
+        if *screenshot != prev_screenshot {
+            self.tx
+                .nes_event(ConfigEvent::Screenshot(screenshot.clone()));
         }
     }
 
-    fn genie_codes_entry(&mut self, ui: &mut Ui, cfg: &mut Config) {
-        ui.strong("Add Genie Code:")
-            .on_hover_cursor(CursorIcon::Help)
-            .on_hover_text(
-                "A Game Genie Code is a 6 or 8 letter string that temporarily modifies game memory during operation. e.g. `AATOZE` will start Super Mario Bros. with 9 lives."
-            );
-        ui.horizontal(|ui| {
-            let entry_res = ui.text_edit_singleline(&mut self.pending_genie_entry.code);
-            let has_entry = !self.pending_genie_entry.code.is_empty();
-            let submit_res = ui.add_enabled(has_entry, Button::new("➕"));
-            if entry_res.changed() {
-                self.pending_genie_entry.error = None;
-            }
-            if (has_entry && entry_res.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)))
-                || submit_res.clicked()
-            {
-                match GenieCode::parse(&self.pending_genie_entry.code) {
-                    Ok(hex) => {
-                        let code =
-                            GenieCode::from_raw(mem::take(&mut self.pending_genie_entry.code), hex);
-                        if !cfg.deck.genie_codes.contains(&code) {
-                            cfg.deck.genie_codes.push(code.clone());
-                            self.tx.nes_event(ConfigEvent::GenieCodeAdded(code));
-                        }
-                    }
-                    Err(err) => self.pending_genie_entry.error = Some(err.to_string()),
+    fn rom_db_settings(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let settings = &mut cfg.emulation.rom_db;
+        ui.label(format!(
+            "Version {}",
+            tetanes_core::rom_db::active().version()
+        ));
+        Grid::new("rom_db_source")
+            .num_columns(2)
+            .spacing([20.0, 6.0])
+            .show(ui, |ui| {
+                ui.label("Update URL:");
+                let mut url = settings.url.clone().unwrap_or_default();
+                if ui
+                    .text_edit_singleline(&mut url)
+                    .on_hover_text("Base URL serving `latest` and `rom_db-<version>.dat` files.")
+                    .changed()
+                {
+                    settings.url = (!url.is_empty()).then_some(url);
                 }
-            }
-        });
-        if let Some(error) = &self.pending_genie_entry.error {
-            ui.allocate_space(Vec2::new(Self::MENU_WIDTH, 0.0));
-            ui.colored_label(Color32::RED, error);
-        }
+                ui.end_row();
 
-        if !cfg.deck.genie_codes.is_empty() {
-            ui.separator();
-            ui.strong("Current Genie Codes:");
-            ScrollArea::vertical().show(ui, |ui| {
-                cfg.deck.genie_codes.retain(|genie| {
-                    ui.horizontal(|ui| {
-                        ui.label(genie.code());
-                        // icon: waste basket
-                        if ui.button("🗑").clicked() {
-                            self.tx
-                                .nes_event(ConfigEvent::GenieCodeRemoved(genie.code().to_string()));
-                            false
-                        } else {
-                            true
-                        }
-                    })
-                    .inner
-                });
+                ui.label("Public Key:");
+                let mut public_key = settings.public_key.clone().unwrap_or_default();
+                if ui
+                    .text_edit_singleline(&mut public_key)
+                    .on_hover_text("Hex-encoded Ed25519 key the databases are signed with.")
+                    .changed()
+                {
+                    settings.public_key = (!public_key.is_empty()).then_some(public_key);
+                }
+                ui.end_row();
             });
+        let configured = settings.url.is_some() && settings.public_key.is_some();
+        ui.add_enabled(
+            configured,
+            Checkbox::new(&mut settings.auto_update, "Update Automatically"),
+        )
+        .on_hover_text("Check for a newer ROM database on startup.");
+        ui.horizontal(|ui| {
+            let mut pinned = settings.pinned_version.is_some();
+            ui.checkbox(&mut pinned, "Pin Version")
+                .on_hover_text("Only install the selected version instead of the latest.");
+            let mut version = settings
+                .pinned_version
+                .unwrap_or_else(|| tetanes_core::rom_db::active().version());
+            ui.add_enabled(
+                pinned,
+                DragValue::new(&mut version).clamp_range(1..=u32::MAX),
+            );
+            settings.pinned_version = pinned.then_some(version);
+        });
+        if ui
+            .add_enabled(configured, Button::new("Check for Updates"))
+            .clicked()
+        {
+            rom_db::spawn_update(settings, self.tx.clone(), true);
         }
     }
 
-    fn menubar_checkbox(&mut self, ui: &mut Ui, cfg: &mut Config, shortcut: ShowShortcut) {
-        let shortcut_txt = shortcut
-            .then(|| self.fmt_shortcut(Setting::ToggleMenubar))
+    fn livesplit_settings(&mut self, ui: &mut Ui, cfg: &mut Config) {
+        let settings = &mut cfg.emulation.livesplit;
+        let prev_settings = settings.clone();
+
+        let autosplitter_dir = livesplit::autosplitter_dir()
+            .map(|dir| dir.display().to_string())
             .unwrap_or_default();
-        let icon = shortcut.then(|| "☰ ").unwrap_or_default();
-        let checkbox = Checkbox::new(
-            &mut cfg.renderer.show_menubar,
-            format!("{icon}Show Menu Bar"),
+        ui.checkbox(&mut settings.enabled, "Enable LiveSplit Server")
+            .on_hover_text(format!(
+                "Start, split and reset the timer in LiveSplit using autosplitters from {autosplitter_dir}."
+            ));
+        ui.add_enabled(
+            settings.enabled,
+            TextEdit::singleline(&mut settings.addr).hint_text(livesplit::DEFAULT_ADDR),
         )
-        .shortcut_text(shortcut_txt);
-        let res = ui.add(checkbox).on_hover_text("Show the menu bar.");
-        if res.clicked() && !cfg.renderer.show_menubar {
-            self.menu_height = 0.0;
-            self.resize_window = true;
+        .on_hover_text("Host and port of the LiveSplit Server component.");
+
+        if *settings != prev_settings {
+            self.tx.nes_event(ConfigEvent::LiveSplit(settings.clone()));
         }
     }
 
-    fn messages_checkbox(&mut self, ui: &mut Ui, cfg: &mut Config, shortcut: ShowShortcut) {
-        let shortcut_txt = shortcut
-            .then(|| self.fmt_shortcut(Setting::ToggleMessages))
-            .unwrap_or_default();
-        // icon: document with text
-        let icon = shortcut.then(|| "🖹 ").unwrap_or_default();
-        let checkbox = Checkbox::new(
-            &mut cfg.renderer.show_messages,
-            format!("{icon}Show Messages"),
-        )
-        .shortcut_text(shortcut_txt);
-        ui.add(checkbox)
-            .on_hover_text("Show shortcut and emulator messages.");
+    fn touch_controller_settings(ui: &mut Ui, cfg: &mut Config) {
+        let touch = &mut cfg.renderer.touch_controller;
+        ui.horizontal(|ui| {
+            for mode in [
+                TouchControllerMode::Auto,
+                TouchControllerMode::Always,
+                TouchControllerMode::Never,
+            ] {
+                ui.radio_value(&mut touch.mode, mode, mode.as_ref());
+            }
+        })
+        .response
+        .on_hover_text("Auto shows the controller once the screen is touched.");
+        ui.add_enabled_ui(touch.mode != TouchControllerMode::Never, |ui| {
+            ui.add(
+                Slider::new(&mut touch.opacity, 0.1..=1.0)
+                    .step_by(0.05)
+                    .text("Opacity"),
+            );
+            ui.add(
+                Slider::new(&mut touch.size, 0.5..=2.0)
+                    .step_by(0.25)
+                    .suffix("x")
+                    .text("Size"),
+            );
+            ui.checkbox(&mut touch.left_handed, "Left Handed")
+                .on_hover_text("Put the D-Pad on the right and A/B on the left.");
+        });
+    }
+
+    fn overlay_position_radio(ui: &mut Ui, value: &mut OverlayPosition) {
+        ui.vertical(|ui| {
+            for position in [OverlayPosition::TopLeft, OverlayPosition::BottomLeft] {
+                ui.radio_value(value, position, position.as_ref());
+            }
+        });
+        ui.vertical(|ui| {
+            for position in [OverlayPosition::TopRight, OverlayPosition::BottomRight] {
+                ui.radio_value(value, position, position.as_ref());
+            }
+        });
     }
 
     fn window_scale_radio(&mut self, ui: &mut Ui, cfg: &mut Config) {
@@ -2581,6 +6825,22 @@ impl Gui {
         }
     }
 
+    /// Returns the largest window scale that fits on the current monitor, leaving room for
+    /// window decorations and taskbars.
+    fn fit_screen_scale(&self, ctx: &Context, cfg: &Config) -> Option<f32> {
+        const SCREEN_MARGIN: f32 = 0.9;
+
+        let monitor_size = ctx.input(|i| i.viewport().monitor_size)?;
+        let frame_size = cfg.overscan(self.region(cfg)).frame_size();
+        let size = cfg.renderer.orientation.size(Vec2::new(
+            frame_size.x * self.aspect_ratio(cfg),
+            frame_size.y,
+        ));
+        let width = monitor_size.x * SCREEN_MARGIN / size.x;
+        let height = (monitor_size.y * SCREEN_MARGIN - self.menu_height) / size.y;
+        Some(width.min(height).max(1.0))
+    }
+
     fn fullscreen_checkbox(&mut self, ui: &mut Ui, cfg: &mut Config, shortcut: ShowShortcut) {
         let shortcut_txt = shortcut
             .then(|| self.fmt_shortcut(Setting::ToggleFullscreen))
@@ -2634,6 +6894,7 @@ impl Gui {
             None => cfg_bindings.push(ActionBindings {
                 action,
                 bindings: [Some(input), None],
+                repeat: None,
             }),
         }
     }
@@ -2668,6 +6929,96 @@ impl Gui {
             .unwrap_or_default()
     }
 
+    /// Apply the configured theme and UI scale. Scales text and widgets rather than the zoom
+    /// factor, so the NES frame keeps the window scale.
+    pub fn set_style(ctx: &Context, cfg: &Config) {
+        let mut visuals = if cfg.renderer.dark_theme {
+            Self::dark_theme()
+        } else {
+            Self::light_theme()
+        };
+        if cfg.renderer.high_contrast {
+            visuals = Self::high_contrast_theme(visuals);
+        }
+
+        let scale = cfg.renderer.ui_scale;
+        let default = egui::Style::default();
+        ctx.style_mut(|style| {
+            style.visuals = visuals;
+            style.text_styles = default
+                .text_styles
+                .into_iter()
+                .map(|(text_style, font)| {
+                    (
+                        text_style,
+                        egui::FontId::new(font.size * scale, font.family),
+                    )
+                })
+                .collect();
+
+            let (spacing, default) = (&mut style.spacing, &default.spacing);
+            spacing.item_spacing = default.item_spacing * scale;
+            spacing.button_padding = default.button_padding * scale;
+            spacing.interact_size = default.interact_size * scale;
+            spacing.indent = default.indent * scale;
+            spacing.slider_width = default.slider_width * scale;
+            spacing.combo_width = default.combo_width * scale;
+            spacing.text_edit_width = default.text_edit_width * scale;
+            spacing.icon_width = default.icon_width * scale;
+            spacing.icon_width_inner = default.icon_width_inner * scale;
+            spacing.icon_spacing = default.icon_spacing * scale;
+            spacing.menu_width = default.menu_width * scale;
+            spacing.tooltip_width = default.tooltip_width * scale;
+        });
+    }
+
+    /// High-contrast variant of `visuals` with pure background and text colors, a single bright
+    /// accent color and bolder outlines around interactive widgets.
+    fn high_contrast_theme(mut visuals: egui::Visuals) -> egui::Visuals {
+        let (bg, fg, accent) = if visuals.dark_mode {
+            (Color32::BLACK, Color32::WHITE, Color32::YELLOW)
+        } else {
+            (Color32::WHITE, Color32::BLACK, hex_color!("#0033cc"))
+        };
+
+        let widgets = &mut visuals.widgets;
+        for widget in [
+            &mut widgets.noninteractive,
+            &mut widgets.inactive,
+            &mut widgets.hovered,
+            &mut widgets.active,
+            &mut widgets.open,
+        ] {
+            widget.weak_bg_fill = bg;
+            widget.bg_fill = bg;
+            widget.fg_stroke = Stroke::new(widget.fg_stroke.width.max(1.5), fg);
+        }
+        widgets.noninteractive.bg_stroke = Stroke::new(1.0, fg);
+        widgets.inactive.bg_stroke = Stroke::new(1.5, fg);
+        widgets.hovered.bg_stroke = Stroke::new(2.0, accent);
+        widgets.hovered.fg_stroke.color = accent;
+        widgets.active.bg_stroke = Stroke::new(2.5, accent);
+        widgets.active.fg_stroke.color = accent;
+        widgets.open.bg_stroke = Stroke::new(2.0, accent);
+
+        Visuals {
+            selection: Selection {
+                bg_fill: accent,
+                stroke: Stroke::new(2.0, bg),
+            },
+            hyperlink_color: accent,
+            faint_bg_color: bg,
+            extreme_bg_color: bg,
+            code_bg_color: bg,
+            window_fill: bg,
+            window_stroke: Stroke::new(2.0, fg),
+            panel_fill: bg,
+            text_cursor: Stroke::new(2.0, accent),
+            striped: false,
+            ..visuals
+        }
+    }
+
     fn dark_theme() -> egui::Visuals {
         Visuals {
             dark_mode: true,
@@ -2824,12 +7175,24 @@ const fn bytes_to_mb(bytes: u64) -> u64 {
     bytes / 0x100000
 }
 
-fn cursor_to_zapper(x: f32, y: f32, rect: Rect) -> Option<Pos2> {
+fn cursor_to_zapper(
+    x: f32,
+    y: f32,
+    rect: Rect,
+    overscan: Overscan,
+    orientation: Orientation,
+) -> Option<Pos2> {
     let width = Ppu::WIDTH as f32;
     let height = Ppu::HEIGHT as f32;
-    // Normalize x/y to 0..=1 and scale to PPU dimensions
-    let x = ((x - rect.min.x) / rect.width()) * width;
-    let y = ((y - rect.min.y) / rect.height()) * height;
+    // Normalize x/y to 0..=1, undo rotation and mirroring and scale to the cropped frame within
+    // PPU dimensions
+    let pos = orientation.to_frame(Pos2::new(
+        (x - rect.min.x) / rect.width(),
+        (y - rect.min.y) / rect.height(),
+    ));
+    let uv = overscan.uv();
+    let x = (uv.min.x + pos.x * uv.width()) * width;
+    let y = (uv.min.y + pos.y * uv.height()) * height;
     ((0.0..width).contains(&x) && (0.0..height).contains(&y)).then_some(Pos2::new(x, y))
 }
 
@@ -2956,6 +7319,12 @@ impl<'a> ShortcutText<'a> for Checkbox<'a> {}
 impl<'a> ShortcutText<'a> for ToggleValue<'a> {}
 impl<'a, T> ShortcutText<'a> for RadioValue<'a, T> {}
 
+/// Format an NSF track time as `minutes:seconds`.
+fn fmt_track_time(time: Duration) -> String {
+    let secs = time.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
 fn format_input(input: Input) -> String {
     match input {
         Input::Key(keycode, modifiers) => {
@@ -0,0 +1,716 @@
+//! User-provided WGSL shader presets applied to the NES frame on the GPU.
+//!
+//! Presets are read from the `shaders` folder in the configuration directory and use a format
+//! modeled after slang presets:
+//!
+//! ```text
+//! # Comments start with `#`
+//! shaders = 2
+//!
+//! shader0 = crt.wgsl
+//! scale0 = 4.0
+//! filter_linear0 = false
+//!
+//! shader1 = bloom.wgsl
+//! filter_linear1 = true
+//! float_framebuffer1 = true
+//!
+//! # Filtering used to draw the final output to the window
+//! filter_linear2 = true
+//!
+//! # Parameter overrides
+//! CURVATURE = 0.05
+//! ```
+//!
+//! Shader paths are relative to the preset. Each pass is a WGSL module defining an `fs_main`
+//! fragment entry point, which is appended to [`PRELUDE`] providing the vertex stage, the
+//! `uniforms`, `source` (the previous pass output, or the NES frame for the first pass),
+//! `source_sampler` and `original` (the NES frame) bindings, and a `param` function.
+//!
+//! Parameters are declared in WGSL comments, similar to slang's `#pragma parameter`, and read in
+//! declaration order with `param(0)`, `param(1)`, etc:
+//!
+//! ```text
+//! // #pragma parameter CURVATURE "Screen Curvature" 0.1 0.0 0.5 0.01
+//! ```
+
+use crate::nes::{config::Config, renderer::texture::Texture};
+use anyhow::{bail, Context};
+use egui::{load::SizedTexture, TextureId};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+/// File extension of shader presets.
+pub const PRESET_EXTENSION: &str = "wgslp";
+/// Maximum number of parameters declared by a single pass.
+pub const MAX_PARAMETERS: usize = 16;
+
+/// Declarations prepended to every pass.
+pub const PRELUDE: &str = r"
+struct Uniforms {
+    // width, height, 1 / width, 1 / height
+    source_size: vec4<f32>,
+    original_size: vec4<f32>,
+    output_size: vec4<f32>,
+    frame_count: u32,
+    params: array<vec4<f32>, 4>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+@group(0) @binding(1) var source: texture_2d<f32>;
+@group(0) @binding(2) var source_sampler: sampler;
+@group(0) @binding(3) var original: texture_2d<f32>;
+
+fn param(index: u32) -> f32 {
+    return uniforms.params[index / 4u][index % 4u];
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+";
+
+/// Size of the `Uniforms` struct declared in [`PRELUDE`], in 4-byte words.
+const UNIFORM_WORDS: usize = 32;
+
+/// Path identifying the built-in CRT preset rendered for [`VideoFilter::Crt`], which isn't a
+/// file.
+///
+/// [`VideoFilter::Crt`]: tetanes_core::video::VideoFilter::Crt
+pub const CRT_PRESET: &str = "CRT (built-in)";
+/// Source of the built-in CRT shader.
+const CRT_SHADER: &str = include_str!("../../../assets/crt.wgsl");
+
+/// Directory shader presets are loaded from.
+#[must_use]
+pub fn presets_dir() -> Option<PathBuf> {
+    Config::default_config_dir().map(|dir| dir.join("shaders"))
+}
+
+/// Returns all shader presets in the [`presets_dir`], sorted by path.
+#[must_use]
+pub fn list_presets() -> Vec<PathBuf> {
+    let Some(Ok(entries)) = presets_dir().map(std::fs::read_dir) else {
+        return Vec::new();
+    };
+    let mut presets = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == PRESET_EXTENSION))
+        .collect::<Vec<_>>();
+    presets.sort();
+    presets
+}
+
+/// Display name of the preset at `path`.
+#[must_use]
+pub fn preset_name(path: &Path) -> String {
+    path.file_stem()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// A tunable shader parameter.
+#[derive(Debug, Clone, PartialEq)]
+#[must_use]
+pub struct Parameter {
+    pub name: String,
+    pub label: String,
+    pub default: f32,
+    pub min: f32,
+    pub max: f32,
+    pub step: f32,
+}
+
+impl Parameter {
+    /// Parse a `#pragma parameter NAME "Label" default min max [step]` declaration.
+    fn parse(line: &str) -> Option<anyhow::Result<Self>> {
+        let declaration = line
+            .trim()
+            .strip_prefix("//")?
+            .trim()
+            .strip_prefix("#pragma parameter")?
+            .trim();
+        Some(
+            Self::parse_declaration(declaration)
+                .with_context(|| format!("invalid parameter declaration: {declaration:?}")),
+        )
+    }
+
+    fn parse_declaration(declaration: &str) -> anyhow::Result<Self> {
+        let (name, rest) = declaration
+            .split_once(char::is_whitespace)
+            .context("missing label")?;
+        let (label, values) = rest
+            .trim()
+            .strip_prefix('"')
+            .and_then(|rest| rest.split_once('"'))
+            .context("missing quoted label")?;
+        let values = values
+            .split_whitespace()
+            .map(str::parse::<f32>)
+            .collect::<Result<Vec<_>, _>>()?;
+        let (default, min, max, step) = match values[..] {
+            [default, min, max] => (default, min, max, 0.01),
+            [default, min, max, step] => (default, min, max, step),
+            _ => bail!("expected default, min, max and optional step values"),
+        };
+        Ok(Self {
+            name: name.to_string(),
+            label: label.to_string(),
+            default,
+            min,
+            max,
+            step,
+        })
+    }
+}
+
+/// A single pass of a [`Preset`].
+#[derive(Debug, Clone)]
+#[must_use]
+struct PassDesc {
+    path: PathBuf,
+    /// WGSL source, including the [`PRELUDE`].
+    source: String,
+    scale: f32,
+    filter_linear: bool,
+    float_framebuffer: bool,
+    parameters: Vec<Parameter>,
+}
+
+/// A chain of shader passes loaded from a preset file.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct Preset {
+    path: PathBuf,
+    passes: Vec<PassDesc>,
+    output_filter_linear: bool,
+    overrides: HashMap<String, f32>,
+}
+
+impl Preset {
+    /// Load and validate a preset and its shaders.
+    ///
+    /// # Errors
+    ///
+    /// If the preset or any shader fails to load, parse or validate, then an error is returned.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read shader preset {path:?}"))?;
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let mut values = HashMap::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .with_context(|| format!("invalid shader preset line: {line:?}"))?;
+            values.insert(
+                key.trim().to_string(),
+                value.trim().trim_matches('"').to_string(),
+            );
+        }
+
+        let parse_bool = |key: &str| match values.get(key).map(String::as_str) {
+            None => Ok(false),
+            Some("true" | "1") => Ok(true),
+            Some("false" | "0") => Ok(false),
+            Some(value) => Err(anyhow::anyhow!("invalid value for `{key}`: {value:?}")),
+        };
+
+        let count = values
+            .get("shaders")
+            .context("shader preset is missing `shaders`")?
+            .parse::<usize>()
+            .context("invalid value for `shaders`")?;
+        if count == 0 {
+            bail!("shader preset has no shaders");
+        }
+        let mut passes = Vec::with_capacity(count);
+        for index in 0..count {
+            let shader_key = format!("shader{index}");
+            let shader_path = dir.join(
+                values
+                    .get(&shader_key)
+                    .with_context(|| format!("shader preset is missing `{shader_key}`"))?,
+            );
+            let scale_key = format!("scale{index}");
+            let scale = match values.get(&scale_key) {
+                Some(scale) => scale
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|scale| *scale > 0.0)
+                    .with_context(|| format!("invalid value for `{scale_key}`: {scale:?}"))?,
+                None => 1.0,
+            };
+            passes.push(PassDesc::load(
+                shader_path,
+                scale,
+                parse_bool(&format!("filter_linear{index}"))?,
+                parse_bool(&format!("float_framebuffer{index}"))?,
+            )?);
+        }
+        let output_filter_linear = parse_bool(&format!("filter_linear{count}"))?;
+
+        // Remaining numeric values override parameter defaults
+        let overrides = values
+            .iter()
+            .filter_map(|(key, value)| Some((key.clone(), value.parse::<f32>().ok()?)))
+            .filter(|(key, _)| {
+                passes
+                    .iter()
+                    .any(|pass| pass.parameters.iter().any(|param| &param.name == key))
+            })
+            .collect();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            passes,
+            output_filter_linear,
+            overrides,
+        })
+    }
+
+    /// The built-in CRT preset, drawing scanlines and screen curvature at 4x the frame size.
+    ///
+    /// # Errors
+    ///
+    /// If the built-in shader fails to validate, then an error is returned.
+    pub fn crt() -> anyhow::Result<Self> {
+        Ok(Self {
+            path: PathBuf::from(CRT_PRESET),
+            passes: vec![PassDesc::new(
+                PathBuf::from("crt.wgsl"),
+                CRT_SHADER,
+                4.0,
+                true,
+                false,
+            )?],
+            output_filter_linear: true,
+            overrides: HashMap::new(),
+        })
+    }
+
+    /// Path the preset was loaded from.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Display name of the preset.
+    #[must_use]
+    pub fn name(&self) -> String {
+        preset_name(&self.path)
+    }
+
+    /// Returns the parameters declared by all passes, with defaults overridden by the preset.
+    /// Parameters declared by multiple passes are only returned once.
+    pub fn parameters(&self) -> Vec<Parameter> {
+        let mut parameters: Vec<Parameter> = Vec::new();
+        for param in self.passes.iter().flat_map(|pass| &pass.parameters) {
+            if parameters.iter().all(|p| p.name != param.name) {
+                let mut param = param.clone();
+                if let Some(value) = self.overrides.get(&param.name) {
+                    param.default = *value;
+                }
+                parameters.push(param);
+            }
+        }
+        parameters
+    }
+
+    fn value(&self, param: &Parameter, values: &BTreeMap<String, f32>) -> f32 {
+        values
+            .get(&param.name)
+            .or_else(|| self.overrides.get(&param.name))
+            .copied()
+            .unwrap_or(param.default)
+            .clamp(param.min.min(param.max), param.max.max(param.min))
+    }
+}
+
+impl PassDesc {
+    fn load(
+        path: PathBuf,
+        scale: f32,
+        filter_linear: bool,
+        float_framebuffer: bool,
+    ) -> anyhow::Result<Self> {
+        let shader = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read shader {path:?}"))?;
+        Self::new(path, &shader, scale, filter_linear, float_framebuffer)
+    }
+
+    fn new(
+        path: PathBuf,
+        shader: &str,
+        scale: f32,
+        filter_linear: bool,
+        float_framebuffer: bool,
+    ) -> anyhow::Result<Self> {
+        let parameters = shader
+            .lines()
+            .filter_map(Parameter::parse)
+            .collect::<anyhow::Result<Vec<_>>>()
+            .with_context(|| format!("invalid shader {path:?}"))?;
+        if parameters.len() > MAX_PARAMETERS {
+            bail!("shader {path:?} declares more than {MAX_PARAMETERS} parameters");
+        }
+        let source = format!("{PRELUDE}\n{shader}");
+        Self::validate(&source).with_context(|| format!("invalid shader {path:?}"))?;
+        Ok(Self {
+            path,
+            source,
+            scale,
+            filter_linear,
+            float_framebuffer,
+            parameters,
+        })
+    }
+
+    /// Validate the WGSL source up front, as invalid shaders passed to wgpu are fatal errors.
+    fn validate(source: &str) -> anyhow::Result<()> {
+        use naga::valid::{Capabilities, ValidationFlags, Validator};
+
+        let module = naga::front::wgsl::parse_str(source)
+            .map_err(|err| anyhow::anyhow!(err.emit_to_string(source)))?;
+        Validator::new(ValidationFlags::all(), Capabilities::empty())
+            .validate(&module)
+            .map_err(|err| anyhow::anyhow!(err.emit_to_string(source)))?;
+        let has_fragment = module
+            .entry_points
+            .iter()
+            .any(|entry| entry.name == "fs_main" && entry.stage == naga::ShaderStage::Fragment);
+        if !has_fragment {
+            bail!("missing `fs_main` fragment entry point");
+        }
+        Ok(())
+    }
+
+    const fn format(&self) -> wgpu::TextureFormat {
+        if self.float_framebuffer {
+            wgpu::TextureFormat::Rgba16Float
+        } else {
+            wgpu::TextureFormat::Rgba8UnormSrgb
+        }
+    }
+}
+
+#[derive(Debug)]
+#[must_use]
+struct Pass {
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    uniforms: wgpu::Buffer,
+    // Kept alive for `view`
+    _texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    source_size: [u32; 2],
+    output_size: [u32; 2],
+}
+
+/// GPU resources for rendering a [`Preset`] from the NES frame texture into an output texture
+/// drawn by egui.
+#[derive(Debug)]
+#[must_use]
+pub struct ShaderChain {
+    preset: Preset,
+    passes: Vec<Pass>,
+    original_size: [u32; 2],
+    output_id: TextureId,
+    frame_count: u32,
+    last_values: Option<BTreeMap<String, f32>>,
+}
+
+impl ShaderChain {
+    /// Create the render pipelines for `preset`, reading from the `source` texture.
+    ///
+    /// # Errors
+    ///
+    /// If the pipelines fail to be created, then an error is returned.
+    pub fn new(
+        device: &wgpu::Device,
+        renderer: &mut egui_wgpu::Renderer,
+        preset: Preset,
+        source: &Texture,
+    ) -> anyhow::Result<Self> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("shader preset"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                Self::texture_layout_entry(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                Self::texture_layout_entry(3),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("shader preset"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let max_size = device.limits().max_texture_dimension_2d;
+        let original_size = [source.size.width, source.size.height];
+        let mut passes: Vec<Pass> = Vec::with_capacity(preset.passes.len());
+        for desc in &preset.passes {
+            let label = desc.path.to_str();
+            let (source_view, source_size) =
+                passes.last().map_or((&source.view, original_size), |pass| {
+                    (&pass.view, pass.output_size)
+                });
+            let output_size = source_size
+                .map(|size| ((size as f32 * desc.scale).round() as u32).clamp(1, max_size));
+
+            let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label,
+                source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(&desc.source)),
+            });
+            let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label,
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &module,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &module,
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: desc.format(),
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            });
+
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label,
+                size: wgpu::Extent3d {
+                    width: output_size[0],
+                    height: output_size[1],
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: desc.format(),
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+                label,
+                size: (UNIFORM_WORDS * 4) as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let sampler = device.create_sampler(&Self::sampler_descriptor(desc.filter_linear));
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label,
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniforms.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(source_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::TextureView(&source.view),
+                    },
+                ],
+            });
+
+            passes.push(Pass {
+                pipeline,
+                bind_group,
+                uniforms,
+                _texture: texture,
+                view,
+                source_size,
+                output_size,
+            });
+        }
+
+        let error = device.pop_error_scope();
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(err) = pollster::block_on(error) {
+            bail!("failed to create shader pipelines: {err}");
+        }
+        // Wasm can't block, so errors are only logged by wgpu
+        #[cfg(target_arch = "wasm32")]
+        drop(error);
+
+        let output = &passes.last().context("shader preset has no passes")?.view;
+        let output_id = renderer.register_native_texture_with_sampler_options(
+            device,
+            output,
+            Self::sampler_descriptor(preset.output_filter_linear),
+        );
+
+        Ok(Self {
+            preset,
+            passes,
+            original_size,
+            output_id,
+            frame_count: 0,
+            last_values: None,
+        })
+    }
+
+    const fn texture_layout_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+        wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        }
+    }
+
+    fn sampler_descriptor(filter_linear: bool) -> wgpu::SamplerDescriptor<'static> {
+        let filter = if filter_linear {
+            wgpu::FilterMode::Linear
+        } else {
+            wgpu::FilterMode::Nearest
+        };
+        wgpu::SamplerDescriptor {
+            label: Some("shader preset sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        }
+    }
+
+    /// The preset being rendered.
+    pub const fn preset(&self) -> &Preset {
+        &self.preset
+    }
+
+    /// Returns the output texture, displayed at the same size as the `source` texture.
+    pub fn sized_texture(&self, source: &Texture) -> SizedTexture {
+        SizedTexture::new(self.output_id, source.sized_texture().size)
+    }
+
+    /// Free the output texture registered with egui.
+    pub fn destroy(self, renderer: &mut egui_wgpu::Renderer) {
+        renderer.free_texture(&self.output_id);
+    }
+
+    /// Render all passes if a new frame was uploaded to the source texture or parameter `values`
+    /// changed.
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        values: &BTreeMap<String, f32>,
+        new_frame: bool,
+    ) {
+        #[cfg(feature = "profiling")]
+        puffin::profile_function!();
+
+        let values_changed = self.last_values.as_ref() != Some(values);
+        if !new_frame && !values_changed {
+            return;
+        }
+        if new_frame {
+            self.frame_count = self.frame_count.wrapping_add(1);
+        }
+        if values_changed {
+            self.last_values = Some(values.clone());
+        }
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("shader preset"),
+        });
+        for (pass, desc) in self.passes.iter().zip(&self.preset.passes) {
+            let mut uniforms = [0.0f32; UNIFORM_WORDS];
+            uniforms[0..4].copy_from_slice(&size_vec(pass.source_size));
+            uniforms[4..8].copy_from_slice(&size_vec(self.original_size));
+            uniforms[8..12].copy_from_slice(&size_vec(pass.output_size));
+            uniforms[12] = f32::from_bits(self.frame_count);
+            for (value, param) in uniforms[16..].iter_mut().zip(&desc.parameters) {
+                *value = self.preset.value(param, values);
+            }
+            queue.write_buffer(&pass.uniforms, 0, bytemuck::cast_slice(&uniforms));
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("shader preset"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &pass.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_pipeline(&pass.pipeline);
+            render_pass.set_bind_group(0, &pass.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+        queue.submit(Some(encoder.finish()));
+    }
+}
+
+/// Size as `vec4(width, height, 1 / width, 1 / height)`.
+fn size_vec([width, height]: [u32; 2]) -> [f32; 4] {
+    let (width, height) = (width as f32, height as f32);
+    [width, height, width.recip(), height.recip()]
+}
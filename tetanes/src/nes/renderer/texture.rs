@@ -9,6 +9,8 @@ pub struct Texture {
     pub size: wgpu::Extent3d,
     pub view: wgpu::TextureView,
     pub aspect_ratio: f32,
+    /// Factor the texture is upscaled by, which isn't reflected in its displayed size.
+    pub scale: u32,
 }
 
 impl Texture {
@@ -17,12 +19,13 @@ impl Texture {
         renderer: &mut egui_wgpu::Renderer,
         width: u32,
         height: u32,
+        scale: u32,
         aspect_ratio: f32,
         label: Option<&'static str>,
     ) -> Self {
         let size = wgpu::Extent3d {
-            width,
-            height,
+            width: width * scale,
+            height: height * scale,
             depth_or_array_layers: 1,
         };
         let texture = device.create_texture(&wgpu::TextureDescriptor {
@@ -64,6 +67,7 @@ impl Texture {
             size,
             view,
             aspect_ratio,
+            scale,
             id,
         }
     }
@@ -74,23 +78,37 @@ impl Texture {
         renderer: &mut egui_wgpu::Renderer,
         width: u32,
         height: u32,
+        scale: u32,
         aspect_ratio: f32,
     ) {
         renderer.free_texture(&self.id);
-        *self = Self::new(device, renderer, width, height, aspect_ratio, self.label);
+        *self = Self::new(
+            device,
+            renderer,
+            width,
+            height,
+            scale,
+            aspect_ratio,
+            self.label,
+        );
     }
 
     pub fn sized_texture(&self) -> SizedTexture {
         SizedTexture::new(
             self.id,
             Vec2 {
-                x: self.size.width as f32 * self.aspect_ratio,
-                y: self.size.height as f32,
+                x: (self.size.width / self.scale) as f32 * self.aspect_ratio,
+                y: (self.size.height / self.scale) as f32,
             },
         )
     }
 
+    /// Upload `bytes` to the texture, skipping them if their size doesn't match, e.g. for frames
+    /// sent before a filter change resized the texture.
     pub fn update(&self, queue: &wgpu::Queue, bytes: &[u8]) {
+        if bytes.len() != (4 * self.size.width * self.size.height) as usize {
+            return;
+        }
         queue.write_texture(
             wgpu::ImageCopyTexture {
                 aspect: wgpu::TextureAspect::All,
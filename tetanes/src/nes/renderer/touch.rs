@@ -0,0 +1,227 @@
+//! On-screen virtual controller drawn over the NES frame for touch screens, e.g. in mobile
+//! browsers without a keyboard or gamepad.
+
+use crate::nes::{
+    config::{Config, TouchControllerConfig, TouchControllerMode},
+    event::{EmulationEvent, NesEvent, SendNesEvent},
+};
+use egui::{
+    Align2, Color32, Context, Event, FontId, Id, LayerId, Order, Painter, Pos2, Rect, Rounding,
+    Stroke, TouchId, TouchPhase, Vec2,
+};
+use std::collections::BTreeMap;
+use tetanes_core::input::{JoypadBtn, Player};
+use winit::{event::ElementState, event_loop::EventLoopProxy};
+
+/// Positions of each button, scaled to the area the controller is drawn in.
+#[derive(Debug, Copy, Clone)]
+struct ButtonLayout {
+    /// Size the layout is relative to, roughly the radius of the A and B buttons.
+    unit: f32,
+    dpad: Pos2,
+    a: Pos2,
+    b: Pos2,
+    select: Rect,
+    start: Rect,
+}
+
+impl ButtonLayout {
+    fn new(rect: Rect, cfg: &TouchControllerConfig) -> Self {
+        let unit = 0.1 * rect.width().min(rect.height()) * cfg.size;
+        let bottom = rect.bottom() - 1.8 * unit;
+        let (dpad_x, ab_x) = if cfg.left_handed {
+            (rect.right() - 1.8 * unit, rect.left() + 1.8 * unit)
+        } else {
+            (rect.left() + 1.8 * unit, rect.right() - 1.8 * unit)
+        };
+        let center_x = rect.center().x;
+        let button_size = Vec2::new(1.4 * unit, 0.5 * unit);
+        Self {
+            unit,
+            dpad: Pos2::new(dpad_x, bottom),
+            a: Pos2::new(ab_x + 0.6 * unit, bottom - 0.4 * unit),
+            b: Pos2::new(ab_x - 0.9 * unit, bottom + 0.4 * unit),
+            select: Rect::from_center_size(
+                Pos2::new(center_x - 0.9 * unit, rect.bottom() - 0.6 * unit),
+                button_size,
+            ),
+            start: Rect::from_center_size(
+                Pos2::new(center_x + 0.9 * unit, rect.bottom() - 0.6 * unit),
+                button_size,
+            ),
+        }
+    }
+
+    /// Add the buttons pressed by a finger at `pos`. The D-Pad is split into 8 directions so
+    /// diagonals can be pressed with a single finger.
+    fn buttons_at(&self, pos: Pos2, pressed: &mut Vec<JoypadBtn>) {
+        let unit = self.unit;
+        let offset = pos - self.dpad;
+        if (0.25 * unit..=1.6 * unit).contains(&offset.length()) {
+            // sin(22.5°), splitting the D-Pad into 45° sectors
+            const DIAGONAL: f32 = 0.38;
+            let dir = offset.normalized();
+            for (pressed_dir, button) in [
+                (dir.x < -DIAGONAL, JoypadBtn::Left),
+                (dir.x > DIAGONAL, JoypadBtn::Right),
+                (dir.y < -DIAGONAL, JoypadBtn::Up),
+                (dir.y > DIAGONAL, JoypadBtn::Down),
+            ] {
+                if pressed_dir {
+                    pressed.push(button);
+                }
+            }
+        }
+        for (center, button) in [(self.a, JoypadBtn::A), (self.b, JoypadBtn::B)] {
+            if pos.distance(center) <= 0.8 * unit {
+                pressed.push(button);
+            }
+        }
+        for (rect, button) in [
+            (self.select, JoypadBtn::Select),
+            (self.start, JoypadBtn::Start),
+        ] {
+            if rect.expand(0.2 * unit).contains(pos) {
+                pressed.push(button);
+            }
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use]
+pub struct TouchController {
+    /// Set once the screen is touched, to only show the controller on touch screens by default.
+    touched: bool,
+    /// Position of each finger on the screen.
+    touches: BTreeMap<TouchId, Pos2>,
+    /// Buttons currently held down.
+    pressed: Vec<JoypadBtn>,
+}
+
+impl TouchController {
+    /// Draw the controller over `rect` and send button changes from the fingers touching it as
+    /// player one joypad input.
+    pub fn show(&mut self, ctx: &Context, rect: Rect, cfg: &Config, tx: &EventLoopProxy<NesEvent>) {
+        let touch_cfg = cfg.renderer.touch_controller;
+        let pointer = ctx.input(|i| {
+            for event in &i.events {
+                if let Event::Touch { id, phase, pos, .. } = event {
+                    self.touched = true;
+                    match phase {
+                        TouchPhase::Start | TouchPhase::Move => {
+                            self.touches.insert(*id, *pos);
+                        }
+                        TouchPhase::End | TouchPhase::Cancel => {
+                            self.touches.remove(id);
+                        }
+                    }
+                }
+            }
+            // Allows trying the controller out with a mouse
+            i.pointer
+                .primary_down()
+                .then(|| i.pointer.interact_pos())
+                .flatten()
+        });
+
+        let visible = match touch_cfg.mode {
+            TouchControllerMode::Auto => self.touched,
+            TouchControllerMode::Always => true,
+            TouchControllerMode::Never => false,
+        };
+        let layout = ButtonLayout::new(rect, &touch_cfg);
+        let mut pressed = Vec::new();
+        if visible {
+            for pos in pointer.into_iter().chain(self.touches.values().copied()) {
+                layout.buttons_at(pos, &mut pressed);
+            }
+        }
+
+        for &button in &self.pressed {
+            if !pressed.contains(&button) {
+                Self::send(tx, cfg, button, ElementState::Released);
+            }
+        }
+        for &button in &pressed {
+            if !self.pressed.contains(&button) {
+                Self::send(tx, cfg, button, ElementState::Pressed);
+            }
+        }
+        self.pressed = pressed;
+
+        if visible {
+            let painter = ctx.layer_painter(LayerId::new(Order::Foreground, Id::new("touch")));
+            self.paint(&painter, &layout, touch_cfg.opacity);
+        }
+    }
+
+    fn send(tx: &EventLoopProxy<NesEvent>, cfg: &Config, button: JoypadBtn, state: ElementState) {
+        let button = cfg.renderer.orientation.remap(button);
+        tx.nes_event(EmulationEvent::Joypad((Player::One, button, state)));
+    }
+
+    fn paint(&self, painter: &Painter, layout: &ButtonLayout, opacity: f32) {
+        let unit = layout.unit;
+        let fill = |button| {
+            if self.pressed.contains(&button) {
+                Color32::from_gray(200).gamma_multiply(opacity)
+            } else {
+                Color32::from_gray(40).gamma_multiply(opacity)
+            }
+        };
+        let stroke = Stroke::new(2.0, Color32::WHITE.gamma_multiply(opacity));
+        let text_color = Color32::WHITE.gamma_multiply(opacity);
+        let font = FontId::proportional(0.6 * unit);
+
+        let arm = Vec2::splat(0.8 * unit);
+        let dpad_fill = Color32::from_gray(40).gamma_multiply(opacity);
+        for size in [Vec2::new(2.4 * unit, arm.y), Vec2::new(arm.x, 2.4 * unit)] {
+            painter.rect(
+                Rect::from_center_size(layout.dpad, size),
+                Rounding::same(0.1 * unit),
+                dpad_fill,
+                stroke,
+            );
+        }
+        for (offset, button) in [
+            (Vec2::new(-0.8, 0.0), JoypadBtn::Left),
+            (Vec2::new(0.8, 0.0), JoypadBtn::Right),
+            (Vec2::new(0.0, -0.8), JoypadBtn::Up),
+            (Vec2::new(0.0, 0.8), JoypadBtn::Down),
+        ] {
+            if self.pressed.contains(&button) {
+                let rect = Rect::from_center_size(layout.dpad + offset * unit, arm);
+                painter.rect_filled(rect.shrink(2.0), Rounding::same(0.1 * unit), fill(button));
+            }
+        }
+
+        for (center, button, label) in
+            [(layout.a, JoypadBtn::A, "A"), (layout.b, JoypadBtn::B, "B")]
+        {
+            painter.circle(center, 0.6 * unit, fill(button), stroke);
+            painter.text(
+                center,
+                Align2::CENTER_CENTER,
+                label,
+                font.clone(),
+                text_color,
+            );
+        }
+
+        let font = FontId::proportional(0.3 * unit);
+        for (rect, button, label) in [
+            (layout.select, JoypadBtn::Select, "SELECT"),
+            (layout.start, JoypadBtn::Start, "START"),
+        ] {
+            painter.rect(rect, Rounding::same(0.25 * unit), fill(button), stroke);
+            painter.text(
+                rect.center(),
+                Align2::CENTER_CENTER,
+                label,
+                font.clone(),
+                text_color,
+            );
+        }
+    }
+}
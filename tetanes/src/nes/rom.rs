@@ -1,3 +1,7 @@
+pub mod patch;
+pub mod stream;
+pub mod watch;
+
 #[derive(Clone, PartialEq)]
 pub struct RomData(pub Vec<u8>);
 
@@ -0,0 +1,33 @@
+//! Soft patching ROMs with IPS, BPS or UPS patches as they're loaded, leaving the original file
+//! unchanged, e.g. to play ROM hacks and translations.
+
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+use tetanes_core::{fs, patch::Patch};
+
+/// Returns a patch file next to `path` with the same name, e.g. `Game.ips` for `Game.nes`.
+#[must_use]
+pub fn find_sibling(path: &Path) -> Option<PathBuf> {
+    Patch::EXTENSIONS
+        .iter()
+        .map(|ext| path.with_extension(ext))
+        .find(|patch| patch.is_file())
+}
+
+/// Apply the patch at `patch_path` to `rom`, returning the name to load the patched ROM as so it
+/// doesn't share save states with the original.
+///
+/// # Errors
+///
+/// If the patch can't be read, is invalid or doesn't match `rom`, then an error is returned and
+/// `rom` is left unchanged.
+pub fn apply(name: &str, rom: &mut Vec<u8>, patch_path: &Path) -> anyhow::Result<String> {
+    let patch_name = fs::filename(patch_path);
+    let patch = std::fs::read(patch_path)
+        .with_context(|| format!("failed to read patch {patch_path:?}"))?;
+    let patch = Patch::parse(&patch).with_context(|| format!("invalid patch {patch_name}"))?;
+    *rom = patch
+        .apply(rom)
+        .with_context(|| format!("failed to apply patch {patch_name}"))?;
+    Ok(format!("{name} + {patch_name}"))
+}
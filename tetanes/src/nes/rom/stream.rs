@@ -0,0 +1,254 @@
+//! Reading ROM files from slow or unreliable storage, like network shares or SD cards over USB.
+//!
+//! ROMs are read in chunks on a background thread, retrying failed reads with an increasing delay
+//! and reporting progress to the renderer. The whole ROM is kept in memory once read, so I/O
+//! errors afterwards can't affect emulation.
+
+use crate::nes::{
+    event::{EmulationEvent, NesEvent, RendererEvent, SendNesEvent, UiEvent},
+    renderer::gui::MessageType,
+    rom::{patch, RomData},
+};
+use anyhow::{bail, Context};
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tetanes_core::fs;
+use tracing::{error, warn};
+use winit::event_loop::EventLoopProxy;
+
+/// Size of each chunk read, so progress can be reported for large or slow files.
+const CHUNK_SIZE: usize = 64 * 1024;
+/// Number of times a failed read is retried before giving up.
+const MAX_RETRIES: u32 = 5;
+/// Delay before the first retry, doubling after each failed attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Progress reading a ROM file.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct ReadProgress {
+    pub name: String,
+    /// Bytes read so far.
+    pub read: u64,
+    /// Size of the file in bytes, if known.
+    pub total: Option<u64>,
+    /// Number of failed reads retried so far.
+    pub retries: u32,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ReadProgress {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            read: 0,
+            total: None,
+            retries: 0,
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Fraction of the file read, if its size is known.
+    #[must_use]
+    pub fn fraction(&self) -> Option<f32> {
+        self.total
+            .filter(|&total| total > 0)
+            .map(|total| (self.read as f64 / total as f64) as f32)
+    }
+
+    /// Stop reading the file. The ROM isn't loaded.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// A ROM file being read on a background thread.
+#[derive(Debug)]
+#[must_use]
+pub struct RomReader {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl RomReader {
+    /// Read the ROM at `path` on a background thread, sending [`RendererEvent::RomReadProgress`]
+    /// while reading and [`EmulationEvent::LoadRom`] once finished. `patch`, or a patch file next
+    /// to the ROM with the same name if `auto_patch` is set, is applied once read.
+    pub fn spawn(
+        path: PathBuf,
+        patch: Option<PathBuf>,
+        auto_patch: bool,
+        tx: EventLoopProxy<NesEvent>,
+    ) -> anyhow::Result<Self> {
+        let mut progress = ReadProgress::new(fs::filename(&path));
+        let cancelled = Arc::clone(&progress.cancelled);
+        std::thread::Builder::new()
+            .name("rom_reader".into())
+            .spawn(move || {
+                let res = read(&path, &mut progress, |progress| {
+                    tx.nes_event(RendererEvent::RomReadProgress(Some(progress.clone())));
+                });
+                tx.nes_event(RendererEvent::RomReadProgress(None));
+                match res {
+                    Ok(mut data) => {
+                        let name = progress.name;
+                        if let Some(name) =
+                            Self::apply_patch(&path, patch, auto_patch, name, &mut data, &tx)
+                        {
+                            tx.nes_event(EmulationEvent::LoadRom((name, RomData(data))));
+                        }
+                    }
+                    Err(_) if progress.cancelled.load(Ordering::Relaxed) => (),
+                    Err(err) => {
+                        error!("failed to read rom {path:?}: {err:?}");
+                        tx.nes_event(UiEvent::Error(format!(
+                            "failed to read {}: {err}",
+                            progress.name
+                        )));
+                    }
+                }
+            })
+            .context("failed to spawn rom reader thread")?;
+        Ok(Self { cancelled })
+    }
+
+    /// Stop reading the file. The ROM isn't loaded.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Apply `patch`, or a patch file next to the ROM if `auto_patch` is set, returning the name
+    /// to load the ROM as. A patch given explicitly that fails to apply returns `None` so the ROM
+    /// isn't loaded, while the original ROM is loaded if a detected patch fails to apply.
+    fn apply_patch(
+        path: &Path,
+        patch: Option<PathBuf>,
+        auto_patch: bool,
+        name: String,
+        data: &mut Vec<u8>,
+        tx: &EventLoopProxy<NesEvent>,
+    ) -> Option<String> {
+        let explicit = patch.is_some();
+        let patch_path = patch.or_else(|| auto_patch.then(|| patch::find_sibling(path)).flatten());
+        let Some(patch_path) = patch_path else {
+            return Some(name);
+        };
+        match patch::apply(&name, data, &patch_path) {
+            Ok(patched) => {
+                tx.nes_event(UiEvent::Message((
+                    MessageType::Info,
+                    format!("Applied patch {}", fs::filename(&patch_path)),
+                )));
+                Some(patched)
+            }
+            Err(err) => {
+                error!("failed to patch rom {path:?}: {err:?}");
+                if explicit {
+                    tx.nes_event(UiEvent::Error(format!("{err:#}")));
+                    None
+                } else {
+                    tx.nes_event(UiEvent::Message((
+                        MessageType::Warn,
+                        format!("{err:#}. Loading the original ROM."),
+                    )));
+                    Some(name)
+                }
+            }
+        }
+    }
+}
+
+/// Read the file at `path` in chunks, calling `on_progress` after each chunk. Failed reads are
+/// retried from where they left off, reopening the file in case the handle became invalid.
+///
+/// # Errors
+///
+/// If the file is missing or not readable, a read still fails after retrying, or reading was
+/// cancelled, then an error is returned.
+pub fn read(
+    path: &Path,
+    progress: &mut ReadProgress,
+    mut on_progress: impl FnMut(&ReadProgress),
+) -> anyhow::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    let mut chunk = vec![0x00; CHUNK_SIZE];
+    let mut file: Option<File> = None;
+    let mut attempts = 0;
+    loop {
+        if progress.cancelled.load(Ordering::Relaxed) {
+            bail!("cancelled reading {path:?}");
+        }
+        let res = match &mut file {
+            Some(file) => file.read(&mut chunk),
+            None => match open(path, data.len() as u64) {
+                Ok((opened, total)) => {
+                    progress.total = progress.total.or(total);
+                    file = Some(opened);
+                    continue;
+                }
+                Err(err) => Err(err),
+            },
+        };
+        let err = match res {
+            Ok(0) => match progress.total {
+                // Reads ending early may be a transient failure, so retry them
+                Some(total) if progress.read < total => io::ErrorKind::UnexpectedEof.into(),
+                _ => return Ok(data),
+            },
+            Ok(len) => {
+                data.extend_from_slice(&chunk[..len]);
+                progress.read = data.len() as u64;
+                attempts = 0;
+                on_progress(progress);
+                continue;
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) if is_permanent(&err) => {
+                return Err(err).with_context(|| format!("failed to read {path:?}"));
+            }
+            Err(err) => err,
+        };
+
+        if attempts == MAX_RETRIES {
+            return Err(err)
+                .with_context(|| format!("failed to read {path:?} after {MAX_RETRIES} retries"));
+        }
+        let backoff = INITIAL_BACKOFF * 2u32.pow(attempts);
+        attempts += 1;
+        progress.retries += 1;
+        warn!(
+            "failed to read {path:?}, retrying in {backoff:?} ({attempts}/{MAX_RETRIES}): {err:?}"
+        );
+        on_progress(progress);
+        std::thread::sleep(backoff);
+        file = None;
+    }
+}
+
+/// Open the file at `path`, seeking to `offset`. Returns the file and its size, if known.
+fn open(path: &Path, offset: u64) -> io::Result<(File, Option<u64>)> {
+    let mut file = File::open(path)?;
+    let total = file.metadata().ok().map(|metadata| metadata.len());
+    if offset > 0 {
+        file.seek(SeekFrom::Start(offset))?;
+    }
+    Ok((file, total))
+}
+
+/// Whether retrying a failed read won't help.
+fn is_permanent(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::NotFound
+            | io::ErrorKind::PermissionDenied
+            | io::ErrorKind::InvalidInput
+            | io::ErrorKind::Unsupported
+    )
+}
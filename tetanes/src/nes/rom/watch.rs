@@ -0,0 +1,66 @@
+//! Live-reloading the loaded ROM when its file changes, e.g. so homebrew developers rebuilding
+//! with cc65 or asm6 see their changes without reloading by hand.
+
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tetanes_core::time::{Duration, Instant};
+
+/// Polls a ROM file for changes.
+#[derive(Debug)]
+#[must_use]
+pub struct RomWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_polled: Instant,
+    /// Set when a change was seen, to wait until the file stops changing before reloading.
+    changed: bool,
+}
+
+impl RomWatcher {
+    const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+    pub fn new(path: PathBuf) -> Self {
+        let modified = Self::modified(&path);
+        Self {
+            path,
+            modified,
+            last_polled: Instant::now(),
+            changed: false,
+        }
+    }
+
+    /// Path of the watched ROM file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    /// Returns whether the file was modified and hasn't changed since last polled, so builds
+    /// still being written aren't loaded.
+    pub fn poll(&mut self) -> bool {
+        if self.last_polled.elapsed() < Self::POLL_INTERVAL {
+            return false;
+        }
+        self.last_polled = Instant::now();
+
+        // Builds may delete the file before writing it again
+        let modified = Self::modified(&self.path);
+        if modified.is_none() {
+            false
+        } else if modified != self.modified {
+            self.modified = modified;
+            self.changed = true;
+            false
+        } else {
+            std::mem::take(&mut self.changed)
+        }
+    }
+}
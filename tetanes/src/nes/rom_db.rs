@@ -0,0 +1,256 @@
+//! Updates the ROM database used to detect game regions and compatibility patches from a signed
+//! online source.
+//!
+//! No official source is published yet, so updates are only checked once a URL and the public key
+//! its databases are signed with are configured.
+//!
+//! Downloaded databases are cached in the data directory so they're available offline. Each
+//! published file, and the cached copy, is an Ed25519 signature followed by a database serialized
+//! with [`RomDatabase::to_bytes`]. The signature is verified every time a file is loaded, falling
+//! back to the bundled database if it doesn't match.
+//!
+//! A NES 2.0 XML database can also be placed in the data directory to fill in the headers of
+//! `iNES` ROMs. See [`tetanes_core::cart_db`].
+
+use crate::nes::{config::Config, event::NesEvent};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tetanes_core::rom_db::RomDatabase;
+use winit::event_loop::EventLoopProxy;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::nes::{
+    event::{SendNesEvent, UiEvent},
+    renderer::gui::MessageType,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use tetanes_core::rom_db;
+
+/// ROM database update configuration.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[must_use]
+#[serde(default)] // Ensures new fields don't break existing configurations
+pub struct RomDbConfig {
+    /// Check for a newer database on startup.
+    pub auto_update: bool,
+    /// Only install this database version instead of the latest.
+    pub pinned_version: Option<u32>,
+    /// Base URL serving `latest`, containing the latest version number, and
+    /// `rom_db-<version>.dat` files.
+    pub url: Option<String>,
+    /// Hex-encoded Ed25519 public key database files are signed with.
+    pub public_key: Option<String>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl RomDbConfig {
+    /// Whether `version` should be installed over the bundled database.
+    const fn accepts(&self, version: u32) -> bool {
+        match self.pinned_version {
+            Some(pinned) => version == pinned,
+            None => version > RomDatabase::BUNDLED_VERSION,
+        }
+    }
+
+    /// Parse the configured [`RomDbConfig::public_key`].
+    fn public_key(&self) -> anyhow::Result<[u8; 32]> {
+        use anyhow::Context;
+
+        let key = self
+            .public_key
+            .as_deref()
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .context("no rom database public key configured")?;
+        anyhow::ensure!(
+            key.len() == 64 && key.is_ascii(),
+            "rom database public key must be 64 hex digits"
+        );
+        let mut bytes = [0; 32];
+        for (byte, digits) in bytes.iter_mut().zip(key.as_bytes().chunks_exact(2)) {
+            // Checked to be ASCII above
+            let digits = std::str::from_utf8(digits)?;
+            *byte = u8::from_str_radix(digits, 16)
+                .with_context(|| format!("invalid rom database public key digits: {digits:?}"))?;
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const SIGNATURE_LEN: usize = 64;
+const CACHE_FILENAME: &str = "rom_db.dat";
+const CART_DB_FILENAME: &str = "nes20db.xml";
+
+/// Path to the cached ROM database.
+#[must_use]
+pub fn cache_path() -> Option<PathBuf> {
+    Config::default_data_dir().map(|dir| dir.join(CACHE_FILENAME))
+}
+
+/// Verify the signature of a ROM database file with `public_key` and parse it.
+///
+/// # Errors
+///
+/// If the signature doesn't match or the database is invalid, then an error is returned.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn verify(data: &[u8], public_key: &[u8; 32]) -> anyhow::Result<RomDatabase> {
+    use anyhow::Context;
+    use ed25519_dalek::{Signature, VerifyingKey};
+
+    anyhow::ensure!(data.len() > SIGNATURE_LEN, "rom database is truncated");
+    let (signature, database) = data.split_at(SIGNATURE_LEN);
+    let signature = Signature::from_bytes(signature.try_into()?);
+    VerifyingKey::from_bytes(public_key)?
+        .verify_strict(database, &signature)
+        .context("invalid rom database signature")?;
+    Ok(RomDatabase::from_bytes(database)?)
+}
+
+/// Install the cached ROM database, if it's valid and accepted by `cfg`. Called on startup before
+/// any ROM is loaded.
+pub fn load_cached(cfg: &RomDbConfig) {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let Some(path) = cache_path().filter(|path| path.exists()) else {
+            return;
+        };
+        match cfg.public_key().and_then(|public_key| {
+            let data = std::fs::read(&path)?;
+            verify(&data, &public_key)
+        }) {
+            Ok(database) if cfg.accepts(database.version()) => rom_db::install(database),
+            Ok(database) => tracing::debug!(
+                "ignoring cached rom database version {}",
+                database.version()
+            ),
+            Err(err) => tracing::warn!("failed to load cached rom database {path:?}: {err:?}"),
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = cfg;
+}
+
+/// Path to the NES 2.0 XML database, which users provide.
+#[must_use]
+pub fn cart_db_path() -> Option<PathBuf> {
+    Config::default_data_dir().map(|dir| dir.join(CART_DB_FILENAME))
+}
+
+/// Install the NES 2.0 XML database from [`cart_db_path`], if present. Called on startup before
+/// any ROM is loaded.
+pub fn load_cart_db() {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use tetanes_core::cart_db::{self, CartDb};
+
+        let Some(path) = cart_db_path().filter(|path| path.exists()) else {
+            return;
+        };
+        match std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|xml| Ok(CartDb::parse(&xml)?))
+        {
+            Ok(database) => cart_db::install(database),
+            Err(err) => tracing::warn!("failed to load cart database {path:?}: {err:?}"),
+        }
+    }
+}
+
+/// Check for a newer ROM database in the background, installing and caching it if found. Failures
+/// keep using the cached or bundled database. When `notify` is set, the result is shown as a
+/// message even if the database is already up to date.
+pub fn spawn_update(cfg: &RomDbConfig, tx: EventLoopProxy<NesEvent>, notify: bool) {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let cfg = cfg.clone();
+        let res = std::thread::Builder::new()
+            .name("rom_db_update".into())
+            .spawn(move || {
+                let message = match update(&cfg) {
+                    Ok(Some(version)) => Some((
+                        MessageType::Info,
+                        format!("Updated ROM database to version {version}."),
+                    )),
+                    Ok(None) => notify.then(|| {
+                        (
+                            MessageType::Info,
+                            format!(
+                                "ROM database version {} is up to date.",
+                                rom_db::active().version()
+                            ),
+                        )
+                    }),
+                    Err(err) => {
+                        tracing::warn!("failed to update rom database: {err:?}");
+                        notify.then(|| {
+                            (
+                                MessageType::Error,
+                                "Failed to update ROM database.".to_string(),
+                            )
+                        })
+                    }
+                };
+                if let Some(message) = message {
+                    tx.nes_event(UiEvent::Message(message));
+                }
+            });
+        if let Err(err) = res {
+            tracing::error!("failed to spawn rom database update thread: {err:?}");
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = (cfg, tx, notify);
+}
+
+/// Download, verify and cache the database version accepted by `cfg` if it's not already
+/// installed. Returns the installed version, if any.
+#[cfg(not(target_arch = "wasm32"))]
+fn update(cfg: &RomDbConfig) -> anyhow::Result<Option<u32>> {
+    use anyhow::Context;
+
+    let url = cfg
+        .url
+        .as_deref()
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .context("no rom database url configured")?
+        .trim_end_matches('/');
+    let public_key = cfg.public_key()?;
+    let client = reqwest::blocking::Client::builder()
+        .timeout(std::time::Duration::from_secs(30))
+        .build()?;
+    let version = match cfg.pinned_version {
+        Some(version) => version,
+        None => client
+            .get(format!("{url}/latest"))
+            .send()
+            .and_then(|res| res.error_for_status())
+            .and_then(|res| res.text())?
+            .trim()
+            .parse::<u32>()
+            .context("invalid latest rom database version")?,
+    };
+    if !cfg.accepts(version) || rom_db::active().version() == version {
+        return Ok(None);
+    }
+
+    let data = client
+        .get(format!("{url}/rom_db-{version}.dat"))
+        .send()
+        .and_then(|res| res.error_for_status())
+        .and_then(|res| res.bytes())?;
+    let database = verify(&data, &public_key)?;
+    anyhow::ensure!(
+        database.version() == version,
+        "expected rom database version {version}, found {}",
+        database.version()
+    );
+    if let Some(path) = cache_path() {
+        if let Err(err) = tetanes_core::fs::save_raw(&path, &data) {
+            tracing::warn!("failed to cache rom database {path:?}: {err:?}");
+        }
+    }
+    rom_db::install(database);
+    Ok(Some(version))
+}
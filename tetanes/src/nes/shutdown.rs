@@ -0,0 +1,72 @@
+//! Coordinated application shutdown, with a watchdog that forces the process to exit if a step
+//! hangs.
+//!
+//! Shutdown happens in order: emulation is stopped, flushing save RAM, save states and audio
+//! output, then the configuration is saved and finally GPU resources are destroyed.
+
+use parking_lot::Mutex;
+use std::sync::Arc;
+use tetanes_core::time::{Duration, Instant};
+use tracing::{debug, error};
+
+/// Shutdown progress, watched by a watchdog thread until dropped.
+#[derive(Debug)]
+#[must_use]
+pub struct Shutdown {
+    /// Description of the current step, logged if shutdown times out.
+    step: Arc<Mutex<&'static str>>,
+    /// Disarms the watchdog when dropped.
+    _done_tx: Option<crossbeam::channel::Sender<()>>,
+}
+
+impl Shutdown {
+    /// How long shutdown can take before the process is forced to exit.
+    pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Start shutting down, forcing the process to exit if not dropped within `timeout`.
+    pub fn start(timeout: Duration) -> Self {
+        debug!("shutting down");
+        let step = Arc::new(Mutex::new("starting shutdown"));
+        // Wasm can't block or spawn threads and the page outlives the event loop anyway
+        let done_tx = if cfg!(target_arch = "wasm32") {
+            None
+        } else {
+            let (done_tx, done_rx) = crossbeam::channel::bounded::<()>(0);
+            let res = std::thread::Builder::new()
+                .name("shutdown_watchdog".into())
+                .spawn({
+                    let step = Arc::clone(&step);
+                    move || {
+                        let start = Instant::now();
+                        if let Err(crossbeam::channel::RecvTimeoutError::Timeout) =
+                            done_rx.recv_timeout(timeout)
+                        {
+                            error!(
+                                "shutdown timed out after {timeout:?} while {}, forcing exit",
+                                step.lock()
+                            );
+                            std::process::exit(1);
+                        }
+                        debug!("shutdown completed in {:?}", start.elapsed());
+                    }
+                });
+            match res {
+                Ok(_) => Some(done_tx),
+                Err(err) => {
+                    error!("failed to spawn shutdown watchdog: {err:?}");
+                    None
+                }
+            }
+        };
+        Self {
+            step,
+            _done_tx: done_tx,
+        }
+    }
+
+    /// Record the step shutdown is currently on, e.g. `"stopping emulation"`.
+    pub fn step(&self, step: &'static str) {
+        debug!("shutdown: {step}");
+        *self.step.lock() = step;
+    }
+}
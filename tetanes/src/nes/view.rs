@@ -0,0 +1,261 @@
+//! Embeddable egui widget for a live emulator view.
+//!
+//! [`NesView`] owns a [`ControlDeck`] and renders its frame buffer to an egui texture, so other
+//! egui-based tools, like level or tile editors, can embed a running game. Keyboard input is only
+//! forwarded to the deck while the view has focus, which it takes when clicked.
+
+use crate::nes::config::{FrameRate, Orientation};
+use egui::{
+    load::SizedTexture, Color32, ColorImage, Context, EventFilter, Key, Pos2, Rect, Response,
+    Sense, Shape, TextureHandle, TextureOptions, Ui, Vec2, Widget,
+};
+use std::time::Duration;
+use tetanes_core::{
+    common::Regional,
+    control_deck::ControlDeck,
+    input::{JoypadBtn, Player},
+    ppu::Ppu,
+};
+use tracing::error;
+
+/// Callback receiving the audio samples generated each frame.
+pub type AudioCallback = Box<dyn FnMut(&[f32])>;
+
+/// An egui widget displaying a live [`ControlDeck`].
+///
+/// Add it with `ui.add(&mut view)`. Frames are clocked in real time while it's shown and
+/// [`NesView::set_running`] is enabled.
+#[must_use]
+pub struct NesView {
+    deck: ControlDeck,
+    texture: Option<TextureHandle>,
+    bindings: Vec<(Key, Player, JoypadBtn)>,
+    audio_callback: Option<AudioCallback>,
+    orientation: Orientation,
+    running: bool,
+    clock_accumulator: Duration,
+    last_frame_number: Option<u32>,
+}
+
+impl std::fmt::Debug for NesView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NesView")
+            .field("deck", &self.deck)
+            .field("bindings", &self.bindings)
+            .field("orientation", &self.orientation)
+            .field("running", &self.running)
+            .field("clock_accumulator", &self.clock_accumulator)
+            .field("last_frame_number", &self.last_frame_number)
+            .finish_non_exhaustive()
+    }
+}
+
+impl NesView {
+    /// Default keyboard bindings for player one, matching the default `TetaNES` bindings.
+    pub const DEFAULT_BINDINGS: [(Key, Player, JoypadBtn); 12] = [
+        (Key::Z, Player::One, JoypadBtn::A),
+        (Key::A, Player::One, JoypadBtn::TurboA),
+        (Key::X, Player::One, JoypadBtn::B),
+        (Key::S, Player::One, JoypadBtn::TurboB),
+        (Key::ArrowUp, Player::One, JoypadBtn::Up),
+        (Key::ArrowDown, Player::One, JoypadBtn::Down),
+        (Key::ArrowLeft, Player::One, JoypadBtn::Left),
+        (Key::ArrowRight, Player::One, JoypadBtn::Right),
+        (Key::W, Player::One, JoypadBtn::Select),
+        (Key::Q, Player::One, JoypadBtn::Start),
+        // Alternates since `Q` and `W` are often bound by host applications
+        (Key::Backspace, Player::One, JoypadBtn::Select),
+        (Key::Enter, Player::One, JoypadBtn::Start),
+    ];
+    /// Limit on time clocked at once, e.g. after the host application was suspended.
+    const MAX_CLOCK_TIME: Duration = Duration::from_millis(250);
+
+    /// Create a view of `deck` with the default key bindings. The view starts running.
+    pub fn new(deck: ControlDeck) -> Self {
+        Self {
+            deck,
+            texture: None,
+            bindings: Self::DEFAULT_BINDINGS.to_vec(),
+            audio_callback: None,
+            orientation: Orientation::default(),
+            running: true,
+            clock_accumulator: Duration::ZERO,
+            last_frame_number: None,
+        }
+    }
+
+    /// Returns the [`ControlDeck`] shown by this view.
+    pub const fn deck(&self) -> &ControlDeck {
+        &self.deck
+    }
+
+    /// Returns the [`ControlDeck`] shown by this view mutably, e.g. to load a ROM or inspect
+    /// memory.
+    pub fn deck_mut(&mut self) -> &mut ControlDeck {
+        &mut self.deck
+    }
+
+    /// Replace the keyboard bindings forwarded to the deck while the view has focus.
+    pub fn set_bindings(&mut self, bindings: impl IntoIterator<Item = (Key, Player, JoypadBtn)>) {
+        self.bindings = bindings.into_iter().collect();
+    }
+
+    /// Set a callback to receive the audio samples generated each frame. Without one, samples are
+    /// discarded.
+    pub fn set_audio_callback(&mut self, callback: impl FnMut(&[f32]) + 'static) {
+        self.audio_callback = Some(Box::new(callback));
+    }
+
+    /// Returns the rotation and mirroring of the displayed frame.
+    pub const fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    /// Set the rotation and mirroring of the displayed frame, e.g. for a vertically mounted
+    /// monitor. Bound D-Pad keys are remapped to match if [`Orientation::remap_dpad`] is set.
+    pub fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    /// Returns whether frames are clocked while the view is shown.
+    #[must_use]
+    pub const fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Set whether frames are clocked while the view is shown. When paused, the deck can still be
+    /// clocked manually with [`NesView::deck_mut`].
+    pub fn set_running(&mut self, running: bool) {
+        self.running = running;
+        self.clock_accumulator = Duration::ZERO;
+    }
+
+    /// Returns the texture of the last frame, sized with the region's pixel aspect ratio, to
+    /// draw it elsewhere, e.g. as a thumbnail. `None` until the view has been updated once.
+    #[must_use]
+    pub fn texture(&self) -> Option<SizedTexture> {
+        let size = Vec2::new(
+            Ppu::WIDTH as f32 * self.deck.region().aspect_ratio(),
+            Ppu::HEIGHT as f32,
+        );
+        self.texture
+            .as_ref()
+            .map(|texture| SizedTexture::new(texture.id(), size))
+    }
+
+    /// Clock frames for the time elapsed since the last update, if running, and upload the
+    /// latest frame to the texture. Called when the view is shown.
+    pub fn update(&mut self, ctx: &Context) {
+        if self.running && self.deck.is_running() {
+            let frame_duration = FrameRate::from(self.deck.region()).duration();
+            let elapsed = Duration::from_secs_f32(ctx.input(|i| i.unstable_dt).max(0.0));
+            self.clock_accumulator += elapsed.min(Self::MAX_CLOCK_TIME);
+            while self.clock_accumulator >= frame_duration {
+                self.clock_accumulator -= frame_duration;
+                if let Err(err) = self.deck.clock_frame() {
+                    error!("failed to clock frame: {err:?}");
+                    self.set_running(false);
+                    break;
+                }
+                if let Some(callback) = &mut self.audio_callback {
+                    callback(self.deck.audio_samples());
+                }
+                self.deck.clear_audio_samples();
+            }
+            ctx.request_repaint_after(frame_duration.saturating_sub(self.clock_accumulator));
+        }
+
+        let frame_number = self.deck.frame_number();
+        if self.texture.is_none() || self.last_frame_number != Some(frame_number) {
+            self.last_frame_number = Some(frame_number);
+            let (width, height) = self.deck.frame_size();
+            let image = ColorImage::from_rgba_unmultiplied(
+                [width as usize, height as usize],
+                self.deck.frame_buffer(),
+            );
+            match &mut self.texture {
+                Some(texture) => texture.set(image, TextureOptions::NEAREST),
+                None => {
+                    self.texture =
+                        Some(ctx.load_texture("nes_view", image, TextureOptions::NEAREST));
+                }
+            }
+        }
+    }
+
+    /// Forward bound keys to the deck while focused, releasing all buttons otherwise.
+    fn update_input(&mut self, ui: &Ui, focused: bool) {
+        for player in [Player::One, Player::Two, Player::Three, Player::Four] {
+            let joypad = self.deck.joypad_mut(player);
+            for &(_, _, button) in self.bindings.iter().filter(|(_, p, _)| *p == player) {
+                joypad.set_button(button, false);
+                joypad.set_button(self.orientation.remap(button), false);
+            }
+        }
+        if focused {
+            for &(key, player, button) in &self.bindings {
+                if ui.input(|i| i.key_down(key)) {
+                    self.deck
+                        .joypad_mut(player)
+                        .set_button(self.orientation.remap(button), true);
+                }
+            }
+        }
+    }
+}
+
+impl Widget for &mut NesView {
+    fn ui(self, ui: &mut Ui) -> Response {
+        self.update(ui.ctx());
+
+        let aspect_ratio = self.deck.region().aspect_ratio();
+        let frame_size = self.orientation.size(Vec2::new(
+            Ppu::WIDTH as f32 * aspect_ratio,
+            Ppu::HEIGHT as f32,
+        ));
+        let available = ui.available_size();
+        let scale = (available.x / frame_size.x)
+            .min(available.y / frame_size.y)
+            .max(1.0);
+        let (rect, res) = ui.allocate_exact_size(frame_size * scale, Sense::click());
+
+        if res.clicked() {
+            res.request_focus();
+        }
+        let focused = res.has_focus();
+        if focused {
+            // Keep arrow keys and tab from moving focus away while playing
+            ui.memory_mut(|mem| {
+                mem.set_focus_lock_filter(
+                    res.id,
+                    EventFilter {
+                        tab: true,
+                        horizontal_arrows: true,
+                        vertical_arrows: true,
+                        escape: false,
+                    },
+                );
+            });
+        }
+        self.update_input(ui, focused);
+
+        if ui.is_rect_visible(rect) {
+            match self.texture() {
+                Some(texture) => {
+                    let uv = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
+                    let mesh = self.orientation.mesh(texture.id, rect, uv);
+                    ui.painter().add(Shape::mesh(mesh));
+                }
+                None => {
+                    ui.painter().rect_filled(rect, 0.0, Color32::BLACK);
+                }
+            }
+            if focused {
+                ui.painter()
+                    .rect_stroke(rect, 0.0, ui.visuals().selection.stroke);
+            }
+        }
+
+        res
+    }
+}
@@ -1,6 +1,11 @@
-use clap::{Parser, ValueEnum};
+#[cfg(feature = "tui")]
+use crate::tui::TuiOpts;
+use crate::verify_movie::VerifyMovieOpts;
+use crate::{compat::CompatOpts, rom_info::RomInfoOpts};
+use anyhow::Context;
+use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
-use tetanes::nes::config::Config;
+use tetanes::nes::config::{Config, FileKind};
 use tetanes_core::genie::GenieCode;
 
 #[derive(Debug, Clone)]
@@ -9,7 +14,12 @@ pub(crate) struct FourPlayer(tetanes_core::input::FourPlayer);
 impl ValueEnum for FourPlayer {
     fn value_variants<'a>() -> &'a [Self] {
         use tetanes_core::input::FourPlayer::*;
-        &[Self(Disabled), Self(FourScore), Self(Satellite)]
+        &[
+            Self(Disabled),
+            Self(FourScore),
+            Self(Satellite),
+            Self(FamicomAdapter),
+        ]
     }
 
     fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
@@ -23,7 +33,12 @@ pub(crate) struct RamState(tetanes_core::mem::RamState);
 impl ValueEnum for RamState {
     fn value_variants<'a>() -> &'a [Self] {
         use tetanes_core::mem::RamState::*;
-        &[Self(AllZeros), Self(AllOnes), Self(Random)]
+        &[
+            Self(AllZeros),
+            Self(AllOnes),
+            Self(Random),
+            Self(Alternating),
+        ]
     }
 
     fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
@@ -73,6 +88,9 @@ pub struct Opts {
     /// Choose power-up RAM state. [default: "all-zeros"]
     #[arg(short = 'm', long, value_enum)]
     pub(crate) ram_state: Option<RamState>,
+    /// Seed power-up RAM with a reproducible pseudo-random pattern. Overrides `--ram-state`.
+    #[arg(long)]
+    pub(crate) ram_seed: Option<u64>,
     /// Choose default NES region. [default: "ntsc"]
     #[arg(short = 'r', long, value_enum)]
     pub(crate) region: Option<NesRegion>,
@@ -85,6 +103,13 @@ pub struct Opts {
     /// Don't auto save state or save on exit.
     #[arg(long)]
     pub(crate) no_save: bool,
+    /// Resume the session saved when the ROM was last exited, without asking.
+    #[arg(long)]
+    pub(crate) resume: bool,
+    /// Apply an `.ips`, `.bps` or `.ups` patch to the ROM when loaded, leaving the ROM file
+    /// unchanged. A patch next to the ROM with the same name is applied by default.
+    #[arg(long, requires = "path")]
+    pub(crate) patch: Option<PathBuf>,
     #[arg(short = 'x', long)]
     /// Emulation speed. [default: 1.0]
     pub(crate) speed: Option<f32>,
@@ -94,12 +119,59 @@ pub struct Opts {
     /// Custom Config path.
     #[arg(long)]
     pub(crate) config: Option<PathBuf>,
+    /// Override any config setting by its JSON path, e.g. `--set deck.filter=Ntsc` or
+    /// `--set deck.mixer.master_volume=0.5`. Can be repeated.
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    pub(crate) settings: Vec<String>,
+    /// Store configuration, saves, screenshots and recordings in a `userdata` directory next to
+    /// the executable instead of the platform directories. Also enabled by a `portable.txt` file
+    /// next to the executable.
+    #[arg(long)]
+    pub(crate) portable: bool,
     /// "Default Config" (skip user config and previous save states)
     #[arg(short, long)]
     pub(crate) clean: bool,
     /// Start with debugger open.
     #[arg(short, long)]
     pub(crate) debug: bool,
+    /// Run the ROM headless as fast as possible without opening a window, then print the average
+    /// FPS and checksums, e.g. to benchmark. Audio is still mixed unless `--silent` is set.
+    #[arg(long, requires = "path")]
+    pub(crate) headless: bool,
+    /// Number of frames to run with `--headless`. [default: 600]
+    #[arg(long, requires = "headless")]
+    pub(crate) frames: Option<u32>,
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+}
+
+/// Commands that run without opening a window.
+#[derive(Subcommand, Debug)]
+pub(crate) enum Command {
+    /// Run every ROM in a directory headless and write an HTML and JSON compatibility report.
+    Compat(CompatOpts),
+    /// Print a ROM's parsed header details, hashes and database matches.
+    RomInfo(RomInfoOpts),
+    /// Inspect a ROM running headless in the terminal, showing registers, memory and a preview of
+    /// the frame.
+    #[cfg(feature = "tui")]
+    Tui(TuiOpts),
+    /// Play back an FCEUX `.fm2` TAS movie headless at max speed, reporting the end state hash,
+    /// runtime and the frame it desyncs from a previous sync log, if any.
+    VerifyMovie(VerifyMovieOpts),
+}
+
+impl Command {
+    /// Run the command with the deck settings from `cfg`.
+    pub fn run(self, cfg: Config) -> anyhow::Result<()> {
+        match self {
+            Self::Compat(opts) => opts.run(cfg),
+            Self::RomInfo(opts) => opts.run(cfg),
+            #[cfg(feature = "tui")]
+            Self::Tui(opts) => opts.run(cfg),
+            Self::VerifyMovie(opts) => opts.run(cfg),
+        }
+    }
 }
 
 impl Opts {
@@ -118,6 +190,9 @@ impl Opts {
         if let Some(RamState(ram_state)) = self.ram_state {
             cfg.deck.ram_state = ram_state;
         }
+        if let Some(seed) = self.ram_seed {
+            cfg.deck.ram_state = tetanes_core::mem::RamState::Seeded(seed);
+        }
         if let Some(NesRegion(region)) = self.region {
             cfg.deck.region = region;
         }
@@ -137,6 +212,8 @@ impl Opts {
         } else {
             !self.no_save && cfg.emulation.auto_save
         };
+        cfg.emulation.resume = self.resume && !self.clean;
+        cfg.emulation.patch = self.patch;
         if let Some(save_slot) = self.save_slot {
             cfg.emulation.save_slot = save_slot
         }
@@ -147,9 +224,21 @@ impl Opts {
 
         cfg.audio.enabled = !self.silent && cfg.audio.enabled;
 
-        cfg.renderer.roms_path = self.path.or(cfg.renderer.roms_path);
+        if let Some(path) = self.path {
+            // An explicitly provided path takes priority over the last ROM directory
+            cfg.renderer.recent_dirs.remove(&FileKind::Rom);
+            cfg.renderer.roms_path = Some(path);
+        }
         cfg.renderer.fullscreen = self.fullscreen || cfg.renderer.fullscreen;
 
+        // Applied last so they take priority over other options
+        for setting in self.settings {
+            let (key, value) = setting
+                .split_once('=')
+                .with_context(|| format!("invalid setting {setting:?}, expected KEY=VALUE"))?;
+            cfg.set(key.trim(), value.trim())?;
+        }
+
         Ok(cfg)
     }
 }
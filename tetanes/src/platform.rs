@@ -31,12 +31,28 @@ pub fn open_file_dialog(
     platform::open_file_dialog_impl(title, name, extensions, dir)
 }
 
+pub fn open_folder_dialog(
+    title: impl Into<String>,
+    dir: Option<PathBuf>,
+) -> anyhow::Result<Option<PathBuf>> {
+    platform::open_folder_dialog_impl(title, dir)
+}
+
+/// Returns the edges of the window covered by system UI, like status bars, navigation bars or
+/// display cutouts on mobile devices, in physical pixels.
+pub fn safe_area_insets() -> egui::Margin {
+    platform::safe_area_insets_impl()
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[must_use]
 pub enum Feature {
     Filesystem,
     Viewports,
     Suspend,
+    LanTransfer,
+    LiveSplit,
+    RomDbUpdates,
 }
 
 pub const fn supports(feature: Feature) -> bool {
@@ -0,0 +1,128 @@
+//! Prints parsed header details, hashes and database matches for a ROM, e.g. for curating ROM sets
+//! or attaching to bug reports.
+
+use anyhow::Context;
+use clap::Args;
+use std::{fmt::Write, path::PathBuf};
+use tetanes::nes::{config::Config, rom_db};
+use tetanes_core::{
+    cart::{Cart, HeaderFix},
+    cart_db,
+    common::Regional,
+    mem::RamState,
+};
+
+/// ROM info options.
+#[derive(Args, Debug)]
+#[must_use]
+pub struct RomInfoOpts {
+    /// The NES ROM to inspect.
+    pub(crate) path: PathBuf,
+}
+
+/// Format a size in bytes as kilobytes, or `None` if empty.
+fn size(len: usize) -> String {
+    if len == 0 {
+        "None".to_string()
+    } else if len < 0x0400 {
+        format!("{len} bytes")
+    } else {
+        format!("{}K", len / 0x0400)
+    }
+}
+
+impl RomInfoOpts {
+    /// Load the ROM with the databases configured in `cfg` and print its details.
+    pub fn run(self, cfg: Config) -> anyhow::Result<()> {
+        rom_db::load_cached(&cfg.emulation.rom_db);
+        rom_db::load_cart_db();
+
+        let cart = Cart::from_path(&self.path, RamState::default())
+            .with_context(|| format!("failed to load rom {:?}", self.path))?;
+
+        let mut info = Vec::<(&str, String)>::new();
+        info.push(("Name", cart.name().to_string()));
+        let format = if cart.is_nsf() {
+            "NSF"
+        } else if cart.is_nes2() {
+            "NES 2.0"
+        } else {
+            "iNES"
+        };
+        info.push(("Format", format.to_string()));
+        let mut mapper = format!(
+            "{} (submapper {})",
+            cart.mapper_board(),
+            cart.submapper_num()
+        );
+        if cart.board().is_none() {
+            mapper.push_str(" - unsupported");
+        }
+        info.push(("Mapper", mapper));
+        info.push(("PRG-ROM", size(cart.prg_rom().len())));
+        info.push(("CHR-ROM", size(cart.chr_rom().len())));
+        info.push(("CHR-RAM", size(cart.chr_ram().len())));
+        info.push(("PRG-RAM", size(cart.prg_ram().len())));
+        info.push(("Battery", cart.battery_backed().to_string()));
+        info.push(("Mirroring", format!("{:?}", cart.mirroring())));
+        let console = if cart.is_vs_system() {
+            format!("Vs. System ({})", cart.ppu_model())
+        } else {
+            "NES/Famicom".to_string()
+        };
+        info.push(("Console", console));
+        info.push((
+            "Region",
+            format!("{} ({})", cart.region(), cart.region_source()),
+        ));
+
+        info.push(("CRC32", format!("{:08X}", cart.crc32())));
+        info.push(("PRG-ROM CRC32", format!("{:08X}", cart.prg_rom_crc32())));
+        if let Some(crc32) = cart.chr_rom_crc32() {
+            info.push(("CHR-ROM CRC32", format!("{crc32:08X}")));
+        }
+        let sha1 = cart.sha1().iter().fold(String::new(), |mut sha1, byte| {
+            let _ = write!(sha1, "{byte:02X}");
+            sha1
+        });
+        info.push(("SHA-1", sha1));
+
+        let mut repaired = if cart.header_repaired() {
+            format!(
+                "Repaired (original: mapper {}, PRG-ROM {}, CHR-ROM {})",
+                cart.original_header().mapper_num,
+                size(usize::from(cart.original_header().prg_rom_banks) * 0x4000),
+                size(usize::from(cart.original_header().chr_rom_banks) * 0x2000),
+            )
+        } else {
+            "As dumped".to_string()
+        };
+        if let Some(fix) = HeaderFix::lookup(cart.crc32()) {
+            let _ = write!(repaired, ", known bad dump of {}", fix.title);
+        }
+        info.push(("Header", repaired));
+        let cart_db = match (cart.db_entry(), cart_db::active()) {
+            (Some(entry), _) => entry
+                .title
+                .clone()
+                .unwrap_or_else(|| "Matched, untitled".to_string()),
+            (None, Some(_)) => "No match".to_string(),
+            (None, None) => format!(
+                "Not installed, see {:?}",
+                rom_db::cart_db_path().unwrap_or_default()
+            ),
+        };
+        info.push(("Cart Database", cart_db));
+        let compat_patch = cart.compat_patch().map_or_else(
+            || "None".to_string(),
+            |patch| format!("{} ({}): {}", patch.title, patch.region, patch.description),
+        );
+        info.push(("Compat Patch", compat_patch));
+
+        let width = info.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+        for (label, value) in info {
+            println!("{:width$}  {value}", format!("{label}:"), width = width + 1);
+        }
+        Ok(())
+    }
+}
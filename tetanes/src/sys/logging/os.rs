@@ -22,7 +22,8 @@ where
         .filename_prefix("tetanes")
         .filename_suffix("log")
         .build(
-            dirs::data_local_dir()
+            tetanes_core::control_deck::Config::portable_dir()
+                .or_else(dirs::data_local_dir)
                 .map(|dir| dir.join("logs"))
                 .unwrap_or_else(|| PathBuf::from("logs")),
         )
@@ -1,5 +1,5 @@
 use crate::{
-    nes::{event::EmulationEvent, Running},
+    nes::{event::EmulationEvent, rom_db, Running},
     platform::{BuilderExt, EventLoopExt, Feature, Initialize},
 };
 use std::path::PathBuf;
@@ -13,10 +13,15 @@ use winit::{
 pub const fn supports_impl(feature: Feature) -> bool {
     match feature {
         Feature::Suspend => cfg!(target_os = "android"),
-        Feature::Filesystem | Feature::Viewports => true,
+        Feature::Filesystem
+        | Feature::Viewports
+        | Feature::LanTransfer
+        | Feature::LiveSplit
+        | Feature::RomDbUpdates => true,
     }
 }
 
+#[cfg(not(target_os = "android"))]
 pub fn open_file_dialog_impl(
     title: impl Into<String>,
     name: impl Into<String>,
@@ -32,8 +37,93 @@ pub fn open_file_dialog_impl(
     Ok(dialog.pick_file())
 }
 
+#[cfg(not(target_os = "android"))]
+pub fn open_folder_dialog_impl(
+    title: impl Into<String>,
+    dir: Option<PathBuf>,
+) -> anyhow::Result<Option<PathBuf>> {
+    let mut dialog = rfd::FileDialog::new().set_title(title);
+    if let Some(dir) = dir {
+        dialog = dialog.set_directory(dir);
+    }
+    Ok(dialog.pick_folder())
+}
+
+#[cfg(target_os = "android")]
+pub fn open_file_dialog_impl(
+    _title: impl Into<String>,
+    _name: impl Into<String>,
+    _extensions: &[impl ToString],
+    _dir: Option<PathBuf>,
+) -> anyhow::Result<Option<PathBuf>> {
+    anyhow::bail!(
+        "file dialogs are not supported on Android, copy ROMs to the library folder instead"
+    )
+}
+
+#[cfg(target_os = "android")]
+pub fn open_folder_dialog_impl(
+    _title: impl Into<String>,
+    _dir: Option<PathBuf>,
+) -> anyhow::Result<Option<PathBuf>> {
+    anyhow::bail!("folder dialogs are not supported on Android")
+}
+
+#[cfg(target_os = "android")]
+pub use android::set_android_app;
+
+#[cfg(target_os = "android")]
+mod android {
+    use std::sync::OnceLock;
+    use winit::platform::android::activity::AndroidApp;
+
+    static ANDROID_APP: OnceLock<AndroidApp> = OnceLock::new();
+
+    /// Store the `AndroidApp` passed to `android_main` to query window insets later.
+    pub fn set_android_app(app: AndroidApp) {
+        let _ = ANDROID_APP.set(app);
+    }
+
+    pub fn android_app() -> Option<&'static AndroidApp> {
+        ANDROID_APP.get()
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn safe_area_insets_impl() -> egui::Margin {
+    egui::Margin::ZERO
+}
+
+#[cfg(target_os = "android")]
+pub fn safe_area_insets_impl() -> egui::Margin {
+    let Some(app) = android::android_app() else {
+        return egui::Margin::ZERO;
+    };
+    let Some(window) = app.native_window() else {
+        return egui::Margin::ZERO;
+    };
+    // The content rect excludes the status bar, navigation bar and display cutouts
+    let content = app.content_rect();
+    egui::Margin {
+        left: content.left.max(0) as f32,
+        right: (window.width() - content.right).max(0) as f32,
+        top: content.top.max(0) as f32,
+        bottom: (window.height() - content.bottom).max(0) as f32,
+    }
+}
+
 impl Initialize for Running {
     fn initialize(&mut self) -> anyhow::Result<()> {
+        // Install the cached ROM database before any ROM is loaded
+        rom_db::load_cached(&self.cfg.emulation.rom_db);
+        rom_db::load_cart_db();
+        if self.cfg.emulation.rom_db.auto_update {
+            rom_db::spawn_update(&self.cfg.emulation.rom_db, self.tx.clone(), false);
+        }
+        if !self.cfg.renderer.library_dirs.is_empty() {
+            self.renderer.scan_library(&self.cfg);
+        }
+
         if let Some(path) = self.cfg.renderer.roms_path.take() {
             if path.is_file() {
                 if let Some(parent) = path.parent() {
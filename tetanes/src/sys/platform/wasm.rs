@@ -7,9 +7,13 @@ use crate::{
     platform::{BuilderExt, EventLoopExt, Feature, Initialize},
 };
 use anyhow::{bail, Context};
-use std::path::PathBuf;
+use std::{cell::RefCell, path::PathBuf};
 use wasm_bindgen::{closure::Closure, JsCast, JsValue};
-use web_sys::{js_sys::Uint8Array, window, FileReader, HtmlCanvasElement, HtmlInputElement};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{
+    js_sys::{Array, Function, Object, Promise, Reflect, Uint8Array},
+    window, DragEvent, File, HtmlCanvasElement, HtmlInputElement,
+};
 use winit::{
     event::Event,
     event_loop::{EventLoop, EventLoopProxy, EventLoopWindowTarget},
@@ -17,6 +21,11 @@ use winit::{
     window::WindowBuilder,
 };
 
+thread_local! {
+    /// Used to send files picked with the File System Access API, which resolves asynchronously.
+    static EVENT_TX: RefCell<Option<EventLoopProxy<NesEvent>>> = const { RefCell::new(None) };
+}
+
 pub const fn supports_impl(_feature: Feature) -> bool {
     false
 }
@@ -27,14 +36,25 @@ pub fn open_file_dialog_impl(
     extensions: &[impl ToString],
     _dir: Option<PathBuf>,
 ) -> anyhow::Result<Option<PathBuf>> {
-    let input_id = match extensions[0].to_string().as_str() {
-        "nes" => html_ids::ROM_INPUT,
-        "replay" => html_ids::REPLAY_INPUT,
+    let kind = match extensions[0].to_string().as_str() {
+        "nes" => FileKind::Rom,
+        "replay" => FileKind::Replay,
         _ => bail!("unsupported file extension"),
     };
+    if let (true, Some(tx)) = (supports_file_picker(), EVENT_TX.with_borrow(Clone::clone)) {
+        spawn_local(async move {
+            match pick_file(kind).await {
+                Ok(Some(file)) => load_file(tx, kind, file),
+                Ok(None) => tx.nes_event(UiEvent::FileDialogCancelled),
+                Err(err) => on_error(&tx, err),
+            }
+        });
+        return Ok(None);
+    }
+
     let input = web_sys::window()
         .and_then(|window| window.document())
-        .and_then(|document| document.get_element_by_id(input_id))
+        .and_then(|document| document.get_element_by_id(kind.input_id()))
         .and_then(|input| input.dyn_into::<HtmlInputElement>().ok());
     match input {
         Some(input) => input.click(),
@@ -44,75 +64,46 @@ pub fn open_file_dialog_impl(
     Ok(None)
 }
 
+pub fn open_folder_dialog_impl(
+    _title: impl Into<String>,
+    _dir: Option<PathBuf>,
+) -> anyhow::Result<Option<PathBuf>> {
+    bail!("folder dialogs are not supported")
+}
+
+pub const fn safe_area_insets_impl() -> egui::Margin {
+    egui::Margin::ZERO
+}
+
 impl Initialize for Running {
     fn initialize(&mut self) -> anyhow::Result<()> {
         let window = web_sys::window().context("valid js window")?;
         let document = window.document().context("valid html document")?;
 
-        let on_error = |tx: &EventLoopProxy<NesEvent>, err: JsValue| {
-            tx.nes_event(UiEvent::Error(
-                err.as_string()
-                    .unwrap_or_else(|| "failed to load rom".to_string()),
-            ));
-        };
+        EVENT_TX.set(Some(self.tx.clone()));
 
-        for input_id in [html_ids::ROM_INPUT, html_ids::REPLAY_INPUT] {
+        for kind in [FileKind::Rom, FileKind::Replay] {
             let on_change = Closure::<dyn FnMut(_)>::new({
                 let tx = self.tx.clone();
-                move |evt: web_sys::MouseEvent| {
-                    match FileReader::new() {
-                        Ok(reader) => {
-                            let Some(file) = evt
-                                .current_target()
-                                .and_then(|target| target.dyn_into::<HtmlInputElement>().ok())
-                                .and_then(|input| input.files())
-                                .and_then(|files| files.item(0))
-                            else {
-                                tx.nes_event(UiEvent::FileDialogCancelled);
-                                return;
-                            };
-                            match reader.read_as_array_buffer(&file) {
-                                Ok(_) => {
-                                    let onload = Closure::<dyn FnMut()>::new({
-                                        let reader = reader.clone();
-                                        let tx = tx.clone();
-                                        move || match reader.result() {
-                                            Ok(result) => {
-                                                let data = Uint8Array::new(&result);
-                                                let event = match input_id {
-                                                    html_ids::ROM_INPUT => EmulationEvent::LoadRom(
-                                                        (file.name(), RomData(data.to_vec())),
-                                                    ),
-                                                    html_ids::REPLAY_INPUT => {
-                                                        EmulationEvent::LoadReplay((
-                                                            file.name(),
-                                                            ReplayData(data.to_vec()),
-                                                        ))
-                                                    }
-                                                    _ => unreachable!("unsupported input id"),
-                                                };
-                                                tx.nes_event(event);
-                                                focus_canvas();
-                                            }
-                                            Err(err) => on_error(&tx, err),
-                                        }
-                                    });
-                                    reader.set_onload(Some(onload.as_ref().unchecked_ref()));
-                                    onload.forget();
-                                }
-                                Err(err) => on_error(&tx, err),
-                            }
-                        }
-                        Err(err) => on_error(&tx, err),
-                    };
+                move |evt: web_sys::Event| {
+                    let file = evt
+                        .current_target()
+                        .and_then(|target| target.dyn_into::<HtmlInputElement>().ok())
+                        .and_then(|input| input.files())
+                        .and_then(|files| files.item(0));
+                    match file {
+                        Some(file) => load_file(tx.clone(), kind, file),
+                        None => tx.nes_event(UiEvent::FileDialogCancelled),
+                    }
                 }
             });
 
             let on_cancel = Closure::<dyn FnMut(_)>::new({
                 let tx = self.tx.clone();
-                move |_: web_sys::MouseEvent| tx.nes_event(UiEvent::FileDialogCancelled)
+                move |_: web_sys::Event| tx.nes_event(UiEvent::FileDialogCancelled)
             });
 
+            let input_id = kind.input_id();
             let input = document
                 .get_element_by_id(input_id)
                 .with_context(|| format!("valid {input_id} button"))?;
@@ -130,6 +121,38 @@ impl Initialize for Running {
             on_cancel.forget();
         }
 
+        // Dropping files anywhere on the page loads them instead of navigating away
+        let on_drag_over = Closure::<dyn FnMut(_)>::new(|evt: DragEvent| evt.prevent_default());
+        let on_drop = Closure::<dyn FnMut(_)>::new({
+            let tx = self.tx.clone();
+            move |evt: DragEvent| {
+                evt.prevent_default();
+                let Some(file) = evt
+                    .data_transfer()
+                    .and_then(|data| data.files())
+                    .and_then(|files| files.item(0))
+                else {
+                    return;
+                };
+                match FileKind::from_name(&file.name()) {
+                    Some(kind) => load_file(tx.clone(), kind, file),
+                    None => tx.nes_event(UiEvent::Error(format!(
+                        "unsupported file: {}. Drop a `.nes` ROM or `.replay` recording.",
+                        file.name()
+                    ))),
+                }
+            }
+        });
+        for (event, callback) in [("dragover", &on_drag_over), ("drop", &on_drop)] {
+            if let Err(err) =
+                document.add_event_listener_with_callback(event, callback.as_ref().unchecked_ref())
+            {
+                on_error(&self.tx, err);
+            }
+        }
+        on_drag_over.forget();
+        on_drop.forget();
+
         if let Some(status) = document.get_element_by_id(html_ids::LOADING_STATUS) {
             tracing::info!(
                 "removing hidden class from loading status: {}",
@@ -145,6 +168,128 @@ impl Initialize for Running {
     }
 }
 
+/// Kinds of files that can be opened in the browser.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum FileKind {
+    Rom,
+    Replay,
+}
+
+impl FileKind {
+    fn from_name(name: &str) -> Option<Self> {
+        let (_, extension) = name.rsplit_once('.')?;
+        [Self::Rom, Self::Replay]
+            .into_iter()
+            .find(|kind| extension.eq_ignore_ascii_case(kind.extension()))
+    }
+
+    const fn extension(self) -> &'static str {
+        match self {
+            Self::Rom => "nes",
+            Self::Replay => "replay",
+        }
+    }
+
+    const fn description(self) -> &'static str {
+        match self {
+            Self::Rom => "NES ROMs",
+            Self::Replay => "Replay Recordings",
+        }
+    }
+
+    const fn input_id(self) -> &'static str {
+        match self {
+            Self::Rom => html_ids::ROM_INPUT,
+            Self::Replay => html_ids::REPLAY_INPUT,
+        }
+    }
+
+    const fn event(self, name: String, data: Vec<u8>) -> EmulationEvent {
+        match self {
+            Self::Rom => EmulationEvent::LoadRom((name, RomData(data))),
+            Self::Replay => EmulationEvent::LoadReplay((name, ReplayData(data))),
+        }
+    }
+}
+
+fn on_error(tx: &EventLoopProxy<NesEvent>, err: JsValue) {
+    tx.nes_event(UiEvent::Error(
+        err.as_string()
+            .unwrap_or_else(|| format!("failed to load file: {err:?}")),
+    ));
+}
+
+/// Read `file` in the background and send an event to load it.
+fn load_file(tx: EventLoopProxy<NesEvent>, kind: FileKind, file: File) {
+    spawn_local(async move {
+        match JsFuture::from(file.array_buffer()).await {
+            Ok(buffer) => {
+                let data = Uint8Array::new(&buffer).to_vec();
+                tx.nes_event(kind.event(file.name(), data));
+                focus_canvas();
+            }
+            Err(err) => on_error(&tx, err),
+        }
+    });
+}
+
+/// Whether the File System Access API is available. Currently only in Chromium-based browsers.
+///
+/// See: <https://developer.mozilla.org/en-US/docs/Web/API/File_System_API>
+fn supports_file_picker() -> bool {
+    window().is_some_and(|window| {
+        Reflect::has(&window, &JsValue::from_str("showOpenFilePicker")).unwrap_or(false)
+    })
+}
+
+/// Pick a file using the File System Access API. Returns `None` if cancelled.
+async fn pick_file(kind: FileKind) -> Result<Option<File>, JsValue> {
+    let window = window().ok_or_else(|| JsValue::from_str("failed to get window"))?;
+    let show_picker =
+        Reflect::get(&window, &JsValue::from_str("showOpenFilePicker"))?.dyn_into::<Function>()?;
+
+    let accept = Object::new();
+    Reflect::set(
+        &accept,
+        &JsValue::from_str("application/octet-stream"),
+        &Array::of1(&JsValue::from_str(&format!(".{}", kind.extension()))),
+    )?;
+    let file_type = Object::new();
+    Reflect::set(
+        &file_type,
+        &JsValue::from_str("description"),
+        &JsValue::from_str(kind.description()),
+    )?;
+    Reflect::set(&file_type, &JsValue::from_str("accept"), &accept)?;
+    let options = Object::new();
+    Reflect::set(
+        &options,
+        &JsValue::from_str("types"),
+        &Array::of1(&file_type),
+    )?;
+
+    let picked = show_picker
+        .call1(&window, &options)?
+        .dyn_into::<Promise>()?;
+    let handles = match JsFuture::from(picked).await {
+        Ok(handles) => Array::from(&handles),
+        // The picker rejects with an `AbortError` when cancelled
+        Err(err)
+            if Reflect::get(&err, &JsValue::from_str("name"))
+                .ok()
+                .and_then(|name| name.as_string())
+                .is_some_and(|name| name == "AbortError") =>
+        {
+            return Ok(None);
+        }
+        Err(err) => return Err(err),
+    };
+    let handle = handles.get(0);
+    let get_file = Reflect::get(&handle, &JsValue::from_str("getFile"))?.dyn_into::<Function>()?;
+    let file = JsFuture::from(get_file.call0(&handle)?.dyn_into::<Promise>()?).await?;
+    Ok(Some(file.dyn_into::<File>()?))
+}
+
 impl BuilderExt for WindowBuilder {
     /// Sets platform-specific window options.
     fn with_platform(self, _title: &str) -> Self {
@@ -0,0 +1,449 @@
+//! Terminal frontend that runs a ROM headless while showing CPU and PPU registers, memory and a
+//! braille or ASCII preview of the frame, e.g. to debug automation on a server over SSH.
+
+use anyhow::Context;
+use clap::Args;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph},
+    Frame, Terminal,
+};
+use std::{
+    io,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use tetanes::nes::config::Config;
+use tetanes_core::{
+    common::{Regional, Reset, ResetKind},
+    control_deck::{ControlDeck, HeadlessMode},
+    cpu::Status,
+    mem::{Access, Mem},
+    ppu::{Ppu, Registers},
+};
+
+/// Terminal inspector options.
+#[derive(Args, Debug)]
+#[must_use]
+pub struct TuiOpts {
+    /// The NES ROM to run.
+    pub(crate) path: PathBuf,
+    /// Preview the frame with ASCII characters instead of braille, for terminals without Unicode
+    /// support.
+    #[arg(long)]
+    pub(crate) ascii: bool,
+    /// Start paused.
+    #[arg(short, long)]
+    pub(crate) paused: bool,
+}
+
+/// How the frame preview is drawn.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Preview {
+    /// 2x4 dots per character.
+    Braille,
+    /// One brightness ramp character per 1x2 block.
+    Ascii,
+}
+
+impl Preview {
+    /// Pixels covered by each character, in dots.
+    const fn dots(self) -> (usize, usize) {
+        match self {
+            Self::Braille => (2, 4),
+            Self::Ascii => (1, 2),
+        }
+    }
+}
+
+/// Number of memory bytes shown per row.
+const MEMORY_ROW: u16 = 0x10;
+/// Brightness ramp used for the ASCII preview, from darkest to brightest.
+const ASCII_RAMP: &[u8] = b" .:-=+*#%@";
+
+/// Restores the terminal when dropped, including when returning early on errors.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new() -> anyhow::Result<Self> {
+        enable_raw_mode()?;
+        let guard = Self;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), LeaveAlternateScreen);
+        let _ = disable_raw_mode();
+    }
+}
+
+/// Inspector state.
+#[must_use]
+struct Inspector {
+    deck: ControlDeck,
+    name: String,
+    paused: bool,
+    preview: Preview,
+    /// First address shown in the memory view.
+    mem_addr: u16,
+    /// Error that stopped emulation, if any.
+    error: Option<String>,
+    quit: bool,
+}
+
+impl Inspector {
+    fn frame_duration(&self) -> Duration {
+        Duration::from_secs_f32(Ppu::region_frame_rate(self.deck.region()).recip())
+    }
+
+    fn step(&mut self, step: fn(&mut ControlDeck) -> tetanes_core::control_deck::Result<usize>) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Err(err) = step(&mut self.deck) {
+            self.error = Some(err.to_string());
+            self.paused = true;
+        }
+    }
+
+    fn on_key(&mut self, key: KeyEvent) {
+        let page = MEMORY_ROW * 0x10;
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.quit = true;
+            }
+            KeyCode::Char(' ') => self.paused = !self.paused,
+            KeyCode::Char('f') => {
+                self.paused = true;
+                self.step(ControlDeck::clock_frame);
+            }
+            KeyCode::Char('l') => {
+                self.paused = true;
+                self.step(ControlDeck::clock_scanline);
+            }
+            KeyCode::Char('s') => {
+                self.paused = true;
+                self.step(ControlDeck::clock_instr);
+            }
+            KeyCode::Char('r') => {
+                self.deck.reset(ResetKind::Soft);
+                self.error = None;
+            }
+            KeyCode::Char('R') => {
+                self.deck.reset(ResetKind::Hard);
+                self.error = None;
+            }
+            KeyCode::Char('m') => {
+                self.preview = match self.preview {
+                    Preview::Braille => Preview::Ascii,
+                    Preview::Ascii => Preview::Braille,
+                };
+            }
+            KeyCode::Up => self.mem_addr = self.mem_addr.wrapping_sub(MEMORY_ROW),
+            KeyCode::Down => self.mem_addr = self.mem_addr.wrapping_add(MEMORY_ROW),
+            KeyCode::PageUp => self.mem_addr = self.mem_addr.wrapping_sub(page),
+            KeyCode::PageDown => self.mem_addr = self.mem_addr.wrapping_add(page),
+            KeyCode::Home => self.mem_addr = 0x0000,
+            _ => (),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame<'_>) {
+        let [main, help] = split(
+            frame.size(),
+            Direction::Vertical,
+            [Constraint::Min(0), Constraint::Length(1)],
+        );
+        let [preview, side] = split(
+            main,
+            Direction::Horizontal,
+            [Constraint::Min(0), Constraint::Length(62)],
+        );
+        let [registers, disasm, memory] = split(
+            side,
+            Direction::Vertical,
+            [
+                Constraint::Length(7),
+                Constraint::Length(10),
+                Constraint::Min(0),
+            ],
+        );
+
+        let status = match (&self.error, self.paused) {
+            (Some(err), _) => format!("Error: {err}"),
+            (None, true) => "Paused".to_string(),
+            (None, false) => "Running".to_string(),
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" {} - {status} ", self.name));
+        let lines = self.preview_lines(block.inner(preview));
+        frame.render_widget(Paragraph::new(lines).block(block), preview);
+
+        frame.render_widget(
+            Paragraph::new(self.register_lines()).block(titled(" Registers ")),
+            registers,
+        );
+        let rows = disasm.height.saturating_sub(2);
+        frame.render_widget(
+            Paragraph::new(self.disasm_lines(rows)).block(titled(" Disassembly ")),
+            disasm,
+        );
+        let rows = memory.height.saturating_sub(2);
+        frame.render_widget(
+            Paragraph::new(self.memory_lines(rows)).block(titled(" Memory ")),
+            memory,
+        );
+        frame.render_widget(
+            Paragraph::new(concat!(
+                " q: quit  space: pause  f: frame  l: scanline  s: instruction  ",
+                "r/R: reset/power  m: preview  up/down/pgup/pgdn: memory"
+            ))
+            .style(Style::default().add_modifier(Modifier::REVERSED)),
+            help,
+        );
+    }
+
+    fn register_lines(&self) -> Vec<Line<'static>> {
+        let cpu = self.deck.cpu();
+        let ppu = &cpu.bus.ppu;
+        let flags = [
+            (Status::N, 'N'),
+            (Status::V, 'V'),
+            (Status::U, '-'),
+            (Status::B, 'B'),
+            (Status::D, 'D'),
+            (Status::I, 'I'),
+            (Status::Z, 'Z'),
+            (Status::C, 'C'),
+        ]
+        .into_iter()
+        .map(|(flag, c)| {
+            if cpu.status.contains(flag) {
+                c
+            } else {
+                c.to_ascii_lowercase()
+            }
+        })
+        .collect::<String>();
+        vec![
+            Line::from(format!(
+                "PC: ${:04X}  A: ${:02X}  X: ${:02X}  Y: ${:02X}  SP: ${:02X}",
+                cpu.pc, cpu.acc, cpu.x, cpu.y, cpu.sp
+            )),
+            Line::from(format!("P:  {flags} (${:02X})", cpu.status.bits())),
+            Line::from(format!(
+                "Cycle: {}  Frame: {}",
+                cpu.cycle,
+                self.deck.frame_number()
+            )),
+            Line::from(format!(
+                "PPU: {:3},{:3}  V: ${:04X}  STATUS: ${:02X}",
+                ppu.cycle,
+                ppu.scanline,
+                ppu.scroll.addr(),
+                ppu.peek_status()
+            )),
+            Line::from(format!("Region: {:?}", self.deck.region())),
+        ]
+    }
+
+    fn disasm_lines(&mut self, rows: u16) -> Vec<Line<'static>> {
+        let cpu = self.deck.cpu_mut();
+        let mut pc = cpu.pc;
+        (0..rows)
+            .map(|row| {
+                let prefix = if row == 0 { "> " } else { "  " };
+                Line::from(format!("{prefix}{}", cpu.disassemble(&mut pc)))
+            })
+            .collect()
+    }
+
+    fn memory_lines(&self, rows: u16) -> Vec<Line<'static>> {
+        let bus = &self.deck.cpu().bus;
+        (0..rows)
+            .map(|row| {
+                let addr = self.mem_addr.wrapping_add(row * MEMORY_ROW);
+                let bytes = (0..MEMORY_ROW)
+                    .map(|offset| bus.peek(addr.wrapping_add(offset), Access::Dummy))
+                    .collect::<Vec<_>>();
+                let hex = bytes
+                    .iter()
+                    .map(|byte| format!("{byte:02X}"))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let ascii = bytes
+                    .iter()
+                    .map(|&byte| {
+                        if byte.is_ascii_graphic() {
+                            char::from(byte)
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>();
+                Line::from(format!("${addr:04X}: {hex} {ascii}"))
+            })
+            .collect()
+    }
+
+    /// Scale the frame to fit `area`, keeping its aspect ratio, and draw it with the current
+    /// [`Preview`] mode.
+    fn preview_lines(&self, area: Rect) -> Vec<Line<'static>> {
+        let (dots_x, dots_y) = self.preview.dots();
+        let (width, height) = (Ppu::WIDTH as usize, Ppu::HEIGHT as usize);
+        let frame_buffer = self.deck.cpu().bus.ppu.frame_buffer();
+        let luma = frame_buffer
+            .iter()
+            .map(|&pixel| {
+                let (red, green, blue) = Ppu::system_palette(pixel);
+                (u32::from(red) * 299 + u32::from(green) * 587 + u32::from(blue) * 114) / 1000
+            })
+            .collect::<Vec<_>>();
+        // Dots are roughly square, so scale evenly on both axes
+        let scale = f32::min(
+            (usize::from(area.width) * dots_x) as f32 / width as f32,
+            (usize::from(area.height) * dots_y) as f32 / height as f32,
+        );
+        if scale <= 0.0 {
+            return Vec::new();
+        }
+        let cols = ((width as f32 * scale) as usize / dots_x).max(1);
+        let rows = ((height as f32 * scale) as usize / dots_y).max(1);
+        let sample = |dot_x: usize, dot_y: usize| {
+            let x = ((dot_x as f32 / scale) as usize).min(width - 1);
+            let y = ((dot_y as f32 / scale) as usize).min(height - 1);
+            luma[x + y * width]
+        };
+
+        match self.preview {
+            Preview::Braille => {
+                // Threshold on average brightness so dark and bright scenes both show detail
+                let threshold = luma.iter().sum::<u32>() / luma.len().max(1) as u32;
+                // Braille dot bits, indexed by [row][column]
+                const DOTS: [[u32; 2]; 4] =
+                    [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+                (0..rows)
+                    .map(|row| {
+                        (0..cols)
+                            .map(|col| {
+                                let mut bits = 0;
+                                for (dy, dot_row) in DOTS.iter().enumerate() {
+                                    for (dx, dot) in dot_row.iter().enumerate() {
+                                        if sample(col * 2 + dx, row * 4 + dy) > threshold {
+                                            bits |= dot;
+                                        }
+                                    }
+                                }
+                                char::from_u32(0x2800 + bits).unwrap_or(' ')
+                            })
+                            .collect::<String>()
+                            .into()
+                    })
+                    .collect()
+            }
+            Preview::Ascii => (0..rows)
+                .map(|row| {
+                    (0..cols)
+                        .map(|col| {
+                            let luma = (sample(col, row * 2) + sample(col, row * 2 + 1)) / 2;
+                            let index = luma as usize * (ASCII_RAMP.len() - 1) / 255;
+                            char::from(ASCII_RAMP[index.min(ASCII_RAMP.len() - 1)])
+                        })
+                        .collect::<String>()
+                        .into()
+                })
+                .collect(),
+        }
+    }
+}
+
+fn split<const N: usize>(
+    area: Rect,
+    direction: Direction,
+    constraints: [Constraint; N],
+) -> [Rect; N] {
+    let areas = Layout::default()
+        .direction(direction)
+        .constraints(constraints)
+        .split(area);
+    std::array::from_fn(|i| areas[i])
+}
+
+fn titled(title: &'static str) -> Block<'static> {
+    Block::default().borders(Borders::ALL).title(title)
+}
+
+impl TuiOpts {
+    /// Run the inspector with the deck settings from `cfg` until quit.
+    pub fn run(self, cfg: Config) -> anyhow::Result<()> {
+        let mut deck_cfg = cfg.deck;
+        deck_cfg.headless_mode = HeadlessMode::NO_AUDIO;
+        let mut deck = ControlDeck::with_config(deck_cfg);
+        deck.load_rom_path(&self.path)
+            .with_context(|| format!("failed to load rom {:?}", self.path))?;
+        let name = deck
+            .loaded_rom()
+            .map_or_else(String::new, |rom| rom.name.clone());
+        let mut inspector = Inspector {
+            deck,
+            name,
+            paused: self.paused,
+            preview: if self.ascii {
+                Preview::Ascii
+            } else {
+                Preview::Braille
+            },
+            mem_addr: 0x0000,
+            error: None,
+            quit: false,
+        };
+
+        let _guard = TerminalGuard::new()?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+        let mut next_frame = Instant::now();
+        while !inspector.quit {
+            terminal.draw(|frame| inspector.draw(frame))?;
+
+            let timeout = if inspector.paused {
+                Duration::from_millis(100)
+            } else {
+                next_frame.saturating_duration_since(Instant::now())
+            };
+            if event::poll(timeout)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press {
+                        inspector.on_key(key);
+                    }
+                }
+                continue;
+            }
+
+            if !inspector.paused {
+                inspector.step(ControlDeck::clock_frame);
+                next_frame += inspector.frame_duration();
+                // Don't try to catch up after falling behind, e.g. when the terminal is slow
+                let now = Instant::now();
+                if next_frame < now {
+                    next_frame = now;
+                }
+            } else {
+                next_frame = Instant::now();
+            }
+        }
+
+        Ok(())
+    }
+}
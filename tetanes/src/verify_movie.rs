@@ -0,0 +1,109 @@
+//! TAS movie verification that plays back an FCEUX `.fm2` movie headless at max speed and
+//! reports whether it still syncs, e.g. to check movies after emulator updates.
+
+use anyhow::Context;
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tetanes::nes::config::Config;
+use tetanes_core::movie::Movie;
+
+/// Movie verification options.
+#[derive(Args, Debug)]
+#[must_use]
+pub struct VerifyMovieOpts {
+    /// The NES ROM the movie was recorded with.
+    pub(crate) rom: PathBuf,
+    /// The `.fm2` movie to play back.
+    pub(crate) movie: PathBuf,
+    /// Sync log from a previous verification to compare frame hashes against, reporting the
+    /// first frame that desyncs.
+    #[arg(short, long)]
+    pub(crate) baseline: Option<PathBuf>,
+    /// Write a sync log of every frame hash to compare against with `--baseline` later.
+    #[arg(short, long)]
+    pub(crate) output: Option<PathBuf>,
+}
+
+/// Frame hashes from a movie playback.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[must_use]
+pub struct SyncLog {
+    pub version: String,
+    pub rom: String,
+    pub end_hash: u64,
+    pub frame_hashes: Vec<u64>,
+}
+
+impl VerifyMovieOpts {
+    /// Play back the movie with the deck settings from `cfg` and print the results.
+    pub fn run(self, cfg: Config) -> anyhow::Result<()> {
+        let data = std::fs::read_to_string(&self.movie)
+            .with_context(|| format!("failed to read movie {:?}", self.movie))?;
+        let movie =
+            Movie::parse(&data).with_context(|| format!("invalid movie {:?}", self.movie))?;
+        let baseline = self
+            .baseline
+            .as_ref()
+            .map(|path| {
+                let data = std::fs::read(path)
+                    .with_context(|| format!("failed to read sync log {path:?}"))?;
+                serde_json::from_slice::<SyncLog>(&data)
+                    .with_context(|| format!("invalid sync log {path:?}"))
+            })
+            .transpose()?;
+
+        let name = self
+            .rom
+            .file_name()
+            .map_or_else(|| self.rom.to_string_lossy(), |name| name.to_string_lossy())
+            .into_owned();
+        if let Some(rom_filename) = &movie.rom_filename {
+            if !name.starts_with(rom_filename.as_str()) {
+                tracing::warn!("movie was recorded with {rom_filename:?}, not {name:?}");
+            }
+        }
+        let rom = std::fs::read(&self.rom)
+            .with_context(|| format!("failed to read rom {:?}", self.rom))?;
+        let report = movie
+            .play(cfg.deck, &name, &mut rom.as_slice())
+            .with_context(|| format!("failed to play back movie {:?}", self.movie))?;
+
+        println!("Frames: {}", report.frames);
+        println!("End State Hash: {:016X}", report.end_hash);
+        println!(
+            "Runtime: {:.2}s ({:.1} fps)",
+            report.elapsed.as_secs_f64(),
+            report.fps()
+        );
+        let desync = baseline.as_ref().map(|baseline| {
+            report.desync_frame(&baseline.frame_hashes).map_or_else(
+                || (baseline.end_hash != report.end_hash).then(|| "end state differs".to_string()),
+                |frame| Some(format!("frame {frame}")),
+            )
+        });
+        match &desync {
+            Some(Some(desync)) => println!("Desync: {desync}"),
+            Some(None) => println!("Desync: none"),
+            None => (),
+        }
+
+        if let Some(path) = &self.output {
+            let log = SyncLog {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                rom: name,
+                end_hash: report.end_hash,
+                frame_hashes: report.frame_hashes,
+            };
+            let data = serde_json::to_vec(&log).context("failed to serialize sync log")?;
+            std::fs::write(path, data)
+                .with_context(|| format!("failed to write sync log {path:?}"))?;
+            println!("Wrote {path:?}");
+        }
+
+        if let Some(Some(desync)) = desync {
+            anyhow::bail!("movie desynced: {desync}");
+        }
+        Ok(())
+    }
+}